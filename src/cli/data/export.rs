@@ -63,6 +63,8 @@ impl Context for Export {
             skip_wal: false,
             streaming_output: false,
             streaming_id: None,
+            timezone: None,
+            debug_cache: false,
         };
 
         let req = search::Request {
@@ -74,6 +76,7 @@ impl Context for Export {
             search_type,
             search_event_context,
             use_cache: None,
+            explain: false,
         };
 
         match SearchService::search("", &c.org, stream_type, None, &req).await {
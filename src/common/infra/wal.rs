@@ -42,6 +42,11 @@ static SEARCHING_FILES: Lazy<parking_lot::RwLock<SearchingFileLocker>> =
 static SEARCHING_REQUESTS: Lazy<parking_lot::RwLock<HashMap<String, Vec<String>>>> =
     Lazy::new(Default::default);
 
+// UPLOADED_FILES for wal files already merged into a storage file, but still on disk
+// because searches are using them, value is the storage file key
+static UPLOADED_FILES: Lazy<parking_lot::RwLock<HashMap<String, String>>> =
+    Lazy::new(Default::default);
+
 type RwData = RwLock<HashMap<String, Arc<RwFile>>>;
 
 struct SearchingFileLocker {
@@ -404,6 +409,23 @@ pub fn clean_lock_files() {
     locker.clean();
 }
 
+pub fn set_uploaded_files(files: &[String], storage_file: &str) {
+    let mut uploaded = UPLOADED_FILES.write();
+    for file in files.iter() {
+        uploaded.insert(file.clone(), storage_file.to_string());
+    }
+}
+
+pub fn get_uploaded_file(file: &str) -> Option<String> {
+    UPLOADED_FILES.read().get(file).cloned()
+}
+
+pub fn remove_uploaded_file(file: &str) {
+    let mut uploaded = UPLOADED_FILES.write();
+    uploaded.remove(file);
+    uploaded.shrink_to_fit();
+}
+
 pub fn lock_request(trace_id: &str, files: &[String]) {
     log::info!("[trace_id {}] lock_request for wal files", trace_id);
     let mut locker = SEARCHING_REQUESTS.write();
@@ -459,4 +481,20 @@ mod tests {
         assert_eq!(file.size().await, data.len() as i64);
         assert!(file.name().contains(&format!("{}/{}", thread_id, key)));
     }
+
+    #[test]
+    fn test_wal_uploaded_files() {
+        let files = vec![
+            "files/test_org/logs/uploaded/1.parquet".to_string(),
+            "files/test_org/logs/uploaded/2.parquet".to_string(),
+        ];
+        let storage_file = "files/test_org/logs/uploaded/2025/01/01/00/merged.parquet";
+        set_uploaded_files(&files, storage_file);
+        assert_eq!(get_uploaded_file(&files[0]).as_deref(), Some(storage_file));
+        assert_eq!(get_uploaded_file(&files[1]).as_deref(), Some(storage_file));
+        remove_uploaded_file(&files[0]);
+        assert!(get_uploaded_file(&files[0]).is_none());
+        assert_eq!(get_uploaded_file(&files[1]).as_deref(), Some(storage_file));
+        remove_uploaded_file(&files[1]);
+    }
 }
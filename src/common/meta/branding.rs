@@ -0,0 +1,56 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Branding of the deployment, shown on the login page and in the UI
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Branding {
+    #[serde(default)]
+    pub product_name: Option<String>,
+    #[serde(default)]
+    pub login_message: Option<String>,
+    #[serde(default)]
+    pub logo: Option<BrandingLogo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BrandingLogo {
+    /// `image/png` or `image/svg+xml`
+    pub content_type: String,
+    /// Base64 encoded image, SVGs are stored sanitized
+    pub data: String,
+    /// Unix timestamp in microseconds, versions the logo url
+    pub updated_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BrandingRequest {
+    #[serde(default)]
+    pub product_name: Option<String>,
+    /// Notice shown on the login page, e.g. a legal banner
+    #[serde(default)]
+    pub login_message: Option<String>,
+    /// Base64 encoded PNG or SVG image, the logo is removed without it
+    #[serde(default)]
+    pub logo: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BrandingResponse {
+    pub product_name: Option<String>,
+    pub login_message: Option<String>,
+    pub logo_url: Option<String>,
+}
@@ -134,6 +134,33 @@ impl HttpResponse {
         ))
     }
 
+    /// Send a PayloadTooLarge response in json format and associate the
+    /// provided error as `error` field.
+    pub fn payload_too_large(error: impl ToString) -> ActixHttpResponse {
+        ActixHttpResponse::PayloadTooLarge().json(Self::error(
+            StatusCode::PAYLOAD_TOO_LARGE.into(),
+            error.to_string(),
+        ))
+    }
+
+    /// Send an UnsupportedMediaType response in json format and associate the
+    /// provided error as `error` field.
+    pub fn unsupported_media_type(error: impl ToString) -> ActixHttpResponse {
+        ActixHttpResponse::UnsupportedMediaType().json(Self::error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE.into(),
+            error.to_string(),
+        ))
+    }
+
+    /// Send a ServiceUnavailable response in json format and associate the
+    /// provided error as `error` field.
+    pub fn service_unavailable(error: impl ToString) -> ActixHttpResponse {
+        ActixHttpResponse::ServiceUnavailable().json(Self::error(
+            StatusCode::SERVICE_UNAVAILABLE.into(),
+            error.to_string(),
+        ))
+    }
+
     /// Send a response in json format, status code is 200.
     /// The payload should be serde-serializable.
     pub fn json(payload: impl Serialize) -> ActixHttpResponse {
@@ -103,6 +103,20 @@ pub struct BulkResponse {
     pub took: u128,
     pub errors: bool,
     pub items: Vec<HashMap<String, BulkResponseItem>>,
+    /// Positions in the request payload of the records buffered for each
+    /// stream, set only when the caller asked for positional errors.
+    #[serde(skip)]
+    pub positions: Option<HashMap<String, Vec<usize>>>,
+}
+
+impl BulkResponse {
+    /// Payload position of the `idx`-th record buffered for `stream_name`
+    pub fn record_position(&self, stream_name: &str, idx: usize) -> Option<usize> {
+        self.positions
+            .as_ref()
+            .and_then(|p| p.get(stream_name))
+            .and_then(|p| p.get(idx).copied())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -126,6 +140,9 @@ pub struct BulkResponseItem {
     #[serde(rename = "originalRecord")]
     #[schema(value_type = Object)]
     pub original_record: Option<json::Value>,
+    /// Index of the action within the `_bulk` payload, set in positional mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
 }
 
 pub enum IngestionStatus {
@@ -181,6 +198,7 @@ impl BulkResponseItem {
             status: 422,
             error: Some(error),
             original_record: orig_record,
+            position: None,
         }
     }
 
@@ -205,6 +223,7 @@ impl BulkResponseItem {
             status: 200,
             error: None,
             original_record: None,
+            position: None,
         }
     }
 }
@@ -0,0 +1,71 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::UserRole;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    pub email: String,
+    pub role: UserRole,
+    /// Email the invite link to the invitee, needs SMTP to be configured
+    #[serde(default)]
+    pub send_email: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateInviteResponse {
+    pub id: String,
+    /// Only returned once, the server keeps a hash of it
+    pub token: String,
+    /// Unix timestamp in microseconds
+    pub expires_at: i64,
+    pub email_sent: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InviteResponse {
+    pub id: String,
+    pub email: String,
+    pub role: UserRole,
+    pub created_by: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InviteList {
+    pub data: Vec<InviteResponse>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AcceptInviteRequest {
+    pub token: String,
+    /// Required unless SSO is enabled, ignored if the user already exists
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub last_name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AcceptInviteResponse {
+    pub org: String,
+    pub email: String,
+    pub role: UserRole,
+}
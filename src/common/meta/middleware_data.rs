@@ -27,7 +27,7 @@ use serde::{Deserialize, Serialize};
 use uaparser::{Parser, UserAgentParser};
 
 use crate::{
-    common::{infra::config::MAXMIND_DB_CLIENT, utils::http::parse_ip_addr},
+    common::{infra::config::MAXMIND_DB_CLIENT, utils::http::get_real_ip},
     USER_AGENT_REGEX_FILE,
 };
 
@@ -99,21 +99,10 @@ impl RumExtraData {
         // Now extend the existing hashmap with tags.
         user_agent_hashmap.extend(tags);
         {
-            let headers = req.headers();
-            let conn_info = req.connection_info().clone();
-            let ip_address = match headers.contains_key("X-Forwarded-For")
-                || headers.contains_key("Forwarded")
-            {
-                true => conn_info.realip_remote_addr().unwrap(),
-                false => conn_info.peer_addr().unwrap(),
-            };
-
-            user_agent_hashmap.insert("ip".into(), ip_address.into());
-            let ip = match parse_ip_addr(ip_address) {
-                Ok((ip, _)) => ip,
-                // Default to ipv4 loopback address
-                Err(_) => IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
-            };
+            // Default to ipv4 loopback address
+            let ip = get_real_ip(req.request())
+                .unwrap_or(IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)));
+            user_agent_hashmap.insert("ip".into(), ip.to_string().into());
 
             let maxminddb_client = MAXMIND_DB_CLIENT.read().await;
             let geo_info = if let Some(client) = maxminddb_client.as_ref() {
@@ -198,6 +187,44 @@ mod tests {
         assert!(!data.contains_key("o2-api-key"));
     }
 
+    #[tokio::test]
+    async fn test_rum_records_carry_real_ip() {
+        use actix_web::{test, App, HttpResponse};
+        use actix_web_lab::middleware::from_fn;
+
+        use crate::handler::http::router::middlewares::real_ip;
+
+        async fn ingest(data: web::ReqData<RumExtraData>) -> HttpResponse {
+            HttpResponse::Ok().json(&data.data["ip"])
+        }
+        let app = test::init_service(
+            App::new().wrap(from_fn(real_ip)).service(
+                web::scope("/rum")
+                    .wrap(from_fn(RumExtraData::extractor))
+                    .route("/v1/logs", web::post().to(ingest)),
+            ),
+        )
+        .await;
+
+        // behind a load balancer
+        let req = test::TestRequest::post()
+            .uri("/rum/v1/logs?oo-param1=value1")
+            .peer_addr("10.0.0.1:43210".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "203.0.113.7, 10.0.0.1"))
+            .to_request();
+        let ip: String = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(ip, "203.0.113.7");
+
+        // garbage falls back to the peer address
+        let req = test::TestRequest::post()
+            .uri("/rum/v1/logs")
+            .peer_addr("10.0.0.1:43210".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "unknown"))
+            .to_request();
+        let ip: String = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(ip, "10.0.0.1");
+    }
+
     #[tokio::test]
     async fn test_filter_tags() {
         // Create a mock query string
@@ -14,8 +14,10 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 pub mod authz;
+pub mod branding;
 pub mod http;
 pub mod ingestion;
+pub mod invite;
 pub mod maxmind;
 pub mod middleware_data;
 pub mod organization;
@@ -151,6 +151,10 @@ pub struct OrganizationSettingPayload {
     pub enable_websocket_search: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_auto_refresh_interval: Option<u32>,
+    /// Searches the organization can run at the same time on a node, 0 means
+    /// no limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_max_concurrency: Option<usize>,
 }
 
 #[derive(Serialize, ToSchema, Deserialize, Debug, Clone)]
@@ -169,6 +173,9 @@ pub struct OrganizationSetting {
     pub enable_websocket_search: bool,
     #[serde(default = "default_auto_refresh_interval")]
     pub min_auto_refresh_interval: u32,
+    /// Overrides `ZO_SEARCH_MAX_CONCURRENCY_PER_ORG` for the organization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_max_concurrency: Option<usize>,
 }
 
 impl Default for OrganizationSetting {
@@ -180,6 +187,7 @@ impl Default for OrganizationSetting {
             toggle_ingestion_logs: default_toggle_ingestion_logs(),
             enable_websocket_search: default_enable_websocket_search(),
             min_auto_refresh_interval: default_auto_refresh_interval(),
+            search_max_concurrency: None,
         }
     }
 }
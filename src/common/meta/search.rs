@@ -48,6 +48,13 @@ pub struct CacheQueryRequest {
     pub ts_column: String,
     pub discard_interval: i64,
     pub is_descending: bool,
+    /// Count-only `track_total_hits` query, whose cached responses can't be
+    /// trimmed to the query time range
+    #[serde(default)]
+    pub is_count: bool,
+    /// Width in months of calendar histogram buckets, 0 for fixed width ones
+    #[serde(default)]
+    pub calendar_months: i64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Default)]
@@ -67,7 +74,7 @@ pub struct MultiCachedQueryResponse {
     pub trace_id: String,
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
 pub enum ResultCacheSelectionStrategy {
     #[serde(rename = "overlap")]
     Overlap,
@@ -91,3 +98,79 @@ impl FromStr for ResultCacheSelectionStrategy {
         }
     }
 }
+
+/// Explanation of the result cache decision for a search request, as produced
+/// by the cache check without executing the search.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Default)]
+pub struct CacheExplainReport {
+    pub trace_id: String,
+    pub hash_inputs: CacheHashInputs,
+    /// Cache key the results of the query are stored under.
+    pub file_path: String,
+    pub is_aggregate: bool,
+    pub ts_column: Option<String>,
+    pub is_descending: Option<bool>,
+    /// Histogram interval of the query in seconds.
+    pub histogram_interval: Option<i64>,
+    pub query_start_time: i64,
+    pub query_end_time: i64,
+    pub selection_strategy: ResultCacheSelectionStrategy,
+    /// Cached windows that existed for the cache key.
+    pub windows: Vec<CacheWindowExplain>,
+    pub selection_reason: Option<String>,
+    /// Time ranges that would still be searched, derived from the bounds of the
+    /// selected window.
+    pub deltas: Vec<QueryDelta>,
+    pub disqualification: Option<CacheDisqualification>,
+    pub outcome: CacheExplainOutcome,
+}
+
+/// Values hashed into the result cache key of a query.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Default)]
+pub struct CacheHashInputs {
+    /// SQL after newline normalization.
+    pub sql: String,
+    pub has_vrl: bool,
+    #[serde(default)]
+    pub track_total_hits: bool,
+    pub regions: Vec<String>,
+    pub clusters: Vec<String>,
+}
+
+/// A cached window along with the score each selection strategy gives it.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Default, PartialEq, Eq)]
+pub struct CacheWindowExplain {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub overlaps_query: bool,
+    pub overlap_score: i64,
+    pub duration_score: i64,
+    pub both_score: i64,
+    pub selected: bool,
+}
+
+/// Condition that prevents a query from being served from the result cache.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum CacheDisqualification {
+    CacheDisabled,
+    PaginatedQuery,
+    ParseError { error: String },
+    MultiStream { streams: Vec<String> },
+    NoTimestampColumn,
+    TrackTotalHits,
+    OrderByNotTimestamp { field: String },
+    DiscardWindow { start_time: i64, end_time: i64 },
+    LocalDayHistogram { timezone: String },
+    MultipleHistogramIntervals { intervals: Vec<i64> },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheExplainOutcome {
+    Hit,
+    PartialHit,
+    #[default]
+    Miss,
+    Disqualified,
+}
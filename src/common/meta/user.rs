@@ -299,6 +299,59 @@ pub struct UserList {
     pub data: Vec<UserResponse>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkUserRoleChange {
+    pub email: String,
+    pub role: UserRole,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct BulkUserUpdateRequest {
+    #[serde(default)]
+    pub changes: Vec<BulkUserRoleChange>,
+    /// Emails of the users to remove from the organization
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkUserAction {
+    Update,
+    Remove,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkUserStatus {
+    /// Accepted, will be applied when not a dry run
+    Planned,
+    /// The user already has the requested role
+    Unchanged,
+    Applied,
+    /// The email is not a member of the organization
+    UnknownUser,
+    Rejected,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkUserUpdateItem {
+    pub email: String,
+    pub action: BulkUserAction,
+    pub current_role: Option<UserRole>,
+    pub proposed_role: Option<UserRole>,
+    pub status: BulkUserStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkUserUpdateResponse {
+    pub dry_run: bool,
+    pub items: Vec<BulkUserUpdateItem>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct SignInUser {
     pub name: String,
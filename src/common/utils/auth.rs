@@ -96,6 +96,17 @@ pub(crate) fn is_root_user(user_id: &str) -> bool {
     }
 }
 
+/// Whether the user is the root user or an admin of the org
+pub(crate) async fn is_org_admin(org_id: &str, user_id: &str) -> bool {
+    if is_root_user(user_id) {
+        return true;
+    }
+    match crate::service::db::user::get(Some(org_id), user_id).await {
+        Ok(Some(user)) => matches!(user.role, UserRole::Admin | UserRole::Root),
+        _ => false,
+    }
+}
+
 #[cfg(feature = "enterprise")]
 pub fn get_role(role: UserRole) -> UserRole {
     use std::str::FromStr;
@@ -227,6 +238,11 @@ impl FromRequest for AuthExtractor {
         let url_len = path_columns.len();
         let org_id = path_columns[0].to_string();
 
+        // partial stream settings updates need the same permission as full ones
+        if method.eq("PATCH") && url_len == 4 && path_columns[3].eq("settings") {
+            method = "PUT".to_string();
+        }
+
         // This is case for ingestion endpoints where we need to check
         // permissions on the stream
         if method.eq("POST") && INGESTION_EP.contains(&path_columns[url_len - 1]) {
@@ -330,6 +346,10 @@ impl FromRequest for AuthExtractor {
             } else if path_columns[2].starts_with("_values")
                 || path_columns[2].starts_with("_around")
             {
+                // the batched _values is a POST, but only reads the stream
+                if method.eq("POST") {
+                    method = "GET".to_string();
+                }
                 // special case of _values/_around , where we need permission on that stream,
                 // as it is part of search, but still 3-part route
                 format!(
@@ -515,7 +535,7 @@ impl FromRequest for AuthExtractor {
                 || path.contains("/traces/latest")
                 || path.contains("clusters")
                 || path.contains("query_manager")
-                || path.contains("/short")
+                || (path.contains("/short") && !path.contains("/short_urls"))
                 || path.contains("/ws")
             {
                 return ready(Ok(AuthExtractor {
@@ -770,6 +790,76 @@ pub async fn check_permissions(
     true
 }
 
+/// Capability a user needs to run an ad-hoc VRL function (`query_fn`) with a search, named in
+/// the error returned to users without it.
+pub const ADHOC_FUNCTION_CAPABILITY: &str = "function:execute_adhoc";
+
+/// Object id checked on the functions object type for [`ADHOC_FUNCTION_CAPABILITY`] by the
+/// enterprise RBAC.
+#[cfg(feature = "enterprise")]
+const ADHOC_FUNCTION_OBJECT: &str = "_execute_adhoc";
+
+/// Returns false if the user may not run ad-hoc VRL functions in the org, either because its
+/// role is listed in `ZO_QUERY_FUNCTION_DENIED_ROLES` or because the RBAC denies it. Root and
+/// admin users are always allowed.
+///
+/// Without the enterprise RBAC the roles list is the only restriction, it is empty by default,
+/// so every user of the org may run functions until the roles are listed there.
+pub async fn can_execute_adhoc_function(org_id: &str, user_id: &str) -> bool {
+    if is_root_user(user_id) {
+        return true;
+    }
+    let role = match USERS.get(&format!("{org_id}/{user_id}")) {
+        Some(user) => user.role.clone(),
+        None => return false,
+    };
+    if role.eq(&UserRole::Admin) {
+        return true;
+    }
+    if is_adhoc_function_denied_role(
+        &config::get_config().common.query_function_denied_roles,
+        &role,
+    ) {
+        return false;
+    }
+
+    #[cfg(feature = "enterprise")]
+    return check_permissions(
+        Some(ADHOC_FUNCTION_OBJECT.to_string()),
+        org_id,
+        user_id,
+        "functions",
+        "GET",
+    )
+    .await;
+    #[cfg(not(feature = "enterprise"))]
+    return true;
+}
+
+/// Rejects a search that brings an ad-hoc VRL function (`query_fn`) the user may not run, it
+/// must be called before any search work is done.
+pub async fn check_adhoc_function_permission(
+    org_id: &str,
+    user_id: &str,
+    query_fn: Option<&str>,
+) -> Result<(), infra::errors::ErrorCodes> {
+    if query_fn.is_none_or(|f| f.trim().is_empty())
+        || can_execute_adhoc_function(org_id, user_id).await
+    {
+        return Ok(());
+    }
+    Err(infra::errors::ErrorCodes::SearchFunctionNotAllowed(
+        ADHOC_FUNCTION_CAPABILITY.to_string(),
+    ))
+}
+
+fn is_adhoc_function_denied_role(denied_roles: &str, role: &UserRole) -> bool {
+    let role = role.to_string();
+    denied_roles
+        .split(',')
+        .any(|v| v.trim().eq_ignore_ascii_case(&role))
+}
+
 #[cfg(test)]
 mod tests {
     use infra::db as infra_db;
@@ -824,6 +914,44 @@ mod tests {
         assert!(!is_root_user("root2@example.com"));
     }
 
+    #[test]
+    fn test_is_adhoc_function_denied_role() {
+        assert!(!is_adhoc_function_denied_role("", &UserRole::Member));
+        assert!(is_adhoc_function_denied_role(
+            "member, service_account",
+            &UserRole::Member
+        ));
+        assert!(is_adhoc_function_denied_role(
+            "member,Service_Account",
+            &UserRole::ServiceAccount
+        ));
+        assert!(!is_adhoc_function_denied_role("member", &UserRole::Admin));
+    }
+
+    #[tokio::test]
+    async fn test_can_execute_adhoc_function_unknown_user() {
+        assert!(!can_execute_adhoc_function("default", "nobody@example.com").await);
+    }
+
+    #[tokio::test]
+    async fn test_check_adhoc_function_permission() {
+        let user_id = "no_functions@example.com";
+        assert!(check_adhoc_function_permission("default", user_id, None)
+            .await
+            .is_ok());
+        assert!(
+            check_adhoc_function_permission("default", user_id, Some(" "))
+                .await
+                .is_ok()
+        );
+        match check_adhoc_function_permission("default", user_id, Some(".a = 1")).await {
+            Err(infra::errors::ErrorCodes::SearchFunctionNotAllowed(capability)) => {
+                assert_eq!(capability, ADHOC_FUNCTION_CAPABILITY)
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn test_get_hash() {
         let hash =
@@ -0,0 +1,197 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Read;
+
+use bytes::Bytes;
+use config::get_config;
+use flate2::read::GzDecoder;
+
+/// Frame magic number every zstd payload starts with.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+/// Magic number every gzip member starts with.
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compresses a result cache payload according to `ZO_RESULT_CACHE_COMPRESSION`.
+/// The data is kept as is when compression is disabled or fails.
+pub fn compress_cache_data(data: Bytes) -> Bytes {
+    if get_config().common.result_cache_compression != "zstd" {
+        return data;
+    }
+    match zstd::encode_all(data.as_ref(), ZSTD_LEVEL) {
+        Ok(v) => Bytes::from(v),
+        Err(e) => {
+            log::error!("compress result cache data error: {}", e);
+            data
+        }
+    }
+}
+
+/// Decompresses a result cache payload read from disk. Entries are detected
+/// by the zstd magic bytes, so files written before compression was enabled
+/// are returned unchanged.
+pub fn decompress_cache_data(data: Bytes) -> std::io::Result<Bytes> {
+    if !data.starts_with(&ZSTD_MAGIC) {
+        return Ok(data);
+    }
+    zstd::decode_all(data.as_ref()).map(Bytes::from)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestBodyError {
+    #[error("Unsupported content encoding {0}, expected gzip or zstd")]
+    UnsupportedEncoding(String),
+    #[error("Malformed {0} request body: {1}")]
+    Malformed(&'static str, std::io::Error),
+    #[error("Request body is larger than {0} bytes after decompression")]
+    TooLarge(usize),
+}
+
+/// Decompresses a request body according to its `Content-Encoding`, reading at
+/// most `limit` decompressed bytes so small bodies can't inflate unbounded.
+/// Bodies without the magic number of the encoding are returned as they are,
+/// the http server may have decompressed them already.
+pub fn decode_request_body(
+    content_encoding: Option<&str>,
+    body: Bytes,
+    limit: usize,
+) -> Result<Bytes, RequestBodyError> {
+    let encoding = content_encoding
+        .map(|v| v.trim().to_lowercase())
+        .unwrap_or_default();
+    match encoding.as_str() {
+        "" | "identity" => Ok(body),
+        "gzip" | "x-gzip" => {
+            if !body.starts_with(&GZIP_MAGIC) {
+                return Ok(body);
+            }
+            read_limited("gzip", GzDecoder::new(body.as_ref()), limit)
+        }
+        "zstd" => {
+            if !body.starts_with(&ZSTD_MAGIC) {
+                return Ok(body);
+            }
+            let decoder = zstd::Decoder::new(body.as_ref())
+                .map_err(|e| RequestBodyError::Malformed("zstd", e))?;
+            read_limited("zstd", decoder, limit)
+        }
+        _ => Err(RequestBodyError::UnsupportedEncoding(encoding)),
+    }
+}
+
+fn read_limited(
+    encoding: &'static str,
+    reader: impl Read,
+    limit: usize,
+) -> Result<Bytes, RequestBodyError> {
+    let mut data = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| RequestBodyError::Malformed(encoding, e))?;
+    if data.len() > limit {
+        return Err(RequestBodyError::TooLarge(limit));
+    }
+    Ok(Bytes::from(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_data_zstd_round_trip() {
+        let data = Bytes::from(r#"{"took":1,"hits":[{"_timestamp":1,"log":"foo"}]}"#.repeat(64));
+        let compressed = zstd::encode_all(data.as_ref(), ZSTD_LEVEL).unwrap();
+        assert!(compressed.starts_with(&ZSTD_MAGIC));
+        assert!(compressed.len() < data.len());
+        let decompressed = decompress_cache_data(Bytes::from(compressed)).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn test_cache_data_legacy_uncompressed() {
+        let data = Bytes::from(r#"{"took":1,"hits":[]}"#);
+        assert_eq!(decompress_cache_data(data.clone()).unwrap(), data);
+        let data = Bytes::from_static(&[0x0a, 0x03, 0x66, 0x6f, 0x6f]);
+        assert_eq!(decompress_cache_data(data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_cache_data_compression_disabled() {
+        let data = Bytes::from(r#"{"took":1,"hits":[]}"#);
+        assert_eq!(compress_cache_data(data.clone()), data);
+    }
+
+    fn gzip(data: &[u8]) -> Bytes {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(data).unwrap();
+        Bytes::from(encoder.finish().unwrap())
+    }
+
+    #[test]
+    fn test_decode_request_body() {
+        let data = Bytes::from_static(&[0x0a, 0x03, 0x66, 0x6f, 0x6f]);
+        assert_eq!(
+            decode_request_body(Some("gzip"), gzip(&data), 1024).unwrap(),
+            data
+        );
+        let compressed = Bytes::from(zstd::encode_all(data.as_ref(), ZSTD_LEVEL).unwrap());
+        assert_eq!(
+            decode_request_body(Some("zstd"), compressed, 1024).unwrap(),
+            data
+        );
+        assert_eq!(decode_request_body(None, data.clone(), 1024).unwrap(), data);
+        // already decompressed by the http server
+        assert_eq!(
+            decode_request_body(Some("GZIP"), data.clone(), 1024).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_decode_request_body_errors() {
+        let data = Bytes::from_static(b"foo");
+        assert!(matches!(
+            decode_request_body(Some("br"), data, 1024),
+            Err(RequestBodyError::UnsupportedEncoding(encoding)) if encoding == "br"
+        ));
+        let truncated = gzip(&[0; 64]).slice(..12);
+        assert!(matches!(
+            decode_request_body(Some("gzip"), truncated, 1024),
+            Err(RequestBodyError::Malformed("gzip", _))
+        ));
+    }
+
+    #[test]
+    fn test_decode_request_body_limit_applies_after_decompression() {
+        // a tiny body inflating past the limit
+        let bomb = gzip(&vec![0; 1024 * 1024]);
+        assert!(bomb.len() < 4096);
+        assert!(matches!(
+            decode_request_body(Some("gzip"), bomb.clone(), 64 * 1024),
+            Err(RequestBodyError::TooLarge(65536))
+        ));
+        assert_eq!(
+            decode_request_body(Some("gzip"), bomb, 1024 * 1024)
+                .unwrap()
+                .len(),
+            1024 * 1024
+        );
+    }
+}
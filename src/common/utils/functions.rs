@@ -13,11 +13,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+};
 
 use config::{
     meta::function::VRLCompilerConfig, GEO_IP_ASN_ENRICHMENT_TABLE, GEO_IP_CITY_ENRICHMENT_TABLE,
 };
+use sqlparser::{
+    ast::{visit_expressions, Expr},
+    dialect::PostgreSqlDialect,
+    parser::Parser,
+};
 use vector_enrichment::{Table, TableRegistry};
 
 use crate::common::{
@@ -38,6 +46,39 @@ pub async fn get_all_transform_keys(org_id: &str) -> Vec<String> {
         .collect()
 }
 
+/// Returns the functions of the org the SQL query calls, sorted by name
+pub async fn get_used_transform_keys(org_id: &str, sql: &str) -> Vec<String> {
+    let called = called_function_names(sql);
+    if called.is_empty() {
+        return vec![];
+    }
+    let mut keys = get_all_transform_keys(org_id)
+        .await
+        .into_iter()
+        .filter(|fn_name| called.contains(fn_name))
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Returns the names of the functions the SQL query calls, without their
+/// qualifiers, or none if the query can't be parsed.
+fn called_function_names(sql: &str) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let Ok(statements) = Parser::parse_sql(&PostgreSqlDialect {}, sql) else {
+        return names;
+    };
+    let _ = visit_expressions(&statements, |expr| {
+        if let Expr::Function(f) = expr {
+            if let Some(name) = f.name.0.last() {
+                names.insert(name.value.clone());
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    names
+}
+
 pub fn init_vrl_runtime() -> vrl::compiler::runtime::Runtime {
     vrl::compiler::runtime::Runtime::new(vrl::prelude::state::RuntimeState::default())
 }
@@ -90,3 +131,20 @@ pub fn get_vrl_compiler_config(org_id: &str) -> VRLCompilerConfig {
     config.set_custom(registry);
     VRLCompilerConfig { config, functions }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_called_function_names() {
+        let names = called_function_names(
+            "SELECT unmask(log), geo_ip(ip) FROM \"default\" WHERE msg = 'mask(x)' AND \
+             lower(mask_v2(a)) = 'b'",
+        );
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["geo_ip", "lower", "mask_v2", "unmask"]);
+        assert!(called_function_names("SELECT mask(").is_empty());
+    }
+}
@@ -22,10 +22,14 @@ use std::{
 use actix_web::{
     http::header::{HeaderMap, HeaderName},
     web::Query,
+    HttpMessage, HttpRequest,
 };
-use config::meta::{
-    search::{SearchEventContext, SearchEventType},
-    stream::StreamType,
+use config::{
+    get_config,
+    meta::{
+        search::{SearchEventContext, SearchEventType},
+        stream::StreamType,
+    },
 };
 use opentelemetry::{global, propagation::Extractor, trace::TraceContextExt};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
@@ -153,6 +157,79 @@ pub fn parse_ip_addr(ip_address: &str) -> Result<(IpAddr, Option<u16>), AddrPars
     Ok((ip, port))
 }
 
+/// The client IP of a request, added to its extensions by the `real_ip`
+/// middleware.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RealIp(pub IpAddr);
+
+/// Gets the client IP of a request, see [`resolve_real_ip`].
+pub fn get_real_ip(req: &HttpRequest) -> Option<IpAddr> {
+    if let Some(RealIp(ip)) = req.extensions().get::<RealIp>() {
+        return Some(*ip);
+    }
+    resolve_real_ip(req.headers(), req.peer_addr().map(|addr| addr.ip()))
+}
+
+/// Resolves the client IP from the header set by the trusted proxies, see
+/// `ZO_TRUSTED_PROXY_HEADER` and `ZO_TRUSTED_PROXY_HOPS`. Falls back to the
+/// peer address when the header is missing or can not be parsed.
+pub fn resolve_real_ip(headers: &HeaderMap, peer_addr: Option<IpAddr>) -> Option<IpAddr> {
+    let cfg = get_config();
+    resolve_real_ip_with(
+        headers,
+        peer_addr,
+        &cfg.http.trusted_proxy_header,
+        cfg.http.trusted_proxy_hops,
+    )
+}
+
+fn resolve_real_ip_with(
+    headers: &HeaderMap,
+    peer_addr: Option<IpAddr>,
+    proxy_header: &str,
+    proxy_hops: usize,
+) -> Option<IpAddr> {
+    let ip = if proxy_header.eq_ignore_ascii_case("x-forwarded-for") {
+        // proxies may append to the header or add another one
+        let entries = headers
+            .get_all("x-forwarded-for")
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        if proxy_hops == 0 {
+            // no trusted proxy, anyone could have sent the header
+            None
+        } else {
+            let idx = entries.len().saturating_sub(proxy_hops);
+            entries.get(idx).and_then(|v| parse_forwarded_ip(v))
+        }
+    } else if proxy_header.eq_ignore_ascii_case("x-real-ip") {
+        headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_forwarded_ip(v.trim()))
+    } else {
+        None
+    };
+    ip.or(peer_addr)
+}
+
+/// Parses an IP forwarded by a proxy, which may carry a port, without logging
+/// garbage sent by clients.
+fn parse_forwarded_ip(ip_address: &str) -> Option<IpAddr> {
+    ip_address
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| ip_address.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            ip_address
+                .strip_prefix('[')
+                .and_then(|v| v.strip_suffix(']'))
+                .and_then(|v| v.parse::<IpAddr>().ok())
+        })
+}
+
 // Extractor for request headers
 pub struct RequestHeaderExtractor<'a> {
     headers: &'a HeaderMap,
@@ -215,6 +292,68 @@ mod tests {
         assert_eq!(resp, Some(StreamType::Traces));
     }
 
+    fn resolve(headers: &[(&str, &str)], proxy_header: &str, proxy_hops: usize) -> String {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        let peer_addr = Some("10.0.0.1".parse().unwrap());
+        resolve_real_ip_with(&map, peer_addr, proxy_header, proxy_hops)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_resolve_real_ip() {
+        let xff = "x-forwarded-for";
+        let xff_list = [(xff, "203.0.113.7, 198.51.100.2, 192.0.2.9")];
+        // no trusted proxy, the header is ignored
+        assert_eq!(resolve(&xff_list, xff, 0), "10.0.0.1");
+        // the load balancer appended the client
+        assert_eq!(resolve(&xff_list, xff, 1), "192.0.2.9");
+        assert_eq!(resolve(&xff_list, xff, 2), "198.51.100.2");
+        // fewer entries than trusted hops
+        assert_eq!(resolve(&xff_list, xff, 5), "203.0.113.7");
+        // entries split across several headers
+        let headers = [(xff, "203.0.113.7"), (xff, "198.51.100.2")];
+        assert_eq!(resolve(&headers, xff, 1), "198.51.100.2");
+
+        // ipv6 with and without ports
+        assert_eq!(resolve(&[(xff, "2001:db8::1")], xff, 1), "2001:db8::1");
+        assert_eq!(
+            resolve(&[(xff, "[2001:db8::1]:443")], xff, 1),
+            "2001:db8::1"
+        );
+        assert_eq!(resolve(&[(xff, "[2001:db8::1]")], xff, 1), "2001:db8::1");
+        assert_eq!(resolve(&[(xff, "203.0.113.7:8080")], xff, 1), "203.0.113.7");
+
+        // x-real-ip
+        let headers = [("x-real-ip", " 203.0.113.7 "), (xff, "198.51.100.2")];
+        assert_eq!(resolve(&headers, "x-real-ip", 0), "203.0.113.7");
+        assert_eq!(resolve(&headers, xff, 1), "198.51.100.2");
+        assert_eq!(resolve(&headers, "none", 0), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_resolve_real_ip_falls_back_to_peer_addr() {
+        let xff = "x-forwarded-for";
+        assert_eq!(resolve(&[], xff, 1), "10.0.0.1");
+        assert_eq!(resolve(&[], "x-real-ip", 0), "10.0.0.1");
+        assert_eq!(resolve(&[(xff, "unknown")], xff, 1), "10.0.0.1");
+        assert_eq!(resolve(&[(xff, "")], xff, 1), "10.0.0.1");
+        assert_eq!(resolve(&[(xff, "203.0.113.7,,")], xff, 1), "10.0.0.1");
+        assert_eq!(resolve(&[(xff, "300.1.2.3")], xff, 1), "10.0.0.1");
+        assert_eq!(resolve(&[(xff, "<script>")], xff, 1), "10.0.0.1");
+        assert_eq!(
+            resolve(&[("x-real-ip", "::1::2")], "x-real-ip", 0),
+            "10.0.0.1"
+        );
+        assert_eq!(resolve_real_ip_with(&HeaderMap::new(), None, xff, 1), None);
+    }
+
     /// Test logic for IP parsing
     #[test]
     fn test_ip_parsing() {
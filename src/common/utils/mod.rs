@@ -15,6 +15,7 @@
 
 pub mod auth;
 mod auth_tests;
+pub mod compression;
 pub mod functions;
 pub mod http;
 pub mod jwt;
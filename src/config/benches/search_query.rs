@@ -0,0 +1,54 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::meta::search::{Query, SearchQueryRef};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use proto::cluster_rpc;
+
+/// Compares reading the query of a search request for planning its SQL through
+/// the gRPC form, as the result cache checks used to, with borrowing it.
+pub fn search_query_benchmark(c: &mut Criterion) {
+    let query = Query {
+        sql: format!(
+            "SELECT histogram(_timestamp) AS ts, k8s_namespace_name, count(*) AS cnt \
+             FROM \"default\" WHERE {} GROUP BY ts, k8s_namespace_name ORDER BY ts DESC",
+            (0..20)
+                .map(|i| format!("k8s_pod_name != 'pod-{i}'"))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        ),
+        start_time: 1_700_000_000_000_000,
+        end_time: 1_700_003_600_000_000,
+        size: 100,
+        query_fn: Some("LmxldmVsID0gImluZm8iCi4=".to_string()),
+        timezone: Some("UTC".to_string()),
+        ..Default::default()
+    };
+
+    let mut group = c.benchmark_group("search/query_for_sql");
+    group.bench_function("proto_conversion", |b| {
+        b.iter(|| {
+            let query: cluster_rpc::SearchQuery = black_box(&query).clone().into();
+            black_box(SearchQueryRef::from(&query).sql.len())
+        });
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| black_box(SearchQueryRef::from(black_box(&query)).sql.len()));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, search_query_benchmark);
+criterion_main!(benches);
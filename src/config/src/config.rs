@@ -31,6 +31,7 @@ use lettre::{
     AsyncSmtpTransport, Tokio1Executor,
 };
 use once_cell::sync::Lazy;
+use serde::Serialize;
 
 use crate::{
     meta::cluster,
@@ -217,7 +218,14 @@ pub static MEM_TABLE_INDIVIDUAL_STREAMS: Lazy<HashMap<String, usize>> = Lazy::ne
     map
 });
 
-pub static CONFIG: Lazy<ArcSwap<Config>> = Lazy::new(|| ArcSwap::from(Arc::new(init())));
+pub static CONFIG: Lazy<ArcSwap<Config>> = Lazy::new(|| {
+    let cfg = init();
+    *LOADED_ENV.write() = zo_env();
+    ArcSwap::from(Arc::new(cfg))
+});
+/// The `ZO_*` environment `CONFIG` was built from
+static LOADED_ENV: Lazy<parking_lot::RwLock<BTreeMap<String, String>>> =
+    Lazy::new(Default::default);
 static INSTANCE_ID: Lazy<RwHashMap<String, String>> = Lazy::new(Default::default);
 
 pub static TELEMETRY_CLIENT: Lazy<segment::HttpClient> = Lazy::new(|| {
@@ -235,8 +243,172 @@ pub fn get_config() -> Arc<Config> {
 }
 
 pub fn refresh_config() -> Result<(), anyhow::Error> {
-    CONFIG.store(Arc::new(init()));
-    Ok(())
+    reload_config().map(|_| ())
+}
+
+/// Settings only read at startup, a reload keeps their current value
+type KeepField = fn(&mut Config, &Config);
+const RESTART_REQUIRED: &[(&str, KeepField)] = &[
+    ("ZO_HTTP_PORT", |new, old| new.http.port = old.http.port),
+    ("ZO_HTTP_ADDR", |new, old| {
+        new.http.addr = old.http.addr.clone()
+    }),
+    ("ZO_HTTP_IPV6_ENABLED", |new, old| {
+        new.http.ipv6_enabled = old.http.ipv6_enabled
+    }),
+    ("ZO_HTTP_TLS_ENABLED", |new, old| {
+        new.http.tls_enabled = old.http.tls_enabled
+    }),
+    ("ZO_HTTP_TLS_CERT_PATH", |new, old| {
+        new.http.tls_cert_path = old.http.tls_cert_path.clone()
+    }),
+    ("ZO_HTTP_TLS_KEY_PATH", |new, old| {
+        new.http.tls_key_path = old.http.tls_key_path.clone()
+    }),
+    ("ZO_GRPC_PORT", |new, old| new.grpc.port = old.grpc.port),
+    ("ZO_GRPC_ADDR", |new, old| {
+        new.grpc.addr = old.grpc.addr.clone()
+    }),
+    ("ZO_GRPC_TLS_ENABLED", |new, old| {
+        new.grpc.tls_enabled = old.grpc.tls_enabled
+    }),
+    ("ZO_GRPC_TLS_CERT_DOMAIN", |new, old| {
+        new.grpc.tls_cert_domain = old.grpc.tls_cert_domain.clone()
+    }),
+    ("ZO_GRPC_TLS_CERT_PATH", |new, old| {
+        new.grpc.tls_cert_path = old.grpc.tls_cert_path.clone()
+    }),
+    ("ZO_GRPC_TLS_KEY_PATH", |new, old| {
+        new.grpc.tls_key_path = old.grpc.tls_key_path.clone()
+    }),
+    ("ZO_TCP_PORT", |new, old| {
+        new.tcp.tcp_port = old.tcp.tcp_port
+    }),
+    ("ZO_UDP_PORT", |new, old| {
+        new.tcp.udp_port = old.tcp.udp_port
+    }),
+    ("ZO_LOCAL_MODE", |new, old| {
+        new.common.local_mode = old.common.local_mode;
+        new.common.is_local_storage = old.common.is_local_storage;
+    }),
+    ("ZO_LOCAL_MODE_STORAGE", |new, old| {
+        new.common.local_mode_storage = old.common.local_mode_storage.clone();
+        new.common.is_local_storage = old.common.is_local_storage;
+    }),
+    ("ZO_NODE_ROLE", |new, old| {
+        new.common.node_role = old.common.node_role.clone()
+    }),
+    ("ZO_CLUSTER_COORDINATOR", |new, old| {
+        new.common.cluster_coordinator = old.common.cluster_coordinator.clone()
+    }),
+    ("ZO_QUEUE_STORE", |new, old| {
+        new.common.queue_store = old.common.queue_store.clone()
+    }),
+    ("ZO_META_STORE", |new, old| {
+        new.common.meta_store = old.common.meta_store.clone()
+    }),
+    ("ZO_META_POSTGRES_DSN", |new, old| {
+        new.common.meta_postgres_dsn = old.common.meta_postgres_dsn.clone()
+    }),
+    ("ZO_META_MYSQL_DSN", |new, old| {
+        new.common.meta_mysql_dsn = old.common.meta_mysql_dsn.clone()
+    }),
+    ("ZO_DATA_DIR", |new, old| {
+        new.common.data_dir = old.common.data_dir.clone()
+    }),
+    ("ZO_DATA_WAL_DIR", |new, old| {
+        new.common.data_wal_dir = old.common.data_wal_dir.clone()
+    }),
+    ("ZO_DATA_STREAM_DIR", |new, old| {
+        new.common.data_stream_dir = old.common.data_stream_dir.clone()
+    }),
+    ("ZO_DATA_DB_DIR", |new, old| {
+        new.common.data_db_dir = old.common.data_db_dir.clone()
+    }),
+    ("ZO_DATA_CACHE_DIR", |new, old| {
+        new.common.data_cache_dir = old.common.data_cache_dir.clone()
+    }),
+];
+
+/// Outcome of a config reload, by environment variable name
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ConfigReload {
+    /// Applied right away
+    pub changed: Vec<String>,
+    /// Ignored until the process restarts
+    pub requires_restart: Vec<String>,
+}
+
+/// Rebuilds `CONFIG` from the environment, keeping the settings that are only
+/// read at startup. An invalid environment leaves the current config in place.
+pub fn reload_config() -> Result<ConfigReload, anyhow::Error> {
+    let mut cfg = std::panic::catch_unwind(init).map_err(|e| {
+        let msg = e
+            .downcast_ref::<String>()
+            .map(|s| s.as_str())
+            .or_else(|| e.downcast_ref::<&str>().copied())
+            .unwrap_or("invalid config");
+        anyhow::anyhow!("{msg}")
+    })?;
+
+    let old = get_config();
+    let mut loaded = LOADED_ENV.write();
+    let (reload, next_env) = apply_reload(&mut cfg, &old, &loaded, zo_env());
+    CONFIG.store(Arc::new(cfg));
+    *loaded = next_env;
+    Ok(reload)
+}
+
+/// Restores the restart-only settings of `cfg` from `old` and classifies the
+/// variables changed between `loaded` and `env`. Returns the environment the
+/// new config is running with.
+fn apply_reload(
+    cfg: &mut Config,
+    old: &Config,
+    loaded: &BTreeMap<String, String>,
+    env: BTreeMap<String, String>,
+) -> (ConfigReload, BTreeMap<String, String>) {
+    for (_, keep) in RESTART_REQUIRED {
+        keep(cfg, old);
+    }
+
+    let mut reload = ConfigReload::default();
+    let mut next_env = env.clone();
+    for name in changed_env_names(loaded, &env) {
+        if RESTART_REQUIRED.iter().any(|(n, _)| *n == name) {
+            // still running with the old value
+            match loaded.get(&name) {
+                Some(v) => next_env.insert(name.clone(), v.clone()),
+                None => next_env.remove(&name),
+            };
+            reload.requires_restart.push(name);
+        } else {
+            reload.changed.push(name);
+        }
+    }
+    (reload, next_env)
+}
+
+fn zo_env() -> BTreeMap<String, String> {
+    std::env::vars()
+        .filter(|(k, _)| k.starts_with("ZO_"))
+        .collect()
+}
+
+/// Names of the variables set, unset or changed between `old` and `new`
+fn changed_env_names(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut names = old
+        .keys()
+        .chain(new.keys())
+        .filter(|k| old.get(*k) != new.get(*k))
+        .cloned()
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    names
 }
 
 pub fn cache_instance_id(instance_id: &str) {
@@ -397,6 +569,36 @@ pub struct WebSocket {
     pub session_gc_interval_secs: i64,
     #[env_config(name = "ZO_WEBSOCKET_PING_INTERVAL_SECS", default = 15)]
     pub ping_interval_secs: i64,
+    #[env_config(
+        name = "ZO_WEBSOCKET_MAX_MISSED_PONGS",
+        default = 3,
+        help = "Close a websocket session after this many pings in a row went unanswered, 0 to disable"
+    )]
+    pub max_missed_pongs: i64,
+    #[env_config(
+        name = "ZO_WEBSOCKET_ALLOW_ANONYMOUS",
+        default = false,
+        help = "Allow websocket sessions without an authenticated user"
+    )]
+    pub allow_anonymous: bool,
+    #[env_config(
+        name = "ZO_WEBSOCKET_COMPRESSION_THRESHOLD_BYTES",
+        default = 16384,
+        help = "Messages of at least this size are gzipped for sessions opened with compress=true"
+    )]
+    pub compression_threshold_bytes: usize,
+    #[env_config(
+        name = "ZO_WEBSOCKET_SEND_BUFFER_SIZE",
+        default = 64,
+        help = "Messages a websocket session buffers for a client that does not keep up"
+    )]
+    pub send_buffer_size: usize,
+    #[env_config(
+        name = "ZO_WEBSOCKET_SLOW_CONSUMER_TIMEOUT_SECS",
+        default = 30,
+        help = "Close a websocket session when its send buffer stays full for this long"
+    )]
+    pub slow_consumer_timeout_secs: u64,
 }
 
 #[derive(EnvConfig)]
@@ -413,6 +615,30 @@ pub struct ReportServer {
     pub addr: String,
     #[env_config(name = "ZO_HTTP_IPV6_ENABLED", default = false)]
     pub ipv6_enabled: bool,
+    #[env_config(
+        name = "ZO_REPORT_RENDER_CONCURRENCY",
+        default = 2,
+        help = "Maximum number of reports rendered by chromium at the same time"
+    )]
+    pub render_concurrency: usize,
+    #[env_config(
+        name = "ZO_REPORT_RENDER_QUEUE_MAX_DEPTH",
+        default = 100,
+        help = "Maximum number of report renders waiting for a free slot, renders beyond it are rejected. 0 means unlimited"
+    )]
+    pub render_queue_max_depth: usize,
+    #[env_config(
+        name = "ZO_REPORT_RENDER_TIMEOUT",
+        default = 300,
+        help = "Maximum seconds a single report render may take, chromium is killed after it"
+    )]
+    pub render_timeout: u64,
+    #[env_config(
+        name = "ZO_REPORT_SCHEDULE_JITTER",
+        default = 60,
+        help = "Maximum seconds added to the next run of cron scheduled reports so identical crons spread out"
+    )]
+    pub schedule_jitter: u64,
 }
 
 #[derive(EnvConfig)]
@@ -525,6 +751,18 @@ pub struct Auth {
     pub ext_auth_salt: String,
     #[env_config(name = "O2_SCRIPT_SERVER_TOKEN")]
     pub script_server_token: String,
+    #[env_config(
+        name = "ZO_INVITE_EXPIRY_HOURS",
+        default = 72,
+        help = "Hours an organization invite can be accepted for"
+    )]
+    pub invite_expiry_hours: i64,
+    #[env_config(
+        name = "ZO_INVITE_PURGE_INTERVAL",
+        default = 3600,
+        help = "Seconds between two purges of the expired organization invites"
+    )]
+    pub invite_purge_interval: u64,
 }
 
 #[derive(EnvConfig)]
@@ -549,6 +787,18 @@ pub struct Http {
         help = "this value must use webpki or native. it means use standard root certificates from webpki-roots or native-roots as a rustls certificate store"
     )]
     pub tls_root_certificates: String,
+    #[env_config(
+        name = "ZO_TRUSTED_PROXY_HEADER",
+        default = "X-Forwarded-For",
+        help = "header the proxies in front of openobserve put the client IP in: X-Forwarded-For, X-Real-IP or none to use the peer address"
+    )]
+    pub trusted_proxy_header: String,
+    #[env_config(
+        name = "ZO_TRUSTED_PROXY_HOPS",
+        default = 1,
+        help = "trailing X-Forwarded-For entries added by trusted proxies, the client IP is the first of them. 0 ignores X-Forwarded-For and uses the peer address"
+    )]
+    pub trusted_proxy_hops: usize,
 }
 
 #[derive(EnvConfig)]
@@ -756,6 +1006,12 @@ pub struct Common {
     pub telemetry_heartbeat: i64,
     #[env_config(name = "ZO_PROMETHEUS_ENABLED", default = true)]
     pub prometheus_enabled: bool,
+    #[env_config(
+        name = "ZO_PROMETHEUS_FILE_SIZE_STREAM_LABEL",
+        default = false,
+        help = "Label the file size histograms with the stream name, for debugging only as it multiplies the series"
+    )]
+    pub prometheus_file_size_stream_label: bool,
     #[env_config(name = "ZO_PRINT_KEY_CONFIG", default = false)]
     pub print_key_config: bool,
     #[env_config(name = "ZO_PRINT_KEY_EVENT", default = false)]
@@ -777,6 +1033,12 @@ pub struct Common {
     pub usage_reporting_url: String,
     #[env_config(name = "ZO_USAGE_REPORTING_CREDS", default = "")]
     pub usage_reporting_creds: String,
+    #[env_config(
+        name = "ZO_USAGE_REPORTING_RETRY_QUEUE_SIZE",
+        default = 100,
+        help = "maximum number of usage batches kept to retry when they can't be ingested or ZO_USAGE_REPORTING_URL can't be reached, the oldest ones are dropped when full"
+    )]
+    pub usage_reporting_retry_queue_size: usize,
     #[env_config(name = "ZO_USAGE_BATCH_SIZE", default = 2000)]
     pub usage_batch_size: usize,
     #[env_config(
@@ -786,6 +1048,12 @@ pub struct Common {
     )]
     // in seconds
     pub usage_publish_interval: i64,
+    #[env_config(
+        name = "ZO_USAGE_REPORT_BY_USER",
+        default = true,
+        help = "aggregate usage per user email, disable to report a single row for all users"
+    )]
+    pub usage_report_by_user: bool,
     #[env_config(name = "ZO_MMDB_DATA_DIR")] // ./data/openobserve/mmdb/
     pub mmdb_data_dir: String,
     #[env_config(name = "ZO_MMDB_DISABLE_DOWNLOAD", default = false)]
@@ -986,6 +1254,12 @@ pub struct Common {
         help = "Discard data of last n seconds from cached results"
     )]
     pub result_cache_discard_duration: i64,
+    #[env_config(
+        name = "ZO_RESULT_CACHE_COMPRESSION",
+        default = "none",
+        help = "Compression for result cache files on disk, also used by the metrics cache, possible values - none, zstd"
+    )]
+    pub result_cache_compression: String,
     #[env_config(
         name = "ZO_METRICS_CACHE_ENABLED",
         default = true,
@@ -1004,6 +1278,30 @@ pub struct Common {
     pub min_auto_refresh_interval: u32,
     #[env_config(name = "ZO_ADDITIONAL_REPORTING_ORGS", default = "")]
     pub additional_reporting_orgs: String,
+    #[env_config(
+        name = "ZO_SYNTHETIC_ENABLED",
+        default = false,
+        help = "Enable the root-only /api/_synthetic endpoints that generate test data"
+    )]
+    pub synthetic_enabled: bool,
+    #[env_config(
+        name = "ZO_QUERY_FUNCTION_DENIED_ROLES",
+        default = "",
+        help = "Comma separated user roles which are not allowed to run ad-hoc VRL functions (query_fn) with a search, root and admin users are always allowed. Without the enterprise RBAC this is the only restriction, so all users may run functions while it is empty"
+    )]
+    pub query_function_denied_roles: String,
+    #[env_config(
+        name = "ZO_SCHEMA_EVOLUTION_AUDIT_ENABLED",
+        default = true,
+        help = "Record the schema changes made by ingestion into the schema_evolution metadata stream of the organization"
+    )]
+    pub schema_evolution_audit_enabled: bool,
+    #[env_config(
+        name = "ZO_QUERY_RANGE_CLAMP_ENABLED",
+        default = true,
+        help = "Clamp the end of a query time range to the newest data of its streams from the stream stats, so the empty trailing range is not partitioned and searched"
+    )]
+    pub query_range_clamp_enabled: bool,
 }
 
 #[derive(EnvConfig)]
@@ -1082,6 +1380,12 @@ pub struct Limit {
     pub query_thread_num: usize,
     #[env_config(name = "ZO_QUERY_TIMEOUT", default = 600)]
     pub query_timeout: u64,
+    #[env_config(
+        name = "ZO_QUERY_MAX_TIMEOUT",
+        default = 0,
+        help = "Max timeout in seconds a search request can set for itself, 0 uses the larger of ZO_QUERY_TIMEOUT and ZO_SEARCH_JOB_TIMEOUT"
+    )]
+    pub query_max_timeout: u64,
     #[env_config(name = "ZO_QUERY_INGESTER_TIMEOUT", default = 0)]
     // default equal to query_timeout
     pub query_ingester_timeout: u64,
@@ -1163,6 +1467,12 @@ pub struct Limit {
     pub alert_schedule_concurrency: i64,
     #[env_config(name = "ZO_ALERT_SCHEDULE_TIMEOUT", default = 90)] // seconds
     pub alert_schedule_timeout: i64,
+    #[env_config(
+        name = "ZO_SCHEMA_DRIFT_ALERT_INTERVAL",
+        default = 60,
+        help = "Seconds between two evaluations of the schema drift alerts, the schema changes of a window are sent in one notification"
+    )]
+    pub schema_drift_alert_interval: u64,
     #[env_config(name = "ZO_REPORT_SCHEDULE_TIMEOUT", default = 300)] // seconds
     pub report_schedule_timeout: i64,
     #[env_config(name = "ZO_DERIVED_STREAM_SCHEDULE_INTERVAL", default = 300)] // seconds
@@ -1267,6 +1577,24 @@ pub struct Limit {
     pub distinct_values_interval: u64,
     #[env_config(name = "ZO_DISTINCT_VALUES_HOURLY", default = false)]
     pub distinct_values_hourly: bool,
+    #[env_config(
+        name = "ZO_SCHEMA_EVOLUTION_AUDIT_INTERVAL",
+        default = 10,
+        help = "Interval in seconds to flush the buffered schema evolution audit records"
+    )]
+    pub schema_evolution_audit_interval: u64,
+    #[env_config(
+        name = "ZO_QUERY_RANGE_CLAMP_MARGIN",
+        default = 0,
+        help = "Seconds kept after the newest data of the stream stats when clamping a query range, for data which is ingested but not in the stats yet. 0 uses ZO_MAX_FILE_RETENTION_TIME plus ZO_CALCULATE_STATS_INTERVAL"
+    )]
+    pub query_range_clamp_margin: u64,
+    #[env_config(
+        name = "ZO_SERVICE_LATENCY_INDEX_INTERVAL",
+        default = 60,
+        help = "interval in seconds to flush the hourly service/operation latency aggregates of traces"
+    )]
+    pub service_latency_index_interval: u64,
     #[env_config(name = "ZO_CONSISTENT_HASH_VNODES", default = 1000)]
     pub consistent_hash_vnodes: usize,
     #[env_config(
@@ -1291,6 +1619,30 @@ pub struct Limit {
     pub max_enrichment_table_size: usize,
     #[env_config(name = "ZO_SHORT_URL_RETENTION_DAYS", default = 30)] // days
     pub short_url_retention_days: i64,
+    #[env_config(
+        name = "ZO_TRASH_RETENTION_DAYS",
+        default = 30,
+        help = "days deleted dashboards and alerts are kept in the trash before they are purged"
+    )]
+    pub trash_retention_days: i64,
+    #[env_config(
+        name = "ZO_SCHEMA_NOT_FOUND_CACHE_TTL",
+        default = 30,
+        help = "seconds a lookup of a stream schema that does not exist is remembered, to avoid hitting the meta store again. 0 disables it"
+    )]
+    pub schema_not_found_cache_ttl: i64,
+    #[env_config(
+        name = "ZO_SEARCH_MAX_CONCURRENCY_PER_ORG",
+        default = 0,
+        help = "searches an organization can run at the same time on a node, 0 means no limit. Can be overridden per organization in its settings"
+    )]
+    pub search_max_concurrency_per_org: usize,
+    #[env_config(
+        name = "ZO_SEARCH_CONCURRENCY_WAIT_TIMEOUT",
+        default = 10,
+        help = "seconds a search waits for a free slot when its organization is at its concurrency limit before it is rejected, 0 rejects it immediately"
+    )]
+    pub search_concurrency_wait_timeout: u64,
     #[env_config(
         name = "ZO_INVERTED_INDEX_CACHE_MAX_ENTRIES",
         default = 100000,
@@ -1321,6 +1673,48 @@ pub struct Limit {
         help = "maximum series to display in charts"
     )]
     pub max_dashboard_series: usize,
+    #[env_config(
+        name = "ZO_SYNTHETIC_MAX_GENERATORS",
+        default = 5,
+        help = "Maximum number of synthetic data generators running in the cluster"
+    )]
+    pub synthetic_max_generators: usize,
+    #[env_config(
+        name = "ZO_SYNTHETIC_MAX_RATE",
+        default = 10000,
+        help = "Maximum records per second of a synthetic data generator"
+    )]
+    pub synthetic_max_rate: u64,
+    #[env_config(
+        name = "ZO_SYNTHETIC_MAX_DURATION",
+        default = 86400,
+        help = "unit: Second. Maximum duration of a synthetic data generator"
+    )]
+    pub synthetic_max_duration: u64,
+    #[env_config(
+        name = "ZO_VALUES_BATCH_MAX_FIELDS",
+        default = 100,
+        help = "Maximum number of fields of a batched _values request"
+    )]
+    pub values_batch_max_fields: usize,
+    #[env_config(
+        name = "ZO_VALUES_BATCH_MAX_SIZE",
+        default = 1000,
+        help = "Maximum number of values returned per field by a batched _values request"
+    )]
+    pub values_batch_max_size: usize,
+    #[env_config(
+        name = "ZO_BRANDING_LOGO_MAX_SIZE",
+        default = 256,
+        help = "Maximum size of the branding logo in KB"
+    )]
+    pub branding_logo_max_size: usize,
+    #[env_config(
+        name = "ZO_BRANDING_LOGO_MAX_DIMENSION",
+        default = 1024,
+        help = "Maximum width and height of the branding logo in pixels"
+    )]
+    pub branding_logo_max_dimension: u32,
 }
 
 #[derive(EnvConfig)]
@@ -1331,6 +1725,12 @@ pub struct Compact {
     pub interval: u64,
     #[env_config(name = "ZO_COMPACT_OLD_DATA_INTERVAL", default = 3600)] // seconds
     pub old_data_interval: u64,
+    #[env_config(
+        name = "ZO_COMPACT_ORIGINAL_DATA_INTERVAL",
+        default = 3600, // seconds
+        help = "Interval of the job dropping the _original column of the files out of the stream original_data_retention_days"
+    )]
+    pub original_data_interval: u64,
     #[env_config(name = "ZO_COMPACT_STRATEGY", default = "file_time")] // file_size, file_time
     pub strategy: String,
     #[env_config(name = "ZO_COMPACT_SYNC_TO_DB_INTERVAL", default = 600)] // seconds
@@ -1349,6 +1749,12 @@ pub struct Compact {
     pub old_data_min_records: i64,
     #[env_config(name = "ZO_COMPACT_OLD_DATA_MIN_FILES", default = 10)] // files
     pub old_data_min_files: i64,
+    #[env_config(
+        name = "ZO_COMPACT_OLD_DATA_RETENTION_CHECK",
+        default = true,
+        help = "Skip the old data merge jobs of the hours that are out of the stream data retention"
+    )]
+    pub old_data_retention_check: bool,
     #[env_config(name = "ZO_COMPACT_DELETE_FILES_DELAY_HOURS", default = 2)] // hours
     pub delete_files_delay_hours: i64,
     #[env_config(name = "ZO_COMPACT_BLOCKED_ORGS", default = "")] // use comma to split
@@ -1375,6 +1781,18 @@ pub struct Compact {
     pub job_clean_wait_time: i64,
     #[env_config(name = "ZO_COMPACT_PENDING_JOBS_METRIC_INTERVAL", default = 300)] // seconds
     pub pending_jobs_metric_interval: u64,
+    #[env_config(
+        name = "ZO_COMPACT_JOBS_METRIC_MAX_SERIES",
+        default = 100,
+        help = "Max number of organization and stream type series of the compactor jobs metrics, the streams with a smaller backlog are reported as other"
+    )]
+    pub jobs_metric_max_series: usize,
+    #[env_config(
+        name = "ZO_COMPACT_DOWNSAMPLING_JOB_CONCURRENCY",
+        default = 0,
+        help = "Max number of downsampling rules processed concurrently by a compactor, default is the number of cpu cores"
+    )]
+    pub downsampling_job_concurrency: usize,
 }
 
 #[derive(EnvConfig)]
@@ -1590,6 +2008,26 @@ pub struct S3 {
         help = "The size of the file will switch to multi-part upload in MB"
     )]
     pub multi_part_upload_size: usize,
+    #[env_config(
+        name = "ZO_S3_DATE_PREFIX_POSITION",
+        default = "",
+        help = "Write new stream files under a dt=YYYY-MM-DD/ key component so bucket lifecycle rules can target whole days, possible values - '' (disabled), 'root', 'stream'"
+    )]
+    pub date_prefix_position: String,
+    #[env_config(
+        name = "ZO_S3_LIFECYCLE_MIN_AGE_DAYS",
+        default = 0,
+        help = "Check file list entries of this age for objects archived or expired by bucket lifecycle rules, 0 to disable"
+    )]
+    pub lifecycle_min_age_days: i64,
+    #[env_config(name = "ZO_S3_LIFECYCLE_RECONCILE_INTERVAL", default = 86400)] // seconds
+    pub lifecycle_reconcile_interval: u64,
+    #[env_config(
+        name = "ZO_S3_STARTUP_CHECK_ENABLED",
+        default = true,
+        help = "Probe the object storage with a put/get/delete at startup, disable it for air-gapped cold starts"
+    )]
+    pub startup_check_enabled: bool,
 }
 
 #[derive(Debug, EnvConfig)]
@@ -1678,6 +2116,12 @@ pub struct Pipeline {
         help = "pipeline exporter client max connections"
     )]
     pub max_connections: usize,
+    #[env_config(
+        name = "ZO_PIPELINE_SLOW_STAGE_THRESHOLD",
+        default = 1000,
+        help = "log a warning when a pipeline stage takes longer than this to process a batch, unit is milliseconds, 0 to disable"
+    )]
+    pub slow_stage_threshold: u64,
 }
 
 #[derive(EnvConfig)]
@@ -1785,6 +2229,11 @@ pub fn init() -> Config {
         panic!("pipeline config error: {e}");
     }
 
+    // check report server config
+    if let Err(e) = check_report_server_config(&mut cfg) {
+        panic!("report server config error: {e}");
+    }
+
     cfg
 }
 
@@ -1812,6 +2261,22 @@ fn check_limit_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.limit.job_runtime_blocking_worker_num == 0 {
         cfg.limit.job_runtime_blocking_worker_num = 512;
     }
+    if cfg.limit.service_latency_index_interval == 0 {
+        cfg.limit.service_latency_index_interval = 60;
+    }
+    if cfg.limit.schema_evolution_audit_interval == 0 {
+        cfg.limit.schema_evolution_audit_interval = 10;
+    }
+    if cfg.limit.query_max_timeout == 0 {
+        cfg.limit.query_max_timeout = cfg
+            .limit
+            .query_timeout
+            .max(cfg.limit.search_job_timeout.max(0) as u64);
+    }
+    if cfg.limit.query_range_clamp_margin == 0 {
+        cfg.limit.query_range_clamp_margin =
+            cfg.limit.max_file_retention_time + cfg.limit.calculate_stats_interval;
+    }
     // HACK for thread_num equal to CPU core * 4
     if cfg.limit.query_thread_num == 0 {
         if cfg.common.local_mode {
@@ -1877,6 +2342,18 @@ fn check_limit_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.limit.max_dashboard_series == 0 {
         cfg.limit.max_dashboard_series = 100;
     }
+    if cfg.limit.values_batch_max_fields == 0 {
+        cfg.limit.values_batch_max_fields = 100;
+    }
+    if cfg.limit.values_batch_max_size == 0 {
+        cfg.limit.values_batch_max_size = 1000;
+    }
+    if cfg.limit.branding_logo_max_size == 0 {
+        cfg.limit.branding_logo_max_size = 256;
+    }
+    if cfg.limit.branding_logo_max_dimension == 0 {
+        cfg.limit.branding_logo_max_dimension = 1024;
+    }
 
     // check for uds
     #[allow(deprecated)]
@@ -1919,6 +2396,24 @@ fn check_common_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
         cfg.limit.metrics_cache_max_entries = 100_000;
     }
 
+    // check usage reporting
+    match cfg.common.usage_reporting_mode.as_str() {
+        "local" => {}
+        "remote" | "both" => {
+            if cfg.common.usage_reporting_url.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "ZO_USAGE_REPORTING_URL must be set when ZO_USAGE_REPORTING_MODE is '{}'",
+                    cfg.common.usage_reporting_mode
+                ));
+            }
+        }
+        mode => {
+            return Err(anyhow::anyhow!(
+                "ZO_USAGE_REPORTING_MODE must be one of 'local', 'remote' or 'both', got '{mode}'"
+            ));
+        }
+    }
+
     // check search job retention
     if cfg.limit.search_job_retention == 0 {
         return Err(anyhow::anyhow!("search job retention is set to zero"));
@@ -1999,35 +2494,26 @@ fn check_common_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
         cfg.common.bloom_filter_ndv_ratio = 100;
     }
 
+    // check result cache compression
+    cfg.common.result_cache_compression = cfg.common.result_cache_compression.to_lowercase();
+    if cfg.common.result_cache_compression.is_empty() {
+        cfg.common.result_cache_compression = "none".to_string();
+    }
+    if !["none", "zstd"].contains(&cfg.common.result_cache_compression.as_str()) {
+        return Err(anyhow::anyhow!(
+            "ZO_RESULT_CACHE_COMPRESSION must be one of none, zstd."
+        ));
+    }
+
     // check default inverted index search format
     #[allow(deprecated)]
     {
-        cfg.common.inverted_index_store_format =
-            cfg.common.inverted_index_store_format.to_lowercase();
-        if cfg.common.inverted_index_store_format.is_empty() {
-            cfg.common.inverted_index_store_format = "parquet".to_string();
-        }
-        if !["both", "parquet", "tantivy"]
-            .contains(&cfg.common.inverted_index_store_format.as_str())
-        {
-            return Err(anyhow::anyhow!(
-                "ZO_INVERTED_INDEX_STORE_FORMAT must be one of parquet, tantivy, both."
-            ));
-        }
-        cfg.common.inverted_index_search_format =
-            cfg.common.inverted_index_search_format.to_lowercase();
-        if cfg.common.inverted_index_search_format.is_empty() {
-            cfg.common.inverted_index_search_format =
-                cfg.common.inverted_index_store_format.clone();
-        }
-        if cfg.common.inverted_index_search_format == "both" {
-            cfg.common.inverted_index_search_format = "parquet".to_string();
-        }
-        if !["parquet", "tantivy"].contains(&cfg.common.inverted_index_search_format.as_str()) {
-            return Err(anyhow::anyhow!(
-                "ZO_INVERTED_INDEX_SEARCH_FORMAT must be one of parquet, tantivy."
-            ));
-        }
+        let (store_format, search_format) = check_inverted_index_formats(
+            &cfg.common.inverted_index_store_format,
+            &cfg.common.inverted_index_search_format,
+        )?;
+        cfg.common.inverted_index_store_format = store_format;
+        cfg.common.inverted_index_search_format = search_format;
     }
 
     // check for join match one
@@ -2039,6 +2525,52 @@ fn check_common_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Normalizes the inverted index store and search formats, returning them as
+/// `(store_format, search_format)`. Searching an index format that is never
+/// stored would silently find nothing, so it is corrected when only one
+/// format is stored and rejected when the wrong one is searched explicitly.
+fn check_inverted_index_formats(
+    store_format: &str,
+    search_format: &str,
+) -> Result<(String, String), anyhow::Error> {
+    let mut store_format = store_format.to_lowercase();
+    if store_format.is_empty() {
+        store_format = "parquet".to_string();
+    }
+    if !["both", "parquet", "tantivy"].contains(&store_format.as_str()) {
+        return Err(anyhow::anyhow!(
+            "ZO_INVERTED_INDEX_STORE_FORMAT must be one of parquet, tantivy, both."
+        ));
+    }
+    let mut search_format = search_format.to_lowercase();
+    if search_format.is_empty() {
+        search_format = store_format.clone();
+    }
+    if search_format == "both" {
+        search_format = "parquet".to_string();
+    }
+    if !["parquet", "tantivy"].contains(&search_format.as_str()) {
+        return Err(anyhow::anyhow!(
+            "ZO_INVERTED_INDEX_SEARCH_FORMAT must be one of parquet, tantivy."
+        ));
+    }
+    match (store_format.as_str(), search_format.as_str()) {
+        ("parquet", "tantivy") => {
+            return Err(anyhow::anyhow!(
+                "ZO_INVERTED_INDEX_SEARCH_FORMAT=tantivy requires ZO_INVERTED_INDEX_STORE_FORMAT to be tantivy or both, but it is parquet, so no tantivy index would ever be found."
+            ));
+        }
+        ("tantivy", "parquet") => {
+            log::warn!(
+                "ZO_INVERTED_INDEX_STORE_FORMAT is tantivy, no parquet index is stored to search, using ZO_INVERTED_INDEX_SEARCH_FORMAT=tantivy"
+            );
+            search_format = "tantivy".to_string();
+        }
+        _ => {}
+    }
+    Ok((store_format, search_format))
+}
+
 fn check_grpc_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.grpc.tls_enabled
         && (cfg.grpc.tls_cert_domain.is_empty()
@@ -2059,6 +2591,15 @@ fn check_http_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
              and ZO_HTTP_TLS_KEY_PATH must be set."
         ));
     }
+    cfg.http.trusted_proxy_header = cfg.http.trusted_proxy_header.trim().to_lowercase();
+    if cfg.http.trusted_proxy_header.is_empty() {
+        cfg.http.trusted_proxy_header = "none".to_string();
+    }
+    if !["x-forwarded-for", "x-real-ip", "none"].contains(&cfg.http.trusted_proxy_header.as_str()) {
+        return Err(anyhow::anyhow!(
+            "ZO_TRUSTED_PROXY_HEADER must be one of X-Forwarded-For, X-Real-IP or none."
+        ));
+    }
     Ok(())
 }
 
@@ -2377,6 +2918,9 @@ fn check_compact_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.compact.old_data_interval < 1 {
         cfg.compact.old_data_interval = 3600;
     }
+    if cfg.compact.original_data_interval < 1 {
+        cfg.compact.original_data_interval = 3600;
+    }
     if cfg.compact.old_data_max_days < 1 {
         cfg.compact.old_data_max_days = 7;
     }
@@ -2396,6 +2940,9 @@ fn check_compact_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     if cfg.compact.pending_jobs_metric_interval == 0 {
         cfg.compact.pending_jobs_metric_interval = 300;
     }
+    if cfg.compact.downsampling_job_concurrency == 0 {
+        cfg.compact.downsampling_job_concurrency = cfg.limit.cpu_num;
+    }
 
     Ok(())
 }
@@ -2457,6 +3004,16 @@ fn check_s3_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
         cfg.s3.keepalive_timeout = 20;
     }
 
+    cfg.s3.date_prefix_position = cfg.s3.date_prefix_position.to_lowercase();
+    if !["", "root", "stream"].contains(&cfg.s3.date_prefix_position.as_str()) {
+        return Err(anyhow::anyhow!(
+            "ZO_S3_DATE_PREFIX_POSITION must be one of '', 'root' or 'stream'"
+        ));
+    }
+    if cfg.s3.lifecycle_reconcile_interval == 0 {
+        cfg.s3.lifecycle_reconcile_interval = 86400;
+    }
+
     Ok(())
 }
 
@@ -2500,6 +3057,16 @@ fn check_health_check_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn check_report_server_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
+    if cfg.report_server.render_concurrency == 0 {
+        cfg.report_server.render_concurrency = 2;
+    }
+    if cfg.report_server.render_timeout == 0 {
+        cfg.report_server.render_timeout = 300;
+    }
+    Ok(())
+}
+
 #[inline]
 pub fn is_local_disk_storage() -> bool {
     get_config().common.is_local_storage
@@ -2547,6 +3114,101 @@ fn check_encryption_config(cfg: &mut Config) -> Result<(), anyhow::Error> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_inverted_index_formats() {
+        let cases = [
+            ("parquet", "parquet", Some(("parquet", "parquet"))),
+            ("parquet", "tantivy", None),
+            ("parquet", "both", Some(("parquet", "parquet"))),
+            ("tantivy", "parquet", Some(("tantivy", "tantivy"))),
+            ("tantivy", "tantivy", Some(("tantivy", "tantivy"))),
+            ("tantivy", "both", Some(("tantivy", "tantivy"))),
+            ("both", "parquet", Some(("both", "parquet"))),
+            ("both", "tantivy", Some(("both", "tantivy"))),
+            ("both", "both", Some(("both", "parquet"))),
+        ];
+        for (store, search, expected) in cases {
+            let ret = check_inverted_index_formats(store, search);
+            match expected {
+                Some((store_format, search_format)) => assert_eq!(
+                    ret.unwrap(),
+                    (store_format.to_string(), search_format.to_string()),
+                    "store: {store}, search: {search}"
+                ),
+                None => assert!(
+                    ret.unwrap_err().to_string().contains("tantivy or both"),
+                    "store: {store}, search: {search}"
+                ),
+            }
+        }
+
+        // empty and mixed case values are normalized first
+        assert_eq!(
+            check_inverted_index_formats("", "").unwrap(),
+            ("parquet".to_string(), "parquet".to_string())
+        );
+        assert_eq!(
+            check_inverted_index_formats("Both", "").unwrap(),
+            ("both".to_string(), "parquet".to_string())
+        );
+        assert!(check_inverted_index_formats("PARQUET", "Tantivy").is_err());
+        assert!(check_inverted_index_formats("lucene", "parquet").is_err());
+        assert!(check_inverted_index_formats("parquet", "lucene").is_err());
+    }
+
+    #[test]
+    fn test_changed_env_names() {
+        let old = BTreeMap::from([
+            ("ZO_A".to_string(), "1".to_string()),
+            ("ZO_B".to_string(), "1".to_string()),
+        ]);
+        let new = BTreeMap::from([
+            ("ZO_B".to_string(), "2".to_string()),
+            ("ZO_C".to_string(), "1".to_string()),
+        ]);
+        assert_eq!(
+            changed_env_names(&old, &new),
+            vec!["ZO_A".to_string(), "ZO_B".to_string(), "ZO_C".to_string()]
+        );
+        assert!(changed_env_names(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_reload_config() {
+        let old = Config::init().unwrap();
+        let mut cfg = Config::init().unwrap();
+        cfg.common.result_cache_enabled = !old.common.result_cache_enabled;
+        cfg.http.port = old.http.port + 1;
+        let loaded = BTreeMap::from([
+            ("ZO_HTTP_PORT".to_string(), old.http.port.to_string()),
+            (
+                "ZO_RESULT_CACHE_ENABLED".to_string(),
+                old.common.result_cache_enabled.to_string(),
+            ),
+        ]);
+        let env = BTreeMap::from([
+            ("ZO_HTTP_PORT".to_string(), cfg.http.port.to_string()),
+            (
+                "ZO_RESULT_CACHE_ENABLED".to_string(),
+                cfg.common.result_cache_enabled.to_string(),
+            ),
+        ]);
+
+        let (reload, next_env) = apply_reload(&mut cfg, &old, &loaded, env.clone());
+        assert_eq!(reload.changed, vec!["ZO_RESULT_CACHE_ENABLED".to_string()]);
+        assert_eq!(reload.requires_restart, vec!["ZO_HTTP_PORT".to_string()]);
+        assert_eq!(
+            cfg.common.result_cache_enabled,
+            !old.common.result_cache_enabled
+        );
+        assert_eq!(cfg.http.port, old.http.port);
+
+        // still reported until the process restarts
+        let (reload, _) = apply_reload(&mut cfg, &old, &next_env, env);
+        assert!(reload.changed.is_empty());
+        assert_eq!(reload.requires_restart, vec!["ZO_HTTP_PORT".to_string()]);
+    }
+
     #[test]
     fn test_get_config() {
         let mut cfg = Config::init().unwrap();
@@ -2612,6 +3274,15 @@ mod tests {
         assert_eq!(cfg.compact.data_retention_days, 10);
         assert_eq!(cfg.limit.req_cols_per_record_limit, 1000);
 
+        cfg.common.usage_reporting_mode = "remote".to_string();
+        cfg.common.usage_reporting_url = "".to_string();
+        assert!(check_common_config(&mut cfg).is_err());
+        cfg.common.usage_reporting_url = "http://localhost:5080/api/_meta/usage/_json".to_string();
+        assert!(check_common_config(&mut cfg).is_ok());
+        cfg.common.usage_reporting_mode = "cloud".to_string();
+        assert!(check_common_config(&mut cfg).is_err());
+        cfg.common.usage_reporting_mode = "local".to_string();
+
         cfg.compact.data_retention_days = 2;
         let ret = check_compact_config(&mut cfg);
         assert!(ret.is_err());
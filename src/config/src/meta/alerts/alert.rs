@@ -15,7 +15,7 @@
 
 use chrono::{DateTime, FixedOffset};
 use hashbrown::HashMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use svix_ksuid::Ksuid;
 use utoipa::ToSchema;
 
@@ -46,6 +46,7 @@ pub struct Alert {
     pub query_condition: QueryCondition,
     #[serde(default)]
     pub trigger_condition: TriggerCondition,
+    #[serde(alias = "destination", deserialize_with = "deserialize_destinations")]
     pub destinations: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_attributes: Option<HashMap<String, String>>,
@@ -72,6 +73,13 @@ pub struct Alert {
     pub updated_at: Option<DateTime<FixedOffset>>,
     #[serde(default)]
     pub last_edited_by: Option<String>,
+    /// Unix timestamp in microseconds until which notifications are suppressed
+    #[serde(default)]
+    pub silence_until: Option<i64>,
+    /// Writes every state change of the alert into the `alert_events` stream
+    /// of the organization
+    #[serde(default)]
+    pub emit_state_events: bool,
 }
 
 impl PartialEq for Alert {
@@ -104,6 +112,8 @@ impl Default for Alert {
             updated_at: None,
             last_edited_by: None,
             last_satisfied_at: None,
+            silence_until: None,
+            emit_state_events: false,
         }
     }
 }
@@ -159,6 +169,12 @@ impl Alert {
         }
     }
 
+    /// Returns true if notifications for the alert are suppressed at the given
+    /// Unix timestamp in microseconds.
+    pub fn is_silenced_at(&self, now: i64) -> bool {
+        self.silence_until.is_some_and(|until| now < until)
+    }
+
     /// Not to be used for new alerts.
     pub fn get_last_triggered_at_from_table(&self) -> Option<i64> {
         self.last_triggered_at
@@ -254,3 +270,57 @@ impl ListAlertsParams {
         self
     }
 }
+
+/// Deserializes alert destinations from either a list of destination names or,
+/// for backwards compatibility, a single destination name.
+pub fn deserialize_destinations<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(dest) if dest.is_empty() => vec![],
+        OneOrMany::One(dest) => vec![dest],
+        OneOrMany::Many(dests) => dests,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_destinations() {
+        let alert: Alert =
+            json::from_str(r#"{"name":"a","destinations":["slack","email"]}"#).unwrap();
+        assert_eq!(alert.destinations, vec!["slack", "email"]);
+
+        let alert: Alert = json::from_str(r#"{"name":"a","destinations":"slack"}"#).unwrap();
+        assert_eq!(alert.destinations, vec!["slack"]);
+
+        let alert: Alert = json::from_str(r#"{"name":"a","destination":"slack"}"#).unwrap();
+        assert_eq!(alert.destinations, vec!["slack"]);
+
+        let alert: Alert = json::from_str(r#"{"name":"a","destinations":""}"#).unwrap();
+        assert!(alert.destinations.is_empty());
+
+        assert!(json::from_str::<Alert>(r#"{"name":"a","destinations":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_is_silenced_at() {
+        let mut alert = Alert::default();
+        assert!(!alert.is_silenced_at(100));
+
+        alert.silence_until = Some(200);
+        assert!(alert.is_silenced_at(100));
+        assert!(!alert.is_silenced_at(200));
+        assert!(!alert.is_silenced_at(300));
+    }
+}
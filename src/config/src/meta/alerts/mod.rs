@@ -16,9 +16,13 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::{meta::search::SearchEventType, utils::json::Value};
+use crate::{
+    meta::search::SearchEventType,
+    utils::json::{Map, Value},
+};
 
 pub mod alert;
+pub mod state;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
 pub struct TriggerCondition {
@@ -28,7 +32,7 @@ pub struct TriggerCondition {
     pub operator: Operator, // >=
     #[serde(default)]
     pub threshold: i64, // 3 times
-    /// (seconds)
+    /// (seconds) for alerts, (minutes) for derived streams
     #[serde(default)]
     pub frequency: i64, // 1 minute
     #[serde(default)]
@@ -43,6 +47,102 @@ pub struct TriggerCondition {
     /// (seconds)
     #[serde(default)]
     pub tolerance_in_secs: Option<i64>,
+    /// Compares the query result against an earlier window instead of
+    /// `operator` and `threshold`, ignored by derived streams like those
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineCondition>,
+}
+
+/// Fires when the aggregate of the current window compares to `multiplier`
+/// times the aggregate of the same window `offset` ago, e.g. `>` 2.0 with an
+/// offset of `1h` fires when the value more than doubled since one hour ago.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct BaselineCondition {
+    /// How far back the baseline window is: `30m`, `1h`, `1d`, `1w`
+    pub offset: String,
+    #[serde(default = "default_baseline_operator")]
+    pub operator: Operator,
+    pub multiplier: f64,
+    /// The aggregated column, `alert_agg_value` for custom queries with an
+    /// aggregation, whose `having` condition still filters both windows.
+    /// The values of all the returned rows are summed.
+    pub column: String,
+}
+
+fn default_baseline_operator() -> Operator {
+    Operator::GreaterThan
+}
+
+/// The result of comparing the current window against its baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BaselineOutcome {
+    Fire {
+        current: f64,
+        baseline: f64,
+    },
+    NotFire {
+        current: f64,
+        baseline: f64,
+    },
+    /// The baseline window has no data, it never fires
+    NoBaseline,
+}
+
+impl BaselineCondition {
+    /// Returns the offset in microseconds, `None` if it is not a positive
+    /// number followed by one of the `m`, `h`, `d`, `w` units.
+    pub fn offset_micros(&self) -> Option<i64> {
+        let offset = self.offset.trim();
+        let unit = offset.chars().last()?;
+        let value = offset[..offset.len() - unit.len_utf8()]
+            .parse::<i64>()
+            .ok()?;
+        if value <= 0 {
+            return None;
+        }
+        let minutes = match unit {
+            'm' => value,
+            'h' => value * 60,
+            'd' => value * 60 * 24,
+            'w' => value * 60 * 24 * 7,
+            _ => return None,
+        };
+        minutes.checked_mul(60_000_000)
+    }
+
+    /// Reduces the rows of a window to the compared value, `None` when the
+    /// window has no data.
+    pub fn aggregate(&self, rows: &[Map<String, Value>]) -> Option<f64> {
+        let values = rows
+            .iter()
+            .filter_map(|row| match row.get(&self.column)? {
+                Value::Number(v) => v.as_f64(),
+                Value::String(v) => v.parse::<f64>().ok(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        (!values.is_empty()).then(|| values.iter().sum())
+    }
+
+    pub fn evaluate(&self, current: f64, baseline: Option<f64>) -> BaselineOutcome {
+        let Some(baseline) = baseline else {
+            return BaselineOutcome::NoBaseline;
+        };
+        let expected = baseline * self.multiplier;
+        let fire = match self.operator {
+            Operator::GreaterThan => current > expected,
+            Operator::GreaterThanEquals => current >= expected,
+            Operator::LessThan => current < expected,
+            Operator::LessThanEquals => current <= expected,
+            _ => false,
+        };
+        if fire {
+            BaselineOutcome::Fire { current, baseline }
+        } else {
+            BaselineOutcome::NotFire { current, baseline }
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
@@ -158,6 +258,10 @@ pub enum QueryType {
     SQL,
     #[serde(rename = "promql")]
     PromQL,
+    /// Notifies when fields are added to or change type in the watched
+    /// streams. The stream name of the alert can be a `*` pattern.
+    #[serde(rename = "schema_drift")]
+    SchemaDrift,
 }
 
 impl std::fmt::Display for QueryType {
@@ -166,6 +270,7 @@ impl std::fmt::Display for QueryType {
             QueryType::Custom => write!(f, "custom"),
             QueryType::SQL => write!(f, "sql"),
             QueryType::PromQL => write!(f, "promql"),
+            QueryType::SchemaDrift => write!(f, "schema_drift"),
         }
     }
 }
@@ -176,6 +281,7 @@ impl From<&str> for QueryType {
             "custom" => QueryType::Custom,
             "sql" => QueryType::SQL,
             "promql" => QueryType::PromQL,
+            "schema_drift" => QueryType::SchemaDrift,
             _ => QueryType::Custom,
         }
     }
@@ -229,3 +335,97 @@ impl std::fmt::Display for Operator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::json;
+
+    fn baseline(operator: Operator, multiplier: f64) -> BaselineCondition {
+        BaselineCondition {
+            offset: "1h".to_string(),
+            operator,
+            multiplier,
+            column: "alert_agg_value".to_string(),
+        }
+    }
+
+    fn rows(values: &[f64]) -> Vec<Map<String, Value>> {
+        values
+            .iter()
+            .map(|v| {
+                json::json!({ "alert_agg_value": v })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_baseline_rise() {
+        let cond = baseline(Operator::GreaterThan, 2.0);
+        let current = cond.aggregate(&rows(&[150.0, 60.0])).unwrap();
+        let previous = cond.aggregate(&rows(&[100.0]));
+        assert_eq!(
+            cond.evaluate(current, previous),
+            BaselineOutcome::Fire {
+                current: 210.0,
+                baseline: 100.0
+            }
+        );
+        assert!(matches!(
+            cond.evaluate(200.0, previous),
+            BaselineOutcome::NotFire { .. }
+        ));
+    }
+
+    #[test]
+    fn test_baseline_fall() {
+        let cond = baseline(Operator::LessThan, 0.5);
+        let previous = cond.aggregate(&rows(&[100.0]));
+        assert!(matches!(
+            cond.evaluate(40.0, previous),
+            BaselineOutcome::Fire { .. }
+        ));
+        assert!(matches!(
+            cond.evaluate(50.0, previous),
+            BaselineOutcome::NotFire { .. }
+        ));
+        // a rise never fires a fall condition
+        assert!(matches!(
+            cond.evaluate(500.0, previous),
+            BaselineOutcome::NotFire { .. }
+        ));
+    }
+
+    #[test]
+    fn test_baseline_missing() {
+        let cond = baseline(Operator::GreaterThan, 2.0);
+        assert_eq!(cond.aggregate(&[]), None);
+        // rows without the column have no baseline either
+        let row = json::json!({ "other": 1 }).as_object().unwrap().clone();
+        assert_eq!(cond.aggregate(&[row]), None);
+        assert_eq!(cond.evaluate(1000.0, None), BaselineOutcome::NoBaseline);
+        // a baseline of 0 is data, the comparison still applies
+        let previous = cond.aggregate(&rows(&[0.0]));
+        assert!(matches!(
+            cond.evaluate(1.0, previous),
+            BaselineOutcome::Fire { .. }
+        ));
+    }
+
+    #[test]
+    fn test_baseline_offset_micros() {
+        let mut cond = baseline(Operator::GreaterThan, 2.0);
+        assert_eq!(cond.offset_micros(), Some(3_600_000_000));
+        cond.offset = "30m".to_string();
+        assert_eq!(cond.offset_micros(), Some(1_800_000_000));
+        cond.offset = "1w".to_string();
+        assert_eq!(cond.offset_micros(), Some(7 * 24 * 3_600_000_000));
+        for offset in ["", "h", "0h", "-1h", "1x", "1.5h"] {
+            cond.offset = offset.to_string();
+            assert_eq!(cond.offset_micros(), None, "{offset}");
+        }
+    }
+}
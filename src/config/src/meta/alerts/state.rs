@@ -0,0 +1,343 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State machine of scheduled alerts.
+//!
+//! The scheduler keeps the state of every alert in the data of its trigger.
+//! An evaluation that satisfies the condition makes the alert `Pending` until
+//! its notification is delivered, then `Firing`. The first evaluation that no
+//! longer satisfies the condition resolves a pending or firing alert.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    meta::{alerts::alert::Alert, stream::StreamType},
+    utils::json::{self, Map, Value},
+};
+
+/// Maximum length of the observed value recorded in a state event
+const OBSERVED_VALUE_MAX_LEN: usize = 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    /// The condition was never satisfied since the trigger was created
+    #[default]
+    Inactive,
+    /// The condition is satisfied, the notification is not delivered yet
+    Pending,
+    /// The condition is satisfied and notified, or its notification is
+    /// suppressed by a silence
+    Firing,
+    /// The condition is no longer satisfied after being pending or firing
+    Resolved,
+}
+
+impl AlertState {
+    pub fn is_inactive(&self) -> bool {
+        *self == AlertState::Inactive
+    }
+
+    /// State after an evaluation, before any notification is sent.
+    pub fn evaluated(self, satisfied: bool) -> Self {
+        match (self, satisfied) {
+            (AlertState::Pending | AlertState::Firing, true) => self,
+            (_, true) => AlertState::Pending,
+            (AlertState::Pending | AlertState::Firing, false) => AlertState::Resolved,
+            (_, false) => self,
+        }
+    }
+
+    /// State after the notification of a satisfied evaluation, `delivered` is
+    /// false when it could not be sent to any destination.
+    pub fn notified(self, delivered: bool) -> Self {
+        match self {
+            AlertState::Pending if delivered => AlertState::Firing,
+            _ => self,
+        }
+    }
+}
+
+impl std::fmt::Display for AlertState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertState::Inactive => write!(f, "inactive"),
+            AlertState::Pending => write!(f, "pending"),
+            AlertState::Firing => write!(f, "firing"),
+            AlertState::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationOutcome {
+    /// No notification was sent for the transition
+    #[default]
+    None,
+    Sent,
+    /// Sent to some of the destinations only
+    PartiallySent,
+    /// Not sent because the alert is silenced
+    Suppressed,
+}
+
+/// A change of the state of an alert, written as one record of the
+/// `alert_events` stream of the organization.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertStateEvent {
+    /// Identifies the transition, records with the same id are the same event
+    pub event_id: String,
+    pub alert_id: String,
+    pub alert_name: String,
+    pub stream_type: StreamType,
+    pub stream_name: String,
+    pub old_state: AlertState,
+    pub new_state: AlertState,
+    /// Unix timestamp in microseconds of the evaluation that changed the state
+    pub evaluated_at: i64,
+    /// Number of rows that satisfied the condition
+    pub matched_rows: usize,
+    /// First row that satisfied the condition as JSON, truncated
+    pub observed_value: String,
+    pub notification: NotificationOutcome,
+    pub notification_message: String,
+}
+
+impl AlertStateEvent {
+    pub fn new(
+        alert: &Alert,
+        old_state: AlertState,
+        new_state: AlertState,
+        evaluated_at: i64,
+    ) -> Self {
+        let alert_id = alert
+            .id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| alert.get_unique_key());
+        Self {
+            event_id: format!("{alert_id}/{evaluated_at}/{new_state}"),
+            alert_id,
+            alert_name: alert.name.clone(),
+            stream_type: alert.stream_type,
+            stream_name: alert.stream_name.clone(),
+            old_state,
+            new_state,
+            evaluated_at,
+            ..Default::default()
+        }
+    }
+
+    /// Records the rows that satisfied the condition.
+    pub fn set_observed(&mut self, rows: &[Map<String, Value>]) {
+        self.matched_rows = rows.len();
+        self.observed_value = rows
+            .first()
+            .map(|row| {
+                let value = json::to_string(row).unwrap_or_default();
+                match value.char_indices().nth(OBSERVED_VALUE_MAX_LEN) {
+                    Some((end, _)) => value[..end].to_string(),
+                    None => value,
+                }
+            })
+            .unwrap_or_default();
+    }
+
+    /// Records the outcome of the notification sent for the transition.
+    pub fn set_notification(&mut self, outcome: NotificationOutcome, message: &str) {
+        self.notification = outcome;
+        self.notification_message = message.to_string();
+    }
+
+    /// Returns the record to ingest, timestamped at `now` so that events
+    /// emitted late are not rejected as too old.
+    pub fn to_record(&self, now: i64) -> Value {
+        let mut record = json::to_value(self).unwrap_or_default();
+        if let Value::Object(map) = &mut record {
+            map.insert(crate::TIMESTAMP_COL_NAME.to_string(), now.into());
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::triggers::ScheduledTriggerData;
+
+    fn alert(emit_state_events: bool) -> Alert {
+        let mut alert = Alert::default();
+        alert.id = Some(svix_ksuid::Ksuid::new(None, None));
+        alert.name = "errors".to_string();
+        alert.stream_type = StreamType::Logs;
+        alert.stream_name = "app".to_string();
+        alert.emit_state_events = emit_state_events;
+        alert
+    }
+
+    fn rows(n: usize) -> Vec<Map<String, Value>> {
+        (0..n)
+            .map(|i| {
+                let mut row = Map::new();
+                row.insert("count".to_string(), (i * 10).into());
+                row
+            })
+            .collect()
+    }
+
+    /// Runs one evaluation the way the scheduler does, returning the trigger
+    /// data as persisted before the notification and after it.
+    fn evaluate(
+        data: &mut ScheduledTriggerData,
+        alert: &Alert,
+        now: i64,
+        satisfied: bool,
+        delivered: bool,
+    ) -> (String, String) {
+        let state = data.alert_state.evaluated(satisfied);
+        if let Some(event) = data.transition_alert(alert, state, now) {
+            event.set_observed(&rows(if satisfied { 2 } else { 0 }));
+        }
+        let before_notify = json::to_string(data).unwrap();
+        if satisfied {
+            let state = data.alert_state.notified(delivered);
+            if let Some(event) = data.transition_alert(alert, state, now) {
+                event.set_notification(NotificationOutcome::Sent, "ok");
+            }
+        }
+        (before_notify, json::to_string(data).unwrap())
+    }
+
+    #[test]
+    fn test_alert_state_transitions() {
+        use AlertState::*;
+
+        assert_eq!(Inactive.evaluated(false), Inactive);
+        assert_eq!(Inactive.evaluated(true), Pending);
+        assert_eq!(Pending.evaluated(true), Pending);
+        assert_eq!(Pending.evaluated(false), Resolved);
+        assert_eq!(Firing.evaluated(true), Firing);
+        assert_eq!(Firing.evaluated(false), Resolved);
+        assert_eq!(Resolved.evaluated(false), Resolved);
+        assert_eq!(Resolved.evaluated(true), Pending);
+
+        assert_eq!(Pending.notified(true), Firing);
+        assert_eq!(Pending.notified(false), Pending);
+        assert_eq!(Firing.notified(true), Firing);
+        assert_eq!(Firing.notified(false), Firing);
+
+        let alert = alert(true);
+        let mut data = ScheduledTriggerData::default();
+        evaluate(&mut data, &alert, 1, false, true);
+        assert!(data.pending_state_events.is_empty());
+        evaluate(&mut data, &alert, 2, true, false);
+        evaluate(&mut data, &alert, 3, true, true);
+        evaluate(&mut data, &alert, 4, true, true);
+        evaluate(&mut data, &alert, 5, false, true);
+        evaluate(&mut data, &alert, 6, false, true);
+        let transitions = data
+            .pending_state_events
+            .iter()
+            .map(|e| (e.old_state, e.new_state, e.evaluated_at))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions,
+            vec![
+                (Inactive, Pending, 2),
+                (Pending, Firing, 3),
+                (Firing, Resolved, 5)
+            ]
+        );
+        assert_eq!(data.alert_state, Resolved);
+
+        // the state is tracked even when the alert does not emit events
+        let alert = self::alert(false);
+        let mut data = ScheduledTriggerData::default();
+        evaluate(&mut data, &alert, 1, true, true);
+        assert_eq!(data.alert_state, Firing);
+        assert!(data.pending_state_events.is_empty());
+    }
+
+    #[test]
+    fn test_alert_state_restart_recovery() {
+        let alert = alert(true);
+        let mut data = ScheduledTriggerData::default();
+
+        // the scheduler stopped after persisting the pending state, before
+        // notifying
+        let (persisted, _) = evaluate(&mut data, &alert, 1, true, true);
+        let mut data: ScheduledTriggerData = json::from_str(&persisted).unwrap();
+        assert_eq!(data.alert_state, AlertState::Pending);
+        assert_eq!(data.pending_state_events.len(), 1);
+
+        // the restarted scheduler emits the persisted event, and the next
+        // evaluation does not queue it again
+        data.pending_state_events.clear();
+        evaluate(&mut data, &alert, 2, true, true);
+        let transitions = data
+            .pending_state_events
+            .iter()
+            .map(|e| (e.old_state, e.new_state))
+            .collect::<Vec<_>>();
+        assert_eq!(transitions, vec![(AlertState::Pending, AlertState::Firing)]);
+
+        // the scheduler stopped after persisting the transition, before
+        // emitting it
+        let (_, persisted) = evaluate(&mut data, &alert, 3, false, true);
+        let mut data: ScheduledTriggerData = json::from_str(&persisted).unwrap();
+        assert_eq!(data.alert_state, AlertState::Resolved);
+        assert_eq!(data.pending_state_events.len(), 2);
+        data.pending_state_events.clear();
+        evaluate(&mut data, &alert, 4, false, true);
+        assert!(data.pending_state_events.is_empty());
+
+        // the trigger data of older versions starts inactive
+        let data: ScheduledTriggerData = json::from_str(r#"{"tolerance":0}"#).unwrap();
+        assert_eq!(data.alert_state, AlertState::Inactive);
+        assert!(data.pending_state_events.is_empty());
+        let data = json::to_string(&ScheduledTriggerData::default()).unwrap();
+        assert!(!data.contains("alert_state"));
+    }
+
+    #[test]
+    fn test_alert_state_event_payload() {
+        let alert = alert(true);
+        let mut event = AlertStateEvent::new(&alert, AlertState::Pending, AlertState::Firing, 100);
+        event.set_observed(&rows(3));
+        event.set_notification(NotificationOutcome::PartiallySent, "1 of 2 failed");
+
+        let record = event.to_record(200);
+        let alert_id = alert.id.unwrap().to_string();
+        assert_eq!(record["_timestamp"], 200);
+        assert_eq!(record["event_id"], format!("{alert_id}/100/firing"));
+        assert_eq!(record["alert_id"], alert_id);
+        assert_eq!(record["alert_name"], "errors");
+        assert_eq!(record["stream_type"], "logs");
+        assert_eq!(record["stream_name"], "app");
+        assert_eq!(record["old_state"], "pending");
+        assert_eq!(record["new_state"], "firing");
+        assert_eq!(record["evaluated_at"], 100);
+        assert_eq!(record["matched_rows"], 3);
+        assert_eq!(record["observed_value"], r#"{"count":0}"#);
+        assert_eq!(record["notification"], "partially_sent");
+        assert_eq!(record["notification_message"], "1 of 2 failed");
+
+        // the observed value is truncated
+        let mut row = Map::new();
+        row.insert("msg".to_string(), "é".repeat(2000).into());
+        event.set_observed(&[row]);
+        assert_eq!(event.observed_value.chars().count(), OBSERVED_VALUE_MAX_LEN);
+    }
+}
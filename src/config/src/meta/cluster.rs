@@ -266,4 +266,6 @@ pub fn get_internal_grpc_token() -> String {
 pub enum CompactionJobType {
     Current,
     Historical,
+    /// Drops the `_original` column of the files out of its retention
+    OriginalData,
 }
@@ -37,6 +37,7 @@ pub struct Dashboard {
     pub v3: Option<v3::Dashboard>,
     pub v4: Option<v4::Dashboard>,
     pub v5: Option<v5::Dashboard>,
+    pub v6: Option<v6::Dashboard>,
     pub version: i32,
     pub hash: String,
     #[serde(default)]
@@ -51,6 +52,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.dashboard_id.as_str()),
             4 => self.v4.as_ref().map(|inner| inner.dashboard_id.as_str()),
             5 => self.v5.as_ref().map(|inner| inner.dashboard_id.as_str()),
+            6 => self.v6.as_ref().map(|inner| inner.dashboard_id.as_str()),
             _ => None,
         }
     }
@@ -82,6 +84,11 @@ impl Dashboard {
                 v5: Some(inner),
                 ..
             } => inner.dashboard_id = dashboard_id,
+            Self {
+                version: 6,
+                v6: Some(inner),
+                ..
+            } => inner.dashboard_id = dashboard_id,
             _ => {}
         };
     }
@@ -97,6 +104,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.owner.as_str()),
             4 => self.v4.as_ref().map(|inner| inner.owner.as_str()),
             5 => self.v5.as_ref().map(|inner| inner.owner.as_str()),
+            6 => self.v6.as_ref().map(|inner| inner.owner.as_str()),
             _ => None,
         }
     }
@@ -108,6 +116,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.title.as_str()),
             4 => self.v4.as_ref().map(|inner| inner.title.as_str()),
             5 => self.v5.as_ref().map(|inner| inner.title.as_str()),
+            6 => self.v6.as_ref().map(|inner| inner.title.as_str()),
             _ => None,
         }
     }
@@ -139,6 +148,11 @@ impl Dashboard {
                 v5: Some(inner),
                 ..
             } => inner.title = title,
+            Self {
+                version: 6,
+                v6: Some(inner),
+                ..
+            } => inner.title = title,
             _ => {}
         };
     }
@@ -150,6 +164,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.description.as_str()),
             4 => self.v4.as_ref().map(|inner| inner.description.as_str()),
             5 => self.v5.as_ref().map(|inner| inner.description.as_str()),
+            6 => self.v6.as_ref().map(|inner| inner.description.as_str()),
             _ => None,
         }
     }
@@ -161,6 +176,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.role.as_str()),
             4 => self.v4.as_ref().map(|inner| inner.role.as_str()),
             5 => self.v5.as_ref().map(|inner| inner.role.as_str()),
+            6 => self.v6.as_ref().map(|inner| inner.role.as_str()),
             _ => None,
         }
     }
@@ -168,7 +184,7 @@ impl Dashboard {
     /// Returns the timestamp with timezone of the time at which the dashboard
     /// was created.
     ///
-    /// This value is stored in JSON for versions 1-5 of the dashboard. However
+    /// This value is stored in JSON for versions 1-6 of the dashboard. However
     /// future versions of the dashboard should utilize the `created_at` Unix
     /// timestamp field in the database to represent the creation timestamp.
     pub fn created_at_deprecated(&self) -> Option<DateTime<FixedOffset>> {
@@ -178,6 +194,7 @@ impl Dashboard {
             3 => self.v3.as_ref().map(|inner| inner.created),
             4 => self.v4.as_ref().map(|inner| inner.created),
             5 => self.v5.as_ref().map(|inner| inner.created),
+            6 => self.v6.as_ref().map(|inner| inner.created),
             _ => None,
         }
     }
@@ -189,6 +206,7 @@ pub mod v2;
 pub mod v3;
 pub mod v4;
 pub mod v5;
+pub mod v6;
 
 pub fn datetime_now() -> DateTime<FixedOffset> {
     Utc::now().with_timezone(&FixedOffset::east_opt(0).expect(
@@ -211,6 +229,23 @@ pub struct ListDashboardsParams {
 
     /// The optional page size and page index of results to retrieve.
     pub page_size_and_idx: Option<(u64, u64)>,
+
+    /// The order in which to list dashboards.
+    pub sort_by: ListDashboardsSortBy,
+}
+
+/// The order in which to list dashboards.
+///
+/// Dashboards that are equal in the sort order are listed by folder name and
+/// then by the order they were created in, so that pages never overlap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListDashboardsSortBy {
+    #[default]
+    TitleAsc,
+    TitleDesc,
+    UpdatedAtAsc,
+    UpdatedAtDesc,
 }
 
 impl ListDashboardsParams {
@@ -222,6 +257,7 @@ impl ListDashboardsParams {
             folder_id: None,
             title_pat: None,
             page_size_and_idx: None,
+            sort_by: ListDashboardsSortBy::default(),
         }
     }
 
@@ -245,4 +281,10 @@ impl ListDashboardsParams {
         self.page_size_and_idx = Some((page_size, page_idx));
         self
     }
+
+    /// Sort the results in the given order.
+    pub fn sort_by(mut self, sort_by: ListDashboardsSortBy) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
 }
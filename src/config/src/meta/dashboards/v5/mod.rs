@@ -63,6 +63,7 @@ impl From<Dashboard> for super::Dashboard {
             v3: None,
             v4: None,
             v5: Some(value),
+            v6: None,
             version,
             hash,
             updated_at,
@@ -70,6 +71,26 @@ impl From<Dashboard> for super::Dashboard {
     }
 }
 
+/// Downgrades a v6 dashboard, which has the same content, for the exports
+/// read by the versions without v6.
+impl From<super::v6::Dashboard> for Dashboard {
+    fn from(value: super::v6::Dashboard) -> Self {
+        Self {
+            version: 5,
+            dashboard_id: value.dashboard_id,
+            title: value.title,
+            description: value.description,
+            role: value.role,
+            owner: value.owner,
+            created: value.created,
+            tabs: value.tabs,
+            variables: value.variables,
+            default_datetime_duration: value.default_datetime_duration,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Layout {
@@ -0,0 +1,98 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::hash::{Hash, Hasher};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Tabs and panels are unchanged since v5.
+pub use super::v5::{
+    AggregationFunc, AxisArg, AxisItem, BaseMap, ColorCfg, Config, CustomFieldsOption,
+    DateTimeOptions, DrillDown, DrillDownData, DrillDownVariables, Field, FilterCondition, Filters,
+    GroupType, LabelOption, LabelPosition, Layout, LegendWidth, LineInterpolation, MapSymbolStyle,
+    MapType, MapView, Mapping, MarkLine, OverrideConfig, Panel, PanelConfig, PanelFields,
+    PanelFilter, Query, QueryConfig, QueryData, SizeByValue, Tab, TimeShift, Trellis, Value,
+    VariableList, Variables,
+};
+use super::{datetime_now, v5};
+
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Dashboard {
+    version: i32,
+    #[serde(default)]
+    pub dashboard_id: String,
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default = "datetime_now")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created: DateTime<FixedOffset>,
+    #[serde(default)]
+    pub tabs: Vec<Tab>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Variables>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_datetime_duration: Option<DateTimeOptions>,
+    #[serde(default, skip_serializing)]
+    pub updated_at: i64,
+}
+
+impl From<Dashboard> for super::Dashboard {
+    fn from(value: Dashboard) -> Self {
+        let version: i32 = 6;
+
+        let mut hasher = std::hash::DefaultHasher::new();
+        hasher.write_i32(version);
+        value.hash(&mut hasher);
+        let hash = hasher.finish().to_string();
+        let updated_at = value.updated_at;
+
+        Self {
+            v1: None,
+            v2: None,
+            v3: None,
+            v4: None,
+            v5: None,
+            v6: Some(value),
+            version,
+            hash,
+            updated_at,
+        }
+    }
+}
+
+impl From<v5::Dashboard> for Dashboard {
+    fn from(value: v5::Dashboard) -> Self {
+        Self {
+            version: 6,
+            dashboard_id: value.dashboard_id,
+            title: value.title,
+            description: value.description,
+            role: value.role,
+            owner: value.owner,
+            created: value.created,
+            tabs: value.tabs,
+            variables: value.variables,
+            default_datetime_duration: value.default_datetime_duration,
+            updated_at: value.updated_at,
+        }
+    }
+}
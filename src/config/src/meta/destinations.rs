@@ -79,6 +79,13 @@ pub struct Endpoint {
     pub skip_tls_verify: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    /// PEM encoded CA certificates trusted for this endpoint in addition to the
+    /// system roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_pem: Option<String>,
+    /// Proxy used to reach this endpoint, `http://` or `https://`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
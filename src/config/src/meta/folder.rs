@@ -30,3 +30,21 @@ pub enum FolderType {
 }
 
 pub const DEFAULT_FOLDER: &str = "default";
+
+/// The IDs of the objects contained in a folder.
+///
+/// Reports are not stored in folders themselves; a report is considered part
+/// of a dashboards folder when it includes one of the folder's dashboards.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderContents {
+    pub dashboards: Vec<String>,
+    pub alerts: Vec<String>,
+    pub reports: Vec<String>,
+}
+
+impl FolderContents {
+    /// Returns `true` if the folder contains no dashboards, alerts, or reports.
+    pub fn is_empty(&self) -> bool {
+        self.dashboards.is_empty() && self.alerts.is_empty() && self.reports.is_empty()
+    }
+}
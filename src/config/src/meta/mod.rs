@@ -32,6 +32,8 @@ pub mod self_reporting;
 pub mod short_url;
 pub mod sql;
 pub mod stream;
+pub mod synthetic;
 pub mod timed_annotations;
+pub mod trash;
 pub mod triggers;
 pub mod websocket;
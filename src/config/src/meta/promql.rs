@@ -220,6 +220,33 @@ pub struct RequestFormatQuery {
     pub query: String,
 }
 
+/// Inspect the metrics result cache.
+#[derive(Debug, Deserialize)]
+pub struct RequestCacheStatus {
+    /// Number of queries with the most cached entries to return. Defaults
+    /// to 10.
+    pub top: Option<usize>,
+    /// Include the queries of every org, only for the root user.
+    #[serde(default)]
+    pub all_orgs: bool,
+}
+
+/// Clear entries of the metrics result cache, either by `query_hash` or by
+/// `query`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RequestCacheDelete {
+    /// Hash key of the cached query, as returned by the cache status.
+    pub query_hash: Option<String>,
+    /// PromQL expression.
+    pub query: Option<String>,
+    /// Query resolution step width in `duration` format or float number of
+    /// seconds. Entries of every step are removed if left empty.
+    pub step: Option<String>,
+    /// Remove the entries of every org, only for the root user.
+    #[serde(default)]
+    pub all_orgs: bool,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Function {
     Avg,
@@ -13,6 +13,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
+
 use proto::cluster_rpc;
 use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
@@ -62,6 +64,10 @@ pub struct Request {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub use_cache: Option<bool>, // used for search job,
+    /// Returns how the query is planned, see [`ExplainResponse`], instead of
+    /// running it
+    #[serde(default)]
+    pub explain: bool,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -122,6 +128,18 @@ pub struct Query {
     pub streaming_output: bool,
     #[serde(default)]
     pub streaming_id: Option<String>,
+    /// Time zone histogram buckets are aligned to when histogram() has no time zone argument,
+    /// e.g. `America/Los_Angeles`, defaults to UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Adds `cache_detail` to the response, describing which time ranges were served from the
+    /// result cache
+    #[serde(default)]
+    pub debug_cache: bool,
+    /// Adds `pruning_stats` to the response, describing how many parquet row groups the
+    /// query skipped, set by the `debug=pruning` query parameter
+    #[serde(default)]
+    pub debug_pruning: bool,
 }
 
 fn default_size() -> i64 {
@@ -145,10 +163,54 @@ impl Default for Query {
             skip_wal: false,
             streaming_output: false,
             streaming_id: None,
+            timezone: None,
+            debug_cache: false,
+            debug_pruning: false,
         }
     }
 }
 
+/// Validates and normalizes a search time range in microseconds.
+///
+/// An `end_time` of 0 means now and an inverted range is rejected. A range longer than
+/// `max_query_range` hours (0 means unlimited) is clamped to its most recent part, and the
+/// message to report in the response's `function_error` is returned.
+pub fn validate_time_range(
+    start_time: &mut i64,
+    end_time: &mut i64,
+    max_query_range: i64,
+) -> Result<Option<String>, anyhow::Error> {
+    if *end_time == 0 {
+        *end_time = chrono::Utc::now().timestamp_micros();
+    }
+    if *start_time > *end_time {
+        return Err(anyhow::anyhow!(
+            "Invalid time range: start_time {} is greater than end_time {}",
+            start_time,
+            end_time
+        ));
+    }
+    let max_query_range_micros = max_query_range * 3600 * 1_000_000;
+    if max_query_range > 0 && (*end_time - *start_time) > max_query_range_micros {
+        *start_time = *end_time - max_query_range_micros;
+        return Ok(Some(format!(
+            "Query duration is modified due to query range restriction of {} hours",
+            max_query_range
+        )));
+    }
+    Ok(None)
+}
+
+impl Query {
+    /// See [`validate_time_range`]
+    pub fn validate_time_range(
+        &mut self,
+        max_query_range: i64,
+    ) -> Result<Option<String>, anyhow::Error> {
+        validate_time_range(&mut self.start_time, &mut self.end_time, max_query_range)
+    }
+}
+
 impl Request {
     #[inline]
     pub fn decode(&mut self) -> Result<(), std::io::Error> {
@@ -168,6 +230,31 @@ impl Request {
     }
 }
 
+/// Plan of a search request sent with `explain`, the query is not run.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ExplainResponse {
+    /// Streams read by the query
+    pub stream_names: Vec<String>,
+    /// Whether the inverted index is used to select the files to search
+    pub use_inverted_index: bool,
+    /// Query run against the tantivy inverted index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_condition: Option<String>,
+    /// Equality filters the files are pruned by, by stream
+    pub equal_items: hashbrown::HashMap<String, Vec<ExplainFilterItem>>,
+    /// Prefix filters the files are pruned by, by stream
+    pub prefix_items: hashbrown::HashMap<String, Vec<ExplainFilterItem>>,
+    /// Interval in seconds of the first histogram of the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram_interval: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, ToSchema)]
+pub struct ExplainFilterItem {
+    pub field: String,
+    pub value: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
 #[schema(as = SearchResponse)]
 pub struct Response {
@@ -199,11 +286,34 @@ pub struct Response {
     #[serde(default)]
     #[serde(skip_serializing_if = "String::is_empty")]
     pub function_error: String,
+    /// Query function errors with the time range each one happened in, kept
+    /// apart from `function_error` so merged responses don't lose any of them
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub function_errors: Vec<FunctionError>,
     #[serde(default)]
     pub is_partial: bool,
+    /// Caveats about the hits that don't make the response partial, e.g. a
+    /// column aged out of some of the searched data
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub histogram_interval: Option<i64>, // seconds, for histogram
+    /// Distinct intervals of all the histograms of the query in seconds, the
+    /// first one is `histogram_interval`. Only set if the query has several.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub histogram_intervals: Vec<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram_timezone: Option<String>, // time zone histogram buckets are aligned to
+    /// Calendar interval of the histogram, like `1 month`, whose buckets vary
+    /// in length. `histogram_interval` then counts 30 days per month.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram_calendar_interval: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_start_time: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -214,6 +324,196 @@ pub struct Response {
     pub work_group: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub order_by: Option<OrderBy>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_detail: Option<CacheDetail>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing: Option<SearchRouting>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pruning_stats: Option<PruningStats>,
+    /// The part of the requested time range the query was narrowed to, as
+    /// the streams have no data outside of it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range_clamped_to_data: Option<ClampedTimeRange>,
+    /// Peak DataFusion memory of the query in bytes, kept for usage reporting
+    #[serde(default)]
+    #[serde(skip_serializing)]
+    pub peak_memory: usize,
+}
+
+/// A time range in microseconds, the end is exclusive.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ClampedTimeRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl ClampedTimeRange {
+    /// Returns the part of `[start, end)` within this range, if any.
+    pub fn intersect(&self, start: i64, end: i64) -> Option<(i64, i64)> {
+        let (start, end) = (start.max(self.start), end.min(self.end));
+        (start < end).then_some((start, end))
+    }
+}
+
+/// An error of the query function while processing the hits of a time range.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct FunctionError {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub error: String,
+}
+
+impl std::fmt::Display for FunctionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (time range {} - {})",
+            self.error, self.start_time, self.end_time
+        )
+    }
+}
+
+/// The regions and clusters a search was routed to, returned when the request
+/// sets `regions` or `clusters`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SearchRouting {
+    pub regions: Vec<String>,
+    pub clusters: Vec<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Time ranges of a response served from the result cache and from search,
+/// returned when the query sets `debug_cache`.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema, PartialEq, Eq)]
+pub struct CacheDetail {
+    pub segments: Vec<CacheSegment>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct CacheSegment {
+    pub start_time: i64,
+    pub end_time: i64,
+    /// number of hits the segment contributed before the limit was applied
+    pub hits: usize,
+    pub source: CacheSegmentSource,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheSegmentSource {
+    Cache,
+    Search,
+}
+
+/// Parquet row group pruning of the files a query scanned, returned when the
+/// search is requested with `debug=pruning`.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema, PartialEq, Eq)]
+pub struct PruningStats {
+    /// Pruning of each `{stream_type}/{stream_name}` the query scanned
+    pub streams: BTreeMap<String, RowGroupPruning>,
+    /// The files with the most bytes scanned, to spot outliers
+    pub top_files: Vec<FilePruning>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, ToSchema, PartialEq, Eq)]
+pub struct RowGroupPruning {
+    pub row_groups_total: usize,
+    /// skipped by the inverted index before the file was opened
+    pub row_groups_pruned_by_index: usize,
+    /// skipped by the min/max statistics of the row group
+    pub row_groups_pruned_by_statistics: usize,
+    /// skipped by the bloom filters of the row group
+    pub row_groups_pruned_by_bloom_filter: usize,
+    pub row_groups_read: usize,
+    pub bytes_scanned: usize,
+    pub bytes_skipped: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct FilePruning {
+    pub file: String,
+    pub stream: String,
+    pub pruning: RowGroupPruning,
+}
+
+impl RowGroupPruning {
+    pub fn add(&mut self, other: &RowGroupPruning) {
+        self.row_groups_total += other.row_groups_total;
+        self.row_groups_pruned_by_index += other.row_groups_pruned_by_index;
+        self.row_groups_pruned_by_statistics += other.row_groups_pruned_by_statistics;
+        self.row_groups_pruned_by_bloom_filter += other.row_groups_pruned_by_bloom_filter;
+        self.row_groups_read += other.row_groups_read;
+        self.bytes_scanned += other.bytes_scanned;
+        self.bytes_skipped += other.bytes_skipped;
+    }
+}
+
+impl PruningStats {
+    /// Number of files kept in `top_files`
+    pub const TOP_FILES: usize = 5;
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    pub fn add_file(&mut self, file: FilePruning) {
+        self.streams
+            .entry(file.stream.clone())
+            .or_default()
+            .add(&file.pruning);
+        self.top_files.push(file);
+        self.truncate_top_files();
+    }
+
+    /// Adds up the pruning of another part of the query, e.g. the files
+    /// scanned by another node or the search of another time range
+    pub fn merge(&mut self, other: &PruningStats) {
+        for (stream, pruning) in other.streams.iter() {
+            self.streams.entry(stream.clone()).or_default().add(pruning);
+        }
+        self.top_files.extend(other.top_files.iter().cloned());
+        self.truncate_top_files();
+    }
+
+    fn truncate_top_files(&mut self) {
+        self.top_files.sort_by(|a, b| {
+            b.pruning
+                .bytes_scanned
+                .cmp(&a.pruning.bytes_scanned)
+                .then_with(|| a.file.cmp(&b.file))
+        });
+        self.top_files.truncate(Self::TOP_FILES);
+    }
+}
+
+/// What a node reports back once its part of the query was executed, sent as
+/// the last message of its flight response.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ExecutionStats {
+    #[serde(flatten)]
+    pub pruning: PruningStats,
+    /// Peak bytes reserved from the DataFusion memory pools of the query,
+    /// summed over the nodes
+    #[serde(default)]
+    pub peak_memory: usize,
+    /// The nodes the query was sent to
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<ResponseNodeTook>,
+}
+
+impl ExecutionStats {
+    pub fn merge(&mut self, other: &ExecutionStats) {
+        self.pruning.merge(&other.pruning);
+        self.peak_memory += other.peak_memory;
+        self.nodes.extend(other.nodes.iter().cloned());
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
@@ -239,7 +539,7 @@ impl ResponseTook {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, ToSchema, PartialEq, Eq)]
 pub struct ResponseNodeTook {
     pub node: String,
     pub is_ingester: bool,
@@ -264,13 +564,23 @@ impl Response {
             response_type: "".to_string(),
             trace_id: "".to_string(),
             function_error: "".to_string(),
+            function_errors: Vec::new(),
             is_partial: false,
+            warnings: Vec::new(),
             histogram_interval: None,
+            histogram_intervals: Vec::new(),
+            histogram_timezone: None,
+            histogram_calendar_interval: None,
             new_start_time: None,
             new_end_time: None,
             result_cache_ratio: 0,
             work_group: None,
             order_by: None,
+            cache_detail: None,
+            routing: None,
+            pruning_stats: None,
+            range_clamped_to_data: None,
+            peak_memory: 0,
         }
     }
 
@@ -293,6 +603,12 @@ impl Response {
         // self.total = self.hits.len();
     }
 
+    pub fn add_warning(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
     pub fn add_hit(&mut self, hit: &json::Value) {
         self.hits.push(hit.to_owned());
         self.total += 1;
@@ -362,10 +678,37 @@ impl Response {
         }
     }
 
+    /// Marks the response as partial because its time range was clamped by
+    /// [`validate_time_range`]
+    pub fn set_range_error(&mut self, range_error: String, start_time: i64, end_time: i64) {
+        self.is_partial = true;
+        self.function_error = if self.function_error.is_empty() {
+            range_error
+        } else {
+            format!("{} \n {}", range_error, self.function_error)
+        };
+        self.new_start_time = Some(start_time);
+        self.new_end_time = Some(end_time);
+    }
+
     pub fn set_histogram_interval(&mut self, val: Option<i64>) {
         self.histogram_interval = val;
     }
 
+    /// Keeps the intervals only when there is more than one, a single
+    /// interval is already reported by `histogram_interval`.
+    pub fn set_histogram_intervals(&mut self, val: Vec<i64>) {
+        self.histogram_intervals = if val.len() > 1 { val } else { Vec::new() };
+    }
+
+    pub fn set_histogram_timezone(&mut self, val: Option<String>) {
+        self.histogram_timezone = val;
+    }
+
+    pub fn set_histogram_calendar_interval(&mut self, val: Option<String>) {
+        self.histogram_calendar_interval = val;
+    }
+
     pub fn set_work_group(&mut self, val: Option<String>) {
         self.work_group = val;
     }
@@ -390,6 +733,8 @@ pub struct SearchPartitionRequest {
     pub query_fn: Option<String>,
     #[serde(default)]
     pub streaming_output: bool,
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 impl SearchPartitionRequest {
@@ -422,6 +767,7 @@ impl From<&Request> for SearchPartitionRequest {
             clusters: req.clusters.clone(),
             query_fn: req.query.query_fn.clone(),
             streaming_output: req.query.streaming_output,
+            timezone: req.query.timezone.clone(),
         }
     }
 }
@@ -436,12 +782,21 @@ pub struct SearchPartitionResponse {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub histogram_interval: Option<i64>, // seconds, for histogram
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram_timezone: Option<String>, // time zone histogram buckets are aligned to
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram_calendar_interval: Option<String>, // calendar interval, like `1 month`
     pub max_query_range: i64, // hours, for histogram
     pub partitions: Vec<[i64; 2]>,
     pub order_by: OrderBy,
     pub streaming_output: bool,
     pub streaming_aggs: bool,
     pub streaming_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range_clamped_to_data: Option<ClampedTimeRange>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, ToSchema)]
@@ -499,6 +854,9 @@ impl SearchHistoryRequest {
                 skip_wal: false,
                 streaming_output: false,
                 streaming_id: None,
+                timezone: None,
+                debug_cache: false,
+                debug_pruning: false,
             },
             encoding: RequestEncoding::Empty,
             regions: Vec::new(),
@@ -507,6 +865,7 @@ impl SearchHistoryRequest {
             search_type: Some(SearchEventType::Other),
             search_event_context: None,
             use_cache: None,
+            explain: false,
         };
         Ok(search_req)
     }
@@ -639,6 +998,25 @@ pub struct QueryInfo {
     pub end_time: i64,
 }
 
+/// Searches waiting in or running after the local search queue of a node.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct QueueStatusResponse {
+    pub waiting: usize,
+    pub running: usize,
+    pub status: Vec<QueuedSearch>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct QueuedSearch {
+    pub trace_id: String,
+    pub org_id: String,
+    pub user_id: Option<String>,
+    pub sql_hash: String,
+    pub enqueued_at: i64,
+    /// When the search left the queue, unset while it is waiting
+    pub started_at: Option<i64>,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct CancelQueryResponse {
     pub trace_id: String,
@@ -685,7 +1063,7 @@ impl ScanStats {
 impl From<Query> for cluster_rpc::SearchQuery {
     fn from(query: Query) -> Self {
         cluster_rpc::SearchQuery {
-            sql: query.sql.clone(),
+            sql: query.sql,
             quick_mode: query.quick_mode,
             query_type: query.query_type.clone(),
             from: query.from as i32,
@@ -697,6 +1075,53 @@ impl From<Query> for cluster_rpc::SearchQuery {
             query_fn: query.query_fn.unwrap_or_default(),
             action_id: query.action_id.unwrap_or_default(),
             skip_wal: query.skip_wal,
+            timezone: query.timezone.unwrap_or_default(),
+        }
+    }
+}
+
+/// The fields of a search query that planning its SQL reads, borrowed from
+/// either a [`Query`] or a [`cluster_rpc::SearchQuery`]. The result cache
+/// checks plan the SQL of every request, this spares them converting the query
+/// into its gRPC form.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchQueryRef<'a> {
+    pub sql: &'a str,
+    pub from: i64,
+    pub size: i64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub quick_mode: bool,
+    pub track_total_hits: bool,
+    pub timezone: &'a str,
+}
+
+impl<'a> From<&'a Query> for SearchQueryRef<'a> {
+    fn from(query: &'a Query) -> Self {
+        Self {
+            sql: &query.sql,
+            from: query.from,
+            size: query.size,
+            start_time: query.start_time,
+            end_time: query.end_time,
+            quick_mode: query.quick_mode,
+            track_total_hits: query.track_total_hits,
+            timezone: query.timezone.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+impl<'a> From<&'a cluster_rpc::SearchQuery> for SearchQueryRef<'a> {
+    fn from(query: &'a cluster_rpc::SearchQuery) -> Self {
+        Self {
+            sql: &query.sql,
+            from: query.from as i64,
+            size: query.size as i64,
+            start_time: query.start_time,
+            end_time: query.end_time,
+            quick_mode: query.quick_mode,
+            track_total_hits: query.track_total_hits,
+            timezone: &query.timezone,
         }
     }
 }
@@ -1030,6 +1455,9 @@ impl MultiStreamRequest {
                     skip_wal: self.skip_wal,
                     streaming_output: false,
                     streaming_id: None,
+                    timezone: None,
+                    debug_cache: false,
+                    debug_pruning: false,
                 },
                 regions: self.regions.clone(),
                 clusters: self.clusters.clone(),
@@ -1038,6 +1466,7 @@ impl MultiStreamRequest {
                 search_type: self.search_type,
                 search_event_context: self.search_event_context.clone(),
                 use_cache: None,
+                explain: false,
             });
         }
         res
@@ -1055,6 +1484,100 @@ pub struct PaginationQuery {
 mod tests {
     use super::*;
 
+    fn file_pruning(file: &str, stream: &str, bytes_scanned: usize) -> FilePruning {
+        FilePruning {
+            file: file.to_string(),
+            stream: stream.to_string(),
+            pruning: RowGroupPruning {
+                row_groups_total: 4,
+                row_groups_pruned_by_statistics: 3,
+                row_groups_read: 1,
+                bytes_scanned,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_pruning_stats_merge() {
+        let mut node1 = PruningStats::default();
+        for i in 0..4 {
+            node1.add_file(file_pruning(&format!("a{i}"), "logs/a", i * 10));
+        }
+        let mut node2 = PruningStats::default();
+        node2.add_file(file_pruning("b0", "logs/b", 25));
+        node2.add_file(file_pruning("b1", "logs/b", 5));
+        node2.add_file(file_pruning("a4", "logs/a", 100));
+
+        node1.merge(&node2);
+        assert_eq!(node1.streams.len(), 2);
+        assert_eq!(node1.streams["logs/a"].row_groups_total, 20);
+        assert_eq!(node1.streams["logs/a"].row_groups_pruned_by_statistics, 15);
+        assert_eq!(node1.streams["logs/b"].row_groups_read, 2);
+        assert_eq!(node1.streams["logs/b"].bytes_scanned, 30);
+        let top_files = node1
+            .top_files
+            .iter()
+            .map(|f| f.file.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(top_files, vec!["a4", "a3", "b0", "a2", "a1"]);
+    }
+
+    #[test]
+    fn test_search_query_ref_matches_proto() {
+        let query = Query {
+            sql: "SELECT * FROM \"default\"".to_string(),
+            from: 10,
+            size: 20,
+            start_time: 1,
+            end_time: 2,
+            quick_mode: true,
+            track_total_hits: true,
+            timezone: Some("Asia/Kolkata".to_string()),
+            ..Default::default()
+        };
+        let proto_query: cluster_rpc::SearchQuery = query.clone().into();
+        let (native, proto) = (
+            SearchQueryRef::from(&query),
+            SearchQueryRef::from(&proto_query),
+        );
+        assert_eq!(format!("{native:?}"), format!("{proto:?}"));
+
+        let query = Query::default();
+        assert_eq!(SearchQueryRef::from(&query).timezone, "");
+    }
+
+    #[test]
+    fn test_execution_stats_merge() {
+        let mut pruning = PruningStats::default();
+        pruning.add_file(file_pruning("a0", "logs/a", 10));
+        let node = |name: &str| ResponseNodeTook {
+            node: name.to_string(),
+            is_ingester: false,
+            took: 10,
+        };
+        let mut stats = ExecutionStats {
+            pruning: pruning.clone(),
+            peak_memory: 1024,
+            nodes: vec![node("querier-1")],
+        };
+        stats.merge(&ExecutionStats {
+            pruning,
+            peak_memory: 2048,
+            nodes: vec![node("querier-2")],
+        });
+        assert_eq!(stats.peak_memory, 3072);
+        assert_eq!(stats.nodes, vec![node("querier-1"), node("querier-2")]);
+        assert_eq!(stats.pruning.streams["logs/a"].row_groups_total, 10);
+
+        // the trailer of nodes only sending the pruning stats still parses
+        let old = json::to_vec(&stats.pruning).unwrap();
+        let parsed: ExecutionStats = json::from_slice(&old).unwrap();
+        assert_eq!(parsed.pruning, stats.pruning);
+        assert_eq!(parsed.peak_memory, 0);
+        assert!(parsed.nodes.is_empty());
+    }
+
     #[test]
     fn test_response() {
         let mut res = Response::default();
@@ -1067,6 +1590,65 @@ mod tests {
         assert_eq!(res.total, 11);
     }
 
+    #[test]
+    fn test_validate_time_range_inverted() {
+        let mut query = Query {
+            start_time: 200,
+            end_time: 100,
+            ..Default::default()
+        };
+        assert!(query.validate_time_range(0).is_err());
+        assert_eq!((query.start_time, query.end_time), (200, 100));
+    }
+
+    #[test]
+    fn test_validate_time_range_zero_end_time() {
+        let now = chrono::Utc::now().timestamp_micros();
+        let mut query = Query::default();
+        assert_eq!(query.validate_time_range(0).unwrap(), None);
+        assert_eq!(query.start_time, 0);
+        assert!(query.end_time >= now);
+
+        // an empty range is valid
+        let mut query = Query {
+            start_time: 100,
+            end_time: 100,
+            ..Default::default()
+        };
+        assert_eq!(query.validate_time_range(1).unwrap(), None);
+        assert_eq!((query.start_time, query.end_time), (100, 100));
+    }
+
+    #[test]
+    fn test_validate_time_range_clamped() {
+        let hour = 3600 * 1_000_000;
+        let end_time = 10 * hour;
+        let mut query = Query {
+            start_time: 0,
+            end_time,
+            ..Default::default()
+        };
+        let range_error = query.validate_time_range(2).unwrap().unwrap();
+        assert_eq!(query.start_time, end_time - 2 * hour);
+        assert_eq!(query.end_time, end_time);
+
+        let mut res = Response::default();
+        res.set_range_error(range_error, query.start_time, query.end_time);
+        assert!(res.is_partial);
+        assert!(res.function_error.contains("2 hours"));
+        assert_eq!(res.new_start_time, Some(end_time - 2 * hour));
+        assert_eq!(res.new_end_time, Some(end_time));
+
+        // within the limit nothing changes
+        let mut query = Query {
+            start_time: end_time - hour,
+            end_time,
+            ..Default::default()
+        };
+        assert_eq!(query.validate_time_range(2).unwrap(), None);
+        assert_eq!(query.start_time, end_time - hour);
+    }
+
     #[test]
     fn test_request_encoding() {
         let req = json::json!(
@@ -13,12 +13,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
     get_config,
     meta::{
-        search::{SearchEventContext, SearchEventType},
+        search::{Response, SearchEventContext, SearchEventType},
         stream::{FileMeta, StreamType},
     },
     SIZE_IN_MB,
@@ -121,6 +123,31 @@ pub struct UsageData {
     pub work_group: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_name: Option<String>,
+    /// Set on ingestion into a sampling companion stream to the stream the
+    /// records were sampled from, so they are not billed twice
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampled_from: Option<String>,
+    /// Number of file partitions the query scanned
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_files: Option<usize>,
+    /// Peak DataFusion memory of the query in bytes
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<usize>,
+    /// Number of nodes the query was executed on
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_nodes: Option<usize>,
+    /// Set on `FunctionCall` rows to the function the row is attributed to
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_name: Option<String>,
+    /// Set on usage of data written by a synthetic data generator
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<bool>,
 }
 
 #[derive(Hash, PartialEq, Eq)]
@@ -133,6 +160,8 @@ pub struct GroupKey {
     pub event: UsageEvent,
     pub email: String,
     pub node: String,
+    pub synthetic: bool,
+    pub function_name: String,
 }
 
 pub struct AggregatedData {
@@ -145,6 +174,9 @@ pub enum UsageEvent {
     Ingestion,
     Search,
     Functions,
+    /// Usage of one named function, reported next to the `Functions` row of
+    /// all functions of a request
+    FunctionCall,
     Other,
 }
 
@@ -154,6 +186,7 @@ impl std::fmt::Display for UsageEvent {
             UsageEvent::Ingestion => write!(f, "Ingestion"),
             UsageEvent::Search => write!(f, "Search"),
             UsageEvent::Functions => write!(f, "Functions"),
+            UsageEvent::FunctionCall => write!(f, "FunctionCall"),
             UsageEvent::Other => write!(f, "Other"),
         }
     }
@@ -276,6 +309,11 @@ pub struct RequestStats {
     pub min_ts: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_ts: Option<i64>,
+    /// Names of the functions the request called, each gets a `FunctionCall`
+    /// usage row
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_names: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_email: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -295,6 +333,23 @@ pub struct RequestStats {
     pub work_group: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_name: Option<String>,
+    /// Set on ingestion into a sampling companion stream to the stream the
+    /// records were sampled from, so they are not billed twice
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampled_from: Option<String>,
+    /// Number of file partitions the query scanned
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_files: Option<usize>,
+    /// Peak DataFusion memory of the query in bytes
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<usize>,
+    /// Number of nodes the query was executed on
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_nodes: Option<usize>,
 }
 impl Default for RequestStats {
     fn default() -> Self {
@@ -305,6 +360,7 @@ impl Default for RequestStats {
             response_time: 0.0,
             request_body: None,
             function: None,
+            function_names: None,
             cached_ratio: None,
             compressed_size: None,
             min_ts: None,
@@ -318,6 +374,10 @@ impl Default for RequestStats {
             is_partial: false,
             work_group: None,
             node_name: Some(get_config().common.instance_name.clone()),
+            sampled_from: None,
+            scan_files: None,
+            peak_memory: None,
+            num_nodes: None,
         }
     }
 }
@@ -330,6 +390,7 @@ impl From<FileMeta> for RequestStats {
             dropped_records: 0,
             response_time: 0.0,
             function: None,
+            function_names: None,
             request_body: None,
             cached_ratio: None,
             compressed_size: Some(meta.compressed_size as f64 / SIZE_IN_MB),
@@ -344,10 +405,29 @@ impl From<FileMeta> for RequestStats {
             is_partial: false,
             work_group: None,
             node_name: None,
+            sampled_from: None,
+            scan_files: None,
+            peak_memory: None,
+            num_nodes: None,
         }
     }
 }
 
+impl RequestStats {
+    /// Records the resources the search answered by `res` consumed
+    pub fn set_search_resources(&mut self, res: &Response) {
+        self.scan_files = Some(res.file_count);
+        self.peak_memory = Some(res.peak_memory);
+        self.num_nodes = res.took_detail.as_ref().map(|took| {
+            took.nodes
+                .iter()
+                .map(|node| node.node.as_str())
+                .collect::<HashSet<_>>()
+                .len()
+        });
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Stats {
     pub records: i64,
@@ -366,3 +446,85 @@ pub struct Stats {
     #[serde(default)]
     pub index_size: Option<f64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::search::{ResponseNodeTook, ResponseTook};
+
+    #[test]
+    fn test_usage_data_without_resources_deserializes() {
+        let old = r#"{
+            "_timestamp": 1700000000000000,
+            "event": "Search",
+            "year": 2024,
+            "month": 1,
+            "day": 2,
+            "hour": 3,
+            "event_time_hour": "2024010203",
+            "org_id": "default",
+            "request_body": "SELECT * FROM t",
+            "size": 1.5,
+            "unit": "MB",
+            "user_email": "root@example.com",
+            "response_time": 0.2,
+            "stream_type": "logs",
+            "num_records": 10,
+            "stream_name": "t"
+        }"#;
+        let usage: UsageData = serde_json::from_str(old).unwrap();
+        assert_eq!(usage.scan_files, None);
+        assert_eq!(usage.peak_memory, None);
+        assert_eq!(usage.num_nodes, None);
+
+        // unset fields are not written to the usage stream
+        let value = serde_json::to_value(&usage).unwrap();
+        assert!(value.get("scan_files").is_none());
+        assert!(value.get("peak_memory").is_none());
+        assert!(value.get("num_nodes").is_none());
+
+        let stats: RequestStats =
+            serde_json::from_str(r#"{"size": 1.0, "records": 1, "response_time": 0.1}"#).unwrap();
+        assert_eq!(stats.scan_files, None);
+        assert_eq!(stats.peak_memory, None);
+        assert_eq!(stats.num_nodes, None);
+    }
+
+    #[test]
+    fn test_request_stats_search_resources() {
+        let node = |name: &str, is_ingester: bool| ResponseNodeTook {
+            node: name.to_string(),
+            is_ingester,
+            took: 5,
+        };
+        let mut res = Response::new(0, 100);
+        res.set_file_count(12);
+        res.peak_memory = 64 * 1024 * 1024;
+        res.took_detail = Some(ResponseTook {
+            nodes: vec![
+                node("querier-1", false),
+                node("querier-2", false),
+                node("ingester-1", true),
+                // a node queried for two partitions is counted once
+                node("querier-1", false),
+            ],
+            ..Default::default()
+        });
+
+        let mut stats = RequestStats {
+            node_name: None,
+            ..Default::default()
+        };
+        stats.set_search_resources(&res);
+        assert_eq!(stats.scan_files, Some(12));
+        assert_eq!(stats.peak_memory, Some(64 * 1024 * 1024));
+        assert_eq!(stats.num_nodes, Some(3));
+
+        let value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(value["scan_files"], 12);
+        assert_eq!(value["peak_memory"], 64 * 1024 * 1024);
+        assert_eq!(value["num_nodes"], 3);
+        let parsed: RequestStats = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.num_nodes, Some(3));
+    }
+}
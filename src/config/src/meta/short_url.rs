@@ -14,7 +14,9 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
 
 #[derive(Clone, Debug, Default, Deserialize, ToSchema)]
 pub struct ShortenUrlRequest {
@@ -25,3 +27,50 @@ pub struct ShortenUrlRequest {
 pub struct ShortenUrlResponse {
     pub short_url: String,
 }
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct ListShortUrlsQuery {
+    /// The number of short URLs per page, defaults to 100.
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+    /// The zero based index of the page.
+    #[serde(default)]
+    pub page_idx: u64,
+}
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ListShortUrlsResponse {
+    pub list: Vec<ShortUrlItem>,
+    /// The total number of short URLs of the org.
+    pub total: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ShortUrlItem {
+    pub id: String,
+    pub short_url: String,
+    pub original_url: String,
+    /// Creation time in microseconds.
+    pub created_at: i64,
+    /// The last time the short URL was followed in microseconds, recorded at
+    /// most once per hour.
+    pub last_accessed: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct PurgeShortUrlsQuery {
+    /// Must be set to true to delete the short URLs past the retention period.
+    #[serde(default)]
+    pub expired: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct PurgeShortUrlsResponse {
+    pub deleted: usize,
+}
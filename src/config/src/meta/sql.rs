@@ -72,6 +72,46 @@ pub fn resolve_stream_names_with_type(sql: &str) -> Result<Vec<TableReference>,
     Ok(tables)
 }
 
+/// get stream names with their stream types from a sql, the type of a stream is
+/// taken from its qualifier, e.g. `"traces"."spans"`, and defaults to `stream_type`
+pub fn resolve_stream_names_with_types(
+    sql: &str,
+    stream_type: StreamType,
+) -> Result<Vec<(String, StreamType)>, anyhow::Error> {
+    let tables = resolve_stream_names_with_type(sql)?;
+    check_stream_type_qualifiers(&tables)?;
+    Ok(tables
+        .iter()
+        .map(|table| (table.stream_name(), table.get_stream_type(stream_type)))
+        .collect())
+}
+
+/// Rejects the streams qualified with something that is not a stream type, they
+/// would silently be searched as logs.
+pub fn check_stream_type_qualifiers(tables: &[TableReference]) -> Result<(), anyhow::Error> {
+    for table in tables.iter().filter(|table| table.has_stream_type()) {
+        let qualifier = table.stream_type().to_lowercase();
+        if !matches!(
+            qualifier.as_str(),
+            "logs"
+                | "metrics"
+                | "traces"
+                | "enrichment_tables"
+                | "enrich"
+                | "file_list"
+                | "metadata"
+                | "index"
+        ) {
+            return Err(anyhow::anyhow!(
+                "Unknown stream type {} of stream {}",
+                table.stream_type(),
+                table.stream_name()
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub trait TableReferenceExt {
     fn stream_type(&self) -> String;
     fn stream_name(&self) -> String;
@@ -1066,6 +1106,29 @@ mod tests {
         assert_eq!(sql.fields, vec!["a", "b", "c"]);
     }
 
+    #[test]
+    fn test_resolve_stream_names_with_types() {
+        let sql = r#"SELECT l.trace_id, t.duration FROM "default" AS l JOIN "traces"."default" AS t ON l.trace_id = t.trace_id"#;
+        let mut streams = resolve_stream_names_with_types(sql, StreamType::Logs).unwrap();
+        streams.sort_by_key(|(_, stream_type)| stream_type.to_string());
+        assert_eq!(
+            streams,
+            vec![
+                ("default".to_string(), StreamType::Logs),
+                ("default".to_string(), StreamType::Traces),
+            ]
+        );
+
+        let sql = "SELECT * FROM logs.app JOIN metrics.cpu ON app.host = cpu.host";
+        let streams = resolve_stream_names_with_types(sql, StreamType::Traces).unwrap();
+        assert!(streams.contains(&("app".to_string(), StreamType::Logs)));
+        assert!(streams.contains(&("cpu".to_string(), StreamType::Metrics)));
+
+        assert!(
+            resolve_stream_names_with_types("SELECT * FROM foo.app", StreamType::Logs).is_err()
+        );
+    }
+
     #[test]
     fn test_sql_new() {
         let table = "index.1.2022";
@@ -29,6 +29,7 @@ use crate::{
         hash::{gxhash, Sum64},
         json::{self, Map, Value},
     },
+    ID_COL_NAME,
 };
 
 pub const ALL_STREAM_TYPES: [StreamType; 7] = [
@@ -541,10 +542,67 @@ pub struct UpdateStreamSettings {
     pub max_query_range: Option<i64>,
     #[serde(default)]
     pub store_original_data: Option<bool>,
+    /// Days, 0 keeps `_original` as long as the records
+    #[serde(default)]
+    pub original_data_retention_days: Option<i64>,
     #[serde(default)]
     pub approx_partition: Option<bool>,
     #[serde(default)]
     pub extended_retention_days: UpdateSettingsWrapper<TimeRange>,
+    /// Set to enable or change sampling, a rate of 0 turns it off
+    #[serde(default)]
+    pub sampling: Option<StreamSampling>,
+    #[serde(default)]
+    pub cache_latest_files: Option<bool>,
+    /// Seconds, 0 removes the memory cache ttl
+    #[serde(default)]
+    pub memory_cache_ttl: Option<i64>,
+}
+
+/// Partial stream settings, every field given replaces the stored value as a
+/// whole and the omitted ones are kept.
+#[derive(Clone, Debug, Default, Deserialize, ToSchema)]
+pub struct PatchStreamSettings {
+    #[serde(default)]
+    pub partition_time_level: Option<PartitionTimeLevel>,
+    #[serde(default)]
+    pub partition_keys: Option<Vec<StreamPartition>>,
+    #[serde(default)]
+    pub full_text_search_keys: Option<Vec<String>>,
+    #[serde(default)]
+    pub index_fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub bloom_filter_fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub data_retention: Option<i64>,
+    #[serde(default)]
+    pub flatten_level: Option<i64>,
+    /// An empty list removes the user defined schema
+    #[serde(default)]
+    pub defined_schema_fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub max_query_range: Option<i64>,
+    #[serde(default)]
+    pub store_original_data: Option<bool>,
+    /// Days, 0 keeps `_original` as long as the records
+    #[serde(default)]
+    pub original_data_retention_days: Option<i64>,
+    #[serde(default)]
+    pub approx_partition: Option<bool>,
+    #[serde(default)]
+    pub extended_retention_days: Option<Vec<TimeRange>>,
+    /// A rate of 0 turns sampling off
+    #[serde(default)]
+    pub sampling: Option<StreamSampling>,
+    #[serde(default)]
+    pub cache_latest_files: Option<bool>,
+    /// Seconds, 0 removes the memory cache ttl
+    #[serde(default)]
+    pub memory_cache_ttl: Option<i64>,
+    /// The `settings_version` the patch was made against, the patch is
+    /// rejected if the settings were changed since
+    #[serde(default)]
+    pub settings_version: Option<i64>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
@@ -638,6 +696,60 @@ impl TimeRange {
         result
     }
 }
+/// Suffix of the companion stream a sampled stream writes into when no
+/// destination is configured.
+pub const SAMPLED_STREAM_SUFFIX: &str = "_sampled";
+
+/// Ingest-time sampling of accepted records into a companion stream.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StreamSampling {
+    /// Fraction of records to keep, in `(0, 1]`. A rate of 0 turns sampling off.
+    pub rate: f64,
+    /// Companion stream, defaults to `<stream>_sampled`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub destination: String,
+    /// Field whose value decides whether a record is sampled, defaults to
+    /// `_o2_id` and then to the whole record
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl StreamSampling {
+    const SCALE: u64 = 1_000_000;
+
+    pub fn destination(&self, stream_name: &str) -> String {
+        if self.destination.is_empty() {
+            format!("{stream_name}{SAMPLED_STREAM_SUFFIX}")
+        } else {
+            self.destination.clone()
+        }
+    }
+
+    /// Decides from the record content alone, so the same record is always
+    /// either sampled or not.
+    pub fn is_sampled(&self, record: &Map<String, Value>) -> bool {
+        if self.rate <= 0.0 {
+            return false;
+        }
+        if self.rate >= 1.0 {
+            return true;
+        }
+        let key = match self
+            .field
+            .as_deref()
+            .and_then(|f| record.get(f))
+            .or_else(|| record.get(ID_COL_NAME))
+        {
+            Some(v) => json::get_string_value(v),
+            None => json::to_string(record).unwrap_or_default(),
+        };
+        let h = gxhash::new().sum64(&key);
+        h % Self::SCALE < (self.rate * Self::SCALE as f64) as u64
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, ToSchema)]
 pub struct StreamSettings {
     #[serde(skip_serializing_if = "Option::None")]
@@ -663,6 +775,10 @@ pub struct StreamSettings {
     pub max_query_range: i64, // hours
     #[serde(default)]
     pub store_original_data: bool,
+    /// Days the `_original` column is kept, it is dropped from older files
+    /// by the compactor. 0 keeps it as long as the records.
+    #[serde(default)]
+    pub original_data_retention_days: i64,
     #[serde(default)]
     pub approx_partition: bool,
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -672,6 +788,22 @@ pub struct StreamSettings {
     pub index_updated_at: i64,
     #[serde(default)]
     pub extended_retention_days: Vec<TimeRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub sampling: Option<StreamSampling>,
+    /// Overrides `ZO_MEMORY_CACHE_CACHE_LATEST_FILES` for the stream. The files
+    /// of the streams with it enabled are the last evicted from memory cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub cache_latest_files: Option<bool>,
+    /// Seconds the files of the stream are kept in memory cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub memory_cache_ttl: Option<i64>,
+    /// Incremented on every write, PATCH requests pass it back to detect
+    /// concurrent changes
+    #[serde(default)]
+    pub settings_version: i64,
 }
 
 impl Serialize for StreamSettings {
@@ -696,9 +828,14 @@ impl Serialize for StreamSettings {
         state.serialize_field("data_retention", &self.data_retention)?;
         state.serialize_field("max_query_range", &self.max_query_range)?;
         state.serialize_field("store_original_data", &self.store_original_data)?;
+        state.serialize_field(
+            "original_data_retention_days",
+            &self.original_data_retention_days,
+        )?;
         state.serialize_field("approx_partition", &self.approx_partition)?;
         state.serialize_field("index_updated_at", &self.index_updated_at)?;
         state.serialize_field("extended_retention_days", &self.extended_retention_days)?;
+        state.serialize_field("settings_version", &self.settings_version)?;
 
         match self.defined_schema_fields.as_ref() {
             Some(fields) => {
@@ -723,6 +860,30 @@ impl Serialize for StreamSettings {
                 state.skip_field("flatten_level")?;
             }
         }
+        match self.sampling.as_ref() {
+            Some(sampling) => {
+                state.serialize_field("sampling", sampling)?;
+            }
+            None => {
+                state.skip_field("sampling")?;
+            }
+        }
+        match self.cache_latest_files.as_ref() {
+            Some(cache_latest_files) => {
+                state.serialize_field("cache_latest_files", cache_latest_files)?;
+            }
+            None => {
+                state.skip_field("cache_latest_files")?;
+            }
+        }
+        match self.memory_cache_ttl.as_ref() {
+            Some(memory_cache_ttl) => {
+                state.serialize_field("memory_cache_ttl", memory_cache_ttl)?;
+            }
+            None => {
+                state.skip_field("memory_cache_ttl")?;
+            }
+        }
         state.end()
     }
 }
@@ -814,6 +975,11 @@ impl From<&str> for StreamSettings {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
+        let original_data_retention_days = settings
+            .get("original_data_retention_days")
+            .and_then(|v| v.as_i64())
+            .unwrap_or_default();
+
         let approx_partition = settings
             .get("approx_partition")
             .and_then(|v| v.as_bool())
@@ -848,6 +1014,19 @@ impl From<&str> for StreamSettings {
             }
         }
 
+        let sampling = settings
+            .get("sampling")
+            .and_then(|v| json::from_value::<StreamSampling>(v.clone()).ok());
+
+        let cache_latest_files = settings.get("cache_latest_files").and_then(|v| v.as_bool());
+
+        let memory_cache_ttl = settings.get("memory_cache_ttl").and_then(|v| v.as_i64());
+
+        let settings_version = settings
+            .get("settings_version")
+            .and_then(|v| v.as_i64())
+            .unwrap_or_default();
+
         Self {
             partition_time_level,
             partition_keys,
@@ -859,10 +1038,77 @@ impl From<&str> for StreamSettings {
             flatten_level,
             defined_schema_fields,
             store_original_data,
+            original_data_retention_days,
             approx_partition,
             distinct_value_fields,
             index_updated_at,
             extended_retention_days,
+            sampling,
+            cache_latest_files,
+            memory_cache_ttl,
+            settings_version,
+        }
+    }
+}
+
+impl StreamSettings {
+    /// Replaces every field set in `patch`, leaving the others untouched.
+    /// Changing the full text search or index fields moves `index_updated_at`.
+    pub fn apply_patch(&mut self, patch: PatchStreamSettings, now: i64) {
+        if let Some(partition_time_level) = patch.partition_time_level {
+            self.partition_time_level = Some(partition_time_level);
+        }
+        if let Some(partition_keys) = patch.partition_keys {
+            self.partition_keys = partition_keys;
+        }
+        if let Some(full_text_search_keys) = patch.full_text_search_keys {
+            if full_text_search_keys != self.full_text_search_keys {
+                self.index_updated_at = now;
+            }
+            self.full_text_search_keys = full_text_search_keys;
+        }
+        if let Some(index_fields) = patch.index_fields {
+            if index_fields != self.index_fields {
+                self.index_updated_at = now;
+            }
+            self.index_fields = index_fields;
+        }
+        if let Some(bloom_filter_fields) = patch.bloom_filter_fields {
+            self.bloom_filter_fields = bloom_filter_fields;
+        }
+        if let Some(data_retention) = patch.data_retention {
+            self.data_retention = data_retention;
+        }
+        if let Some(flatten_level) = patch.flatten_level {
+            self.flatten_level = Some(flatten_level);
+        }
+        if let Some(defined_schema_fields) = patch.defined_schema_fields {
+            self.defined_schema_fields =
+                (!defined_schema_fields.is_empty()).then_some(defined_schema_fields);
+        }
+        if let Some(max_query_range) = patch.max_query_range {
+            self.max_query_range = max_query_range;
+        }
+        if let Some(store_original_data) = patch.store_original_data {
+            self.store_original_data = store_original_data;
+        }
+        if let Some(original_data_retention_days) = patch.original_data_retention_days {
+            self.original_data_retention_days = original_data_retention_days;
+        }
+        if let Some(approx_partition) = patch.approx_partition {
+            self.approx_partition = approx_partition;
+        }
+        if let Some(extended_retention_days) = patch.extended_retention_days {
+            self.extended_retention_days = extended_retention_days;
+        }
+        if let Some(sampling) = patch.sampling {
+            self.sampling = Some(sampling);
+        }
+        if let Some(cache_latest_files) = patch.cache_latest_files {
+            self.cache_latest_files = Some(cache_latest_files);
+        }
+        if let Some(memory_cache_ttl) = patch.memory_cache_ttl {
+            self.memory_cache_ttl = Some(memory_cache_ttl);
         }
     }
 }
@@ -1218,4 +1464,189 @@ mod tests {
         let expected_res = vec![TimeRange::new(0, 199), TimeRange::new(200, 300)];
         assert_eq!(TimeRange::flatten_overlapping_ranges(ranges), expected_res);
     }
+
+    #[test]
+    fn test_sampling_is_deterministic() {
+        let sampling = StreamSampling {
+            rate: 0.1,
+            ..Default::default()
+        };
+        let records = (0..10_000)
+            .map(|i| {
+                json::json!({ "_o2_id": format!("id-{i}"), "message": "hello" })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        let first = records
+            .iter()
+            .map(|r| sampling.is_sampled(r))
+            .collect::<Vec<_>>();
+        let second = records
+            .iter()
+            .map(|r| sampling.is_sampled(r))
+            .collect::<Vec<_>>();
+        assert_eq!(first, second);
+        let sampled = first.iter().filter(|v| **v).count();
+        assert!((800..1200).contains(&sampled), "sampled {sampled}");
+
+        // a record without _o2_id is hashed as a whole
+        let record = json::json!({ "message": "hello" })
+            .as_object()
+            .unwrap()
+            .clone();
+        let decision = sampling.is_sampled(&record);
+        assert!((0..100).all(|_| sampling.is_sampled(&record) == decision));
+
+        // records sharing the chosen field value share the decision
+        let sampling = StreamSampling {
+            rate: 0.5,
+            field: Some("trace_id".to_string()),
+            ..Default::default()
+        };
+        for i in 0..100 {
+            let a = json::json!({ "trace_id": format!("t-{i}"), "span": 1 });
+            let b = json::json!({ "trace_id": format!("t-{i}"), "span": 2 });
+            assert_eq!(
+                sampling.is_sampled(a.as_object().unwrap()),
+                sampling.is_sampled(b.as_object().unwrap())
+            );
+        }
+
+        let off = StreamSampling::default();
+        let all = StreamSampling {
+            rate: 1.0,
+            ..Default::default()
+        };
+        assert!(records.iter().all(|r| !off.is_sampled(r)));
+        assert!(records.iter().all(|r| all.is_sampled(r)));
+    }
+
+    #[test]
+    fn test_sampling_settings() {
+        let sampling = StreamSampling {
+            rate: 0.01,
+            ..Default::default()
+        };
+        assert_eq!(sampling.destination("app"), "app_sampled");
+        let sampling = StreamSampling {
+            rate: 0.01,
+            destination: "app_1pct".to_string(),
+            field: None,
+        };
+        assert_eq!(sampling.destination("app"), "app_1pct");
+
+        let settings = StreamSettings {
+            sampling: Some(sampling.clone()),
+            ..Default::default()
+        };
+        let stored = StreamSettings::from(json::to_string(&settings).unwrap().as_str());
+        assert_eq!(stored.sampling, Some(sampling));
+        let stored = StreamSettings::from(
+            json::to_string(&StreamSettings::default())
+                .unwrap()
+                .as_str(),
+        );
+        assert!(stored.sampling.is_none());
+    }
+
+    #[test]
+    fn test_memory_cache_settings() {
+        let settings = StreamSettings {
+            cache_latest_files: Some(true),
+            memory_cache_ttl: Some(3600),
+            ..Default::default()
+        };
+        let stored = StreamSettings::from(json::to_string(&settings).unwrap().as_str());
+        assert_eq!(stored.cache_latest_files, Some(true));
+        assert_eq!(stored.memory_cache_ttl, Some(3600));
+        let stored = StreamSettings::from(
+            json::to_string(&StreamSettings::default())
+                .unwrap()
+                .as_str(),
+        );
+        assert!(stored.cache_latest_files.is_none());
+        assert!(stored.memory_cache_ttl.is_none());
+    }
+
+    #[test]
+    fn test_apply_patch_keeps_omitted_fields() {
+        let mut settings = StreamSettings {
+            partition_keys: vec![StreamPartition::new("service")],
+            full_text_search_keys: vec!["message".to_string()],
+            index_fields: vec!["host".to_string()],
+            data_retention: 30,
+            defined_schema_fields: Some(vec!["message".to_string()]),
+            max_query_range: 24,
+            ..Default::default()
+        };
+
+        // retention alone must not wipe the full text search fields
+        let patch: PatchStreamSettings = json::from_str(r#"{"data_retention": 7}"#).unwrap();
+        settings.apply_patch(patch, 100);
+        assert_eq!(settings.data_retention, 7);
+        assert_eq!(settings.full_text_search_keys, vec!["message".to_string()]);
+        assert_eq!(settings.index_fields, vec!["host".to_string()]);
+        assert_eq!(
+            settings.partition_keys,
+            vec![StreamPartition::new("service")]
+        );
+        assert_eq!(settings.max_query_range, 24);
+        assert_eq!(settings.index_updated_at, 0);
+    }
+
+    #[test]
+    fn test_apply_patch_replaces_each_field_type() {
+        let mut settings = StreamSettings {
+            full_text_search_keys: vec!["message".to_string()],
+            defined_schema_fields: Some(vec!["message".to_string()]),
+            ..Default::default()
+        };
+        let patch: PatchStreamSettings = json::from_str(
+            r#"{
+                "partition_time_level": "daily",
+                "partition_keys": [{"field": "region"}],
+                "full_text_search_keys": ["log"],
+                "index_fields": ["host", "pod"],
+                "bloom_filter_fields": ["trace_id"],
+                "flatten_level": 2,
+                "defined_schema_fields": [],
+                "max_query_range": 48,
+                "store_original_data": true,
+                "approx_partition": true,
+                "extended_retention_days": [{"start": 1, "end": 2}],
+                "sampling": {"rate": 0.1}
+            }"#,
+        )
+        .unwrap();
+        settings.apply_patch(patch, 100);
+        assert_eq!(
+            settings.partition_time_level,
+            Some(PartitionTimeLevel::Daily)
+        );
+        assert_eq!(
+            settings.partition_keys,
+            vec![StreamPartition::new("region")]
+        );
+        assert_eq!(settings.full_text_search_keys, vec!["log".to_string()]);
+        assert_eq!(
+            settings.index_fields,
+            vec!["host".to_string(), "pod".to_string()]
+        );
+        assert_eq!(settings.bloom_filter_fields, vec!["trace_id".to_string()]);
+        assert_eq!(settings.flatten_level, Some(2));
+        assert_eq!(settings.defined_schema_fields, None);
+        assert_eq!(settings.max_query_range, 48);
+        assert!(settings.store_original_data);
+        assert!(settings.approx_partition);
+        assert_eq!(settings.extended_retention_days, vec![TimeRange::new(1, 2)]);
+        assert_eq!(settings.sampling.as_ref().map(|s| s.rate), Some(0.1));
+        assert_eq!(settings.index_updated_at, 100);
+
+        // the version survives a round trip through the stored json
+        settings.settings_version = 3;
+        let stored = StreamSettings::from(json::to_string(&settings).unwrap().as_str());
+        assert_eq!(stored.settings_version, 3);
+    }
 }
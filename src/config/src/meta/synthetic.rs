@@ -0,0 +1,220 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Shape of the records of a synthetic logs generator
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogTemplate {
+    #[default]
+    Nginx,
+    JsonApp,
+    K8s,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    #[default]
+    Gauge,
+    Counter,
+}
+
+/// Number of distinct values of the labels the generated records are spread
+/// over
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Cardinality {
+    #[serde(default = "default_services")]
+    pub services: usize,
+    #[serde(default = "default_hosts")]
+    pub hosts: usize,
+}
+
+impl Default for Cardinality {
+    fn default() -> Self {
+        Self {
+            services: default_services(),
+            hosts: default_hosts(),
+        }
+    }
+}
+
+fn default_services() -> usize {
+    20
+}
+
+fn default_hosts() -> usize {
+    100
+}
+
+fn default_depth() -> usize {
+    3
+}
+
+fn default_fanout() -> usize {
+    2
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SyntheticLogsRequest {
+    pub org: String,
+    pub stream: String,
+    pub rate_per_sec: u64,
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub template: LogTemplate,
+    #[serde(default)]
+    pub cardinality: Cardinality,
+    /// Seed of the record builder, generated when not set
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SyntheticMetricsRequest {
+    pub org: String,
+    /// Name of the metric, which is also the stream it is written to
+    pub stream: String,
+    /// Number of samples per second, spread over the series
+    pub rate_per_sec: u64,
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub metric_type: MetricKind,
+    #[serde(default)]
+    pub cardinality: Cardinality,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SyntheticTracesRequest {
+    pub org: String,
+    pub stream: String,
+    /// Number of traces per second
+    pub rate_per_sec: u64,
+    pub duration_secs: u64,
+    /// Levels of the call tree, the root span included
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+    /// Number of child spans of every span above the last level
+    #[serde(default = "default_fanout")]
+    pub fanout: usize,
+    #[serde(default)]
+    pub cardinality: Cardinality,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// What a generator produces, stored with the generator so any node can list
+/// it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyntheticSpec {
+    Logs {
+        template: LogTemplate,
+        cardinality: Cardinality,
+    },
+    Metrics {
+        metric_type: MetricKind,
+        cardinality: Cardinality,
+    },
+    Traces {
+        depth: usize,
+        fanout: usize,
+        cardinality: Cardinality,
+    },
+}
+
+impl SyntheticSpec {
+    /// Number of records one unit of `rate_per_sec` writes, the spans of a
+    /// whole call tree for traces
+    pub fn records_per_unit(&self) -> u64 {
+        match self {
+            SyntheticSpec::Traces { depth, fanout, .. } => {
+                let mut total = 0u64;
+                let mut level = 1u64;
+                for _ in 0..*depth {
+                    total = total.saturating_add(level);
+                    level = level.saturating_mul(*fanout as u64);
+                }
+                total
+            }
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SyntheticGenerator {
+    pub id: String,
+    pub org: String,
+    pub stream: String,
+    pub rate_per_sec: u64,
+    pub duration_secs: u64,
+    pub seed: u64,
+    /// Start time in microseconds
+    pub created_at: i64,
+    pub created_by: String,
+    /// Node running the generator
+    pub node: String,
+    #[serde(flatten)]
+    pub spec: SyntheticSpec,
+}
+
+impl SyntheticGenerator {
+    /// Time in microseconds after which the generator stops
+    pub fn end_time(&self) -> i64 {
+        self.created_at + (self.duration_secs as i64) * 1_000_000
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ListSyntheticGenerators {
+    pub list: Vec<SyntheticGenerator>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_per_unit() {
+        let spec = SyntheticSpec::Traces {
+            depth: 3,
+            fanout: 2,
+            cardinality: Cardinality::default(),
+        };
+        assert_eq!(spec.records_per_unit(), 7);
+        let spec = SyntheticSpec::Logs {
+            template: LogTemplate::K8s,
+            cardinality: Cardinality::default(),
+        };
+        assert_eq!(spec.records_per_unit(), 1);
+    }
+
+    #[test]
+    fn test_logs_request_defaults() {
+        let req: SyntheticLogsRequest = crate::utils::json::from_str(
+            r#"{"org":"default","stream":"app","rate_per_sec":10,"duration_secs":60,"template":"json_app","cardinality":{"services":5}}"#,
+        )
+        .unwrap();
+        assert_eq!(req.template, LogTemplate::JsonApp);
+        assert_eq!(req.cardinality.services, 5);
+        assert_eq!(req.cardinality.hosts, 100);
+        assert!(req.seed.is_none());
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+/// Indicates the type of resource that was moved to the trash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashItemType {
+    Dashboard,
+    Alert,
+}
+
+/// A soft-deleted resource that can still be restored from the trash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashItem {
+    pub item_type: TrashItemType,
+    /// The dashboard ID of a dashboard or the KSUID of an alert.
+    pub id: String,
+    /// The title of a dashboard or the name of an alert.
+    pub name: String,
+    /// The ID of the folder that the resource will be restored to.
+    pub folder_id: String,
+    pub folder_name: String,
+    /// Unix timestamp in microseconds when the resource was moved to the trash.
+    pub deleted_at: i64,
+    pub deleted_by: Option<String>,
+}
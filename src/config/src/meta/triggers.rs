@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+use crate::meta::alerts::{
+    alert::Alert,
+    state::{AlertState, AlertStateEvent},
+};
+
 #[derive(Debug, Clone, sqlx::Type, PartialEq, Serialize, Deserialize, Default)]
 #[repr(i32)]
 pub enum TriggerStatus {
@@ -18,6 +23,8 @@ pub enum TriggerModule {
     #[default]
     Alert,
     DerivedStream,
+    /// A backfill job of a scheduled pipeline
+    Backfill,
 }
 
 impl std::fmt::Display for TriggerModule {
@@ -26,6 +33,7 @@ impl std::fmt::Display for TriggerModule {
             TriggerModule::Alert => write!(f, "alert"),
             TriggerModule::Report => write!(f, "report"),
             TriggerModule::DerivedStream => write!(f, "derived_stream"),
+            TriggerModule::Backfill => write!(f, "backfill"),
         }
     }
 }
@@ -64,6 +72,17 @@ pub struct ScheduledTriggerData {
     pub tolerance: i64,
     #[serde(default)]
     pub last_satisfied_at: Option<i64>,
+    /// Start of the first period processed by a derived stream, the data
+    /// before it can only be processed by a backfill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_period_start: Option<i64>,
+    /// State of the alert after its last evaluation
+    #[serde(default, skip_serializing_if = "AlertState::is_inactive")]
+    pub alert_state: AlertState,
+    /// State changes persisted with the trigger but not yet written to the
+    /// alert events stream
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_state_events: Vec<AlertStateEvent>,
 }
 
 impl ScheduledTriggerData {
@@ -72,4 +91,30 @@ impl ScheduledTriggerData {
         self.period_end_time = None;
         self.tolerance = 0;
     }
+
+    /// Moves the alert to `new_state`. If the state changes and the alert
+    /// emits state events, the transition is queued to be emitted once the
+    /// trigger data is persisted, and the queued event is returned.
+    pub fn transition_alert(
+        &mut self,
+        alert: &Alert,
+        new_state: AlertState,
+        evaluated_at: i64,
+    ) -> Option<&mut AlertStateEvent> {
+        let old_state = self.alert_state;
+        if old_state == new_state {
+            return None;
+        }
+        self.alert_state = new_state;
+        if !alert.emit_state_events {
+            return None;
+        }
+        self.pending_state_events.push(AlertStateEvent::new(
+            alert,
+            old_state,
+            new_state,
+            evaluated_at,
+        ));
+        self.pending_state_events.last_mut()
+    }
 }
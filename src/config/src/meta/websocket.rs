@@ -19,6 +19,10 @@ use crate::meta::search::{Response, SearchEventContext};
 
 pub const MAX_QUERY_RANGE_LIMIT_ERROR_MESSAGE: &str = "Reached Max query range limit.";
 
+/// Clients sending a `protocol_version` of at least this value receive `progress` events
+/// while the search runs. Older clients only receive the search responses.
+pub const PROGRESS_EVENTS_PROTOCOL_VERSION: u32 = 2;
+
 pub enum SearchResultType {
     Cached(Response),
     Search(Response),
@@ -36,4 +40,93 @@ pub struct SearchEventReq {
     pub search_event_context: Option<SearchEventContext>,
     #[serde(default)]
     pub fallback_order_by_col: Option<String>,
+    #[serde(default)]
+    pub protocol_version: u32,
+}
+
+impl SearchEventReq {
+    pub fn wants_progress_events(&self) -> bool {
+        self.protocol_version >= PROGRESS_EVENTS_PROTOCOL_VERSION
+    }
+}
+
+/// Tracks how much of the requested time range a websocket search has covered
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchProgress {
+    /// Requested time range in microseconds
+    total_range: i64,
+    /// Time range covered so far in microseconds
+    scanned_range: i64,
+    pub scan_size: usize,
+    pub hits: usize,
+}
+
+impl SearchProgress {
+    pub fn new(start_time: i64, end_time: i64) -> Self {
+        Self {
+            total_range: (end_time - start_time).max(0),
+            ..Default::default()
+        }
+    }
+
+    /// Record a completed partition, delta or cached response
+    pub fn record(&mut self, start_time: i64, end_time: i64, scan_size: usize, hits: usize) {
+        self.scanned_range += (end_time - start_time).max(0);
+        self.scan_size += scan_size;
+        self.hits += hits;
+    }
+
+    /// Percentage of the requested time range covered so far, from 0 to 100
+    pub fn percent(&self) -> u8 {
+        if self.total_range == 0 {
+            return 100;
+        }
+        let scanned = self.scanned_range.min(self.total_range) as f64;
+        (scanned / self.total_range as f64 * 100.0).floor() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_progress() {
+        let mut progress = SearchProgress::new(0, 400);
+        assert_eq!(progress.percent(), 0);
+
+        progress.record(300, 400, 1024, 10);
+        assert_eq!(progress.percent(), 25);
+
+        progress.record(0, 300, 2048, 5);
+        assert_eq!(progress.percent(), 100);
+        assert_eq!(progress.scan_size, 3072);
+        assert_eq!(progress.hits, 15);
+
+        // overlapping ranges never go past 100
+        progress.record(0, 100, 0, 0);
+        assert_eq!(progress.percent(), 100);
+
+        assert_eq!(SearchProgress::new(10, 10).percent(), 100);
+    }
+
+    #[test]
+    fn test_wants_progress_events() {
+        let json = serde_json::json!({
+            "trace_id": "abc",
+            "payload": {"query": {"sql": "select * from t", "start_time": 0, "end_time": 1}},
+            "time_offset": null,
+            "stream_type": "logs",
+            "use_cache": true,
+            "search_type": "ui",
+        });
+        let req: SearchEventReq = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(req.protocol_version, 0);
+        assert!(!req.wants_progress_events());
+
+        let mut json = json;
+        json["protocol_version"] = serde_json::json!(PROGRESS_EVENTS_PROTOCOL_VERSION);
+        let req: SearchEventReq = serde_json::from_value(json).unwrap();
+        assert!(req.wants_progress_events());
+    }
 }
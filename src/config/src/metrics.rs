@@ -24,6 +24,10 @@ use prometheus::{
 pub const NAMESPACE: &str = "zo";
 const HELP_SUFFIX: &str =
     "Please include 'organization, 'stream type', and 'stream' labels for this metric.";
+const PIPELINE_HELP_SUFFIX: &str = "Please include 'organization', 'pipeline_id', 'stage_index', and 'stage_type' labels for this metric.";
+/// 1KB to 1GB, four times bigger at each bucket
+pub static FILE_SIZE_BUCKETS: Lazy<Vec<f64>> =
+    Lazy::new(|| prometheus::exponential_buckets(1024.0, 4.0, 11).expect("Buckets created"));
 pub const SPAN_METRICS_BUCKET: [f64; 15] = [
     0.1, 0.5, 1.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0,
     60000.0,
@@ -237,6 +241,20 @@ pub static INGEST_WAL_LOCK_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     .expect("Metric created")
 });
 
+pub static INGEST_FILE_SIZE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "ingest_file_size_bytes",
+            "Size of the parquet files uploaded by ingesters",
+        )
+        .namespace(NAMESPACE)
+        .buckets(FILE_SIZE_BUCKETS.clone())
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "stream"],
+    )
+    .expect("Metric created")
+});
+
 // querier memory cache stats
 pub static QUERY_MEMORY_CACHE_LIMIT_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
     IntGaugeVec::new(
@@ -365,6 +383,32 @@ pub static QUERY_METRICS_CACHE_HITS: Lazy<IntCounterVec> = Lazy::new(|| {
     .expect("Metric created")
 });
 
+// query parquet pruning stats
+pub static QUERY_PARQUET_ROW_GROUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "query_parquet_row_groups",
+            "Querier parquet row groups by pruning result: pruned_by_index, pruned_by_statistics, pruned_by_bloom_filter or read",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "result"],
+    )
+    .expect("Metric created")
+});
+pub static QUERY_PARQUET_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "query_parquet_bytes",
+            "Querier parquet bytes by pruning result: scanned or skipped",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "result"],
+    )
+    .expect("Metric created")
+});
+
 // compactor stats
 pub static COMPACT_USED_TIME: Lazy<CounterVec> = Lazy::new(|| {
     CounterVec::new(
@@ -402,6 +446,45 @@ pub static COMPACT_MERGED_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .expect("Metric created")
 });
+pub static COMPACT_INPUT_FILE_SIZE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "compact_input_file_size_bytes",
+            "Size of the files merged by the compactor",
+        )
+        .namespace(NAMESPACE)
+        .buckets(FILE_SIZE_BUCKETS.clone())
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "stream"],
+    )
+    .expect("Metric created")
+});
+pub static COMPACT_OUTPUT_FILE_SIZE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "compact_output_file_size_bytes",
+            "Size of the files produced by the compactor",
+        )
+        .namespace(NAMESPACE)
+        .buckets(FILE_SIZE_BUCKETS.clone())
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "stream"],
+    )
+    .expect("Metric created")
+});
+pub static COMPACT_MERGE_RATIO: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "compact_merge_ratio",
+            "Files produced over files merged by a compactor merge batch",
+        )
+        .namespace(NAMESPACE)
+        .buckets(vec![0.01, 0.02, 0.05, 0.1, 0.2, 0.3, 0.5, 0.7, 1.0])
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "stream"],
+    )
+    .expect("Metric created")
+});
 pub static COMPACT_PENDING_JOBS: Lazy<IntGaugeVec> = Lazy::new(|| {
     IntGaugeVec::new(
         Opts::new(
@@ -414,6 +497,105 @@ pub static COMPACT_PENDING_JOBS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .expect("Metric created")
 });
+pub static COMPACT_PENDING_JOBS_OLDEST_AGE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "compact_pending_jobs_oldest_age_seconds",
+            "Age in seconds of the oldest compactor pending job.".to_owned(),
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type"],
+    )
+    .expect("Metric created")
+});
+pub static COMPACT_RUNNING_JOBS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "compact_running_jobs",
+            "Compactor running jobs count.".to_owned(),
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type"],
+    )
+    .expect("Metric created")
+});
+pub static COMPACT_DOWNSAMPLING_OFFSET_LAG: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "compact_downsampling_offset_lag_seconds",
+            "Seconds between now and the offset of a downsampling rule, the data after the offset is not downsampled yet.".to_owned(),
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream", "step"],
+    )
+    .expect("Metric created")
+});
+
+// pipeline stats
+pub static PIPELINE_STAGE_EXECUTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "pipeline_stage_executions",
+            "Pipeline stage executions. ".to_owned() + PIPELINE_HELP_SUFFIX,
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "pipeline_id", "stage_index", "stage_type"],
+    )
+    .expect("Metric created")
+});
+pub static PIPELINE_STAGE_TIME: Lazy<CounterVec> = Lazy::new(|| {
+    CounterVec::new(
+        Opts::new(
+            "pipeline_stage_time",
+            "Pipeline stage execution time in seconds. ".to_owned() + PIPELINE_HELP_SUFFIX,
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "pipeline_id", "stage_index", "stage_type"],
+    )
+    .expect("Metric created")
+});
+pub static PIPELINE_STAGE_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "pipeline_stage_errors",
+            "Pipeline stage errors. ".to_owned() + PIPELINE_HELP_SUFFIX,
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "pipeline_id", "stage_index", "stage_type"],
+    )
+    .expect("Metric created")
+});
+pub static PIPELINE_STAGE_RECORDS_IN: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "pipeline_stage_records_in",
+            "Records received by pipeline stage. ".to_owned() + PIPELINE_HELP_SUFFIX,
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "pipeline_id", "stage_index", "stage_type"],
+    )
+    .expect("Metric created")
+});
+pub static PIPELINE_STAGE_RECORDS_OUT: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "pipeline_stage_records_out",
+            "Records emitted by pipeline stage. ".to_owned() + PIPELINE_HELP_SUFFIX,
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "pipeline_id", "stage_index", "stage_type"],
+    )
+    .expect("Metric created")
+});
+
 // TODO deletion / archiving stats
 
 // storage stats
@@ -620,6 +802,33 @@ pub static META_NUM_DASHBOARDS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .expect("Metric created")
 });
+pub static META_SCHEMA_NOT_FOUND_CACHE_HITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "meta_schema_not_found_cache_hits",
+            "Lookups of stream schemas that do not exist served without the meta store",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type"],
+    )
+    .expect("Metric created")
+});
+pub static META_STREAM_TIME_TO_QUERYABLE: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "meta_stream_time_to_queryable",
+            "Seconds from the earliest record of a new stream until this node can search it",
+        )
+        .namespace(NAMESPACE)
+        .buckets(vec![
+            0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+        ])
+        .const_labels(create_const_labels()),
+        &["organization", "stream_type", "source"],
+    )
+    .expect("Metric created")
+});
 
 // metrics for query manager
 pub static QUERY_RUNNING_NUMS: Lazy<IntGaugeVec> = Lazy::new(|| {
@@ -640,6 +849,30 @@ pub static QUERY_PENDING_NUMS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .expect("Metric created")
 });
+pub static QUERY_QUEUE_WAIT_TIME: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "query_queue_wait_time",
+            "Seconds a query waited in the search queue",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization"],
+    )
+    .expect("Metric created")
+});
+pub static QUERY_REJECTED_NUMS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "query_rejected_nums",
+            "Queries rejected because their organization ran too many at once",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization"],
+    )
+    .expect("Metric created")
+});
 pub static QUERY_TIMEOUT_NUMS: Lazy<IntCounterVec> = Lazy::new(|| {
     IntCounterVec::new(
         Opts::new("query_timeout_nums", "Timeout query numbers")
@@ -658,6 +891,18 @@ pub static QUERY_CANCELED_NUMS: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .expect("Metric created")
 });
+pub static WEBSOCKET_BACKPRESSURE_NUMS: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "websocket_backpressure_nums",
+            "Websocket messages that waited for the client because its send buffer was full",
+        )
+        .namespace(NAMESPACE)
+        .const_labels(create_const_labels()),
+        &["organization"],
+    )
+    .expect("Metric created")
+});
 
 // This corresponds to mysql or pgsql queries, not sqlite as that is local and can be ignored
 pub static DB_QUERY_NUMS: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -804,6 +1049,9 @@ fn register_metrics(registry: &Registry) {
     registry
         .register(Box::new(INGEST_WAL_LOCK_TIME.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(INGEST_FILE_SIZE_BYTES.clone()))
+        .expect("Metric registered");
 
     // querier stats
     registry
@@ -836,6 +1084,12 @@ fn register_metrics(registry: &Registry) {
     registry
         .register(Box::new(QUERY_METRICS_CACHE_HITS.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(QUERY_PARQUET_ROW_GROUPS.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(QUERY_PARQUET_BYTES.clone()))
+        .expect("Metric registered");
 
     // query manager
     registry
@@ -844,12 +1098,21 @@ fn register_metrics(registry: &Registry) {
     registry
         .register(Box::new(QUERY_PENDING_NUMS.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(QUERY_QUEUE_WAIT_TIME.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(QUERY_REJECTED_NUMS.clone()))
+        .expect("Metric registered");
     registry
         .register(Box::new(QUERY_TIMEOUT_NUMS.clone()))
         .expect("Metric registered");
     registry
         .register(Box::new(QUERY_CANCELED_NUMS.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(WEBSOCKET_BACKPRESSURE_NUMS.clone()))
+        .expect("Metric registered");
 
     // compactor stats
     registry
@@ -861,9 +1124,44 @@ fn register_metrics(registry: &Registry) {
     registry
         .register(Box::new(COMPACT_MERGED_BYTES.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_INPUT_FILE_SIZE_BYTES.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_OUTPUT_FILE_SIZE_BYTES.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_MERGE_RATIO.clone()))
+        .expect("Metric registered");
     registry
         .register(Box::new(COMPACT_PENDING_JOBS.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_PENDING_JOBS_OLDEST_AGE.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_RUNNING_JOBS.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(COMPACT_DOWNSAMPLING_OFFSET_LAG.clone()))
+        .expect("Metric registered");
+
+    // pipeline stats
+    registry
+        .register(Box::new(PIPELINE_STAGE_EXECUTIONS.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(PIPELINE_STAGE_TIME.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(PIPELINE_STAGE_ERRORS.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(PIPELINE_STAGE_RECORDS_IN.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(PIPELINE_STAGE_RECORDS_OUT.clone()))
+        .expect("Metric registered");
 
     // storage stats
     registry
@@ -924,6 +1222,12 @@ fn register_metrics(registry: &Registry) {
     registry
         .register(Box::new(META_NUM_DASHBOARDS.clone()))
         .expect("Metric registered");
+    registry
+        .register(Box::new(META_SCHEMA_NOT_FOUND_CACHE_HITS.clone()))
+        .expect("Metric registered");
+    registry
+        .register(Box::new(META_STREAM_TIME_TO_QUERYABLE.clone()))
+        .expect("Metric registered");
 
     // db stats
     registry
@@ -959,6 +1263,19 @@ fn register_metrics(registry: &Registry) {
         .expect("Metric registered");
 }
 
+/// The stream label of the file size histograms, left empty unless enabled
+/// for debugging so the number of series stays bounded
+pub fn file_size_stream_label(stream_name: &str) -> &str {
+    if crate::config::get_config()
+        .common
+        .prometheus_file_size_stream_label
+    {
+        stream_name
+    } else {
+        ""
+    }
+}
+
 fn create_const_labels() -> HashMap<String, String> {
     let cfg = crate::config::get_config();
     let mut labels = HashMap::new();
@@ -985,3 +1302,52 @@ pub fn get_registry() -> Registry {
     register_metrics(&registry);
     registry
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_size_metrics_register_and_record() {
+        assert_eq!(FILE_SIZE_BUCKETS.first(), Some(&1024.0));
+        assert_eq!(FILE_SIZE_BUCKETS.last(), Some(&(1024.0 * 1024.0 * 1024.0)));
+
+        // a config refresh builds the registry again from the same metrics
+        let _ = get_registry();
+        let registry = get_registry();
+
+        let stream = file_size_stream_label("file_size_test");
+        assert_eq!(stream, "");
+        INGEST_FILE_SIZE_BYTES
+            .with_label_values(&["default", "logs", stream])
+            .observe(3.0 * 1024.0 * 1024.0);
+        COMPACT_INPUT_FILE_SIZE_BYTES
+            .with_label_values(&["default", "logs", stream])
+            .observe(2048.0);
+        COMPACT_OUTPUT_FILE_SIZE_BYTES
+            .with_label_values(&["default", "logs", stream])
+            .observe(256.0 * 1024.0 * 1024.0);
+        COMPACT_MERGE_RATIO
+            .with_label_values(&["default", "logs", stream])
+            .observe(0.1);
+
+        let families = registry.gather();
+        for name in [
+            "zo_ingest_file_size_bytes",
+            "zo_compact_input_file_size_bytes",
+            "zo_compact_output_file_size_bytes",
+            "zo_compact_merge_ratio",
+        ] {
+            let family = families
+                .iter()
+                .find(|f| f.get_name() == name)
+                .unwrap_or_else(|| panic!("{name} is not registered"));
+            let samples: u64 = family
+                .get_metric()
+                .iter()
+                .map(|m| m.get_histogram().get_sample_count())
+                .sum();
+            assert!(samples >= 1, "{name} recorded nothing");
+        }
+    }
+}
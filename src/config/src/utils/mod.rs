@@ -27,6 +27,7 @@ pub mod parquet;
 pub mod prom_json_encoder;
 pub mod rand;
 pub mod record_batch_ext;
+pub mod render_queue;
 pub mod schema;
 pub mod schema_ext;
 pub mod size;
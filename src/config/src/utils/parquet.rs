@@ -179,6 +179,25 @@ pub async fn read_metadata_from_bytes(data: &bytes::Bytes) -> Result<FileMeta, a
     Ok(meta)
 }
 
+/// Returns the uncompressed size of `column` summed over the row groups, 0
+/// when the file doesn't have it
+pub async fn read_column_size_from_bytes(
+    data: &bytes::Bytes,
+    column: &str,
+) -> Result<i64, anyhow::Error> {
+    let schema_reader = Cursor::new(data.clone());
+    let arrow_reader = ParquetRecordBatchStreamBuilder::new(schema_reader).await?;
+    let size = arrow_reader
+        .metadata()
+        .row_groups()
+        .iter()
+        .flat_map(|rg| rg.columns())
+        .filter(|c| c.column_path().string() == column)
+        .map(|c| c.uncompressed_size())
+        .sum();
+    Ok(size)
+}
+
 pub async fn read_metadata_from_file(path: &PathBuf) -> Result<FileMeta, anyhow::Error> {
     let mut meta = FileMeta::default();
     let mut file = tokio::fs::File::open(path).await?;
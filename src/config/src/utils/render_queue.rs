@@ -0,0 +1,598 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounded FIFO scheduler for headless chromium report renders.
+//!
+//! Every render, scheduled or triggered manually, has to take one of the
+//! `ZO_REPORT_RENDER_CONCURRENCY` slots before launching chromium. Renders
+//! waiting for a slot are queued in arrival order, the queue is bounded by
+//! `ZO_REPORT_RENDER_QUEUE_MAX_DEPTH` and each render is limited to
+//! `ZO_REPORT_RENDER_TIMEOUT` seconds, after which the process tree of the
+//! registered browser is killed.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, Signal};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+use crate::{get_config, utils::time::now_micros};
+
+/// Render duration assumed for estimates until the first render completes
+const DEFAULT_RENDER_DURATION: Duration = Duration::from_secs(30);
+
+pub static REPORT_RENDER_QUEUE: Lazy<RenderQueue> = Lazy::new(|| {
+    let cfg = get_config();
+    RenderQueue::new(
+        cfg.report_server.render_concurrency,
+        cfg.report_server.render_queue_max_depth,
+        Duration::from_secs(cfg.report_server.render_timeout),
+    )
+});
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The queue already holds the maximum number of waiting renders
+    QueueFull(usize),
+    /// The render did not finish in time and was killed
+    Timeout(Duration),
+    /// The renderer itself returned an error
+    Failed(anyhow::Error),
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::QueueFull(depth) => {
+                write!(f, "report render queue is full, max depth: {depth}")
+            }
+            RenderError::Timeout(timeout) => {
+                write!(f, "report render timed out after {}s", timeout.as_secs())
+            }
+            RenderError::Failed(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderState {
+    #[default]
+    Idle,
+    Queued,
+    Running,
+}
+
+/// Run status of a report in the render queue
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RenderStatus {
+    pub state: RenderState,
+    /// 1-based position in the queue, 0 when not queued
+    pub position: usize,
+    /// Number of renders waiting for a slot
+    pub queue_depth: usize,
+    /// Number of renders currently running
+    pub running: usize,
+    /// Time the render was queued, in microseconds
+    pub enqueued_at: i64,
+    /// Actual start time when running, estimated start time when queued, in microseconds
+    pub estimated_start: i64,
+}
+
+/// Handed to the renderer so it can register the browser process to kill on timeout
+#[derive(Clone, Debug, Default)]
+pub struct RenderContext {
+    pid: Arc<Mutex<Option<u32>>>,
+}
+
+impl RenderContext {
+    pub fn set_pid(&self, pid: u32) {
+        *self.pid.lock() = Some(pid);
+    }
+
+    fn pid(&self) -> Option<u32> {
+        *self.pid.lock()
+    }
+}
+
+struct Queued {
+    id: u64,
+    key: String,
+    enqueued_at: i64,
+}
+
+struct Running {
+    key: String,
+    enqueued_at: i64,
+    started_at: i64,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    queue: VecDeque<Queued>,
+    running: HashMap<u64, Running>,
+    // moving average of completed render durations
+    avg_render: Option<Duration>,
+}
+
+struct Inner {
+    concurrency: usize,
+    max_depth: usize,
+    timeout: Duration,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+#[derive(Clone)]
+pub struct RenderQueue {
+    inner: Arc<Inner>,
+}
+
+impl RenderQueue {
+    /// Creates a queue running up to `concurrency` renders at once, with at
+    /// most `max_depth` renders waiting for a slot, `0` means unlimited.
+    pub fn new(concurrency: usize, max_depth: usize, timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                concurrency: concurrency.max(1),
+                max_depth,
+                timeout,
+                state: Mutex::new(State::default()),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Queues a render for `key` and runs it once a slot is free.
+    ///
+    /// The slot is released however the render ends, so a failing or
+    /// panicking render never shrinks the pool.
+    pub async fn run<T, F, Fut>(&self, key: &str, render: F) -> Result<T, RenderError>
+    where
+        F: FnOnce(RenderContext) -> Fut,
+        Fut: Future<Output = Result<T, anyhow::Error>>,
+    {
+        let ticket = self.enqueue(key)?;
+        let mut slot = self.acquire(ticket).await;
+        match tokio::time::timeout(self.inner.timeout, render(slot.ctx.clone())).await {
+            Ok(Ok(v)) => {
+                slot.finished = true;
+                Ok(v)
+            }
+            Ok(Err(e)) => {
+                slot.finished = true;
+                Err(RenderError::Failed(e))
+            }
+            Err(_) => {
+                log::error!(
+                    "[REPORT] render of {key} timed out after {}s, killing browser",
+                    self.inner.timeout.as_secs()
+                );
+                // dropping the slot kills the registered browser
+                Err(RenderError::Timeout(self.inner.timeout))
+            }
+        }
+    }
+
+    /// Returns the queue status of the oldest render for `key`
+    pub fn status(&self, key: &str) -> RenderStatus {
+        let state = self.inner.state.lock();
+        let queue_depth = state.queue.len();
+        let running = state.running.len();
+        if let Some(r) = state
+            .running
+            .values()
+            .filter(|r| r.key == key)
+            .min_by_key(|r| r.started_at)
+        {
+            return RenderStatus {
+                state: RenderState::Running,
+                position: 0,
+                queue_depth,
+                running,
+                enqueued_at: r.enqueued_at,
+                estimated_start: r.started_at,
+            };
+        }
+        match state.queue.iter().position(|q| q.key == key) {
+            Some(pos) => RenderStatus {
+                state: RenderState::Queued,
+                position: pos + 1,
+                queue_depth,
+                running,
+                enqueued_at: state.queue[pos].enqueued_at,
+                estimated_start: now_micros() + self.estimated_wait(&state, pos).as_micros() as i64,
+            },
+            None => RenderStatus {
+                queue_depth,
+                running,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn enqueue(&self, key: &str) -> Result<Ticket, RenderError> {
+        let mut state = self.inner.state.lock();
+        if self.inner.max_depth > 0 && state.queue.len() >= self.inner.max_depth {
+            return Err(RenderError::QueueFull(self.inner.max_depth));
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.queue.push_back(Queued {
+            id,
+            key: key.to_string(),
+            enqueued_at: now_micros(),
+        });
+        Ok(Ticket {
+            id,
+            inner: self.inner.clone(),
+        })
+    }
+
+    async fn acquire(&self, ticket: Ticket) -> Slot {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            {
+                let mut state = self.inner.state.lock();
+                if state.running.len() < self.inner.concurrency
+                    && state.queue.front().map(|q| q.id) == Some(ticket.id)
+                {
+                    let queued = state.queue.pop_front().unwrap();
+                    state.running.insert(
+                        ticket.id,
+                        Running {
+                            key: queued.key,
+                            enqueued_at: queued.enqueued_at,
+                            started_at: now_micros(),
+                            started: Instant::now(),
+                        },
+                    );
+                    // the next render in line may fit into a free slot as well
+                    self.inner.notify.notify_waiters();
+                    return Slot {
+                        id: ticket.id,
+                        inner: self.inner.clone(),
+                        ctx: RenderContext::default(),
+                        finished: false,
+                    };
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// Estimated time until the render at queue index `pos` gets a slot
+    fn estimated_wait(&self, state: &State, pos: usize) -> Duration {
+        let concurrency = self.inner.concurrency;
+        let free = concurrency.saturating_sub(state.running.len());
+        if pos < free {
+            return Duration::ZERO;
+        }
+        let avg = state.avg_render.unwrap_or(DEFAULT_RENDER_DURATION);
+        let mut remaining = state
+            .running
+            .values()
+            .map(|r| avg.saturating_sub(r.started.elapsed()))
+            .collect::<Vec<_>>();
+        remaining.sort();
+        // renders ahead of this one that still need a slot
+        let ahead = pos - free;
+        let first = remaining
+            .get(ahead % concurrency)
+            .copied()
+            .unwrap_or_default();
+        first + avg * (ahead / concurrency) as u32
+    }
+}
+
+/// A render waiting in the queue, removed again if the caller goes away
+struct Ticket {
+    id: u64,
+    inner: Arc<Inner>,
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock();
+        if let Some(pos) = state.queue.iter().position(|q| q.id == self.id) {
+            state.queue.remove(pos);
+            self.inner.notify.notify_waiters();
+        }
+    }
+}
+
+/// A running render holding one of the slots
+struct Slot {
+    id: u64,
+    inner: Arc<Inner>,
+    ctx: RenderContext,
+    finished: bool,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if !self.finished {
+            // timed out, cancelled or panicked, make sure chromium does not linger
+            if let Some(pid) = self.ctx.pid() {
+                kill_process_tree(pid);
+            }
+        }
+        let mut state = self.inner.state.lock();
+        if let Some(r) = state.running.remove(&self.id) {
+            let took = r.started.elapsed();
+            state.avg_render = Some(match state.avg_render {
+                Some(avg) => (avg * 3 + took) / 4,
+                None => took,
+            });
+        }
+        self.inner.notify.notify_waiters();
+    }
+}
+
+/// Kills `pid` together with every process it spawned.
+///
+/// Chromium forks renderer, gpu and zygote processes which survive the
+/// browser process, so killing only the browser leaves zombies behind.
+pub fn kill_process_tree(pid: u32) {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    let mut tree = vec![Pid::from_u32(pid)];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        for (child, process) in system.processes() {
+            if process.parent() == Some(parent) && !tree.contains(child) {
+                tree.push(*child);
+            }
+        }
+        i += 1;
+    }
+    for pid in tree {
+        if let Some(process) = system.process(pid) {
+            if process.kill_with(Signal::Kill) != Some(true) {
+                log::warn!("[REPORT] failed to kill render process {pid}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    async fn wait_until(queue: &RenderQueue, f: impl Fn(&State) -> bool) {
+        for _ in 0..500 {
+            if f(&queue.inner.state.lock()) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("render queue did not reach the expected state");
+    }
+
+    #[tokio::test]
+    async fn test_render_queue_concurrency_ceiling() {
+        let queue = RenderQueue::new(2, 100, Duration::from_secs(10));
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let mut tasks = vec![];
+        for i in 0..8 {
+            let queue = queue.clone();
+            let current = current.clone();
+            let peak = peak.clone();
+            tasks.push(tokio::spawn(async move {
+                queue
+                    .run(&format!("default/report{i}"), move |_| async move {
+                        let n = current.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(n, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(30)).await;
+                        current.fetch_sub(1, Ordering::SeqCst);
+                        Ok(i)
+                    })
+                    .await
+            }));
+        }
+        for (i, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap().unwrap(), i);
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(queue.status("default/report0").state, RenderState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_render_queue_fifo_ordering() {
+        let queue = RenderQueue::new(1, 3, Duration::from_secs(10));
+        let release = Arc::new(Notify::new());
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let blocker = {
+            let queue = queue.clone();
+            let release = release.clone();
+            tokio::spawn(async move {
+                queue
+                    .run("default/blocker", |_| async move {
+                        release.notified().await;
+                        Ok(())
+                    })
+                    .await
+            })
+        };
+        wait_until(&queue, |s| s.running.len() == 1).await;
+
+        let mut tasks = vec![];
+        for i in 1..=3 {
+            let queue_cloned = queue.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                queue_cloned
+                    .run(&format!("default/report{i}"), move |_| async move {
+                        order.lock().push(i);
+                        Ok(())
+                    })
+                    .await
+            }));
+            wait_until(&queue, |s| s.queue.len() == i).await;
+        }
+
+        let status = queue.status("default/report3");
+        assert_eq!(status.state, RenderState::Queued);
+        assert_eq!(status.position, 3);
+        assert_eq!(status.queue_depth, 3);
+        assert!(status.estimated_start >= status.enqueued_at);
+        assert!(
+            queue.status("default/report2").estimated_start
+                <= queue.status("default/report3").estimated_start
+        );
+        assert_eq!(queue.status("default/blocker").state, RenderState::Running);
+
+        // the queue is full
+        let ret = queue.run("default/report4", |_| async { Ok(()) }).await;
+        assert!(matches!(ret, Err(RenderError::QueueFull(3))));
+
+        release.notify_one();
+        blocker.await.unwrap().unwrap();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(*order.lock(), vec![1, 2, 3]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_render_queue_timeout_kills_process_tree() {
+        use std::io::BufRead;
+
+        let queue = RenderQueue::new(1, 10, Duration::from_millis(300));
+        let browser = Arc::new(Mutex::new(None));
+        let grandchild = Arc::new(Mutex::new(None));
+
+        let ret = {
+            let browser = browser.clone();
+            let grandchild = grandchild.clone();
+            queue
+                .run("default/slow", |ctx| async move {
+                    // stands in for chromium forking its helper processes
+                    let mut child = std::process::Command::new("sh")
+                        .args(["-c", "sleep 30 & echo $!; wait"])
+                        .stdout(std::process::Stdio::piped())
+                        .spawn()?;
+                    ctx.set_pid(child.id());
+                    let mut line = String::new();
+                    std::io::BufReader::new(child.stdout.take().unwrap()).read_line(&mut line)?;
+                    *grandchild.lock() = Some(line.trim().parse::<u32>()?);
+                    *browser.lock() = Some(child);
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    Ok(())
+                })
+                .await
+        };
+        assert!(matches!(ret, Err(RenderError::Timeout(_))));
+
+        let mut child = browser.lock().take().unwrap();
+        let mut exited = false;
+        for _ in 0..200 {
+            if child.try_wait().unwrap().is_some() {
+                exited = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(exited, "browser process was not killed");
+
+        let grandchild = Pid::from_u32(grandchild.lock().unwrap());
+        let mut system = sysinfo::System::new();
+        system.refresh_processes(ProcessesToUpdate::Some(&[grandchild]), true);
+        assert!(
+            system
+                .process(grandchild)
+                .is_none_or(|p| p.status() == sysinfo::ProcessStatus::Zombie),
+            "forked render process was not killed"
+        );
+
+        // the slot is free again
+        assert_eq!(queue.status("default/slow").state, RenderState::Idle);
+        queue
+            .run("default/next", |_| async { Ok(()) })
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_render_queue_max_depth() {
+        let queue = RenderQueue::new(1, 2, Duration::from_secs(10));
+        let _first = queue.enqueue("default/report1").unwrap();
+        let _second = queue.enqueue("default/report2").unwrap();
+        assert!(matches!(
+            queue.enqueue("default/report3"),
+            Err(RenderError::QueueFull(2))
+        ));
+
+        let unlimited = RenderQueue::new(1, 0, Duration::from_secs(10));
+        let tickets = (0..10)
+            .map(|i| unlimited.enqueue(&format!("default/report{i}")))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(unlimited.inner.state.lock().queue.len(), tickets.len());
+    }
+
+    #[tokio::test]
+    async fn test_render_queue_failure_does_not_poison_pool() {
+        let queue = RenderQueue::new(1, 10, Duration::from_secs(10));
+
+        let ret = queue
+            .run("default/failing", |_| async {
+                Err::<(), _>(anyhow::anyhow!("dashboard not found"))
+            })
+            .await;
+        assert!(matches!(ret, Err(RenderError::Failed(_))));
+
+        let panicking = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                queue
+                    .run(
+                        "default/panicking",
+                        |_| -> std::future::Ready<Result<(), anyhow::Error>> {
+                            panic!("renderer crashed")
+                        },
+                    )
+                    .await
+            })
+        };
+        assert!(panicking.await.is_err());
+
+        let ret = queue.run("default/healthy", |_| async { Ok(1) }).await;
+        assert_eq!(ret.unwrap(), 1);
+        let state = queue.inner.state.lock();
+        assert!(state.running.is_empty());
+        assert!(state.queue.is_empty());
+    }
+}
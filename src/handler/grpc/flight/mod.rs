@@ -29,11 +29,15 @@ use arrow_flight::{
     HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
 };
 use arrow_schema::Schema;
-use config::{meta::search::ScanStats, metrics};
+use config::{
+    meta::search::{ExecutionStats, ScanStats},
+    metrics,
+};
 use datafusion::{
     common::{DataFusionError, Result},
     execution::SendableRecordBatchStream,
-    physical_plan::{displayable, execute_stream},
+    physical_plan::{displayable, execute_stream, ExecutionPlan},
+    prelude::SessionContext,
 };
 use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
 #[cfg(feature = "enterprise")]
@@ -47,7 +51,13 @@ use crate::service::search::SEARCH_SERVER;
 use crate::{
     handler::grpc::MetadataMap,
     service::search::{
-        grpc::flight as grpcFlight, request::FlightSearchRequest, utils::AsyncDefer,
+        datafusion::{
+            peak_memory::get_peak_memory,
+            pruning::{collect_pruning_stats, report_pruning_metrics},
+        },
+        grpc::flight as grpcFlight,
+        request::FlightSearchRequest,
+        utils::AsyncDefer,
     },
 };
 
@@ -150,6 +160,12 @@ impl FlightService for FlightServiceImpl {
 
         schema = add_scan_stats_to_schema(schema, scan_stats);
 
+        let execution_stats = (
+            req.query_identifier.org_id.clone(),
+            ctx.clone(),
+            physical_plan.clone(),
+        );
+
         let start = std::time::Instant::now();
         let write_options: IpcWriteOptions = IpcWriteOptions::default()
             .try_with_compression(Some(CompressionType::ZSTD))
@@ -172,6 +188,7 @@ impl FlightService for FlightServiceImpl {
                 timeout,
             ))
             .map_err(|err| Status::from_error(Box::new(err)));
+        let flight_data_stream = with_execution_stats(flight_data_stream, execution_stats);
 
         Ok(Response::new(
             Box::pin(flight_data_stream) as Self::DoGetStream
@@ -363,6 +380,76 @@ async fn get_ctx_and_physical_plan(
     Ok((ctx, physical_plan, None, scan_stats))
 }
 
+/// The execution stats are only known once the plan was executed, so the last
+/// message of the response is held back and sent with them in its app_metadata.
+fn with_execution_stats<S>(
+    stream: S,
+    execution_stats: (String, SessionContext, Arc<dyn ExecutionPlan>),
+) -> impl Stream<Item = Result<FlightData, Status>> + Send + 'static
+where
+    S: Stream<Item = Result<FlightData, Status>> + Send + 'static,
+{
+    let state = (
+        Box::pin(stream.fuse()),
+        None::<FlightData>,
+        None::<Status>,
+        Some(execution_stats),
+    );
+    futures::stream::unfold(
+        state,
+        |(mut stream, mut last, mut err, mut execution_stats)| async move {
+            if let Some(e) = err.take() {
+                return Some((Err(e), (stream, last, err, execution_stats)));
+            }
+            loop {
+                match stream.next().await {
+                    Some(Ok(flight_data)) => {
+                        if let Some(prev) = last.replace(flight_data) {
+                            return Some((Ok(prev), (stream, last, err, execution_stats)));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        return match last.take() {
+                            Some(prev) => {
+                                Some((Ok(prev), (stream, last, Some(e), execution_stats)))
+                            }
+                            None => Some((Err(e), (stream, last, err, execution_stats))),
+                        };
+                    }
+                    None => {
+                        let mut flight_data = last.take()?;
+                        if let Some((org_id, ctx, plan)) = execution_stats.take() {
+                            if let Some(app_metadata) =
+                                execution_stats_metadata(&org_id, &ctx, plan.as_ref())
+                            {
+                                flight_data.app_metadata = app_metadata.into();
+                            }
+                        }
+                        return Some((Ok(flight_data), (stream, last, err, execution_stats)));
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// The row group pruning of the parquet files scanned for the response, which
+/// is also exported as metrics here, and the peak memory the query took.
+fn execution_stats_metadata(
+    org_id: &str,
+    ctx: &SessionContext,
+    plan: &dyn ExecutionPlan,
+) -> Option<Vec<u8>> {
+    let (scanned, mut stats) = collect_pruning_stats(plan);
+    report_pruning_metrics(org_id, &scanned);
+    stats.pruning.merge(&scanned);
+    stats.peak_memory += get_peak_memory(ctx);
+    if stats == ExecutionStats::default() {
+        return None;
+    }
+    serde_json::to_vec(&stats).ok()
+}
+
 fn add_scan_stats_to_schema(schema: Arc<Schema>, scan_stats: ScanStats) -> Arc<Schema> {
     let mut metadata = schema.metadata().clone();
     let stats_string = serde_json::to_string(&scan_stats).unwrap_or_default();
@@ -15,7 +15,6 @@
 
 use config::{
     cluster::LOCAL_NODE,
-    get_config,
     meta::{
         cluster::{Role, RoleGroup},
         stream::FileKey,
@@ -28,7 +27,10 @@ use proto::cluster_rpc::{event_server::Event, EmptyResponse, FileList};
 use tonic::{Request, Response, Status};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-use crate::{common::infra::cluster::get_node_from_consistent_hash, handler::grpc::MetadataMap};
+use crate::{
+    common::infra::cluster::get_node_from_consistent_hash, handler::grpc::MetadataMap,
+    service::search::datafusion::distributed_plan::streaming_aggs_exec,
+};
 
 pub struct Eventer;
 
@@ -50,11 +52,20 @@ impl Event for Eventer {
             .filter(|v| !v.deleted)
             .map(FileKey::from)
             .collect::<Vec<_>>();
-        let cfg = get_config();
+
+        // the partial aggregations of the added or deleted files are stale
+        if LOCAL_NODE.is_querier() {
+            let items = req.items.iter().map(FileKey::from).collect::<Vec<_>>();
+            streaming_aggs_exec::invalidate_files(&items);
+        }
 
         // cache latest files for querier
-        if cfg.memory_cache.cache_latest_files && LOCAL_NODE.is_querier() {
+        if LOCAL_NODE.is_querier() {
             for item in put_items.iter() {
+                // the stream settings can pin or opt out a stream
+                if !infra::cache::file_data::memory::cache_latest_files_by_key(&item.key).await {
+                    continue;
+                }
                 let Some(node_name) = get_node_from_consistent_hash(
                     &item.key,
                     &Role::Querier,
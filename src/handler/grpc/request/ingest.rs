@@ -122,6 +122,7 @@ impl Ingest for Ingester {
                     &org_id,
                     &stream_name,
                     json_records,
+                    &[],
                     append_data,
                 )
                 .await
@@ -44,6 +44,8 @@ impl QueryCache for QueryCacheServerImpl {
                 ts_column: req.timestamp_col,
                 discard_interval: req.discard_interval,
                 is_descending: req.is_descending,
+                is_count: req.is_count,
+                calendar_months: req.calendar_months,
             },
         )
         .await
@@ -73,7 +75,8 @@ impl QueryCache for QueryCacheServerImpl {
         request: Request<DeleteResultCacheRequest>,
     ) -> Result<Response<DeleteResultCacheResponse>, Status> {
         let req: DeleteResultCacheRequest = request.into_inner();
-        let deleted = cacher::delete_cache(&req.path).await.is_ok();
+        let time_range = req.start_time.zip(req.end_time);
+        let deleted = cacher::delete_cache(&req.path, time_range).await.is_ok();
 
         Ok(Response::new(DeleteResultCacheResponse { deleted }))
     }
@@ -93,6 +96,8 @@ impl QueryCache for QueryCacheServerImpl {
                 ts_column: req.timestamp_col,
                 discard_interval: req.discard_interval,
                 is_descending: req.is_descending,
+                is_count: req.is_count,
+                calendar_months: req.calendar_months,
             },
         )
         .await;
@@ -141,7 +141,6 @@ impl Search for Searcher {
             stream_type,
             req.user_id.clone(),
             &request,
-            "".to_string(),
         )
         .await;
 
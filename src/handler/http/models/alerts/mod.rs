@@ -52,6 +52,10 @@ pub struct Alert {
     #[serde(default)]
     pub trigger_condition: TriggerCondition,
 
+    #[serde(
+        alias = "destination",
+        deserialize_with = "meta_alerts::alert::deserialize_destinations"
+    )]
     pub destinations: Vec<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -92,9 +96,20 @@ pub struct Alert {
     #[serde(default)]
     #[schema(read_only)]
     pub last_edited_by: Option<String>,
+
+    /// Time until which notifications are suppressed. Unix timestamp in
+    /// microseconds. Updates without it keep the current silence, which is
+    /// removed through the silence endpoint.
+    #[serde(default)]
+    pub silence_until: Option<i64>,
+
+    /// Writes every state change of the alert, from pending to firing to
+    /// resolved, into the `alert_events` stream of the organization.
+    #[serde(default)]
+    pub emit_state_events: bool,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, ToSchema, PartialEq)]
 pub struct TriggerCondition {
     #[serde(rename = "period")]
     pub period_minutes: i64,
@@ -106,9 +121,10 @@ pub struct TriggerCondition {
     #[serde(default)]
     pub threshold_count: i64,
 
-    #[serde(rename = "frequency")]
+    /// How often the alert is evaluated, in seconds. The deprecated
+    /// `frequency` field, in minutes, is still accepted in requests.
     #[serde(default)]
-    pub frequency_minutes: i64,
+    pub frequency_seconds: i64,
 
     #[serde(default)]
     pub cron: String,
@@ -126,6 +142,98 @@ pub struct TriggerCondition {
     #[serde(rename = "tolerance_in_secs")]
     #[serde(default)]
     pub tolerance_seconds: Option<i64>,
+
+    /// Compares the query result against an earlier window instead of
+    /// `operator` and `threshold`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineCondition>,
+}
+
+/// The fields of [TriggerCondition] as they may appear in a request body.
+#[derive(Deserialize)]
+struct TriggerConditionFields {
+    #[serde(rename = "period")]
+    period_minutes: i64,
+    #[serde(default)]
+    operator: Operator,
+    #[serde(rename = "threshold")]
+    #[serde(default)]
+    threshold_count: i64,
+    #[serde(default)]
+    frequency_seconds: Option<i64>,
+    /// Deprecated, the frequency in minutes.
+    #[serde(rename = "frequency")]
+    #[serde(default)]
+    frequency_minutes: Option<i64>,
+    #[serde(default)]
+    cron: String,
+    #[serde(default)]
+    frequency_type: FrequencyType,
+    #[serde(rename = "silence")]
+    #[serde(default)]
+    silence_minutes: i64,
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(rename = "tolerance_in_secs")]
+    #[serde(default)]
+    tolerance_seconds: Option<i64>,
+    #[serde(default)]
+    baseline: Option<BaselineCondition>,
+}
+
+impl<'de> Deserialize<'de> for TriggerCondition {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = TriggerConditionFields::deserialize(deserializer)?;
+        let frequency_seconds = match (fields.frequency_seconds, fields.frequency_minutes) {
+            (Some(seconds), minutes) => {
+                if minutes.is_some() {
+                    log::warn!(
+                        "trigger_condition.frequency is deprecated and ignored when trigger_condition.frequency_seconds is set"
+                    );
+                }
+                seconds
+            }
+            (None, Some(minutes)) => {
+                log::warn!(
+                    "trigger_condition.frequency is deprecated, use trigger_condition.frequency_seconds instead"
+                );
+                minutes * 60
+            }
+            (None, None) => 0,
+        };
+        Ok(Self {
+            period_minutes: fields.period_minutes,
+            operator: fields.operator,
+            threshold_count: fields.threshold_count,
+            frequency_seconds,
+            cron: fields.cron,
+            frequency_type: fields.frequency_type,
+            silence_minutes: fields.silence_minutes,
+            timezone: fields.timezone,
+            tolerance_seconds: fields.tolerance_seconds,
+            baseline: fields.baseline,
+        })
+    }
+}
+
+/// Fires when the aggregate of the current window compares to `multiplier`
+/// times the aggregate of the same window `offset` ago.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct BaselineCondition {
+    /// How far back the baseline window is: `30m`, `1h`, `1d`, `1w`.
+    pub offset: String,
+
+    #[serde(default = "default_baseline_operator")]
+    pub operator: Operator,
+
+    pub multiplier: f64,
+
+    /// The aggregated column, the values of all the returned rows are summed.
+    pub column: String,
+}
+
+fn default_baseline_operator() -> Operator {
+    Operator::GreaterThan
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
@@ -204,6 +312,8 @@ pub enum QueryType {
     SQL,
     #[serde(rename = "promql")]
     PromQL,
+    #[serde(rename = "schema_drift")]
+    SchemaDrift,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -299,6 +409,8 @@ impl From<(meta_alerts::alert::Alert, Option<Trigger>)> for Alert {
             owner: alert.owner,
             updated_at: alert.updated_at.map(|t| t.timestamp()),
             last_edited_by: alert.last_edited_by,
+            silence_until: alert.silence_until,
+            emit_state_events: alert.emit_state_events,
         }
     }
 }
@@ -309,12 +421,24 @@ impl From<meta_alerts::TriggerCondition> for TriggerCondition {
             period_minutes: value.period,
             operator: value.operator.into(),
             threshold_count: value.threshold,
-            frequency_minutes: value.frequency / 60,
+            frequency_seconds: value.frequency,
             cron: value.cron,
             frequency_type: value.frequency_type.into(),
             silence_minutes: value.silence,
             timezone: value.timezone,
             tolerance_seconds: value.tolerance_in_secs,
+            baseline: value.baseline.map(|b| b.into()),
+        }
+    }
+}
+
+impl From<meta_alerts::BaselineCondition> for BaselineCondition {
+    fn from(value: meta_alerts::BaselineCondition) -> Self {
+        Self {
+            offset: value.offset,
+            operator: value.operator.into(),
+            multiplier: value.multiplier,
+            column: value.column,
         }
     }
 }
@@ -390,6 +514,7 @@ impl From<meta_alerts::QueryType> for QueryType {
             meta_alerts::QueryType::Custom => Self::Custom,
             meta_alerts::QueryType::SQL => Self::SQL,
             meta_alerts::QueryType::PromQL => Self::PromQL,
+            meta_alerts::QueryType::SchemaDrift => Self::SchemaDrift,
         }
     }
 }
@@ -471,6 +596,8 @@ impl From<Alert> for meta_alerts::alert::Alert {
         alert.enabled = value.enabled;
         alert.tz_offset = value.tz_offset;
         alert.owner = value.owner;
+        alert.silence_until = value.silence_until;
+        alert.emit_state_events = value.emit_state_events;
 
         alert
     }
@@ -482,12 +609,24 @@ impl From<TriggerCondition> for meta_alerts::TriggerCondition {
             period: value.period_minutes,
             operator: value.operator.into(),
             threshold: value.threshold_count,
-            frequency: value.frequency_minutes * 60,
+            frequency: value.frequency_seconds,
             cron: value.cron,
             frequency_type: value.frequency_type.into(),
             silence: value.silence_minutes,
             timezone: value.timezone,
             tolerance_in_secs: value.tolerance_seconds,
+            baseline: value.baseline.map(|b| b.into()),
+        }
+    }
+}
+
+impl From<BaselineCondition> for meta_alerts::BaselineCondition {
+    fn from(value: BaselineCondition) -> Self {
+        Self {
+            offset: value.offset,
+            operator: value.operator.into(),
+            multiplier: value.multiplier,
+            column: value.column,
         }
     }
 }
@@ -563,6 +702,7 @@ impl From<QueryType> for meta_alerts::QueryType {
             QueryType::Custom => Self::Custom,
             QueryType::SQL => Self::SQL,
             QueryType::PromQL => Self::PromQL,
+            QueryType::SchemaDrift => Self::SchemaDrift,
         }
     }
 }
@@ -622,3 +762,62 @@ impl From<StreamType> for meta_stream::StreamType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_condition_frequency_seconds_round_trip() {
+        let trigger_condition: TriggerCondition =
+            serde_json::from_str(r#"{"period":10,"frequency_seconds":300}"#).unwrap();
+        assert_eq!(trigger_condition.frequency_seconds, 300);
+
+        let value = serde_json::to_value(&trigger_condition).unwrap();
+        assert_eq!(value["frequency_seconds"], 300);
+        assert!(value.get("frequency").is_none());
+        let round_trip: TriggerCondition = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, trigger_condition);
+
+        let meta: meta_alerts::TriggerCondition = round_trip.into();
+        assert_eq!(meta.frequency, 300);
+        assert_eq!(TriggerCondition::from(meta), trigger_condition);
+    }
+
+    #[test]
+    fn test_trigger_condition_deprecated_frequency() {
+        // the deprecated field is in minutes
+        let trigger_condition: TriggerCondition =
+            serde_json::from_str(r#"{"period":10,"frequency":5}"#).unwrap();
+        assert_eq!(trigger_condition.frequency_seconds, 300);
+        let value = serde_json::to_value(&trigger_condition).unwrap();
+        assert_eq!(value["frequency_seconds"], 300);
+
+        // the new field wins when both are set
+        let trigger_condition: TriggerCondition =
+            serde_json::from_str(r#"{"period":10,"frequency":5,"frequency_seconds":120}"#).unwrap();
+        assert_eq!(trigger_condition.frequency_seconds, 120);
+
+        let trigger_condition: TriggerCondition = serde_json::from_str(r#"{"period":10}"#).unwrap();
+        assert_eq!(trigger_condition.frequency_seconds, 0);
+    }
+
+    #[test]
+    fn test_trigger_condition_baseline_round_trip() {
+        let trigger_condition: TriggerCondition = serde_json::from_str(
+            r#"{"period":10,"baseline":{"offset":"1h","multiplier":2.0,"column":"alert_agg_value"}}"#,
+        )
+        .unwrap();
+        let baseline = trigger_condition.baseline.as_ref().unwrap();
+        assert_eq!(baseline.operator, Operator::GreaterThan);
+        assert_eq!(baseline.multiplier, 2.0);
+
+        let meta: meta_alerts::TriggerCondition = trigger_condition.clone().into();
+        assert_eq!(meta.baseline.as_ref().unwrap().offset, "1h");
+        assert_eq!(TriggerCondition::from(meta), trigger_condition);
+
+        let trigger_condition: TriggerCondition = serde_json::from_str(r#"{"period":10}"#).unwrap();
+        let value = serde_json::to_value(&trigger_condition).unwrap();
+        assert!(value.get("baseline").is_none());
+    }
+}
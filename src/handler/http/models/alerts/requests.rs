@@ -90,6 +90,24 @@ pub struct EnableAlertQuery {
     pub value: bool,
 }
 
+/// HTTP request body for `EnableAlerts` endpoint.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct EnableAlertsRequestBody {
+    /// IDs of the alerts to enable or disable.
+    pub alert_ids: Vec<Ksuid>,
+
+    /// Set to `true` to enable the alerts or `false` to disable the alerts.
+    pub value: bool,
+}
+
+/// HTTP request body for `SilenceAlert` endpoint.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct SilenceAlertRequestBody {
+    /// Time until which notifications are suppressed. Unix timestamp in
+    /// microseconds. Set to `null` to remove the silence.
+    pub silence_until: Option<i64>,
+}
+
 impl From<CreateAlertRequestBody> for meta_alerts::Alert {
     fn from(value: CreateAlertRequestBody) -> Self {
         value.alert.into()
@@ -19,6 +19,7 @@ use svix_ksuid::Ksuid;
 use utoipa::ToSchema;
 
 use super::{Alert, QueryCondition};
+use crate::service::alerts::alert::DestinationSendResult;
 
 /// HTTP response body for `GetAlert` endpoint.
 #[derive(Clone, Debug, Serialize, ToSchema)]
@@ -51,6 +52,67 @@ pub struct EnableAlertResponseBody {
     pub enabled: bool,
 }
 
+/// HTTP response body for `EnableAlerts` endpoint.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct EnableAlertsResponseBody {
+    pub enabled: bool,
+
+    /// IDs of the alerts whose enabled state changed.
+    pub changed: Vec<Ksuid>,
+
+    /// IDs of the alerts that were already in the requested state.
+    pub unchanged: Vec<Ksuid>,
+
+    /// Alerts that could not be updated.
+    pub failed: Vec<EnableAlertsFailure>,
+}
+
+/// An alert that could not be updated by the `EnableAlerts` endpoint.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct EnableAlertsFailure {
+    pub alert_id: Ksuid,
+    pub error: String,
+}
+
+/// HTTP response body for `TriggerAlert` endpoint when only some of the alert
+/// destinations could be notified.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct TriggerAlertResponseBody {
+    pub code: u16,
+    pub message: String,
+    pub results: Vec<TriggerAlertDestinationResult>,
+}
+
+/// The outcome of sending the notification to one destination of a triggered alert.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct TriggerAlertDestinationResult {
+    pub destination: String,
+    pub success: bool,
+
+    /// The response of the destination, or why the notification could not be sent.
+    pub message: String,
+}
+
+impl From<DestinationSendResult> for TriggerAlertDestinationResult {
+    fn from(value: DestinationSendResult) -> Self {
+        let (success, message) = match value.result {
+            Ok(resp) => (true, resp),
+            Err(e) => (false, e),
+        };
+        Self {
+            destination: value.destination,
+            success,
+            message,
+        }
+    }
+}
+
+/// HTTP response body for `SilenceAlert` endpoint.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct SilenceAlertResponseBody {
+    pub silence_until: Option<i64>,
+}
+
 impl From<(meta_alerts::Alert, Option<Trigger>)> for GetAlertResponseBody {
     fn from(value: (meta_alerts::Alert, Option<Trigger>)) -> Self {
         Self(value.into())
@@ -1,12 +1,18 @@
 use chrono::{DateTime, FixedOffset, Utc};
 use config::meta::{
-    dashboards::{v1, v2, v3, v4, v5, Dashboard as MetaDashboard},
+    dashboards::{v1, v2, v3, v4, v5, v6, Dashboard as MetaDashboard, ListDashboardsSortBy},
     folder::Folder as MetaFolder,
+    stream::StreamType,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use utoipa::ToSchema;
 
+use crate::service::dashboards::{
+    validate::{PanelQuery, QueryIssue, QueryValidation},
+    DashboardConflict, DashboardImportResult,
+};
+
 /// HTTP request body for the `CreateDashboard` endpoint.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDashboardRequestBody(JsonValue);
@@ -48,15 +54,26 @@ pub struct ListDashboardsQuery {
     /// The optional number of dashboards to retrieve. If not set then all
     /// dashboards that match the query parameters will be returned.
     ///
-    /// Currently this parameter is only untilized by the API when the `title`
-    /// parameter is also set.
+    /// Currently this parameter is only untilized by the API when either the
+    /// `title` or the `pageIdx` parameter is also set.
     page_size: Option<u64>,
+
+    /// The optional zero-based index of the page of dashboards to retrieve,
+    /// defaults to the first page. Only used when `pageSize` is set.
+    page_idx: Option<u64>,
+
+    /// The optional order in which to list dashboards, defaults to
+    /// `title_asc`.
+    sort_by: Option<ListDashboardsSortBy>,
 }
 
 /// HTTP response body for `ListDashboards` endpoint.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ListDashboardsResponseBody {
     pub dashboards: Vec<ListDashboardsResponseBodyItem>,
+    /// The total number of dashboards that match the query parameters across
+    /// all pages.
+    pub total: u64,
 }
 
 /// An item in the list returned by the `ListDashboards` endpoint.
@@ -72,6 +89,8 @@ pub struct ListDashboardsResponseBodyItem {
     pub v4: Option<v4::Dashboard>,
     #[deprecated(note = "use GetDashboard endpoint to get dashboard details")]
     pub v5: Option<v5::Dashboard>,
+    #[deprecated(note = "use GetDashboard endpoint to get dashboard details")]
+    pub v6: Option<v6::Dashboard>,
 
     pub version: i32,
     pub hash: String,
@@ -90,6 +109,87 @@ pub struct ListDashboardsResponseBodyItem {
     pub updated_at: i64,
 }
 
+/// Dashboard version of the exports which don't ask for one. The versions
+/// without v6 only import v5 dashboards.
+pub const DEFAULT_EXPORT_VERSION: i32 = 5;
+
+/// HTTP URL query component that contains parameters for exporting a
+/// dashboard.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct ExportDashboardQuery {
+    /// Dashboard version of the export, 5 by default or 6.
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+/// HTTP response body for `ExportDashboard` endpoint.
+///
+/// Contains the dashboard converted to the requested version along with the
+/// hash of the stored dashboard, so that the exported JSON can be imported
+/// again to overwrite the stored dashboard.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExportDashboardResponseBody {
+    #[serde(flatten)]
+    pub dashboard: ExportedDashboard,
+    pub hash: String,
+}
+
+/// A dashboard in one of the versions it can be exported in.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum ExportedDashboard {
+    V5(v5::Dashboard),
+    V6(v6::Dashboard),
+}
+
+/// HTTP request body for `ImportDashboards` endpoint.
+///
+/// Each item is a dashboard JSON document of any supported version.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ImportDashboardsRequestBody(pub Vec<JsonValue>);
+
+/// HTTP URL query component that contains parameters for importing
+/// dashboards.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct ImportDashboardsQuery {
+    /// Overwrite existing dashboards with a matching dashboard ID instead of
+    /// creating new dashboards with new IDs.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// HTTP response body for `ImportDashboards` endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportDashboardsResponseBody {
+    pub dashboards: Vec<ImportDashboardsResponseBodyItem>,
+}
+
+/// The import report for a single dashboard in the `ImportDashboards`
+/// request body, in the same order as the request body.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportDashboardsResponseBodyItem {
+    pub status: ImportDashboardStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dashboard_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The outcome of importing a single dashboard.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportDashboardStatus {
+    Created,
+    Updated,
+    Failed,
+}
+
 /// HTTP request body for `MoveDashboard` endpoint.
 #[derive(Debug, Clone, PartialEq, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -98,6 +198,66 @@ pub struct MoveDashboardRequestBody {
     pub to: String,
 }
 
+/// HTTP request body for `DuplicateDashboard` endpoint.
+#[derive(Debug, Clone, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateDashboardRequestBody {
+    /// Title of the copy.
+    pub title: String,
+    /// Folder to put the copy in, defaults to the folder of the original.
+    #[serde(default)]
+    pub folder: Option<String>,
+}
+
+/// HTTP request body for `ValidateDashboardQueries` endpoint.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ValidateDashboardQueriesRequestBody {
+    pub queries: Vec<ValidateDashboardQueriesRequestBodyItem>,
+}
+
+/// A panel query to validate.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ValidateDashboardQueriesRequestBodyItem {
+    pub sql: String,
+    /// The type of the streams without a stream type qualifier, defaults to
+    /// `logs`.
+    #[serde(default)]
+    pub stream_type: StreamType,
+    /// The VRL function applied to the query results.
+    #[serde(default)]
+    pub vrl: Option<String>,
+}
+
+/// HTTP response body for `ValidateDashboardQueries` endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidateDashboardQueriesResponseBody {
+    pub results: Vec<ValidateDashboardQueriesResponseBodyItem>,
+}
+
+/// The validation result of a query in the `ValidateDashboardQueries` request
+/// body, in the same order as the request body.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidateDashboardQueriesResponseBodyItem {
+    pub valid: bool,
+    pub errors: Vec<QueryIssue>,
+    pub warnings: Vec<QueryIssue>,
+}
+
+/// HTTP response body for an `UpdateDashboard` request whose hash conflicts
+/// with a concurrent change to the dashboard.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDashboardConflictResponseBody {
+    pub code: u16,
+    pub message: String,
+    /// The current hash of the stored dashboard, to retry the update with.
+    pub hash: String,
+    /// When the stored dashboard was last updated, in microseconds.
+    pub updated_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
 /// Version-specific dashboard details and hash.
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -107,6 +267,7 @@ pub struct DashboardDetails {
     pub v3: Option<v3::Dashboard>,
     pub v4: Option<v4::Dashboard>,
     pub v5: Option<v5::Dashboard>,
+    pub v6: Option<v6::Dashboard>,
     pub version: i32,
     pub hash: String,
     pub updated_at: i64,
@@ -148,6 +309,63 @@ impl From<MetaDashboard> for UpdateDashboardResponseBody {
     }
 }
 
+impl From<DashboardConflict> for UpdateDashboardConflictResponseBody {
+    fn from(value: DashboardConflict) -> Self {
+        Self {
+            code: actix_web::http::StatusCode::CONFLICT.as_u16(),
+            message: "Conflict: Failed to save due to concurrent changes. Please refresh the page after backing up your work to avoid losing changes.".to_string(),
+            hash: value.hash,
+            updated_at: value.updated_at,
+            owner: value.owner,
+        }
+    }
+}
+
+impl ExportDashboardResponseBody {
+    /// Returns the export of the latest version dashboard in `version`, or
+    /// `None` if the dashboard can't be exported in it.
+    pub fn new(dashboard: v6::Dashboard, hash: String, version: Option<i32>) -> Option<Self> {
+        let dashboard = match version.unwrap_or(DEFAULT_EXPORT_VERSION) {
+            5 => ExportedDashboard::V5(dashboard.into()),
+            6 => ExportedDashboard::V6(dashboard),
+            _ => return None,
+        };
+        Some(Self { dashboard, hash })
+    }
+}
+
+impl From<Vec<DashboardImportResult>> for ImportDashboardsResponseBody {
+    fn from(value: Vec<DashboardImportResult>) -> Self {
+        let dashboards = value.into_iter().map(|r| r.into()).collect();
+        Self { dashboards }
+    }
+}
+
+impl From<DashboardImportResult> for ImportDashboardsResponseBodyItem {
+    fn from(value: DashboardImportResult) -> Self {
+        let (status, dashboard) = match value {
+            DashboardImportResult::Created(d) => (ImportDashboardStatus::Created, d),
+            DashboardImportResult::Updated(d) => (ImportDashboardStatus::Updated, d),
+            DashboardImportResult::Failed { title, error } => {
+                return Self {
+                    status: ImportDashboardStatus::Failed,
+                    dashboard_id: None,
+                    title,
+                    hash: None,
+                    reason: Some(error.to_string()),
+                };
+            }
+        };
+        Self {
+            status,
+            dashboard_id: dashboard.dashboard_id().map(|id| id.to_owned()),
+            title: dashboard.title().map(|t| t.to_owned()),
+            hash: Some(dashboard.hash),
+            reason: None,
+        }
+    }
+}
+
 impl ListDashboardsQuery {
     pub fn into(self, org_id: &str) -> config::meta::dashboards::ListDashboardsParams {
         let mut query = match &self {
@@ -182,26 +400,30 @@ impl ListDashboardsQuery {
             }
         };
 
-        // The API currently only supports using page_size to limit the output
-        // to the top results. And the page_size parameter is only used when the
-        // title parameter is provided to search dashboards by title pattern.
-        // When the title parameter is not set we simply want to return all
-        // dashboards that match the selected folder so we ignore the page_size
-        // parameter.
-        if self.title.is_some_and(|t| !t.is_empty()) {
+        // The page_size parameter is used when the title parameter is provided
+        // to search dashboards by title pattern, or when a page is explicitly
+        // requested with the page_idx parameter. Otherwise we simply want to
+        // return all dashboards that match the selected folder so we ignore the
+        // page_size parameter to preserve backwards-compatability.
+        if self.title.is_some_and(|t| !t.is_empty()) || self.page_idx.is_some() {
             if let Some(page_size) = self.page_size {
-                query = query.paginate(page_size, 0)
+                query = query.paginate(page_size, self.page_idx.unwrap_or_default())
             }
         }
 
+        if let Some(sort_by) = self.sort_by {
+            query = query.sort_by(sort_by);
+        }
+
         query
     }
 }
 
-impl From<Vec<(MetaFolder, MetaDashboard)>> for ListDashboardsResponseBody {
-    fn from(value: Vec<(MetaFolder, MetaDashboard)>) -> Self {
-        let dashboards = value.into_iter().map(|fd| fd.into()).collect();
-        Self { dashboards }
+impl From<(Vec<(MetaFolder, MetaDashboard)>, u64)> for ListDashboardsResponseBody {
+    fn from(value: (Vec<(MetaFolder, MetaDashboard)>, u64)) -> Self {
+        let (dashboards, total) = value;
+        let dashboards = dashboards.into_iter().map(|fd| fd.into()).collect();
+        Self { dashboards, total }
     }
 }
 
@@ -233,6 +455,7 @@ impl From<(MetaFolder, MetaDashboard)> for ListDashboardsResponseBodyItem {
             v3: dashboard.v3,
             v4: dashboard.v4,
             v5: dashboard.v5,
+            v6: dashboard.v6,
         }
     }
 }
@@ -246,6 +469,7 @@ impl From<MetaDashboard> for DashboardDetails {
             v3: value.v3,
             v4: value.v4,
             v5: value.v5,
+            v6: value.v6,
             hash: value.hash,
             updated_at: value.updated_at,
         }
@@ -279,10 +503,126 @@ fn parse_dashboard_request(value: JsonValue) -> Result<MetaDashboard, serde_json
             let inner: v4::Dashboard = serde_json::from_value(value)?;
             inner.into()
         }
-        _ => {
+        5 => {
             let inner: v5::Dashboard = serde_json::from_value(value)?;
             inner.into()
         }
+        6 => {
+            let inner: v6::Dashboard = serde_json::from_value(value)?;
+            inner.into()
+        }
+        v => {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported dashboard version: {v}"
+            )));
+        }
     };
     Ok(dash)
 }
+
+impl From<ValidateDashboardQueriesRequestBodyItem> for PanelQuery {
+    fn from(value: ValidateDashboardQueriesRequestBodyItem) -> Self {
+        Self {
+            sql: value.sql,
+            stream_type: value.stream_type,
+            vrl: value.vrl,
+        }
+    }
+}
+
+impl From<Vec<QueryValidation>> for ValidateDashboardQueriesResponseBody {
+    fn from(value: Vec<QueryValidation>) -> Self {
+        let results = value.into_iter().map(|r| r.into()).collect();
+        Self { results }
+    }
+}
+
+impl From<QueryValidation> for ValidateDashboardQueriesResponseBodyItem {
+    fn from(value: QueryValidation) -> Self {
+        Self {
+            valid: value.is_valid(),
+            errors: value.errors,
+            warnings: value.warnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_query(
+        title: Option<&str>,
+        page_size: Option<u64>,
+        page_idx: Option<u64>,
+    ) -> ListDashboardsQuery {
+        ListDashboardsQuery {
+            folder: None,
+            title: title.map(str::to_string),
+            page_size,
+            page_idx,
+            sort_by: None,
+        }
+    }
+
+    #[test]
+    fn test_list_dashboards_query_pagination() {
+        // without a title or page index all dashboards of the folder are listed
+        let params = list_query(None, Some(10), None).into("org");
+        assert_eq!(params.folder_id.as_deref(), Some("default"));
+        assert_eq!(params.page_size_and_idx, None);
+
+        // searching by title returns the first page by default
+        let params = list_query(Some("cpu"), Some(10), None).into("org");
+        assert_eq!(params.page_size_and_idx, Some((10, 0)));
+
+        // explicitly requested pages
+        let params = list_query(None, Some(10), Some(0)).into("org");
+        assert_eq!(params.page_size_and_idx, Some((10, 0)));
+        let params = list_query(Some("cpu"), Some(10), Some(3)).into("org");
+        assert_eq!(params.page_size_and_idx, Some((10, 3)));
+
+        // a page index without a page size lists everything
+        let params = list_query(None, None, Some(3)).into("org");
+        assert_eq!(params.page_size_and_idx, None);
+    }
+
+    #[test]
+    fn test_list_dashboards_query_sort_by() {
+        let params = list_query(None, None, None).into("org");
+        assert_eq!(params.sort_by, ListDashboardsSortBy::TitleAsc);
+
+        let query: ListDashboardsQuery =
+            serde_json::from_value(serde_json::json!({ "sortBy": "updated_at_desc" })).unwrap();
+        let params = query.into("org");
+        assert_eq!(params.sort_by, ListDashboardsSortBy::UpdatedAtDesc);
+    }
+
+    #[test]
+    fn test_export_dashboard_version() {
+        let dashboard: v6::Dashboard = serde_json::from_value(serde_json::json!({
+            "version": 6,
+            "dashboardId": "d1",
+            "title": "cpu",
+            "description": "",
+        }))
+        .unwrap();
+        let export = |version| {
+            ExportDashboardResponseBody::new(dashboard.clone(), "42".to_string(), version)
+                .map(|body| serde_json::to_value(body).unwrap())
+        };
+
+        // older versions only import v5 exports
+        let v5 = export(None).unwrap();
+        assert_eq!(v5["version"], 5);
+        assert_eq!(v5["dashboardId"], "d1");
+        assert_eq!(v5["hash"], "42");
+        assert_eq!(export(Some(5)).unwrap(), v5);
+
+        let v6 = export(Some(6)).unwrap();
+        assert_eq!(v6["version"], 6);
+        assert_eq!(v6["title"], "cpu");
+        assert!(export(Some(4)).is_none());
+        assert!(export(Some(7)).is_none());
+    }
+}
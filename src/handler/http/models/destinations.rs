@@ -25,7 +25,9 @@ use o2_enterprise::enterprise::actions::action_manager::ActionEndpoint;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::service::db::alerts::destinations::DestinationError;
+use crate::service::{
+    alerts::destinations::redact_proxy_url, db::alerts::destinations::DestinationError,
+};
 
 impl From<meta_dest::Destination> for Destination {
     fn from(value: meta_dest::Destination) -> Self {
@@ -47,6 +49,8 @@ impl From<meta_dest::Destination> for Destination {
                     method: endpoint.method,
                     skip_tls_verify: endpoint.skip_tls_verify,
                     headers: endpoint.headers,
+                    ca_cert_pem: endpoint.ca_cert_pem,
+                    proxy_url: endpoint.proxy_url.as_deref().map(redact_proxy_url),
                     destination_type: DestinationType::Http,
                     template: Some(template),
                     ..Default::default()
@@ -66,6 +70,8 @@ impl From<meta_dest::Destination> for Destination {
                 method: endpoint.method,
                 skip_tls_verify: endpoint.skip_tls_verify,
                 headers: endpoint.headers,
+                ca_cert_pem: endpoint.ca_cert_pem,
+                proxy_url: endpoint.proxy_url.as_deref().map(redact_proxy_url),
                 destination_type: DestinationType::Http,
                 ..Default::default()
             },
@@ -87,6 +93,8 @@ impl Destination {
                             method: self.method,
                             skip_tls_verify: self.skip_tls_verify,
                             headers: self.headers,
+                            ca_cert_pem: self.ca_cert_pem,
+                            proxy_url: self.proxy_url,
                         })
                     }
                     DestinationType::Sns => meta_dest::DestinationType::Sns(meta_dest::AwsSns {
@@ -106,6 +114,8 @@ impl Destination {
                             },
                             skip_tls_verify: action_endpoint.skip_tls,
                             headers: None,
+                            ca_cert_pem: None,
+                            proxy_url: None,
                         })
                     }
                 };
@@ -125,6 +135,8 @@ impl Destination {
                     method: self.method,
                     skip_tls_verify: self.skip_tls_verify,
                     headers: self.headers,
+                    ca_cert_pem: self.ca_cert_pem,
+                    proxy_url: self.proxy_url,
                 };
                 Ok(meta_dest::Destination {
                     id: None,
@@ -189,6 +201,13 @@ pub struct Destination {
     pub skip_tls_verify: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+    /// PEM encoded CA certificates trusted for the `url` in addition to the system roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_pem: Option<String>,
+    /// Proxy used to reach the `url`, `http://` or `https://`. Credentials in the
+    /// URL are redacted in responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub template: Option<String>,
     /// Required when `destination_type` is `Email`
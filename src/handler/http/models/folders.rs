@@ -45,6 +45,39 @@ pub struct ListFoldersResponseBody {
     pub list: Vec<Folder>,
 }
 
+/// HTTP URL query component that contains parameters for deleting folders.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct DeleteFolderQuery {
+    /// Delete the folder together with all of its contents.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// HTTP response body for `GetFolderContents` and `DeleteFolder` endpoints.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FolderContentsResponseBody {
+    pub dashboards: FolderContentItems,
+    pub alerts: FolderContentItems,
+    pub reports: FolderContentItems,
+}
+
+/// The objects of one type that are contained in a folder.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FolderContentItems {
+    pub count: usize,
+    pub ids: Vec<String>,
+}
+
+/// HTTP response body for `DeleteFolder` endpoint when the folder is not empty
+/// or some of its contents can't be deleted by the user.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FolderContentsErrorResponseBody {
+    pub code: u16,
+    pub message: String,
+    pub contents: FolderContentsResponseBody,
+}
+
 /// Indicates the type of data that the folder can contain.
 #[derive(Clone, Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
@@ -127,3 +160,46 @@ impl From<Folder> for config::meta::folder::Folder {
         }
     }
 }
+
+impl From<config::meta::folder::FolderContents> for FolderContentsResponseBody {
+    fn from(value: config::meta::folder::FolderContents) -> Self {
+        Self {
+            dashboards: value.dashboards.into(),
+            alerts: value.alerts.into(),
+            reports: value.reports.into(),
+        }
+    }
+}
+
+impl From<Vec<String>> for FolderContentItems {
+    fn from(ids: Vec<String>) -> Self {
+        Self {
+            count: ids.len(),
+            ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_contents_response_body() {
+        let contents = config::meta::folder::FolderContents {
+            dashboards: vec!["dash1".to_string(), "dash2".to_string()],
+            alerts: vec![],
+            reports: vec!["report1".to_string()],
+        };
+        let body: FolderContentsResponseBody = contents.into();
+        let json = serde_json::to_value(body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "dashboards": { "count": 2, "ids": ["dash1", "dash2"] },
+                "alerts": { "count": 0, "ids": [] },
+                "reports": { "count": 1, "ids": ["report1"] },
+            })
+        );
+    }
+}
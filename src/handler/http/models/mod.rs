@@ -21,3 +21,6 @@ pub mod alerts;
 pub mod dashboards;
 pub mod destinations;
 pub mod folders;
+pub mod retention;
+pub mod trash;
+pub mod values;
@@ -0,0 +1,41 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! These models define the schemas of HTTP request and response JSON bodies in
+//! retention API endpoints.
+
+use serde::Deserialize;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// HTTP URL query component of the `RetentionPreview` endpoint.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct RetentionPreviewQuery {
+    /// The proposed org retention in days, defaults to the current retention.
+    pub days: Option<i64>,
+    /// Optional stream name filter.
+    pub stream: Option<String>,
+    /// The number of streams per page, defaults to 100.
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    /// The zero based index of the page.
+    #[serde(default)]
+    pub page_idx: usize,
+}
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
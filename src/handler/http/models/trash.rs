@@ -0,0 +1,120 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! These models define the schemas of HTTP request and response JSON bodies in
+//! trash API endpoints.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// HTTP URL query component that contains parameters for listing and
+/// restoring trashed items.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(style = Form, parameter_in = Query)]
+pub struct TrashQuery {
+    /// Optional item type filter parameter.
+    #[serde(rename = "type")]
+    pub item_type: Option<TrashItemType>,
+}
+
+/// The type of a trashed item.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashItemType {
+    Dashboard,
+    Alert,
+}
+
+/// HTTP response body for `ListTrash` endpoint.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ListTrashResponseBody {
+    pub list: Vec<TrashItem>,
+}
+
+/// A dashboard or alert in the trash.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    #[serde(rename = "type")]
+    pub item_type: TrashItemType,
+
+    /// The dashboard ID of a dashboard or the ID of an alert.
+    pub id: String,
+
+    /// The title of a dashboard or the name of an alert.
+    pub name: String,
+
+    /// The folder that the item will be restored to.
+    pub folder_id: String,
+    pub folder_name: String,
+
+    /// Unix timestamp in microseconds when the item was moved to the trash.
+    pub deleted_at: i64,
+    pub deleted_by: Option<String>,
+
+    /// Unix timestamp in microseconds after which the item is purged.
+    pub expires_at: i64,
+}
+
+/// HTTP response body for `RestoreTrashItem` endpoint.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreTrashItemResponseBody {
+    #[serde(rename = "type")]
+    pub item_type: TrashItemType,
+    pub id: String,
+
+    /// The folder that the item was restored to.
+    pub folder_id: String,
+}
+
+impl ListTrashResponseBody {
+    /// Converts the trashed items into the response body, using
+    /// `expires_after` microseconds as the retention period of the trash.
+    pub fn new(items: Vec<config::meta::trash::TrashItem>, expires_after: i64) -> Self {
+        let list = items
+            .into_iter()
+            .map(|item| TrashItem {
+                item_type: item.item_type.into(),
+                expires_at: item.deleted_at + expires_after,
+                id: item.id,
+                name: item.name,
+                folder_id: item.folder_id,
+                folder_name: item.folder_name,
+                deleted_at: item.deleted_at,
+                deleted_by: item.deleted_by,
+            })
+            .collect();
+        Self { list }
+    }
+}
+
+impl From<TrashItemType> for config::meta::trash::TrashItemType {
+    fn from(value: TrashItemType) -> Self {
+        match value {
+            TrashItemType::Dashboard => Self::Dashboard,
+            TrashItemType::Alert => Self::Alert,
+        }
+    }
+}
+
+impl From<config::meta::trash::TrashItemType> for TrashItemType {
+    fn from(value: config::meta::trash::TrashItemType) -> Self {
+        match value {
+            config::meta::trash::TrashItemType::Dashboard => Self::Dashboard,
+            config::meta::trash::TrashItemType::Alert => Self::Alert,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! These models define the schemas of HTTP request and response JSON bodies in
+//! the batched values API endpoint.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const DEFAULT_SIZE: usize = 10;
+
+/// HTTP request body for the `SearchValuesBatch` endpoint.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct BatchValuesRequest {
+    /// The fields to fetch the top values of.
+    pub fields: Vec<BatchValuesField>,
+    /// Start of the time range in microseconds.
+    pub start_time: i64,
+    /// End of the time range in microseconds.
+    pub end_time: i64,
+    /// Optional SQL condition shared by all fields, e.g. `level = 'error'`.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Optional keyword the values of every field have to contain.
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// Number of values per field when not set on the field, defaults to 10.
+    #[serde(default = "default_size")]
+    pub size: usize,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub clusters: Vec<String>,
+    /// Timeout in seconds.
+    #[serde(default)]
+    pub timeout: i64,
+}
+
+fn default_size() -> usize {
+    DEFAULT_SIZE
+}
+
+/// A field of a batched values request.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct BatchValuesField {
+    pub name: String,
+    /// Number of values of the field, overrides the size of the request.
+    #[serde(default)]
+    pub size: Option<usize>,
+}
+
+/// HTTP response body for the `SearchValuesBatch` endpoint.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct BatchValuesResponse {
+    pub took: usize,
+    /// The top values of every requested field.
+    pub values: BTreeMap<String, FieldValues>,
+    pub scan_size: usize,
+    pub scan_records: usize,
+}
+
+/// The top values of a field, or the error that kept them from being fetched.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct FieldValues {
+    pub values: Vec<FieldValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A value of a field and the number of records it occurs in.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct FieldValue {
+    pub value: String,
+    pub count: i64,
+}
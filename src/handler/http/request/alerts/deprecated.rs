@@ -354,11 +354,23 @@ async fn get_alert(path: web::Path<(String, String, String)>, req: HttpRequest)
     )
 )]
 #[delete("/{org_id}/{stream_name}/alerts/{alert_name}")]
-async fn delete_alert(path: web::Path<(String, String, String)>, req: HttpRequest) -> HttpResponse {
+async fn delete_alert(
+    path: web::Path<(String, String, String)>,
+    user_email: UserEmail,
+    req: HttpRequest,
+) -> HttpResponse {
     let (org_id, stream_name, name) = path.into_inner();
     let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
     let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
-    match alert::delete_by_name(&org_id, stream_type, &stream_name, &name).await {
+    match alert::delete_by_name(
+        &org_id,
+        stream_type,
+        &stream_name,
+        &name,
+        &user_email.user_id,
+    )
+    .await
+    {
         Ok(_) => MetaHttpResponse::ok("Alert deleted"),
         Err(e) => e.into(),
     }
@@ -418,6 +430,7 @@ async fn enable_alert(path: web::Path<(String, String, String)>, req: HttpReques
     ),
     responses(
         (status = 200, description = "Success",  content_type = "application/json", body = HttpResponse),
+        (status = 207, description = "Some destinations failed", content_type = "application/json", body = crate::handler::http::models::alerts::responses::TriggerAlertResponseBody),
         (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure",  content_type = "application/json", body = HttpResponse),
     )
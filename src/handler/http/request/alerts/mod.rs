@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse};
+use actix_web::{delete, get, http::StatusCode, patch, post, put, web, HttpRequest, HttpResponse};
 use config::meta::{
     alerts::alert::Alert as MetaAlert,
     folder::DEFAULT_FOLDER,
@@ -27,10 +27,14 @@ use crate::{
     common::{meta::http::HttpResponse as MetaHttpResponse, utils::auth::UserEmail},
     handler::http::models::alerts::{
         requests::{
-            CreateAlertRequestBody, EnableAlertQuery, ListAlertsQuery, MoveAlertsRequestBody,
-            UpdateAlertRequestBody,
+            CreateAlertRequestBody, EnableAlertQuery, EnableAlertsRequestBody, ListAlertsQuery,
+            MoveAlertsRequestBody, SilenceAlertRequestBody, UpdateAlertRequestBody,
+        },
+        responses::{
+            EnableAlertResponseBody, EnableAlertsFailure, EnableAlertsResponseBody,
+            GetAlertResponseBody, ListAlertsResponseBody, SilenceAlertResponseBody,
+            TriggerAlertResponseBody,
         },
-        responses::{EnableAlertResponseBody, GetAlertResponseBody, ListAlertsResponseBody},
     },
     service::{
         alerts::alert::{self, AlertError},
@@ -65,11 +69,25 @@ impl From<AlertError> for HttpResponse {
             AlertError::SqlContainsSelectStar => MetaHttpResponse::bad_request(value),
             AlertError::PromqlMissingQuery => MetaHttpResponse::bad_request(value),
             AlertError::SendNotificationError { .. } => MetaHttpResponse::internal_error(value),
+            AlertError::PartialSendNotificationError { results } => HttpResponse::MultiStatus()
+                .json(TriggerAlertResponseBody {
+                    code: StatusCode::MULTI_STATUS.into(),
+                    message: value.to_string(),
+                    results: results.iter().cloned().map(Into::into).collect(),
+                }),
             AlertError::GetDestinationWithTemplateError(err) => {
                 MetaHttpResponse::internal_error(err)
             }
             AlertError::PeriodExceedsMaxQueryRange { .. } => MetaHttpResponse::bad_request(value),
+            AlertError::FrequencyBelowScheduleInterval { .. } => {
+                MetaHttpResponse::bad_request(value)
+            }
             AlertError::ResolveStreamNameError(_) => MetaHttpResponse::internal_error(value),
+            AlertError::BaselineNotSupported => MetaHttpResponse::bad_request(value),
+            AlertError::BaselineInvalidOffset { .. } => MetaHttpResponse::bad_request(value),
+            AlertError::BaselineOffsetBelowPeriod => MetaHttpResponse::bad_request(value),
+            AlertError::BaselineInvalidMultiplier => MetaHttpResponse::bad_request(value),
+            AlertError::BaselineInvalidOperator => MetaHttpResponse::bad_request(value),
             AlertError::PermittedAlertsMissingUser => MetaHttpResponse::forbidden(""),
             AlertError::PermittedAlertsValidator(err) => MetaHttpResponse::forbidden(err),
             AlertError::NotSupportedAlertDestinationType(err) => MetaHttpResponse::forbidden(err),
@@ -210,11 +228,11 @@ pub async fn update_alert(
     )
 )]
 #[delete("/v2/{org_id}/alerts/{alert_id}")]
-async fn delete_alert(path: web::Path<(String, Ksuid)>) -> HttpResponse {
+async fn delete_alert(path: web::Path<(String, Ksuid)>, user_email: UserEmail) -> HttpResponse {
     let (org_id, alert_id) = path.into_inner();
 
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
-    match alert::delete_by_id(client, &org_id, alert_id).await {
+    match alert::delete_by_id(client, &org_id, alert_id, &user_email.user_id).await {
         Ok(_) => MetaHttpResponse::ok("Alert deleted"),
         Err(e) => e.into(),
     }
@@ -318,6 +336,123 @@ async fn enable_alert(path: web::Path<(String, Ksuid)>, req: HttpRequest) -> Htt
     }
 }
 
+/// EnableAlerts
+///
+/// Enables or disables several alerts at once. Each alert is updated
+/// independently, so an alert that is missing or that the user may not modify
+/// does not prevent the others from being updated.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "EnableAlerts",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = EnableAlertsRequestBody, description = "Identifies alerts and the enabled state to set", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = EnableAlertsResponseBody),
+        (status = 400, description = "Error",   content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/v2/{org_id}/alerts/enable")]
+async fn enable_alerts(
+    path: web::Path<String>,
+    req_body: web::Json<EnableAlertsRequestBody>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let org_id = path.into_inner();
+    let EnableAlertsRequestBody {
+        mut alert_ids,
+        value: should_enable,
+    } = req_body.into_inner();
+    if alert_ids.is_empty() {
+        return MetaHttpResponse::bad_request("No alert ids provided");
+    }
+    alert_ids.sort();
+    alert_ids.dedup();
+
+    #[cfg(not(feature = "enterprise"))]
+    let _ = req;
+    #[cfg(feature = "enterprise")]
+    let Some(user_id) = req.headers().get("user_id").and_then(|v| v.to_str().ok()) else {
+        return MetaHttpResponse::forbidden("");
+    };
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let mut resp_body = EnableAlertsResponseBody {
+        enabled: should_enable,
+        ..Default::default()
+    };
+    for alert_id in alert_ids {
+        #[cfg(feature = "enterprise")]
+        if !crate::common::utils::auth::check_permissions(
+            Some(alert_id.to_string()),
+            &org_id,
+            user_id,
+            "alerts",
+            "PUT",
+        )
+        .await
+        {
+            resp_body.failed.push(EnableAlertsFailure {
+                alert_id,
+                error: "Unauthorized Access".to_string(),
+            });
+            continue;
+        }
+
+        match alert::enable_by_id(client, &org_id, alert_id, should_enable).await {
+            Ok(true) => resp_body.changed.push(alert_id),
+            Ok(false) => resp_body.unchanged.push(alert_id),
+            Err(e) => resp_body.failed.push(EnableAlertsFailure {
+                alert_id,
+                error: e.to_string(),
+            }),
+        }
+    }
+    MetaHttpResponse::json(resp_body)
+}
+
+/// SilenceAlert
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Alerts",
+    operation_id = "SilenceAlert",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("alert_id" = Ksuid, Path, description = "Alert ID"),
+    ),
+    request_body(content = SilenceAlertRequestBody, description = "Silence window", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = SilenceAlertResponseBody),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",  content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[put("/v2/{org_id}/alerts/{alert_id}/silence")]
+async fn silence_alert(
+    path: web::Path<(String, Ksuid)>,
+    req_body: web::Json<SilenceAlertRequestBody>,
+) -> HttpResponse {
+    let (org_id, alert_id) = path.into_inner();
+    let silence_until = req_body.into_inner().silence_until;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    match alert::silence_by_id(client, &org_id, alert_id, silence_until).await {
+        Ok(_) => {
+            let resp_body = SilenceAlertResponseBody { silence_until };
+            MetaHttpResponse::json(resp_body)
+        }
+        Err(e) => e.into(),
+    }
+}
+
 /// TriggerAlert
 #[utoipa::path(
     context_path = "/api",
@@ -332,6 +467,7 @@ async fn enable_alert(path: web::Path<(String, Ksuid)>, req: HttpRequest) -> Htt
     ),
     responses(
         (status = 200, description = "Success",  content_type = "application/json", body = HttpResponse),
+        (status = 207, description = "Some destinations failed", content_type = "application/json", body = TriggerAlertResponseBody),
         (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure",  content_type = "application/json", body = HttpResponse),
     )
@@ -0,0 +1,169 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use std::io::Error;
+
+use actix_web::{get, http::header, put, web, HttpRequest, HttpResponse};
+use config::utils::base64;
+
+use crate::{
+    common::{
+        meta::{
+            branding::{BrandingRequest, BrandingResponse},
+            http::HttpResponse as MetaHttpResponse,
+        },
+        utils::auth::{is_root_user, UserEmail},
+    },
+    service::branding,
+};
+
+const ROOT_ONLY: &str = "Only root user can change the branding";
+
+/// The logo url is versioned, revalidating after a day covers the unversioned
+/// url
+const LOGO_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// GetBranding
+///
+/// Returns the product name, login message and logo url of the deployment.
+/// Doesn't need authentication, the login page reads it.
+#[utoipa::path(
+    get,
+    context_path = "/api/_settings",
+    tag = "Branding",
+    operation_id = "GetBranding",
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BrandingResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[get("/branding")]
+pub async fn get() -> Result<HttpResponse, Error> {
+    match branding::get().await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// GetBrandingLogo
+///
+/// Serves the logo of the deployment, doesn't need authentication.
+#[utoipa::path(
+    get,
+    context_path = "/api/_settings",
+    tag = "Branding",
+    operation_id = "GetBrandingLogo",
+    responses(
+        (status = 200, description = "PNG or SVG image"),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "No logo configured", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[get("/branding/logo")]
+pub async fn logo(req: HttpRequest) -> Result<HttpResponse, Error> {
+    let logo = match branding::get_logo().await {
+        Ok(Some(logo)) => logo,
+        Ok(None) => return Ok(MetaHttpResponse::not_found("No logo configured")),
+        Err(e) => return Ok(e.into()),
+    };
+    let etag = format!("\"{}\"", logo.updated_at);
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+    if if_none_match == Some(etag.as_str()) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .insert_header((header::CACHE_CONTROL, LOGO_CACHE_CONTROL))
+            .finish());
+    }
+    let data = match base64::decode_raw(&logo.data) {
+        Ok(data) => data,
+        Err(e) => return Ok(MetaHttpResponse::internal_error(e)),
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(logo.content_type)
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, LOGO_CACHE_CONTROL))
+        // SVGs are sanitized on upload, never let them run anything either way
+        .insert_header((
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; style-src 'unsafe-inline'; sandbox",
+        ))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(data))
+}
+
+/// UpdateBranding
+///
+/// Replaces the branding of the deployment, fields left out are cleared.
+#[utoipa::path(
+    put,
+    context_path = "/api/_settings",
+    tag = "Branding",
+    operation_id = "UpdateBranding",
+    security(
+        ("Authorization"= [])
+    ),
+    request_body(content = BrandingRequest, description = "Branding", content_type = "application/json", example = json!({
+        "product_name": "Acme Observability",
+        "login_message": "Authorized use only.",
+        "logo": "PHN2ZyB3aWR0aD0iMTIwIiBoZWlnaHQ9IjQwIj48L3N2Zz4="
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BrandingResponse),
+        (status = 400, description = "Invalid request", content_type = "application/json", body = MetaHttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[put("/branding")]
+pub async fn update(
+    body: web::Json<BrandingRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    match branding::set(body.into_inner()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+
+    use crate::handler::http::router::get_branding_routes;
+
+    #[tokio::test]
+    async fn test_branding_read_without_auth() {
+        infra::db::create_table().await.unwrap();
+        let app = test::init_service(App::new().configure(get_branding_routes)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/_settings/branding")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // changing the branding needs credentials
+        let req = test::TestRequest::put()
+            .uri("/api/_settings/branding")
+            .set_json(serde_json::json!({"product_name": "Acme"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -20,9 +20,13 @@ use actix_web::{delete, get, http, post, put, web, HttpRequest, HttpResponse, Re
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
     handler::http::models::dashboards::{
-        CreateDashboardRequestBody, CreateDashboardResponseBody, GetDashboardResponseBody,
+        CreateDashboardRequestBody, CreateDashboardResponseBody, DuplicateDashboardRequestBody,
+        ExportDashboardQuery, ExportDashboardResponseBody, GetDashboardResponseBody,
+        ImportDashboardsQuery, ImportDashboardsRequestBody, ImportDashboardsResponseBody,
         ListDashboardsQuery, ListDashboardsResponseBody, MoveDashboardRequestBody,
-        UpdateDashboardRequestBody, UpdateDashboardResponseBody,
+        UpdateDashboardConflictResponseBody, UpdateDashboardRequestBody,
+        UpdateDashboardResponseBody, ValidateDashboardQueriesRequestBody,
+        ValidateDashboardQueriesResponseBody,
     },
     service::dashboards::{self, DashboardError},
 };
@@ -35,16 +39,37 @@ impl From<DashboardError> for HttpResponse {
         match value {
             DashboardError::InfraError(err) => MetaHttpResponse::internal_error(err),
             DashboardError::DashboardNotFound => MetaHttpResponse::not_found("Dashboard not found"),
-            DashboardError::UpdateMissingHash => MetaHttpResponse::internal_error("Request to update existing dashboard with missing or invalid hash value. BUG"),
-            DashboardError::UpdateConflictingHash => MetaHttpResponse::conflict("Conflict: Failed to save due to concurrent changes. Please refresh the page after backing up your work to avoid losing changes."),
-            DashboardError::PutMissingTitle => MetaHttpResponse::internal_error("Dashboard should have title"),
-            DashboardError::MoveMissingFolderParam => MetaHttpResponse::bad_request("Please specify from & to folder from dashboard movement"),
-            DashboardError::MoveDestinationFolderNotFound => MetaHttpResponse::not_found("Folder not found"),
+            DashboardError::UpdateMissingHash => MetaHttpResponse::internal_error(
+                "Request to update existing dashboard with missing or invalid hash value. BUG",
+            ),
+            DashboardError::UpdateConflictingHash(conflict) => {
+                HttpResponse::Conflict().json(UpdateDashboardConflictResponseBody::from(conflict))
+            }
+            DashboardError::PutMissingTitle => {
+                MetaHttpResponse::bad_request("Dashboard should have title")
+            }
+            DashboardError::MoveMissingFolderParam => MetaHttpResponse::bad_request(
+                "Please specify from & to folder from dashboard movement",
+            ),
+            DashboardError::MoveDestinationFolderNotFound => {
+                MetaHttpResponse::not_found("Folder not found")
+            }
             DashboardError::CreateFolderNotFound => MetaHttpResponse::not_found("Folder not found"),
-            DashboardError::CreateDefaultFolder => MetaHttpResponse::internal_error("Error saving default folder"),
-            DashboardError::DistinctValueError => MetaHttpResponse::internal_error("Error in updating distinct values"),
-            DashboardError::MoveDashboardDeleteOld(dashb_id, folder_id, e) => MetaHttpResponse::internal_error(format!("error deleting the dashboard {dashb_id} from old folder {folder_id} : {e}")),
+            DashboardError::CreateDefaultFolder => {
+                MetaHttpResponse::internal_error("Error saving default folder")
+            }
+            DashboardError::DistinctValueError => {
+                MetaHttpResponse::internal_error("Error in updating distinct values")
+            }
+            DashboardError::MoveDashboardDeleteOld(dashb_id, folder_id, e) => {
+                MetaHttpResponse::internal_error(format!(
+                    "error deleting the dashboard {dashb_id} from old folder {folder_id} : {e}"
+                ))
+            }
             DashboardError::ListPermittedDashboardsError(err) => MetaHttpResponse::forbidden(err),
+            DashboardError::InvalidDashboard(err) => {
+                MetaHttpResponse::bad_request(format!("Invalid dashboard: {err}"))
+            }
         }
     }
 }
@@ -112,6 +137,7 @@ pub async fn create_dashboard(
     responses(
         (status = StatusCode::OK, description = "Dashboard updated", body = UpdateDashboardResponseBody),
         (status = StatusCode::NOT_FOUND, description = "Dashboard not found", body = HttpResponse),
+        (status = StatusCode::CONFLICT, description = "Dashboard was changed concurrently", body = UpdateDashboardConflictResponseBody),
         (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Failed to update the dashboard", body = HttpResponse),
     ),
 )]
@@ -220,9 +246,10 @@ async fn get_dashboard(path: web::Path<(String, String)>) -> impl Responder {
     ),
 )]
 #[delete("/{org_id}/dashboards/{dashboard_id}")]
-async fn delete_dashboard(path: web::Path<(String, String)>) -> impl Responder {
+async fn delete_dashboard(path: web::Path<(String, String)>, req: HttpRequest) -> impl Responder {
     let (org_id, dashboard_id) = path.into_inner();
-    match dashboards::delete_dashboard(&org_id, &dashboard_id).await {
+    let user_id = get_user_id(req).unwrap_or_default();
+    match dashboards::delete_dashboard(&org_id, &dashboard_id, &user_id).await {
         Ok(()) => HttpResponse::Ok().json(MetaHttpResponse::message(
             http::StatusCode::OK.into(),
             "Dashboard deleted".to_string(),
@@ -271,6 +298,184 @@ async fn move_dashboard(
     }
 }
 
+/// ExportDashboard
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Dashboards",
+    operation_id = "ExportDashboard",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("folder_id" = String, Path, description = "Folder ID"),
+        ("dashboard_id" = String, Path, description = "Dashboard ID"),
+        ExportDashboardQuery
+    ),
+    responses(
+        (status = StatusCode::OK, body = ExportDashboardResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Unsupported export version", body = HttpResponse),
+        (status = StatusCode::NOT_FOUND, description = "Dashboard not found", body = HttpResponse),
+    ),
+)]
+#[get("/{org_id}/folders/{folder_id}/dashboards/{dashboard_id}/export")]
+async fn export_dashboard(
+    path: web::Path<(String, String, String)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (org_id, folder_id, dashboard_id) = path.into_inner();
+    let Ok(query) = web::Query::<ExportDashboardQuery>::from_query(req.query_string()) else {
+        return MetaHttpResponse::bad_request("Error parsing query parameters");
+    };
+    let (dashboard, hash) =
+        match dashboards::export_dashboard(&org_id, &folder_id, &dashboard_id).await {
+            Ok(exported) => exported,
+            Err(err) => return err.into(),
+        };
+    match ExportDashboardResponseBody::new(dashboard, hash, query.version) {
+        Some(resp_body) => MetaHttpResponse::json(resp_body),
+        None => MetaHttpResponse::bad_request("Unsupported export version, use 5 or 6"),
+    }
+}
+
+/// ImportDashboards
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Dashboards",
+    operation_id = "ImportDashboards",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("folder_id" = String, Path, description = "Folder ID"),
+        ImportDashboardsQuery
+    ),
+    request_body(
+        content = ImportDashboardsRequestBody,
+        description = "Dashboards of any supported version",
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Import report for each dashboard", body = ImportDashboardsResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request", body = HttpResponse),
+    ),
+)]
+#[post("/{org_id}/folders/{folder_id}/dashboards/_import")]
+async fn import_dashboards(
+    path: web::Path<(String, String)>,
+    req_body: web::Json<ImportDashboardsRequestBody>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (org_id, folder_id) = path.into_inner();
+    let Ok(query) = web::Query::<ImportDashboardsQuery>::from_query(req.query_string()) else {
+        return MetaHttpResponse::bad_request("Error parsing query parameters");
+    };
+    let results = dashboards::import_dashboards(
+        &org_id,
+        &folder_id,
+        req_body.into_inner().0,
+        query.overwrite,
+    )
+    .await;
+    let resp_body: ImportDashboardsResponseBody = results.into();
+    MetaHttpResponse::json(resp_body)
+}
+
+/// DuplicateDashboard
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Dashboards",
+    operation_id = "DuplicateDashboard",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("folder_id" = String, Path, description = "Folder ID"),
+        ("dashboard_id" = String, Path, description = "Dashboard ID"),
+    ),
+    request_body(
+        content = DuplicateDashboardRequestBody,
+        description = "Title and folder of the copy",
+        example = json!({
+            "title": "Network Traffic Overview (copy)",
+            "folder": "Destination folder id",
+        }),
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Dashboard duplicated", body = CreateDashboardResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request", body = HttpResponse),
+        (status = StatusCode::NOT_FOUND, description = "Dashboard or folder not found", body = HttpResponse),
+    ),
+)]
+#[post("/{org_id}/folders/{folder_id}/dashboards/{dashboard_id}/_duplicate")]
+async fn duplicate_dashboard(
+    path: web::Path<(String, String, String)>,
+    req_body: web::Json<DuplicateDashboardRequestBody>,
+) -> impl Responder {
+    let (org_id, folder_id, dashboard_id) = path.into_inner();
+    let req_body = req_body.into_inner();
+    let saved = match dashboards::duplicate_dashboard(
+        &org_id,
+        &folder_id,
+        &dashboard_id,
+        &req_body.title,
+        req_body.folder.as_deref(),
+    )
+    .await
+    {
+        Ok(saved) => saved,
+        Err(err) => return err.into(),
+    };
+    let resp_body: CreateDashboardResponseBody = saved.into();
+    MetaHttpResponse::json(resp_body)
+}
+
+/// ValidateDashboardQueries
+///
+/// Checks the SQL of panel queries against the current stream schemas without
+/// running them. A query failing validation doesn't fail the request.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Dashboards",
+    operation_id = "ValidateDashboardQueries",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(
+        content = ValidateDashboardQueriesRequestBody,
+        description = "Panel queries to validate",
+        example = json!({
+            "queries": [{
+                "sql": "SELECT histogram(_timestamp) AS ts, count(*) FROM default GROUP BY ts",
+                "stream_type": "logs",
+            }],
+        }),
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Validation result of each query", body = ValidateDashboardQueriesResponseBody),
+    ),
+)]
+#[post("/{org_id}/dashboards/_validate_queries")]
+async fn validate_queries(
+    path: web::Path<String>,
+    req_body: web::Json<ValidateDashboardQueriesRequestBody>,
+) -> impl Responder {
+    let org_id = path.into_inner();
+    let queries = req_body
+        .into_inner()
+        .queries
+        .into_iter()
+        .map(|q| q.into())
+        .collect::<Vec<_>>();
+    let results = dashboards::validate::validate_queries(&org_id, &queries).await;
+    let resp_body: ValidateDashboardQueriesResponseBody = results.into();
+    MetaHttpResponse::json(resp_body)
+}
+
 fn get_folder(req: HttpRequest) -> String {
     let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
     crate::common::utils::http::get_folder(&query)
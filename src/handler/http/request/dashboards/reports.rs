@@ -16,7 +16,10 @@
 use std::{collections::HashMap, io::Error};
 
 use actix_web::{delete, get, http, post, put, web, HttpRequest, HttpResponse};
-use config::meta::dashboards::reports::{Report, ReportListFilters};
+use config::{
+    meta::dashboards::reports::{Report, ReportListFilters},
+    utils::render_queue::RenderStatus,
+};
 
 use crate::{
     common::{meta::http::HttpResponse as MetaHttpResponse, utils::auth::UserEmail},
@@ -281,6 +284,7 @@ async fn enable_report(
         (status = 200, description = "Success",  content_type = "application/json", body = HttpResponse),
         (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure",  content_type = "application/json", body = HttpResponse),
+        (status = 503, description = "Render queue is full",  content_type = "application/json", body = HttpResponse),
     )
 )]
 #[put("/{org_id}/reports/{name}/trigger")]
@@ -288,6 +292,39 @@ async fn trigger_report(path: web::Path<(String, String)>) -> Result<HttpRespons
     let (org_id, name) = path.into_inner();
     match reports::trigger(&org_id, &name).await {
         Ok(_) => Ok(MetaHttpResponse::ok("Report triggered")),
+        Err(e) => match e {
+            (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
+            (http::StatusCode::SERVICE_UNAVAILABLE, e) => {
+                Ok(MetaHttpResponse::service_unavailable(e))
+            }
+            (_, e) => Ok(MetaHttpResponse::internal_error(e)),
+        },
+    }
+}
+
+/// GetReportStatus
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Reports",
+    operation_id = "GetReportStatus",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("name" = String, Path, description = "Report name"),
+    ),
+    responses(
+        (status = 200, description = "Success",  content_type = "application/json", body = RenderStatus),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure",  content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/reports/{name}/status")]
+async fn get_report_status(path: web::Path<(String, String)>) -> Result<HttpResponse, Error> {
+    let (org_id, name) = path.into_inner();
+    match reports::render_status(&org_id, &name).await {
+        Ok(status) => Ok(MetaHttpResponse::json(status)),
         Err(e) => match e {
             (http::StatusCode::NOT_FOUND, e) => Ok(MetaHttpResponse::not_found(e)),
             (_, e) => Ok(MetaHttpResponse::internal_error(e)),
@@ -16,15 +16,22 @@
 use std::io::Error;
 
 use actix_multipart::Multipart;
-use actix_web::{post, web, HttpRequest, HttpResponse};
+use actix_web::{get, http::header, post, web, HttpRequest, HttpResponse};
 use config::SIZE_IN_MB;
 use hashbrown::HashMap;
 
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
-    service::enrichment_table::{extract_multipart, save_enrichment_data},
+    handler::http::router::ui::if_none_match,
+    service::enrichment_table::{
+        download::{self, DownloadFormat, TableExport},
+        extract_multipart, save_enrichment_data,
+    },
 };
 
+const TABLE_VERSION_HEADER: &str = "X-O2-Table-Version";
+const NEXT_ROW_HEADER: &str = "X-O2-Next-Row";
+
 /// CreateEnrichmentTable
 #[utoipa::path(
     context_path = "/api",
@@ -81,8 +88,8 @@ pub async fn save_enrichment_table(
                     Some(append_data) => append_data.parse::<bool>().unwrap_or(false),
                     None => false,
                 };
-                let json_record = extract_multipart(payload).await?;
-                save_enrichment_data(&org_id, &table_name, json_record, append_data).await
+                let (columns, json_record) = extract_multipart(payload).await?;
+                save_enrichment_data(&org_id, &table_name, json_record, &columns, append_data).await
             } else {
                 Ok(MetaHttpResponse::bad_request(
                     "Bad Request, content-type must be multipart/form-data",
@@ -94,3 +101,240 @@ pub async fn save_enrichment_table(
         )),
     }
 }
+
+/// DownloadEnrichmentTable
+///
+/// Downloads the whole table with the columns in the order they were uploaded
+/// in. Large tables can be fetched in chunks with `from_row` and `rows`, the
+/// `X-O2-Next-Row` response header tells where the next chunk starts, or with
+/// a `Range` header. The `X-O2-Table-Version` header, also sent as `ETag`,
+/// changes with the table contents and can be passed in `If-None-Match`.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Functions",
+    operation_id = "DownloadEnrichmentTable",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("table_name" = String, Path, description = "Table name"),
+        ("format" = Option<String>, Query, description = "csv (default) or ndjson"),
+        ("from_row" = Option<usize>, Query, description = "Row to start from, the csv header is only sent with the first row"),
+        ("rows" = Option<usize>, Query, description = "Maximum number of rows to return"),
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Table contents"),
+        (status = StatusCode::PARTIAL_CONTENT, description = "Requested range of the table contents"),
+        (status = StatusCode::NOT_MODIFIED, description = "Table version matches If-None-Match"),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request", body = HttpResponse),
+        (status = StatusCode::NOT_FOUND, description = "Table not found", body = HttpResponse),
+        (status = StatusCode::RANGE_NOT_SATISFIABLE, description = "Range not satisfiable"),
+    ),
+)]
+#[get("/{org_id}/enrichment_tables/{table_name}/_download")]
+pub async fn download_enrichment_table(
+    path: web::Path<(String, String)>,
+    query: web::Query<HashMap<String, String>>,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, table_name) = path.into_inner();
+    let format = match query.get("format") {
+        Some(format) => match format.parse::<DownloadFormat>() {
+            Ok(format) => format,
+            Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+        },
+        None => DownloadFormat::Csv,
+    };
+    let from_row = match query.get("from_row").map(|v| v.parse::<usize>()) {
+        Some(Ok(v)) => v,
+        Some(Err(_)) => return Ok(MetaHttpResponse::bad_request("Invalid from_row")),
+        None => 0,
+    };
+    let rows = match query.get("rows").map(|v| v.parse::<usize>()) {
+        Some(Ok(v)) => Some(v),
+        Some(Err(_)) => return Ok(MetaHttpResponse::bad_request("Invalid rows")),
+        None => None,
+    };
+
+    match download::export(&org_id, &table_name).await {
+        Ok(Some(table)) => Ok(download_response(&req, &table, format, from_row, rows)),
+        Ok(None) => Ok(MetaHttpResponse::not_found(format!(
+            "enrichment table [{table_name}] not found"
+        ))),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+fn download_response(
+    req: &HttpRequest,
+    table: &TableExport,
+    format: DownloadFormat,
+    from_row: usize,
+    rows: Option<usize>,
+) -> HttpResponse {
+    let version = table.version();
+    let etag = format!("\"{version}\"");
+    if if_none_match(req.headers(), &etag) || if_none_match(req.headers(), version) {
+        return HttpResponse::NotModified()
+            .insert_header((TABLE_VERSION_HEADER, version))
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+
+    let (body, next_row) = table.render(format, from_row, rows);
+    let range = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_range(v, body.len()));
+    let mut resp = match range {
+        None => HttpResponse::Ok(),
+        Some(Some(_)) => HttpResponse::PartialContent(),
+        Some(None) => {
+            return HttpResponse::RangeNotSatisfiable()
+                .insert_header((header::CONTENT_RANGE, format!("bytes */{}", body.len())))
+                .insert_header((TABLE_VERSION_HEADER, version))
+                .insert_header((header::ETAG, etag))
+                .finish();
+        }
+    };
+    resp.content_type(format.content_type())
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header((TABLE_VERSION_HEADER, version))
+        .insert_header((header::ETAG, etag));
+    if let Some(next_row) = next_row {
+        resp.insert_header((NEXT_ROW_HEADER, next_row.to_string()));
+    }
+    match range {
+        Some(Some((start, end))) => resp
+            .insert_header((
+                header::CONTENT_RANGE,
+                format!("bytes {start}-{end}/{}", body.len()),
+            ))
+            .body(body[start..=end].to_vec()),
+        _ => resp.body(body),
+    }
+}
+
+/// Parses a single `bytes` range into inclusive offsets, `None` when it can't
+/// be satisfied for a body of `len` bytes.
+fn parse_range(range: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<usize>().ok()?;
+            (len.checked_sub(suffix.min(len))?, len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.checked_sub(1)?),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<usize>().ok()?.min(len.checked_sub(1)?),
+        ),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body::MessageBody, http::StatusCode, test::TestRequest};
+    use config::utils::json;
+
+    use super::*;
+
+    fn table() -> TableExport {
+        let records = [
+            json::json!({"_timestamp": 1, "city": "Austin", "zone": "us"}),
+            json::json!({"_timestamp": 2, "city": "Berlin", "zone": "eu"}),
+        ]
+        .into_iter()
+        .map(|v| v.as_object().unwrap().clone())
+        .collect();
+        TableExport::new(vec!["zone".to_string(), "city".to_string()], records)
+    }
+
+    fn body(resp: HttpResponse) -> Vec<u8> {
+        resp.into_body().try_into_bytes().unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_download_conditional_fetch() {
+        let table = table();
+        let req = TestRequest::default().to_http_request();
+        let resp = download_response(&req, &table, DownloadFormat::Csv, 0, None);
+        assert_eq!(resp.status(), StatusCode::OK);
+        let version = resp.headers().get(TABLE_VERSION_HEADER).unwrap().clone();
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(etag.to_str().unwrap(), format!("\"{}\"", table.version()));
+        assert_eq!(body(resp), b"zone,city\nus,Austin\neu,Berlin\n");
+
+        for tag in [etag, version] {
+            let req = TestRequest::default()
+                .insert_header((header::IF_NONE_MATCH, tag))
+                .to_http_request();
+            let resp = download_response(&req, &table, DownloadFormat::Csv, 0, None);
+            assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+            assert!(body(resp).is_empty());
+        }
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"stale\""))
+            .to_http_request();
+        let resp = download_response(&req, &table, DownloadFormat::Csv, 0, None);
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_download_resume() {
+        let table = table();
+        let req = TestRequest::default().to_http_request();
+        let full = body(download_response(
+            &req,
+            &table,
+            DownloadFormat::Csv,
+            0,
+            None,
+        ));
+
+        let resp = download_response(&req, &table, DownloadFormat::Csv, 0, Some(1));
+        let next_row = resp
+            .headers()
+            .get(NEXT_ROW_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        let next_row = next_row.parse::<usize>().unwrap();
+        let mut resumed = body(resp);
+        let resp = download_response(&req, &table, DownloadFormat::Csv, next_row, None);
+        assert!(resp.headers().get(NEXT_ROW_HEADER).is_none());
+        resumed.extend(body(resp));
+        assert_eq!(resumed, full);
+
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=10-"))
+            .to_http_request();
+        let resp = download_response(&req, &table, DownloadFormat::Csv, 0, None);
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            &format!("bytes 10-{}/{}", full.len() - 1, full.len())
+        );
+        assert_eq!(body(resp), &full[10..]);
+
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, format!("bytes={}-", full.len())))
+            .to_http_request();
+        let resp = download_response(&req, &table, DownloadFormat::Csv, 0, None);
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-200", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=9-0", 100), None);
+        assert_eq!(parse_range("items=0-9", 100), None);
+    }
+}
@@ -13,13 +13,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{
+    delete, get, http::StatusCode, post, put, web, HttpRequest, HttpResponse, Responder,
+};
 
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
     handler::http::models::folders::{
-        CreateFolderRequestBody, CreateFolderResponseBody, FolderType, ListFoldersResponseBody,
-        UpdateFolderRequestBody,
+        CreateFolderRequestBody, CreateFolderResponseBody, DeleteFolderQuery,
+        FolderContentsErrorResponseBody, FolderContentsResponseBody, FolderType,
+        ListFoldersResponseBody, UpdateFolderRequestBody,
     },
     service::folders::{self, FolderError},
 };
@@ -34,13 +37,42 @@ impl From<FolderError> for HttpResponse {
             FolderError::UpdateDefaultFolder => {
                 MetaHttpResponse::bad_request("Can't update default folder")
             }
-            FolderError::DeleteWithDashboards => MetaHttpResponse::bad_request(
-                "Folder contains dashboards, please move/delete dashboards from folder",
-            ),
-            FolderError::DeleteWithAlerts => MetaHttpResponse::bad_request(
-                "Folder contains alerts, please move/delete alerts from folder",
-            ),
+            FolderError::NotEmpty(contents) => {
+                HttpResponse::Conflict().json(FolderContentsErrorResponseBody {
+                    code: StatusCode::CONFLICT.into(),
+                    message: "Folder is not empty, use force to delete it with its contents"
+                        .to_string(),
+                    contents: contents.into(),
+                })
+            }
+            FolderError::ContentsForbidden(contents) => {
+                HttpResponse::Forbidden().json(FolderContentsErrorResponseBody {
+                    code: StatusCode::FORBIDDEN.into(),
+                    message: "Not permitted to delete some of the folder contents".to_string(),
+                    contents: contents.into(),
+                })
+            }
+            FolderError::ContentsChanged(contents) => {
+                HttpResponse::Conflict().json(FolderContentsErrorResponseBody {
+                    code: StatusCode::CONFLICT.into(),
+                    message: "Folder contents changed while deleting it, review them and retry"
+                        .to_string(),
+                    contents: contents.into(),
+                })
+            }
+            FolderError::ContentsCleanupFailed(contents) => HttpResponse::InternalServerError()
+                .json(FolderContentsErrorResponseBody {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.into(),
+                    message: "Folder deleted, but some of its contents could not be cleaned up"
+                        .to_string(),
+                    contents: contents.into(),
+                }),
+            FolderError::ForceDeleteDefaultFolder => {
+                MetaHttpResponse::bad_request("Can't force delete default folder")
+            }
+            FolderError::ReportError(err) => MetaHttpResponse::internal_error(err),
             FolderError::NotFound => MetaHttpResponse::not_found("Folder not found"),
+            FolderError::TrashError(err) => MetaHttpResponse::internal_error(err),
             FolderError::PermittedFoldersMissingUser => MetaHttpResponse::forbidden(""),
             FolderError::PermittedFoldersValidator(err) => MetaHttpResponse::forbidden(err),
             FolderError::FolderNameAlreadyExists => MetaHttpResponse::bad_request(
@@ -231,7 +263,41 @@ pub async fn get_folder_by_name(path: web::Path<(String, FolderType, String)>) -
     }
 }
 
+/// GetFolderContents
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Folders",
+    operation_id = "GetFolderContents",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("folder_type" = FolderType, Path, description = "Type of data the folder can contain"),
+        ("folder_id" = String, Path, description = "Folder ID"),
+    ),
+    responses(
+        (status = StatusCode::OK, body = FolderContentsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "Folder not found", body = HttpResponse),
+    ),
+)]
+#[get("/v2/{org_id}/folders/{folder_type}/{folder_id}/contents")]
+pub async fn get_folder_contents(path: web::Path<(String, FolderType, String)>) -> impl Responder {
+    let (org_id, folder_type, folder_id) = path.into_inner();
+    match folders::get_folder_contents(&org_id, &folder_id, folder_type.into()).await {
+        Ok(contents) => {
+            let body: FolderContentsResponseBody = contents.into();
+            HttpResponse::Ok().json(body)
+        }
+        Err(err) => err.into(),
+    }
+}
+
 /// DeleteFolder
+///
+/// Deletes an empty folder. A folder that still has contents is only deleted
+/// with `force=true`, which moves its dashboards or alerts to the trash and
+/// deletes the reports of its dashboards. The response lists what was removed.
 #[utoipa::path(
     context_path = "/api",
     tag = "Folders",
@@ -243,18 +309,39 @@ pub async fn get_folder_by_name(path: web::Path<(String, FolderType, String)>) -
         ("org_id" = String, Path, description = "Organization name"),
         ("folder_type" = FolderType, Path, description = "Type of data the folder can contain"),
         ("folder_id" = String, Path, description = "Folder ID"),
+        DeleteFolderQuery,
     ),
     responses(
-        (status = StatusCode::OK, description = "Success", body = HttpResponse),
+        (status = StatusCode::OK, description = "Success", body = FolderContentsResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "Some contents can't be deleted", body = FolderContentsErrorResponseBody),
         (status = StatusCode::NOT_FOUND, description = "NotFound", body = HttpResponse),
-        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Error", body = HttpResponse),
+        (status = StatusCode::CONFLICT, description = "Folder is not empty, or its contents changed", body = FolderContentsErrorResponseBody),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Error, or contents that could not be cleaned up", body = FolderContentsErrorResponseBody),
     ),
 )]
 #[delete("/v2/{org_id}/folders/{folder_type}/{folder_id}")]
-async fn delete_folder(path: web::Path<(String, FolderType, String)>) -> impl Responder {
+async fn delete_folder(
+    path: web::Path<(String, FolderType, String)>,
+    query: web::Query<DeleteFolderQuery>,
+    req: HttpRequest,
+) -> impl Responder {
     let (org_id, folder_type, folder_id) = path.into_inner();
-    match folders::delete_folder(&org_id, &folder_id, folder_type.into()).await {
-        Ok(()) => HttpResponse::Ok().body("Folder deleted"),
+    let Some(user_id) = req.headers().get("user_id").and_then(|v| v.to_str().ok()) else {
+        return MetaHttpResponse::forbidden("");
+    };
+    match folders::delete_folder(
+        &org_id,
+        &folder_id,
+        folder_type.into(),
+        query.force,
+        user_id,
+    )
+    .await
+    {
+        Ok(contents) => {
+            let body: FolderContentsResponseBody = contents.into();
+            HttpResponse::Ok().json(body)
+        }
         Err(err) => err.into(),
     }
 }
@@ -446,6 +533,37 @@ pub mod deprecated {
         }
     }
 
+    /// GetFolderContents
+    #[deprecated]
+    #[utoipa::path(
+        context_path = "/api",
+        tag = "Folders",
+        operation_id = "GetFolderContents",
+        security(
+            ("Authorization" = [])
+        ),
+        params(
+            ("org_id" = String, Path, description = "Organization name"),
+            ("folder_id" = String, Path, description = "Folder ID"),
+        ),
+        responses(
+            (status = StatusCode::OK, body = FolderContentsResponseBody),
+            (status = StatusCode::NOT_FOUND, description = "Folder not found", body = HttpResponse),
+        ),
+    )]
+    #[get("/{org_id}/folders/{folder_id}/contents")]
+    pub async fn get_folder_contents(path: web::Path<(String, String)>) -> impl Responder {
+        let (org_id, folder_id) = path.into_inner();
+        let folder_type = config::meta::folder::FolderType::Dashboards;
+        match folders::get_folder_contents(&org_id, &folder_id, folder_type).await {
+            Ok(contents) => {
+                let body: FolderContentsResponseBody = contents.into();
+                HttpResponse::Ok().json(body)
+            }
+            Err(err) => err.into(),
+        }
+    }
+
     /// DeleteFolder
     #[deprecated]
     #[utoipa::path(
@@ -458,19 +576,32 @@ pub mod deprecated {
         params(
             ("org_id" = String, Path, description = "Organization name"),
             ("folder_id" = String, Path, description = "Folder ID"),
+            DeleteFolderQuery,
         ),
         responses(
-            (status = StatusCode::OK, description = "Success", body = HttpResponse),
+            (status = StatusCode::OK, description = "Success", body = FolderContentsResponseBody),
+            (status = StatusCode::FORBIDDEN, description = "Some contents can't be deleted", body = FolderContentsErrorResponseBody),
             (status = StatusCode::NOT_FOUND, description = "NotFound", body = HttpResponse),
+            (status = StatusCode::CONFLICT, description = "Folder is not empty", body = FolderContentsErrorResponseBody),
             (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Error", body = HttpResponse),
         ),
     )]
     #[delete("/{org_id}/folders/{folder_id}")]
-    async fn delete_folder(path: web::Path<(String, String)>) -> impl Responder {
+    async fn delete_folder(
+        path: web::Path<(String, String)>,
+        query: web::Query<DeleteFolderQuery>,
+        req: HttpRequest,
+    ) -> impl Responder {
         let (org_id, folder_id) = path.into_inner();
         let folder_type = config::meta::folder::FolderType::Dashboards;
-        match folders::delete_folder(&org_id, &folder_id, folder_type).await {
-            Ok(()) => HttpResponse::Ok().body("Folder deleted"),
+        let Some(user_id) = req.headers().get("user_id").and_then(|v| v.to_str().ok()) else {
+            return MetaHttpResponse::forbidden("");
+        };
+        match folders::delete_folder(&org_id, &folder_id, folder_type, query.force, user_id).await {
+            Ok(contents) => {
+                let body: FolderContentsResponseBody = contents.into();
+                HttpResponse::Ok().json(body)
+            }
             Err(err) => err.into(),
         }
     }
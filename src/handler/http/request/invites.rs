@@ -0,0 +1,222 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use std::io::Error;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+
+use crate::{
+    common::{
+        meta::{
+            http::HttpResponse as MetaHttpResponse,
+            invite::{AcceptInviteRequest, CreateInviteRequest, InviteList},
+        },
+        utils::auth::UserEmail,
+    },
+    service::invites,
+};
+
+/// CreateInvite
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Users",
+    operation_id = "CreateInvite",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = CreateInviteRequest, description = "Invitee email and role", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = CreateInviteResponse),
+        (status = 400, description = "Invalid email or role", content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Not Allowed", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/invites")]
+pub async fn create(
+    org_id: web::Path<String>,
+    req: web::Json<CreateInviteRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    match invites::create_invite(&org_id, req.into_inner(), &user_email.user_id).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// ListInvites
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Users",
+    operation_id = "ListInvites",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    responses(
+        (status = 200, description = "Pending invites, newest first", content_type = "application/json", body = InviteList),
+        (status = 403, description = "Not Allowed", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/invites")]
+pub async fn list(org_id: web::Path<String>, user_email: UserEmail) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    match invites::list_invites(&org_id, &user_email.user_id).await {
+        Ok(data) => Ok(HttpResponse::Ok().json(InviteList { data })),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// RevokeInvite
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Users",
+    operation_id = "RevokeInvite",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("invite_id" = String, Path, description = "Invite id"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Not Allowed", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "No pending invite with this id", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/invites/{invite_id}")]
+pub async fn revoke(
+    path: web::Path<(String, String)>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let (org_id, invite_id) = path.into_inner();
+    match invites::revoke_invite(&org_id, &invite_id, &user_email.user_id).await {
+        Ok(()) => Ok(MetaHttpResponse::ok("Invite revoked")),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// AcceptInvite
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "Users",
+    operation_id = "AcceptInvite",
+    request_body(content = AcceptInviteRequest, description = "Invite token and the password of the new user", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = AcceptInviteResponse),
+        (status = 400, description = "Password required", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "Unknown token", content_type = "application/json", body = HttpResponse),
+        (status = 409, description = "Invite already accepted", content_type = "application/json", body = HttpResponse),
+        (status = 410, description = "Invite expired or revoked", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/invites/accept")]
+pub async fn accept(req: web::Json<AcceptInviteRequest>) -> Result<HttpResponse, Error> {
+    match invites::accept_invite(req.into_inner()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+
+    use super::*;
+    use crate::common::{
+        infra::config::USERS,
+        meta::user::{User, UserRole},
+    };
+
+    fn set_up_member(org_id: &str, email: &str) {
+        USERS.insert(
+            format!("{org_id}/{email}"),
+            User {
+                email: email.to_string(),
+                first_name: "member".to_string(),
+                last_name: "".to_string(),
+                password: "pass#123".to_string(),
+                salt: String::new(),
+                token: "token".to_string(),
+                rum_token: None,
+                role: UserRole::Member,
+                org: org_id.to_string(),
+                is_external: false,
+                password_ext: None,
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_members_cant_list_or_revoke_invites() {
+        set_up_member("invites_org", "member@zo.dev");
+        let app = test::init_service(
+            App::new().service(web::scope("/api").service(list).service(revoke)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/invites_org/invites")
+            .insert_header(("user_id", "member@zo.dev"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::delete()
+            .uri("/api/invites_org/invites/2bxUIKQsfIfMzbmvmMdmjAyvHhP")
+            .insert_header(("user_id", "member@zo.dev"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // without a user there is nobody to check the permissions of
+        let req = test::TestRequest::get()
+            .uri("/api/invites_org/invites")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_org_named_invites_is_routed_to_the_org() {
+        // the org named "invites" is not shadowed by accepting an invite
+        set_up_member("invites", "member@zo.dev");
+        let app = test::init_service(
+            App::new()
+                .service(web::scope("/auth").service(accept))
+                .service(web::scope("/api").service(list)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/invites/invites")
+            .insert_header(("user_id", "member@zo.dev"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/auth/invites/accept")
+            .set_json(serde_json::json!({}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
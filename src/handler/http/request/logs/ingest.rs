@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Error;
+use std::{collections::HashMap, io::Error};
 
 use actix_web::{http, post, web, HttpRequest, HttpResponse};
 
@@ -24,7 +24,7 @@ use crate::{
             GCPIngestionRequest, IngestionRequest, KinesisFHIngestionResponse, KinesisFHRequest,
         },
     },
-    handler::http::request::{CONTENT_TYPE_JSON, CONTENT_TYPE_PROTO},
+    handler::http::request::{otlp_request_body, OtlpFormat},
     service::{
         logs,
         logs::otlp_http::{logs_json_handler, logs_proto_handler},
@@ -41,6 +41,7 @@ use crate::{
     ),
     params(
         ("org_id" = String, Path, description = "Organization name"),
+        ("errors" = Option<String>, Query, description = "Set to `positional` to return an item for every action, in payload order, with the position of failed ones"),
     ),
     request_body(content = String, description = "Ingest data (ndjson)", content_type = "application/json"),
     responses(
@@ -52,13 +53,17 @@ use crate::{
 pub async fn bulk(
     thread_id: web::Data<usize>,
     org_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
     body: web::Bytes,
     in_req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
     let org_id = org_id.into_inner();
     let user_email = in_req.headers().get("user_id").unwrap().to_str().unwrap();
+    let positional = query
+        .get("errors")
+        .is_some_and(|v| v.eq_ignore_ascii_case("positional"));
     Ok(
-        match logs::bulk::ingest(**thread_id, &org_id, body, user_email).await {
+        match logs::bulk::ingest(**thread_id, &org_id, body, user_email, positional).await {
             Ok(v) => MetaHttpResponse::json(v),
             Err(e) => {
                 log::error!("Error processing request {org_id}/_bulk: {:?}", e);
@@ -285,6 +290,9 @@ pub async fn handle_gcp_request(
     request_body(content = String, description = "ExportLogsServiceRequest", content_type = "application/x-protobuf"),
     responses(
         (status = 200, description = "Success", content_type = "application/json", body = IngestionResponse, example = json!({"code": 200})),
+        (status = 400, description = "Malformed payload", content_type = "application/json", body = HttpResponse),
+        (status = 413, description = "Payload too large after decompression", content_type = "application/json", body = HttpResponse),
+        (status = 415, description = "Unsupported content type or encoding", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
     )
 )]
@@ -296,48 +304,36 @@ pub async fn otlp_logs_write(
     body: web::Bytes,
 ) -> Result<HttpResponse, Error> {
     let org_id = org_id.into_inner();
-    let content_type = req.headers().get("Content-Type").unwrap().to_str().unwrap();
+    let (format, body) = match otlp_request_body(&req, body) {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
     let user_email = req.headers().get("user_id").unwrap().to_str().unwrap();
     let in_stream_name = req
         .headers()
         .get(&config::get_config().grpc.stream_header_key)
         .map(|header| header.to_str().unwrap());
-    if content_type.eq(CONTENT_TYPE_PROTO) {
-        // log::info!("otlp::logs_proto_handler");
-        match logs_proto_handler(**thread_id, &org_id, body, in_stream_name, user_email).await {
-            Ok(v) => Ok(v),
-            Err(e) => {
-                log::error!(
-                    "Error processing otlp pb logs write request {org_id}/{:?}: {:?}",
-                    in_stream_name,
-                    e
-                );
-                Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
-                    http::StatusCode::BAD_REQUEST.into(),
-                    e.to_string(),
-                )))
-            }
+    let res = match format {
+        OtlpFormat::Proto => {
+            logs_proto_handler(**thread_id, &org_id, body, in_stream_name, user_email).await
         }
-    } else if content_type.starts_with(CONTENT_TYPE_JSON) {
-        // log::info!("otlp::logs_json_handler");
-        match logs_json_handler(**thread_id, &org_id, body, in_stream_name, user_email).await {
-            Ok(v) => Ok(v),
-            Err(e) => {
-                log::error!(
-                    "Error processing otlp json logs write request {org_id}/{:?}: {:?}",
-                    in_stream_name,
-                    e
-                );
-                Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
-                    http::StatusCode::BAD_REQUEST.into(),
-                    e.to_string(),
-                )))
-            }
+        OtlpFormat::Json => {
+            logs_json_handler(**thread_id, &org_id, body, in_stream_name, user_email).await
+        }
+    };
+    match res {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            log::error!(
+                "Error processing otlp {:?} logs write request {org_id}/{:?}: {:?}",
+                format,
+                in_stream_name,
+                e
+            );
+            Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                e.to_string(),
+            )))
         }
-    } else {
-        Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
-            http::StatusCode::BAD_REQUEST.into(),
-            "Bad Request".to_string(),
-        )))
     }
 }
@@ -19,7 +19,7 @@ use actix_web::{http, post, web, HttpRequest, HttpResponse};
 
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
-    handler::http::request::{CONTENT_TYPE_JSON, CONTENT_TYPE_PROTO},
+    handler::http::request::{otlp_request_body, OtlpFormat},
     service::metrics,
 };
 
@@ -64,6 +64,9 @@ pub async fn json(org_id: web::Path<String>, body: web::Bytes) -> Result<HttpRes
     request_body(content = String, description = "ExportMetricsServiceRequest", content_type = "application/x-protobuf"),
     responses(
         (status = 200, description = "Success", content_type = "application/json", body = IngestionResponse, example = json!({"code": 200})),
+        (status = 400, description = "Malformed payload", content_type = "application/json", body = HttpResponse),
+        (status = 413, description = "Payload too large after decompression", content_type = "application/json", body = HttpResponse),
+        (status = 415, description = "Unsupported content type or encoding", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
     )
 )]
@@ -74,15 +77,12 @@ pub async fn otlp_metrics_write(
     body: web::Bytes,
 ) -> Result<HttpResponse, Error> {
     let org_id = org_id.into_inner();
-    let content_type = req.headers().get("Content-Type").unwrap().to_str().unwrap();
-    if content_type.eq(CONTENT_TYPE_PROTO) {
-        metrics::otlp::otlp_proto(&org_id, body).await
-    } else if content_type.starts_with(CONTENT_TYPE_JSON) {
-        metrics::otlp::otlp_json(&org_id, body).await
-    } else {
-        Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
-            http::StatusCode::BAD_REQUEST.into(),
-            "Bad Request".to_string(),
-        )))
+    let (format, body) = match otlp_request_body(&req, body) {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
+    match format {
+        OtlpFormat::Proto => metrics::otlp::otlp_proto(&org_id, body).await,
+        OtlpFormat::Json => metrics::otlp::otlp_json(&org_id, body).await,
     }
 }
@@ -13,16 +13,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use config::get_config;
+
+use crate::common::{
+    meta::http::HttpResponse as MetaHttpResponse,
+    utils::compression::{decode_request_body, RequestBodyError},
+};
+
 #[cfg(feature = "enterprise")]
 pub mod actions;
 pub mod alerts;
 pub mod authz;
+pub mod branding;
 pub mod clusters;
 pub mod dashboards;
 pub mod enrichment_table;
 #[allow(deprecated)]
 pub mod folders;
 pub mod functions;
+pub mod invites;
 #[cfg(feature = "enterprise")]
 pub mod keys;
 pub mod kv;
@@ -31,6 +41,7 @@ pub mod metrics;
 pub mod organization;
 pub mod pipeline;
 pub mod promql;
+pub mod retention;
 pub mod rum;
 #[cfg(feature = "enterprise")]
 pub mod script_server;
@@ -39,10 +50,156 @@ pub mod service_accounts;
 pub mod short_url;
 pub mod status;
 pub mod stream;
+pub mod synthetic;
 pub mod syslog;
 pub mod traces;
+pub mod trash;
 pub mod users;
 pub mod websocket;
 
 pub const CONTENT_TYPE_JSON: &str = "application/json";
 pub const CONTENT_TYPE_PROTO: &str = "application/x-protobuf";
+
+/// Payload formats of the OTLP/HTTP endpoints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtlpFormat {
+    Proto,
+    Json,
+}
+
+/// Returns the payload format of an OTLP/HTTP request and its body,
+/// decompressed by its `Content-Encoding` within `ZO_PAYLOAD_LIMIT`. The error
+/// response tells unsupported content types and encodings (415) apart from
+/// bodies that don't decompress (400) or inflate past the limit (413).
+pub fn otlp_request_body(
+    req: &HttpRequest,
+    body: web::Bytes,
+) -> Result<(OtlpFormat, web::Bytes), HttpResponse> {
+    decode_otlp_request(req, body, get_config().limit.req_payload_limit)
+}
+
+fn decode_otlp_request(
+    req: &HttpRequest,
+    body: web::Bytes,
+    limit: usize,
+) -> Result<(OtlpFormat, web::Bytes), HttpResponse> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let format = match mime.as_str() {
+        CONTENT_TYPE_PROTO => OtlpFormat::Proto,
+        CONTENT_TYPE_JSON => OtlpFormat::Json,
+        _ => {
+            return Err(MetaHttpResponse::unsupported_media_type(format!(
+                "Unsupported content type {content_type:?}, expected {CONTENT_TYPE_PROTO} or {CONTENT_TYPE_JSON}"
+            )));
+        }
+    };
+    let encoding = req
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok());
+    match decode_request_body(encoding, body, limit) {
+        Ok(body) => Ok((format, body)),
+        Err(e @ RequestBodyError::UnsupportedEncoding(_)) => {
+            Err(MetaHttpResponse::unsupported_media_type(e))
+        }
+        Err(e @ RequestBodyError::Malformed(..)) => Err(MetaHttpResponse::bad_request(e)),
+        Err(e @ RequestBodyError::TooLarge(_)) => Err(MetaHttpResponse::payload_too_large(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use actix_web::{http::StatusCode, test};
+    use flate2::{write::GzEncoder, Compression};
+    use opentelemetry_proto::tonic::{
+        collector::logs::v1::ExportLogsServiceRequest,
+        common::v1::{any_value::Value, AnyValue},
+        logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+    };
+    use prost::Message;
+
+    use super::*;
+
+    fn logs_request(body: &str) -> ExportLogsServiceRequest {
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1_700_000_000_000_000_000,
+                        body: Some(AnyValue {
+                            value: Some(Value::StringValue(body.to_string())),
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn gzip(data: &[u8]) -> web::Bytes {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        web::Bytes::from(encoder.finish().unwrap())
+    }
+
+    fn otlp_request(content_type: &str, content_encoding: Option<&str>) -> HttpRequest {
+        let mut req = test::TestRequest::post().insert_header((header::CONTENT_TYPE, content_type));
+        if let Some(encoding) = content_encoding {
+            req = req.insert_header((header::CONTENT_ENCODING, encoding));
+        }
+        req.to_http_request()
+    }
+
+    #[test]
+    fn test_otlp_request_gzip_proto() {
+        let logs = logs_request("GET /index.html 200");
+        let body = gzip(&logs.encode_to_vec());
+        let req = otlp_request(CONTENT_TYPE_PROTO, Some("gzip"));
+        let (format, body) = decode_otlp_request(&req, body, 1024).unwrap();
+        assert_eq!(format, OtlpFormat::Proto);
+        assert_eq!(ExportLogsServiceRequest::decode(body).unwrap(), logs);
+
+        let req = otlp_request("application/json; charset=utf-8", None);
+        let (format, _) = decode_otlp_request(&req, web::Bytes::from("{}"), 1024).unwrap();
+        assert_eq!(format, OtlpFormat::Json);
+    }
+
+    #[test]
+    fn test_otlp_request_oversized_after_decompression() {
+        let logs = logs_request(&"x".repeat(64 * 1024));
+        let body = gzip(&logs.encode_to_vec());
+        assert!(body.len() < 1024);
+        let req = otlp_request(CONTENT_TYPE_PROTO, Some("gzip"));
+        let resp = decode_otlp_request(&req, body, 1024).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_otlp_request_errors() {
+        let body = web::Bytes::from_static(b"foo");
+        let req = otlp_request("text/plain", None);
+        let resp = decode_otlp_request(&req, body.clone(), 1024).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let req = otlp_request(CONTENT_TYPE_PROTO, Some("br"));
+        let resp = decode_otlp_request(&req, body, 1024).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let req = otlp_request(CONTENT_TYPE_PROTO, Some("gzip"));
+        let truncated = gzip(&[0; 64]).slice(..12);
+        let resp = decode_otlp_request(&req, truncated, 1024).unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
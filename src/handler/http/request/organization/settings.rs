@@ -101,6 +101,11 @@ async fn create(
         }
     }
 
+    if let Some(search_max_concurrency) = settings.search_max_concurrency {
+        field_found = true;
+        data.search_max_concurrency = Some(search_max_concurrency);
+    }
+
     if !field_found {
         return Ok(MetaHttpResponse::bad_request("No valid field found"));
     }
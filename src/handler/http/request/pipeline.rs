@@ -21,15 +21,25 @@ use config::{ider, meta::pipeline::Pipeline};
 
 use crate::{
     common::meta::http::HttpResponse as MetaHttpResponse,
-    service::{db::pipeline::PipelineError, pipeline},
+    service::{
+        db::pipeline::PipelineError,
+        pipeline::{
+            self,
+            backfill::{self, BackfillRequest},
+        },
+    },
 };
 
 impl From<PipelineError> for HttpResponse {
     fn from(value: PipelineError) -> Self {
         match value {
             PipelineError::InfraError(err) => MetaHttpResponse::internal_error(err),
-            PipelineError::NotFound(_) => MetaHttpResponse::not_found(value),
-            PipelineError::Modified(_) => MetaHttpResponse::conflict(value),
+            PipelineError::NotFound(_) | PipelineError::BackfillNotFound(_) => {
+                MetaHttpResponse::not_found(value)
+            }
+            PipelineError::Modified(_) | PipelineError::BackfillOverlap(_) => {
+                MetaHttpResponse::conflict(value)
+            }
             error => MetaHttpResponse::bad_request(error),
         }
     }
@@ -258,3 +268,93 @@ pub async fn enable_pipeline(
         Err(e) => Ok(e.into()),
     }
 }
+
+/// GetPipelineStats
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Pipelines",
+    operation_id = "getPipelineStats",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("pipeline_id" = String, Path, description = "Pipeline ID"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = PipelineStatsSnapshot),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/pipelines/{pipeline_id}/stats")]
+pub async fn get_pipeline_stats(
+    path: web::Path<(String, String)>,
+    _req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, pipeline_id) = path.into_inner();
+    match pipeline::get_pipeline_stats(&org_id, &pipeline_id).await {
+        Ok(stats) => Ok(HttpResponse::Ok().json(stats)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// CreatePipelineBackfill
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Pipelines",
+    operation_id = "createPipelineBackfill",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("pipeline_id" = String, Path, description = "Pipeline ID"),
+    ),
+    request_body(content = BackfillRequest, description = "Time range to backfill", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BackfillJob),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+        (status = 409, description = "Conflict", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/pipelines/{pipeline_id}/_backfill")]
+pub async fn create_backfill(
+    path: web::Path<(String, String)>,
+    req: web::Json<BackfillRequest>,
+) -> Result<HttpResponse, Error> {
+    let (org_id, pipeline_id) = path.into_inner();
+    match backfill::create_backfill(&org_id, &pipeline_id, req.into_inner()).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(job)),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// GetPipelineBackfill
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Pipelines",
+    operation_id = "getPipelineBackfill",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("pipeline_id" = String, Path, description = "Pipeline ID"),
+        ("job_id" = String, Path, description = "Backfill job ID"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BackfillJob),
+        (status = 404, description = "NotFound", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/pipelines/{pipeline_id}/_backfill/{job_id}")]
+pub async fn get_backfill(
+    path: web::Path<(String, String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (org_id, pipeline_id, job_id) = path.into_inner();
+    match backfill::get_backfill(&org_id, &pipeline_id, &job_id).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(job)),
+        Err(e) => Ok(e.into()),
+    }
+}
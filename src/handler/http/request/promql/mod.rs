@@ -15,7 +15,7 @@
 
 use std::io::Error;
 
-use actix_web::{get, http, post, web, HttpRequest, HttpResponse};
+use actix_web::{delete, get, http, post, web, HttpRequest, HttpResponse};
 use config::utils::time::{parse_milliseconds, parse_str_to_timestamp_micros};
 use infra::errors;
 use promql_parser::parser;
@@ -23,7 +23,13 @@ use promql_parser::parser;
 use {config::meta::stream::StreamType, o2_openfga::meta::mapping::OFGA_MODELS};
 
 use crate::{
-    common::{meta::http::HttpResponse as MetaHttpResponse, utils::http::get_or_create_trace_id},
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse,
+        utils::{
+            auth::{is_root_user, UserEmail},
+            http::get_or_create_trace_id,
+        },
+    },
     service::{metrics, promql},
 };
 
@@ -988,6 +994,139 @@ fn format_query(_org_id: &str, query: &str, _in_req: HttpRequest) -> Result<Http
     Ok(HttpResponse::Ok().json(promql::ApiFuncResponse::ok(expr.prettify(), None)))
 }
 
+/// Metrics result cache status of the org on this node
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Metrics",
+    operation_id = "PrometheusCacheStatus",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("top" = Option<usize>, Query, description = "Number of queries with the most cached entries to return, defaults to 10"),
+        ("all_orgs" = Option<bool>, Query, description = "Include the queries of every org, only for the root user"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = promql::search::cache::CacheStatus, example = json!({
+            "buckets": [0, 2, 1],
+            "total_entries": 3,
+            "total_bytes": 20480,
+            "top_queries": [{"query_hash": "b235015c612525ad7c11c109e3fdc261", "org_id": "default", "query": "up", "entries": 2, "bytes": 16384}]
+        })),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/prometheus/cache/status")]
+pub async fn cache_status(
+    org_id: web::Path<String>,
+    req: web::Query<config::meta::promql::RequestCacheStatus>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = match cache_scope(&org_id, req.all_orgs, &user_email) {
+        Ok(org_id) => org_id,
+        Err(resp) => return Ok(resp),
+    };
+    let status = promql::search::cache::status(org_id, req.top.unwrap_or(10)).await;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// The org whose cache entries a request acts on, `None` for every org which
+/// only the root user may ask for
+fn cache_scope<'a>(
+    org_id: &'a str,
+    all_orgs: bool,
+    user_email: &UserEmail,
+) -> Result<Option<&'a str>, HttpResponse> {
+    if !all_orgs {
+        return Ok(Some(org_id));
+    }
+    if !is_root_user(&user_email.user_id) {
+        return Err(MetaHttpResponse::forbidden(
+            "only the root user can access the cache of every org",
+        ));
+    }
+    Ok(None)
+}
+
+/// Clear entries of the metrics result cache of the org on this node
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Metrics",
+    operation_id = "PrometheusCacheDelete",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("query_hash" = Option<String>, Query, description = "Hash key of the cached query, as returned by the cache status"),
+        ("query" = Option<String>, Query, description = "Prometheus expression query string, can also be sent in a JSON body"),
+        ("step" = Option<String>, Query, description = "Query resolution step width in duration format or float number of seconds, entries of every step are removed if left empty"),
+        ("all_orgs" = Option<bool>, Query, description = "Remove the entries of every org, only for the root user"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/prometheus/cache")]
+pub async fn cache_delete(
+    org_id: web::Path<String>,
+    req: web::Query<config::meta::promql::RequestCacheDelete>,
+    body: web::Bytes,
+    user_email: UserEmail,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let trace_id = get_or_create_trace_id(in_req.headers(), &tracing::Span::none());
+    let mut req = req.into_inner();
+    if !body.is_empty() {
+        match config::utils::json::from_slice::<config::meta::promql::RequestCacheDelete>(&body) {
+            Ok(v) => {
+                req.query = v.query.or(req.query);
+                req.step = v.step.or(req.step);
+                req.query_hash = v.query_hash.or(req.query_hash);
+                req.all_orgs |= v.all_orgs;
+            }
+            Err(e) => {
+                return Ok(MetaHttpResponse::bad_request(e));
+            }
+        }
+    }
+
+    let org_id = match cache_scope(&org_id, req.all_orgs, &user_email) {
+        Ok(org_id) => org_id,
+        Err(resp) => return Ok(resp),
+    };
+    let removed = match (req.query_hash, req.query) {
+        (Some(query_hash), _) => {
+            promql::search::cache::delete_by_hash(&trace_id, org_id, &query_hash).await
+        }
+        (None, Some(query)) => {
+            let step = match req.step {
+                None => None,
+                Some(v) => match parse_milliseconds(&v) {
+                    Ok(v) => Some((v * 1_000) as i64),
+                    Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+                },
+            };
+            promql::search::cache::delete_by_query(&trace_id, org_id, &query, step).await
+        }
+        (None, None) => {
+            return Ok(MetaHttpResponse::bad_request(
+                "either query_hash or query is required",
+            ));
+        }
+    };
+    match removed {
+        Ok(removed) => Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
+            http::StatusCode::OK.into(),
+            format!("removed {removed} cache entries"),
+        ))),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
 fn search_timeout(timeout: Option<String>) -> i64 {
     match timeout {
         None => 0,
@@ -0,0 +1,74 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use config::get_config;
+
+use crate::{
+    common::meta::http::HttpResponse as MetaHttpResponse,
+    handler::http::models::retention::RetentionPreviewQuery,
+    service::compact::retention::{self, RetentionPreviewPage},
+};
+
+/// RetentionPreview
+///
+/// Reports per stream what the retention would delete if the org retention was
+/// `days`, without creating any delete job.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Retention",
+    operation_id = "RetentionPreview",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        RetentionPreviewQuery,
+    ),
+    responses(
+        (status = StatusCode::OK, body = RetentionPreviewPage),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid parameters", body = HttpResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = HttpResponse),
+    ),
+)]
+#[get("/{org_id}/retention/preview")]
+pub async fn preview(
+    path: web::Path<String>,
+    query: web::Query<RetentionPreviewQuery>,
+) -> impl Responder {
+    let org_id = path.into_inner();
+    let query = query.into_inner();
+    let days = query
+        .days
+        .unwrap_or_else(|| get_config().compact.data_retention_days);
+    if days <= 0 {
+        return MetaHttpResponse::bad_request("days must be greater than 0");
+    }
+    if query.page_size == 0 {
+        return MetaHttpResponse::bad_request("page_size must be greater than 0");
+    }
+    match retention::preview(
+        &org_id,
+        days,
+        query.stream.as_deref(),
+        query.page_size,
+        query.page_idx,
+    )
+    .await
+    {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => MetaHttpResponse::internal_error(e),
+    }
+}
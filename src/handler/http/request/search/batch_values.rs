@@ -0,0 +1,475 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Query planning of the batched `_values` endpoint. The fields of a batch are
+//! answered by at most two queries, one on the distinct values stream for the
+//! fields it covers and one on the stream itself for the others.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::ControlFlow,
+};
+
+use config::{
+    meta::stream::{StreamSettings, StreamType},
+    utils::json,
+    DISTINCT_FIELDS, TIMESTAMP_COL_NAME,
+};
+use sqlparser::{
+    ast::{visit_expressions, visit_relations, Expr, FunctionArguments},
+    dialect::PostgreSqlDialect,
+    parser::Parser,
+    tokenizer::Token,
+};
+
+use crate::{
+    handler::http::models::values::{FieldValue, FieldValues},
+    service::metadata::distinct_values::DISTINCT_STREAM_PREFIX,
+};
+
+/// A query answering the values of some fields of a batch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValuesQuery {
+    pub stream_type: StreamType,
+    pub fields: Vec<String>,
+    pub sql: String,
+}
+
+/// Whether the distinct values stream has the `fields` and every field used
+/// by `query_sql` since `start_time`.
+pub fn distinct_stream_covers(
+    settings: &StreamSettings,
+    fields: &[String],
+    query_sql: &str,
+    start_time: i64,
+) -> bool {
+    let is_distinct = |f: &String| {
+        DISTINCT_FIELDS.contains(f)
+            || settings
+                .distinct_value_fields
+                .iter()
+                .any(|entry| entry.name == *f && entry.added_ts <= start_time)
+    };
+
+    // all fields which are requested must be in the distinct stream
+    if !fields.iter().all(is_distinct) {
+        return false;
+    }
+
+    // all the fields used in the query sent must be in the distinct stream
+    match config::meta::sql::Sql::new(query_sql) {
+        // if sql is invalid, we let it follow the original search and fail
+        Err(_) => false,
+        Ok(sql) => sql
+            .fields
+            .iter()
+            .filter(|f| *f != TIMESTAMP_COL_NAME) // _timestamp is hardcoded in queries
+            .all(is_distinct),
+    }
+}
+
+/// Parses the user filter of a batch as a single expression on the stream and
+/// returns it rendered back to SQL, only the rendered filter may be used to
+/// build the queries. Subqueries and references to other tables are rejected
+/// as they would read streams the caller has no permission on.
+pub fn parse_filter(filter: &str) -> Result<String, String> {
+    if filter.is_empty() {
+        return Ok(String::new());
+    }
+    let dialect = PostgreSqlDialect {};
+    let mut parser = Parser::new(&dialect)
+        .try_with_sql(filter)
+        .map_err(|e| format!("invalid filter: {e}"))?;
+    let expr = parser
+        .parse_expr()
+        .map_err(|e| format!("invalid filter: {e}"))?;
+    if parser.peek_token().token != Token::EOF {
+        return Err("invalid filter: it must be a single expression".to_string());
+    }
+
+    let has_relation = visit_relations(&expr, |_| ControlFlow::Break(())).is_break();
+    let has_subquery = visit_expressions(&expr, |e| match e {
+        Expr::Subquery(_) | Expr::Exists { .. } | Expr::InSubquery { .. } => ControlFlow::Break(()),
+        Expr::Function(f) if matches!(f.args, FunctionArguments::Subquery(_)) => {
+            ControlFlow::Break(())
+        }
+        Expr::CompoundIdentifier(_) | Expr::QualifiedWildcard(_) => ControlFlow::Break(()),
+        _ => ControlFlow::Continue(()),
+    })
+    .is_break();
+    if has_relation || has_subquery {
+        return Err("invalid filter: subqueries and other tables are not allowed".to_string());
+    }
+    Ok(expr.to_string())
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_str(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// The query of one field, the fields of a batch are combined with UNION ALL
+fn field_sql(
+    field: &str,
+    size: usize,
+    from: &str,
+    filter: &str,
+    keyword: &str,
+    count_fn: &str,
+) -> String {
+    let mut conditions = Vec::new();
+    if !filter.is_empty() {
+        conditions.push(format!("({filter})"));
+    }
+    if !keyword.is_empty() {
+        conditions.push(format!(
+            "{} ILIKE {}",
+            quote_ident(field),
+            quote_str(&format!("%{keyword}%"))
+        ));
+    }
+    let sql_where = if conditions.is_empty() {
+        "".to_string()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+    format!(
+        "(SELECT {} AS zo_sql_field, CAST({} AS VARCHAR) AS zo_sql_key, {count_fn} AS zo_sql_num FROM {}{sql_where} GROUP BY zo_sql_key ORDER BY zo_sql_num DESC LIMIT {size})",
+        quote_str(field),
+        quote_ident(field),
+        quote_ident(from),
+    )
+}
+
+/// Builds the query of the `fields` on the stream or its distinct values
+/// stream.
+pub fn build_query(
+    stream_name: &str,
+    stream_type: StreamType,
+    fields: &[(String, usize)],
+    filter: &str,
+    keyword: &str,
+    use_distinct_stream: bool,
+) -> ValuesQuery {
+    let (from, count_fn, query_stream_type) = if use_distinct_stream {
+        // the distinct stream has partially aggregated counts, so sum them
+        (
+            format!(
+                "{DISTINCT_STREAM_PREFIX}_{}_{stream_name}",
+                stream_type.as_str()
+            ),
+            "SUM(count)",
+            StreamType::Metadata,
+        )
+    } else {
+        (stream_name.to_string(), "COUNT(*)", stream_type)
+    };
+    let sql = fields
+        .iter()
+        .map(|(field, size)| field_sql(field, *size, &from, filter, keyword, count_fn))
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    ValuesQuery {
+        stream_type: query_stream_type,
+        fields: fields.iter().map(|(field, _)| field.clone()).collect(),
+        sql,
+    }
+}
+
+/// Splits the fields into the ones the distinct values stream covers and the
+/// others, and builds one query for each non empty group. The `filter` must be
+/// the one returned by [`parse_filter`].
+pub fn build_queries(
+    stream_name: &str,
+    stream_type: StreamType,
+    settings: &StreamSettings,
+    fields: &[(String, usize)],
+    filter: &str,
+    keyword: &str,
+    start_time: i64,
+) -> Vec<ValuesQuery> {
+    let filter_sql = if filter.is_empty() {
+        format!(
+            "SELECT {TIMESTAMP_COL_NAME} FROM {}",
+            quote_ident(stream_name)
+        )
+    } else {
+        format!(
+            "SELECT {TIMESTAMP_COL_NAME} FROM {} WHERE {filter}",
+            quote_ident(stream_name)
+        )
+    };
+    let has_distinct_stream = matches!(stream_type, StreamType::Logs | StreamType::Traces);
+    let (distinct, raw): (Vec<_>, Vec<_>) = fields.iter().cloned().partition(|(field, _)| {
+        has_distinct_stream
+            && distinct_stream_covers(
+                settings,
+                std::slice::from_ref(field),
+                &filter_sql,
+                start_time,
+            )
+    });
+
+    let mut queries = Vec::with_capacity(2);
+    if !distinct.is_empty() {
+        queries.push(build_query(
+            stream_name,
+            stream_type,
+            &distinct,
+            filter,
+            keyword,
+            true,
+        ));
+    }
+    if !raw.is_empty() {
+        queries.push(build_query(
+            stream_name,
+            stream_type,
+            &raw,
+            filter,
+            keyword,
+            false,
+        ));
+    }
+    queries
+}
+
+/// Collects the hits of a query into the top values of each field, keeping at
+/// most `sizes[field]` values.
+pub fn collect_values(
+    hits: &[json::Value],
+    sizes: &HashMap<String, usize>,
+) -> BTreeMap<String, Vec<FieldValue>> {
+    let mut counts: HashMap<String, HashMap<String, i64>> = HashMap::new();
+    for row in hits {
+        let Some(field) = row.get("zo_sql_field").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let key = row
+            .get("zo_sql_key")
+            .map(json::get_string_value)
+            .unwrap_or_default();
+        let num = row.get("zo_sql_num").and_then(|v| v.as_i64()).unwrap_or(0);
+        *counts
+            .entry(field.to_string())
+            .or_default()
+            .entry(key)
+            .or_insert(0) += num;
+    }
+    counts
+        .into_iter()
+        .map(|(field, values)| {
+            let mut values = values
+                .into_iter()
+                .map(|(value, count)| FieldValue { value, count })
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            values.truncate(sizes.get(&field).copied().unwrap_or_default());
+            (field, values)
+        })
+        .collect()
+}
+
+/// Fills in the values of the fields of a query that succeeded, fields without
+/// any hits get an empty list.
+pub fn fill_values(
+    out: &mut BTreeMap<String, FieldValues>,
+    fields: &[String],
+    hits: &[json::Value],
+    sizes: &HashMap<String, usize>,
+) {
+    let mut values = collect_values(hits, sizes);
+    for field in fields {
+        out.insert(
+            field.clone(),
+            FieldValues {
+                values: values.remove(field).unwrap_or_default(),
+                error: None,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::meta::stream::DistinctField;
+
+    use super::*;
+
+    fn settings() -> StreamSettings {
+        StreamSettings {
+            distinct_value_fields: vec![
+                DistinctField {
+                    name: "k8s_namespace".to_string(),
+                    added_ts: 100,
+                },
+                DistinctField {
+                    name: "k8s_pod".to_string(),
+                    added_ts: 5000,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn fields(names: &[&str]) -> Vec<(String, usize)> {
+        names.iter().map(|n| (n.to_string(), 10)).collect()
+    }
+
+    #[test]
+    fn test_build_queries_mixed_fields() {
+        let queries = build_queries(
+            "app",
+            StreamType::Logs,
+            &settings(),
+            &fields(&["k8s_namespace", "level", "service_name", "k8s_pod"]),
+            "",
+            "",
+            1000,
+        );
+        assert_eq!(queries.len(), 2);
+
+        // k8s_pod was only added to the distinct stream after start_time
+        let distinct = &queries[0];
+        assert_eq!(distinct.stream_type, StreamType::Metadata);
+        assert_eq!(distinct.fields, vec!["k8s_namespace", "service_name"]);
+        assert!(distinct.sql.contains("FROM \"distinct_values_logs_app\""));
+        assert!(distinct.sql.contains("SUM(count) AS zo_sql_num"));
+        assert_eq!(distinct.sql.matches(" UNION ALL ").count(), 1);
+
+        let raw = &queries[1];
+        assert_eq!(raw.stream_type, StreamType::Logs);
+        assert_eq!(raw.fields, vec!["level", "k8s_pod"]);
+        assert!(raw.sql.contains("FROM \"app\""));
+        assert!(raw.sql.contains("COUNT(*) AS zo_sql_num"));
+        assert!(!raw.sql.contains("distinct_values"));
+    }
+
+    #[test]
+    fn test_build_queries_filter_decides_source() {
+        // the filter uses a field the distinct stream doesn't have
+        let queries = build_queries(
+            "app",
+            StreamType::Logs,
+            &settings(),
+            &fields(&["k8s_namespace", "level"]),
+            "level = 'error'",
+            "",
+            1000,
+        );
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].stream_type, StreamType::Logs);
+        assert_eq!(queries[0].fields, vec!["k8s_namespace", "level"]);
+        assert!(queries[0].sql.contains("WHERE (level = 'error')"));
+
+        let queries = build_queries(
+            "app",
+            StreamType::Logs,
+            &settings(),
+            &fields(&["k8s_namespace"]),
+            "k8s_namespace = 'prod'",
+            "web",
+            1000,
+        );
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].stream_type, StreamType::Metadata);
+        assert!(queries[0]
+            .sql
+            .contains("WHERE (k8s_namespace = 'prod') AND \"k8s_namespace\" ILIKE '%web%'"));
+
+        // only logs and traces have a distinct values stream
+        let queries = build_queries(
+            "cpu",
+            StreamType::Metrics,
+            &settings(),
+            &fields(&["k8s_namespace"]),
+            "",
+            "",
+            1000,
+        );
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].stream_type, StreamType::Metrics);
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(parse_filter("").unwrap(), "");
+        assert_eq!(
+            parse_filter("level = 'error' AND code >= 500").unwrap(),
+            "level = 'error' AND code >= 500"
+        );
+        assert_eq!(
+            parse_filter("str_match(message, 'timeout') -- trailing comment").unwrap(),
+            "str_match(message, 'timeout')"
+        );
+
+        for filter in [
+            "1=1) UNION SELECT name, name, 1 FROM \"other_stream\" --",
+            "1=1; DROP TABLE app",
+            "level IN (SELECT level FROM \"other_stream\")",
+            "EXISTS (SELECT 1 FROM \"other_stream\")",
+            "(SELECT max(code) FROM \"other_stream\") > 0",
+            "\"other_stream\".level = 'error'",
+        ] {
+            assert!(parse_filter(filter).is_err(), "{filter}");
+        }
+    }
+
+    #[test]
+    fn test_field_sql_is_bounded_and_quoted() {
+        let sql = field_sql("it's", 5, "app", "", "o'k", "COUNT(*)");
+        assert_eq!(
+            sql,
+            "(SELECT 'it''s' AS zo_sql_field, CAST(\"it's\" AS VARCHAR) AS zo_sql_key, COUNT(*) AS zo_sql_num FROM \"app\" WHERE \"it's\" ILIKE '%o''k%' GROUP BY zo_sql_key ORDER BY zo_sql_num DESC LIMIT 5)"
+        );
+    }
+
+    #[test]
+    fn test_fill_values() {
+        let hits = vec![
+            json::json!({"zo_sql_field": "level", "zo_sql_key": "info", "zo_sql_num": 10}),
+            json::json!({"zo_sql_field": "level", "zo_sql_key": "error", "zo_sql_num": 30}),
+            json::json!({"zo_sql_field": "level", "zo_sql_key": "warn", "zo_sql_num": 20}),
+            json::json!({"zo_sql_field": "host", "zo_sql_key": "a", "zo_sql_num": 1}),
+        ];
+        let sizes = HashMap::from([
+            ("level".to_string(), 2),
+            ("host".to_string(), 10),
+            ("pod".to_string(), 10),
+        ]);
+        let mut out = BTreeMap::new();
+        let fields = vec!["level".to_string(), "host".to_string(), "pod".to_string()];
+        fill_values(&mut out, &fields, &hits, &sizes);
+
+        assert_eq!(
+            out["level"].values,
+            vec![
+                FieldValue {
+                    value: "error".to_string(),
+                    count: 30
+                },
+                FieldValue {
+                    value: "warn".to_string(),
+                    count: 20
+                },
+            ]
+        );
+        assert_eq!(out["host"].values.len(), 1);
+        assert_eq!(out["pod"], FieldValues::default());
+    }
+}
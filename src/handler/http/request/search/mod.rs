@@ -15,7 +15,11 @@
 
 use std::{collections::HashMap, io::Error};
 
-use actix_web::{get, http::StatusCode, post, web, HttpRequest, HttpResponse};
+use actix_web::{
+    delete, get,
+    http::{header, StatusCode},
+    post, web, HttpRequest, HttpResponse,
+};
 use arrow_schema::Schema;
 use chrono::{Duration, Utc};
 use config::{
@@ -23,12 +27,12 @@ use config::{
     meta::{
         search::{SearchEventType, SearchHistoryHitResponse},
         self_reporting::usage::{RequestStats, UsageType, USAGE_STREAM},
-        sql::resolve_stream_names,
+        sql::resolve_stream_names_with_types,
         stream::StreamType,
     },
     metrics,
     utils::{base64, json},
-    DISTINCT_FIELDS, META_ORG_ID, TIMESTAMP_COL_NAME,
+    META_ORG_ID, TIMESTAMP_COL_NAME,
 };
 use infra::{cache::stats, errors};
 use tracing::{Instrument, Span};
@@ -41,15 +45,15 @@ use crate::{
     common::{
         meta::{self, http::HttpResponse as MetaHttpResponse},
         utils::{
-            functions,
+            auth, functions,
             http::{
                 get_or_create_trace_id, get_search_event_context_from_request,
                 get_search_type_from_request, get_stream_type_from_request,
                 get_use_cache_from_request, get_work_group,
             },
-            stream::get_settings_max_query_range,
         },
     },
+    handler::http::models::values::{BatchValuesRequest, BatchValuesResponse, FieldValues},
     service::{
         metadata::distinct_values::DISTINCT_STREAM_PREFIX,
         search as SearchService,
@@ -57,9 +61,12 @@ use crate::{
     },
 };
 
+pub mod batch_values;
 pub mod multi_streams;
 #[cfg(feature = "enterprise")]
 pub mod query_manager;
+#[cfg(not(feature = "enterprise"))]
+pub mod queue;
 pub mod saved_view;
 #[cfg(feature = "enterprise")]
 pub mod search_job;
@@ -81,40 +88,20 @@ async fn can_use_distinct_stream(
     let stream_settings = infra::schema::get_settings(org, stream_name, stream_type)
         .await
         .unwrap_or_default();
+    batch_values::distinct_stream_covers(&stream_settings, fields, query_sql, start_time)
+}
 
-    // all fields which are requested must be in the distinct stream
-    let all_fields_distinct = fields.iter().all(|f| {
-        if DISTINCT_FIELDS.contains(f) {
-            return true;
-        }
-        stream_settings
-            .distinct_value_fields
-            .iter()
-            .any(|entry| entry.name == *f && entry.added_ts <= start_time)
-    });
-
-    // all the fields used in the query sent must be in the distinct stream
-    let query_fields: Vec<_> = match config::meta::sql::Sql::new(query_sql) {
-        // if sql is invalid, we let it follow the original search and fail
-        Err(_) => return false,
-        Ok(sql) => sql
-            .fields
-            .into_iter()
-            .filter(|f| f != "_timestamp")// _timestamp is hardcoded in queries
-            .collect(),
-    };
-
-    let all_query_fields_distinct = query_fields.iter().all(|f| {
-        if DISTINCT_FIELDS.contains(f) {
-            return true;
-        }
-        stream_settings
-            .distinct_value_fields
-            .iter()
-            .any(|entry| entry.name == *f && entry.added_ts <= start_time)
-    });
-
-    all_fields_distinct && all_query_fields_distinct
+/// Returns the forbidden response for a request that brings an ad-hoc VRL function
+/// (`query_fn`) the user may not run, see [`auth::check_adhoc_function_permission`].
+pub(crate) async fn check_query_fn_permission(
+    org_id: &str,
+    user_id: &str,
+    query_fn: Option<&str>,
+) -> Option<HttpResponse> {
+    auth::check_adhoc_function_permission(org_id, user_id, query_fn)
+        .await
+        .err()
+        .map(|code| HttpResponse::Forbidden().json(meta::http::HttpResponse::error_code(code)))
 }
 
 /// SearchStreamData
@@ -127,6 +114,7 @@ async fn can_use_distinct_stream(
     ),
     params(
         ("org_id" = String, Path, description = "Organization name"),
+        ("debug" = Option<String>, Query, description = "Set to `pruning` to return the parquet row group pruning stats of the query"),
     ),
     request_body(content = SearchRequest, description = "Search query", content_type = "application/json", example = json!({
         "query": {
@@ -164,6 +152,7 @@ async fn can_use_distinct_stream(
             "scan_size": 28943
         })),
         (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 429, description = "The organization is running too many searches", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
     )
 )]
@@ -177,7 +166,6 @@ pub async fn search(
     let cfg = get_config();
 
     let org_id = org_id.into_inner();
-    let mut range_error = String::new();
     let http_span = if cfg.common.tracing_search_enabled || cfg.common.tracing_enabled {
         tracing::info_span!("/api/{org_id}/_search", org_id = org_id.clone())
     } else {
@@ -205,6 +193,9 @@ pub async fn search(
         return Ok(MetaHttpResponse::bad_request(e));
     }
     req.use_cache = Some(use_cache);
+    if query.get("debug").is_some_and(|v| v == "pruning") {
+        req.query.debug_pruning = true;
+    }
 
     // set search event type
     if req.search_type.is_none() {
@@ -220,9 +211,20 @@ pub async fn search(
             .and_then(|event_type| get_search_event_context_from_request(event_type, &query));
     }
 
-    // get stream name
-    let stream_names = match resolve_stream_names(&req.query.sql) {
-        Ok(v) => v.clone(),
+    // get stream name and check permissions on stream, with the stream type it is qualified with
+    match resolve_stream_names_with_types(&req.query.sql, stream_type) {
+        #[cfg(feature = "enterprise")]
+        Ok(stream_names) => {
+            for (stream_name, stream_type) in stream_names {
+                if let Some(res) =
+                    check_stream_permissions(&stream_name, &org_id, &user_id, &stream_type).await
+                {
+                    return Ok(res);
+                }
+            }
+        }
+        #[cfg(not(feature = "enterprise"))]
+        Ok(_) => {}
         Err(e) => {
             return Ok(
                 HttpResponse::InternalServerError().json(meta::http::HttpResponse::error(
@@ -231,34 +233,17 @@ pub async fn search(
                 )),
             );
         }
-    };
+    }
 
-    // get stream settings
-    for stream_name in stream_names {
-        if let Some(settings) =
-            infra::schema::get_settings(&org_id, &stream_name, stream_type).await
-        {
-            let max_query_range =
-                get_settings_max_query_range(settings.max_query_range, &org_id, Some(&user_id))
-                    .await;
-            if max_query_range > 0
-                && (req.query.end_time - req.query.start_time) > max_query_range * 3600 * 1_000_000
-            {
-                req.query.start_time = req.query.end_time - max_query_range * 3600 * 1_000_000;
-                range_error = format!(
-                    "Query duration is modified due to query range restriction of {} hours",
-                    max_query_range
-                );
-            }
-        }
+    // reject invalid time ranges early, the max query range is applied by the cache search
+    if let Err(e) = req.query.validate_time_range(0) {
+        return Ok(MetaHttpResponse::bad_request(e));
+    }
 
-        // Check permissions on stream
-        #[cfg(feature = "enterprise")]
-        if let Some(res) =
-            check_stream_permissions(&stream_name, &org_id, &user_id, &stream_type).await
-        {
-            return Ok(res);
-        }
+    if let Some(res) =
+        check_query_fn_permission(&org_id, &user_id, req.query.query_fn.as_deref()).await
+    {
+        return Ok(res);
     }
 
     #[cfg(feature = "enterprise")]
@@ -320,17 +305,19 @@ pub async fn search(
         }
     }
 
+    if req.explain {
+        return Ok(
+            match SearchService::explain(&org_id, stream_type, &req).await {
+                Ok(res) => HttpResponse::Ok().json(res),
+                Err(e) => MetaHttpResponse::bad_request(e),
+            },
+        );
+    }
+
     // run search with cache
-    let res = SearchService::cache::search(
-        &trace_id,
-        &org_id,
-        stream_type,
-        Some(user_id),
-        &req,
-        range_error,
-    )
-    .instrument(http_span)
-    .await;
+    let res = SearchService::cache::search(&trace_id, &org_id, stream_type, Some(user_id), &req)
+        .instrument(http_span)
+        .await;
     match res {
         Ok(res) => Ok(HttpResponse::Ok().json(res)),
         Err(err) => {
@@ -343,6 +330,15 @@ pub async fn search(
                             code,
                             Some(trace_id),
                         )),
+                    errors::ErrorCodes::SearchTooManyRequests(_) => HttpResponse::TooManyRequests()
+                        .insert_header((
+                            header::RETRY_AFTER,
+                            SearchService::concurrency::retry_after_secs(),
+                        ))
+                        .json(meta::http::HttpResponse::error_code_with_trace_id(
+                            code,
+                            Some(trace_id),
+                        )),
                     _ => HttpResponse::InternalServerError().json(
                         meta::http::HttpResponse::error_code_with_trace_id(code, Some(trace_id)),
                     ),
@@ -437,6 +433,15 @@ pub async fn around(
     let mut query_fn = query
         .get("query_fn")
         .and_then(|v| base64::decode_url(v).ok());
+    if let Some(res) = check_query_fn_permission(
+        &org_id,
+        user_id.as_deref().unwrap_or(""),
+        query_fn.as_deref(),
+    )
+    .await
+    {
+        return Ok(res);
+    }
     if let Some(vrl_function) = &query_fn {
         if !vrl_function.trim().ends_with('.') {
             query_fn = Some(format!("{} \n .", vrl_function));
@@ -538,6 +543,9 @@ pub async fn around(
             skip_wal: false,
             streaming_output: false,
             streaming_id: None,
+            timezone: None,
+            debug_cache: false,
+            debug_pruning: false,
         },
         encoding: config::meta::search::RequestEncoding::Empty,
         regions: regions.clone(),
@@ -546,6 +554,7 @@ pub async fn around(
         search_type: Some(SearchEventType::UI),
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     let search_res = SearchService::search(&trace_id, &org_id, stream_type, user_id.clone(), &req)
         .instrument(http_span.clone())
@@ -594,6 +603,9 @@ pub async fn around(
             skip_wal: false,
             streaming_output: false,
             streaming_id: None,
+            timezone: None,
+            debug_cache: false,
+            debug_pruning: false,
         },
         encoding: config::meta::search::RequestEncoding::Empty,
         regions,
@@ -602,6 +614,7 @@ pub async fn around(
         search_type: Some(SearchEventType::UI),
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     let search_res = SearchService::search(&trace_id, &org_id, stream_type, user_id.clone(), &req)
         .instrument(http_span)
@@ -798,6 +811,9 @@ async fn values_v1(
     let mut query_fn = query
         .get("query_fn")
         .and_then(|v| base64::decode_url(v).ok());
+    if let Some(res) = check_query_fn_permission(org_id, user_id, query_fn.as_deref()).await {
+        return Ok(res);
+    }
     if let Some(vrl_function) = &query_fn {
         if !vrl_function.trim().ends_with('.') {
             query_fn = Some(format!("{} \n .", vrl_function));
@@ -937,6 +953,7 @@ async fn values_v1(
         search_type: Some(SearchEventType::Values),
         search_event_context: None,
         use_cache: Some(use_cache),
+        explain: false,
     };
 
     // skip fields which aren't part of the schema
@@ -1000,7 +1017,6 @@ async fn values_v1(
             actual_stream_type,
             Some(user_id.to_string()),
             &req,
-            "".to_string(),
         )
         .instrument(http_span)
         .await;
@@ -1016,6 +1032,17 @@ async fn values_v1(
                                 code,
                                 Some(trace_id),
                             )),
+                        errors::ErrorCodes::SearchTooManyRequests(_) => {
+                            HttpResponse::TooManyRequests()
+                                .insert_header((
+                                    header::RETRY_AFTER,
+                                    SearchService::concurrency::retry_after_secs(),
+                                ))
+                                .json(meta::http::HttpResponse::error_code_with_trace_id(
+                                    code,
+                                    Some(trace_id),
+                                ))
+                        }
                         _ => HttpResponse::InternalServerError().json(
                             meta::http::HttpResponse::error_code_with_trace_id(
                                 code,
@@ -1121,6 +1148,291 @@ async fn values_v1(
     Ok(HttpResponse::Ok().json(resp))
 }
 
+/// SearchTopNValuesBatch
+///
+/// Fetches the top values of several fields in one request. The fields are
+/// answered by at most two queries, one on the distinct values stream for the
+/// fields it covers and one on the stream itself. A field that fails gets its
+/// error instead of values without failing the whole batch.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "SearchValuesBatch",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "stream_name name"),
+        ("type" = Option<String>, Query, description = "stream type, defaults to logs"),
+    ),
+    request_body(content = BatchValuesRequest, description = "Fields and shared time range", content_type = "application/json", example = json!({
+        "fields": [{"name": "k8s_namespace_name"}, {"name": "level", "size": 5}],
+        "start_time": 1675182660872049i64,
+        "end_time": 1675185660872049i64,
+        "filter": "k8s_cluster = 'prod'",
+        "size": 10
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BatchValuesResponse, example = json!({
+            "took": 155,
+            "values": {
+                "level": {"values": [{"value": "info", "count": 120}, {"value": "error", "count": 3}]},
+                "unknown_field": {"values": [], "error": "field not found in stream schema"}
+            },
+            "scan_size": 28943,
+            "scan_records": 1000
+        })),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/{stream_name}/_values")]
+pub async fn values_batch(
+    path: web::Path<(String, String)>,
+    body: web::Json<BatchValuesRequest>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let start = std::time::Instant::now();
+    let started_at = Utc::now().timestamp_micros();
+    let cfg = get_config();
+    let (org_id, stream_name) = path.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let http_span = if cfg.common.tracing_search_enabled {
+        tracing::info_span!(
+            "/api/{org_id}/{stream_name}/_values",
+            org_id = org_id.clone(),
+            stream_name = stream_name.clone()
+        )
+    } else {
+        Span::none()
+    };
+    let trace_id = get_or_create_trace_id(in_req.headers(), &http_span);
+
+    let req = body.into_inner();
+    if req.fields.is_empty() {
+        return Ok(MetaHttpResponse::bad_request("fields is empty"));
+    }
+    if req.fields.len() > cfg.limit.values_batch_max_fields {
+        return Ok(MetaHttpResponse::bad_request(format!(
+            "too many fields, the maximum is {}",
+            cfg.limit.values_batch_max_fields
+        )));
+    }
+    // If this is a enrichment table, we need to get the start_time and end_time from the stats
+    let (start_time, end_time) = if stream_type.eq(&StreamType::EnrichmentTables) {
+        let stats = stats::get_stream_stats(&org_id, &stream_name, stream_type);
+        (stats.doc_time_min, stats.doc_time_max)
+    } else {
+        (req.start_time, req.end_time)
+    };
+    if start_time == 0 {
+        return Ok(MetaHttpResponse::bad_request("start_time is empty"));
+    }
+    if end_time == 0 {
+        return Ok(MetaHttpResponse::bad_request("end_time is empty"));
+    }
+    let (start_time, end_time) = if start_time == end_time {
+        (start_time - 1, end_time + 1)
+    } else {
+        (start_time, end_time)
+    };
+
+    // fields which aren't part of the schema are reported without a query
+    let schema = infra::schema::get(&org_id, &stream_name, stream_type)
+        .await
+        .unwrap_or(Schema::empty());
+    let mut resp = BatchValuesResponse::default();
+    let mut fields = Vec::with_capacity(req.fields.len());
+    let mut sizes = HashMap::with_capacity(req.fields.len());
+    for field in req.fields {
+        if schema.field_with_name(&field.name).is_err() {
+            resp.values.insert(
+                field.name,
+                FieldValues {
+                    values: vec![],
+                    error: Some("field not found in stream schema".to_string()),
+                },
+            );
+            continue;
+        }
+        let size = field
+            .size
+            .unwrap_or(req.size)
+            .min(cfg.limit.values_batch_max_size);
+        if sizes.insert(field.name.clone(), size).is_none() {
+            fields.push((field.name, size));
+        }
+    }
+
+    let filter = match batch_values::parse_filter(req.filter.as_deref().unwrap_or_default().trim())
+    {
+        Ok(filter) => filter,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    let filter = filter.as_str();
+    let keyword = req.keyword.as_deref().unwrap_or_default().trim();
+    let settings = infra::schema::get_settings(&org_id, &stream_name, stream_type)
+        .await
+        .unwrap_or_default();
+    let queries = batch_values::build_queries(
+        &stream_name,
+        stream_type,
+        &settings,
+        &fields,
+        filter,
+        keyword,
+        start_time,
+    );
+
+    let search_req = config::meta::search::Request {
+        query: config::meta::search::Query {
+            from: 0,
+            size: config::meta::sql::MAX_LIMIT,
+            start_time,
+            end_time,
+            ..Default::default()
+        },
+        encoding: config::meta::search::RequestEncoding::Empty,
+        regions: req.regions,
+        clusters: req.clusters,
+        timeout: req.timeout,
+        search_type: Some(SearchEventType::Values),
+        search_event_context: None,
+        use_cache: Some(false),
+        explain: false,
+    };
+    let search = |query: batch_values::ValuesQuery| {
+        let mut search_req = search_req.clone();
+        search_req.query.sql = query.sql.clone();
+        let trace_id = trace_id.clone();
+        let org_id = org_id.clone();
+        let user_id = user_id.clone();
+        let http_span = http_span.clone();
+        async move {
+            let ret = SearchService::cache::search(
+                &trace_id,
+                &org_id,
+                query.stream_type,
+                Some(user_id),
+                &search_req,
+            )
+            .instrument(http_span)
+            .await;
+            (query, ret)
+        }
+    };
+
+    let mut sqls = Vec::with_capacity(queries.len());
+    let mut work_groups = Vec::with_capacity(queries.len());
+    let mut failed = Vec::new();
+    for (query, ret) in futures::future::join_all(queries.into_iter().map(search)).await {
+        sqls.push(query.sql.clone());
+        match ret {
+            Ok(ret) => {
+                batch_values::fill_values(&mut resp.values, &query.fields, &ret.hits, &sizes);
+                resp.scan_size += ret.scan_size;
+                resp.scan_records += ret.scan_records;
+                work_groups.push(ret.work_group);
+            }
+            Err(e) if query.fields.len() > 1 => {
+                // find out which fields failed by querying them one by one
+                log::warn!(
+                    "[trace_id {trace_id}] search values batch error: {e}, retrying per field"
+                );
+                let use_distinct_stream = query.stream_type == StreamType::Metadata;
+                failed.extend(query.fields.iter().map(|field| {
+                    batch_values::build_query(
+                        &stream_name,
+                        stream_type,
+                        &[(field.clone(), sizes[field])],
+                        filter,
+                        keyword,
+                        use_distinct_stream,
+                    )
+                }));
+            }
+            Err(e) => {
+                log::error!("[trace_id {trace_id}] search values error: {e}");
+                for field in query.fields {
+                    resp.values.insert(
+                        field,
+                        FieldValues {
+                            values: vec![],
+                            error: Some(e.to_string()),
+                        },
+                    );
+                }
+            }
+        }
+    }
+    for (query, ret) in futures::future::join_all(failed.into_iter().map(search)).await {
+        match ret {
+            Ok(ret) => {
+                batch_values::fill_values(&mut resp.values, &query.fields, &ret.hits, &sizes);
+                resp.scan_size += ret.scan_size;
+                resp.scan_records += ret.scan_records;
+                work_groups.push(ret.work_group);
+            }
+            Err(e) => {
+                log::error!("[trace_id {trace_id}] search values error: {e}");
+                for field in query.fields {
+                    resp.values.insert(
+                        field,
+                        FieldValues {
+                            values: vec![],
+                            error: Some(e.to_string()),
+                        },
+                    );
+                }
+            }
+        }
+    }
+    resp.took = start.elapsed().as_millis() as usize;
+
+    let time = start.elapsed().as_secs_f64();
+    http_report_metrics(
+        start,
+        &org_id,
+        stream_type,
+        &stream_name,
+        "200",
+        "_values/batch",
+    );
+
+    let req_stats = RequestStats {
+        records: resp.values.len() as i64,
+        response_time: time,
+        size: resp.scan_size as f64,
+        request_body: Some(sqls.join("; ")),
+        user_email: Some(user_id),
+        min_ts: Some(start_time),
+        max_ts: Some(end_time),
+        search_type: Some(SearchEventType::Values),
+        trace_id: Some(trace_id),
+        work_group: get_work_group(work_groups),
+        ..Default::default()
+    };
+    report_request_usage_stats(
+        req_stats,
+        &org_id,
+        &stream_name,
+        stream_type,
+        UsageType::SearchTopNValues,
+        0,
+        started_at,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(resp))
+}
+
 /// SearchStreamPartition
 #[utoipa::path(
     context_path = "/api",
@@ -1219,6 +1531,162 @@ pub async fn search_partition(
     }
 }
 
+/// SearchCacheExplain
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "SearchCacheExplain",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    request_body(content = SearchRequest, description = "Search query", content_type = "application/json", example = json!({
+        "query": {
+            "sql": "select * from k8s ",
+            "start_time": 1675182660872049i64,
+            "end_time": 1675185660872049i64,
+            "from": 0,
+            "size": 10
+        }
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = CacheExplainReport),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/_search_cache_explain")]
+pub async fn search_cache_explain(
+    org_id: web::Path<String>,
+    in_req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let cfg = get_config();
+    let http_span = if cfg.common.tracing_search_enabled {
+        tracing::info_span!(
+            "/api/{org_id}/_search_cache_explain",
+            org_id = org_id.clone()
+        )
+    } else {
+        Span::none()
+    };
+    let trace_id = get_or_create_trace_id(in_req.headers(), &http_span);
+
+    let org_id = org_id.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
+
+    let mut req: config::meta::search::Request = match json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => return Ok(MetaHttpResponse::bad_request(e)),
+    };
+    if let Err(e) = req.decode() {
+        return Ok(MetaHttpResponse::bad_request(e));
+    }
+
+    // Check permissions on stream
+    #[cfg(feature = "enterprise")]
+    {
+        let user_id = in_req
+            .headers()
+            .get("user_id")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        for (stream_name, stream_type) in
+            resolve_stream_names_with_types(&req.query.sql, stream_type).unwrap_or_default()
+        {
+            if let Some(res) =
+                check_stream_permissions(&stream_name, &org_id, &user_id, &stream_type).await
+            {
+                return Ok(res);
+            }
+        }
+    }
+
+    let report =
+        SearchService::cache::explain::explain_cache(&trace_id, &org_id, stream_type, &req)
+            .instrument(http_span)
+            .await;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Invalidates the cached results of a stream whose time range overlaps with
+/// the requested one, e.g. after re-ingesting corrected data of a past window
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "SearchCacheDelete",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Query, description = "Stream name"),
+        ("type" = String, Query, description = "Stream type"),
+        ("start_time" = i64, Query, description = "Start time of the window to invalidate"),
+        ("end_time" = i64, Query, description = "End time of the window to invalidate"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[delete("/{org_id}/search/cache")]
+pub async fn delete_search_cache(
+    org_id: web::Path<String>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    if !get_config().common.result_cache_enabled {
+        return Ok(MetaHttpResponse::bad_request("Result Cache is disabled"));
+    }
+    let org_id = org_id.into_inner();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+    let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
+    let Some(stream_name) = query.get("stream_name").filter(|v| !v.is_empty()) else {
+        return Ok(MetaHttpResponse::bad_request("stream_name is required"));
+    };
+    let time = |key: &str| query.get(key).and_then(|v| v.parse::<i64>().ok());
+    let (Some(start_time), Some(end_time)) = (time("start_time"), time("end_time")) else {
+        return Ok(MetaHttpResponse::bad_request(
+            "start_time and end_time are required",
+        ));
+    };
+    if start_time > end_time {
+        return Ok(MetaHttpResponse::bad_request(
+            "start_time should be less than end_time",
+        ));
+    }
+
+    // Check permissions on stream
+    #[cfg(feature = "enterprise")]
+    {
+        let user_id = in_req
+            .headers()
+            .get("user_id")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        if let Some(res) =
+            check_stream_permissions(stream_name, &org_id, &user_id, &stream_type).await
+        {
+            return Ok(res);
+        }
+    }
+
+    let path = format!("{}/{}/{}", org_id, stream_type, stream_name);
+    if SearchService::cluster::cacher::delete_cached_results(path, Some((start_time, end_time)))
+        .await
+    {
+        Ok(MetaHttpResponse::ok("cache deleted"))
+    } else {
+        Ok(MetaHttpResponse::bad_request(
+            "Error deleting cache, please retry",
+        ))
+    }
+}
+
 /// Search History
 #[utoipa::path(
     context_path = "/api",
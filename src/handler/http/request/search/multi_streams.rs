@@ -47,6 +47,7 @@ use crate::{
             stream::get_settings_max_query_range,
         },
     },
+    handler::http::request::search::check_query_fn_permission,
     service::{search as SearchService, self_reporting::report_request_usage_stats},
 };
 
@@ -155,10 +156,14 @@ pub async fn search_multi(
         }
     };
 
+    let user_id = in_req.headers().get("user_id").unwrap().to_str().unwrap();
     let mut query_fn = multi_req
         .query_fn
         .as_ref()
         .and_then(|v| base64::decode_url(v).ok());
+    if let Some(res) = check_query_fn_permission(&org_id, user_id, query_fn.as_deref()).await {
+        return Ok(res);
+    }
 
     if let Some(vrl_function) = &query_fn {
         if !vrl_function.trim().ends_with('.') {
@@ -168,8 +173,14 @@ pub async fn search_multi(
 
     let mut range_error = String::new();
 
-    let user_id = in_req.headers().get("user_id").unwrap().to_str().unwrap();
     let mut queries = multi_req.to_query_req();
+    for req in queries.iter() {
+        if let Some(res) =
+            check_query_fn_permission(&org_id, user_id, req.query.query_fn.as_deref()).await
+        {
+            return Ok(res);
+        }
+    }
     let mut multi_res = search::Response::new(multi_req.from, multi_req.size);
 
     let per_query_resp = multi_req.per_query_response;
@@ -450,6 +461,7 @@ pub async fn search_multi(
                 }
                 if multi_res.histogram_interval.is_none() && res.histogram_interval.is_some() {
                     multi_res.histogram_interval = res.histogram_interval;
+                    multi_res.histogram_intervals = res.histogram_intervals.clone();
                 }
             }
             Err(err) => {
@@ -857,6 +869,22 @@ pub async fn around_multi(
     let mut query_fn = query
         .get("query_fn")
         .and_then(|v| base64::decode_url(v).ok());
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .unwrap()
+        .to_str()
+        .ok()
+        .map(|v| v.to_string());
+    if let Some(res) = check_query_fn_permission(
+        &org_id,
+        user_id.as_deref().unwrap_or(""),
+        query_fn.as_deref(),
+    )
+    .await
+    {
+        return Ok(res);
+    }
     if let Some(vrl_function) = &query_fn {
         if !vrl_function.trim().ends_with('.') {
             query_fn = Some(format!("{} \n .", vrl_function));
@@ -921,14 +949,6 @@ pub async fn around_multi(
         ..Default::default()
     };
 
-    let user_id = in_req
-        .headers()
-        .get("user_id")
-        .unwrap()
-        .to_str()
-        .ok()
-        .map(|v| v.to_string());
-
     for around_sql in around_sqls.iter() {
         metrics::QUERY_PENDING_NUMS
             .with_label_values(&[&org_id])
@@ -973,6 +993,9 @@ pub async fn around_multi(
                 skip_wal: false,
                 streaming_output: false,
                 streaming_id: None,
+                timezone: None,
+                debug_cache: false,
+                debug_pruning: false,
             },
             encoding: config::meta::search::RequestEncoding::Empty,
             regions: regions.clone(),
@@ -981,6 +1004,7 @@ pub async fn around_multi(
             search_type: Some(search::SearchEventType::UI),
             search_event_context: None,
             use_cache: None,
+            explain: false,
         };
         let search_res =
             SearchService::search(&trace_id, &org_id, stream_type, user_id.clone(), &req)
@@ -1051,6 +1075,9 @@ pub async fn around_multi(
                 skip_wal: false,
                 streaming_output: false,
                 streaming_id: None,
+                timezone: None,
+                debug_cache: false,
+                debug_pruning: false,
             },
             encoding: config::meta::search::RequestEncoding::Empty,
             regions: regions.clone(),
@@ -1059,6 +1086,7 @@ pub async fn around_multi(
             search_type: Some(search::SearchEventType::UI),
             search_event_context: None,
             use_cache: None,
+            explain: false,
         };
         let search_res =
             SearchService::search(&trace_id, &org_id, stream_type, user_id.clone(), &req)
@@ -0,0 +1,60 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Error;
+
+use actix_web::{get, web, HttpResponse};
+use config::meta::search::QueueStatusResponse;
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse,
+        utils::auth::{is_org_admin, UserEmail},
+    },
+    service::search as SearchService,
+};
+
+// GetSearchQueueStatus
+//
+// Lists the searches of the organization waiting in or running after the
+// search queue of the node serving the request. Only for admins.
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Search",
+    operation_id = "GetSearchQueueStatus",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = QueueStatusResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/query_manager/status")]
+pub async fn queue_status(
+    org_id: web::Path<String>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    if !is_org_admin(&org_id, &user_email.user_id).await {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only admins can see the search queue",
+        ));
+    }
+    Ok(HttpResponse::Ok().json(SearchService::queue::status(&org_id)))
+}
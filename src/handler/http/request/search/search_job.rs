@@ -20,7 +20,7 @@ use config::{
     get_config,
     meta::{
         search::{Request, Response, SearchEventType},
-        sql::resolve_stream_names,
+        sql::resolve_stream_names_with_types,
         stream::StreamType,
     },
     utils::json,
@@ -98,7 +98,7 @@ pub async fn submit_job(
     }
 
     // get stream name
-    let stream_names = match resolve_stream_names(&req.query.sql) {
+    let streams = match resolve_stream_names_with_types(&req.query.sql, stream_type) {
         Ok(v) => v.clone(),
         Err(e) => {
             return Ok(
@@ -110,14 +110,18 @@ pub async fn submit_job(
         }
     };
 
-    // Check permissions on stream
-    for stream_name in stream_names.iter() {
+    // Check permissions on stream, with the stream type it is qualified with
+    for (stream_name, stream_type) in streams.iter() {
         if let Some(res) =
-            check_stream_permissions(stream_name, &org_id, &user_id, &stream_type).await
+            check_stream_permissions(stream_name, &org_id, &user_id, stream_type).await
         {
             return Ok(res);
         }
     }
+    let stream_names = streams
+        .into_iter()
+        .map(|(stream_name, _)| stream_name)
+        .collect::<Vec<_>>();
 
     // add stream_names for rbac
     let stream_names = json::to_string(&stream_names).unwrap();
@@ -415,10 +419,19 @@ fn apply_pagination(response: Response, from: i64, size: i64) -> HttpResponse {
 // check permissions
 async fn check_permissions(job: &JobModel, org_id: &str, user_id: &str) -> Option<HttpResponse> {
     let stream_type = StreamType::from(job.stream_type.as_str());
-    let stream_names: Vec<String> = json::from_str(&job.stream_names).unwrap();
-    for stream_name in stream_names.iter() {
-        if let Some(res) =
-            check_stream_permissions(stream_name, org_id, user_id, &stream_type).await
+    // the streams of the sql keep the stream type they are qualified with
+    let streams = json::from_str::<Request>(&job.payload)
+        .ok()
+        .and_then(|req| resolve_stream_names_with_types(&req.query.sql, stream_type).ok())
+        .unwrap_or_else(|| {
+            json::from_str::<Vec<String>>(&job.stream_names)
+                .unwrap()
+                .into_iter()
+                .map(|stream_name| (stream_name, stream_type))
+                .collect()
+        });
+    for (stream_name, stream_type) in streams.iter() {
+        if let Some(res) = check_stream_permissions(stream_name, org_id, user_id, stream_type).await
         {
             return Some(res);
         }
@@ -16,13 +16,19 @@
 use std::io::Error;
 
 use actix_http::StatusCode;
-use actix_web::{get, post, web, HttpRequest, HttpResponse};
-use config::meta::short_url::ShortenUrlResponse;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
+use config::meta::short_url::{
+    ListShortUrlsQuery, ListShortUrlsResponse, PurgeShortUrlsQuery, PurgeShortUrlsResponse,
+    ShortenUrlResponse,
+};
 
 use crate::{
     common::{
         meta::{self, http::HttpResponse as MetaHttpResponse},
-        utils::redirect_response::RedirectResponseBuilder,
+        utils::{
+            auth::{is_org_admin, UserEmail},
+            redirect_response::RedirectResponseBuilder,
+        },
     },
     service::short_url,
 };
@@ -116,3 +122,133 @@ pub async fn retrieve(
         Ok(redirect.redirect_http())
     }
 }
+
+/// List the short URLs of an organization
+#[utoipa::path(
+    get,
+    context_path = "/api",
+    operation_id = "ListShortUrls",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ListShortUrlsQuery,
+    ),
+    responses(
+        (status = 200, description = "Short URLs of the organization, newest first", body = ListShortUrlsResponse),
+        (status = 403, description = "Forbidden", body = HttpResponse),
+        (status = 500, description = "Internal Server Error", body = HttpResponse),
+    ),
+    tag = "Short Url"
+)]
+#[get("/{org_id}/short_urls")]
+pub async fn list(
+    org_id: web::Path<String>,
+    query: web::Query<ListShortUrlsQuery>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_org_admin(&org_id, &user_email.user_id).await {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only admins can list the short URLs",
+        ));
+    }
+    if query.page_size == 0 {
+        return Ok(MetaHttpResponse::bad_request(
+            "page_size must be greater than 0",
+        ));
+    }
+    match short_url::list(&org_id, query.page_size, query.page_idx).await {
+        Ok(list) => Ok(HttpResponse::Ok().json(list)),
+        Err(e) => {
+            log::error!("Failed to list short URLs: {:?}", e);
+            Ok(MetaHttpResponse::internal_error(e))
+        }
+    }
+}
+
+/// Delete a short URL of an organization
+#[utoipa::path(
+    delete,
+    context_path = "/api",
+    operation_id = "DeleteShortUrl",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("short_id" = String, Path, description = "The short ID of the short URL", example = "ddbffcea3ad44292"),
+    ),
+    responses(
+        (status = 200, description = "Short URL deleted", body = HttpResponse),
+        (status = 403, description = "Forbidden", body = HttpResponse),
+        (status = 404, description = "Short URL not found", body = HttpResponse),
+        (status = 500, description = "Internal Server Error", body = HttpResponse),
+    ),
+    tag = "Short Url"
+)]
+#[delete("/{org_id}/short_urls/{short_id}")]
+pub async fn delete(
+    path: web::Path<(String, String)>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let (org_id, short_id) = path.into_inner();
+    if !is_org_admin(&org_id, &user_email.user_id).await {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only admins can delete short URLs",
+        ));
+    }
+    match short_url::delete(&org_id, &short_id).await {
+        Ok(true) => Ok(MetaHttpResponse::ok("Short URL deleted")),
+        Ok(false) => Ok(MetaHttpResponse::not_found("Short URL not found")),
+        Err(e) => {
+            log::error!("Failed to delete short URL: {:?}", e);
+            Ok(MetaHttpResponse::internal_error(e))
+        }
+    }
+}
+
+/// Delete the short URLs of an organization that are past the retention period
+#[utoipa::path(
+    delete,
+    context_path = "/api",
+    operation_id = "PurgeShortUrls",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        PurgeShortUrlsQuery,
+    ),
+    responses(
+        (status = 200, description = "Number of deleted short URLs", body = PurgeShortUrlsResponse),
+        (status = 400, description = "Invalid request", body = HttpResponse),
+        (status = 403, description = "Forbidden", body = HttpResponse),
+        (status = 500, description = "Internal Server Error", body = HttpResponse),
+    ),
+    tag = "Short Url"
+)]
+#[delete("/{org_id}/short_urls")]
+pub async fn purge(
+    org_id: web::Path<String>,
+    query: web::Query<PurgeShortUrlsQuery>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_org_admin(&org_id, &user_email.user_id).await {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only admins can delete short URLs",
+        ));
+    }
+    if !query.expired {
+        return Ok(MetaHttpResponse::bad_request(
+            "Only expired short URLs can be deleted in bulk, set expired=true",
+        ));
+    }
+    match short_url::purge_expired(&org_id).await {
+        Ok(deleted) => Ok(HttpResponse::Ok().json(PurgeShortUrlsResponse { deleted })),
+        Err(e) => {
+            log::error!("Failed to purge short URLs: {:?}", e);
+            Ok(MetaHttpResponse::internal_error(e))
+        }
+    }
+}
@@ -18,15 +18,15 @@ use std::{io::Error, sync::Arc};
 use actix_web::{
     cookie,
     cookie::{Cookie, SameSite},
-    get, head,
+    delete, get, head,
     http::header,
-    put, web, HttpRequest, HttpResponse,
+    post, put, web, HttpRequest, HttpResponse,
 };
 use arrow_schema::Schema;
 use config::{
     cluster::LOCAL_NODE,
     get_config, get_instance_id,
-    meta::{cluster::NodeStatus, function::ZoFunction},
+    meta::{cluster::NodeStatus, function::ZoFunction, stream::StreamType},
     utils::{json, schema_ext::SchemaExt},
     Config, META_ORG_ID, QUICK_MODEL_FIELDS, SQL_FULL_TEXT_SEARCH_FIELDS, TIMESTAMP_COL_NAME,
 };
@@ -36,7 +36,7 @@ use infra::{
     file_list,
     schema::{STREAM_SCHEMAS, STREAM_SCHEMAS_COMPRESSED, STREAM_SCHEMAS_LATEST},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 #[cfg(feature = "enterprise")]
 use {
@@ -69,11 +69,15 @@ use crate::{
             http::HttpResponse as MetaHttpResponse,
             user::{AuthTokens, AuthTokensExt},
         },
+        utils::auth::{is_root_user, UserEmail},
     },
     service::{
         db,
         search::{
-            datafusion::{storage::file_statistics_cache, udf::DEFAULT_FUNCTIONS},
+            datafusion::{
+                distributed_plan::streaming_aggs_exec, storage::file_statistics_cache,
+                udf::DEFAULT_FUNCTIONS,
+            },
             tantivy::puffin_directory::reader_cache,
         },
     },
@@ -84,6 +88,17 @@ pub struct HealthzResponse {
     status: String,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct StorageHealthzResponse {
+    status: String,
+    storage: String,
+    /// Latency of the check, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[derive(Serialize)]
 struct ConfigResponse<'a> {
     version: String,
@@ -156,6 +171,55 @@ pub async fn healthz() -> Result<HttpResponse, Error> {
     }))
 }
 
+/// Healthz of the object storage
+///
+/// Lists the probe prefix of the bucket, nothing is written. The cause of a
+/// failure is only logged, the response doesn't name the bucket or region.
+#[utoipa::path(
+    path = "/healthz/storage",
+    tag = "Meta",
+    security(
+        ("Authorization"= [])
+    ),
+    responses(
+        (status = 200, description="Status OK", content_type = "application/json", body = StorageHealthzResponse, example = json!({"status": "ok", "storage": "s3", "latency": 12})),
+        (status = 503, description="Storage Unavailable", content_type = "application/json", body = StorageHealthzResponse, example = json!({"status": "not ok", "storage": "s3", "error": "storage unavailable"})),
+    )
+)]
+#[get("")]
+pub async fn healthz_storage() -> Result<HttpResponse, Error> {
+    // there is no remote storage to check
+    if config::is_local_disk_storage() {
+        return Ok(HttpResponse::Ok().json(StorageHealthzResponse {
+            status: "ok".to_string(),
+            storage: "disk".to_string(),
+            latency: None,
+            error: None,
+        }));
+    }
+    let storage = match get_config().s3.provider.as_str() {
+        "" => "s3".to_string(),
+        provider => provider.to_string(),
+    };
+    Ok(match infra::storage::health::check().await {
+        Ok(latency) => HttpResponse::Ok().json(StorageHealthzResponse {
+            status: "ok".to_string(),
+            storage,
+            latency: Some(latency),
+            error: None,
+        }),
+        Err(e) => {
+            log::error!("[STORAGE] {e}");
+            HttpResponse::ServiceUnavailable().json(StorageHealthzResponse {
+                status: "not ok".to_string(),
+                storage,
+                latency: None,
+                error: Some("storage unavailable".to_string()),
+            })
+        }
+    })
+}
+
 /// Healthz HEAD
 /// Vector pipeline healthcheck support
 #[head("/healthz")]
@@ -378,32 +442,36 @@ pub async fn cache_status() -> Result<HttpResponse, Error> {
     Ok(HttpResponse::Ok().json(stats))
 }
 
-#[get("")]
-pub async fn config_reload() -> Result<HttpResponse, Error> {
-    if let Err(e) = config::refresh_config() {
-        return Ok(
-            HttpResponse::InternalServerError().json(serde_json::json!({"status": e.to_string()}))
-        );
+/// Reloads the config from the environment and reports the variables that
+/// changed. Settings only read at startup keep their value until a restart.
+#[post("/{org_id}/config/reload")]
+pub async fn reload_config(
+    _org_id: web::Path<String>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only root user can reload config",
+        ));
     }
+    let reload = match config::reload_config() {
+        Ok(reload) => reload,
+        Err(e) => return Ok(MetaHttpResponse::internal_error(e)),
+    };
     #[cfg(feature = "enterprise")]
     if let Err(e) = refresh_o2_config()
         .and_then(|_| refresh_dex_config())
         .and_then(|_| refresh_openfga_config())
     {
-        return Ok(
-            HttpResponse::InternalServerError().json(serde_json::json!({"status": e.to_string()}))
-        );
+        return Ok(MetaHttpResponse::internal_error(e));
     }
-    let status = "successfully reloaded config";
-    // Audit this event
     #[cfg(feature = "enterprise")]
     audit(AuditMessage {
-        // Since this is not a protected route, there is no way to get the user email
-        user_email: "".to_string(),
-        org_id: "".to_string(),
+        user_email: user_email.user_id.clone(),
+        org_id: _org_id.into_inner(),
         _timestamp: chrono::Utc::now().timestamp_micros(),
         protocol: Protocol::Http(HttpMeta {
-            method: "GET".to_string(),
+            method: "POST".to_string(),
             path: "/config/reload".to_string(),
             query_params: "".to_string(),
             body: "".to_string(),
@@ -411,7 +479,13 @@ pub async fn config_reload() -> Result<HttpResponse, Error> {
         }),
     })
     .await;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status": status})))
+    if !reload.requires_restart.is_empty() {
+        log::warn!(
+            "config reloaded, changes to {} require a restart",
+            reload.requires_restart.join(", ")
+        );
+    }
+    Ok(HttpResponse::Ok().json(reload))
 }
 
 async fn get_stream_schema_status() -> (usize, usize, usize) {
@@ -808,3 +882,85 @@ async fn node_metrics() -> Result<HttpResponse, Error> {
     let metrics = config::utils::sysinfo::get_node_metrics();
     Ok(MetaHttpResponse::json(metrics))
 }
+
+#[derive(Deserialize)]
+struct PendingJobsQuery {
+    limit: Option<usize>,
+}
+
+/// Lists the streams with pending compaction merge jobs, ordered by the
+/// number of pending jobs
+#[get("/compact/pending_jobs")]
+async fn compact_pending_jobs(
+    query: web::Query<PendingJobsQuery>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only root user can list the pending compaction jobs",
+        ));
+    }
+    match crate::service::compact::stats::pending_jobs_by_stream(query.limit).await {
+        Ok(jobs) => Ok(MetaHttpResponse::json(jobs)),
+        Err(e) => Ok(MetaHttpResponse::internal_error(e)),
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamingAggsEvictQuery {
+    id: Option<String>,
+    org_id: Option<String>,
+    stream_type: Option<StreamType>,
+    stream: Option<String>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+}
+
+#[get("/cache/streaming_aggs")]
+async fn list_streaming_aggs_cache(user_email: UserEmail) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only root user can list the streaming aggregation cache",
+        ));
+    }
+    Ok(MetaHttpResponse::json(streaming_aggs_exec::list_cache()))
+}
+
+/// Evicts the streaming aggregation cache entry of `id`, or the entries covering
+/// `stream` of `org_id` in the optional time range.
+#[delete("/cache/streaming_aggs")]
+async fn delete_streaming_aggs_cache(
+    query: web::Query<StreamingAggsEvictQuery>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(
+            "Only root user can evict the streaming aggregation cache",
+        ));
+    }
+    let query = query.into_inner();
+    let evicted = match (query.id, query.org_id, query.stream) {
+        (Some(id), ..) => {
+            if streaming_aggs_exec::evict(&id) {
+                vec![id]
+            } else {
+                vec![]
+            }
+        }
+        (None, Some(org_id), Some(stream)) => streaming_aggs_exec::invalidate(
+            &org_id,
+            query.stream_type.unwrap_or_default(),
+            &stream,
+            (
+                query.start_time.unwrap_or(0),
+                query.end_time.unwrap_or(i64::MAX),
+            ),
+        ),
+        _ => {
+            return Ok(MetaHttpResponse::bad_request(
+                "id, or org_id and stream are required",
+            ));
+        }
+    };
+    Ok(MetaHttpResponse::json(json::json!({ "evicted": evicted })))
+}
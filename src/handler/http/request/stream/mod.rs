@@ -18,9 +18,9 @@ use std::{
     io::{Error, ErrorKind},
 };
 
-use actix_web::{delete, get, http, post, put, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{delete, get, http, patch, post, put, web, HttpRequest, HttpResponse, Responder};
 use config::{
-    meta::stream::{StreamSettings, StreamType, UpdateStreamSettings},
+    meta::stream::{PatchStreamSettings, StreamSettings, StreamType, UpdateStreamSettings},
     utils::schema::format_stream_name,
 };
 
@@ -153,51 +153,117 @@ async fn update_settings(
             .await?;
 
     // sync the data retention to index stream
-    if stream_type.is_basic_type() && stream_settings.data_retention.is_some() {
-        #[allow(deprecated)]
-        let index_stream_name =
-            if cfg.common.inverted_index_old_format && stream_type == StreamType::Logs {
-                stream_name.to_string()
-            } else {
-                format!("{}_{}", stream_name, stream_type)
-            };
-        if infra::schema::get(&org_id, &index_stream_name, StreamType::Index)
-            .await
-            .is_ok()
-        {
-            let index_stream_settings = UpdateStreamSettings {
-                data_retention: stream_settings.data_retention,
-                ..Default::default()
-            };
-            match stream::update_stream_settings(
-                &org_id,
-                &index_stream_name,
-                StreamType::Index,
-                index_stream_settings,
-            )
-            .await
-            {
-                Ok(_) => {
-                    log::debug!(
-                        "Data retention settings for {} synced to index stream {}",
-                        stream_name,
-                        index_stream_name
-                    );
-                }
-                Err(e) => {
-                    log::error!(
-                        "Failed to sync data retention settings to index stream {}: {}",
-                        index_stream_name,
-                        e
-                    );
-                }
-            }
-        }
+    if let Some(data_retention) = stream_settings.data_retention {
+        sync_index_stream_retention(&org_id, &stream_name, stream_type, data_retention).await;
     }
 
     Ok(main_stream_res)
 }
 
+/// PatchStreamSettings
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Streams",
+    operation_id = "PatchStreamSettings",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("type" = String, Query, description = "Stream type"),
+    ),
+    request_body(content = PatchStreamSettings, description = "Stream settings to change, omitted fields are kept", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = StreamSettings),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 409, description = "Settings were changed since `settings_version`", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[patch("/{org_id}/streams/{stream_name}/settings")]
+async fn patch_settings(
+    path: web::Path<(String, String)>,
+    patch: web::Json<PatchStreamSettings>,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (org_id, mut stream_name) = path.into_inner();
+    if !config::get_config().common.skip_formatting_stream_name {
+        stream_name = format_stream_name(&stream_name);
+    }
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
+    let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
+    if stream_type == StreamType::EnrichmentTables || stream_type == StreamType::Index {
+        return Ok(
+            HttpResponse::BadRequest().json(meta::http::HttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                format!("Stream type '{}' not allowed", stream_type),
+            )),
+        );
+    }
+    let patch = patch.into_inner();
+    let data_retention = patch.data_retention;
+    let resp = stream::patch_stream_settings(&org_id, &stream_name, stream_type, patch).await?;
+    if let Some(data_retention) = data_retention {
+        if resp.status().is_success() {
+            sync_index_stream_retention(&org_id, &stream_name, stream_type, data_retention).await;
+        }
+    }
+    Ok(resp)
+}
+
+/// Applies the data retention of a stream to its index stream, if any
+async fn sync_index_stream_retention(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    data_retention: i64,
+) {
+    if !stream_type.is_basic_type() {
+        return;
+    }
+    #[allow(deprecated)]
+    let index_stream_name = if config::get_config().common.inverted_index_old_format
+        && stream_type == StreamType::Logs
+    {
+        stream_name.to_string()
+    } else {
+        format!("{}_{}", stream_name, stream_type)
+    };
+    if infra::schema::get(org_id, &index_stream_name, StreamType::Index)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let index_stream_settings = UpdateStreamSettings {
+        data_retention: Some(data_retention),
+        ..Default::default()
+    };
+    match stream::update_stream_settings(
+        org_id,
+        &index_stream_name,
+        StreamType::Index,
+        index_stream_settings,
+    )
+    .await
+    {
+        Ok(_) => {
+            log::debug!(
+                "Data retention settings for {} synced to index stream {}",
+                stream_name,
+                index_stream_name
+            );
+        }
+        Err(e) => {
+            log::error!(
+                "Failed to sync data retention settings to index stream {}: {}",
+                index_stream_name,
+                e
+            );
+        }
+    }
+}
+
 /// DeleteStreamFields
 #[utoipa::path(
     context_path = "/api",
@@ -257,6 +323,7 @@ async fn delete_fields(
         ("org_id" = String, Path, description = "Organization name"),
         ("stream_name" = String, Path, description = "Stream name"),
         ("type" = String, Query, description = "Stream type"),
+        ("time_range" = Option<String>, Query, description = "Only delete the data in `start,end`, each time is a date like 2024-11-03 or a RFC3339 time with hour precision like 2024-11-03T14:00:00Z, the end is exclusive"),
     ),
     responses(
         (status = 200, description = "Success", content_type = "application/json", body = HttpResponse),
@@ -271,7 +338,12 @@ async fn delete(
     let (org_id, stream_name) = path.into_inner();
     let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).unwrap();
     let stream_type = get_stream_type_from_request(&query).unwrap_or_default();
-    stream::delete_stream(&org_id, &stream_name, stream_type).await
+    match query.get("time_range") {
+        Some(time_range) => {
+            stream::delete_stream_data(&org_id, &stream_name, stream_type, time_range).await
+        }
+        None => stream::delete_stream(&org_id, &stream_name, stream_type).await,
+    }
 }
 
 /// ListStreams
@@ -389,7 +461,7 @@ async fn delete_stream_cache(
         format!("{}/{}/{}", org_id, stream_type, stream_name)
     };
 
-    match crate::service::search::cluster::cacher::delete_cached_results(path).await {
+    match crate::service::search::cluster::cacher::delete_cached_results(path, None).await {
         true => Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
             http::StatusCode::OK.into(),
             "cache deleted".to_string(),
@@ -0,0 +1,223 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Error;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use config::meta::synthetic::{
+    ListSyntheticGenerators, SyntheticGenerator, SyntheticLogsRequest, SyntheticMetricsRequest,
+    SyntheticTracesRequest,
+};
+
+use crate::{
+    common::{
+        meta::http::HttpResponse as MetaHttpResponse,
+        utils::auth::{is_root_user, UserEmail},
+    },
+    service::synthetic::{self, SyntheticError},
+};
+
+const ROOT_ONLY: &str = "Only root user can manage synthetic data generators";
+
+fn error_response(e: SyntheticError) -> HttpResponse {
+    match e {
+        SyntheticError::Disabled => MetaHttpResponse::forbidden(e),
+        SyntheticError::NotIngester => MetaHttpResponse::service_unavailable(e),
+        SyntheticError::TooMany(_) => MetaHttpResponse::conflict(e),
+        SyntheticError::InvalidRequest(_) => MetaHttpResponse::bad_request(e),
+        SyntheticError::Db(e) => MetaHttpResponse::internal_error(e),
+    }
+}
+
+/// StartSyntheticLogs
+///
+/// Starts a generator writing synthetic logs to the stream through the normal
+/// ingestion path until its duration ends or it is canceled.
+#[utoipa::path(
+    post,
+    context_path = "/api",
+    tag = "Synthetic",
+    operation_id = "StartSyntheticLogs",
+    security(
+        ("Authorization"= [])
+    ),
+    request_body(content = SyntheticLogsRequest, description = "Logs generator", content_type = "application/json", example = json!({
+        "org": "default",
+        "stream": "synthetic_nginx",
+        "rate_per_sec": 100,
+        "duration_secs": 600,
+        "template": "nginx",
+        "cardinality": {"services": 20, "hosts": 100}
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SyntheticGenerator),
+        (status = 400, description = "Invalid request", content_type = "application/json", body = MetaHttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+        (status = 409, description = "Too many generators", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[post("/_synthetic/logs")]
+pub async fn start_logs(
+    body: web::Json<SyntheticLogsRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    match synthetic::start_logs(body.into_inner(), &user_email.user_id).await {
+        Ok(generator) => Ok(HttpResponse::Ok().json(generator)),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// StartSyntheticMetrics
+///
+/// Starts a generator writing samples of a gauge or counter metric, spread
+/// over series of the configured label cardinality.
+#[utoipa::path(
+    post,
+    context_path = "/api",
+    tag = "Synthetic",
+    operation_id = "StartSyntheticMetrics",
+    security(
+        ("Authorization"= [])
+    ),
+    request_body(content = SyntheticMetricsRequest, description = "Metrics generator", content_type = "application/json", example = json!({
+        "org": "default",
+        "stream": "synthetic_requests_total",
+        "rate_per_sec": 100,
+        "duration_secs": 600,
+        "metric_type": "counter",
+        "cardinality": {"services": 5, "hosts": 10}
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SyntheticGenerator),
+        (status = 400, description = "Invalid request", content_type = "application/json", body = MetaHttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+        (status = 409, description = "Too many generators", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[post("/_synthetic/metrics")]
+pub async fn start_metrics(
+    body: web::Json<SyntheticMetricsRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    match synthetic::start_metrics(body.into_inner(), &user_email.user_id).await {
+        Ok(generator) => Ok(HttpResponse::Ok().json(generator)),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// StartSyntheticTraces
+///
+/// Starts a generator writing call trees of the configured depth and fanout,
+/// `rate_per_sec` is the number of traces per second.
+#[utoipa::path(
+    post,
+    context_path = "/api",
+    tag = "Synthetic",
+    operation_id = "StartSyntheticTraces",
+    security(
+        ("Authorization"= [])
+    ),
+    request_body(content = SyntheticTracesRequest, description = "Traces generator", content_type = "application/json", example = json!({
+        "org": "default",
+        "stream": "synthetic",
+        "rate_per_sec": 10,
+        "duration_secs": 600,
+        "depth": 3,
+        "fanout": 2
+    })),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SyntheticGenerator),
+        (status = 400, description = "Invalid request", content_type = "application/json", body = MetaHttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+        (status = 409, description = "Too many generators", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[post("/_synthetic/traces")]
+pub async fn start_traces(
+    body: web::Json<SyntheticTracesRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    match synthetic::start_traces(body.into_inner(), &user_email.user_id).await {
+        Ok(generator) => Ok(HttpResponse::Ok().json(generator)),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// ListSyntheticGenerators
+#[utoipa::path(
+    get,
+    context_path = "/api",
+    tag = "Synthetic",
+    operation_id = "ListSyntheticGenerators",
+    security(
+        ("Authorization"= [])
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = ListSyntheticGenerators),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[get("/_synthetic")]
+pub async fn list(user_email: UserEmail) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    match synthetic::list().await {
+        Ok(list) => Ok(HttpResponse::Ok().json(ListSyntheticGenerators { list })),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// CancelSyntheticGenerator
+#[utoipa::path(
+    delete,
+    context_path = "/api",
+    tag = "Synthetic",
+    operation_id = "CancelSyntheticGenerator",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("id" = String, Path, description = "Generator id"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = MetaHttpResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = MetaHttpResponse),
+        (status = 404, description = "NotFound", content_type = "application/json", body = MetaHttpResponse),
+    )
+)]
+#[delete("/_synthetic/{id}")]
+pub async fn cancel(path: web::Path<String>, user_email: UserEmail) -> Result<HttpResponse, Error> {
+    if !is_root_user(&user_email.user_id) {
+        return Ok(MetaHttpResponse::forbidden(ROOT_ONLY));
+    }
+    let id = path.into_inner();
+    match synthetic::cancel(&id).await {
+        Ok(true) => Ok(MetaHttpResponse::ok("Synthetic data generator canceled")),
+        Ok(false) => Ok(MetaHttpResponse::not_found(format!(
+            "Synthetic data generator {id} not found"
+        ))),
+        Err(e) => Ok(error_response(e)),
+    }
+}
@@ -26,8 +26,8 @@ use crate::{
         meta::{self, http::HttpResponse as MetaHttpResponse},
         utils::http::get_or_create_trace_id,
     },
-    handler::http::request::{CONTENT_TYPE_JSON, CONTENT_TYPE_PROTO},
-    service::{search as SearchService, traces},
+    handler::http::request::{otlp_request_body, OtlpFormat},
+    service::{metadata::service_latency_index, search as SearchService, traces},
 };
 
 /// TracesIngest
@@ -41,6 +41,9 @@ use crate::{
     request_body(content = String, description = "ExportTraceServiceRequest", content_type = "application/x-protobuf"),
     responses(
         (status = 200, description = "Success", content_type = "application/json", body = IngestionResponse, example = json!({"code": 200})),
+        (status = 400, description = "Malformed payload", content_type = "application/json", body = HttpResponse),
+        (status = 413, description = "Payload too large after decompression", content_type = "application/json", body = HttpResponse),
+        (status = 415, description = "Unsupported content type or encoding", content_type = "application/json", body = HttpResponse),
         (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
     )
 )]
@@ -68,22 +71,17 @@ async fn handle_req(
     body: web::Bytes,
 ) -> Result<HttpResponse, Error> {
     let org_id = org_id.into_inner();
-    let content_type = req.headers().get("Content-Type").unwrap().to_str().unwrap();
+    let (format, body) = match otlp_request_body(&req, body) {
+        Ok(v) => v,
+        Err(resp) => return Ok(resp),
+    };
     let in_stream_name = req
         .headers()
         .get(&get_config().grpc.stream_header_key)
         .map(|header| header.to_str().unwrap());
-    if content_type.eq(CONTENT_TYPE_PROTO) {
-        traces::otlp_proto(&org_id, body, in_stream_name).await
-    } else if content_type.starts_with(CONTENT_TYPE_JSON) {
-        traces::otlp_json(&org_id, body, in_stream_name).await
-    } else {
-        Ok(
-            HttpResponse::BadRequest().json(meta::http::HttpResponse::error(
-                http::StatusCode::BAD_REQUEST.into(),
-                "Bad Request".to_string(),
-            )),
-        )
+    match format {
+        OtlpFormat::Proto => traces::otlp_proto(&org_id, body, in_stream_name).await,
+        OtlpFormat::Json => traces::otlp_json(&org_id, body, in_stream_name).await,
     }
 }
 
@@ -152,48 +150,9 @@ pub async fn get_latest_traces(
     let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
 
     // Check permissions on stream
-
     #[cfg(feature = "enterprise")]
-    {
-        use o2_openfga::meta::mapping::OFGA_MODELS;
-
-        use crate::common::{
-            infra::config::USERS,
-            utils::auth::{is_root_user, AuthExtractor},
-        };
-        let user_id = in_req.headers().get("user_id").unwrap();
-        if !is_root_user(user_id.to_str().unwrap()) {
-            let user: meta::user::User = USERS
-                .get(&format!("{org_id}/{}", user_id.to_str().unwrap()))
-                .unwrap()
-                .clone();
-            let stream_type_str = StreamType::Traces.as_str();
-
-            if !crate::handler::http::auth::validator::check_permissions(
-                user_id.to_str().unwrap(),
-                AuthExtractor {
-                    auth: "".to_string(),
-                    method: "GET".to_string(),
-                    o2_type: format!(
-                        "{}:{}",
-                        OFGA_MODELS
-                            .get(stream_type_str)
-                            .map_or(stream_type_str, |model| model.key),
-                        stream_name
-                    ),
-                    org_id: org_id.clone(),
-                    bypass_check: false,
-                    parent_id: "".to_string(),
-                },
-                user.role,
-                user.is_external,
-            )
-            .await
-            {
-                return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
-            }
-        }
-        // Check permissions on stream ends
+    if !check_stream_permissions(&org_id, &stream_name, &user_id).await {
+        return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
     }
 
     let filter = match query.get("filter") {
@@ -289,6 +248,9 @@ pub async fn get_latest_traces(
             skip_wal: false,
             streaming_output: false,
             streaming_id: None,
+            timezone: None,
+            debug_cache: false,
+            debug_pruning: false,
         },
         encoding: config::meta::search::RequestEncoding::Empty,
         regions: vec![],
@@ -297,6 +259,7 @@ pub async fn get_latest_traces(
         search_type: None,
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     let stream_type = StreamType::Traces;
     let user_id = in_req
@@ -531,6 +494,163 @@ pub async fn get_latest_traces(
     Ok(HttpResponse::Ok().json(resp))
 }
 
+#[cfg(feature = "enterprise")]
+async fn check_stream_permissions(org_id: &str, stream_name: &str, user_id: &str) -> bool {
+    use o2_openfga::meta::mapping::OFGA_MODELS;
+
+    use crate::common::{
+        infra::config::USERS,
+        utils::auth::{is_root_user, AuthExtractor},
+    };
+
+    if is_root_user(user_id) {
+        return true;
+    }
+    let Some(user) = USERS.get(&format!("{org_id}/{user_id}")).map(|u| u.clone()) else {
+        return false;
+    };
+    let stream_type_str = StreamType::Traces.as_str();
+    crate::handler::http::auth::validator::check_permissions(
+        user_id,
+        AuthExtractor {
+            auth: "".to_string(),
+            method: "GET".to_string(),
+            o2_type: format!(
+                "{}:{}",
+                OFGA_MODELS
+                    .get(stream_type_str)
+                    .map_or(stream_type_str, |model| model.key),
+                stream_name
+            ),
+            org_id: org_id.to_string(),
+            bypass_check: false,
+            parent_id: "".to_string(),
+        },
+        user.role,
+        user.is_external,
+    )
+    .await
+}
+
+/// GetTracesServices
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Traces",
+    operation_id = "GetTracesServices",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("stream_name" = String, Path, description = "Stream name"),
+        ("start_time" = i64, Query, description = "start time"),
+        ("end_time" = i64, Query, description = "end time"),
+    ),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = SearchResponse, example = json!({
+            "took": 12,
+            "hits": [
+                {
+                    "service_name": "checkout",
+                    "span_count": 120,
+                    "error_count": 3,
+                    "duration_sum": 360000,
+                    "duration_max": 15000,
+                    "duration_avg": 3000,
+                    "operations": [
+                        {
+                            "operation_name": "POST /pay",
+                            "span_count": 120,
+                            "error_count": 3,
+                            "duration_sum": 360000,
+                            "duration_max": 15000,
+                            "duration_avg": 3000
+                        }
+                    ]
+                }
+            ]
+        })),
+        (status = 400, description = "Failure", content_type = "application/json", body = HttpResponse),
+        (status = 500, description = "Failure", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[get("/{org_id}/{stream_name}/traces/services")]
+pub async fn get_services(
+    path: web::Path<(String, String)>,
+    in_req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let start = std::time::Instant::now();
+    let (org_id, stream_name) = path.into_inner();
+    let http_span = if get_config().common.tracing_search_enabled {
+        tracing::info_span!(
+            "/api/{org_id}/{stream_name}/traces/services",
+            org_id = org_id.clone(),
+            stream_name = stream_name.clone()
+        )
+    } else {
+        Span::none()
+    };
+    let trace_id = get_or_create_trace_id(in_req.headers(), &http_span);
+    let user_id = in_req
+        .headers()
+        .get("user_id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let query = web::Query::<HashMap<String, String>>::from_query(in_req.query_string()).unwrap();
+
+    // Check permissions on stream
+    #[cfg(feature = "enterprise")]
+    if !check_stream_permissions(&org_id, &stream_name, &user_id).await {
+        return Ok(MetaHttpResponse::forbidden("Unauthorized Access"));
+    }
+
+    let start_time = query
+        .get("start_time")
+        .map_or(0, |v| v.parse::<i64>().unwrap_or(0));
+    if start_time == 0 {
+        return Ok(MetaHttpResponse::bad_request("start_time is empty"));
+    }
+    let end_time = query
+        .get("end_time")
+        .map_or(0, |v| v.parse::<i64>().unwrap_or(0));
+    if end_time == 0 {
+        return Ok(MetaHttpResponse::bad_request("end_time is empty"));
+    }
+
+    let services = match service_latency_index::query(
+        &trace_id,
+        &org_id,
+        &stream_name,
+        start_time,
+        end_time,
+        Some(user_id),
+    )
+    .instrument(http_span)
+    .await
+    {
+        Ok(v) => v,
+        Err(err) => {
+            log::error!("get traces services error: {:?}", err);
+            return Ok(match err {
+                errors::Error::ErrorCode(code) => HttpResponse::InternalServerError()
+                    .json(meta::http::HttpResponse::error_code(code)),
+                _ => HttpResponse::InternalServerError().json(meta::http::HttpResponse::error(
+                    http::StatusCode::INTERNAL_SERVER_ERROR.into(),
+                    err.to_string(),
+                )),
+            });
+        }
+    };
+
+    let time = start.elapsed().as_secs_f64();
+    let mut resp: HashMap<&str, json::Value> = HashMap::new();
+    resp.insert("took", json::Value::from((time * 1000.0) as usize));
+    resp.insert("hits", json::to_value(services).unwrap());
+    resp.insert("trace_id", json::Value::from(trace_id));
+    Ok(HttpResponse::Ok().json(resp))
+}
+
 #[derive(Debug, Serialize)]
 struct TraceResponseItem {
     trace_id: String,
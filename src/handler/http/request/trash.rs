@@ -0,0 +1,114 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+
+use crate::{
+    common::meta::http::HttpResponse as MetaHttpResponse,
+    handler::http::models::trash::{
+        ListTrashResponseBody, RestoreTrashItemResponseBody, TrashQuery,
+    },
+    service::trash::{self, TrashError},
+};
+
+impl From<TrashError> for HttpResponse {
+    fn from(value: TrashError) -> Self {
+        match value {
+            TrashError::InfraError(err) => MetaHttpResponse::internal_error(err),
+            TrashError::DashboardError(err) => err.into(),
+            TrashError::AlertError(err) => err.into(),
+            TrashError::NotFound => MetaHttpResponse::not_found("Item not found in trash"),
+            TrashError::CreateDefaultFolder => {
+                MetaHttpResponse::internal_error("Error saving default folder")
+            }
+            TrashError::PermittedFolders(err) => MetaHttpResponse::forbidden(err),
+        }
+    }
+}
+
+/// ListTrash
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Trash",
+    operation_id = "ListTrash",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        TrashQuery,
+    ),
+    responses(
+        (status = StatusCode::OK, body = ListTrashResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden", body = HttpResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = HttpResponse),
+    ),
+)]
+#[get("/{org_id}/trash")]
+pub async fn list_trash(
+    path: web::Path<String>,
+    query: web::Query<TrashQuery>,
+    req: HttpRequest,
+) -> impl Responder {
+    let org_id = path.into_inner();
+    let item_type = query.into_inner().item_type.map(Into::into);
+    let user_id = req.headers().get("user_id").and_then(|v| v.to_str().ok());
+    match trash::list(&org_id, user_id, item_type).await {
+        Ok(items) => {
+            let body = ListTrashResponseBody::new(items, trash::expires_after());
+            HttpResponse::Ok().json(body)
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// RestoreTrashItem
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Trash",
+    operation_id = "RestoreTrashItem",
+    security(
+        ("Authorization" = [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("id" = String, Path, description = "Dashboard ID or alert ID of the trashed item"),
+        TrashQuery,
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Item restored", body = RestoreTrashItemResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden", body = HttpResponse),
+        (status = StatusCode::NOT_FOUND, description = "Item not found in trash", body = HttpResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = HttpResponse),
+    ),
+)]
+#[post("/{org_id}/trash/{id}/restore")]
+pub async fn restore_trash_item(
+    path: web::Path<(String, String)>,
+    query: web::Query<TrashQuery>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (org_id, id) = path.into_inner();
+    let item_type = query.into_inner().item_type.map(Into::into);
+    let user_id = req.headers().get("user_id").and_then(|v| v.to_str().ok());
+    match trash::restore(&org_id, user_id, &id, item_type).await {
+        Ok((item_type, folder)) => HttpResponse::Ok().json(RestoreTrashItemResponseBody {
+            item_type: item_type.into(),
+            id,
+            folder_id: folder.folder_id,
+        }),
+        Err(err) => err.into(),
+    }
+}
@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{io::Error, sync::Arc};
+use std::{collections::HashMap, io::Error, sync::Arc};
 
 use actix_web::{
     cookie, delete, get,
@@ -40,8 +40,8 @@ use crate::{
         meta::{
             self,
             user::{
-                AuthTokens, RolesResponse, SignInResponse, SignInUser, UpdateUser, UserOrgRole,
-                UserRequest, UserRole,
+                AuthTokens, BulkUserUpdateRequest, BulkUserUpdateResponse, RolesResponse,
+                SignInResponse, SignInUser, UpdateUser, UserOrgRole, UserRequest, UserRole,
             },
         },
         utils::auth::{generate_presigned_url, UserEmail},
@@ -160,6 +160,47 @@ pub async fn update(
     users::update_user(&org_id, &email_id, self_update, initiator_id, user).await
 }
 
+/// BulkUpdateUsers
+#[utoipa::path(
+    context_path = "/api",
+    tag = "Users",
+    operation_id = "UserBulkUpdate",
+    security(
+        ("Authorization"= [])
+    ),
+    params(
+        ("org_id" = String, Path, description = "Organization name"),
+        ("dry_run" = Option<bool>, Query, description = "Only return the planned changes without applying them"),
+    ),
+    request_body(content = BulkUserUpdateRequest, description = "Role changes and users to remove", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Success", content_type = "application/json", body = BulkUserUpdateResponse),
+        (status = 403, description = "Forbidden", content_type = "application/json", body = HttpResponse),
+    )
+)]
+#[post("/{org_id}/users/_bulk_update")]
+pub async fn bulk_update(
+    org_id: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    req: web::Json<BulkUserUpdateRequest>,
+    user_email: UserEmail,
+) -> Result<HttpResponse, Error> {
+    let org_id = org_id.into_inner();
+    let dry_run = match query.get("dry_run") {
+        Some(v) => v.parse::<bool>().unwrap_or_default(),
+        None => false,
+    };
+    #[cfg(not(feature = "enterprise"))]
+    let mut req = req.into_inner();
+    #[cfg(feature = "enterprise")]
+    let req = req.into_inner();
+    #[cfg(not(feature = "enterprise"))]
+    for change in req.changes.iter_mut() {
+        change.role = meta::user::UserRole::Admin;
+    }
+    users::bulk_update_users(&org_id, &user_email.user_id, req, dry_run).await
+}
+
 /// AddUserToOrganization
 #[utoipa::path(
     context_path = "/api",
@@ -20,15 +20,76 @@ pub mod utils;
 
 use actix_web::{get, web, Error, HttpRequest, HttpResponse};
 use config::get_config;
+use serde::Deserialize;
 use session::WsSession;
-use utils::sessions_cache_utils;
+use utils::{is_valid_request_id, sessions_cache_utils, WsServerEvents, WS_COMPRESSION_ENCODING};
+
+use crate::common::meta::http::HttpResponse as MetaHttpResponse;
+
+/// Path parameters of the websocket endpoint
+#[derive(Debug, Deserialize)]
+pub struct WsPathParams {
+    pub org_id: String,
+    pub request_id: String,
+}
+
+/// Query parameters of the websocket endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct WSQueryParams {
+    /// Gzip large messages and send them as binary frames
+    #[serde(default)]
+    pub compress: bool,
+}
+
+/// Reasons a websocket upgrade request is rejected
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum WsRequestError {
+    #[error("org_id is required")]
+    MissingOrgId,
+    #[error("request_id must be a UUID, got: {0}")]
+    InvalidRequestId(String),
+    #[error("user_id is required")]
+    MissingUserId,
+}
+
+impl From<WsRequestError> for HttpResponse {
+    fn from(value: WsRequestError) -> Self {
+        match value {
+            WsRequestError::MissingOrgId | WsRequestError::InvalidRequestId(_) => {
+                MetaHttpResponse::bad_request(value)
+            }
+            WsRequestError::MissingUserId => MetaHttpResponse::unauthorized(value),
+        }
+    }
+}
+
+/// Validates the identifiers of a websocket upgrade request.
+/// Returns the user id, which is empty only for anonymous sessions.
+fn validate_ws_request(
+    params: &WsPathParams,
+    user_id: Option<&str>,
+    allow_anonymous: bool,
+) -> Result<String, WsRequestError> {
+    if params.org_id.trim().is_empty() {
+        return Err(WsRequestError::MissingOrgId);
+    }
+    if !is_valid_request_id(&params.request_id) {
+        return Err(WsRequestError::InvalidRequestId(params.request_id.clone()));
+    }
+    let user_id = match user_id.map(str::trim) {
+        Some(user_id) if !user_id.is_empty() => user_id.to_string(),
+        _ if allow_anonymous => String::new(),
+        _ => return Err(WsRequestError::MissingUserId),
+    };
+    Ok(user_id)
+}
 
 #[get("{org_id}/ws/{request_id}")]
 pub async fn websocket(
-    path_params: web::Path<(String, String)>,
+    path_params: web::Path<WsPathParams>,
+    query: web::Query<WSQueryParams>,
     req: HttpRequest,
     stream: web::Payload,
-    in_req: HttpRequest,
 ) -> Result<HttpResponse, Error> {
     let cfg = get_config();
 
@@ -40,22 +101,42 @@ pub async fn websocket(
         return Ok(HttpResponse::NotFound().body("WebSocket is disabled"));
     }
 
-    let (org_id, request_id) = path_params.into_inner();
+    let params = path_params.into_inner();
+    let user_id = req.headers().get("user_id").and_then(|v| v.to_str().ok());
+    let user_id = match validate_ws_request(&params, user_id, cfg.websocket.allow_anonymous) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!(
+                "[WS_HANDLER]: Node Role: {} Rejected websocket request: {}",
+                cfg.common.node_role,
+                e
+            );
+            return Ok(e.into());
+        }
+    };
+    let WsPathParams { org_id, request_id } = params;
 
     let prefix = format!("{}/api/", get_config().common.base_uri);
     let path = req.path().strip_prefix(&prefix).unwrap().to_string();
 
-    let user_id = in_req
-        .headers()
-        .get("user_id")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("")
-        .to_string();
-
     let (res, session, msg_stream) = actix_ws::handle(&req, stream)?;
 
-    let ws_session = WsSession::new(session);
+    let compress = query.compress;
+    let ws_session = WsSession::new(session, &request_id, &org_id).with_compression(compress);
     sessions_cache_utils::insert_session(&request_id, ws_session);
+    if compress {
+        let start = WsServerEvents::SessionStart {
+            compression: Some(WS_COMPRESSION_ENCODING.to_string()),
+            compression_threshold: cfg.websocket.compression_threshold_bytes,
+        };
+        if let Err(e) = session::send_message(&request_id, start.to_json()).await {
+            log::error!(
+                "[WS_HANDLER]: request_id: {} Failed to send session start: {}",
+                request_id,
+                e
+            );
+        }
+    }
     log::info!(
         "[WS_HANDLER]: Node Role: {} Got websocket request for request_id: {}",
         cfg.common.node_role,
@@ -74,3 +155,77 @@ pub async fn init() -> Result<(), anyhow::Error> {
     sessions_cache_utils::run_gc_ws_sessions().await;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST_ID: &str = "0d5cca1c-d02c-4fcc-8832-436b7fe6e1ab";
+
+    fn params(org_id: &str, request_id: &str) -> WsPathParams {
+        WsPathParams {
+            org_id: org_id.to_string(),
+            request_id: request_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_ws_request() {
+        let user_id = validate_ws_request(
+            &params("default", REQUEST_ID),
+            Some("root@example.com"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(user_id, "root@example.com");
+    }
+
+    #[test]
+    fn test_validate_ws_request_rejects_missing_org_id() {
+        let ret = validate_ws_request(&params(" ", REQUEST_ID), Some("root@example.com"), false);
+        assert_eq!(ret.unwrap_err(), WsRequestError::MissingOrgId);
+    }
+
+    #[test]
+    fn test_validate_ws_request_rejects_malformed_request_id() {
+        for request_id in [
+            "",
+            "abc",
+            "0d5cca1cd02c4fcc8832436b7fe6e1ab",
+            "0d5cca1c-d02c-4fcc-8832-436b7fe6e1az",
+            "0d5cca1c_d02c_4fcc_8832_436b7fe6e1ab",
+        ] {
+            let ret = validate_ws_request(
+                &params("default", request_id),
+                Some("root@example.com"),
+                false,
+            );
+            assert_eq!(
+                ret.unwrap_err(),
+                WsRequestError::InvalidRequestId(request_id.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_ws_request_rejects_anonymous() {
+        for user_id in [None, Some(""), Some("  ")] {
+            let ret = validate_ws_request(&params("default", REQUEST_ID), user_id, false);
+            assert_eq!(ret.unwrap_err(), WsRequestError::MissingUserId);
+        }
+
+        // allowed when anonymous sessions are enabled
+        let user_id = validate_ws_request(&params("default", REQUEST_ID), None, true).unwrap();
+        assert!(user_id.is_empty());
+    }
+
+    #[test]
+    fn test_ws_request_error_status() {
+        let resp: HttpResponse = WsRequestError::MissingOrgId.into();
+        assert_eq!(resp.status(), 400);
+        let resp: HttpResponse = WsRequestError::InvalidRequestId("abc".to_string()).into();
+        assert_eq!(resp.status(), 400);
+        let resp: HttpResponse = WsRequestError::MissingUserId.into();
+        assert_eq!(resp.status(), 401);
+    }
+}
@@ -20,8 +20,10 @@ use config::{
             Response, SearchEventType, SearchPartitionRequest, SearchPartitionResponse,
             PARTIAL_ERROR_RESPONSE_MESSAGE,
         },
-        sql::{resolve_stream_names, OrderBy},
-        websocket::{SearchEventReq, SearchResultType, MAX_QUERY_RANGE_LIMIT_ERROR_MESSAGE},
+        sql::{resolve_stream_names_with_types, OrderBy},
+        websocket::{
+            SearchEventReq, SearchProgress, SearchResultType, MAX_QUERY_RANGE_LIMIT_ERROR_MESSAGE,
+        },
     },
 };
 use infra::errors::{Error, ErrorCodes};
@@ -34,6 +36,7 @@ use crate::{
     common::{
         meta::search::{CachedQueryResponse, MultiCachedQueryResponse, QueryDelta},
         utils::{
+            auth,
             stream::get_max_query_range,
             websocket::{
                 calc_queried_range, get_search_type_from_ws_req, update_histogram_interval_in_query,
@@ -49,6 +52,16 @@ use crate::{
     },
 };
 
+#[cfg(not(feature = "enterprise"))]
+pub async fn handle_cancel(trace_id: &str, _org_id: &str) -> WsServerEvents {
+    // the search runs on this node, dropping the search task already stopped it
+    log::info!("[WS_HANDLER]: Cancel search for trace_id: {}", trace_id);
+    WsServerEvents::CancelResponse {
+        trace_id: trace_id.to_string(),
+        is_success: true,
+    }
+}
+
 #[cfg(feature = "enterprise")]
 pub async fn handle_cancel(trace_id: &str, org_id: &str) -> WsServerEvents {
     match crate::service::search::cancel_query(org_id, trace_id).await {
@@ -97,14 +110,38 @@ pub async fn handle_search_request(
         end_time
     );
 
+    // only clients that opted in receive progress events
+    let mut progress = req
+        .wants_progress_events()
+        .then(|| SearchProgress::new(start_time, end_time));
+
+    if let Err(code) = auth::check_adhoc_function_permission(
+        org_id,
+        user_id,
+        req.payload.query.query_fn.as_deref(),
+    )
+    .await
+    {
+        let err_res = WsServerEvents::error_response(
+            Error::ErrorCode(code),
+            Some(req_id.to_string()),
+            Some(trace_id),
+        );
+        send_message(req_id, err_res.to_json().to_string()).await?;
+        return Ok(());
+    }
+
     // check and append search event type
     if req.payload.search_type.is_none() {
         req.payload.search_type = Some(req.search_type);
     }
 
+    // every search of the request is cut off by the timeout the client asks for
+    req.payload.timeout = SearchService::get_query_timeout(req.payload.timeout) as i64;
+
     // get stream name
-    let stream_names = match resolve_stream_names(&req.payload.query.sql) {
-        Ok(v) => v.clone(),
+    let streams = match resolve_stream_names_with_types(&req.payload.query.sql, stream_type) {
+        Ok(v) => v,
         Err(e) => {
             let err_res = WsServerEvents::error_response(
                 Error::Message(e.to_string()),
@@ -116,11 +153,11 @@ pub async fn handle_search_request(
         }
     };
 
-    // Check permissions for each stream
+    // Check permissions for each stream, with the stream type it is qualified with
     #[cfg(feature = "enterprise")]
-    for stream_name in stream_names.iter() {
+    for (stream_name, stream_type) in streams.iter() {
         if let Err(e) =
-            enterprise_utils::check_permissions(stream_name, stream_type, user_id, org_id).await
+            enterprise_utils::check_permissions(stream_name, *stream_type, user_id, org_id).await
         {
             let err_res = WsServerEvents::error_response(
                 Error::Message(e),
@@ -131,6 +168,10 @@ pub async fn handle_search_request(
             return Ok(());
         }
     }
+    let stream_names = streams
+        .into_iter()
+        .map(|(stream_name, _)| stream_name)
+        .collect::<Vec<_>>();
 
     // handle search result size
     let req_size = if req.payload.query.size == 0 {
@@ -149,7 +190,7 @@ pub async fn handle_search_request(
     }
 
     // create new sql query with histogram interval
-    let sql = Sql::new(&req.payload.query.clone().into(), org_id, stream_type).await?;
+    let sql = Sql::new(&req.payload.query, org_id, stream_type).await?;
     if let Some(interval) = sql.histogram_interval {
         // modify the sql query statement to include the histogram interval
         let updated_query = update_histogram_interval_in_query(&req.payload.query.sql, interval)?;
@@ -230,6 +271,7 @@ pub async fn handle_search_request(
                 max_query_range,
                 remaining_query_range,
                 &order_by,
+                &mut progress,
             )
             .await?;
         } else {
@@ -251,6 +293,7 @@ pub async fn handle_search_request(
                 user_id,
                 accumulated_results,
                 max_query_range,
+                &mut progress,
             )
             .await?;
         }
@@ -282,6 +325,7 @@ pub async fn handle_search_request(
             user_id,
             accumulated_results,
             max_query_range,
+            &mut progress,
         )
         .await?;
     }
@@ -316,7 +360,6 @@ async fn do_search(
         req.stream_type,
         Some(user_id.to_string()),
         &req.payload,
-        "".to_string(),
     )
     .instrument(span)
     .await;
@@ -352,6 +395,7 @@ async fn handle_cache_responses_and_deltas(
     max_query_range: i64,
     remaining_query_range: i64,
     mut order_by: &OrderBy,
+    progress: &mut Option<SearchProgress>,
 ) -> Result<(), Error> {
     // Force set order_by to desc for dashboards & histogram
     // so that deltas are processed in the reverse order
@@ -434,6 +478,7 @@ async fn handle_cache_responses_and_deltas(
                     user_id,
                     &mut remaining_query_range,
                     cached_search_duration,
+                    progress,
                 )
                 .await?;
                 delta_iter.next(); // Move to the next delta after processing
@@ -447,6 +492,7 @@ async fn handle_cache_responses_and_deltas(
                     accumulated_results,
                     &mut curr_res_size,
                     req.fallback_order_by_col.clone(),
+                    progress,
                 )
                 .await?;
                 cached_resp_iter.next();
@@ -469,6 +515,7 @@ async fn handle_cache_responses_and_deltas(
                 user_id,
                 &mut remaining_query_range,
                 cached_search_duration,
+                progress,
             )
             .await?;
             delta_iter.next(); // Move to the next delta after processing
@@ -482,6 +529,7 @@ async fn handle_cache_responses_and_deltas(
                 accumulated_results,
                 &mut curr_res_size,
                 req.fallback_order_by_col.clone(),
+                progress,
             )
             .await?;
         }
@@ -514,6 +562,7 @@ async fn process_delta(
     user_id: &str,
     remaining_query_range: &mut f64,
     cache_req_duration: i64,
+    progress: &mut Option<SearchProgress>,
 ) -> Result<(), Error> {
     log::info!(
         "[WS_SEARCH]: Processing delta for trace_id: {}, delta: {:?}",
@@ -641,6 +690,17 @@ async fn process_delta(
             send_message(req_id, ws_search_res.to_json().to_string()).await?;
         }
 
+        send_progress(
+            req_id,
+            &trace_id,
+            progress,
+            start_time,
+            end_time,
+            search_res.scan_size,
+            search_res.hits.len(),
+        )
+        .await?;
+
         // Stop if `remaining_query_range` is less than 0
         if *remaining_query_range <= 0.00 {
             log::info!(
@@ -699,6 +759,7 @@ async fn get_partitions(
         // vrl is not required for _search_partition
         query_fn: Default::default(),
         streaming_output: true,
+        timezone: search_payload.query.timezone.clone(),
     };
 
     let res = SearchService::search_partition(
@@ -725,6 +786,7 @@ async fn send_cached_responses(
     accumulated_results: &mut Vec<SearchResultType>,
     curr_res_size: &mut i64,
     fallback_order_by_col: Option<String>,
+    progress: &mut Option<SearchProgress>,
 ) -> Result<(), Error> {
     if let Some(is_cancelled) = search_registry_utils::is_cancelled(trace_id) {
         if is_cancelled {
@@ -795,6 +857,17 @@ async fn send_cached_responses(
     );
     send_message(req_id, ws_search_res.to_json().to_string()).await?;
 
+    send_progress(
+        req_id,
+        trace_id,
+        progress,
+        cached.response_start_time,
+        cached.response_end_time,
+        0,
+        cached.cached_response.hits.len(),
+    )
+    .await?;
+
     Ok(())
 }
 
@@ -809,6 +882,7 @@ async fn do_partitioned_search(
     user_id: &str,
     accumulated_results: &mut Vec<SearchResultType>,
     max_query_range: i64, // hours
+    progress: &mut Option<SearchProgress>,
 ) -> Result<(), Error> {
     // limit the search by max_query_range
     let mut range_error = String::new();
@@ -925,6 +999,17 @@ async fn do_partitioned_search(
             send_message(req_id, ws_search_res.to_json().to_string()).await?;
         }
 
+        send_progress(
+            req_id,
+            trace_id,
+            progress,
+            start_time,
+            end_time,
+            search_res.scan_size,
+            search_res.hits.len(),
+        )
+        .await?;
+
         // Stop if reached the requested result size
         if req_size != -1 && curr_res_size >= req_size {
             log::info!(
@@ -961,6 +1046,30 @@ async fn do_partitioned_search(
     Ok(())
 }
 
+/// Records a searched time range and sends a `progress` event if the client opted in
+async fn send_progress(
+    req_id: &str,
+    trace_id: &str,
+    progress: &mut Option<SearchProgress>,
+    start_time: i64,
+    end_time: i64,
+    scan_size: usize,
+    hits: usize,
+) -> Result<(), Error> {
+    let Some(progress) = progress.as_mut() else {
+        return Ok(());
+    };
+    progress.record(start_time, end_time, scan_size, hits);
+
+    let ws_progress = WsServerEvents::Progress {
+        trace_id: trace_id.to_string(),
+        percent: progress.percent(),
+        scan_size: progress.scan_size,
+        hits: progress.hits,
+    };
+    send_message(req_id, ws_progress.to_json().to_string()).await
+}
+
 async fn send_partial_search_resp(
     req_id: &str,
     trace_id: &str,
@@ -1032,7 +1141,7 @@ async fn write_results_to_cache(
         }
     }
 
-    let merged_response = cache::merge_response(
+    let merged_response = match cache::merge_response(
         &c_resp.trace_id,
         &mut cached_responses,
         &mut search_responses,
@@ -1040,7 +1149,17 @@ async fn write_results_to_cache(
         c_resp.limit,
         c_resp.is_descending,
         c_resp.took,
-    );
+    ) {
+        Ok(res) => res,
+        Err(e) => {
+            log::warn!(
+                "[WS_SEARCH]: Skip writing results to cache for trace_id: {}, {}",
+                c_resp.trace_id,
+                e
+            );
+            return Ok(());
+        }
+    };
 
     // There are 3 types of partial responses:
     // 1. VRL error
@@ -1049,8 +1168,7 @@ async fn write_results_to_cache(
     // Cache partial results only if there is a range error
     let skip_cache_results = (merged_response.is_partial
         && (merged_response.new_start_time.is_none() || merged_response.new_end_time.is_none()))
-        || (!merged_response.function_error.is_empty()
-            && merged_response.function_error.contains("vrl"));
+        || !merged_response.function_errors.is_empty();
 
     if cfg.common.result_cache_enabled && !skip_cache_results {
         cache::write_results_v2(
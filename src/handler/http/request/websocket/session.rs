@@ -13,16 +13,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{
-    sync::atomic::{AtomicBool, Ordering},
-    time::Duration,
-};
+use std::time::Duration;
 
 use actix_http::ws::{CloseCode, CloseReason};
 use actix_ws::{MessageStream, Session};
 use config::{
     get_config,
     meta::websocket::{SearchEventReq, SearchResultType},
+    metrics,
 };
 use dashmap::DashMap;
 use futures::StreamExt;
@@ -34,14 +32,15 @@ use o2_enterprise::enterprise::common::{
 };
 use once_cell::sync::Lazy;
 use rand::prelude::SliceRandom;
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, error::TrySendError};
 
-use super::utils::search_registry_utils::SearchState;
-#[cfg(feature = "enterprise")]
-use crate::handler::http::request::websocket::utils::search_registry_utils;
+use super::utils::search_registry_utils::{SearchState, SessionSearchTasks};
 use crate::handler::http::request::websocket::{
     search,
-    utils::{sessions_cache_utils, WsClientEvents, WsServerEvents},
+    utils::{
+        compress_message, search_registry_utils, sessions_cache_utils, WsClientEvents,
+        WsServerEvents,
+    },
 };
 #[cfg(feature = "enterprise")]
 use crate::service::self_reporting::audit;
@@ -49,6 +48,24 @@ use crate::service::self_reporting::audit;
 // Global registry for search requests by `trace_id`
 pub static SEARCH_REGISTRY: Lazy<DashMap<String, SearchState>> = Lazy::new(DashMap::new);
 
+// How long closing a session may wait for room in the connection buffer
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A message waiting in the send buffer of a session
+#[derive(Debug)]
+enum OutboundMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum EnqueueError {
+    #[error("slow consumer, send buffer full for {0:?}")]
+    SlowConsumer(Duration),
+    #[error("session closed")]
+    Closed,
+}
+
 // Do not clone the session, instead use a reference to the session
 pub struct WsSession {
     inner: Option<Session>,
@@ -56,24 +73,59 @@ pub struct WsSession {
     last_activity_ts: i64,
     // Utc timestamp in microseconds
     created_ts: i64,
-    message_in_flight: AtomicBool,
+    // Messages waiting to be written to the client by the writer task
+    outbound: mpsc::Sender<OutboundMessage>,
+    org_id: String,
+    // Search tasks spawned by this session, aborted when the session closes
+    search_tasks: SessionSearchTasks,
+    // Pings sent since the last pong from the client
+    missed_pongs: i64,
+    // Gzip large messages, requested by the client with `compress=true`
+    compress: bool,
 }
 
 impl WsSession {
-    pub fn new(inner: Session) -> Self {
+    pub fn new(inner: Session, req_id: &str, org_id: &str) -> Self {
+        let cfg = get_config();
         let now = chrono::Utc::now().timestamp_micros();
+        let (outbound, rx) = mpsc::channel(cfg.websocket.send_buffer_size.max(1));
+        tokio::spawn(write_outbound(
+            req_id.to_string(),
+            inner.clone(),
+            rx,
+            Duration::from_secs(cfg.websocket.slow_consumer_timeout_secs),
+        ));
         Self {
             inner: Some(inner),
             last_activity_ts: now,
             created_ts: now,
-            message_in_flight: AtomicBool::new(false),
+            outbound,
+            org_id: org_id.to_string(),
+            search_tasks: SessionSearchTasks::default(),
+            missed_pongs: 0,
+            compress: false,
         }
     }
 
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
     pub fn update_activity(&mut self) {
         self.last_activity_ts = chrono::Utc::now().timestamp_micros();
     }
 
+    /// The client answered the pings sent so far
+    pub fn record_pong(&mut self) {
+        self.missed_pongs = 0;
+    }
+
+    /// Whether the client left the last `max_missed_pongs` pings unanswered
+    pub fn is_unresponsive(&self, max_missed_pongs: i64) -> bool {
+        max_missed_pongs > 0 && self.missed_pongs >= max_missed_pongs
+    }
+
     pub fn is_expired(&self) -> bool {
         let cfg = get_config();
         let now = chrono::Utc::now().timestamp_micros();
@@ -86,16 +138,26 @@ impl WsSession {
             || (now - self.created_ts) > max_lifetime_micros
     }
 
-    /// Send a text message to the client
-    pub async fn text(&mut self, msg: String) -> Result<(), actix_ws::Closed> {
-        self.update_activity();
-        if let Some(ref mut session) = self.inner {
-            session.text(msg).await
-        } else {
-            Err(actix_ws::Closed)
+    /// Prepares a message for the send buffer, gzipped into a binary frame
+    /// when the session asked for compression and the message is large enough
+    fn encode(&self, msg: String) -> OutboundMessage {
+        if !self.compress || msg.len() < get_config().websocket.compression_threshold_bytes {
+            return OutboundMessage::Text(msg);
+        }
+        match compress_message(&msg) {
+            Ok(data) => OutboundMessage::Binary(data),
+            Err(e) => {
+                log::warn!("[WS_HANDLER]: Failed to compress message, sending as text: {e}");
+                OutboundMessage::Text(msg)
+            }
         }
     }
 
+    /// Whether messages are still waiting in the send buffer
+    fn has_pending_messages(&self) -> bool {
+        self.outbound.capacity() < self.outbound.max_capacity()
+    }
+
     /// Close the session with a reason
     pub async fn close(&mut self, reason: Option<CloseReason>) -> Result<(), actix_ws::Closed> {
         self.update_activity();
@@ -120,7 +182,9 @@ impl WsSession {
     pub async fn ping(&mut self, payload: &[u8]) -> Result<(), actix_ws::Closed> {
         self.update_activity();
         if let Some(ref mut session) = self.inner {
-            session.ping(payload).await
+            session.ping(payload).await?;
+            self.missed_pongs += 1;
+            Ok(())
         } else {
             Err(actix_ws::Closed)
         }
@@ -135,13 +199,21 @@ pub async fn run(
     path: String,
 ) {
     let cfg = get_config();
-    let mut ping_interval =
-        tokio::time::interval(Duration::from_secs(cfg.websocket.ping_interval_secs as u64));
+    let mut ping_interval = tokio::time::interval(Duration::from_secs(
+        cfg.websocket.ping_interval_secs.max(1) as u64,
+    ));
+    let max_missed_pongs = cfg.websocket.max_missed_pongs;
     let mut close_reason: Option<CloseReason> = None;
 
     loop {
         tokio::select! {
-            Some(msg) = msg_stream.next() => {
+            msg = msg_stream.next() => {
+                // The client went away without a close frame
+                let Some(msg) = msg else {
+                    log::info!("[WS_HANDLER]: Request Id: {} Message stream ended", req_id);
+                    break;
+                };
+
                 // Update activity on any message
                 if let Some(mut session) = sessions_cache_utils::get_mut_session(&req_id) {
                     session.update_activity();
@@ -161,6 +233,9 @@ pub async fn run(
                     }
                     Ok(actix_ws::Message::Pong(_)) => {
                         log::debug!("[WS_HANDLER] Received pong from {}", req_id);
+                        if let Some(mut session) = sessions_cache_utils::get_mut_session(&req_id) {
+                            session.record_pong();
+                        }
                     }
                     Ok(actix_ws::Message::Text(msg)) => {
                         log::info!("[WS_HANDLER]: Request Id: {} Node Role: {} Received message: {}",
@@ -193,16 +268,89 @@ pub async fn run(
             }
             // Heartbeat to keep the connection alive
             _ = ping_interval.tick() => {
-                if let Some(mut session) = sessions_cache_utils::get_mut_session(&req_id) {
-                    if let Err(e) = session.ping(&[]).await {
-                        log::error!("[WS_HANDLER] Failed to send ping: {}", e);
-                        break;
-                    }
+                if let Err(reason) = heartbeat(&req_id, max_missed_pongs).await {
+                    close_reason = reason;
+                    break;
                 }
             }
         }
     }
-    cleanup_and_close_session(&req_id, close_reason).await;
+    close_session(&req_id, close_reason).await;
+}
+
+/// Pings the client to keep the connection alive through proxies.
+/// Returns the reason to close the session with when the connection is gone, which is also the
+/// case when the client stopped answering the pings.
+async fn heartbeat(req_id: &str, max_missed_pongs: i64) -> Result<(), Option<CloseReason>> {
+    let Some(mut session) = sessions_cache_utils::get_mut_session(req_id) else {
+        return Ok(());
+    };
+    if session.is_unresponsive(max_missed_pongs) {
+        log::warn!(
+            "[WS_HANDLER]: req_id: {} No pong for the last {} pings, closing session",
+            req_id,
+            max_missed_pongs
+        );
+        return Err(Some(CloseReason {
+            code: CloseCode::Away,
+            description: Some(format!("req_id {} Missed pongs", req_id)),
+        }));
+    }
+    if let Err(e) = session.ping(&[]).await {
+        log::error!("[WS_HANDLER] Failed to send ping: {}", e);
+        return Err(None);
+    }
+    Ok(())
+}
+
+/// Tear the session down once its connection is gone
+async fn close_session(req_id: &str, close_reason: Option<CloseReason>) {
+    cancel_session_searches(req_id).await;
+    cleanup_and_close_session(req_id, close_reason).await;
+}
+
+/// Cancel all in-flight searches owned by the session.
+/// The search tasks are aborted and, on enterprise, the cancellation is propagated to the
+/// query nodes through the same path used by the query manager.
+pub async fn cancel_session_searches(req_id: &str) {
+    #[cfg(feature = "enterprise")]
+    let org_id = sessions_cache_utils::get_mut_session(req_id)
+        .map(|session| session.org_id.clone())
+        .unwrap_or_default();
+    let mut trace_ids = sessions_cache_utils::get_mut_session(req_id)
+        .map(|mut session| session.search_tasks.abort_all())
+        .unwrap_or_default();
+
+    // Also pick up searches registered for the session but not yet tracked
+    for entry in SEARCH_REGISTRY.iter() {
+        if entry.value().get_req_id() == req_id && !trace_ids.contains(entry.key()) {
+            trace_ids.push(entry.key().clone());
+        }
+    }
+
+    for trace_id in trace_ids {
+        let Some((_, state)) = SEARCH_REGISTRY.remove(&trace_id) else {
+            continue;
+        };
+        if let SearchState::Running { cancel_tx, .. } = state {
+            let _ = cancel_tx.try_send(());
+            #[cfg(feature = "enterprise")]
+            if !org_id.is_empty() {
+                let _ = search::handle_cancel(&trace_id, &org_id).await;
+            }
+            log::info!(
+                "[WS_HANDLER]: req_id: {} Cancelled in-flight search, trace_id: {}",
+                req_id,
+                trace_id
+            );
+        }
+    }
+}
+
+fn untrack_search_task(req_id: &str, trace_id: &str) {
+    if let Some(mut session) = sessions_cache_utils::get_mut_session(req_id) {
+        session.search_tasks.untrack(trace_id);
+    }
 }
 
 /// Handle the incoming text message
@@ -216,17 +364,16 @@ pub async fn handle_text_message(
     msg: String,
     path: String,
 ) {
-    match serde_json::from_str::<WsClientEvents>(&msg) {
+    match WsClientEvents::from_json(&msg) {
         Ok(client_msg) => {
             match client_msg {
                 WsClientEvents::Search(ref search_req) => {
                     handle_search_event(search_req, org_id, user_id, req_id, path.clone()).await;
                 }
-                #[cfg(feature = "enterprise")]
                 WsClientEvents::Cancel { trace_id } => {
                     // First handle the cancel event
                     // send a cancel flag to the search task
-                    if let Err(e) = handle_cancel_event(&trace_id).await {
+                    if let Err(e) = handle_cancel_event(req_id, &trace_id).await {
                         log::warn!("[WS_HANDLER]: Error in cancelling : {}", e);
                         return;
                     }
@@ -319,54 +466,108 @@ pub async fn handle_text_message(
 }
 
 pub async fn send_message(req_id: &str, msg: String) -> Result<(), Error> {
-    let mut session = if let Some(session) = sessions_cache_utils::get_mut_session(req_id) {
-        session
-    } else {
+    log::debug!("[WS_HANDLER]: req_id: {} sending message: {}", req_id, msg);
+    // don't hold the session across the wait for room in the send buffer
+    let Some((outbound, msg, org_id)) =
+        sessions_cache_utils::get_mut_session(req_id).map(|mut session| {
+            session.update_activity();
+            (
+                session.outbound.clone(),
+                session.encode(msg),
+                session.org_id.clone(),
+            )
+        })
+    else {
         return Err(Error::Message(format!(
             "[req_id {}] session not found",
             req_id
         )));
     };
 
-    // Try to acquire the in-flight flag with retries
-    let mut attempts = 0;
-    while attempts < 3 {
-        match session.message_in_flight.compare_exchange(
-            false,
-            true,
-            Ordering::SeqCst,
-            Ordering::SeqCst,
-        ) {
-            Ok(_) => {
-                // Got the lock, proceed with send
-                log::debug!("[WS_HANDLER]: req_id: {} sending message: {}", req_id, msg);
-                let result = session.text(msg).await.map_err(|e| {
-                    log::error!("[WS_HANDLER]: Failed to send message: {:?}", e);
-                    Error::Message(e.to_string())
-                });
-
-                // Reset the in-flight flag
-                session.message_in_flight.store(false, Ordering::SeqCst);
-                return result;
+    let timeout = Duration::from_secs(get_config().websocket.slow_consumer_timeout_secs);
+    match enqueue(&outbound, msg, &org_id, timeout).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            if matches!(e, EnqueueError::SlowConsumer(_)) {
+                close_slow_consumer(req_id);
+            }
+            log::error!(
+                "[WS_HANDLER]: req_id: {} Failed to send message: {}",
+                req_id,
+                e
+            );
+            Err(Error::Message(e.to_string()))
+        }
+    }
+}
+
+/// Queues a message in a send buffer. When the buffer is full the caller waits
+/// for the client to catch up, for at most `timeout`.
+async fn enqueue<T>(
+    outbound: &mpsc::Sender<T>,
+    msg: T,
+    org_id: &str,
+    timeout: Duration,
+) -> Result<(), EnqueueError> {
+    let msg = match outbound.try_send(msg) {
+        Ok(()) => return Ok(()),
+        Err(TrySendError::Closed(_)) => return Err(EnqueueError::Closed),
+        Err(TrySendError::Full(msg)) => msg,
+    };
+    metrics::WEBSOCKET_BACKPRESSURE_NUMS
+        .with_label_values(&[org_id])
+        .inc();
+    match tokio::time::timeout(timeout, outbound.send(msg)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(_)) => Err(EnqueueError::Closed),
+        Err(_) => Err(EnqueueError::SlowConsumer(timeout)),
+    }
+}
+
+/// Writes the buffered messages of a session to the client one at a time, so
+/// a client that doesn't keep up fills the bounded send buffer. A client that
+/// takes longer than `timeout` to take a message is closed as a slow consumer.
+async fn write_outbound(
+    req_id: String,
+    mut session: Session,
+    mut outbound: mpsc::Receiver<OutboundMessage>,
+    timeout: Duration,
+) {
+    while let Some(msg) = outbound.recv().await {
+        let write = async {
+            match msg {
+                OutboundMessage::Text(text) => session.text(text).await,
+                OutboundMessage::Binary(data) => session.binary(data).await,
             }
+        };
+        match tokio::time::timeout(timeout, write).await {
+            Ok(Ok(())) => {}
+            // the connection is gone
+            Ok(Err(_)) => break,
             Err(_) => {
-                // Message in flight, wait a tiny bit and retry
-                attempts += 1;
-                if attempts < 3 {
-                    tokio::task::yield_now().await;
-                }
+                close_slow_consumer(&req_id);
+                break;
             }
         }
     }
+}
 
-    log::error!(
-        "[WS_HANDLER]: req_id: {} Failed to send message after retries",
+/// Closes the session of a client that stopped reading its messages. This runs
+/// apart from the search task that noticed it, since closing aborts the
+/// searches of the session.
+fn close_slow_consumer(req_id: &str) {
+    log::warn!(
+        "[WS_HANDLER]: req_id: {} Client is not reading its messages, closing session",
         req_id
     );
-
-    Err(Error::Message(
-        "Failed to send message after retries".to_string(),
-    ))
+    let req_id = req_id.to_string();
+    tokio::spawn(async move {
+        let reason = Some(CloseReason {
+            code: CloseCode::Policy,
+            description: Some("slow consumer".to_string()),
+        });
+        close_session(&req_id, reason).await;
+    });
 }
 
 async fn cleanup_and_close_session(req_id: &str, close_reason: Option<CloseReason>) {
@@ -379,28 +580,37 @@ async fn cleanup_and_close_session(req_id: &str, close_reason: Option<CloseReaso
             );
         }
 
-        // Wait for any in-flight message to complete
+        // Give the writer a chance to flush the buffered messages
         let mut retries = 0;
-        while session.message_in_flight.load(Ordering::SeqCst) && retries < 3 {
+        while session.has_pending_messages() && retries < 3 {
             tokio::task::yield_now().await;
             retries += 1;
         }
 
-        if session.message_in_flight.load(Ordering::SeqCst) {
+        if session.has_pending_messages() {
             log::warn!(
-                "[WS_HANDLER]: req_id: {} Closing with message in flight after {} retries",
+                "[WS_HANDLER]: req_id: {} Closing with buffered messages after {} retries",
                 req_id,
                 retries
             );
         }
 
-        // Attempt to close the session
-        if let Err(e) = session.close(close_reason).await {
-            log::error!(
-                "[WS_HANDLER]: req_id: {} Failed to close session gracefully: {:?}",
-                req_id,
-                e
-            );
+        // Attempt to close the session, a stalled client may never make room for the close frame
+        match tokio::time::timeout(CLOSE_TIMEOUT, session.close(close_reason)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                log::error!(
+                    "[WS_HANDLER]: req_id: {} Failed to close session gracefully: {:?}",
+                    req_id,
+                    e
+                );
+            }
+            Err(_) => {
+                log::warn!(
+                    "[WS_HANDLER]: req_id: {} Timed out sending the close frame",
+                    req_id
+                );
+            }
         }
     }
 
@@ -430,6 +640,7 @@ async fn handle_search_event(
     let trace_id = search_req.trace_id.clone();
     let trace_id_for_task = trace_id.clone();
     let search_req = search_req.clone();
+    let session_id = req_id.clone();
 
     #[cfg(feature = "enterprise")]
     let is_audit_enabled = get_o2_config().common.audit_enabled;
@@ -447,7 +658,7 @@ async fn handle_search_event(
     );
 
     // Spawn the search task
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         // Handle the search request
         // If search is cancelled, the task will exit
         // Otherwise, the task will complete and the results will be sent to the client
@@ -462,6 +673,8 @@ async fn handle_search_event(
                 &user_id,
                 search_req.clone(),
             ) => {
+                // The task is finishing on its own, it must not be aborted by the cleanup below
+                untrack_search_task(&req_id, &trace_id_for_task);
                 match search_result {
                     Ok(_) => {
                         if let Some(mut state) = SEARCH_REGISTRY.get_mut(&trace_id_for_task) {
@@ -527,36 +740,63 @@ async fn handle_search_event(
                 // the cancel handler will close the session
 
                 // Just cleanup resources when cancelled
+                untrack_search_task(&req_id, &trace_id_for_task);
                 cleanup_search_resources(&trace_id_for_task).await;
             }
         }
     });
+
+    // Track the task so that it is aborted if the session goes away
+    match sessions_cache_utils::get_mut_session(&session_id) {
+        Some(mut session) if !handle.is_finished() => {
+            session.search_tasks.track(&trace_id, handle.abort_handle());
+        }
+        Some(_) => {}
+        None => {
+            log::info!(
+                "[WS_HANDLER]: req_id: {} session closed, aborting search trace_id: {}",
+                session_id,
+                trace_id
+            );
+            handle.abort();
+            cleanup_search_resources(&trace_id).await;
+        }
+    }
 }
 
 // Cancel handler
-#[cfg(feature = "enterprise")]
-async fn handle_cancel_event(trace_id: &str) -> Result<(), anyhow::Error> {
-    if let Some(mut entry) = SEARCH_REGISTRY.get_mut(trace_id) {
-        let state = entry.value_mut();
-        let cancel_tx = match state {
-            SearchState::Running { cancel_tx, .. } => cancel_tx.clone(),
-            state => {
-                let err_msg = format!("Cannot cancel search in state: {:?}", state);
-                log::warn!("[WS_HANDLER]: {}", err_msg);
-                return Err(anyhow::anyhow!(err_msg));
-            }
-        };
-
-        *entry.value_mut() = SearchState::Cancelled {
-            req_id: trace_id.to_string(),
-        };
-
-        if let Err(e) = cancel_tx.send(()).await {
-            log::error!("[WS_HANDLER]: Failed to send cancel signal: {}", e);
+// Only searches started by the session can be cancelled through it. The search task drops the
+// running search once it gets the signal, which also stops the cluster search spawned for it
+async fn handle_cancel_event(req_id: &str, trace_id: &str) -> Result<(), anyhow::Error> {
+    let Some(mut entry) = SEARCH_REGISTRY.get_mut(trace_id) else {
+        return Err(anyhow::anyhow!("No search found for trace_id: {trace_id}"));
+    };
+    if entry.value().get_req_id() != req_id {
+        log::warn!(
+            "[WS_HANDLER]: req_id: {} tried to cancel trace_id: {} of another session",
+            req_id,
+            trace_id
+        );
+        return Err(anyhow::anyhow!("No search found for trace_id: {trace_id}"));
+    }
+    let state = entry.value_mut();
+    let (cancel_tx, req_id) = match state {
+        SearchState::Running { cancel_tx, req_id } => (cancel_tx.clone(), req_id.clone()),
+        state => {
+            let err_msg = format!("Cannot cancel search in state: {:?}", state);
+            log::warn!("[WS_HANDLER]: {}", err_msg);
+            return Err(anyhow::anyhow!(err_msg));
         }
+    };
 
-        log::info!("[WS_HANDLER]: Search cancelled for trace_id: {}", trace_id);
+    *entry.value_mut() = SearchState::Cancelled { req_id };
+    drop(entry);
+
+    if let Err(e) = cancel_tx.send(()).await {
+        log::error!("[WS_HANDLER]: Failed to send cancel signal: {}", e);
     }
+
+    log::info!("[WS_HANDLER]: Search cancelled for trace_id: {}", trace_id);
     Ok(())
 }
 
@@ -604,3 +844,220 @@ async fn cleanup_search_resources(trace_id: &str) {
     SEARCH_REGISTRY.remove(trace_id);
     log::debug!("[WS_HANDLER]: trace_id: {}, Resources cleaned up", trace_id);
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{error::PayloadError, test::TestRequest, web::Bytes};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missed_pongs_tear_down_session() {
+        let req_id = "5b7d2c3e-8f41-4a6b-9c0d-1e2f3a4b5c6d";
+        let req = TestRequest::default()
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_http_request();
+        // the response keeps the outgoing frames in memory
+        let (_res, session, _msg_stream) = actix_ws::handle(
+            &req,
+            futures::stream::pending::<Result<Bytes, PayloadError>>(),
+        )
+        .unwrap();
+        sessions_cache_utils::insert_session(req_id, WsSession::new(session, req_id, "default"));
+
+        // answered pings keep the session open
+        assert!(heartbeat(req_id, 2).await.is_ok());
+        sessions_cache_utils::get_mut_session(req_id)
+            .unwrap()
+            .record_pong();
+        assert!(heartbeat(req_id, 2).await.is_ok());
+        assert!(heartbeat(req_id, 2).await.is_ok());
+
+        // the last two pings went unanswered
+        let reason = heartbeat(req_id, 2).await.unwrap_err();
+        assert_eq!(reason.as_ref().unwrap().code, CloseCode::Away);
+        close_session(req_id, reason).await;
+        assert!(!sessions_cache_utils::contains_session(req_id));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_only_own_search() {
+        let trace_id = "cancel_trace_other_session";
+        let (cancel_tx, mut cancel_rx) = mpsc::channel(1);
+        SEARCH_REGISTRY.insert(
+            trace_id.to_string(),
+            SearchState::Running {
+                cancel_tx,
+                req_id: "owner_req_id".to_string(),
+            },
+        );
+
+        assert!(handle_cancel_event("other_req_id", trace_id).await.is_err());
+        assert!(handle_cancel_event("other_req_id", "unknown_trace")
+            .await
+            .is_err());
+        assert_eq!(search_registry_utils::is_cancelled(trace_id), Some(false));
+        assert!(cancel_rx.try_recv().is_err());
+
+        handle_cancel_event("owner_req_id", trace_id).await.unwrap();
+        assert_eq!(search_registry_utils::is_cancelled(trace_id), Some(true));
+        assert!(cancel_rx.try_recv().is_ok());
+        SEARCH_REGISTRY.remove(trace_id);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_running_search() {
+        let req_id = "0c9e8d7f-6a5b-4c3d-8e2f-1a0b9c8d7e6f";
+        let trace_id = "cancel_trace_1";
+        let req = TestRequest::default()
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_http_request();
+        let (res, session, _msg_stream) = actix_ws::handle(
+            &req,
+            futures::stream::pending::<Result<Bytes, PayloadError>>(),
+        )
+        .unwrap();
+        sessions_cache_utils::insert_session(req_id, WsSession::new(session, req_id, "default"));
+
+        // a long running search, spawned the same way as `handle_search_event`
+        let (cancel_tx, mut cancel_rx) = mpsc::channel(1);
+        SEARCH_REGISTRY.insert(
+            trace_id.to_string(),
+            SearchState::Running {
+                cancel_tx,
+                req_id: req_id.to_string(),
+            },
+        );
+        let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = async move {
+                    let _search = dropped_tx;
+                    std::future::pending::<()>().await;
+                } => {}
+                _ = cancel_rx.recv() => {
+                    untrack_search_task(req_id, trace_id);
+                    cleanup_search_resources(trace_id).await;
+                }
+            }
+        });
+        sessions_cache_utils::get_mut_session(req_id)
+            .unwrap()
+            .search_tasks
+            .track(trace_id, handle.abort_handle());
+
+        let msg = format!(r#"{{"type":"cancel","trace_id":"{trace_id}"}}"#);
+        handle_text_message("default", "root@example.com", req_id, msg, String::new()).await;
+
+        // the search future is dropped and the task ends
+        let timeout = tokio::time::Duration::from_secs(1);
+        assert!(tokio::time::timeout(timeout, dropped_rx).await.is_ok());
+        assert!(tokio::time::timeout(timeout, handle).await.unwrap().is_ok());
+        assert!(!SEARCH_REGISTRY.contains_key(trace_id));
+        assert!(!sessions_cache_utils::contains_session(req_id));
+
+        // the client got the cancel ack before the session closed
+        let body = tokio::time::timeout(timeout, actix_web::body::to_bytes(res.into_body()))
+            .await
+            .unwrap()
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains(r#""type":"cancel_response""#));
+        assert!(body.contains(trace_id));
+    }
+
+    #[tokio::test]
+    async fn test_writer_closes_slow_consumer() {
+        let req_id = "3f2a1b0c-9d8e-4f7a-b6c5-d4e3f2a1b0c9";
+        let req = TestRequest::default()
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_http_request();
+        // nobody reads the response, so the frames pile up until writes block
+        let (res, session, _msg_stream) = actix_ws::handle(
+            &req,
+            futures::stream::pending::<Result<Bytes, PayloadError>>(),
+        )
+        .unwrap();
+        sessions_cache_utils::insert_session(
+            req_id,
+            WsSession::new(session.clone(), req_id, "default"),
+        );
+
+        let (outbound, rx) = mpsc::channel(128);
+        let writer = tokio::spawn(write_outbound(
+            req_id.to_string(),
+            session,
+            rx,
+            Duration::from_millis(50),
+        ));
+        for _ in 0..128 {
+            outbound
+                .send(OutboundMessage::Text("stalled".to_string()))
+                .await
+                .unwrap();
+        }
+
+        // the writer gives up on the client and closes its session
+        let timeout = Duration::from_secs(1);
+        assert!(tokio::time::timeout(timeout, writer).await.unwrap().is_ok());
+        drop(res);
+        tokio::time::timeout(timeout, async {
+            while sessions_cache_utils::contains_session(req_id) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_waits_for_stalled_client() {
+        let org_id = "backpressure_org";
+        let backpressure = || {
+            metrics::WEBSOCKET_BACKPRESSURE_NUMS
+                .with_label_values(&[org_id])
+                .get()
+        };
+        let timeout = Duration::from_millis(50);
+        // a client that never reads its messages
+        let (outbound, mut stalled) = mpsc::channel::<Vec<u8>>(4);
+
+        for _ in 0..4 {
+            enqueue(&outbound, vec![0u8; 1024], org_id, timeout)
+                .await
+                .unwrap();
+        }
+        assert_eq!(backpressure(), 0);
+
+        // the producer waits for room instead of buffering more
+        let ret = enqueue(&outbound, vec![0u8; 1024], org_id, timeout).await;
+        assert!(matches!(ret, Err(EnqueueError::SlowConsumer(_))));
+        assert_eq!(backpressure(), 1);
+        assert_eq!(stalled.len(), 4);
+
+        // and goes on once the client catches up
+        let producer = tokio::spawn({
+            let outbound = outbound.clone();
+            async move { enqueue(&outbound, vec![0u8; 1024], org_id, Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!producer.is_finished());
+        assert!(stalled.recv().await.is_some());
+        assert!(producer.await.unwrap().is_ok());
+        assert_eq!(backpressure(), 2);
+        assert_eq!(stalled.len(), 4);
+
+        drop(stalled);
+        let ret = enqueue(&outbound, vec![0u8; 1024], org_id, timeout).await;
+        assert!(matches!(ret, Err(EnqueueError::Closed)));
+    }
+}
@@ -13,11 +13,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::io::Write;
+
 use actix_web::http::StatusCode;
 use config::meta::websocket::SearchEventReq;
+use flate2::{write::GzEncoder, Compression};
 use infra::{errors, errors::Error};
 use serde::{Deserialize, Serialize};
 
+/// Whether the `request_id` path segment identifying a websocket session is a
+/// hyphenated UUID. Kept distinct from the `trace_id`s of the searches run by
+/// the session.
+pub fn is_valid_request_id(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
 pub mod enterprise_utils {
     #[allow(unused_imports)]
     use config::meta::stream::StreamType;
@@ -90,10 +105,9 @@ pub mod sessions_cache_utils {
     use config::get_config;
     use futures::FutureExt;
 
-    use super::search_registry_utils::SearchState;
     use crate::{
         common::infra::config::WS_SESSIONS,
-        handler::http::request::websocket::session::{WsSession, SEARCH_REGISTRY},
+        handler::http::request::websocket::session::{cancel_session_searches, WsSession},
     };
 
     pub async fn run_gc_ws_sessions() {
@@ -136,11 +150,12 @@ pub mod sessions_cache_utils {
             .collect();
 
         for session_id in expired {
-            // Clean up associated searches first
-            cleanup_searches_for_session(&session_id);
+            let session_id = session_id.as_str();
+            // Cancel associated searches first
+            cancel_session_searches(session_id).await;
 
             // Close and remove session
-            if let Some(mut session) = get_mut_session(&session_id) {
+            if let Some(mut session) = get_mut_session(session_id) {
                 log::info!("[WS_GC] Closing expired session: {}", session_id);
 
                 if let Err(e) = session
@@ -154,48 +169,13 @@ pub mod sessions_cache_utils {
                 }
             }
 
-            remove_session(&session_id);
+            remove_session(session_id);
             log::info!("[WS_GC] Removed expired session: {}", session_id);
         }
 
         log::info!("[WS_GC] Remaining active sessions: {}", len_sessions());
     }
 
-    fn cleanup_searches_for_session(session_id: &str) {
-        let searches_to_remove: Vec<String> = SEARCH_REGISTRY
-            .iter()
-            .filter_map(|entry| {
-                if entry.value().get_req_id() == session_id {
-                    Some(entry.key().clone())
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        for trace_id in searches_to_remove {
-            if let Some((_, state)) = SEARCH_REGISTRY.remove(&trace_id) {
-                match state {
-                    SearchState::Running { cancel_tx, req_id } => {
-                        let _ = cancel_tx.try_send(());
-                        log::info!(
-                            "[WS_GC] Cancelled running search: {} for session: {}",
-                            trace_id,
-                            req_id
-                        );
-                    }
-                    _ => {
-                        log::debug!(
-                            "[WS_GC] Removed search: {} for session: {}",
-                            trace_id,
-                            session_id
-                        );
-                    }
-                }
-            }
-        }
-    }
-
     /// Insert a new session into the cache
     pub fn insert_session(session_id: &str, session: WsSession) {
         WS_SESSIONS.insert(session_id.to_string(), session);
@@ -225,7 +205,8 @@ pub mod sessions_cache_utils {
 }
 
 pub mod search_registry_utils {
-    use tokio::sync::mpsc;
+    use hashbrown::HashMap;
+    use tokio::{sync::mpsc, task::AbortHandle};
 
     use crate::handler::http::request::websocket::session::SEARCH_REGISTRY;
 
@@ -253,6 +234,44 @@ pub mod search_registry_utils {
         }
     }
 
+    /// The search tasks spawned by a websocket session, keyed by `trace_id`.
+    /// Aborting a task drops the search future it is running.
+    #[derive(Debug, Default)]
+    pub struct SessionSearchTasks {
+        tasks: HashMap<String, AbortHandle>,
+    }
+
+    impl SessionSearchTasks {
+        pub fn track(&mut self, trace_id: &str, handle: AbortHandle) {
+            if let Some(prev) = self.tasks.insert(trace_id.to_string(), handle) {
+                prev.abort();
+            }
+        }
+
+        pub fn untrack(&mut self, trace_id: &str) {
+            self.tasks.remove(trace_id);
+        }
+
+        /// Abort all tracked tasks and return their `trace_id`s
+        pub fn abort_all(&mut self) -> Vec<String> {
+            self.tasks
+                .drain()
+                .map(|(trace_id, handle)| {
+                    handle.abort();
+                    trace_id
+                })
+                .collect()
+        }
+
+        pub fn len(&self) -> usize {
+            self.tasks.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.tasks.is_empty()
+        }
+    }
+
     // Add this function to check if a search is cancelled
     pub fn is_cancelled(trace_id: &str) -> Option<bool> {
         SEARCH_REGISTRY
@@ -283,20 +302,14 @@ pub mod search_registry_utils {
 )]
 pub enum WsClientEvents {
     Search(Box<SearchEventReq>),
-    #[cfg(feature = "enterprise")]
-    Cancel {
-        trace_id: String,
-    },
-    Benchmark {
-        id: String,
-    },
+    Cancel { trace_id: String },
+    Benchmark { id: String },
 }
 
 impl WsClientEvents {
     pub fn get_type(&self) -> String {
         match self {
             WsClientEvents::Search(_) => "search",
-            #[cfg(feature = "enterprise")]
             WsClientEvents::Cancel { .. } => "cancel",
             WsClientEvents::Benchmark { .. } => "benchmark",
         }
@@ -306,6 +319,34 @@ impl WsClientEvents {
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).expect("Failed to serialize WsClientEvents")
     }
+
+    /// Parse a client message, a cancel can also be sent flat as
+    /// `{"type":"cancel","trace_id":"..."}`
+    pub fn from_json(msg: &str) -> Result<Self, serde_json::Error> {
+        #[derive(Deserialize)]
+        struct FlatCancel {
+            #[serde(rename = "type")]
+            event_type: String,
+            trace_id: String,
+        }
+
+        serde_json::from_str(msg).or_else(|e| match serde_json::from_str::<FlatCancel>(msg) {
+            Ok(cancel) if cancel.event_type == "cancel" => Ok(WsClientEvents::Cancel {
+                trace_id: cancel.trace_id,
+            }),
+            _ => Err(e),
+        })
+    }
+}
+
+/// Encoding of the binary frames sent to sessions that asked for compression
+pub const WS_COMPRESSION_ENCODING: &str = "gzip";
+
+/// Gzips a server message, sent as a binary frame instead of a text frame
+pub fn compress_message(msg: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(msg.len() / 4), Compression::fast());
+    encoder.write_all(msg.as_bytes())?;
+    encoder.finish()
 }
 
 /// To represent the query start and end time based of partition or cache
@@ -322,13 +363,26 @@ pub struct TimeOffset {
     rename_all(serialize = "snake_case", deserialize = "snake_case")
 )]
 pub enum WsServerEvents {
+    /// First message of a session opened with `compress=true`, messages of at
+    /// least `compression_threshold` bytes then arrive as gzipped binary frames
+    SessionStart {
+        compression: Option<String>,
+        compression_threshold: usize,
+    },
     SearchResponse {
         trace_id: String,
         results: Box<config::meta::search::Response>,
         time_offset: TimeOffset,
         streaming_aggs: bool,
     },
-    #[cfg(feature = "enterprise")]
+    /// Sent after each partition, delta or cached response to clients that opted in
+    Progress {
+        trace_id: String,
+        /// Percentage of the requested time range searched so far
+        percent: u8,
+        scan_size: usize,
+        hits: usize,
+    },
     CancelResponse {
         trace_id: String,
         is_success: bool,
@@ -373,3 +427,94 @@ impl WsServerEvents {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, time::Duration};
+
+    use config::meta::search::Response;
+    use flate2::read::GzDecoder;
+    use tokio::sync::oneshot;
+
+    use super::{
+        compress_message, search_registry_utils::SessionSearchTasks, TimeOffset, WsServerEvents,
+    };
+
+    struct DropSignal(Option<oneshot::Sender<()>>);
+
+    impl Drop for DropSignal {
+        fn drop(&mut self) {
+            if let Some(tx) = self.0.take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abort_all_drops_search_future() {
+        let (tx, rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let _signal = DropSignal(Some(tx));
+            // a search that never finishes on its own
+            std::future::pending::<()>().await;
+        });
+
+        let mut tasks = SessionSearchTasks::default();
+        tasks.track("trace_1", handle.abort_handle());
+        assert_eq!(tasks.len(), 1);
+
+        // simulate the client disconnecting
+        let aborted = tasks.abort_all();
+        assert_eq!(aborted, vec!["trace_1".to_string()]);
+        assert!(tasks.is_empty());
+
+        let dropped = tokio::time::timeout(Duration::from_secs(1), rx).await;
+        assert!(dropped.is_ok_and(|r| r.is_ok()));
+        assert!(handle.await.unwrap_err().is_cancelled());
+    }
+
+    fn search_results(msg: &str) -> Response {
+        match serde_json::from_str::<WsServerEvents>(msg).unwrap() {
+            WsServerEvents::SearchResponse { results, .. } => *results,
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_compressed_message_matches_uncompressed() {
+        let mut results = Response::new(0, 100);
+        for i in 0..500 {
+            results.add_hit(&serde_json::json!({
+                "_timestamp": 1_700_000_000_000_000i64 + i,
+                "log": format!("GET /api/default/_search 200 took {i}ms"),
+                "kubernetes_namespace_name": "ziox",
+            }));
+        }
+        results.set_total(500);
+        let msg = WsServerEvents::SearchResponse {
+            trace_id: "trace_1".to_string(),
+            results: Box::new(results),
+            time_offset: TimeOffset {
+                start_time: 0,
+                end_time: 1_700_000_000_000_000,
+            },
+            streaming_aggs: false,
+        }
+        .to_json();
+
+        let compressed = compress_message(&msg).unwrap();
+        assert!(compressed.len() < msg.len());
+        let mut decompressed = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        let expected = search_results(&msg);
+        let actual = search_results(&decompressed);
+        assert_eq!(actual.total, 500);
+        assert_eq!(
+            serde_json::to_value(&actual).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+    }
+}
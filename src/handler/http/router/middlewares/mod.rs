@@ -14,7 +14,9 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 mod check_keep_alive;
+mod real_ip;
 mod slow_log;
 
 pub use check_keep_alive::check_keep_alive;
+pub use real_ip::{access_log, real_ip};
 pub use slow_log::SlowLog;
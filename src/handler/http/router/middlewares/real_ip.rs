@@ -0,0 +1,44 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Logger,
+    HttpMessage,
+};
+use actix_web_lab::middleware::Next;
+
+use crate::common::utils::http::{get_real_ip, resolve_real_ip, RealIp};
+
+/// Resolves the client IP once per request, so that every consumer reads the
+/// same value through [`get_real_ip`].
+pub async fn real_ip(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if let Some(ip) = resolve_real_ip(req.headers(), req.peer_addr().map(|addr| addr.ip())) {
+        req.extensions_mut().insert(RealIp(ip));
+    }
+    next.call(req).await
+}
+
+/// The access log, with the client IP resolved like [`real_ip`] does.
+pub fn access_log() -> Logger {
+    Logger::new(r#"%{real_ip}xi "%r" %s %b "%{Content-Length}i" "%{Referer}i" "%{User-Agent}i" %T"#)
+        .custom_request_replace("real_ip", |req| {
+            get_real_ip(req.request()).map_or_else(|| "-".to_string(), |ip| ip.to_string())
+        })
+}
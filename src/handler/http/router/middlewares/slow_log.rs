@@ -24,6 +24,8 @@ use actix_web::{
 };
 use futures_util::future::LocalBoxFuture;
 
+use crate::common::utils::http::get_real_ip;
+
 pub struct SlowLog {
     threshold_secs: u64,
     circuit_breaker_enabled: bool,
@@ -79,16 +81,8 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let start = Instant::now();
-        let remote_addr = match req.headers().contains_key("X-Forwarded-For")
-            || req.headers().contains_key("Forwarded")
-        {
-            true => req
-                .connection_info()
-                .realip_remote_addr()
-                .unwrap_or("-")
-                .to_string(),
-            false => req.connection_info().peer_addr().unwrap_or("-").to_string(),
-        };
+        let remote_addr =
+            get_real_ip(req.request()).map_or_else(|| "-".to_string(), |ip| ip.to_string());
         let path = req
             .uri()
             .path_and_query()
@@ -18,14 +18,13 @@ use std::{rc::Rc, str::FromStr};
 use actix_cors::Cors;
 use actix_web::{
     body::MessageBody,
-    dev::{Service, ServiceRequest, ServiceResponse},
+    dev::{ServiceRequest, ServiceResponse},
     http::header,
     middleware, web, HttpRequest, HttpResponse,
 };
 use actix_web_httpauth::middleware::HttpAuthentication;
 use actix_web_lab::middleware::{from_fn, Next};
 use config::get_config;
-use futures::FutureExt;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 #[cfg(feature = "enterprise")]
@@ -200,12 +199,22 @@ pub fn get_basic_routes(svc: &mut web::ServiceConfig) {
     svc.service(status::healthz)
         .service(status::healthz_head)
         .service(status::schedulez);
+    svc.service(
+        web::scope("/healthz/storage")
+            .wrap(HttpAuthentication::with_fn(
+                super::auth::validator::oo_validator,
+            ))
+            .wrap(cors.clone())
+            .service(status::healthz_storage),
+    );
     svc.service(
         web::scope("/auth")
             .wrap(cors.clone())
             .service(users::authentication)
             .service(users::get_presigned_url)
-            .service(users::get_auth),
+            .service(users::get_auth)
+            // accepting an invite is done before the invitee has credentials
+            .service(invites::accept),
     );
 
     svc.service(
@@ -218,7 +227,8 @@ pub fn get_basic_routes(svc: &mut web::ServiceConfig) {
             .service(status::enable_node)
             .service(status::flush_node)
             .service(status::list_node)
-            .service(status::node_metrics),
+            .service(status::node_metrics)
+            .service(status::compact_pending_jobs),
     );
 
     if get_config().common.swagger_enabled {
@@ -237,41 +247,7 @@ pub fn get_basic_routes(svc: &mut web::ServiceConfig) {
     if get_config().common.ui_enabled {
         svc.service(web::redirect("/", "./web/"));
         svc.service(web::redirect("/web", "./web/"));
-        svc.service(
-            web::scope("/web")
-                .wrap_fn(|req, srv| {
-                    let cfg = get_config();
-                    let prefix = format!("{}/web/", cfg.common.base_uri);
-                    let path = req.path().strip_prefix(&prefix).unwrap().to_string();
-
-                    srv.call(req).map(move |res| {
-                        if path.starts_with("src/")
-                            || path.starts_with("assets/")
-                            || path.starts_with("monacoeditorwork/")
-                            || path.eq("favicon.ico")
-                        {
-                            res
-                        } else {
-                            let res = res.unwrap();
-                            let req = res.request().clone();
-                            let body = res.into_body();
-                            let body = body.try_into_bytes().unwrap();
-                            let body = String::from_utf8(body.to_vec()).unwrap();
-                            let body = body.replace(
-                                r#"<base href="/" />"#,
-                                &format!(r#"<base href="{prefix}" />"#),
-                            );
-                            Ok(ServiceResponse::new(
-                                req,
-                                HttpResponse::Ok()
-                                    .content_type(header::ContentType::html())
-                                    .body(body),
-                            ))
-                        }
-                    })
-                })
-                .service(ui::serve),
-        );
+        svc.service(web::scope("/web").service(ui::serve));
     }
 }
 
@@ -282,8 +258,7 @@ pub fn get_config_routes(svc: &mut web::ServiceConfig) {
         web::scope("/config")
             .wrap(cors.clone())
             .service(status::zo_config)
-            .service(status::logout)
-            .service(web::scope("/reload").service(status::config_reload)),
+            .service(status::logout),
     );
 }
 
@@ -298,8 +273,26 @@ pub fn get_config_routes(svc: &mut web::ServiceConfig) {
             .service(status::dex_login)
             .service(status::refresh_token_with_dex)
             .service(status::logout)
-            .service(users::service_accounts::exchange_token)
-            .service(web::scope("/reload").service(status::config_reload)),
+            .service(users::service_accounts::exchange_token),
+    );
+}
+
+/// The login page reads the branding before the user signs in, so only
+/// changing it needs credentials
+pub fn get_branding_routes(svc: &mut web::ServiceConfig) {
+    let cors = get_cors();
+    svc.service(
+        web::scope("/api/_settings")
+            .wrap(cors)
+            .service(branding::get)
+            .service(branding::logo)
+            .service(
+                web::scope("")
+                    .wrap(HttpAuthentication::with_fn(
+                        super::auth::validator::oo_validator,
+                    ))
+                    .service(branding::update),
+            ),
     );
 }
 
@@ -316,6 +309,19 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
     #[cfg(not(feature = "enterprise"))]
     let server = cfg.common.instance_name_short.to_string();
 
+    get_branding_routes(svc);
+
+    // node local caches, answered by the node that receives the request
+    svc.service(
+        web::scope("/api/_node")
+            .wrap(HttpAuthentication::with_fn(
+                super::auth::validator::oo_validator,
+            ))
+            .wrap(cors.clone())
+            .service(status::list_streaming_aggs_cache)
+            .service(status::delete_streaming_aggs_cache),
+    );
+
     let service = web::scope("/api")
         .wrap(from_fn(audit_middleware))
         .wrap(HttpAuthentication::with_fn(
@@ -323,11 +329,21 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         ))
         .wrap(cors.clone())
         .wrap(middleware::DefaultHeaders::new().add(("X-Api-Node", server)))
+        // before the `/{org_id}/...` routes, which would match them too
+        .service(synthetic::start_logs)
+        .service(synthetic::start_metrics)
+        .service(synthetic::start_traces)
+        .service(synthetic::list)
+        .service(synthetic::cancel)
         .service(users::list)
         .service(users::save)
         .service(users::delete)
         .service(users::update)
+        .service(users::bulk_update)
         .service(users::add_user_to_org)
+        .service(invites::create)
+        .service(invites::list)
+        .service(invites::revoke)
         .service(organization::org::organizations)
         .service(organization::settings::get)
         .service(organization::settings::create)
@@ -354,6 +370,7 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(stream::schema)
         .service(stream::settings)
         .service(stream::update_settings)
+        .service(stream::patch_settings)
         .service(stream::delete_fields)
         .service(stream::delete)
         .service(stream::list)
@@ -364,6 +381,7 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(traces::traces_write)
         .service(traces::otlp_traces_write)
         .service(traces::get_latest_traces)
+        .service(traces::get_services)
         .service(metrics::ingest::json)
         .service(metrics::ingest::otlp_metrics_write)
         .service(promql::remote_write)
@@ -381,11 +399,17 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(promql::label_values)
         .service(promql::format_query_get)
         .service(promql::format_query_post)
+        .service(promql::cache_status)
+        .service(promql::cache_delete)
         .service(enrichment_table::save_enrichment_table)
+        .service(enrichment_table::download_enrichment_table)
         .service(search::search)
         .service(search::search_partition)
+        .service(search::search_cache_explain)
+        .service(search::delete_search_cache)
         .service(search::around)
         .service(search::values)
+        .service(search::values_batch)
         .service(search::search_history)
         .service(search::saved_view::create_view)
         .service(search::saved_view::update_view)
@@ -404,6 +428,10 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(dashboards::get_dashboard)
         .service(dashboards::delete_dashboard)
         .service(dashboards::move_dashboard)
+        .service(dashboards::export_dashboard)
+        .service(dashboards::import_dashboards)
+        .service(dashboards::duplicate_dashboard)
+        .service(dashboards::validate_queries)
         .service(dashboards::reports::create_report)
         .service(dashboards::reports::update_report)
         .service(dashboards::reports::get_report)
@@ -411,6 +439,7 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(dashboards::reports::delete_report)
         .service(dashboards::reports::enable_report)
         .service(dashboards::reports::trigger_report)
+        .service(dashboards::reports::get_report_status)
         .service(dashboards::timed_annotations::create_annotations)
         .service(dashboards::timed_annotations::get_annotations)
         .service(dashboards::timed_annotations::delete_annotations)
@@ -421,19 +450,27 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(folders::update_folder)
         .service(folders::get_folder)
         .service(folders::get_folder_by_name)
+        .service(folders::get_folder_contents)
         .service(folders::delete_folder)
         .service(folders::deprecated::create_folder)
         .service(folders::deprecated::list_folders)
         .service(folders::deprecated::update_folder)
         .service(folders::deprecated::get_folder)
         .service(folders::deprecated::get_folder_by_name)
+        .service(folders::deprecated::get_folder_contents)
         .service(folders::deprecated::delete_folder)
+        .service(trash::list_trash)
+        .service(trash::restore_trash_item)
+        .service(retention::preview)
         .service(alerts::create_alert)
         .service(alerts::get_alert)
+        // must be registered before `update_alert` so that `enable` is not parsed as an alert id
+        .service(alerts::enable_alerts)
         .service(alerts::update_alert)
         .service(alerts::delete_alert)
         .service(alerts::list_alerts)
         .service(alerts::enable_alert)
+        .service(alerts::silence_alert)
         .service(alerts::trigger_alert)
         .service(alerts::move_alerts)
         .service(alerts::deprecated::save_alert)
@@ -464,11 +501,13 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(syslog::update_route)
         .service(syslog::toggle_state)
         .service(enrichment_table::save_enrichment_table)
+        .service(enrichment_table::download_enrichment_table)
         .service(metrics::ingest::otlp_metrics_write)
         .service(logs::ingest::otlp_logs_write)
         .service(traces::otlp_traces_write)
         .service(dashboards::move_dashboard)
         .service(traces::get_latest_traces)
+        .service(traces::get_services)
         .service(logs::ingest::multi)
         .service(logs::ingest::json)
         .service(logs::ingest::handle_kinesis_request)
@@ -494,12 +533,19 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(pipeline::list_streams_with_pipeline)
         .service(pipeline::delete_pipeline)
         .service(pipeline::enable_pipeline)
+        .service(pipeline::get_pipeline_stats)
+        .service(pipeline::create_backfill)
+        .service(pipeline::get_backfill)
         .service(search::multi_streams::search_multi)
         .service(search::multi_streams::_search_partition_multi)
         .service(search::multi_streams::around_multi)
         .service(stream::delete_stream_cache)
+        .service(status::reload_config)
         .service(short_url::shorten)
         .service(short_url::retrieve)
+        .service(short_url::list)
+        .service(short_url::delete)
+        .service(short_url::purge)
         .service(service_accounts::list)
         .service(service_accounts::save)
         .service(service_accounts::delete)
@@ -532,6 +578,9 @@ pub fn get_service_routes(svc: &mut web::ServiceConfig) {
         .service(actions::action::delete_action)
         .service(actions::operations::test_action);
 
+    #[cfg(not(feature = "enterprise"))]
+    let service = service.service(search::queue::queue_status);
+
     svc.service(service);
 }
 
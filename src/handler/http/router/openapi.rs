@@ -22,11 +22,13 @@ use crate::{common::meta, handler::http::request};
 #[openapi(
     paths(
         request::status::healthz,
+        request::status::healthz_storage,
         request::users::list,
         request::users::save,
         request::users::update,
         request::users::delete,
         request::users::add_user_to_org,
+        request::users::bulk_update,
         request::organization::org::organizations,
         request::organization::org::org_summary,
         request::organization::org::get_user_passcode,
@@ -40,6 +42,7 @@ use crate::{common::meta, handler::http::request};
         request::stream::schema,
         request::stream::settings,
         request::stream::update_settings,
+        request::stream::patch_settings,
         request::stream::delete_fields,
         request::stream::delete,
         request::logs::ingest::bulk,
@@ -47,6 +50,7 @@ use crate::{common::meta, handler::http::request};
         request::logs::ingest::json,
         request::traces::traces_write,
         request::traces::get_latest_traces,
+        request::traces::get_services,
         request::metrics::ingest::json,
         request::promql::remote_write,
         request::promql::query_get,
@@ -56,14 +60,20 @@ use crate::{common::meta, handler::http::request};
         request::promql::labels_get,
         request::promql::label_values,
         request::promql::format_query_get,
+        request::promql::cache_status,
+        request::promql::cache_delete,
         request::enrichment_table::save_enrichment_table,
+        request::enrichment_table::download_enrichment_table,
         request::rum::ingest::log,
         request::rum::ingest::data,
         request::rum::ingest::sessionreplay,
         request::search::search,
         request::search::search_partition,
+        request::search::search_cache_explain,
+        request::search::delete_search_cache,
         request::search::around,
         request::search::values,
+        request::search::values_batch,
         request::search::search_history,
         request::search::saved_view::create_view,
         request::search::saved_view::delete_view,
@@ -75,13 +85,26 @@ use crate::{common::meta, handler::http::request};
         request::folders::list_folders,
         request::folders::get_folder,
         request::folders::get_folder_by_name,
+        request::folders::get_folder_contents,
         request::folders::update_folder,
         request::folders::deprecated::delete_folder,
         request::folders::deprecated::create_folder,
         request::folders::deprecated::list_folders,
         request::folders::deprecated::get_folder,
         request::folders::deprecated::get_folder_by_name,
+        request::folders::deprecated::get_folder_contents,
         request::folders::deprecated::update_folder,
+        request::trash::list_trash,
+        request::trash::restore_trash_item,
+        request::retention::preview,
+        request::branding::get,
+        request::branding::logo,
+        request::branding::update,
+        request::synthetic::start_logs,
+        request::synthetic::start_metrics,
+        request::synthetic::start_traces,
+        request::synthetic::list,
+        request::synthetic::cancel,
         request::functions::list_functions,
         request::functions::update_function,
         request::functions::save_function,
@@ -94,6 +117,10 @@ use crate::{common::meta, handler::http::request};
         request::dashboards::get_dashboard,
         request::dashboards::delete_dashboard,
         request::dashboards::move_dashboard,
+        request::dashboards::export_dashboard,
+        request::dashboards::import_dashboards,
+        request::dashboards::duplicate_dashboard,
+        request::dashboards::validate_queries,
         request::dashboards::timed_annotations::create_annotations,
         request::dashboards::timed_annotations::get_annotations,
         request::dashboards::timed_annotations::delete_annotations,
@@ -113,6 +140,8 @@ use crate::{common::meta, handler::http::request};
         request::alerts::delete_alert,
         request::alerts::list_alerts,
         request::alerts::enable_alert,
+        request::alerts::enable_alerts,
+        request::alerts::silence_alert,
         request::alerts::trigger_alert,
         request::alerts::move_alerts,
         request::alerts::templates::list_templates,
@@ -136,6 +165,13 @@ use crate::{common::meta, handler::http::request};
         request::clusters::list_clusters,
         request::short_url::shorten,
         request::short_url::retrieve,
+        request::short_url::list,
+        request::short_url::delete,
+        request::short_url::purge,
+        request::invites::create,
+        request::invites::list,
+        request::invites::revoke,
+        request::invites::accept,
     ),
     components(
         schemas(
@@ -150,7 +186,9 @@ use crate::{common::meta, handler::http::request};
             config::meta::stream::StreamPartitionType,
             config::meta::stream::StreamStats,
             config::meta::stream::PartitionTimeLevel,
+            config::meta::stream::StreamSampling,
             config::meta::stream::UpdateStreamSettings,
+            config::meta::stream::PatchStreamSettings,
             config::meta::dashboards::Dashboard,
             config::meta::dashboards::v1::AxisItem,
             config::meta::dashboards::v1::Dashboard,
@@ -167,6 +205,7 @@ use crate::{common::meta, handler::http::request};
             config::meta::alerts::alert::Alert,
             config::meta::alerts::Aggregation,
             config::meta::alerts::AggFunction,
+            config::meta::alerts::BaselineCondition,
             config::meta::alerts::Condition,
             config::meta::alerts::CompareHistoricData,
             config::meta::alerts::FrequencyType,
@@ -185,9 +224,23 @@ use crate::{common::meta, handler::http::request};
             crate::handler::http::models::dashboards::GetDashboardResponseBody,
             crate::handler::http::models::dashboards::UpdateDashboardRequestBody,
             crate::handler::http::models::dashboards::UpdateDashboardResponseBody,
+            crate::handler::http::models::dashboards::UpdateDashboardConflictResponseBody,
             crate::handler::http::models::dashboards::ListDashboardsResponseBody,
             crate::handler::http::models::dashboards::ListDashboardsResponseBodyItem,
             crate::handler::http::models::dashboards::MoveDashboardRequestBody,
+            crate::handler::http::models::dashboards::ExportDashboardResponseBody,
+            crate::handler::http::models::dashboards::ExportedDashboard,
+            crate::handler::http::models::dashboards::ImportDashboardsRequestBody,
+            crate::handler::http::models::dashboards::DuplicateDashboardRequestBody,
+            crate::handler::http::models::dashboards::ImportDashboardsResponseBody,
+            crate::handler::http::models::dashboards::ImportDashboardsResponseBodyItem,
+            crate::handler::http::models::dashboards::ImportDashboardStatus,
+            crate::handler::http::models::dashboards::ValidateDashboardQueriesRequestBody,
+            crate::handler::http::models::dashboards::ValidateDashboardQueriesRequestBodyItem,
+            crate::handler::http::models::dashboards::ValidateDashboardQueriesResponseBody,
+            crate::handler::http::models::dashboards::ValidateDashboardQueriesResponseBodyItem,
+            crate::service::dashboards::validate::QueryIssue,
+            crate::service::dashboards::validate::QueryIssueKind,
             // Destinations
             crate::handler::http::models::destinations::Destination,
             crate::handler::http::models::destinations::DestinationType,
@@ -200,6 +253,13 @@ use crate::{common::meta, handler::http::request};
             crate::handler::http::models::alerts::responses::ListAlertsResponseBody,
             crate::handler::http::models::alerts::responses::ListAlertsResponseBodyItem,
             crate::handler::http::models::alerts::responses::EnableAlertResponseBody,
+            crate::handler::http::models::alerts::requests::EnableAlertsRequestBody,
+            crate::handler::http::models::alerts::responses::EnableAlertsResponseBody,
+            crate::handler::http::models::alerts::responses::EnableAlertsFailure,
+            crate::handler::http::models::alerts::requests::SilenceAlertRequestBody,
+            crate::handler::http::models::alerts::responses::SilenceAlertResponseBody,
+            crate::handler::http::models::alerts::responses::TriggerAlertResponseBody,
+            crate::handler::http::models::alerts::responses::TriggerAlertDestinationResult,
             crate::handler::http::models::alerts::Alert,
             crate::handler::http::models::alerts::TriggerCondition,
             crate::handler::http::models::alerts::CompareHistoricData,
@@ -217,6 +277,36 @@ use crate::{common::meta, handler::http::request};
             crate::handler::http::models::folders::ListFoldersResponseBody,
             crate::handler::http::models::folders::UpdateFolderRequestBody,
             crate::handler::http::models::folders::FolderType,
+            crate::handler::http::models::folders::FolderContentsResponseBody,
+            crate::handler::http::models::folders::FolderContentItems,
+            crate::handler::http::models::folders::FolderContentsErrorResponseBody,
+            // Trash
+            crate::handler::http::models::trash::ListTrashResponseBody,
+            crate::handler::http::models::trash::RestoreTrashItemResponseBody,
+            crate::handler::http::models::trash::TrashItem,
+            crate::handler::http::models::trash::TrashItemType,
+            // Retention
+            crate::service::compact::retention::RetentionPreview,
+            crate::service::compact::retention::RetentionPreviewPage,
+            // Values
+            crate::handler::http::models::values::BatchValuesRequest,
+            crate::handler::http::models::values::BatchValuesField,
+            crate::handler::http::models::values::BatchValuesResponse,
+            crate::handler::http::models::values::FieldValues,
+            crate::handler::http::models::values::FieldValue,
+            // Branding
+            crate::common::meta::branding::BrandingRequest,
+            crate::common::meta::branding::BrandingResponse,
+            // Synthetic
+            config::meta::synthetic::SyntheticLogsRequest,
+            config::meta::synthetic::SyntheticMetricsRequest,
+            config::meta::synthetic::SyntheticTracesRequest,
+            config::meta::synthetic::SyntheticGenerator,
+            config::meta::synthetic::SyntheticSpec,
+            config::meta::synthetic::ListSyntheticGenerators,
+            config::meta::synthetic::LogTemplate,
+            config::meta::synthetic::MetricKind,
+            config::meta::synthetic::Cardinality,
             config::meta::function::Transform,
             config::meta::function::FunctionList,
             config::meta::function::StreamOrder,
@@ -232,14 +322,28 @@ use crate::{common::meta, handler::http::request};
             config::meta::search::SearchEventContext,
             config::meta::search::SearchPartitionRequest,
             config::meta::search::SearchPartitionResponse,
+            config::meta::search::ExplainResponse,
+            config::meta::search::ExplainFilterItem,
             config::meta::search::SearchHistoryRequest,
             config::meta::search::CancelQueryResponse,
             config::meta::search::QueryStatusResponse,
             config::meta::search::QueryStatus,
+            config::meta::search::QueueStatusResponse,
+            config::meta::search::QueuedSearch,
             config::meta::search::QueryInfo,
             config::meta::search::ScanStats,
+            crate::common::meta::search::CacheExplainReport,
+            crate::common::meta::search::CacheHashInputs,
+            crate::common::meta::search::CacheWindowExplain,
+            crate::common::meta::search::CacheDisqualification,
+            crate::common::meta::search::CacheExplainOutcome,
+            crate::common::meta::search::ResultCacheSelectionStrategy,
+            crate::common::meta::search::QueryDelta,
             config::meta::short_url::ShortenUrlRequest,
             config::meta::short_url::ShortenUrlResponse,
+            config::meta::short_url::ListShortUrlsResponse,
+            config::meta::short_url::ShortUrlItem,
+            config::meta::short_url::PurgeShortUrlsResponse,
             meta::ingestion::RecordStatus,
             meta::ingestion::StreamStatus,
             meta::ingestion::IngestionResponse,
@@ -251,11 +355,23 @@ use crate::{common::meta, handler::http::request};
             meta::saved_view::CreateViewResponse,
             meta::saved_view::UpdateViewRequest,
             meta::user::UpdateUser,
+            meta::invite::CreateInviteRequest,
+            meta::invite::CreateInviteResponse,
+            meta::invite::InviteResponse,
+            meta::invite::InviteList,
+            meta::invite::AcceptInviteRequest,
+            meta::invite::AcceptInviteResponse,
             meta::user::UserRequest,
             meta::user::UserRole,
             meta::user::UserOrgRole,
             meta::user::UserList,
             meta::user::UserResponse,
+            meta::user::BulkUserRoleChange,
+            meta::user::BulkUserUpdateRequest,
+            meta::user::BulkUserUpdateResponse,
+            meta::user::BulkUserUpdateItem,
+            meta::user::BulkUserAction,
+            meta::user::BulkUserStatus,
             meta::user::SignInResponse,
             meta::organization::OrgSummary,
             meta::organization::StreamSummary,
@@ -271,6 +387,7 @@ use crate::{common::meta, handler::http::request};
             meta::organization::RumIngestionResponse,
             meta::organization::RumIngestionToken,
             request::status::HealthzResponse,
+            request::status::StorageHealthzResponse,
             meta::ingestion::BulkResponse,
             meta::ingestion::BulkResponseItem,
             meta::ingestion::ShardResponse,
@@ -279,6 +396,8 @@ use crate::{common::meta, handler::http::request};
             meta::syslog::SyslogRoutes,
             config::meta::promql::Metadata,
             config::meta::promql::MetricType,
+            crate::service::promql::search::cache::CacheStatus,
+            crate::service::promql::search::cache::CachedQuery,
             // Functions
 
          ),
@@ -302,6 +421,10 @@ use crate::{common::meta, handler::http::request};
         (name = "Syslog Routes", description = "Syslog Routes retrieval & management operations"),
         (name = "Clusters", description = "Super cluster operations"),
         (name = "Short Url", description = "Short Url Service"),
+        (name = "Trash", description = "Deleted dashboards and alerts retrieval & recovery operations"),
+        (name = "Retention", description = "Data retention operations"),
+        (name = "Synthetic", description = "Synthetic test data generators"),
+        (name = "Branding", description = "Branding of the deployment"),
     ),
     info(
         description = "OpenObserve API documents [https://openobserve.ai/docs/](https://openobserve.ai/docs/)",
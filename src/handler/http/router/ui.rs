@@ -13,33 +13,188 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use actix_web::{route, web};
-use actix_web_rust_embed_responder::{
-    Compress, EmbedResponse, EmbedableFileResponse, IntoResponse,
+use std::io::Write;
+
+use actix_web::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    route, web, HttpRequest, HttpResponse, Responder,
+};
+use actix_web_rust_embed_responder::{Compress, IntoResponse};
+use config::{
+    get_config,
+    utils::hash::{gxhash, Sum64},
 };
-use rust_embed_for_web::RustEmbed;
+use flate2::{write::GzEncoder, Compression};
+use once_cell::sync::Lazy;
+use rust_embed_for_web::{EmbedableFile, RustEmbed};
 
+/// Assets are compressed with gzip and brotli at build time, the best variant
+/// the client accepts is served.
 #[derive(RustEmbed)]
 #[folder = "web/dist/"]
-#[gzip = false]
-#[br = false]
+#[gzip = true]
+#[br = true]
 struct WebAssets;
 
+/// `Cache-Control` of the files whose name contains a hash of their content.
+const CACHE_CONTROL_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// `Cache-Control` of the files that keep their name across builds, they are
+/// revalidated with their ETag.
+const CACHE_CONTROL_REVALIDATE: &str = "no-cache";
+
+/// index.html with its `<base>` pointing at `ZO_BASE_URI`, rendered once.
+struct Index {
+    body: Vec<u8>,
+    gzip: Option<Vec<u8>>,
+    etag: String,
+}
+
+static INDEX: Lazy<Option<Index>> = Lazy::new(|| {
+    let file = WebAssets::get("index.html")?;
+    let data = file.data();
+    let prefix = format!("{}/web/", get_config().common.base_uri);
+    let body = String::from_utf8_lossy(&data).replace(
+        r#"<base href="/" />"#,
+        &format!(r#"<base href="{prefix}" />"#),
+    );
+    let etag = format!("\"{:x}\"", gxhash::new().sum64(&body));
+    let body = body.into_bytes();
+    let gzip = gzip(&body).filter(|gzip| gzip.len() < body.len());
+    Some(Index { body, gzip, etag })
+});
+
 #[route("/{path:.*}", method = "GET", method = "HEAD")]
-pub async fn serve(path: web::Path<String>) -> EmbedResponse<EmbedableFileResponse> {
-    let mut path = path.as_str();
-
-    if !path.starts_with("src/")
-        && !path.starts_with("assets/")
-        && !path.starts_with("monacoeditorwork/")
-        && !path.eq("favicon.ico")
-    {
-        path = "index.html";
+pub async fn serve(req: HttpRequest, path: web::Path<String>) -> HttpResponse {
+    let path = path.as_str();
+    if !is_static_asset(path) {
+        return serve_index(&req);
     }
 
-    WebAssets::get(path)
+    // the responder negotiates the encoding and answers conditional requests
+    let mut resp = WebAssets::get(path)
         .into_response()
-        .use_compression(Compress::Never)
+        .use_compression(Compress::IfPrecompressed)
+        .respond_to(&req)
+        .map_into_boxed_body();
+    if resp.status().is_success() || resp.status() == StatusCode::NOT_MODIFIED {
+        let cache_control = if is_hashed_asset(path) {
+            CACHE_CONTROL_IMMUTABLE
+        } else {
+            CACHE_CONTROL_REVALIDATE
+        };
+        set_cache_headers(resp.headers_mut(), cache_control);
+    }
+    resp
+}
+
+fn serve_index(req: &HttpRequest) -> HttpResponse {
+    let Some(index) = INDEX.as_ref() else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let mut resp = if if_none_match(req.headers(), &index.etag) {
+        HttpResponse::NotModified().finish()
+    } else {
+        let accept_encoding = req
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        match &index.gzip {
+            Some(gzip) if accepts_encoding(accept_encoding, "gzip") => HttpResponse::Ok()
+                .content_type(header::ContentType::html())
+                .insert_header((header::CONTENT_ENCODING, "gzip"))
+                .body(gzip.clone()),
+            _ => HttpResponse::Ok()
+                .content_type(header::ContentType::html())
+                .body(index.body.clone()),
+        }
+    };
+    let headers = resp.headers_mut();
+    headers.insert(header::ETAG, HeaderValue::from_str(&index.etag).unwrap());
+    set_cache_headers(headers, CACHE_CONTROL_REVALIDATE);
+    resp
+}
+
+/// Returns true for the paths of files in the UI build, every other path is
+/// a route of the single page app and gets index.html.
+fn is_static_asset(path: &str) -> bool {
+    path.starts_with("src/")
+        || path.starts_with("assets/")
+        || path.starts_with("monacoeditorwork/")
+        || path.eq("favicon.ico")
+}
+
+/// Returns true if the file name ends with the content hash the frontend build
+/// appends, eg: `assets/index.B3kx_9-a.js` or `assets/logo-Cz3b1e8Q.svg`.
+fn is_hashed_asset(path: &str) -> bool {
+    const HASH_LEN: usize = 8;
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let Some((stem, _ext)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let stem = stem.as_bytes();
+    let Some(split) = stem.len().checked_sub(HASH_LEN + 1) else {
+        return false;
+    };
+    let hash = &stem[split + 1..];
+    split > 0
+        && matches!(stem[split], b'.' | b'-')
+        && hash
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-')
+        && hash
+            .iter()
+            .any(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+}
+
+fn set_cache_headers(headers: &mut HeaderMap, cache_control: &'static str) {
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    // the body depends on Accept-Encoding even when it isn't compressed
+    headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+}
+
+/// Returns true if `encoding` is listed in the `Accept-Encoding` header value
+/// with a non zero quality, either by name or by `*`.
+fn accepts_encoding(accept_encoding: &str, encoding: &str) -> bool {
+    let mut wildcard = false;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or_default().trim();
+        let quality = parts
+            .find_map(|p| p.trim().strip_prefix("q="))
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if coding.eq_ignore_ascii_case(encoding) {
+            return quality > 0.0;
+        }
+        if coding == "*" {
+            wildcard = quality > 0.0;
+        }
+    }
+    wildcard
+}
+
+/// Returns true if the `If-None-Match` header matches `etag`.
+pub(crate) fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim())
+        .any(|v| v == "*" || v.strip_prefix("W/").unwrap_or(v) == etag)
+}
+
+fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
 }
 
 #[cfg(test)]
@@ -65,4 +220,87 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert!(!resp.status().is_client_error());
     }
+
+    #[tokio::test]
+    async fn test_index_encoding_and_cache_headers() {
+        let app = test::init_service(App::new().service(serve)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/logs")
+            .insert_header((header::ACCEPT_ENCODING, "gzip, deflate, br"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let headers = resp.headers();
+        assert_eq!(headers.get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(headers.get(header::VARY).unwrap(), "Accept-Encoding");
+        assert_eq!(headers.get(header::CACHE_CONTROL).unwrap(), "no-cache");
+        let etag = headers.get(header::ETAG).unwrap().clone();
+
+        // not accepted, served as it is with the same validator
+        let req = test::TestRequest::get()
+            .uri("/logs")
+            .insert_header((header::ACCEPT_ENCODING, "gzip;q=0, identity"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), &etag);
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains(r#"<base href=""#));
+
+        let req = test::TestRequest::get()
+            .uri("/logs")
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), &etag);
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+
+        let req = test::TestRequest::get()
+            .uri("/logs")
+            .insert_header((header::IF_NONE_MATCH, r#""stale""#))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_accepts_encoding() {
+        assert!(accepts_encoding("gzip, deflate, br", "gzip"));
+        assert!(accepts_encoding("br;q=1.0, GZIP;q=0.5", "gzip"));
+        assert!(accepts_encoding("*", "gzip"));
+        assert!(!accepts_encoding("gzip;q=0", "gzip"));
+        assert!(!accepts_encoding("*, gzip;q=0", "gzip"));
+        assert!(!accepts_encoding("br", "gzip"));
+        assert!(!accepts_encoding("", "gzip"));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let mut headers = HeaderMap::new();
+        assert!(!if_none_match(&headers, r#""abc""#));
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static(r#""xyz", W/"abc""#),
+        );
+        assert!(if_none_match(&headers, r#""abc""#));
+        assert!(!if_none_match(&headers, r#""def""#));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(if_none_match(&headers, r#""def""#));
+    }
+
+    #[test]
+    fn test_is_hashed_asset() {
+        assert!(is_hashed_asset("assets/index.B3kx_9-a.js"));
+        assert!(is_hashed_asset("assets/vendor/o2cs-date-fns.Dq2c5N1x.js"));
+        assert!(is_hashed_asset("assets/logo-Cz3b1e8Q.svg"));
+        assert!(!is_hashed_asset("assets/editor.api.v1.js"));
+        assert!(!is_hashed_asset("favicon.ico"));
+        assert!(!is_hashed_asset("monacoeditorwork/editor.worker.bundle.js"));
+        assert!(!is_hashed_asset("src/assets/images/common/logo.svg"));
+        assert!(!is_hashed_asset("assets/.Cz3b1e8Q"));
+    }
 }
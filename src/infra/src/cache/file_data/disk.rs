@@ -87,6 +87,7 @@ pub static LOADING_FROM_DISK_NUM: Lazy<AtomicUsize> = Lazy::new(|| AtomicUsize::
 pub static LOADING_FROM_DISK_DONE: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 
 pub struct FileData {
+    file_type: FileType,
     max_size: usize,
     cur_size: usize,
     root_dir: String,
@@ -94,7 +95,7 @@ pub struct FileData {
     data: CacheStrategy,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     DATA,
     RESULT,
@@ -109,17 +110,28 @@ impl Default for FileData {
 impl FileData {
     fn new(file_type: FileType) -> FileData {
         let cfg = get_config();
-        let size = match file_type {
-            FileType::DATA => cfg.disk_cache.max_size,
-            FileType::RESULT => cfg.disk_cache.result_max_size,
-        };
-        FileData::with_capacity_and_cache_strategy(size, &cfg.disk_cache.cache_strategy)
+        match file_type {
+            FileType::DATA => FileData::with_capacity_and_cache_strategy(
+                cfg.disk_cache.max_size,
+                &cfg.disk_cache.cache_strategy,
+            ),
+            FileType::RESULT => FileData::results_with_capacity(cfg.disk_cache.result_max_size),
+        }
+    }
+
+    /// Cached query results have their own budget, evicted least recently
+    /// served first, so caching data files does not push them out.
+    fn results_with_capacity(max_size: usize) -> FileData {
+        let mut files = FileData::with_capacity_and_cache_strategy(max_size, "lru");
+        files.file_type = FileType::RESULT;
+        files
     }
 
     fn with_capacity_and_cache_strategy(max_size: usize, strategy: &str) -> FileData {
         let cfg = get_config();
 
         FileData {
+            file_type: FileType::DATA,
             max_size,
             cur_size: 0,
             root_dir: format!(
@@ -169,10 +181,14 @@ impl FileData {
                 data_size
             );
             // cache is full, need release some space
-            let need_release_size = min(
-                self.max_size,
-                max(get_config().disk_cache.release_size, data_size * 100),
-            );
+            let need_release_size = match self.file_type {
+                FileType::DATA => min(
+                    self.max_size,
+                    max(get_config().disk_cache.release_size, data_size * 100),
+                ),
+                // only make room for the new result, the others may still be served
+                FileType::RESULT => self.cur_size + data_size - self.max_size + 1,
+            };
             self.gc(trace_id, need_release_size).await?;
         }
 
@@ -213,6 +229,7 @@ impl FileData {
         );
         let mut release_size = 0;
         let mut remove_result_files = vec![];
+        let mut remove_metrics_files = vec![];
         loop {
             let item = self.data.remove();
             if item.is_none() {
@@ -251,13 +268,10 @@ impl FileData {
                 }
             }
 
-            if key.starts_with("results/") {
-                let columns = key.split('/').collect::<Vec<&str>>();
-                let query_key = format!(
-                    "{}_{}_{}_{}",
-                    columns[1], columns[2], columns[3], columns[4]
-                );
-                remove_result_files.push(query_key);
+            if let Some(result) = parse_result_file(&key) {
+                remove_result_files.push(result);
+            } else if key.starts_with("metrics_results/") {
+                remove_metrics_files.push(key.clone());
             }
             // metrics
             let columns = key.split('/').collect::<Vec<&str>>();
@@ -284,12 +298,24 @@ impl FileData {
         }
         self.cur_size -= release_size;
 
+        // drop only the evicted ranges, the other ranges of a query are still cached
         if !remove_result_files.is_empty() {
-            let mut r = QUERY_RESULT_CACHE.write().await;
-            for query_key in remove_result_files {
-                r.remove(&query_key);
+            let mut w = QUERY_RESULT_CACHE.write().await;
+            for (query_key, meta) in remove_result_files {
+                let Some(metas) = w.get_mut(&query_key) else {
+                    continue;
+                };
+                metas.retain(|m| *m != meta);
+                if metas.is_empty() {
+                    w.remove(&query_key);
+                }
             }
-            drop(r);
+            drop(w);
+        }
+        if !remove_metrics_files.is_empty() {
+            let mut w = METRICS_RESULT_CACHE.write().await;
+            w.retain(|key| !remove_metrics_files.contains(key));
+            drop(w);
         }
         log::info!(
             "[trace_id {trace_id}] File disk cache gc done, released {} bytes",
@@ -420,12 +446,15 @@ pub async fn get(file: &str, range: Option<Range<usize>>) -> Option<Bytes> {
         return None;
     }
     let idx = get_bucket_idx(file);
-    let files = if file.starts_with("files") {
-        FILES_READER.get(idx).unwrap()
-    } else {
-        RESULT_FILES_READER.get(idx).unwrap()
-    };
-    files.get(file, range).await
+    if file.starts_with("files") {
+        return FILES_READER.get(idx).unwrap().get(file, range).await;
+    }
+    let data = RESULT_FILES_READER.get(idx).unwrap().get(file, range).await;
+    if data.is_some() {
+        // results are evicted by when they were last served
+        RESULT_FILES[idx].write().await.data.touch(file);
+    }
+    data
 }
 
 #[inline]
@@ -560,6 +589,10 @@ async fn load(root_dir: &PathBuf, scan_dir: &PathBuf) -> Result<(), anyhow::Erro
                             .with_label_values(&[columns[1], columns[2]])
                             .add(data_size as i64);
                     } else if file_key.starts_with("results") {
+                        let Some((query_key, meta)) = parse_result_file(&file_key) else {
+                            log::warn!("skip invalid result cache file: {}", file_key);
+                            continue;
+                        };
                         let mut w = RESULT_FILES[idx].write().await;
                         w.cur_size += data_size;
                         w.data.insert(file_key.clone(), data_size);
@@ -571,23 +604,15 @@ async fn load(root_dir: &PathBuf, scan_dir: &PathBuf) -> Result<(), anyhow::Erro
                             .with_label_values(&[columns[1], columns[2]])
                             .add(data_size as i64);
 
-                        let columns = file_key.split('/').collect::<Vec<&str>>();
-                        let query_key = format!(
-                            "{}_{}_{}_{}",
-                            columns[1], columns[2], columns[3], columns[4]
-                        );
-                        let meta = columns[5].split('_').collect::<Vec<&str>>();
-                        let is_aggregate = meta[2] == "1";
-                        let is_descending = meta[3] == "1";
-                        result_cache.entry(query_key).or_insert_with(Vec::new).push(
-                            ResultCacheMeta {
-                                start_time: meta[0].parse().unwrap(),
-                                end_time: meta[1].parse().unwrap(),
-                                is_aggregate,
-                                is_descending,
-                            },
-                        );
+                        result_cache
+                            .entry(query_key)
+                            .or_insert_with(Vec::new)
+                            .push(meta);
                     } else if file_key.starts_with("metrics_results") {
+                        let mut w = RESULT_FILES[idx].write().await;
+                        w.cur_size += data_size;
+                        w.data.insert(file_key.clone(), data_size);
+                        drop(w);
                         // metrics
                         metrics::QUERY_DISK_METRICS_CACHE_USED_BYTES
                             .with_label_values(&[])
@@ -623,17 +648,7 @@ async fn gc() -> Result<(), anyhow::Error> {
         w.gc("global", cfg.disk_cache.gc_size).await?;
         drop(w);
     }
-    for file in RESULT_FILES.iter() {
-        let r = file.read().await;
-        if r.cur_size + cfg.disk_cache.release_size < r.max_size {
-            drop(r);
-            continue;
-        }
-        drop(r);
-        let mut w = file.write().await;
-        w.gc("global", cfg.disk_cache.gc_size).await?;
-        drop(w);
-    }
+    // result files are evicted when they are written, see `FileData::set`
     Ok(())
 }
 
@@ -704,6 +719,30 @@ pub async fn download(trace_id: &str, file: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Parses the key of a cached search result,
+/// `results/{org}/{stream_type}/{stream}/{hash}/{start}_{end}_{agg}_{desc}.json`,
+/// into its key in [`QUERY_RESULT_CACHE`] and its meta.
+fn parse_result_file(file: &str) -> Option<(String, ResultCacheMeta)> {
+    let columns = file.split('/').collect::<Vec<&str>>();
+    if columns.len() != 6 || columns[0] != "results" {
+        return None;
+    }
+    let name = columns[5].strip_suffix(".json").unwrap_or(columns[5]);
+    let meta = name.split('_').collect::<Vec<&str>>();
+    if meta.len() != 4 {
+        return None;
+    }
+    Some((
+        columns[1..5].join("_"),
+        ResultCacheMeta {
+            start_time: meta[0].parse().ok()?,
+            end_time: meta[1].parse().ok()?,
+            is_aggregate: meta[2] == "1",
+            is_descending: meta[3] == "1",
+        },
+    ))
+}
+
 fn get_bucket_idx(file: &str) -> usize {
     let cfg = get_config();
     if cfg.disk_cache.bucket_num <= 1 {
@@ -868,4 +907,70 @@ mod tests {
 
         assert_eq!(file_data.get(&file_key, None).await, Some(content))
     }
+
+    #[test]
+    fn test_parse_result_file() {
+        let (query_key, meta) =
+            parse_result_file("results/default/logs/k8s/1234/100_200_1_1.json").unwrap();
+        assert_eq!(query_key, "default_logs_k8s_1234");
+        assert_eq!(
+            meta,
+            ResultCacheMeta {
+                start_time: 100,
+                end_time: 200,
+                is_aggregate: true,
+                is_descending: true,
+            }
+        );
+        assert!(parse_result_file("files/default/logs/k8s/1234/100_200_1_1.json").is_none());
+        assert!(parse_result_file("results/default/logs/k8s/1234/100_200.json").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_cache_evicts_least_recently_served() {
+        let trace_id = "session_789";
+        let mut file_data = FileData::results_with_capacity(40);
+        let content = Bytes::from("0123456789");
+        let query_key = "evict_logs_default_1234";
+        let keys = (0..4)
+            .map(|i| {
+                format!(
+                    "results/evict/logs/default/1234/{}_{}_0_1.json",
+                    i * 10,
+                    i * 10 + 10
+                )
+            })
+            .collect::<Vec<_>>();
+        QUERY_RESULT_CACHE.write().await.insert(
+            query_key.to_string(),
+            keys.iter()
+                .map(|key| parse_result_file(key).unwrap().1)
+                .collect(),
+        );
+        for key in keys.iter().take(3) {
+            file_data.set(trace_id, key, content.clone()).await.unwrap();
+        }
+        // the first range was served since, so the second is the least recently served
+        file_data.data.touch(&keys[0]);
+
+        // exceeding the budget evicts only as much as the new result needs
+        file_data
+            .set(trace_id, &keys[3], content.clone())
+            .await
+            .unwrap();
+        assert!(file_data.exist(&keys[0]).await);
+        assert!(!file_data.exist(&keys[1]).await);
+        assert!(file_data.exist(&keys[2]).await);
+        assert!(file_data.exist(&keys[3]).await);
+        assert_eq!(file_data.size(), (40, 30));
+
+        let metas = QUERY_RESULT_CACHE
+            .read()
+            .await
+            .get(query_key)
+            .cloned()
+            .unwrap();
+        let starts = metas.iter().map(|m| m.start_time).collect::<Vec<_>>();
+        assert_eq!(starts, vec![0, 20, 30]);
+    }
 }
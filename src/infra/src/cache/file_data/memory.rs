@@ -15,13 +15,19 @@
 
 use std::{
     cmp::{max, min},
+    collections::HashMap,
     ops::Range,
 };
 
 use bytes::Bytes;
 use config::{
-    get_config, metrics,
-    utils::hash::{gxhash, Sum64},
+    get_config,
+    meta::stream::StreamType,
+    metrics,
+    utils::{
+        hash::{gxhash, Sum64},
+        time::now_micros,
+    },
     RwHashMap,
 };
 use once_cell::sync::Lazy;
@@ -51,6 +57,19 @@ pub struct FileData {
     max_size: usize,
     cur_size: usize,
     data: CacheStrategy,
+    /// files of the streams with `cache_latest_files` enabled, only evicted
+    /// once `data` is empty
+    pinned: CacheStrategy,
+    /// file -> micros after which the file is released by gc
+    expires_at: HashMap<String, i64>,
+}
+
+/// How a file is kept in memory cache, resolved from its stream settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub pinned: bool,
+    /// seconds
+    pub ttl: Option<i64>,
 }
 
 impl Default for FileData {
@@ -73,11 +92,13 @@ impl FileData {
             max_size,
             cur_size: 0,
             data: CacheStrategy::new(strategy),
+            pinned: CacheStrategy::new(strategy),
+            expires_at: HashMap::new(),
         }
     }
 
     async fn exist(&self, file: &str) -> bool {
-        self.data.contains_key(file)
+        self.data.contains_key(file) || self.pinned.contains_key(file)
     }
 
     async fn get(&self, file: &str, range: Option<Range<usize>>) -> Option<Bytes> {
@@ -97,6 +118,17 @@ impl FileData {
     }
 
     async fn set(&mut self, trace_id: &str, file: &str, data: Bytes) -> Result<(), anyhow::Error> {
+        self.set_with_policy(trace_id, file, data, CachePolicy::default())
+            .await
+    }
+
+    async fn set_with_policy(
+        &mut self,
+        trace_id: &str,
+        file: &str,
+        data: Bytes,
+        policy: CachePolicy,
+    ) -> Result<(), anyhow::Error> {
         let data_size = file.len() + data.len();
         if self.cur_size + data_size >= self.max_size {
             log::info!(
@@ -112,7 +144,15 @@ impl FileData {
         }
 
         self.cur_size += data_size;
-        self.data.insert(file.to_string(), data_size);
+        if policy.pinned {
+            self.pinned.insert(file.to_string(), data_size);
+        } else {
+            self.data.insert(file.to_string(), data_size);
+        }
+        if let Some(ttl) = policy.ttl.filter(|ttl| *ttl > 0) {
+            self.expires_at
+                .insert(file.to_string(), now_micros() + ttl * 1_000_000);
+        }
         // write file into cache
         let idx = get_bucket_idx(file);
         DATA[idx].insert(file.to_string(), data);
@@ -136,54 +176,79 @@ impl FileData {
             self.max_size,
             need_release_size
         );
+        // the expired files are released first, they are already off cur_size
+        let expired_size = self.release_expired(trace_id, now_micros()).await;
         let mut release_size = 0;
-        loop {
-            let item = self.data.remove();
-            if item.is_none() {
+        while expired_size + release_size < need_release_size {
+            // files of pinned streams are only evicted when nothing else is left
+            let item = match self.data.remove() {
+                Some(item) => Some(item),
+                None => self.pinned.remove(),
+            };
+            let Some((key, data_size)) = item else {
                 log::warn!(
                     "[trace_id {trace_id}] File memory cache is corrupt, it shouldn't be none"
                 );
                 break;
-            }
-            let (key, data_size) = item.unwrap();
-            // move the file from memory to disk cache
-            let idx = get_bucket_idx(&key);
-            if let Some((key, data)) = DATA[idx].remove(&key) {
-                _ = super::disk::set(trace_id, &key, data).await;
-            }
-            // metrics
-            let columns = key.split('/').collect::<Vec<&str>>();
-            if columns[0] == "files" {
-                metrics::QUERY_MEMORY_CACHE_FILES
-                    .with_label_values(&[columns[1], columns[2]])
-                    .dec();
-                metrics::QUERY_MEMORY_CACHE_USED_BYTES
-                    .with_label_values(&[columns[1], columns[2]])
-                    .sub(data_size as i64);
-            }
+            };
+            self.expires_at.remove(&key);
+            evict(trace_id, &key, data_size).await;
             release_size += data_size;
-            if release_size >= need_release_size {
-                break;
-            }
         }
         self.cur_size -= release_size;
         let _ = DATA.iter().map(|c| c.shrink_to_fit()).collect::<Vec<_>>();
         log::info!(
             "[trace_id {trace_id}] File memory cache gc done, released {} bytes",
-            release_size
+            expired_size + release_size
         );
         Ok(())
     }
 
+    /// Moves the files whose ttl passed to disk cache, returns the released
+    /// bytes
+    async fn release_expired(&mut self, trace_id: &str, now: i64) -> usize {
+        let expired = self
+            .expires_at
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        let mut release_size = 0;
+        for key in expired {
+            self.expires_at.remove(&key);
+            let item = match self.data.remove_key(&key) {
+                Some(item) => Some(item),
+                None => self.pinned.remove_key(&key),
+            };
+            if let Some((key, data_size)) = item {
+                evict(trace_id, &key, data_size).await;
+                release_size += data_size;
+            }
+        }
+        if release_size > 0 {
+            self.cur_size -= release_size;
+            log::info!(
+                "[trace_id {trace_id}] File memory cache released {} bytes of expired files",
+                release_size
+            );
+        }
+        release_size
+    }
+
     async fn remove(&mut self, trace_id: &str, file: &str) -> Result<(), anyhow::Error> {
         log::debug!(
             "[trace_id {trace_id}] File memory cache remove file {}",
             file
         );
 
-        let Some((key, data_size)) = self.data.remove_key(file) else {
+        let item = match self.data.remove_key(file) {
+            Some(item) => Some(item),
+            None => self.pinned.remove_key(file),
+        };
+        let Some((key, data_size)) = item else {
             return Ok(());
         };
+        self.expires_at.remove(&key);
         self.cur_size -= data_size;
         log::debug!(
             "[trace_id {trace_id}] File memory cache remove file done, released {} bytes",
@@ -213,11 +278,29 @@ impl FileData {
     }
 
     fn len(&self) -> usize {
-        self.data.len()
+        self.data.len() + self.pinned.len()
     }
 
     fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.data.is_empty() && self.pinned.is_empty()
+    }
+}
+
+/// Moves an evicted file from memory to disk cache
+async fn evict(trace_id: &str, key: &str, data_size: usize) {
+    let idx = get_bucket_idx(key);
+    if let Some((key, data)) = DATA[idx].remove(key) {
+        _ = super::disk::set(trace_id, &key, data).await;
+    }
+    // metrics
+    let columns = key.split('/').collect::<Vec<&str>>();
+    if columns[0] == "files" {
+        metrics::QUERY_MEMORY_CACHE_FILES
+            .with_label_values(&[columns[1], columns[2]])
+            .dec();
+        metrics::QUERY_MEMORY_CACHE_USED_BYTES
+            .with_label_values(&[columns[1], columns[2]])
+            .sub(data_size as i64);
     }
 }
 
@@ -281,12 +364,13 @@ pub async fn set(trace_id: &str, file: &str, data: Bytes) -> Result<(), anyhow::
     if !get_config().memory_cache.enabled {
         return Ok(());
     }
+    let policy = cache_policy(file).await;
     let idx = get_bucket_idx(file);
     let mut files = FILES[idx].write().await;
     if files.exist(file).await {
         return Ok(());
     }
-    files.set(trace_id, file, data).await
+    files.set_with_policy(trace_id, file, data, policy).await
 }
 
 #[inline]
@@ -305,10 +389,15 @@ async fn gc() -> Result<(), anyhow::Error> {
         return Ok(());
     }
 
+    let now = now_micros();
     for file in FILES.iter() {
         let r = file.read().await;
+        let has_expired = r.expires_at.values().any(|expires_at| *expires_at <= now);
         if r.cur_size + cfg.memory_cache.release_size < r.max_size {
             drop(r);
+            if has_expired {
+                file.write().await.release_expired("global", now).await;
+            }
             continue;
         }
         drop(r);
@@ -369,6 +458,56 @@ pub async fn download(trace_id: &str, file: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Returns whether the newly written files of the stream should be pushed into
+/// memory cache, the stream settings override
+/// `ZO_MEMORY_CACHE_CACHE_LATEST_FILES`
+pub async fn cache_latest_files(org_id: &str, stream_type: StreamType, stream_name: &str) -> bool {
+    let key = format!("{org_id}/{stream_type}/{stream_name}");
+    stream_cache_latest_files(&key).await
+}
+
+/// Same as [cache_latest_files] for a file key like
+/// `files/{org}/{stream_type}/{stream}/...`
+pub async fn cache_latest_files_by_key(file: &str) -> bool {
+    match stream_key(file) {
+        Some(key) => stream_cache_latest_files(&key).await,
+        None => get_config().memory_cache.cache_latest_files,
+    }
+}
+
+async fn stream_cache_latest_files(key: &str) -> bool {
+    crate::schema::STREAM_SETTINGS
+        .read()
+        .await
+        .get(key)
+        .and_then(|settings| settings.cache_latest_files)
+        .unwrap_or(get_config().memory_cache.cache_latest_files)
+}
+
+async fn cache_policy(file: &str) -> CachePolicy {
+    let Some(key) = stream_key(file) else {
+        return CachePolicy::default();
+    };
+    let r = crate::schema::STREAM_SETTINGS.read().await;
+    let Some(settings) = r.get(&key) else {
+        return CachePolicy::default();
+    };
+    CachePolicy {
+        pinned: settings.cache_latest_files.unwrap_or_default(),
+        ttl: settings.memory_cache_ttl,
+    }
+}
+
+/// Resolves `files/{org}/{stream_type}/{stream}/...` to the stream settings
+/// key `{org}/{stream_type}/{stream}`
+fn stream_key(file: &str) -> Option<String> {
+    let columns = file.splitn(5, '/').collect::<Vec<&str>>();
+    if columns.len() < 5 || columns[0] != "files" {
+        return None;
+    }
+    Some(format!("{}/{}/{}", columns[1], columns[2], columns[3]))
+}
+
 fn get_bucket_idx(file: &str) -> usize {
     let cfg = get_config();
     if cfg.memory_cache.bucket_num <= 1 {
@@ -504,4 +643,88 @@ mod tests {
         // get first key, should get error
         assert!(file_data.get(file_key1, None).await.is_none());
     }
+
+    #[tokio::test]
+    async fn test_gc_evicts_pinned_files_last() {
+        let trace_id = "session_789";
+        let mut file_data = FileData::with_capacity_and_cache_strategy(1024 * 1024, "lru");
+        let content = Bytes::from("Some text");
+        let pinned = CachePolicy {
+            pinned: true,
+            ttl: None,
+        };
+        let file_key = |i: usize| {
+            format!("files/default/logs/olympics/2022/10/03/10/6982652937134804993_7_{i}.parquet")
+        };
+        // even keys are pinned, odd keys are not
+        for i in 0..6 {
+            let policy = if i % 2 == 0 {
+                pinned
+            } else {
+                CachePolicy::default()
+            };
+            file_data
+                .set_with_policy(trace_id, &file_key(i), content.clone(), policy)
+                .await
+                .unwrap();
+        }
+        let item_size = file_key(0).len() + content.len();
+
+        // releasing two items only evicts unpinned files
+        file_data.gc(trace_id, item_size * 2).await.unwrap();
+        assert!(!file_data.exist(&file_key(1)).await);
+        assert!(!file_data.exist(&file_key(3)).await);
+        assert!(file_data.exist(&file_key(5)).await);
+        for i in [0, 2, 4] {
+            assert!(file_data.exist(&file_key(i)).await);
+        }
+
+        // pinned files go once no unpinned file is left, in cache order
+        file_data.gc(trace_id, item_size * 2).await.unwrap();
+        assert!(!file_data.exist(&file_key(5)).await);
+        assert!(!file_data.exist(&file_key(0)).await);
+        assert!(file_data.exist(&file_key(2)).await);
+        assert!(file_data.exist(&file_key(4)).await);
+        assert_eq!(file_data.len(), 2);
+        assert_eq!(file_data.size().1, item_size * 2);
+    }
+
+    #[tokio::test]
+    async fn test_release_expired_files() {
+        let trace_id = "session_789";
+        let mut file_data = FileData::with_capacity_and_cache_strategy(1024 * 1024, "fifo");
+        let content = Bytes::from("Some text");
+        let file_key1 = "files/default/logs/olympics/2022/10/03/10/6982652937134804993_8_1.parquet";
+        let file_key2 = "files/default/logs/olympics/2022/10/03/10/6982652937134804993_8_2.parquet";
+        let policy = CachePolicy {
+            pinned: true,
+            ttl: Some(60),
+        };
+        file_data
+            .set_with_policy(trace_id, file_key1, content.clone(), policy)
+            .await
+            .unwrap();
+        file_data
+            .set(trace_id, file_key2, content.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(file_data.release_expired(trace_id, now_micros()).await, 0);
+        let released = file_data
+            .release_expired(trace_id, now_micros() + 61 * 1_000_000)
+            .await;
+        assert_eq!(released, file_key1.len() + content.len());
+        assert!(!file_data.exist(file_key1).await);
+        assert!(file_data.exist(file_key2).await);
+    }
+
+    #[test]
+    fn test_stream_key() {
+        assert_eq!(
+            stream_key("files/default/logs/olympics/2022/10/03/10/7_1.parquet").as_deref(),
+            Some("default/logs/olympics")
+        );
+        assert_eq!(stream_key("files/default/logs/olympics"), None);
+        assert_eq!(stream_key("results/default/logs/olympics/a.json"), None);
+    }
 }
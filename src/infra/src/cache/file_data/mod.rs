@@ -73,6 +73,13 @@ impl CacheStrategy {
         }
     }
 
+    /// Marks the key as recently used, which only matters for LRU.
+    fn touch(&mut self, key: &str) {
+        if let CacheStrategy::Lru(cache) = self {
+            cache.get(key);
+        }
+    }
+
     fn contains_key(&self, key: &str) -> bool {
         match self {
             CacheStrategy::Lru(cache) => cache.contains_key(key),
@@ -186,6 +186,8 @@ pub enum ErrorCodes {
     SearchCancelQuery(String),
     SearchTimeout(String),
     InvalidParams(String),
+    SearchTooManyRequests(String),
+    SearchFunctionNotAllowed(String),
 }
 
 impl From<sea_orm::DbErr> for Error {
@@ -244,6 +246,8 @@ impl ErrorCodes {
             ErrorCodes::SearchCancelQuery(_) => 20009,
             ErrorCodes::SearchTimeout(_) => 20010,
             ErrorCodes::InvalidParams(_) => 20011,
+            ErrorCodes::SearchTooManyRequests(_) => 20012,
+            ErrorCodes::SearchFunctionNotAllowed(_) => 20013,
         }
     }
 
@@ -269,6 +273,12 @@ impl ErrorCodes {
             ErrorCodes::SearchCancelQuery(_) => "Search query was cancelled".to_string(),
             ErrorCodes::SearchTimeout(_) => "Search query timed out".to_string(),
             ErrorCodes::InvalidParams(_) => "Invalid parameters".to_string(),
+            ErrorCodes::SearchTooManyRequests(_) => {
+                "Too many searches running for the organization".to_string()
+            }
+            ErrorCodes::SearchFunctionNotAllowed(capability) => {
+                format!("Missing capability to run query functions: {capability}")
+            }
         }
     }
 
@@ -286,6 +296,8 @@ impl ErrorCodes {
             ErrorCodes::SearchCancelQuery(msg) => msg.to_owned(),
             ErrorCodes::SearchTimeout(msg) => msg.to_owned(),
             ErrorCodes::InvalidParams(msg) => msg.to_owned(),
+            ErrorCodes::SearchTooManyRequests(msg) => msg.to_owned(),
+            ErrorCodes::SearchFunctionNotAllowed(capability) => capability.to_owned(),
         }
     }
 
@@ -303,6 +315,8 @@ impl ErrorCodes {
             ErrorCodes::SearchCancelQuery(msg) => msg.to_string(),
             ErrorCodes::SearchTimeout(msg) => msg.to_owned(),
             ErrorCodes::InvalidParams(msg) => msg.to_owned(),
+            ErrorCodes::SearchTooManyRequests(msg) => msg.to_owned(),
+            ErrorCodes::SearchFunctionNotAllowed(capability) => capability.to_owned(),
         }
     }
 
@@ -352,6 +366,8 @@ impl ErrorCodes {
             20008 => Ok(ErrorCodes::SearchSQLExecuteError(message)),
             20009 => Ok(ErrorCodes::SearchCancelQuery(message)),
             20010 => Ok(ErrorCodes::SearchTimeout(message)),
+            20012 => Ok(ErrorCodes::SearchTooManyRequests(message)),
+            20013 => Ok(ErrorCodes::SearchFunctionNotAllowed(message)),
             _ => Ok(ErrorCodes::ServerInternalError(json.to_string())),
         }
     }
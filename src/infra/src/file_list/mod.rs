@@ -125,6 +125,14 @@ pub trait FileList: Sync + Send + 'static {
         pk_value: Option<(i64, i64)>,
         deleted: bool,
     ) -> Result<Vec<(String, StreamStats)>>;
+    /// sum the files of the stream `query` returns for the time range
+    async fn stats_by_time_range(
+        &self,
+        org_id: &str,
+        stream_type: StreamType,
+        stream_name: &str,
+        time_range: (i64, i64),
+    ) -> Result<StreamStats>;
     async fn get_stream_stats(
         &self,
         org_id: &str,
@@ -162,7 +170,7 @@ pub trait FileList: Sync + Send + 'static {
         offset: i64,
     ) -> Result<i64>;
     async fn get_pending_jobs(&self, node: &str, limit: i64) -> Result<Vec<MergeJobRecord>>;
-    async fn get_pending_jobs_count(&self) -> Result<stdHashMap<String, stdHashMap<String, i64>>>;
+    async fn get_jobs_stats(&self) -> Result<Vec<StreamJobsStats>>;
     async fn set_job_pending(&self, ids: &[i64]) -> Result<()>;
     async fn set_job_done(&self, ids: &[i64]) -> Result<()>;
     async fn update_running_jobs(&self, id: i64) -> Result<()>;
@@ -349,6 +357,18 @@ pub async fn stats(
         .await
 }
 
+#[inline]
+pub async fn stats_by_time_range(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    time_range: (i64, i64),
+) -> Result<StreamStats> {
+    CLIENT
+        .stats_by_time_range(org_id, stream_type, stream_name, time_range)
+        .await
+}
+
 #[inline]
 pub async fn get_stream_stats(
     org_id: &str,
@@ -423,8 +443,8 @@ pub async fn get_pending_jobs(node: &str, limit: i64) -> Result<Vec<MergeJobReco
 }
 
 #[inline]
-pub async fn get_pending_jobs_count() -> Result<stdHashMap<String, stdHashMap<String, i64>>> {
-    CLIENT.get_pending_jobs_count().await
+pub async fn get_jobs_stats() -> Result<Vec<StreamJobsStats>> {
+    CLIENT.get_jobs_stats().await
 }
 
 #[inline]
@@ -590,6 +610,41 @@ pub enum FileListJobStatus {
     Done,
 }
 
+/// Merge jobs of a stream that are not done yet
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StreamJobsStats {
+    /// `org_id/stream_type/stream_name`
+    pub stream: String,
+    pub pending: i64,
+    pub running: i64,
+    /// When the oldest pending job was queued, in microseconds, 0 without
+    /// pending jobs or for jobs queued before this was recorded
+    pub oldest_pending_at: i64,
+}
+
+/// Folds the `stream, status, counts, min_updated_at` rows of the jobs stats
+/// queries into the stats of each stream.
+fn collect_jobs_stats(
+    rows: impl IntoIterator<Item = (String, i32, i64, i64)>,
+) -> Vec<StreamJobsStats> {
+    let mut streams: stdHashMap<String, StreamJobsStats> = stdHashMap::new();
+    for (stream, status, counts, min_updated_at) in rows {
+        let entry = streams
+            .entry(stream.clone())
+            .or_insert_with(|| StreamJobsStats {
+                stream,
+                ..Default::default()
+            });
+        if status == FileListJobStatus::Pending as i32 {
+            entry.pending += counts;
+            entry.oldest_pending_at = min_updated_at;
+        } else if status == FileListJobStatus::Running as i32 {
+            entry.running += counts;
+        }
+    }
+    streams.into_values().collect()
+}
+
 #[derive(Clone, Debug, Default, sqlx::FromRow)]
 pub struct FileId {
     pub id: i64,
@@ -13,8 +13,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap as stdHashMap;
-
 use async_trait::async_trait;
 use config::{
     get_config,
@@ -776,6 +774,43 @@ SELECT stream, MIN(min_ts) AS min_ts, MAX(max_ts) AS max_ts, COUNT(*)::BIGINT AS
             .collect())
     }
 
+    async fn stats_by_time_range(
+        &self,
+        org_id: &str,
+        stream_type: StreamType,
+        stream_name: &str,
+        time_range: (i64, i64),
+    ) -> Result<StreamStats> {
+        let stream_key = format!("{org_id}/{stream_type}/{stream_name}");
+        let (time_start, time_end) = time_range;
+        let max_ts_upper_bound = super::calculate_max_ts_upper_bound(time_end, stream_type);
+        let pool = CLIENT.clone();
+        DB_QUERY_NUMS
+            .with_label_values(&["stats_by_time_range", "file_list"])
+            .inc();
+        let start = std::time::Instant::now();
+        let ret = sqlx::query_as::<_, super::StatsRecord>(
+            r#"
+SELECT stream, MIN(min_ts) AS min_ts, MAX(max_ts) AS max_ts, COUNT(*)::BIGINT AS file_num,
+    SUM(records)::BIGINT AS records, SUM(original_size)::BIGINT AS original_size, SUM(compressed_size)::BIGINT AS compressed_size, SUM(index_size)::BIGINT AS index_size
+    FROM file_list
+    WHERE stream = $1 AND max_ts >= $2 AND max_ts <= $3 AND min_ts <= $4 AND deleted IS FALSE
+    GROUP BY stream;
+            "#,
+        )
+        .bind(stream_key)
+        .bind(time_start)
+        .bind(max_ts_upper_bound)
+        .bind(time_end)
+        .fetch_optional(&pool)
+        .await?;
+        let time = start.elapsed().as_secs_f64();
+        DB_QUERY_TIME
+            .with_label_values(&["stats_by_time_range", "file_list"])
+            .observe(time);
+        Ok(ret.as_ref().map(|r| r.into()).unwrap_or_default())
+    }
+
     async fn get_stream_stats(
         &self,
         org_id: &str,
@@ -1025,12 +1060,13 @@ UPDATE stream_stats
         match
             sqlx
                 ::query(
-                    "INSERT INTO file_list_jobs (org, stream, offsets, status, node, started_at, updated_at) VALUES ($1, $2, $3, $4, '', 0, 0) ON CONFLICT DO NOTHING;"
+                    "INSERT INTO file_list_jobs (org, stream, offsets, status, node, started_at, updated_at) VALUES ($1, $2, $3, $4, '', 0, $5) ON CONFLICT DO NOTHING;"
                 )
                 .bind(org_id)
                 .bind(&stream_key)
                 .bind(offset)
                 .bind(super::FileListJobStatus::Pending)
+                .bind(config::utils::time::now_micros())
                 .execute(&pool).await
         {
             Err(sqlx::Error::Database(e)) => if !e.is_unique_violation() {
@@ -1244,44 +1280,27 @@ SELECT stream, max(id) as id, COUNT(*)::BIGINT AS num
         Ok(())
     }
 
-    async fn get_pending_jobs_count(&self) -> Result<stdHashMap<String, stdHashMap<String, i64>>> {
+    async fn get_jobs_stats(&self) -> Result<Vec<super::StreamJobsStats>> {
         let pool = CLIENT.clone();
 
         DB_QUERY_NUMS
             .with_label_values(&["select", "file_list_jobs"])
             .inc();
-        let ret = sqlx
-            ::query(
-                r#"SELECT stream, status, count(*) as counts FROM file_list_jobs WHERE status = $1 GROUP BY stream, status ORDER BY status desc;"#
+        let ret = sqlx::query(
+            r#"SELECT stream, status, count(*) AS counts, COALESCE(MIN(NULLIF(updated_at, 0)), 0) AS min_updated_at FROM file_list_jobs WHERE status IN ($1, $2) GROUP BY stream, status;"#,
+        )
+        .bind(super::FileListJobStatus::Pending)
+        .bind(super::FileListJobStatus::Running)
+        .fetch_all(&pool)
+        .await?;
+        Ok(super::collect_jobs_stats(ret.iter().map(|r| {
+            (
+                r.get::<String, &str>("stream"),
+                r.get::<i32, &str>("status"),
+                r.get::<i64, &str>("counts"),
+                r.get::<i64, &str>("min_updated_at"),
             )
-            .bind(super::FileListJobStatus::Pending)
-            .fetch_all(&pool).await?;
-
-        let mut job_status: stdHashMap<String, stdHashMap<String, i64>> = stdHashMap::new();
-
-        for r in ret.iter() {
-            let stream = r.get::<String, &str>("stream");
-            let status = r.get::<i32, &str>("status");
-            let counts = if status == 0 {
-                r.get::<i64, &str>("counts")
-            } else {
-                0
-            };
-            let parts: Vec<&str> = stream.split('/').collect();
-            if parts.len() >= 2 {
-                let org = parts[0].to_string();
-                let stream_type = parts[1].to_string();
-                job_status
-                    .entry(org)
-                    .or_default()
-                    .entry(stream_type)
-                    .and_modify(|e| {
-                        *e = counts;
-                    })
-                    .or_insert(counts);
-            }
-        }
-        Ok(job_status)
+        })))
     }
 }
 
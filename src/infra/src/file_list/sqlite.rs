@@ -13,8 +13,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap as stdHashMap;
-
 use async_trait::async_trait;
 use config::{
     get_config,
@@ -689,6 +687,34 @@ SELECT stream, MIN(min_ts) as min_ts, MAX(max_ts) as max_ts, COUNT(*) as file_nu
             .collect())
     }
 
+    async fn stats_by_time_range(
+        &self,
+        org_id: &str,
+        stream_type: StreamType,
+        stream_name: &str,
+        time_range: (i64, i64),
+    ) -> Result<StreamStats> {
+        let stream_key = format!("{org_id}/{stream_type}/{stream_name}");
+        let (time_start, time_end) = time_range;
+        let max_ts_upper_bound = super::calculate_max_ts_upper_bound(time_end, stream_type);
+        let pool = CLIENT_RO.clone();
+        let ret = sqlx::query_as::<_, super::StatsRecord>(
+            r#"
+SELECT stream, MIN(min_ts) as min_ts, MAX(max_ts) as max_ts, COUNT(*) as file_num, SUM(records) as records, SUM(original_size) as original_size, SUM(compressed_size) as compressed_size, SUM(index_size) as index_size
+    FROM file_list
+    WHERE stream = $1 AND max_ts >= $2 AND max_ts <= $3 AND min_ts <= $4 AND deleted IS FALSE
+    GROUP BY stream;
+            "#,
+        )
+        .bind(stream_key)
+        .bind(time_start)
+        .bind(max_ts_upper_bound)
+        .bind(time_end)
+        .fetch_optional(&pool)
+        .await?;
+        Ok(ret.as_ref().map(|r| r.into()).unwrap_or_default())
+    }
+
     async fn get_stream_stats(
         &self,
         org_id: &str,
@@ -903,12 +929,13 @@ UPDATE stream_stats
         let client = CLIENT_RW.clone();
         let client = client.lock().await;
         match sqlx::query(
-            "INSERT INTO file_list_jobs (org, stream, offsets, status, node, started_at, updated_at) VALUES ($1, $2, $3, $4, '', 0, 0);",
+            "INSERT INTO file_list_jobs (org, stream, offsets, status, node, started_at, updated_at) VALUES ($1, $2, $3, $4, '', 0, $5);",
         )
         .bind(org_id)
         .bind(&stream_key)
         .bind(offset)
         .bind(super::FileListJobStatus::Pending)
+        .bind(config::utils::time::now_micros())
         .execute(&*client)
         .await
         {
@@ -1090,38 +1117,23 @@ SELECT stream, max(id) as id, COUNT(*) AS num
         Ok(())
     }
 
-    async fn get_pending_jobs_count(&self) -> Result<stdHashMap<String, stdHashMap<String, i64>>> {
+    async fn get_jobs_stats(&self) -> Result<Vec<super::StreamJobsStats>> {
         let pool = CLIENT_RO.clone();
-
-        let ret =
-            sqlx::query(r#"SELECT stream, status, count(*) as counts FROM file_list_jobs WHERE status = $1 GROUP BY stream, status ORDER BY status desc;"#)
-                .bind(super::FileListJobStatus::Pending)
-                .fetch_all(&pool)
-                .await?;
-
-        let mut job_status: stdHashMap<String, stdHashMap<String, i64>> = stdHashMap::new();
-
-        for r in ret.iter() {
-            let stream = r.get::<String, &str>("stream");
-            let status = r.get::<i32, &str>("status");
-            let counts = if status == 0 {
-                r.get::<i64, &str>("counts")
-            } else {
-                0
-            };
-            let parts: Vec<&str> = stream.split('/').collect();
-            if parts.len() >= 2 {
-                let org = parts[0].to_string();
-                let stream_type = parts[1].to_string();
-                job_status
-                    .entry(org)
-                    .or_default()
-                    .entry(stream_type)
-                    .and_modify(|e| *e = counts)
-                    .or_insert(counts);
-            }
-        }
-        Ok(job_status)
+        let ret = sqlx::query(
+            r#"SELECT stream, status, count(*) AS counts, COALESCE(MIN(NULLIF(updated_at, 0)), 0) AS min_updated_at FROM file_list_jobs WHERE status IN ($1, $2) GROUP BY stream, status;"#,
+        )
+        .bind(super::FileListJobStatus::Pending)
+        .bind(super::FileListJobStatus::Running)
+        .fetch_all(&pool)
+        .await?;
+        Ok(super::collect_jobs_stats(ret.iter().map(|r| {
+            (
+                r.get::<String, &str>("stream"),
+                r.get::<i32, &str>("status"),
+                r.get::<i64, &str>("counts"),
+                r.get::<i64, &str>("min_updated_at"),
+            )
+        })))
     }
 }
 
@@ -1522,3 +1534,56 @@ async fn add_column(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_list::FileList;
+
+    #[tokio::test]
+    async fn test_get_jobs_stats() {
+        create_table().await.unwrap();
+        let client = SqliteFileList::new();
+        // the database is shared with the other tests and runs
+        let org_id = format!("jobs_stats_{}", config::utils::time::now_micros());
+        let queued_from = config::utils::time::now_micros();
+        client
+            .add_job(&org_id, StreamType::Logs, "a", 300)
+            .await
+            .unwrap();
+        client
+            .add_job(&org_id, StreamType::Logs, "a", 100)
+            .await
+            .unwrap();
+        let done = client
+            .add_job(&org_id, StreamType::Logs, "b", 200)
+            .await
+            .unwrap();
+        client
+            .add_job(&org_id, StreamType::Traces, "c", 400)
+            .await
+            .unwrap();
+        client.set_job_done(&[done]).await.unwrap();
+        let queued_to = config::utils::time::now_micros();
+
+        let mut stats = client
+            .get_jobs_stats()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.stream.starts_with(&format!("{org_id}/")))
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.stream.cmp(&b.stream));
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].stream, format!("{org_id}/logs/a"));
+        assert_eq!(stats[0].pending, 2);
+        assert_eq!(stats[1].stream, format!("{org_id}/traces/c"));
+        assert_eq!(stats[1].pending, 1);
+        // the age comes from when the jobs were queued, not the data hour
+        for stat in stats {
+            assert_eq!(stat.running, 0);
+            assert!(stat.oldest_pending_at >= queued_from);
+            assert!(stat.oldest_pending_at <= queued_to);
+        }
+    }
+}
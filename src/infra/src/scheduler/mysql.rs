@@ -525,13 +525,15 @@ WHERE id IN ({});",
         DB_QUERY_NUMS
             .with_label_values(&["delete", "scheduled_jobs"])
             .inc();
-        // Since alert scheduled_jobs contain last_satisfied_at field, we should not delete them
+        // Since alert scheduled_jobs contain last_satisfied_at field, we should not delete them.
+        // Backfill jobs are kept to report their final status.
         sqlx::query(
-            r#"DELETE FROM scheduled_jobs WHERE (status = ? OR retries >= ?) AND module != ?;"#,
+            r#"DELETE FROM scheduled_jobs WHERE (status = ? OR retries >= ?) AND module != ? AND module != ?;"#,
         )
         .bind(TriggerStatus::Completed)
         .bind(max_retries)
         .bind(TriggerModule::Alert)
+        .bind(TriggerModule::Backfill)
         .execute(&pool)
         .await?;
         Ok(())
@@ -481,13 +481,15 @@ WHERE org = $1 AND module = $2 AND module_key = $3;"#;
         DB_QUERY_NUMS
             .with_label_values(&["delete", "scheduled_jobs"])
             .inc();
-        // Since alert scheduled_jobs contain last_satisfied_at field, we should not delete them
+        // Since alert scheduled_jobs contain last_satisfied_at field, we should not delete them.
+        // Backfill jobs are kept to report their final status.
         sqlx::query(
-            r#"DELETE FROM scheduled_jobs WHERE (status = $1 OR retries >= $2) AND module != $3;"#,
+            r#"DELETE FROM scheduled_jobs WHERE (status = $1 OR retries >= $2) AND module != $3 AND module != $4;"#,
         )
         .bind(TriggerStatus::Completed)
         .bind(max_retries)
         .bind(TriggerModule::Alert)
+        .bind(TriggerModule::Backfill)
         .execute(&pool)
         .await?;
         Ok(())
@@ -32,9 +32,11 @@ use serde::Serialize;
 use crate::{
     db as infra_db,
     errors::{DbError, Error, Result},
+    schema::not_found::STREAM_SCHEMAS_NOT_FOUND,
 };
 
 pub mod history;
+pub mod not_found;
 
 pub static STREAM_SCHEMAS: Lazy<RwAHashMap<String, Vec<(i64, Schema)>>> =
     Lazy::new(Default::default);
@@ -68,14 +70,51 @@ pub async fn get_cache(
     stream_name: &str,
     stream_type: StreamType,
 ) -> Result<SchemaCache> {
+    get_cache_with(org_id, stream_name, stream_type, false, || {
+        get_from_db(org_id, stream_name, stream_type)
+    })
+    .await
+}
+
+/// Same as [`get_cache`], but always asks the db when the stream is not
+/// cached, even if it was recently not found. Used by ingestion, which
+/// creates the stream right after a miss.
+pub async fn get_cache_for_ingestion(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+) -> Result<SchemaCache> {
+    get_cache_with(org_id, stream_name, stream_type, true, || {
+        get_from_db(org_id, stream_name, stream_type)
+    })
+    .await
+}
+
+async fn get_cache_with<F, Fut>(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    bypass_not_found: bool,
+    load: F,
+) -> Result<SchemaCache>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Schema>>,
+{
     let key = mk_key(org_id, stream_type, stream_name);
     let cache_key = key.strip_prefix("/schema/").unwrap();
     if let Some(schema) = STREAM_SCHEMAS_LATEST.read().await.get(cache_key).cloned() {
         return Ok(schema);
     }
+    if !bypass_not_found && is_not_found(org_id, stream_type, cache_key, false) {
+        return Ok(SchemaCache::new(Schema::empty()));
+    }
 
     // Get from DB without holding any locks
-    let schema = get_from_db(org_id, stream_name, stream_type).await?;
+    let schema = load().await?;
+    if is_empty_schema(&schema) && remember_not_found(cache_key) {
+        return Ok(SchemaCache::new(schema));
+    }
     let schema = SchemaCache::new(schema);
 
     // Only acquire write lock after DB read is complete
@@ -89,6 +128,204 @@ pub async fn get_cache(
     }
 }
 
+/// Whether the stream was recently not found in the db, counting the hit.
+/// With `recheck`, the first hit on each remembered stream is a miss, so the
+/// caller looks it up once more.
+fn is_not_found(org_id: &str, stream_type: StreamType, cache_key: &str, recheck: bool) -> bool {
+    let now = Utc::now().timestamp_micros();
+    if !STREAM_SCHEMAS_NOT_FOUND.contains(cache_key, now) {
+        return false;
+    }
+    if recheck && STREAM_SCHEMAS_NOT_FOUND.take_recheck(cache_key, now) {
+        return false;
+    }
+    config::metrics::META_SCHEMA_NOT_FOUND_CACHE_HITS
+        .with_label_values(&[org_id, stream_type.as_str()])
+        .inc();
+    true
+}
+
+/// Remembers that the stream was not found in the db, returns false when the
+/// not found cache is disabled.
+fn remember_not_found(cache_key: &str) -> bool {
+    let ttl = get_config().limit.schema_not_found_cache_ttl;
+    STREAM_SCHEMAS_NOT_FOUND.insert(cache_key, Utc::now().timestamp_micros(), ttl);
+    ttl > 0
+}
+
+/// Forgets that a stream was not found, once it has been created. The key is
+/// `{org_id}/{stream_type}/{stream_name}`, as in [`STREAM_SCHEMAS_LATEST`].
+pub fn forget_not_found(cache_key: &str) {
+    STREAM_SCHEMAS_NOT_FOUND.remove(cache_key);
+}
+
+/// Looks a stream that was recently not found up in the db once more, for
+/// searches that would otherwise find nothing. A stream just created on
+/// another node is found before its watch event arrives here. Each remembered
+/// stream is looked up only once, one that is still absent stays remembered.
+pub async fn recheck_not_found(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+) -> Result<Schema> {
+    recheck_not_found_with(org_id, stream_name, stream_type, || {
+        get_from_db(org_id, stream_name, stream_type)
+    })
+    .await
+}
+
+async fn recheck_not_found_with<F, Fut>(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    load: F,
+) -> Result<Schema>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Schema>>,
+{
+    let key = mk_key(org_id, stream_type, stream_name);
+    let cache_key = key.strip_prefix("/schema/").unwrap();
+    if let Some(schema) = STREAM_SCHEMAS_LATEST.read().await.get(cache_key) {
+        return Ok(schema.schema().as_ref().clone());
+    }
+    if !STREAM_SCHEMAS_NOT_FOUND.take_recheck(cache_key, Utc::now().timestamp_micros()) {
+        return Ok(Schema::empty());
+    }
+
+    let schema = load().await?;
+    if is_empty_schema(&schema) {
+        return Ok(schema);
+    }
+    forget_not_found(cache_key);
+    observe_time_to_queryable(org_id, stream_type, stream_name, &schema, "recheck");
+    let mut write_guard = STREAM_SCHEMAS_LATEST.write().await;
+    let schema = write_guard
+        .entry(cache_key.to_string())
+        .or_insert_with(|| SchemaCache::new(schema));
+    Ok(schema.schema().as_ref().clone())
+}
+
+/// Records how long after its earliest record a new stream became queryable
+/// on this node, `source` tells how the schema arrived.
+pub fn observe_time_to_queryable(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    schema: &Schema,
+    source: &str,
+) {
+    let Some(created_at) = schema
+        .metadata()
+        .get("created_at")
+        .and_then(|v| v.parse::<i64>().ok())
+    else {
+        return;
+    };
+    let took = (Utc::now().timestamp_micros() - created_at).max(0) as f64 / 1_000_000.0;
+    config::metrics::META_STREAM_TIME_TO_QUERYABLE
+        .with_label_values(&[org_id, stream_type.as_str(), source])
+        .observe(took);
+    log::info!(
+        "stream [{org_id}/{stream_type}/{stream_name}] queryable {took:.3}s after its first record, via {source}"
+    );
+}
+
+fn is_empty_schema(schema: &Schema) -> bool {
+    schema.fields().is_empty() && schema.metadata().is_empty()
+}
+
+/// Gets the latest schemas of several streams of an org, in the order of
+/// `streams`. Cached schemas are read under a single lock, the misses are
+/// loaded from the db concurrently and cached under a single write lock.
+pub async fn get_many(org_id: &str, streams: &[(String, StreamType)]) -> Vec<Result<Schema>> {
+    get_many_with(org_id, streams, |stream_name, stream_type| async move {
+        get_from_db(org_id, &stream_name, stream_type).await
+    })
+    .await
+}
+
+async fn get_many_with<F, Fut>(
+    org_id: &str,
+    streams: &[(String, StreamType)],
+    load: F,
+) -> Vec<Result<Schema>>
+where
+    F: Fn(String, StreamType) -> Fut,
+    Fut: std::future::Future<Output = Result<Schema>>,
+{
+    let keys = streams
+        .iter()
+        .map(|(stream_name, stream_type)| {
+            let key = mk_key(org_id, *stream_type, stream_name);
+            key.strip_prefix("/schema/").unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    let mut schemas = {
+        let r = STREAM_SCHEMAS_LATEST.read().await;
+        keys.iter()
+            .map(|key| r.get(key).cloned())
+            .collect::<Vec<_>>()
+    };
+    for (i, schema) in schemas.iter_mut().enumerate() {
+        if schema.is_none() && is_not_found(org_id, streams[i].1, &keys[i], false) {
+            *schema = Some(SchemaCache::new(Schema::empty()));
+        }
+    }
+
+    // Get the misses from DB without holding any locks
+    let misses = schemas
+        .iter()
+        .enumerate()
+        .filter_map(|(i, schema)| schema.is_none().then_some(i))
+        .collect::<Vec<_>>();
+    if misses.is_empty() {
+        return schemas
+            .into_iter()
+            .map(|schema| Ok(schema.unwrap().schema().as_ref().clone()))
+            .collect();
+    }
+    let loaded = futures::future::join_all(misses.iter().map(|&i| {
+        let (stream_name, stream_type) = &streams[i];
+        load(stream_name.clone(), *stream_type)
+    }))
+    .await;
+
+    let mut errors = HashMap::new();
+    let mut loaded_schemas = Vec::with_capacity(loaded.len());
+    for (i, res) in misses.into_iter().zip(loaded) {
+        match res {
+            Ok(schema) if is_empty_schema(&schema) && remember_not_found(&keys[i]) => {
+                schemas[i] = Some(SchemaCache::new(schema));
+            }
+            Ok(schema) => loaded_schemas.push((i, schema)),
+            Err(e) => {
+                errors.insert(i, e);
+            }
+        }
+    }
+    if !loaded_schemas.is_empty() {
+        // Keep what another thread may have cached while we were reading DB
+        let mut write_guard = STREAM_SCHEMAS_LATEST.write().await;
+        for (i, schema) in loaded_schemas {
+            let schema = write_guard
+                .entry(keys[i].clone())
+                .or_insert_with(|| SchemaCache::new(schema))
+                .clone();
+            schemas[i] = Some(schema);
+        }
+    }
+
+    schemas
+        .into_iter()
+        .enumerate()
+        .map(|(i, schema)| match schema {
+            Some(schema) => Ok(schema.schema().as_ref().clone()),
+            None => Err(errors.remove(&i).unwrap()),
+        })
+        .collect()
+}
+
 pub async fn get_from_db(
     org_id: &str,
     stream_name: &str,
@@ -202,10 +439,15 @@ pub async fn get_versions(
         drop(r);
     }
 
+    // searches on this node may run before the watch event of a new stream
+    if is_not_found(org_id, stream_type, cache_key, true) {
+        return Ok(vec![]);
+    }
     let db = infra_db::get_db().await;
     Ok(match db.get(&key).await {
         Err(e) => {
             if let Error::DbError(DbError::KeyNotExists(_)) = e {
+                remember_not_found(cache_key);
                 vec![]
             } else {
                 return Err(Error::Message(format!(
@@ -214,6 +456,7 @@ pub async fn get_versions(
             }
         }
         Ok(v) => {
+            forget_not_found(cache_key);
             let schemas: Result<Vec<Schema>> = json::from_slice(&v).map_err(|e| e.into());
             if let Ok(schemas) = schemas {
                 schemas
@@ -380,6 +623,7 @@ pub async fn merge(
     }
     let start_dt = min_ts;
     let key = mk_key(org_id, stream_type, stream_name);
+    let cache_key = key.strip_prefix("/schema/").unwrap().to_string();
     let inferred_schema = schema.clone();
     let (tx, rx) = tokio::sync::oneshot::channel();
     let db = infra_db::get_db().await;
@@ -482,6 +726,7 @@ pub async fn merge(
         }),
     )
     .await?;
+    forget_not_found(&cache_key);
     rx.await.map_err(|e| Error::Message(e.to_string()))
 }
 
@@ -496,6 +741,7 @@ pub async fn update_setting(
         return Ok(());
     }
     let key = mk_key(org_id, stream_type, stream_name);
+    let cache_key = key.strip_prefix("/schema/").unwrap().to_string();
     let db = infra_db::get_db().await;
     db.get_for_update(
         &key.clone(),
@@ -546,6 +792,7 @@ pub async fn update_setting(
         }),
     )
     .await?;
+    forget_not_found(&cache_key);
 
     Ok(())
 }
@@ -810,4 +1057,221 @@ mod tests {
         let res = get_stream_setting_fts_fields(&settings);
         assert!(!res.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_get_many_loads_misses_concurrently() {
+        let delay = std::time::Duration::from_millis(100);
+        let streams = (0..10)
+            .map(|i| (format!("stream_{i}"), StreamType::Logs))
+            .collect::<Vec<_>>();
+        let start = std::time::Instant::now();
+        let schemas = get_many_with("test_get_many_concurrent", &streams, |name, _| async move {
+            tokio::time::sleep(delay).await;
+            Ok(Schema::new(vec![Field::new(name, DataType::Utf8, true)]))
+        })
+        .await;
+        // ten serial loads would take at least a second
+        assert!(start.elapsed() < delay * 5);
+        assert_eq!(schemas.len(), 10);
+        for (i, schema) in schemas.into_iter().enumerate() {
+            assert_eq!(schema.unwrap().field(0).name(), &format!("stream_{i}"));
+        }
+
+        // the loaded schemas are cached
+        let schemas = get_many_with("test_get_many_concurrent", &streams, |_, _| async {
+            Err(Error::Message("should be cached".to_string()))
+        })
+        .await;
+        assert!(schemas.iter().all(|schema| schema.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_get_many_keeps_errors_per_stream() {
+        let streams = vec![
+            ("ok".to_string(), StreamType::Logs),
+            ("broken".to_string(), StreamType::Logs),
+            ("ok".to_string(), StreamType::Traces),
+        ];
+        let load = |name: String, _| async move {
+            if name == "broken" {
+                Err(Error::Message("db unavailable".to_string()))
+            } else {
+                Ok(Schema::new(vec![Field::new("f", DataType::Int64, true)]))
+            }
+        };
+        let schemas = get_many_with("test_get_many_errors", &streams, load).await;
+        assert_eq!(schemas.len(), 3);
+        assert_eq!(schemas[0].as_ref().unwrap().fields().len(), 1);
+        assert!(schemas[1].is_err());
+        assert_eq!(schemas[2].as_ref().unwrap().fields().len(), 1);
+
+        // failed loads are not cached and are retried
+        let schemas = get_many_with("test_get_many_errors", &streams, load).await;
+        assert!(schemas[1].is_err());
+        let r = STREAM_SCHEMAS_LATEST.read().await;
+        assert!(!r.contains_key("test_get_many_errors/logs/broken"));
+        assert!(r.contains_key("test_get_many_errors/logs/ok"));
+        assert!(r.contains_key("test_get_many_errors/traces/ok"));
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_remembers_not_found() {
+        let loads = &std::sync::atomic::AtomicUsize::new(0);
+        let load = move || async move {
+            loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Schema::empty())
+        };
+        let org_id = "test_get_cache_not_found";
+        for _ in 0..3 {
+            let schema = get_cache_with(org_id, "typo", StreamType::Logs, false, load)
+                .await
+                .unwrap();
+            assert!(schema.fields_map().is_empty());
+        }
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(!STREAM_SCHEMAS_LATEST
+            .read()
+            .await
+            .contains_key("test_get_cache_not_found/logs/typo"));
+
+        // other streams are still loaded
+        get_cache_with(org_id, "typo", StreamType::Traces, false, load)
+            .await
+            .unwrap();
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_not_found_expires() {
+        let org_id = "test_get_cache_not_found_expires";
+        let cache_key = "test_get_cache_not_found_expires/logs/typo";
+        let now = Utc::now().timestamp_micros();
+        STREAM_SCHEMAS_NOT_FOUND.insert(cache_key, now - 31_000_000, 30);
+
+        let schema = get_cache_with(org_id, "typo", StreamType::Logs, false, || async {
+            Ok(Schema::new(vec![Field::new("f", DataType::Utf8, true)]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(schema.fields_map().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_after_stream_created() {
+        let org_id = "test_get_cache_created";
+        let cache_key = "test_get_cache_created/logs/new_stream";
+        get_cache_with(org_id, "new_stream", StreamType::Logs, false, || async {
+            Ok(Schema::empty())
+        })
+        .await
+        .unwrap();
+        assert!(STREAM_SCHEMAS_NOT_FOUND.contains(cache_key, Utc::now().timestamp_micros()));
+
+        // creating the stream, locally or through a watch event, forgets it
+        forget_not_found(cache_key);
+        let schema = get_cache_with(org_id, "new_stream", StreamType::Logs, false, || async {
+            Ok(Schema::new(vec![Field::new("f", DataType::Utf8, true)]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(schema.fields_map().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_for_ingestion_bypasses_not_found() {
+        let org_id = "test_get_cache_ingestion";
+        let cache_key = "test_get_cache_ingestion/logs/new_stream";
+        STREAM_SCHEMAS_NOT_FOUND.insert(cache_key, Utc::now().timestamp_micros(), 30);
+
+        // another node created the stream before its watch event arrived here
+        let schema = get_cache_with(org_id, "new_stream", StreamType::Logs, true, || async {
+            Ok(Schema::new(vec![Field::new("f", DataType::Utf8, true)]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(schema.fields_map().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_many_remembers_not_found() {
+        let org_id = "test_get_many_not_found";
+        let streams = vec![
+            ("typo".to_string(), StreamType::Logs),
+            ("ok".to_string(), StreamType::Logs),
+        ];
+        let schemas = get_many_with(org_id, &streams, |name, _| async move {
+            if name == "typo" {
+                Ok(Schema::empty())
+            } else {
+                Ok(Schema::new(vec![Field::new("f", DataType::Utf8, true)]))
+            }
+        })
+        .await;
+        assert!(schemas[0].as_ref().unwrap().fields().is_empty());
+        assert_eq!(schemas[1].as_ref().unwrap().fields().len(), 1);
+
+        let schemas = get_many_with(org_id, &streams, |_, _| async {
+            Err(Error::Message("should be cached".to_string()))
+        })
+        .await;
+        assert!(schemas.iter().all(|schema| schema.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_recheck_finds_stream_created_elsewhere() {
+        let org_id = "test_recheck_created";
+        let cache_key = "test_recheck_created/logs/new_stream";
+        // a search ran here before the first records reached another node
+        get_cache_with(org_id, "new_stream", StreamType::Logs, false, || async {
+            Ok(Schema::empty())
+        })
+        .await
+        .unwrap();
+        assert!(STREAM_SCHEMAS_NOT_FOUND.contains(cache_key, Utc::now().timestamp_micros()));
+
+        // the other node created the stream, its watch event has not arrived yet
+        let schema = recheck_not_found_with(org_id, "new_stream", StreamType::Logs, || async {
+            Ok(Schema::new(vec![Field::new("f", DataType::Utf8, true)]))
+        })
+        .await
+        .unwrap();
+        assert_eq!(schema.fields().len(), 1);
+        assert!(!STREAM_SCHEMAS_NOT_FOUND.contains(cache_key, Utc::now().timestamp_micros()));
+
+        // later searches use the cached schema
+        let schema = get_cache_with(org_id, "new_stream", StreamType::Logs, false, || async {
+            Err(Error::Message("should be cached".to_string()))
+        })
+        .await
+        .unwrap();
+        assert_eq!(schema.fields_map().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recheck_absent_stream_once() {
+        let loads = &std::sync::atomic::AtomicUsize::new(0);
+        let load = move || async move {
+            loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Schema::empty())
+        };
+        let org_id = "test_recheck_absent";
+        let cache_key = "test_recheck_absent/logs/typo";
+
+        // nothing remembered, the lookup that just missed is not repeated
+        let schema = recheck_not_found_with(org_id, "typo", StreamType::Logs, load)
+            .await
+            .unwrap();
+        assert!(schema.fields().is_empty());
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        STREAM_SCHEMAS_NOT_FOUND.insert(cache_key, Utc::now().timestamp_micros(), 30);
+        for _ in 0..3 {
+            let schema = recheck_not_found_with(org_id, "typo", StreamType::Logs, load)
+                .await
+                .unwrap();
+            assert!(schema.fields().is_empty());
+        }
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(STREAM_SCHEMAS_NOT_FOUND.contains(cache_key, Utc::now().timestamp_micros()));
+    }
 }
@@ -0,0 +1,201 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Remembers lookups of stream schemas that were not found in the meta store
+//! for a short time, so that traffic for streams that do not exist, such as
+//! agents shipping to a misspelled stream or stale dashboards searching a
+//! deleted stream, does not hit the meta store on every request. Searches may
+//! look each remembered stream up once more, in case it was created on
+//! another node before its watch event arrived.
+
+use config::RwHashMap;
+use once_cell::sync::Lazy;
+
+/// Streams recently not found, keyed like `STREAM_SCHEMAS_LATEST`.
+pub static STREAM_SCHEMAS_NOT_FOUND: Lazy<NotFoundCache> = Lazy::new(Default::default);
+
+/// Most keys remembered at once, so that clients sending to many random
+/// stream names can not grow the cache without bound
+const MAX_ENTRIES: usize = 100_000;
+
+/// Keys that were not found, along with when that expires.
+#[derive(Debug)]
+pub struct NotFoundCache {
+    expires_at: RwHashMap<String, Entry>,
+    max_entries: usize,
+}
+
+impl Default for NotFoundCache {
+    fn default() -> Self {
+        Self::with_max_entries(MAX_ENTRIES)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// In microseconds
+    expires_at: i64,
+    /// Whether a search already looked the key up once more
+    rechecked: bool,
+}
+
+impl NotFoundCache {
+    fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            expires_at: Default::default(),
+            max_entries,
+        }
+    }
+
+    /// Whether the key was not found less than the TTL ago. Expired entries
+    /// are removed.
+    pub fn contains(&self, key: &str, now: i64) -> bool {
+        let expires_at = match self.expires_at.get(key) {
+            Some(entry) => entry.expires_at,
+            None => return false,
+        };
+        if now < expires_at {
+            return true;
+        }
+        self.expires_at
+            .remove_if(key, |_, entry| entry.expires_at <= now);
+        false
+    }
+
+    /// Remembers that the key was not found for `ttl_secs`, nothing is
+    /// remembered when the TTL is not positive. A key that is still
+    /// remembered keeps whether it was rechecked. When the cache is full the
+    /// expired keys are removed, and new keys are not remembered until some
+    /// expire.
+    pub fn insert(&self, key: &str, now: i64, ttl_secs: i64) {
+        if ttl_secs <= 0 {
+            return;
+        }
+        if self.expires_at.len() >= self.max_entries && !self.expires_at.contains_key(key) {
+            self.expires_at.retain(|_, entry| now < entry.expires_at);
+            if self.expires_at.len() >= self.max_entries {
+                return;
+            }
+        }
+        let expires_at = now + ttl_secs * 1_000_000;
+        self.expires_at
+            .entry(key.to_string())
+            .and_modify(|entry| {
+                if entry.expires_at <= now {
+                    entry.rechecked = false;
+                }
+                entry.expires_at = expires_at;
+            })
+            .or_insert(Entry {
+                expires_at,
+                rechecked: false,
+            });
+    }
+
+    /// Whether the key should be looked up once more, which is true only the
+    /// first time it is asked for each remembered key.
+    pub fn take_recheck(&self, key: &str, now: i64) -> bool {
+        match self.expires_at.get_mut(key) {
+            Some(mut entry) if now < entry.expires_at && !entry.rechecked => {
+                entry.rechecked = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets that the key was not found.
+    pub fn remove(&self, key: &str) {
+        self.expires_at.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_found_cache_ttl() {
+        let cache = NotFoundCache::default();
+        let now = 1_700_000_000_000_000;
+        assert!(!cache.contains("org/logs/typo", now));
+
+        cache.insert("org/logs/typo", now, 30);
+        assert!(cache.contains("org/logs/typo", now));
+        assert!(cache.contains("org/logs/typo", now + 29_999_999));
+        assert!(!cache.contains("org/traces/typo", now));
+
+        // expired entries are removed
+        assert!(!cache.contains("org/logs/typo", now + 30_000_000));
+        assert!(cache.expires_at.is_empty());
+    }
+
+    #[test]
+    fn test_not_found_cache_disabled() {
+        let cache = NotFoundCache::default();
+        let now = 1_700_000_000_000_000;
+        cache.insert("org/logs/typo", now, 0);
+        assert!(!cache.contains("org/logs/typo", now));
+    }
+
+    #[test]
+    fn test_not_found_cache_is_bounded() {
+        let cache = NotFoundCache::with_max_entries(2);
+        let now = 1_700_000_000_000_000;
+        cache.insert("org/logs/a", now, 10);
+        cache.insert("org/logs/b", now, 30);
+        cache.insert("org/logs/c", now, 30);
+        assert!(!cache.contains("org/logs/c", now));
+        // remembered keys are still refreshed when full
+        cache.insert("org/logs/a", now + 1, 10);
+        assert!(cache.contains("org/logs/a", now + 1));
+
+        // expired keys make room
+        cache.insert("org/logs/c", now + 20_000_000, 30);
+        assert!(cache.contains("org/logs/c", now + 20_000_000));
+        assert!(cache.contains("org/logs/b", now + 20_000_000));
+        assert_eq!(cache.expires_at.len(), 2);
+    }
+
+    #[test]
+    fn test_not_found_cache_remove() {
+        let cache = NotFoundCache::default();
+        let now = 1_700_000_000_000_000;
+        cache.insert("org/logs/typo", now, 30);
+        cache.remove("org/logs/typo");
+        assert!(!cache.contains("org/logs/typo", now));
+    }
+
+    #[test]
+    fn test_not_found_cache_recheck_once() {
+        let cache = NotFoundCache::default();
+        let now = 1_700_000_000_000_000;
+        assert!(!cache.take_recheck("org/logs/typo", now));
+
+        cache.insert("org/logs/typo", now, 30);
+        assert!(cache.take_recheck("org/logs/typo", now));
+        assert!(!cache.take_recheck("org/logs/typo", now));
+
+        // still absent after the recheck, remembered without another one
+        cache.insert("org/logs/typo", now + 1_000_000, 30);
+        assert!(cache.contains("org/logs/typo", now + 30_000_000));
+        assert!(!cache.take_recheck("org/logs/typo", now + 1_000_000));
+
+        // a new miss after the entry expired can be rechecked again
+        cache.insert("org/logs/typo", now + 31_000_000, 30);
+        assert!(cache.take_recheck("org/logs/typo", now + 31_000_000));
+        assert!(!cache.take_recheck("org/logs/typo", now + 61_000_000));
+    }
+}
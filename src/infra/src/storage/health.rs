@@ -0,0 +1,188 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Instant;
+
+use config::{cluster::LOCAL_NODE, get_config, is_local_disk_storage};
+use futures::StreamExt;
+use object_store::{path::Path, ObjectStore};
+use serde::Serialize;
+
+/// Probe objects are written under this prefix of the bucket
+const PROBE_PREFIX: &str = "_health";
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "storage health check failed to {operation} probe object {key} in bucket '{bucket}' (provider: '{provider}', region: '{region}'), check the ZO_S3_* settings and credentials: {source}"
+)]
+pub struct ProbeError {
+    pub operation: &'static str,
+    pub key: String,
+    pub bucket: String,
+    pub provider: String,
+    pub region: String,
+    #[source]
+    pub source: object_store::Error,
+}
+
+/// Latency of each step of a storage probe, in milliseconds
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ProbeLatency {
+    pub put: u64,
+    pub get: u64,
+    pub delete: u64,
+    pub total: u64,
+}
+
+/// The probe object of this node, named after the instance so that restarts
+/// reuse the same key instead of leaving behind the objects of failed probes
+fn probe_key() -> String {
+    format!("{PROBE_PREFIX}/{}.probe", LOCAL_NODE.name)
+}
+
+/// Write, read back and delete a small object in the default storage.
+pub async fn probe() -> Result<ProbeLatency, ProbeError> {
+    probe_store(super::DEFAULT.as_ref(), &probe_key()).await
+}
+
+/// Check that the default storage can be listed, without writing to it.
+/// Returns the latency in milliseconds.
+pub async fn check() -> Result<u64, ProbeError> {
+    check_store(super::DEFAULT.as_ref(), &probe_key()).await
+}
+
+/// Run the probe before the node starts serving, it is skipped for local disk storage or
+/// when `ZO_S3_STARTUP_CHECK_ENABLED` is off, e.g. air-gapped cold starts.
+pub async fn check_on_startup() -> Result<(), ProbeError> {
+    if is_local_disk_storage() || !get_config().s3.startup_check_enabled {
+        return Ok(());
+    }
+    let latency = probe().await?;
+    log::info!(
+        "[STORAGE] health check passed, put: {} ms, get: {} ms, delete: {} ms",
+        latency.put,
+        latency.get,
+        latency.delete
+    );
+    Ok(())
+}
+
+pub async fn probe_store(store: &dyn ObjectStore, key: &str) -> Result<ProbeLatency, ProbeError> {
+    let path = Path::from(key);
+    let payload = bytes::Bytes::from(format!("probe {}", config::utils::time::now_micros()));
+    let mut latency = ProbeLatency::default();
+    let start = Instant::now();
+
+    let step = Instant::now();
+    store
+        .put(&path, payload.clone().into())
+        .await
+        .map_err(|e| probe_error("put", key, e))?;
+    latency.put = step.elapsed().as_millis() as u64;
+
+    let step = Instant::now();
+    let data = match store.get(&path).await {
+        Ok(v) => v.bytes().await,
+        Err(e) => Err(e),
+    }
+    .map_err(|e| probe_error("get", key, e))?;
+    latency.get = step.elapsed().as_millis() as u64;
+    if data != payload {
+        return Err(probe_error(
+            "get",
+            key,
+            object_store::Error::Generic {
+                store: "storage",
+                source: "probe object content mismatch".into(),
+            },
+        ));
+    }
+
+    let step = Instant::now();
+    store
+        .delete(&path)
+        .await
+        .map_err(|e| probe_error("delete", key, e))?;
+    latency.delete = step.elapsed().as_millis() as u64;
+
+    latency.total = start.elapsed().as_millis() as u64;
+    Ok(latency)
+}
+
+pub async fn check_store(store: &dyn ObjectStore, key: &str) -> Result<u64, ProbeError> {
+    let start = Instant::now();
+    let prefix = Path::from(PROBE_PREFIX);
+    if let Some(Err(e)) = store.list(Some(&prefix)).next().await {
+        return Err(probe_error("list", key, e));
+    }
+    Ok(start.elapsed().as_millis() as u64)
+}
+
+fn probe_error(operation: &'static str, key: &str, source: object_store::Error) -> ProbeError {
+    let cfg = get_config();
+    ProbeError {
+        operation,
+        key: key.to_string(),
+        bucket: cfg.s3.bucket_name.clone(),
+        provider: cfg.s3.provider.clone(),
+        region: cfg.s3.region_name.clone(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::local::Local;
+
+    #[tokio::test]
+    async fn test_probe_store_round_trip() {
+        let root = std::env::temp_dir().join(format!("o2_probe_ok_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let store = Local::new(root.to_str().unwrap(), false);
+        let key = "_health/test.probe";
+        let latency = probe_store(&store, key).await.unwrap();
+        assert!(latency.total >= latency.put);
+        // the probe object is cleaned up
+        assert!(!root.join(key).exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_store_does_not_write() {
+        let root = std::env::temp_dir().join(format!("o2_probe_check_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let store = Local::new(root.to_str().unwrap(), false);
+        let key = "_health/test.probe";
+        assert!(check_store(&store, key).await.is_ok());
+        assert!(!root.join(PROBE_PREFIX).exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_probe_store_names_failed_operation() {
+        // the storage root is a file, so nothing can be written below it
+        let root = std::env::temp_dir().join(format!("o2_probe_fail_{}", std::process::id()));
+        std::fs::write(&root, b"not a dir").unwrap();
+        let store = Local::new(root.to_str().unwrap(), false);
+        let err = probe_store(&store, "_health/test.probe").await.unwrap_err();
+        assert_eq!(err.operation, "put");
+        let msg = err.to_string();
+        assert!(msg.contains("failed to put probe object _health/test.probe"));
+        assert!(msg.contains("in bucket"));
+        assert!(msg.contains("region"));
+        std::fs::remove_file(&root).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Object key layout of stream data files in the bucket.
+//!
+//! The file list and the rest of the code base always address stream files by
+//! their logical key, eg: `files/default/logs/olympics/2022/10/03/10/xxx.parquet`.
+//! With `ZO_S3_DATE_PREFIX_POSITION` set, new files are written under an extra
+//! `dt=YYYY-MM-DD` key component so bucket lifecycle rules can target whole
+//! days. Files written before keep the legacy key, so reads try both.
+
+use std::future::Future;
+
+use config::get_config;
+use object_store::{path::Path, Error, Result};
+
+/// Where the `dt=YYYY-MM-DD` component is placed in the object key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatePrefixPosition {
+    /// `dt=2022-10-03/files/default/logs/olympics/2022/10/03/10/xxx.parquet`
+    Root,
+    /// `files/default/logs/olympics/dt=2022-10-03/2022/10/03/10/xxx.parquet`
+    Stream,
+}
+
+impl DatePrefixPosition {
+    /// Returns the configured position, `None` if the layout is disabled.
+    pub fn from_config() -> Option<Self> {
+        Self::parse(&get_config().s3.date_prefix_position)
+    }
+
+    pub fn parse(position: &str) -> Option<Self> {
+        match position {
+            "root" => Some(Self::Root),
+            "stream" => Some(Self::Stream),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Root => 0,
+            Self::Stream => 4,
+        }
+    }
+}
+
+/// Returns the object key of the stream file `key` in the date prefixed
+/// layout, or `None` if `key` isn't a stream data file.
+pub fn date_prefixed_key(key: &str, position: DatePrefixPosition) -> Option<String> {
+    // eg: files/default/logs/olympics/2022/10/03/10/6982652937134804993_1.parquet
+    let mut columns = key.splitn(9, '/').collect::<Vec<&str>>();
+    if columns.len() < 9
+        || columns[0] != "files"
+        || !columns[4..8]
+            .iter()
+            .all(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let date = format!("dt={}-{}-{}", columns[4], columns[5], columns[6]);
+    columns.insert(position.index(), &date);
+    Some(columns.join("/"))
+}
+
+/// Returns the object keys `key` may be stored under, starting with the one
+/// new files are written to.
+pub fn object_keys(key: &str, position: Option<DatePrefixPosition>) -> Vec<String> {
+    match position.and_then(|position| date_prefixed_key(key, position)) {
+        Some(prefixed) => vec![prefixed, key.to_string()],
+        None => vec![key.to_string()],
+    }
+}
+
+/// Runs `read` on each of `keys` in order and returns the first result that
+/// isn't a `NotFound` error.
+pub async fn read_first<T, F, Fut>(keys: &[String], read: F) -> Result<T>
+where
+    F: Fn(Path) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut last_err = None;
+    for key in keys {
+        match read(key.as_str().into()).await {
+            Err(e @ Error::NotFound { .. }) => last_err = Some(e),
+            result => return result,
+        }
+    }
+    Err(last_err.unwrap_or_else(|| Error::NotFound {
+        path: String::new(),
+        source: "no object key to read".into(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use object_store::{memory::InMemory, ObjectStore};
+
+    use super::*;
+
+    const KEY: &str = "files/default/logs/olympics/2022/10/03/10/6982652937134804993_1.parquet";
+
+    #[test]
+    fn test_date_prefixed_key() {
+        assert_eq!(
+            date_prefixed_key(KEY, DatePrefixPosition::Root).unwrap(),
+            "dt=2022-10-03/files/default/logs/olympics/2022/10/03/10/6982652937134804993_1.parquet"
+        );
+        assert_eq!(
+            date_prefixed_key(KEY, DatePrefixPosition::Stream).unwrap(),
+            "files/default/logs/olympics/dt=2022-10-03/2022/10/03/10/6982652937134804993_1.parquet"
+        );
+        // prefix columns are kept after the file name
+        assert_eq!(
+            date_prefixed_key(
+                "files/default/logs/olympics/2022/10/03/10/ip=1234/xxx.parquet",
+                DatePrefixPosition::Stream
+            )
+            .unwrap(),
+            "files/default/logs/olympics/dt=2022-10-03/2022/10/03/10/ip=1234/xxx.parquet"
+        );
+        // only stream data files are moved
+        assert!(
+            date_prefixed_key("files/default/actions/xxx.zip", DatePrefixPosition::Root).is_none()
+        );
+        assert!(date_prefixed_key(
+            "meta/default/logs/olympics/2022/10/03/10/xxx.json",
+            DatePrefixPosition::Root
+        )
+        .is_none());
+        assert!(date_prefixed_key(
+            "files/default/logs/olympics/2022/10/latest/10/xxx.parquet",
+            DatePrefixPosition::Root
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_object_keys() {
+        assert_eq!(object_keys(KEY, None), vec![KEY.to_string()]);
+        assert_eq!(
+            object_keys(KEY, Some(DatePrefixPosition::Root)),
+            vec![
+                date_prefixed_key(KEY, DatePrefixPosition::Root).unwrap(),
+                KEY.to_string()
+            ]
+        );
+        assert_eq!(
+            DatePrefixPosition::parse("stream"),
+            Some(DatePrefixPosition::Stream)
+        );
+        assert_eq!(DatePrefixPosition::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_first_consults_both_layouts() {
+        let store = InMemory::new();
+        let keys = object_keys(KEY, Some(DatePrefixPosition::Stream));
+        let read = |path: Path| {
+            let store = &store;
+            async move { store.get(&path).await?.bytes().await }
+        };
+
+        assert!(matches!(
+            read_first(&keys, read).await,
+            Err(Error::NotFound { .. })
+        ));
+
+        // files written before the layout was enabled
+        store
+            .put(&keys[1].as_str().into(), Bytes::from("legacy").into())
+            .await
+            .unwrap();
+        assert_eq!(read_first(&keys, read).await.unwrap(), "legacy");
+
+        // files written with the date prefixed layout
+        store
+            .put(&keys[0].as_str().into(), Bytes::from("prefixed").into())
+            .await
+            .unwrap();
+        assert_eq!(read_first(&keys, read).await.unwrap(), "prefixed");
+    }
+}
@@ -23,6 +23,8 @@ use object_store::{path::Path, GetRange, ObjectMeta, ObjectStore, WriteMultipart
 use once_cell::sync::Lazy;
 use parquet::file::metadata::ParquetMetaDataReader;
 
+pub mod health;
+pub mod layout;
 pub mod local;
 pub mod remote;
 
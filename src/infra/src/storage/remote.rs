@@ -24,7 +24,11 @@ use object_store::{
     ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
 };
 
-use crate::storage::{format_key, CONCURRENT_REQUESTS};
+use crate::storage::{
+    format_key,
+    layout::{object_keys, read_first, DatePrefixPosition},
+    CONCURRENT_REQUESTS,
+};
 
 pub struct Remote {
     client: LimitStore<Box<dyn object_store::ObjectStore>>,
@@ -50,6 +54,14 @@ impl std::fmt::Display for Remote {
     }
 }
 
+/// Bucket keys `file` may be stored under, the one to write new files to first.
+fn remote_keys(file: &str) -> Vec<String> {
+    object_keys(file, DatePrefixPosition::from_config())
+        .into_iter()
+        .map(|key| format_key(&key, true))
+        .collect()
+}
+
 #[async_trait]
 impl ObjectStore for Remote {
     async fn put_opts(
@@ -63,7 +75,7 @@ impl ObjectStore for Remote {
         let data_size = payload.content_length();
         match self
             .client
-            .put_opts(&(format_key(&file, true).into()), payload, opts)
+            .put_opts(&(remote_keys(&file).remove(0).into()), payload, opts)
             .await
         {
             Ok(_) => {
@@ -101,7 +113,7 @@ impl ObjectStore for Remote {
         let file = location.to_string();
         match self
             .client
-            .put_multipart_opts(&(format_key(&file, true).into()), opts)
+            .put_multipart_opts(&(remote_keys(&file).remove(0).into()), opts)
             .await
         {
             Ok(r) => Ok(r),
@@ -115,7 +127,10 @@ impl ObjectStore for Remote {
     async fn get(&self, location: &Path) -> Result<GetResult> {
         let start = std::time::Instant::now();
         let file = location.to_string();
-        let result = self.client.get(&(format_key(&file, true).into())).await?;
+        let result = read_first(&remote_keys(&file), |path| async move {
+            self.client.get(&path).await
+        })
+        .await?;
 
         // metrics
         let data_len = result.meta.size;
@@ -140,10 +155,11 @@ impl ObjectStore for Remote {
     async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
         let start = std::time::Instant::now();
         let file = location.to_string();
-        let result = self
-            .client
-            .get_opts(&(format_key(&file, true).into()), options)
-            .await?;
+        let result = read_first(&remote_keys(&file), |path| {
+            let options = options.clone();
+            async move { self.client.get_opts(&path, options).await }
+        })
+        .await?;
 
         // metrics
         let data_len = result.meta.size;
@@ -167,10 +183,11 @@ impl ObjectStore for Remote {
     async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
         let start = std::time::Instant::now();
         let file = location.to_string();
-        let data = self
-            .client
-            .get_range(&(format_key(&file, true).into()), range)
-            .await?;
+        let data = read_first(&remote_keys(&file), |path| {
+            let range = range.clone();
+            async move { self.client.get_range(&path, range).await }
+        })
+        .await?;
 
         // metrics
         let data_len = data.len();
@@ -192,21 +209,30 @@ impl ObjectStore for Remote {
     }
 
     async fn delete(&self, location: &Path) -> Result<()> {
+        // the file may have been written with either layout, delete both
+        let keys = remote_keys(location.as_ref());
+        let num_keys = keys.len();
         let mut result: Result<()> = Ok(());
-        for _ in 0..3 {
-            result = self
-                .client
-                .delete(&(format_key(location.as_ref(), true).into()))
-                .await;
-            if result.is_ok() {
-                let file = location.to_string();
-                let columns = file.split('/').collect::<Vec<&str>>();
-                metrics::STORAGE_WRITE_REQUESTS
-                    .with_label_values(&[columns[1], columns[2]])
-                    .inc();
+        for key in keys {
+            let key: Path = key.into();
+            for _ in 0..3 {
+                result = match self.client.delete(&key).await {
+                    Err(Error::NotFound { .. }) if num_keys > 1 => Ok(()),
+                    result => result,
+                };
+                if result.is_ok() {
+                    let file = location.to_string();
+                    let columns = file.split('/').collect::<Vec<&str>>();
+                    metrics::STORAGE_WRITE_REQUESTS
+                        .with_label_values(&[columns[1], columns[2]])
+                        .inc();
+                    break;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            }
+            if result.is_err() {
                 break;
             }
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
         result
     }
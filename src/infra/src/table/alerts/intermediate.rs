@@ -21,8 +21,9 @@ use std::{fmt::Display, str::FromStr};
 use config::meta::{
     alerts::{
         AggFunction as MetaAggFunction, Aggregation as MetaAggregation,
-        CompareHistoricData as MetaCompareHistoricData, Condition as MetaCondition,
-        FrequencyType as MetaFrequencyType, Operator as MetaOperator, QueryType as MetaQueryType,
+        BaselineCondition as MetaBaselineCondition, CompareHistoricData as MetaCompareHistoricData,
+        Condition as MetaCondition, FrequencyType as MetaFrequencyType, Operator as MetaOperator,
+        QueryType as MetaQueryType,
     },
     search::SearchEventType as MetaSearchEventType,
     stream::StreamType as MetaStreamType,
@@ -55,6 +56,38 @@ impl From<QueryCompareHistoricData> for MetaCompareHistoricData {
     }
 }
 
+/// Comparison against an earlier window. Stored in the DB as a JSON object.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TriggerBaseline {
+    pub offset: String,
+    pub operator: ConditionOperator,
+    pub multiplier: f64,
+    pub column: String,
+}
+
+impl From<MetaBaselineCondition> for TriggerBaseline {
+    fn from(value: MetaBaselineCondition) -> Self {
+        Self {
+            offset: value.offset,
+            operator: value.operator.into(),
+            multiplier: value.multiplier,
+            column: value.column,
+        }
+    }
+}
+
+impl From<TriggerBaseline> for MetaBaselineCondition {
+    fn from(value: TriggerBaseline) -> Self {
+        Self {
+            offset: value.offset,
+            operator: value.operator.into(),
+            multiplier: value.multiplier,
+            column: value.column,
+        }
+    }
+}
+
 /// Threshold frequency type. Stored in the DB as a 16-bit integere.
 pub enum TriggerFrequencyType {
     Cron,
@@ -205,12 +238,14 @@ pub enum QueryType {
     Custom,
     Sql,
     Promql,
+    SchemaDrift,
 }
 
 impl QueryType {
     const CUSTOM: i16 = 0;
     const SQL: i16 = 1;
     const PROMQL: i16 = 2;
+    const SCHEMA_DRIFT: i16 = 3;
 }
 
 impl From<QueryType> for i16 {
@@ -219,6 +254,7 @@ impl From<QueryType> for i16 {
             QueryType::Custom => QueryType::CUSTOM,
             QueryType::Sql => QueryType::SQL,
             QueryType::Promql => QueryType::PROMQL,
+            QueryType::SchemaDrift => QueryType::SCHEMA_DRIFT,
         }
     }
 }
@@ -231,6 +267,7 @@ impl TryFrom<i16> for QueryType {
             Self::CUSTOM => Ok(QueryType::Custom),
             Self::SQL => Ok(QueryType::Sql),
             Self::PROMQL => Ok(QueryType::Promql),
+            Self::SCHEMA_DRIFT => Ok(QueryType::SchemaDrift),
             _ => Err(FromI16Error {
                 value,
                 ty: "QueryType".to_string(),
@@ -245,6 +282,7 @@ impl From<MetaQueryType> for QueryType {
             MetaQueryType::Custom => QueryType::Custom,
             MetaQueryType::SQL => QueryType::Sql,
             MetaQueryType::PromQL => QueryType::Promql,
+            MetaQueryType::SchemaDrift => QueryType::SchemaDrift,
         }
     }
 }
@@ -255,6 +293,7 @@ impl From<QueryType> for MetaQueryType {
             QueryType::Custom => MetaQueryType::Custom,
             QueryType::Sql => MetaQueryType::SQL,
             QueryType::Promql => MetaQueryType::PromQL,
+            QueryType::SchemaDrift => MetaQueryType::SchemaDrift,
         }
     }
 }
@@ -23,6 +23,7 @@ use config::meta::{
     },
     folder::{Folder as MetaFolder, FolderType},
     stream::StreamType as MetaStreamType,
+    trash::{TrashItem, TrashItemType},
 };
 use hashbrown::HashMap;
 use itertools::Itertools;
@@ -67,7 +68,8 @@ impl TryFrom<alerts::Model> for MetaAlert {
 
         // Transform database JSON values into intermediate types which can be
         // directly translated into service layer types.
-        let destinations: Vec<String> = serde_json::from_value(value.destinations)?;
+        let destinations: Vec<String> =
+            config::meta::alerts::alert::deserialize_destinations(value.destinations)?;
         let context_attributes: Option<HashMap<String, String>> = value
             .context_attributes
             .map(serde_json::from_value)
@@ -88,6 +90,10 @@ impl TryFrom<alerts::Model> for MetaAlert {
             .query_multi_time_range
             .map(serde_json::from_value)
             .transpose()?;
+        let trigger_baseline: Option<intermediate::TriggerBaseline> = value
+            .trigger_baseline
+            .map(serde_json::from_value)
+            .transpose()?;
 
         // Transform the Unix timestamp into a date time that will always use
         // the UTC timezone.
@@ -112,6 +118,8 @@ impl TryFrom<alerts::Model> for MetaAlert {
         alert.owner = value.owner;
         alert.last_edited_by = value.last_edited_by;
         alert.updated_at = updated_at_utc;
+        alert.silence_until = value.silence_until;
+        alert.emit_state_events = value.emit_state_events;
         alert.query_condition = MetaQueryCondition {
             query_type: query_type.into(),
             conditions: query_conditions.map(|cs| cs.into_iter().map(|c| c.into()).collect()),
@@ -136,6 +144,7 @@ impl TryFrom<alerts::Model> for MetaAlert {
             silence: value.trigger_silence_seconds / 60,
             timezone: value.trigger_frequency_cron_timezone,
             tolerance_in_secs: value.trigger_tolerance_seconds,
+            baseline: trigger_baseline.map(|b| b.into()),
         };
         alert.set_last_satisfied_at(value.last_satisfied_at);
         alert.set_last_triggered_at(value.last_triggered_at);
@@ -234,6 +243,8 @@ pub async fn put<C: TransactionTrait>(
             let stream_type = intermediate::StreamType::from(alert.stream_type).to_string();
             let stream_name = alert.stream_name.clone();
             let alert_name = alert.name.clone();
+            delete_trashed_by_name(&txn, org_id, alert.stream_type, &stream_name, &alert_name)
+                .await?;
             let mut alert_am = alerts::ActiveModel {
                 // The following fields can only be set on creation.
                 id: Set(id),
@@ -288,6 +299,15 @@ pub async fn create<C: TransactionTrait>(
     } else {
         svix_ksuid::Ksuid::new(None, None).to_string()
     };
+    delete_trashed_by_name(
+        &txn,
+        org_id,
+        alert.stream_type,
+        &alert.stream_name,
+        &alert.name,
+    )
+    .await?;
+
     let stream_type = intermediate::StreamType::from(alert.stream_type).to_string();
     let mut alert_am = alerts::ActiveModel {
         id: Set(id),
@@ -362,7 +382,7 @@ pub async fn update<C: TransactionTrait + ConnectionTrait>(
     Ok(alert)
 }
 
-/// Deletes an alert by its ID.
+/// Permanently deletes an alert by its ID, whether or not it is in the trash.
 pub async fn delete_by_id<C: ConnectionTrait>(
     conn: &C,
     org_id: &str,
@@ -377,6 +397,26 @@ pub async fn delete_by_id<C: ConnectionTrait>(
     Ok(())
 }
 
+/// Permanently deletes a trashed alert if it was moved to the trash before
+/// `deleted_before` (unix timestamp in microseconds). Returns `false` if no
+/// such alert was found, e.g. because it was restored in the meantime.
+pub async fn delete_trashed_by_id<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+    deleted_before: i64,
+) -> Result<bool, errors::Error> {
+    let _lock = super::get_lock().await;
+    let res = alerts::Entity::delete_many()
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::Id.eq(alert_id.to_string()))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .filter(alerts::Column::DeletedAt.lt(deleted_before))
+        .exec(conn)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
 /// Deletes an alert by its name.
 pub async fn delete_by_name<C: ConnectionTrait>(
     conn: &C,
@@ -405,6 +445,184 @@ pub async fn delete_by_name<C: ConnectionTrait>(
     Ok(())
 }
 
+/// Moves an alert to the trash by marking it as deleted at `deleted_at` (unix
+/// timestamp in microseconds). Trashed alerts are excluded from all other
+/// lookups until they are restored. Returns the trashed alert, or `None` if the
+/// alert does not exist.
+pub async fn soft_delete_by_id<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+    deleted_by: &str,
+    deleted_at: i64,
+) -> Result<Option<MetaAlert>, errors::Error> {
+    let _lock = super::get_lock().await;
+    let Some((_folder_m, alert_m)) = get_model_by_id(conn, org_id, alert_id).await? else {
+        return Ok(None);
+    };
+
+    let mut alert_am: alerts::ActiveModel = alert_m.into();
+    alert_am.deleted_at = Set(Some(deleted_at));
+    alert_am.deleted_by = Set(Some(deleted_by.to_owned()));
+    let alert_m: alerts::Model = alert_am.update(conn).await?.try_into_model()?;
+    Ok(Some(alert_m.try_into()?))
+}
+
+/// Restores a trashed alert. Returns the restored alert and its parent folder,
+/// or `None` if there is no trashed alert with the given ID.
+pub async fn restore_by_id<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+) -> Result<Option<(MetaFolder, MetaAlert)>, errors::Error> {
+    let _lock = super::get_lock().await;
+    let maybe_f_a = alerts::Entity::find_by_id(alert_id.to_string())
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .find_also_related(folders::Entity)
+        .one(conn)
+        .await?
+        .and_then(|(a, maybe_f)| maybe_f.map(|f| (f, a)));
+    let Some((folder_m, alert_m)) = maybe_f_a else {
+        return Ok(None);
+    };
+
+    let mut alert_am: alerts::ActiveModel = alert_m.into();
+    alert_am.deleted_at = Set(None);
+    alert_am.deleted_by = Set(None);
+    let alert_m: alerts::Model = alert_am.update(conn).await?.try_into_model()?;
+    Ok(Some((folder_m.into(), alert_m.try_into()?)))
+}
+
+/// Sets the time until which the notifications of an alert are suppressed,
+/// `None` removes the silence. Returns `false` if the alert does not exist.
+pub async fn set_silence_until<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+    silence_until: Option<i64>,
+) -> Result<bool, errors::Error> {
+    let _lock = super::get_lock().await;
+    let res = alerts::Entity::update_many()
+        .col_expr(alerts::Column::SilenceUntil, Expr::value(silence_until))
+        .filter(alerts::Column::Id.eq(alert_id.to_string()))
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::DeletedAt.is_null())
+        .exec(conn)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+/// Returns `true` if the alert with the given name is in the trash.
+pub async fn is_trashed_by_name<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    stream_type: MetaStreamType,
+    stream_name: &str,
+    alert_name: &str,
+) -> Result<bool, errors::Error> {
+    let _lock = super::get_lock().await;
+    let stream_type_str = intermediate::StreamType::from(stream_type).to_string();
+    let count = alerts::Entity::find()
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::StreamType.eq(stream_type_str))
+        .filter(alerts::Column::StreamName.eq(stream_name))
+        .filter(alerts::Column::Name.eq(alert_name))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .count(conn)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Lists the alerts in the trash of the organization, most recently deleted
+/// first.
+pub async fn list_trashed<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+) -> Result<Vec<TrashItem>, errors::Error> {
+    let _lock = super::get_lock().await;
+    let items = alerts::Entity::find()
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .find_also_related(folders::Entity)
+        .order_by_desc(alerts::Column::DeletedAt)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|(a, maybe_f)| maybe_f.map(|f| trash_item(f, a)))
+        .collect();
+    Ok(items)
+}
+
+/// Lists the alerts that were moved to the trash before `deleted_before` (unix
+/// timestamp in microseconds).
+pub async fn list_trashed_before<C: ConnectionTrait>(
+    conn: &C,
+    deleted_before: i64,
+) -> Result<Vec<MetaAlert>, errors::Error> {
+    let _lock = super::get_lock().await;
+    let alerts = alerts::Entity::find()
+        .filter(alerts::Column::DeletedAt.lt(deleted_before))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(MetaAlert::try_from)
+        .collect::<Result<_, errors::Error>>()?;
+    Ok(alerts)
+}
+
+/// Lists the trashed alerts in a folder.
+pub async fn list_trashed_in_folder<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    folder_id: &str,
+) -> Result<Vec<MetaAlert>, errors::Error> {
+    let _lock = super::get_lock().await;
+    let Some(folder_m) =
+        super::folders::get_model(conn, org_id, folder_id, FolderType::Alerts).await?
+    else {
+        return Ok(vec![]);
+    };
+    let alerts = folder_m
+        .find_related(alerts::Entity)
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(MetaAlert::try_from)
+        .collect::<Result<_, errors::Error>>()?;
+    Ok(alerts)
+}
+
+/// Moves the trashed alerts of a folder into another folder so that the source
+/// folder can be deleted.
+pub async fn move_trashed<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    from_folder_id: &str,
+    to_folder_id: &str,
+) -> Result<(), errors::Error> {
+    let _lock = super::get_lock().await;
+    let Some(from_folder_m) =
+        super::folders::get_model(conn, org_id, from_folder_id, FolderType::Alerts).await?
+    else {
+        return Ok(());
+    };
+    let Some(to_folder_m) =
+        super::folders::get_model(conn, org_id, to_folder_id, FolderType::Alerts).await?
+    else {
+        return Err(errors::DbError::PutAlert(PutAlertError::FolderDoesNotExist).into());
+    };
+
+    alerts::Entity::update_many()
+        .col_expr(alerts::Column::FolderId, Expr::value(to_folder_m.id))
+        .filter(alerts::Column::FolderId.eq(from_folder_m.id))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
 /// Lists alerts.
 pub async fn list<C: ConnectionTrait>(
     conn: &C,
@@ -442,6 +660,7 @@ async fn get_model_by_id<C: ConnectionTrait>(
 ) -> Result<Option<(folders::Model, alerts::Model)>, sea_orm::DbErr> {
     let maybe_f_a = alerts::Entity::find_by_id(alert_id.to_string())
         .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::DeletedAt.is_null())
         .find_also_related(folders::Entity)
         .one(conn)
         .await?
@@ -473,12 +692,34 @@ async fn get_model_by_name<C: ConnectionTrait>(
         .filter(alerts::Column::StreamType.eq(stream_type_str))
         .filter(alerts::Column::StreamName.eq(stream_name))
         .filter(alerts::Column::Name.eq(alert_name))
+        .filter(alerts::Column::DeletedAt.is_null())
         .one(conn)
         .await?;
 
     Ok(Some((folder, maybe_alert)))
 }
 
+/// Permanently deletes the trashed alert with the given name, if any, so that
+/// a new alert can take its name.
+async fn delete_trashed_by_name<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    stream_type: MetaStreamType,
+    stream_name: &str,
+    alert_name: &str,
+) -> Result<(), sea_orm::DbErr> {
+    let stream_type_str = intermediate::StreamType::from(stream_type).to_string();
+    alerts::Entity::delete_many()
+        .filter(alerts::Column::Org.eq(org_id))
+        .filter(alerts::Column::StreamType.eq(stream_type_str))
+        .filter(alerts::Column::StreamName.eq(stream_name))
+        .filter(alerts::Column::Name.eq(alert_name))
+        .filter(alerts::Column::DeletedAt.is_not_null())
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
 /// Lists alert ORM models using the given parameters. Returns each alert and
 /// its parent folder.
 async fn list_models<C: ConnectionTrait>(
@@ -486,6 +727,7 @@ async fn list_models<C: ConnectionTrait>(
     params: ListAlertsParams,
 ) -> Result<Vec<(folders::Model, alerts::Model)>, sea_orm::DbErr> {
     let query = alerts::Entity::find()
+        .filter(alerts::Column::DeletedAt.is_null())
         .find_also_related(folders::Entity)
         .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Alerts)))
         .filter(folders::Column::Org.eq(params.org_id));
@@ -554,6 +796,7 @@ async fn list_all_models<C: ConnectionTrait>(
     conn: &C,
 ) -> Result<Vec<alerts::Model>, sea_orm::DbErr> {
     let alerts = alerts::Entity::find()
+        .filter(alerts::Column::DeletedAt.is_null())
         .all(conn)
         .await?
         .into_iter()
@@ -561,6 +804,20 @@ async fn list_all_models<C: ConnectionTrait>(
     Ok(alerts)
 }
 
+/// Converts a trashed alert ORM entity and its parent folder ORM entity into a
+/// [TrashItem].
+fn trash_item(folder: folders::Model, alert: alerts::Model) -> TrashItem {
+    TrashItem {
+        item_type: TrashItemType::Alert,
+        id: alert.id,
+        name: alert.name,
+        folder_id: folder.folder_id,
+        folder_name: folder.name,
+        deleted_at: alert.deleted_at.unwrap_or_default(),
+        deleted_by: alert.deleted_by,
+    }
+}
+
 /// Updates all mutable fields on the [alerts::ActiveModel].
 ///
 /// For some fields the values will be extracted from and transformed from the
@@ -644,9 +901,17 @@ fn update_mutable_fields(
         alert.trigger_condition.timezone.filter(|s| !s.is_empty());
     let trigger_silence_seconds = alert.trigger_condition.silence * 60;
     let trigger_tolerance_seconds = alert.trigger_condition.tolerance_in_secs;
+    let trigger_baseline = alert
+        .trigger_condition
+        .baseline
+        .map(intermediate::TriggerBaseline::from)
+        .map(serde_json::to_value)
+        .transpose()?;
     let owner = alert.owner.filter(|s| !s.is_empty());
     let last_edited_by = alert.last_edited_by.filter(|s| !s.is_empty());
     let updated_at: i64 = chrono::Utc::now().timestamp();
+    let silence_until = alert.silence_until;
+    let emit_state_events = alert.emit_state_events;
 
     alert_am.is_real_time = Set(is_real_time);
     alert_am.destinations = Set(destinations);
@@ -675,9 +940,16 @@ fn update_mutable_fields(
     alert_am.trigger_frequency_cron_timezone = Set(trigger_frequency_cron_timezone);
     alert_am.trigger_silence_seconds = Set(trigger_silence_seconds);
     alert_am.trigger_tolerance_seconds = Set(trigger_tolerance_seconds);
+    alert_am.trigger_baseline = Set(trigger_baseline);
     alert_am.owner = Set(owner);
     alert_am.last_edited_by = Set(last_edited_by);
     alert_am.updated_at = Set(Some(updated_at));
+    // the silence is only changed through `set_silence_until` or when the
+    // alert explicitly sets one, so that editing an alert keeps its silence
+    if silence_until.is_some() {
+        alert_am.silence_until = Set(silence_until);
+    }
+    alert_am.emit_state_events = Set(emit_state_events);
 
     Ok(())
 }
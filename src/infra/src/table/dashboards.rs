@@ -16,14 +16,16 @@
 use config::meta::{
     dashboards::{
         v1::Dashboard as DashboardV1, v2::Dashboard as DashboardV2, v3::Dashboard as DashboardV3,
-        v4::Dashboard as DashboardV4, v5::Dashboard as DashboardV5, Dashboard,
-        ListDashboardsParams,
+        v4::Dashboard as DashboardV4, v5::Dashboard as DashboardV5, v6::Dashboard as DashboardV6,
+        Dashboard, ListDashboardsParams, ListDashboardsSortBy,
     },
     folder::{Folder, FolderType},
+    trash::{TrashItem, TrashItemType},
 };
 use sea_orm::{
     prelude::Expr, sea_query::Func, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
-    IntoActiveModel, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TryIntoModel,
+    IntoActiveModel, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, SelectTwo, Set,
+    TryIntoModel,
 };
 use serde_json::Value as JsonValue;
 use svix_ksuid::KsuidLike;
@@ -43,7 +45,7 @@ impl TryFrom<dashboards::Model> for Dashboard {
 
     fn try_from(mut value: dashboards::Model) -> Result<Self, Self::Error> {
         if let Some(obj) = value.data.as_object_mut() {
-            // The domain model JSON deserialization logic for v1-v5 expects
+            // The domain model JSON deserialization logic for v1-v6 expects
             // some or all these fields to be present in the JSON even though we
             // store them in DB columns. Therefore we add these values back into
             // the JSON object so that deserializing the JSON can succeed.
@@ -85,6 +87,11 @@ impl TryFrom<dashboards::Model> for Dashboard {
                 let dash = inner.into();
                 Ok(dash)
             }
+            6 => {
+                let inner: DashboardV6 = serde_json::from_value(value.data)?;
+                let dash = inner.into();
+                Ok(dash)
+            }
             _ => Err(GetDashboardError::UnsupportedVersion(value.version).into()),
         }
     }
@@ -271,6 +278,8 @@ pub async fn put(
                     version: Set(version),
                     created_at: Set(created_at_unix),
                     updated_at: Set(updated_at),
+                    deleted_at: Set(None),
+                    deleted_by: Set(None),
                 };
                 let model: dashboards::Model = dash_am.insert(client).await?.try_into_model()?;
                 Ok(model)
@@ -281,12 +290,59 @@ pub async fn put(
     Ok(dash)
 }
 
-/// Deletes a dashboard with the given `folder_id` and `dashboard_id` surrogate
-/// keys.
+/// Permanently deletes a dashboard with the given `folder_id` and
+/// `dashboard_id` surrogate keys, whether or not it is in the trash.
 pub async fn delete_from_folder(
     org_id: &str,
     folder_id: &str,
     dashboard_id: &str,
+) -> Result<(), errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let Some(folder) = get_folder_model(client, org_id, folder_id).await? else {
+        return Ok(());
+    };
+
+    dashboards::Entity::delete_many()
+        .filter(dashboards::Column::FolderId.eq(folder.id))
+        .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .exec(client)
+        .await?;
+    Ok(())
+}
+
+/// Permanently deletes a trashed dashboard if it was moved to the trash before
+/// `deleted_before` (unix timestamp in microseconds). Returns `false` if no
+/// such dashboard was found, e.g. because it was restored in the meantime.
+pub async fn delete_trashed(
+    org_id: &str,
+    folder_id: &str,
+    dashboard_id: &str,
+    deleted_before: i64,
+) -> Result<bool, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let Some(folder) = get_folder_model(client, org_id, folder_id).await? else {
+        return Ok(false);
+    };
+
+    let res = dashboards::Entity::delete_many()
+        .filter(dashboards::Column::FolderId.eq(folder.id))
+        .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_not_null())
+        .filter(dashboards::Column::DeletedAt.lt(deleted_before))
+        .exec(client)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+/// Moves a dashboard to the trash by marking it as deleted at `deleted_at`
+/// (unix timestamp in microseconds). Trashed dashboards are excluded from all
+/// other lookups until they are restored.
+pub async fn soft_delete(
+    org_id: &str,
+    folder_id: &str,
+    dashboard_id: &str,
+    deleted_by: &str,
+    deleted_at: i64,
 ) -> Result<(), errors::Error> {
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
     let model = get_model_from_folder(client, org_id, folder_id, dashboard_id)
@@ -294,30 +350,155 @@ pub async fn delete_from_folder(
         .and_then(|(_folder, maybe_dash)| maybe_dash);
 
     if let Some(model) = model {
-        let _ = model.delete(client).await?;
+        let mut dash_am = model.into_active_model();
+        dash_am.deleted_at = Set(Some(deleted_at));
+        dash_am.deleted_by = Set(Some(deleted_by.to_owned()));
+        dash_am.update(client).await?;
     }
 
     Ok(())
 }
 
+/// Restores a trashed dashboard. Returns the restored dashboard and its parent
+/// folder, or `None` if there is no trashed dashboard with the given ID.
+pub async fn restore(
+    org_id: &str,
+    dashboard_id: &str,
+) -> Result<Option<(Folder, Dashboard)>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let Some((folder_m, dash_m)) = get_trashed_model_by_id(client, org_id, dashboard_id).await?
+    else {
+        return Ok(None);
+    };
+
+    let mut dash_am = dash_m.into_active_model();
+    dash_am.deleted_at = Set(None);
+    dash_am.deleted_by = Set(None);
+    let dash_m: dashboards::Model = dash_am.update(client).await?.try_into_model()?;
+    Ok(Some((folder_m.into(), dash_m.try_into()?)))
+}
+
+/// Lists the dashboards in the trash of the organization, most recently
+/// deleted first.
+pub async fn list_trashed(org_id: &str) -> Result<Vec<TrashItem>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let items = dashboards::Entity::find()
+        .filter(dashboards::Column::DeletedAt.is_not_null())
+        .find_also_related(folders::Entity)
+        .filter(folders::Column::Org.eq(org_id))
+        .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Dashboards)))
+        .order_by_desc(dashboards::Column::DeletedAt)
+        .all(client)
+        .await?
+        .into_iter()
+        .filter_map(|(d, maybe_f)| maybe_f.map(|f| trash_item(f, d)))
+        .collect();
+    Ok(items)
+}
+
+/// Lists the organization ID, folder ID and dashboard ID of every dashboard
+/// that was moved to the trash before `deleted_before` (unix timestamp in
+/// microseconds).
+pub async fn list_trashed_before(
+    deleted_before: i64,
+) -> Result<Vec<(String, String, String)>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let items = dashboards::Entity::find()
+        .filter(dashboards::Column::DeletedAt.lt(deleted_before))
+        .find_also_related(folders::Entity)
+        .all(client)
+        .await?
+        .into_iter()
+        .filter_map(|(d, maybe_f)| maybe_f.map(|f| (f.org, f.folder_id, d.dashboard_id)))
+        .collect();
+    Ok(items)
+}
+
+/// Moves the trashed dashboards of a folder into another folder so that the
+/// source folder can be deleted. Returns the IDs of the moved dashboards.
+pub async fn move_trashed(
+    org_id: &str,
+    from_folder_id: &str,
+    to_folder_id: &str,
+) -> Result<Vec<String>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let Some(from_folder) = get_folder_model(client, org_id, from_folder_id).await? else {
+        return Ok(vec![]);
+    };
+    let to_folder = get_folder_model(client, org_id, to_folder_id)
+        .await?
+        .ok_or(errors::PutDashboardError::FolderDoesNotExist)?;
+
+    let trashed = from_folder
+        .find_related(dashboards::Entity)
+        .filter(dashboards::Column::DeletedAt.is_not_null())
+        .all(client)
+        .await?;
+    let mut ids = Vec::with_capacity(trashed.len());
+    for model in trashed {
+        ids.push(model.dashboard_id.clone());
+        let mut dash_am = model.into_active_model();
+        dash_am.folder_id = Set(to_folder.id.clone());
+        dash_am.update(client).await?;
+    }
+    Ok(ids)
+}
+
+/// Lists the IDs of the trashed dashboards in a folder.
+pub async fn list_trashed_in_folder(
+    org_id: &str,
+    folder_id: &str,
+) -> Result<Vec<String>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let Some(folder) = get_folder_model(client, org_id, folder_id).await? else {
+        return Ok(vec![]);
+    };
+    let ids = folder
+        .find_related(dashboards::Entity)
+        .filter(dashboards::Column::DeletedAt.is_not_null())
+        .all(client)
+        .await?
+        .into_iter()
+        .map(|d| d.dashboard_id)
+        .collect();
+    Ok(ids)
+}
+
 /// Deletes all dashboards.
 pub async fn delete_all() -> Result<(), errors::Error> {
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
 
-    let dashboards = list_all().await?;
-    let ids: Vec<_> = dashboards
-        .iter()
-        .map(|(_, d)| d.dashboard_id().unwrap())
+    // Include trashed dashboards whose distinct values have not been purged
+    // yet.
+    let ids: Vec<_> = dashboards::Entity::find()
+        .all(client)
+        .await?
+        .into_iter()
+        .map(|d| d.dashboard_id)
         .collect();
     // remove all distinct values
     for id in ids {
-        distinct_values::batch_remove(OriginType::Dashboard, id).await?;
+        distinct_values::batch_remove(OriginType::Dashboard, &id).await?;
     }
 
     dashboards::Entity::delete_many().exec(client).await?;
     Ok(())
 }
 
+/// Tries to get a dashboards folder ORM entity.
+async fn get_folder_model(
+    db: &DatabaseConnection,
+    org_id: &str,
+    folder_id: &str,
+) -> Result<Option<folders::Model>, sea_orm::DbErr> {
+    folders::Entity::find()
+        .filter(folders::Column::Org.eq(org_id))
+        .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Dashboards)))
+        .filter(folders::Column::FolderId.eq(folder_id))
+        .one(db)
+        .await
+}
+
 /// Tries to get a dashboard ORM entity and its parent folder ORM entity.
 async fn get_model_from_folder(
     db: &DatabaseConnection,
@@ -325,18 +506,14 @@ async fn get_model_from_folder(
     folder_id: &str,
     dashboard_id: &str,
 ) -> Result<Option<(folders::Model, Option<dashboards::Model>)>, sea_orm::DbErr> {
-    let select_folders = folders::Entity::find()
-        .filter(folders::Column::Org.eq(org_id))
-        .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Dashboards)))
-        .filter(folders::Column::FolderId.eq(folder_id));
-
-    let Some(folder) = select_folders.one(db).await? else {
+    let Some(folder) = get_folder_model(db, org_id, folder_id).await? else {
         return Ok(None);
     };
 
     let maybe_dashboard = folder
         .find_related(dashboards::Entity)
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(db)
         .await?;
 
@@ -352,6 +529,7 @@ async fn get_model_by_id(
 ) -> Result<Option<(folders::Model, dashboards::Model)>, sea_orm::DbErr> {
     let f_and_d = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .find_also_related(folders::Entity)
         .filter(folders::Column::Org.eq(org_id))
         .one(db)
@@ -360,15 +538,32 @@ async fn get_model_by_id(
     Ok(f_and_d)
 }
 
-/// Lists dashboard ORM models using the given parameters. Returns each
-/// dashboard and its parent folder.
-async fn list_models(
+/// Tries to get a trashed dashboard ORM entity and its parent folder ORM
+/// entity by the dashboard ID.
+async fn get_trashed_model_by_id(
     db: &DatabaseConnection,
-    params: ListDashboardsParams,
-) -> Result<Vec<(folders::Model, dashboards::Model)>, sea_orm::DbErr> {
+    org_id: &str,
+    dashboard_id: &str,
+) -> Result<Option<(folders::Model, dashboards::Model)>, sea_orm::DbErr> {
+    let f_and_d = dashboards::Entity::find()
+        .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_not_null())
+        .find_also_related(folders::Entity)
+        .filter(folders::Column::Org.eq(org_id))
+        .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Dashboards)))
+        .one(db)
+        .await?
+        .and_then(|(d, maybe_f)| maybe_f.map(|f| (f, d)));
+    Ok(f_and_d)
+}
+
+/// Builds the query for the dashboards and their parent folders matching the
+/// filters of the parameters.
+fn list_query(params: &ListDashboardsParams) -> SelectTwo<dashboards::Entity, folders::Entity> {
     let query = dashboards::Entity::find()
+        .filter(dashboards::Column::DeletedAt.is_null())
         .find_also_related(folders::Entity)
-        .filter(folders::Column::Org.eq(params.org_id))
+        .filter(folders::Column::Org.eq(&params.org_id))
         .filter(folders::Column::Type.eq::<i16>(folder_type_into_i16(FolderType::Dashboards)));
 
     // Apply the optional folder_id filter.
@@ -379,20 +574,34 @@ async fn list_models(
     };
 
     // Apply the optional title substring filter.
-    let title_pat = params
-        .title_pat
-        .and_then(|p| if p.is_empty() { None } else { Some(p) });
-    let query = if let Some(title_pat) = title_pat {
+    let title_pat = params.title_pat.as_ref().filter(|p| !p.is_empty());
+    if let Some(title_pat) = title_pat {
         let pattern = format!("%{}%", title_pat.to_lowercase());
         query.filter(Expr::expr(Func::lower(Expr::col(dashboards::Column::Title))).like(pattern))
     } else {
         query
-    };
+    }
+}
 
-    // Apply ordering.
+/// Lists dashboard ORM models using the given parameters. Returns each
+/// dashboard and its parent folder.
+async fn list_models(
+    db: &DatabaseConnection,
+    params: ListDashboardsParams,
+) -> Result<Vec<(folders::Model, dashboards::Model)>, sea_orm::DbErr> {
+    let query = list_query(&params);
+
+    // Apply ordering. Ties are broken by the folder name and then by the
+    // time-ordered primary key so that the order, and so pages, are stable.
+    let query = match params.sort_by {
+        ListDashboardsSortBy::TitleAsc => query.order_by_asc(dashboards::Column::Title),
+        ListDashboardsSortBy::TitleDesc => query.order_by_desc(dashboards::Column::Title),
+        ListDashboardsSortBy::UpdatedAtAsc => query.order_by_asc(dashboards::Column::UpdatedAt),
+        ListDashboardsSortBy::UpdatedAtDesc => query.order_by_desc(dashboards::Column::UpdatedAt),
+    };
     let query = query
-        .order_by_asc(dashboards::Column::Title)
-        .order_by_asc(folders::Column::Name);
+        .order_by_asc(folders::Column::Name)
+        .order_by_asc(dashboards::Column::Id);
 
     // Execute the query, either getting all results or a specific page of results.
     let results = if let Some((page_size, page_idx)) = params.page_size_and_idx {
@@ -424,7 +633,9 @@ async fn list_all_models(
     let query = query.order_by(dashboards::Column::Title, sea_orm::Order::Asc);
 
     // Left join on dashboards table.
-    let query = query.find_with_related(dashboards::Entity);
+    let query = query
+        .find_with_related(dashboards::Entity)
+        .filter(dashboards::Column::DeletedAt.is_null());
 
     let dashboards = query
         .all(db)
@@ -435,6 +646,20 @@ async fn list_all_models(
     Ok(dashboards)
 }
 
+/// Converts a trashed dashboard ORM entity and its parent folder ORM entity
+/// into a [TrashItem].
+fn trash_item(folder: folders::Model, dashboard: dashboards::Model) -> TrashItem {
+    TrashItem {
+        item_type: TrashItemType::Dashboard,
+        id: dashboard.dashboard_id,
+        name: dashboard.title,
+        folder_id: folder.folder_id,
+        folder_name: folder.name,
+        deleted_at: dashboard.deleted_at.unwrap_or_default(),
+        deleted_by: dashboard.deleted_by,
+    }
+}
+
 /// Converts the [Dashboard] into the JSON represention of the inner data that
 /// will be stored in the data column of the database.
 ///
@@ -468,6 +693,11 @@ fn inner_data_as_json(dashboard: Dashboard) -> Result<JsonValue, errors::Error>
             v5: Some(inner),
             ..
         } => serde_json::to_value(inner).map_err(errors::Error::SerdeJsonError),
+        Dashboard {
+            version: 6,
+            v6: Some(inner),
+            ..
+        } => serde_json::to_value(inner).map_err(errors::Error::SerdeJsonError),
         Dashboard { version: v, .. } => Err(errors::PutDashboardError::MissingInnerData(v).into()),
     }?;
 
@@ -492,6 +722,69 @@ mod tests {
 
     use super::*;
 
+    /// Returns the row [put] stores for `dashboard`.
+    fn model_for(dashboard: Dashboard) -> dashboards::Model {
+        dashboards::Model {
+            id: "2xHbHqmhXZNG1cYSaBWDrqQpYPe".to_owned(),
+            dashboard_id: dashboard.dashboard_id().unwrap().to_owned(),
+            folder_id: "2xHbHqmhXZNG1cYSaBWDrqQpYPf".to_owned(),
+            owner: dashboard.owner().unwrap().to_owned(),
+            role: dashboard.role().map(|r| r.to_owned()),
+            title: dashboard.title().unwrap().to_owned(),
+            description: dashboard.description().map(|d| d.to_owned()),
+            version: dashboard.version,
+            created_at: dashboard.created_at_deprecated().unwrap().timestamp(),
+            updated_at: dashboard.updated_at,
+            deleted_at: None,
+            deleted_by: None,
+            data: inner_data_as_json(dashboard).unwrap(),
+        }
+    }
+
+    #[test]
+    fn v6_dashboard_put_get_round_trip() {
+        let mut inner: DashboardV6 = serde_json::from_value(serde_json::json!({
+            "version": 6,
+            "dashboardId": "7263859211",
+            "title": "v6 dashboard",
+            "description": "round trip",
+            "role": "",
+            "owner": "root@example.com",
+            "created": "2025-01-02T03:04:05.000000+00:00",
+            "tabs": [{ "tabId": "default", "name": "Default", "panels": [] }],
+            "variables": { "list": [], "showDynamicFilters": true }
+        }))
+        .unwrap();
+        inner.updated_at = 1_735_787_045_000_000;
+        let dashboard: Dashboard = inner.into();
+        assert_eq!(dashboard.version, 6);
+
+        let stored: Dashboard = model_for(dashboard.clone()).try_into().unwrap();
+        assert_eq!(stored.version, 6);
+        assert_eq!(stored.hash, dashboard.hash);
+        assert_eq!(stored, dashboard);
+    }
+
+    #[test]
+    fn unsupported_dashboard_version() {
+        let mut model = model_for(
+            DashboardV6::from(
+                serde_json::from_value::<DashboardV5>(serde_json::json!({
+                    "version": 5,
+                    "dashboardId": "7263859211",
+                    "title": "v5 dashboard",
+                    "description": "",
+                    "owner": "root@example.com",
+                    "created": "2025-01-02T03:04:05.000000+00:00"
+                }))
+                .unwrap(),
+            )
+            .into(),
+        );
+        model.version = 7;
+        assert!(Dashboard::try_from(model).is_err());
+    }
+
     #[tokio::test]
     async fn list_models_psql() -> Result<(), DbErr> {
         let db = MockDatabase::new(DatabaseBackend::Postgres)
@@ -502,13 +795,14 @@ mod tests {
             folder_id: Some("folderId".to_owned()),
             title_pat: Some("tItLePat".to_owned()),
             page_size_and_idx: Some((100, 2)),
+            sort_by: ListDashboardsSortBy::TitleAsc,
         };
         list_models(&db, params).await?;
         assert_eq!(
             db.into_transaction_log(),
             vec![Transaction::from_sql_and_values(
                 DatabaseBackend::Postgres,
-                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "folders"."org" = $1 AND "folders"."type" = $2 AND "folders"."folder_id" = $3 AND LOWER("title") LIKE $4 ORDER BY "dashboards"."title" ASC, "folders"."name" ASC LIMIT $5 OFFSET $6"#,
+                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "dashboards"."deleted_at" AS "A_deleted_at", "dashboards"."deleted_by" AS "A_deleted_by", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "dashboards"."deleted_at" IS NULL AND "folders"."org" = $1 AND "folders"."type" = $2 AND "folders"."folder_id" = $3 AND LOWER("title") LIKE $4 ORDER BY "dashboards"."title" ASC, "folders"."name" ASC, "dashboards"."id" ASC LIMIT $5 OFFSET $6"#,
                 [
                     "orgId".into(),
                     0i16.into(),
@@ -532,13 +826,14 @@ mod tests {
             folder_id: Some("folderId".to_owned()),
             title_pat: Some("tItLePat".to_owned()),
             page_size_and_idx: Some((100, 2)),
+            sort_by: ListDashboardsSortBy::TitleAsc,
         };
         list_models(&db, params).await?;
         assert_eq!(
             db.into_transaction_log(),
             vec![Transaction::from_sql_and_values(
                 DatabaseBackend::MySql,
-                r#"SELECT `dashboards`.`id` AS `A_id`, `dashboards`.`dashboard_id` AS `A_dashboard_id`, `dashboards`.`folder_id` AS `A_folder_id`, `dashboards`.`owner` AS `A_owner`, `dashboards`.`role` AS `A_role`, `dashboards`.`title` AS `A_title`, `dashboards`.`description` AS `A_description`, `dashboards`.`data` AS `A_data`, `dashboards`.`version` AS `A_version`, `dashboards`.`created_at` AS `A_created_at`, `dashboards`.`updated_at` AS `A_updated_at`, `folders`.`id` AS `B_id`, `folders`.`org` AS `B_org`, `folders`.`folder_id` AS `B_folder_id`, `folders`.`name` AS `B_name`, `folders`.`description` AS `B_description`, `folders`.`type` AS `B_type` FROM `dashboards` LEFT JOIN `folders` ON `dashboards`.`folder_id` = `folders`.`id` WHERE `folders`.`org` = ? AND `folders`.`type` = ? AND `folders`.`folder_id` = ? AND LOWER(`title`) LIKE ? ORDER BY `dashboards`.`title` ASC, `folders`.`name` ASC LIMIT ? OFFSET ?"#,
+                r#"SELECT `dashboards`.`id` AS `A_id`, `dashboards`.`dashboard_id` AS `A_dashboard_id`, `dashboards`.`folder_id` AS `A_folder_id`, `dashboards`.`owner` AS `A_owner`, `dashboards`.`role` AS `A_role`, `dashboards`.`title` AS `A_title`, `dashboards`.`description` AS `A_description`, `dashboards`.`data` AS `A_data`, `dashboards`.`version` AS `A_version`, `dashboards`.`created_at` AS `A_created_at`, `dashboards`.`updated_at` AS `A_updated_at`, `dashboards`.`deleted_at` AS `A_deleted_at`, `dashboards`.`deleted_by` AS `A_deleted_by`, `folders`.`id` AS `B_id`, `folders`.`org` AS `B_org`, `folders`.`folder_id` AS `B_folder_id`, `folders`.`name` AS `B_name`, `folders`.`description` AS `B_description`, `folders`.`type` AS `B_type` FROM `dashboards` LEFT JOIN `folders` ON `dashboards`.`folder_id` = `folders`.`id` WHERE `dashboards`.`deleted_at` IS NULL AND `folders`.`org` = ? AND `folders`.`type` = ? AND `folders`.`folder_id` = ? AND LOWER(`title`) LIKE ? ORDER BY `dashboards`.`title` ASC, `folders`.`name` ASC, `dashboards`.`id` ASC LIMIT ? OFFSET ?"#,
                 [
                     "orgId".into(),
                     0i16.into(),
@@ -562,13 +857,14 @@ mod tests {
             folder_id: Some("folderId".to_owned()),
             title_pat: Some("tItLePat".to_owned()),
             page_size_and_idx: Some((100, 2)),
+            sort_by: ListDashboardsSortBy::TitleAsc,
         };
         list_models(&db, params).await?;
         assert_eq!(
             db.into_transaction_log(),
             vec![Transaction::from_sql_and_values(
                 DatabaseBackend::Sqlite,
-                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "folders"."org" = ? AND "folders"."type" = ? AND "folders"."folder_id" = ? AND LOWER("title") LIKE ? ORDER BY "dashboards"."title" ASC, "folders"."name" ASC LIMIT ? OFFSET ?"#,
+                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "dashboards"."deleted_at" AS "A_deleted_at", "dashboards"."deleted_by" AS "A_deleted_by", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "dashboards"."deleted_at" IS NULL AND "folders"."org" = ? AND "folders"."type" = ? AND "folders"."folder_id" = ? AND LOWER("title") LIKE ? ORDER BY "dashboards"."title" ASC, "folders"."name" ASC, "dashboards"."id" ASC LIMIT ? OFFSET ?"#,
                 [
                     "orgId".into(),
                     0i16.into(),
@@ -581,4 +877,51 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn list_models_sorted_by_updated_at_first_page() -> Result<(), DbErr> {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<dashboards::Model>::new()])
+            .into_connection();
+        let params = ListDashboardsParams::new("orgId")
+            .with_folder_id("folderId")
+            .paginate(10, 0)
+            .sort_by(ListDashboardsSortBy::UpdatedAtDesc);
+        list_models(&db, params).await?;
+        assert_eq!(
+            db.into_transaction_log(),
+            vec![Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "dashboards"."deleted_at" AS "A_deleted_at", "dashboards"."deleted_by" AS "A_deleted_by", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "dashboards"."deleted_at" IS NULL AND "folders"."org" = $1 AND "folders"."type" = $2 AND "folders"."folder_id" = $3 ORDER BY "dashboards"."updated_at" DESC, "folders"."name" ASC, "dashboards"."id" ASC LIMIT $4 OFFSET $5"#,
+                [
+                    "orgId".into(),
+                    0i16.into(),
+                    "folderId".into(),
+                    10u64.into(),
+                    0u64.into()
+                ]
+            )]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_models_sorted_by_title_desc_unpaginated() -> Result<(), DbErr> {
+        let db = MockDatabase::new(DatabaseBackend::Sqlite)
+            .append_query_results([Vec::<dashboards::Model>::new()])
+            .into_connection();
+        let params = ListDashboardsParams::new("orgId")
+            .where_title_contains("")
+            .sort_by(ListDashboardsSortBy::TitleDesc);
+        list_models(&db, params).await?;
+        assert_eq!(
+            db.into_transaction_log(),
+            vec![Transaction::from_sql_and_values(
+                DatabaseBackend::Sqlite,
+                r#"SELECT "dashboards"."id" AS "A_id", "dashboards"."dashboard_id" AS "A_dashboard_id", "dashboards"."folder_id" AS "A_folder_id", "dashboards"."owner" AS "A_owner", "dashboards"."role" AS "A_role", "dashboards"."title" AS "A_title", "dashboards"."description" AS "A_description", "dashboards"."data" AS "A_data", "dashboards"."version" AS "A_version", "dashboards"."created_at" AS "A_created_at", "dashboards"."updated_at" AS "A_updated_at", "dashboards"."deleted_at" AS "A_deleted_at", "dashboards"."deleted_by" AS "A_deleted_by", "folders"."id" AS "B_id", "folders"."org" AS "B_org", "folders"."folder_id" AS "B_folder_id", "folders"."name" AS "B_name", "folders"."description" AS "B_description", "folders"."type" AS "B_type" FROM "dashboards" LEFT JOIN "folders" ON "dashboards"."folder_id" = "folders"."id" WHERE "dashboards"."deleted_at" IS NULL AND "folders"."org" = ? AND "folders"."type" = ? ORDER BY "dashboards"."title" DESC, "folders"."name" ASC, "dashboards"."id" ASC"#,
+                ["orgId".into(), 0i16.into()]
+            )]
+        );
+        Ok(())
+    }
 }
@@ -39,9 +39,14 @@ pub struct Model {
     pub trigger_frequency_cron_timezone: Option<String>,
     pub trigger_silence_seconds: i64,
     pub trigger_tolerance_seconds: Option<i64>,
+    pub trigger_baseline: Option<Json>,
     pub owner: Option<String>,
     pub last_edited_by: Option<String>,
     pub updated_at: Option<i64>,
+    pub silence_until: Option<i64>,
+    pub emit_state_events: bool,
+    pub deleted_at: Option<i64>,
+    pub deleted_by: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
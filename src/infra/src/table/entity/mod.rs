@@ -9,6 +9,7 @@ pub mod dashboards;
 pub mod destinations;
 pub mod distinct_value_fields;
 pub mod folders;
+pub mod org_invites;
 pub mod search_job_partitions;
 pub mod search_job_results;
 pub mod search_jobs;
@@ -4,7 +4,8 @@ pub use super::{
     action_scripts::Entity as ActionScripts, alerts::Entity as Alerts,
     cipher_keys::Entity as CipherKeys, dashboards::Entity as Dashboards,
     destinations::Entity as Destinations, distinct_value_fields::Entity as DistinctValueFields,
-    folders::Entity as Folders, search_job_partitions::Entity as SearchJobPartitions,
+    folders::Entity as Folders, org_invites::Entity as OrgInvites,
+    search_job_partitions::Entity as SearchJobPartitions,
     search_job_results::Entity as SearchJobResults, search_jobs::Entity as SearchJobs,
     search_queue::Entity as SearchQueue, templates::Entity as Templates,
     timed_annotation_panels::Entity as TimedAnnotationPanels,
@@ -15,15 +15,19 @@
 
 use config::meta::folder::{Folder, FolderType};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
-    IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, Set, TryIntoModel,
+    prelude::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+    TryIntoModel,
 };
 use svix_ksuid::{Ksuid, KsuidLike};
 
-use super::entity::folders::{ActiveModel, Column, Entity, Model};
+use super::entity::{
+    alerts, dashboards,
+    folders::{ActiveModel, Column, Entity, Model},
+};
 use crate::{
     db::{connect_to_orm, ORM_CLIENT},
-    errors::{self, FromStrError},
+    errors::{self, FromStrError, PutAlertError, PutDashboardError},
 };
 
 impl From<Model> for Folder {
@@ -158,6 +162,109 @@ pub async fn delete(
     Ok(())
 }
 
+/// Deletes a folder together with everything it contains in a single
+/// transaction.
+///
+/// The live dashboards or alerts of the folder are moved to the trash, and all
+/// trashed items of the folder are then moved into the `trash_folder_id`
+/// folder so that they outlive the deleted folder and can still be restored.
+///
+/// Nothing is deleted and `false` is returned if the folder has live
+/// dashboards or alerts other than `expected_ids`, their dashboard IDs or alert
+/// IDs, which were added since the caller listed them.
+pub async fn delete_with_contents(
+    org_id: &str,
+    folder_id: &str,
+    folder_type: FolderType,
+    trash_folder_id: &str,
+    deleted_by: &str,
+    deleted_at: i64,
+    expected_ids: &[String],
+) -> Result<bool, errors::Error> {
+    let _lock = super::get_lock().await;
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let txn = client.begin().await?;
+
+    let Some(folder_m) = get_model(&txn, org_id, folder_id, folder_type).await? else {
+        return Ok(true);
+    };
+    let live_ids: Vec<String> = match folder_type {
+        FolderType::Dashboards => {
+            dashboards::Entity::find()
+                .select_only()
+                .column(dashboards::Column::DashboardId)
+                .filter(dashboards::Column::FolderId.eq(folder_m.id.as_str()))
+                .filter(dashboards::Column::DeletedAt.is_null())
+                .into_tuple()
+                .all(&txn)
+                .await?
+        }
+        FolderType::Alerts => {
+            alerts::Entity::find()
+                .select_only()
+                .column(alerts::Column::Id)
+                .filter(alerts::Column::FolderId.eq(folder_m.id.as_str()))
+                .filter(alerts::Column::DeletedAt.is_null())
+                .into_tuple()
+                .all(&txn)
+                .await?
+        }
+    };
+    if live_ids.iter().any(|id| !expected_ids.contains(id)) {
+        txn.rollback().await?;
+        return Ok(false);
+    }
+    let Some(trash_folder_m) = get_model(&txn, org_id, trash_folder_id, folder_type).await? else {
+        return Err(match folder_type {
+            FolderType::Dashboards => PutDashboardError::FolderDoesNotExist.into(),
+            FolderType::Alerts => {
+                errors::DbError::PutAlert(PutAlertError::FolderDoesNotExist).into()
+            }
+        });
+    };
+
+    match folder_type {
+        FolderType::Dashboards => {
+            dashboards::Entity::update_many()
+                .col_expr(dashboards::Column::DeletedAt, Expr::value(deleted_at))
+                .col_expr(dashboards::Column::DeletedBy, Expr::value(deleted_by))
+                .filter(dashboards::Column::FolderId.eq(folder_m.id.as_str()))
+                .filter(dashboards::Column::DeletedAt.is_null())
+                .exec(&txn)
+                .await?;
+            dashboards::Entity::update_many()
+                .col_expr(
+                    dashboards::Column::FolderId,
+                    Expr::value(trash_folder_m.id.clone()),
+                )
+                .filter(dashboards::Column::FolderId.eq(folder_m.id.as_str()))
+                .exec(&txn)
+                .await?;
+        }
+        FolderType::Alerts => {
+            alerts::Entity::update_many()
+                .col_expr(alerts::Column::DeletedAt, Expr::value(deleted_at))
+                .col_expr(alerts::Column::DeletedBy, Expr::value(deleted_by))
+                .filter(alerts::Column::FolderId.eq(folder_m.id.as_str()))
+                .filter(alerts::Column::DeletedAt.is_null())
+                .exec(&txn)
+                .await?;
+            alerts::Entity::update_many()
+                .col_expr(
+                    alerts::Column::FolderId,
+                    Expr::value(trash_folder_m.id.clone()),
+                )
+                .filter(alerts::Column::FolderId.eq(folder_m.id.as_str()))
+                .exec(&txn)
+                .await?;
+        }
+    }
+
+    folder_m.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(true)
+}
+
 /// Gets a folder ORM entity by its `folder_id`.
 pub(crate) async fn get_model<C: ConnectionTrait>(
     db: &C,
@@ -0,0 +1,116 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Converts alerts whose destinations were stored as a single destination name
+//! into a list containing that one destination.
+
+use sea_orm::{ActiveModelTrait, EntityTrait, PaginatorTrait, QueryOrder, Set, TransactionTrait};
+use sea_orm_migration::prelude::*;
+use serde_json::Value;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let txn = manager.get_connection().begin().await?;
+
+        // Migrate pages of 100 records at a time to avoid loading too many
+        // records into memory.
+        let mut alert_pages = alerts::Entity::find()
+            .order_by_asc(alerts::Column::Id)
+            .paginate(&txn, 100);
+
+        while let Some(alerts) = alert_pages.fetch_and_next().await? {
+            for alert in alerts {
+                let Some(destinations) = single_destination_to_array(&alert.destinations) else {
+                    continue;
+                };
+                alerts::ActiveModel {
+                    id: Set(alert.id),
+                    destinations: Set(destinations),
+                }
+                .update(&txn)
+                .await?;
+            }
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // A list of destinations is the only supported format, so there is
+        // nothing to revert.
+        Ok(())
+    }
+}
+
+/// Returns the list representation of the destinations if they are stored as
+/// a single destination name, or `None` if no conversion is needed.
+fn single_destination_to_array(destinations: &Value) -> Option<Value> {
+    match destinations {
+        Value::String(dest) if dest.is_empty() => Some(Value::Array(vec![])),
+        Value::String(dest) => Some(Value::Array(vec![Value::String(dest.to_owned())])),
+        Value::Null => Some(Value::Array(vec![])),
+        _ => None,
+    }
+}
+
+// The schemas of tables might change after subsequent migrations. Therefore
+// this migration only references ORM models in private submodules that should
+// remain unchanged rather than ORM models in the `entity` module that will be
+// updated to reflect the latest changes to table schemas.
+
+/// Representation of the alerts table at the time this migration executes,
+/// limited to the columns that this migration reads and writes.
+mod alerts {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+    #[sea_orm(table_name = "alerts")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: String,
+        pub destinations: Json,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_single_destination_to_array() {
+        assert_eq!(
+            single_destination_to_array(&json!("slack")),
+            Some(json!(["slack"]))
+        );
+        assert_eq!(single_destination_to_array(&json!("")), Some(json!([])));
+        assert_eq!(single_destination_to_array(&Value::Null), Some(json!([])));
+        assert_eq!(
+            single_destination_to_array(&json!(["slack", "email"])),
+            None
+        );
+    }
+}
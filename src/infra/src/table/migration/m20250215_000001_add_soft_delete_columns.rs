@@ -0,0 +1,87 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adds the deleted_at and deleted_by columns used to move dashboards and
+//! alerts to the trash.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_soft_delete_columns(manager, SoftDelete::Dashboards).await?;
+        add_soft_delete_columns(manager, SoftDelete::Alerts).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_soft_delete_columns(manager, SoftDelete::Dashboards).await?;
+        drop_soft_delete_columns(manager, SoftDelete::Alerts).await?;
+        Ok(())
+    }
+}
+
+// Adds the nullable deleted_at and deleted_by columns to the table.
+async fn add_soft_delete_columns(
+    manager: &SchemaManager<'_>,
+    table: SoftDelete,
+) -> Result<(), DbErr> {
+    let is_mysql = matches!(manager.get_database_backend(), sea_orm::DbBackend::MySql);
+    for mut column in [
+        ColumnDef::new(SoftDelete::DeletedAt)
+            .big_integer()
+            .null()
+            .to_owned(),
+        ColumnDef::new(SoftDelete::DeletedBy)
+            .string_len(256)
+            .null()
+            .to_owned(),
+    ] {
+        let mut stmt = Table::alter();
+        stmt.table(table);
+        if is_mysql {
+            stmt.add_column(&mut column);
+        } else {
+            stmt.add_column_if_not_exists(&mut column);
+        }
+        manager.alter_table(stmt).await?;
+    }
+    Ok(())
+}
+
+// Drops the deleted_at and deleted_by columns from the table.
+async fn drop_soft_delete_columns(
+    manager: &SchemaManager<'_>,
+    table: SoftDelete,
+) -> Result<(), DbErr> {
+    for column in [SoftDelete::DeletedAt, SoftDelete::DeletedBy] {
+        manager
+            .alter_table(Table::alter().table(table).drop_column(column).to_owned())
+            .await?;
+    }
+    Ok(())
+}
+
+/// Identifiers used in queries on the dashboards and alerts tables.
+#[derive(DeriveIden, Clone, Copy)]
+enum SoftDelete {
+    Dashboards,
+    Alerts,
+    DeletedAt,
+    DeletedBy,
+}
@@ -0,0 +1,107 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adds the org and last_accessed columns to the short_urls table so that org
+//! admins can list and manage their short URLs.
+//!
+//! The short_urls table is created from its entity at startup, so on new
+//! installations the columns already exist. Short URLs created before this
+//! migration have an empty org and are only removed by the retention GC.
+
+use sea_orm_migration::prelude::*;
+
+const SHORT_URLS_ORG_CREATED_TS_IDX: &str = "short_urls_org_created_ts_idx";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("short_urls", "org").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ShortUrls::Table)
+                        .add_column(
+                            ColumnDef::new(ShortUrls::Org)
+                                .string_len(100)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager.has_column("short_urls", "last_accessed").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ShortUrls::Table)
+                        .add_column(ColumnDef::new(ShortUrls::LastAccessed).big_integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        if !manager
+            .has_index("short_urls", SHORT_URLS_ORG_CREATED_TS_IDX)
+            .await?
+        {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(SHORT_URLS_ORG_CREATED_TS_IDX)
+                        .table(ShortUrls::Table)
+                        .col(ShortUrls::Org)
+                        .col(ShortUrls::CreatedTs)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(SHORT_URLS_ORG_CREATED_TS_IDX)
+                    .table(ShortUrls::Table)
+                    .to_owned(),
+            )
+            .await?;
+        // sqlite only supports one alter option per statement
+        for column in [ShortUrls::Org, ShortUrls::LastAccessed] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ShortUrls::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Identifiers used in queries on the short_urls table.
+#[derive(DeriveIden, Clone, Copy)]
+enum ShortUrls {
+    Table,
+    Org,
+    CreatedTs,
+    LastAccessed,
+}
@@ -0,0 +1,179 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ORG_INVITES_TOKEN_HASH_IDX: &str = "org_invites_token_hash_idx";
+const ORG_INVITES_ORG_IDX: &str = "org_invites_org_idx";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(create_table_stmt()).await?;
+        manager.create_index(create_token_hash_idx_stmt()).await?;
+        manager.create_index(create_org_idx_stmt()).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name(ORG_INVITES_ORG_IDX).to_owned())
+            .await?;
+        manager
+            .drop_index(Index::drop().name(ORG_INVITES_TOKEN_HASH_IDX).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(OrgInvites::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+fn create_table_stmt() -> TableCreateStatement {
+    Table::create()
+        .table(OrgInvites::Table)
+        .if_not_exists()
+        // The ID is 27-character human readable KSUID.
+        .col(
+            ColumnDef::new(OrgInvites::Id)
+                .char_len(27)
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(OrgInvites::Org).string_len(100).not_null())
+        .col(ColumnDef::new(OrgInvites::Email).string_len(256).not_null())
+        .col(ColumnDef::new(OrgInvites::Role).string_len(100).not_null())
+        // Hex encoded SHA-256 of the token, the token itself is never stored.
+        .col(ColumnDef::new(OrgInvites::TokenHash).char_len(64).not_null())
+        .col(ColumnDef::new(OrgInvites::CreatedBy).string_len(256).not_null())
+        .col(ColumnDef::new(OrgInvites::CreatedAt).big_integer().not_null())
+        .col(ColumnDef::new(OrgInvites::ExpiresAt).big_integer().not_null())
+        .col(ColumnDef::new(OrgInvites::AcceptedAt).big_integer().null())
+        .col(ColumnDef::new(OrgInvites::RevokedAt).big_integer().null())
+        .to_owned()
+}
+
+fn create_token_hash_idx_stmt() -> IndexCreateStatement {
+    sea_query::Index::create()
+        .if_not_exists()
+        .name(ORG_INVITES_TOKEN_HASH_IDX)
+        .table(OrgInvites::Table)
+        .col(OrgInvites::TokenHash)
+        .unique()
+        .to_owned()
+}
+
+fn create_org_idx_stmt() -> IndexCreateStatement {
+    sea_query::Index::create()
+        .if_not_exists()
+        .name(ORG_INVITES_ORG_IDX)
+        .table(OrgInvites::Table)
+        .col(OrgInvites::Org)
+        .col(OrgInvites::CreatedAt)
+        .to_owned()
+}
+
+#[derive(DeriveIden)]
+enum OrgInvites {
+    Table,
+    Id,
+    Org,
+    Email,
+    Role,
+    TokenHash,
+    CreatedBy,
+    CreatedAt,
+    ExpiresAt,
+    AcceptedAt,
+    RevokedAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use collapse::*;
+
+    use super::*;
+
+    #[test]
+    fn postgres() {
+        collapsed_eq!(
+            &create_table_stmt().to_string(PostgresQueryBuilder),
+            r#"CREATE TABLE IF NOT EXISTS "org_invites" ( 
+            "id" char(27) NOT NULL PRIMARY KEY, 
+            "org" varchar(100) NOT NULL, 
+            "email" varchar(256) NOT NULL, 
+            "role" varchar(100) NOT NULL, 
+            "token_hash" char(64) NOT NULL, 
+            "created_by" varchar(256) NOT NULL, 
+            "created_at" bigint NOT NULL, 
+            "expires_at" bigint NOT NULL, 
+            "accepted_at" bigint NULL, 
+            "revoked_at" bigint NULL 
+            )"#
+        );
+        assert_eq!(
+            &create_token_hash_idx_stmt().to_string(PostgresQueryBuilder),
+            r#"CREATE UNIQUE INDEX IF NOT EXISTS "org_invites_token_hash_idx" ON "org_invites" ("token_hash")"#
+        );
+    }
+
+    #[test]
+    fn mysql() {
+        collapsed_eq!(
+            &create_table_stmt().to_string(MysqlQueryBuilder),
+            r#"CREATE TABLE IF NOT EXISTS `org_invites` ( 
+            `id` char(27) NOT NULL PRIMARY KEY, 
+            `org` varchar(100) NOT NULL, 
+            `email` varchar(256) NOT NULL, 
+            `role` varchar(100) NOT NULL, 
+            `token_hash` char(64) NOT NULL, 
+            `created_by` varchar(256) NOT NULL, 
+            `created_at` bigint NOT NULL, 
+            `expires_at` bigint NOT NULL, 
+            `accepted_at` bigint NULL, 
+            `revoked_at` bigint NULL 
+            )"#
+        );
+        assert_eq!(
+            &create_token_hash_idx_stmt().to_string(MysqlQueryBuilder),
+            r#"CREATE UNIQUE INDEX `org_invites_token_hash_idx` ON `org_invites` (`token_hash`)"#
+        );
+    }
+
+    #[test]
+    fn sqlite() {
+        collapsed_eq!(
+            &create_table_stmt().to_string(SqliteQueryBuilder),
+            r#"CREATE TABLE IF NOT EXISTS "org_invites" ( 
+            "id" char(27) NOT NULL PRIMARY KEY, 
+            "org" varchar(100) NOT NULL, 
+            "email" varchar(256) NOT NULL, 
+            "role" varchar(100) NOT NULL, 
+            "token_hash" char(64) NOT NULL, 
+            "created_by" varchar(256) NOT NULL, 
+            "created_at" bigint NOT NULL, 
+            "expires_at" bigint NOT NULL, 
+            "accepted_at" bigint NULL, 
+            "revoked_at" bigint NULL 
+            )"#
+        );
+        assert_eq!(
+            &create_token_hash_idx_stmt().to_string(SqliteQueryBuilder),
+            r#"CREATE UNIQUE INDEX IF NOT EXISTS "org_invites_token_hash_idx" ON "org_invites" ("token_hash")"#
+        );
+    }
+}
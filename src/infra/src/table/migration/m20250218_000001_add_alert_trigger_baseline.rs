@@ -0,0 +1,73 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adds the alert's trigger_baseline column
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_trigger_baseline_column(manager).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alerts::Table)
+                    .drop_column(Alerts::TriggerBaseline)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+// Adds the nullable trigger_baseline JSON column.
+async fn add_trigger_baseline_column(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    if matches!(manager.get_database_backend(), sea_orm::DbBackend::MySql) {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alerts::Table)
+                    .add_column(ColumnDef::new(Alerts::TriggerBaseline).json().null())
+                    .to_owned(),
+            )
+            .await?;
+    } else {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alerts::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Alerts::TriggerBaseline).json().null())
+                    .to_owned(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Identifiers used in queries on the alerts table.
+#[derive(DeriveIden)]
+enum Alerts {
+    Table,
+    TriggerBaseline,
+}
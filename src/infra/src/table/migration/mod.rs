@@ -44,6 +44,13 @@ mod m20250125_133700_populate_destinations_table;
 mod m20250125_153005_delete_metas_destinations;
 mod m20250125_172300_delete_metas_templates;
 mod m20250213_000001_add_dashboard_updated_at;
+mod m20250214_000001_alerts_destinations_to_array;
+mod m20250214_000002_add_alert_silence_until;
+mod m20250215_000001_add_soft_delete_columns;
+mod m20250216_000001_add_short_urls_org_and_last_accessed;
+mod m20250217_000001_create_org_invites_table;
+mod m20250218_000001_add_alert_trigger_baseline;
+mod m20250219_000001_add_alert_emit_state_events;
 
 pub struct Migrator;
 
@@ -79,6 +86,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20250125_133700_populate_destinations_table::Migration),
             Box::new(m20250125_153005_delete_metas_destinations::Migration),
             Box::new(m20250213_000001_add_dashboard_updated_at::Migration),
+            Box::new(m20250214_000001_alerts_destinations_to_array::Migration),
+            Box::new(m20250214_000002_add_alert_silence_until::Migration),
+            Box::new(m20250215_000001_add_soft_delete_columns::Migration),
+            Box::new(m20250216_000001_add_short_urls_org_and_last_accessed::Migration),
+            Box::new(m20250217_000001_create_org_invites_table::Migration),
+            Box::new(m20250218_000001_add_alert_trigger_baseline::Migration),
+            Box::new(m20250219_000001_add_alert_emit_state_events::Migration),
         ]
     }
 }
@@ -0,0 +1,188 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use sea_orm::{
+    prelude::Expr, ColumnTrait, EntityTrait, Order, QueryFilter, QueryOrder, Set, SqlErr,
+};
+use svix_ksuid::{Ksuid, KsuidLike};
+
+use super::{entity::org_invites::*, get_lock};
+use crate::{
+    db::{connect_to_orm, ORM_CLIENT},
+    errors,
+};
+
+/// An invitation to join an organization, the token is only kept as a hash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrgInvite {
+    pub id: String,
+    pub org: String,
+    pub email: String,
+    pub role: String,
+    pub created_by: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub accepted_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+impl From<Model> for OrgInvite {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            org: model.org,
+            email: model.email,
+            role: model.role,
+            created_by: model.created_by,
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+            accepted_at: model.accepted_at,
+            revoked_at: model.revoked_at,
+        }
+    }
+}
+
+/// Stores a new invite and returns its id
+pub async fn add(
+    org: &str,
+    email: &str,
+    role: &str,
+    token_hash: &str,
+    created_by: &str,
+    created_at: i64,
+    expires_at: i64,
+) -> Result<String, errors::Error> {
+    let id = Ksuid::new(None, None).to_string();
+    let record = ActiveModel {
+        id: Set(id.clone()),
+        org: Set(org.to_string()),
+        email: Set(email.to_string()),
+        role: Set(role.to_string()),
+        token_hash: Set(token_hash.to_string()),
+        created_by: Set(created_by.to_string()),
+        created_at: Set(created_at),
+        expires_at: Set(expires_at),
+        accepted_at: Set(None),
+        revoked_at: Set(None),
+    };
+
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    if let Err(e) = Entity::insert(record).exec(client).await {
+        drop(_lock);
+        return match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                Err(errors::Error::DbError(errors::DbError::UniqueViolation))
+            }
+            _ => Err(e.into()),
+        };
+    }
+    drop(_lock);
+
+    Ok(id)
+}
+
+pub async fn get_by_token_hash(token_hash: &str) -> Result<Option<OrgInvite>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let record = Entity::find()
+        .filter(Column::TokenHash.eq(token_hash))
+        .one(client)
+        .await?;
+    Ok(record.map(OrgInvite::from))
+}
+
+/// Invites of the org that were neither accepted nor revoked and did not
+/// expire before `now`, newest first
+pub async fn list_pending(org: &str, now: i64) -> Result<Vec<OrgInvite>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let records = Entity::find()
+        .filter(Column::Org.eq(org))
+        .filter(Column::AcceptedAt.is_null())
+        .filter(Column::RevokedAt.is_null())
+        .filter(Column::ExpiresAt.gt(now))
+        .order_by(Column::CreatedAt, Order::Desc)
+        .all(client)
+        .await?;
+    Ok(records.into_iter().map(OrgInvite::from).collect())
+}
+
+/// Revokes a pending invite, returns false if there is no such invite
+pub async fn revoke(org: &str, id: &str, now: i64) -> Result<bool, errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let res = Entity::update_many()
+        .col_expr(Column::RevokedAt, Expr::value(now))
+        .filter(Column::Org.eq(org))
+        .filter(Column::Id.eq(id))
+        .filter(Column::AcceptedAt.is_null())
+        .filter(Column::RevokedAt.is_null())
+        .exec(client)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
+
+/// Marks a pending invite as accepted. Only one caller can claim an invite,
+/// the others get false.
+pub async fn claim(id: &str, now: i64) -> Result<bool, errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let res = Entity::update_many()
+        .col_expr(Column::AcceptedAt, Expr::value(now))
+        .filter(Column::Id.eq(id))
+        .filter(Column::AcceptedAt.is_null())
+        .filter(Column::RevokedAt.is_null())
+        .filter(Column::ExpiresAt.gt(now))
+        .exec(client)
+        .await?;
+
+    Ok(res.rows_affected == 1)
+}
+
+/// Makes a claimed invite pending again, used when the acceptance failed
+/// after the invite was claimed
+pub async fn release(id: &str) -> Result<(), errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    Entity::update_many()
+        .col_expr(Column::AcceptedAt, Expr::value(Option::<i64>::None))
+        .filter(Column::Id.eq(id))
+        .exec(client)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the invites that expired before `expired_before`, whatever their
+/// state, and returns how many were deleted
+pub async fn purge_expired(expired_before: i64) -> Result<u64, errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let res = Entity::delete_many()
+        .filter(Column::ExpiresAt.lt(expired_before))
+        .exec(client)
+        .await?;
+
+    Ok(res.rows_affected)
+}
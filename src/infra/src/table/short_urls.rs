@@ -15,6 +15,7 @@
 
 use sea_orm::{
     entity::prelude::*,
+    prelude::Expr,
     sea_query::{Alias, DynIden},
     ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, FromQueryResult, Order,
     PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Schema, Set,
@@ -33,11 +34,14 @@ use crate::{
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = true)]
     pub id: i64,
+    #[sea_orm(column_type = "String(StringLen::N(100))", default_value = "")]
+    pub org: String,
     #[sea_orm(column_type = "String(StringLen::N(32))")]
     pub short_id: String,
     #[sea_orm(column_type = "Custom(get_text_type())")]
     pub original_url: String,
     pub created_ts: i64,
+    pub last_accessed: Option<i64>,
 }
 
 fn get_text_type() -> DynIden {
@@ -76,6 +80,15 @@ pub struct ShortId {
     pub short_id: String,
 }
 
+/// A short URL of an org, as listed to the org admins.
+#[derive(FromQueryResult, Debug, Clone, PartialEq, Eq)]
+pub struct ShortUrlItem {
+    pub short_id: String,
+    pub original_url: String,
+    pub created_ts: i64,
+    pub last_accessed: Option<i64>,
+}
+
 pub async fn init() -> Result<(), errors::Error> {
     create_table().await?;
     create_table_index().await?;
@@ -124,8 +137,9 @@ pub async fn create_table_index() -> Result<(), errors::Error> {
     Ok(())
 }
 
-pub async fn add(short_id: &str, original_url: &str) -> Result<(), errors::Error> {
+pub async fn add(org: &str, short_id: &str, original_url: &str) -> Result<(), errors::Error> {
     let record = ActiveModel {
+        org: Set(org.to_string()),
         short_id: Set(short_id.to_string()),
         original_url: Set(original_url.to_string()),
         created_ts: Set(chrono::Utc::now().timestamp_micros()),
@@ -154,6 +168,22 @@ pub async fn remove(short_id: &str) -> Result<(), errors::Error> {
     Ok(())
 }
 
+/// Removes the short URL if it belongs to the org, returning whether it was
+/// removed.
+pub async fn remove_by_org(org: &str, short_id: &str) -> Result<bool, errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let res = Entity::delete_many()
+        .filter(Column::Org.eq(org))
+        .filter(Column::ShortId.eq(short_id))
+        .exec(client)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
+
 pub async fn get(short_id: &str) -> Result<ShortUrlRecord, errors::Error> {
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
     let record = Entity::find()
@@ -184,6 +214,56 @@ pub async fn list(limit: Option<i64>) -> Result<Vec<ShortUrlRecord>, errors::Err
     Ok(records)
 }
 
+/// Lists a page of the short URLs of the org, newest first, along with the
+/// total number of short URLs of the org.
+pub async fn list_by_org(
+    org: &str,
+    page_size: u64,
+    page_idx: u64,
+) -> Result<(Vec<ShortUrlItem>, u64), errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let paginator = Entity::find()
+        .select_only()
+        .column(Column::ShortId)
+        .column(Column::OriginalUrl)
+        .column(Column::CreatedTs)
+        .column(Column::LastAccessed)
+        .filter(Column::Org.eq(org))
+        .order_by(Column::CreatedTs, Order::Desc)
+        .order_by(Column::Id, Order::Desc)
+        .into_model::<ShortUrlItem>()
+        .paginate(client, page_size);
+    let total = paginator.num_items().await?;
+    let records = paginator.fetch_page(page_idx).await?;
+
+    Ok((records, total))
+}
+
+/// Sets the last access time of the short URL unless it was already set
+/// after `not_after`.
+pub async fn set_last_accessed(
+    short_id: &str,
+    accessed_at: i64,
+    not_after: i64,
+) -> Result<(), errors::Error> {
+    // make sure only one client is writing to the database(only for sqlite)
+    let _lock = get_lock().await;
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    Entity::update_many()
+        .col_expr(Column::LastAccessed, Expr::value(accessed_at))
+        .filter(Column::ShortId.eq(short_id))
+        .filter(
+            Column::LastAccessed
+                .is_null()
+                .or(Column::LastAccessed.lte(not_after)),
+        )
+        .exec(client)
+        .await?;
+
+    Ok(())
+}
+
 pub async fn contains(short_id: &str) -> Result<bool, errors::Error> {
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
     let record = Entity::find()
@@ -238,6 +318,23 @@ pub async fn get_expired(
     Ok(records.iter().map(|r| r.short_id.clone()).collect())
 }
 
+/// Gets the ids of the short URLs of the org created before `expired_before`.
+pub async fn get_expired_by_org(
+    org: &str,
+    expired_before: i64,
+) -> Result<Vec<String>, errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let records = Entity::find()
+        .select_only()
+        .column(Column::ShortId)
+        .filter(Column::Org.eq(org))
+        .filter(Column::CreatedTs.lt(expired_before))
+        .into_model::<ShortId>()
+        .all(client)
+        .await?;
+    Ok(records.into_iter().map(|r| r.short_id).collect())
+}
+
 pub async fn batch_remove(short_ids: Vec<String>) -> Result<(), errors::Error> {
     // make sure only one client is writing to the database(only for sqlite)
     let _lock = get_lock().await;
@@ -48,6 +48,7 @@ pub async fn get(
     // Step 1: Resolve the user-facing `dashboard_id` to the primary key (KSUID)
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(client)
         .await?
         .ok_or_else(|| {
@@ -196,6 +197,7 @@ pub async fn delete(dashboard_id: &str, timed_annotation_id: &str) -> Result<(),
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(client)
         .await?
         .ok_or_else(|| {
@@ -232,6 +234,7 @@ pub async fn delete_many(
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(client)
         .await?
         .ok_or_else(|| {
@@ -279,6 +282,7 @@ pub async fn get_one(
     // Step 1: Resolve the user-facing `dashboard_id` to the primary key
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(client)
         .await?
         .ok_or_else(|| {
@@ -351,6 +355,7 @@ pub async fn update(
     // Step 1: Resolve the user-facing `dashboard_id` to the primary key
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(&txn)
         .await?
         .ok_or_else(|| {
@@ -521,6 +526,7 @@ async fn insert_timed_annotation<'a>(
 ) -> Result<TimedAnnotation, errors::Error> {
     let dashboard_record = dashboards::Entity::find()
         .filter(dashboards::Column::DashboardId.eq(dashboard_id))
+        .filter(dashboards::Column::DeletedAt.is_null())
         .one(txn)
         .await?
         .ok_or_else(|| {
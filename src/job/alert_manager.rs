@@ -13,6 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use chrono::Utc;
 use config::{cluster::LOCAL_NODE, get_config};
 #[cfg(feature = "enterprise")]
 use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config;
@@ -20,6 +21,9 @@ use tokio::time;
 
 use crate::service;
 
+/// Interval in seconds between two purges of expired trash items.
+const TRASH_PURGE_INTERVAL: u64 = 3600;
+
 pub async fn run() -> Result<(), anyhow::Error> {
     if !LOCAL_NODE.is_alert_manager() {
         return Ok(());
@@ -61,6 +65,8 @@ pub async fn run() -> Result<(), anyhow::Error> {
         .await?;
     }
 
+    service::alerts::alert::check_frequency_units().await;
+
     tokio::task::spawn(async move { run_schedule_jobs().await });
     tokio::task::spawn(async move { clean_complete_jobs().await });
     tokio::task::spawn(async move { watch_timeout_jobs().await });
@@ -70,10 +76,27 @@ pub async fn run() -> Result<(), anyhow::Error> {
     tokio::task::spawn(async move { run_check_running_search_jobs().await });
     tokio::task::spawn(async move { run_delete_jobs_by_retention().await });
     tokio::task::spawn(async move { run_delete_jobs().await });
+    tokio::task::spawn(async move { run_purge_trash().await });
 
     Ok(())
 }
 
+/// Purges the dashboards and alerts that have been in the trash for longer
+/// than the trash retention period.
+async fn run_purge_trash() -> Result<(), anyhow::Error> {
+    let mut interval = time::interval(time::Duration::from_secs(TRASH_PURGE_INTERVAL));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        let deleted_before = service::trash::retention_cutoff(Utc::now().timestamp_micros());
+        match service::trash::purge(deleted_before).await {
+            Ok(0) => {}
+            Ok(purged) => log::info!("[TRASH] purged {purged} expired items"),
+            Err(e) => log::error!("[TRASH] purge expired items error: {}", e),
+        }
+    }
+}
+
 /// Runs the schedule jobs
 async fn run_schedule_jobs() -> Result<(), anyhow::Error> {
     service::alerts::scheduler::run().await
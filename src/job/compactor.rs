@@ -18,11 +18,7 @@ use std::sync::Arc;
 use config::{
     cluster::LOCAL_NODE,
     get_config,
-    meta::{
-        cluster::CompactionJobType,
-        stream::{FileKey, ALL_STREAM_TYPES},
-    },
-    metrics,
+    meta::{cluster::CompactionJobType, stream::FileKey},
 };
 #[cfg(feature = "enterprise")]
 use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config;
@@ -102,11 +98,13 @@ pub async fn run() -> Result<(), anyhow::Error> {
 
     tokio::task::spawn(async move { run_generate_job().await });
     tokio::task::spawn(async move { run_generate_old_data_job().await });
+    tokio::task::spawn(async move { run_original_data_job().await });
     #[cfg(feature = "enterprise")]
     tokio::task::spawn(async move { run_generate_downsampling_job().await });
     tokio::task::spawn(async move { run_merge(tx).await });
     tokio::task::spawn(async move { run_retention().await });
     tokio::task::spawn(async move { run_delay_deletion().await });
+    tokio::task::spawn(async move { run_lifecycle_reconcile().await });
     tokio::task::spawn(async move { run_sync_to_db().await });
     #[cfg(feature = "enterprise")]
     tokio::task::spawn(async move { run_downsampling_sync_to_db().await });
@@ -127,37 +125,8 @@ async fn run_compactor_pending_jobs_metric() -> Result<(), anyhow::Error> {
         tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
 
         log::debug!("[COMPACTOR] Running compactor pending jobs to report metric");
-        let job_status = match infra::file_list::get_pending_jobs_count().await {
-            Ok(status) => status,
-            Err(e) => {
-                log::error!("[COMPACTOR] run compactor pending jobs metric error: {e}");
-                continue;
-            }
-        };
-
-        // reset all metrics
-        let orgs = crate::service::db::schema::list_organizations_from_cache().await;
-        for org in orgs {
-            for stream_type in ALL_STREAM_TYPES {
-                if metrics::COMPACT_PENDING_JOBS
-                    .with_label_values(&[org.as_str(), stream_type.as_str()])
-                    .get()
-                    > 0
-                {
-                    metrics::COMPACT_PENDING_JOBS
-                        .with_label_values(&[org.as_str(), stream_type.as_str()])
-                        .set(0);
-                }
-            }
-        }
-
-        // set new metrics
-        for (org, inner_map) in job_status {
-            for (stream_type, counter) in inner_map {
-                metrics::COMPACT_PENDING_JOBS
-                    .with_label_values(&[org.as_str(), stream_type.as_str()])
-                    .set(counter);
-            }
+        if let Err(e) = compact::stats::update_jobs_metrics().await {
+            log::error!("[COMPACTOR] run compactor pending jobs metric error: {e}");
         }
     }
 }
@@ -191,6 +160,20 @@ async fn run_generate_old_data_job() -> Result<(), anyhow::Error> {
     }
 }
 
+/// Drop the `_original` column of the files out of its retention
+async fn run_original_data_job() -> Result<(), anyhow::Error> {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(
+            get_config().compact.original_data_interval,
+        ))
+        .await;
+        log::debug!("[COMPACTOR] Running original data job");
+        if let Err(e) = compact::run_generate_job(CompactionJobType::OriginalData).await {
+            log::error!("[COMPACTOR] run original data job error: {e}");
+        }
+    }
+}
+
 /// Generate downsampling job for compactor
 #[cfg(feature = "enterprise")]
 async fn run_generate_downsampling_job() -> Result<(), anyhow::Error> {
@@ -243,6 +226,20 @@ async fn run_retention() -> Result<(), anyhow::Error> {
     }
 }
 
+/// Reconcile file list with objects archived or expired by bucket lifecycle rules
+async fn run_lifecycle_reconcile() -> Result<(), anyhow::Error> {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(
+            get_config().s3.lifecycle_reconcile_interval,
+        ))
+        .await;
+        log::debug!("[COMPACTOR] Running lifecycle reconcile");
+        if let Err(e) = compact::lifecycle::run_reconcile().await {
+            log::error!("[COMPACTOR] run lifecycle reconcile error: {e}");
+        }
+    }
+}
+
 /// Delete files based on the file_file_deleted in the database
 async fn run_delay_deletion() -> Result<(), anyhow::Error> {
     loop {
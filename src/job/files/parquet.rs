@@ -240,6 +240,7 @@ async fn prepare_files(
                 {
                     // delete metadata from cache
                     WAL_PARQUET_METADATA.write().await.remove(&file_key);
+                    wal::remove_uploaded_file(&file_key);
                     // need release all the files
                     PROCESSING_FILES.write().await.remove(&file_key);
                     // delete from skip list
@@ -498,6 +499,14 @@ async fn move_files(
             }
         }
 
+        // searches skip these wal files once the storage file is in their file list,
+        // so they must be marked before the storage file is visible in the file list
+        let new_file_keys = new_file_list
+            .iter()
+            .map(|f| f.key.clone())
+            .collect::<Vec<_>>();
+        wal::set_uploaded_files(&new_file_keys, &new_file_name);
+
         // write file list to storage
         let ret = db::file_list::local::set(&new_file_name, Some(new_file_meta), false).await;
         if let Err(e) = ret {
@@ -506,6 +515,9 @@ async fn move_files(
                 new_file_name,
                 e.to_string()
             );
+            for key in new_file_keys.iter() {
+                wal::remove_uploaded_file(key);
+            }
             // need release all the files
             for file in files_with_size.iter() {
                 PROCESSING_FILES.write().await.remove(&file.key);
@@ -556,6 +568,7 @@ async fn move_files(
 
             // delete metadata from cache
             WAL_PARQUET_METADATA.write().await.remove(&file.key);
+            wal::remove_uploaded_file(&file.key);
 
             // remove the file from processing set
             // log::debug!("Processing files deleted: {:?}", file.key);
@@ -769,6 +782,13 @@ async fn merge_files(
     // upload file
     let buf = Bytes::from(buf);
     storage::put(&new_file_key, buf.clone()).await?;
+    metrics::INGEST_FILE_SIZE_BYTES
+        .with_label_values(&[
+            &org_id,
+            stream_type.as_str(),
+            metrics::file_size_stream_label(&stream_name),
+        ])
+        .observe(new_file_meta.compressed_size as f64);
 
     // skip index generation if not enabled or not basic type
     if !cfg.common.inverted_index_enabled || !stream_type.is_basic_type() {
@@ -0,0 +1,37 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use config::{cluster::LOCAL_NODE, get_config};
+use tokio::time;
+
+use crate::service::invites;
+
+/// Deletes the expired organization invites
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_compactor() {
+        return Ok(());
+    }
+
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().auth.invite_purge_interval.max(60),
+    ));
+    loop {
+        interval.tick().await;
+        match invites::purge_expired().await {
+            Ok(0) => {}
+            Ok(n) => log::info!("[INVITES] Purged {n} expired invites"),
+            Err(e) => log::error!("[INVITES] Failed to purge expired invites: {e}"),
+        }
+    }
+}
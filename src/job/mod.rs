@@ -33,10 +33,12 @@ mod cipher;
 mod compactor;
 pub(crate) mod files;
 mod flatten_compactor;
+mod invites;
 pub mod metrics;
 mod mmdb_downloader;
 mod promql;
 mod promql_self_consume;
+mod schema_drift;
 mod stats;
 pub(crate) mod syslog_server;
 mod telemetry;
@@ -202,6 +204,8 @@ pub async fn init() -> Result<(), anyhow::Error> {
     tokio::task::spawn(async move { metrics::run().await });
     tokio::task::spawn(async move { promql::run().await });
     tokio::task::spawn(async move { alert_manager::run().await });
+    tokio::task::spawn(async move { invites::run().await });
+    tokio::task::spawn(async move { schema_drift::run().await });
 
     // load metrics disk cache
     tokio::task::spawn(async move { crate::service::promql::search::init().await });
@@ -0,0 +1,42 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::{cluster::LOCAL_NODE, get_config};
+use tokio::time;
+
+use crate::service::alerts::schema_drift;
+
+/// Evaluates the schema drift alerts. The schema changes are detected by the
+/// ingesters, which store them for the leader alert manager to notify.
+pub async fn run() -> Result<(), anyhow::Error> {
+    if !LOCAL_NODE.is_ingester() && !LOCAL_NODE.is_alert_manager() {
+        return Ok(());
+    }
+
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().limit.schema_drift_alert_interval.max(1),
+    ));
+    interval.tick().await; // the first tick completes immediately
+    loop {
+        interval.tick().await;
+        if LOCAL_NODE.is_ingester() {
+            schema_drift::flush_pending().await;
+        }
+        let sent = schema_drift::evaluate_pending().await;
+        if sent > 0 {
+            log::info!("[SCHEMA_DRIFT] Sent {sent} schema drift notifications");
+        }
+    }
+}
@@ -247,6 +247,16 @@ async fn main() -> Result<(), anyhow::Error> {
                 job_init_tx.send(false).ok();
                 panic!("common infra init failed: {}", e);
             }
+            // check the object storage before serving any data
+            if config::cluster::LOCAL_NODE.is_ingester()
+                || config::cluster::LOCAL_NODE.is_querier()
+                || config::cluster::LOCAL_NODE.is_compactor()
+            {
+                if let Err(e) = infra::storage::health::check_on_startup().await {
+                    job_init_tx.send(false).ok();
+                    panic!("{}", e);
+                }
+            }
 
             // init enterprise
             #[cfg(feature = "enterprise")]
@@ -672,6 +682,7 @@ async fn init_http_server() -> Result<(), anyhow::Error> {
                             cfg.limit.circuit_breaker_enabled,
                         ))
                         .wrap(from_fn(middlewares::check_keep_alive))
+                        .wrap(from_fn(middlewares::real_ip))
                         .service(router::http::config)
                         .service(router::http::config_paths)
                         .service(router::http::api)
@@ -690,6 +701,7 @@ async fn init_http_server() -> Result<(), anyhow::Error> {
                         cfg.limit.circuit_breaker_enabled,
                     ))
                     .wrap(from_fn(middlewares::check_keep_alive))
+                    .wrap(from_fn(middlewares::real_ip))
                     .configure(get_config_routes)
                     .configure(get_service_routes)
                     .configure(get_other_service_routes)
@@ -704,9 +716,7 @@ async fn init_http_server() -> Result<(), anyhow::Error> {
             .app_data(web::PayloadConfig::new(cfg.limit.req_payload_limit)) // size is in bytes
             .app_data(web::Data::new(local_id))
             .wrap(middleware::Compress::default())
-            .wrap(middleware::Logger::new(
-                r#"%a "%r" %s %b "%{Content-Length}i" "%{Referer}i" "%{User-Agent}i" %T"#,
-            ))
+            .wrap(middlewares::access_log())
             .wrap(RequestTracing::new())
     })
     .keep_alive(KeepAlive::Timeout(Duration::from_secs(
@@ -783,6 +793,7 @@ async fn init_http_server_without_tracing() -> Result<(), anyhow::Error> {
                             cfg.limit.circuit_breaker_enabled,
                         ))
                         .wrap(from_fn(middlewares::check_keep_alive))
+                        .wrap(from_fn(middlewares::real_ip))
                         .service(router::http::config)
                         .service(router::http::config_paths)
                         .service(router::http::api)
@@ -801,6 +812,7 @@ async fn init_http_server_without_tracing() -> Result<(), anyhow::Error> {
                         cfg.limit.circuit_breaker_enabled,
                     ))
                     .wrap(from_fn(middlewares::check_keep_alive))
+                    .wrap(from_fn(middlewares::real_ip))
                     .configure(get_config_routes)
                     .configure(get_service_routes)
                     .configure(get_other_service_routes)
@@ -815,9 +827,7 @@ async fn init_http_server_without_tracing() -> Result<(), anyhow::Error> {
             .app_data(web::PayloadConfig::new(cfg.limit.req_payload_limit)) // size is in bytes
             .app_data(web::Data::new(local_id))
             .wrap(middleware::Compress::default())
-            .wrap(middleware::Logger::new(
-                r#"%a "%r" %s %b "%{Content-Length}i" "%{Referer}i" "%{User-Agent}i" %T"#,
-            ))
+            .wrap(middlewares::access_log())
     })
     .keep_alive(KeepAlive::Timeout(Duration::from_secs(
         cfg.limit.http_keep_alive,
@@ -1055,9 +1065,7 @@ async fn init_script_server() -> Result<(), anyhow::Error> {
             .app_data(web::PayloadConfig::new(cfg.limit.req_payload_limit)) // size is in bytes
             .app_data(web::Data::new(local_id))
             .wrap(middleware::Compress::default())
-            .wrap(middleware::Logger::new(
-                r#"%a "%r" %s %b "%{Content-Length}i" "%{Referer}i" "%{User-Agent}i" %T"#,
-            ))
+            .wrap(middlewares::access_log())
             .wrap(RequestTracing::new())
     })
     .keep_alive(KeepAlive::Timeout(Duration::from_secs(
@@ -893,6 +893,8 @@ pub struct SearchQuery {
     pub skip_wal: bool,
     #[prost(string, tag = "15")]
     pub action_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "16")]
+    pub timezone: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -2103,6 +2105,10 @@ pub struct QueryCacheRequest {
     pub discard_interval: i64,
     #[prost(bool, tag = "9")]
     pub is_descending: bool,
+    #[prost(bool, tag = "10")]
+    pub is_count: bool,
+    #[prost(int64, tag = "11")]
+    pub calendar_months: i64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -2137,6 +2143,10 @@ pub struct MultiQueryCacheResponse {
 pub struct DeleteResultCacheRequest {
     #[prost(string, tag = "1")]
     pub path: ::prost::alloc::string::String,
+    #[prost(int64, optional, tag = "2")]
+    pub start_time: ::core::option::Option<i64>,
+    #[prost(int64, optional, tag = "3")]
+    pub end_time: ::core::option::Option<i64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -2662,6 +2672,9 @@ pub struct SearchInfo {
     pub end_time: i64,
     #[prost(int64, tag = "6")]
     pub timeout: i64,
+    /// storage files already scanned, skip their wal copies
+    #[prost(string, repeated, tag = "7")]
+    pub seam_files: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
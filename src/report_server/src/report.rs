@@ -6,7 +6,7 @@ use chromiumoxide::{
     handler::viewport::Viewport,
     Page,
 };
-use config::get_config;
+use config::{get_config, utils::render_queue::RenderContext};
 use futures::StreamExt;
 use lettre::{
     message::{header::ContentType, MultiPart, SinglePart},
@@ -146,6 +146,7 @@ pub async fn generate_report(
     timezone: &str,
     report_type: ReportType,
     report_name: &str,
+    ctx: RenderContext,
 ) -> Result<(Vec<u8>, String), anyhow::Error> {
     let dashboard_id = &dashboard.dashboard;
     let folder_id = &dashboard.folder;
@@ -165,6 +166,10 @@ pub async fn generate_report(
     let (mut browser, mut handler) =
         Browser::launch(get_chrome_launch_options().await.clone()).await?;
     log::info!("browser launched");
+    // register chromium so the render queue can kill it on timeout
+    if let Some(pid) = browser.get_mut_child().and_then(|child| child.id()) {
+        ctx.set_pid(pid);
+    }
 
     let handle = tokio::task::spawn(async move {
         while let Some(h) = handler.next().await {
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, io::Error};
 
 use actix_web::{get, http::StatusCode, put, web, HttpRequest, HttpResponse as ActixHttpResponse};
+use config::utils::render_queue::{RenderError, REPORT_RENDER_QUEUE};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -36,6 +37,15 @@ impl HttpResponse {
         }
     }
 
+    pub fn service_unavailable(e: String) -> Self {
+        Self {
+            code: StatusCode::SERVICE_UNAVAILABLE.into(),
+            message: e,
+            error_detail: None,
+            trace_id: None,
+        }
+    }
+
     pub fn success(msg: String) -> Self {
         Self {
             code: StatusCode::OK.into(),
@@ -51,6 +61,15 @@ pub async fn healthz() -> Result<ActixHttpResponse, Error> {
     Ok(ActixHttpResponse::Ok().body("Server up and running"))
 }
 
+#[get("/{org_id}/reports/{name}/status")]
+pub async fn report_status(path: web::Path<(String, String)>) -> Result<ActixHttpResponse, Error> {
+    let (org_id, report_name) = path.into_inner();
+    Ok(
+        ActixHttpResponse::Ok()
+            .json(REPORT_RENDER_QUEUE.status(&format!("{org_id}/{report_name}"))),
+    )
+}
+
 #[put("/{org_id}/reports/{name}/send")]
 pub async fn send_report(
     report: web::Json<models::Report>,
@@ -71,21 +90,31 @@ pub async fn send_report(
     } else {
         ReportType::PDF
     };
-    let (pdf_data, email_dashboard_url) = match generate_report(
-        &report.dashboards[0],
-        &org_id,
-        &cfg.report_server.user_email,
-        &cfg.report_server.user_password,
-        &report.email_details.dashb_url,
-        timezone,
-        report_type.clone(),
-        &report_name,
-    )
-    .await
+    let render_key = format!("{org_id}/{report_name}");
+    let (pdf_data, email_dashboard_url) = match REPORT_RENDER_QUEUE
+        .run(&render_key, |ctx| {
+            generate_report(
+                &report.dashboards[0],
+                &org_id,
+                &cfg.report_server.user_email,
+                &cfg.report_server.user_password,
+                &report.email_details.dashb_url,
+                timezone,
+                report_type.clone(),
+                &report_name,
+                ctx,
+            )
+        })
+        .await
     {
         Ok(res) => res,
+        Err(e @ RenderError::QueueFull(_)) => {
+            log::error!("Error queueing report {render_key}: {e}");
+            return Ok(ActixHttpResponse::ServiceUnavailable()
+                .json(HttpResponse::service_unavailable(e.to_string())));
+        }
         Err(e) => {
-            log::error!("Error generating pdf for report {org_id}/{report_name}: {e}");
+            log::error!("Error generating pdf for report {render_key}: {e}");
             return Ok(ActixHttpResponse::InternalServerError()
                 .json(HttpResponse::internal_server_error(e.to_string())));
         }
@@ -2,7 +2,7 @@ use std::net::SocketAddr;
 
 use actix_web::{dev::ServerHandle, middleware, web, App, HttpServer};
 
-use crate::router::{healthz, send_report};
+use crate::router::{healthz, report_status, send_report};
 
 pub async fn spawn_server() -> Result<(), anyhow::Error> {
     // Locate or fetch chromium
@@ -31,7 +31,12 @@ pub async fn spawn_server() -> Result<(), anyhow::Error> {
     log::info!("starting Report server at: {}", haddr);
     let server = HttpServer::new(move || {
         App::new()
-            .service(web::scope("/api").service(send_report).service(healthz))
+            .service(
+                web::scope("/api")
+                    .service(send_report)
+                    .service(report_status)
+                    .service(healthz),
+            )
             .wrap(middleware::Logger::new(
                 r#"%a "%r" %s %b "%{Content-Length}i" "%{Referer}i" "%{User-Agent}i" %T"#,
             ))
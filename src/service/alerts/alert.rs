@@ -25,7 +25,7 @@ use config::{
     meta::{
         alerts::{
             alert::{Alert, AlertListFilter, ListAlertsParams},
-            FrequencyType, Operator, QueryType,
+            BaselineCondition, FrequencyType, Operator, QueryType, TriggerCondition,
         },
         destinations::{
             AwsSns, DestinationType, Email, Endpoint, HTTPType, Module, Template, TemplateType,
@@ -50,6 +50,7 @@ use svix_ksuid::Ksuid;
 
 use crate::{
     common::{
+        infra::config::STREAM_ALERTS,
         meta::authz::Authz,
         utils::auth::{is_ofga_unsupported, remove_ownership, set_ownership},
     },
@@ -127,6 +128,10 @@ pub enum AlertError {
     #[error("{error_message}")]
     SendNotificationError { error_message: String },
 
+    /// The notification reached some, but not all, of the alert destinations.
+    #[error("Failed to send notification to {} of {} destinations", .results.iter().filter(|r| r.result.is_err()).count(), .results.len())]
+    PartialSendNotificationError { results: Vec<DestinationSendResult> },
+
     #[error(transparent)]
     GetDestinationWithTemplateError(#[from] db::alerts::destinations::DestinationError),
 
@@ -136,9 +141,29 @@ pub enum AlertError {
         stream_name: String,
     },
 
+    /// The scheduler can't run an alert more often than it polls for triggers.
+    #[error("Alert frequency must be at least {min_frequency_seconds} seconds")]
+    FrequencyBelowScheduleInterval { min_frequency_seconds: i64 },
+
     #[error("Error resolving stream names in SQL query: {0}")]
     ResolveStreamNameError(#[source] anyhow::Error),
 
+    #[error("Alert baseline needs an aggregated column, it is only supported by scheduled SQL and custom query alerts with an aggregation, without multi time range")]
+    BaselineNotSupported,
+
+    #[error("Alert baseline offset {offset} is invalid, use a number followed by m, h, d or w")]
+    BaselineInvalidOffset { offset: String },
+
+    /// The baseline window would overlap the current one.
+    #[error("Alert baseline offset must be at least the alert period")]
+    BaselineOffsetBelowPeriod,
+
+    #[error("Alert baseline multiplier must be a positive number")]
+    BaselineInvalidMultiplier,
+
+    #[error("Alert baseline operator must be one of >, >=, <, <=")]
+    BaselineInvalidOperator,
+
     /// An error occured trying to get the list of permitted alerts in
     /// enterprise mode because no user_id was provided.
     #[error("user_id required to get permitted alerts in enterprise mode")]
@@ -154,6 +179,14 @@ pub enum AlertError {
     NotSupportedAlertDestinationType(Module),
 }
 
+/// The outcome of sending an alert notification to one of its destinations.
+#[derive(Clone, Debug)]
+pub struct DestinationSendResult {
+    pub destination: String,
+    /// The response of the destination, or why the notification could not be sent.
+    pub result: Result<String, String>,
+}
+
 pub async fn save(
     org_id: &str,
     stream_name: &str,
@@ -253,6 +286,13 @@ async fn prepare_alert(
         // default frequency is 60 seconds
         alert.trigger_condition.frequency =
             std::cmp::max(60, get_config().limit.alert_schedule_interval);
+    } else {
+        let min_frequency_seconds = get_config().limit.alert_schedule_interval;
+        if alert.trigger_condition.frequency < min_frequency_seconds {
+            return Err(AlertError::FrequencyBelowScheduleInterval {
+                min_frequency_seconds,
+            });
+        }
     }
 
     if alert.name.is_empty() || alert.stream_name.is_empty() {
@@ -284,6 +324,11 @@ async fn prepare_alert(
     if alert.destinations.is_empty() {
         return Err(AlertError::AlertDestinationMissing);
     }
+    // the same destination listed twice would be notified twice
+    let mut seen_destinations = HashSet::with_capacity(alert.destinations.len());
+    alert
+        .destinations
+        .retain(|dest| seen_destinations.insert(dest.clone()));
     for dest in alert.destinations.iter() {
         match db::alerts::destinations::get(org_id, dest).await {
             Ok(d) => {
@@ -312,26 +357,30 @@ async fn prepare_alert(
         alert.context_attributes = Some(new_attrs);
     }
 
-    // before saving alert check column type to decide numeric condition
-    let schema = infra::schema::get(org_id, stream_name, stream_type).await?;
-    if stream_name.is_empty() || schema.fields().is_empty() {
-        return Err(AlertError::StreamNotFound {
-            stream_name: stream_name.to_owned(),
-        });
-    }
-
-    // Alerts must follow the max_query_range of the stream as set in the schema
-    if let Some(settings) = unwrap_stream_settings(&schema) {
-        let max_query_range = settings.max_query_range;
-        if max_query_range > 0
-            && !alert.is_real_time
-            && alert.trigger_condition.period > max_query_range * 60
-        {
-            return Err(AlertError::PeriodExceedsMaxQueryRange {
-                max_query_range_hours: max_query_range,
+    // Schema drift alerts can watch a stream pattern or a stream that is not
+    // created yet, and they don't query the stream.
+    if alert.query_condition.query_type != QueryType::SchemaDrift {
+        // before saving alert check column type to decide numeric condition
+        let schema = infra::schema::get(org_id, stream_name, stream_type).await?;
+        if stream_name.is_empty() || schema.fields().is_empty() {
+            return Err(AlertError::StreamNotFound {
                 stream_name: stream_name.to_owned(),
             });
         }
+
+        // Alerts must follow the max_query_range of the stream as set in the schema
+        if let Some(settings) = unwrap_stream_settings(&schema) {
+            let max_query_range = settings.max_query_range;
+            if max_query_range > 0
+                && !alert.is_real_time
+                && alert.trigger_condition.period > max_query_range * 60
+            {
+                return Err(AlertError::PeriodExceedsMaxQueryRange {
+                    max_query_range_hours: max_query_range,
+                    stream_name: stream_name.to_owned(),
+                });
+            }
+        }
     }
 
     if alert.is_real_time && alert.query_condition.query_type != QueryType::Custom {
@@ -395,6 +444,11 @@ async fn prepare_alert(
                 return Err(AlertError::PromqlMissingQuery);
             }
         }
+        QueryType::SchemaDrift => {}
+    }
+
+    if let Some(baseline) = alert.trigger_condition.baseline.as_ref() {
+        validate_baseline(alert, baseline)?;
     }
 
     // Commented intentionally - in case the alert period is big and there
@@ -407,6 +461,46 @@ async fn prepare_alert(
     Ok(())
 }
 
+fn validate_baseline(alert: &Alert, baseline: &BaselineCondition) -> Result<(), AlertError> {
+    let query_condition = &alert.query_condition;
+    let aggregated = match query_condition.query_type {
+        QueryType::Custom => query_condition.aggregation.is_some(),
+        QueryType::SQL => true,
+        QueryType::PromQL | QueryType::SchemaDrift => false,
+    };
+    if alert.is_real_time
+        || !aggregated
+        || baseline.column.is_empty()
+        || query_condition
+            .multi_time_range
+            .as_ref()
+            .is_some_and(|ranges| !ranges.is_empty())
+    {
+        return Err(AlertError::BaselineNotSupported);
+    }
+    let Some(offset) = baseline.offset_micros() else {
+        return Err(AlertError::BaselineInvalidOffset {
+            offset: baseline.offset.clone(),
+        });
+    };
+    if offset < alert.trigger_condition.period * 60_000_000 {
+        return Err(AlertError::BaselineOffsetBelowPeriod);
+    }
+    if !baseline.multiplier.is_finite() || baseline.multiplier <= 0.0 {
+        return Err(AlertError::BaselineInvalidMultiplier);
+    }
+    if !matches!(
+        baseline.operator,
+        Operator::GreaterThan
+            | Operator::GreaterThanEquals
+            | Operator::LessThan
+            | Operator::LessThanEquals
+    ) {
+        return Err(AlertError::BaselineInvalidOperator);
+    }
+    Ok(())
+}
+
 /// Creates a new alert in the specified folder.
 pub async fn create<C: TransactionTrait>(
     conn: &C,
@@ -566,23 +660,16 @@ pub async fn list_v2<C: ConnectionTrait>(
     Ok(alerts)
 }
 
-/// Deletes an alert by its KSUID primary key.
+/// Moves an alert to the trash by its KSUID primary key. The alert can be
+/// restored until it is purged after the trash retention period.
 pub async fn delete_by_id<C: ConnectionTrait>(
     conn: &C,
     org_id: &str,
     alert_id: Ksuid,
+    user_id: &str,
 ) -> Result<(), AlertError> {
-    let Some((_, alert)) = db::alerts::alert::get_by_id(conn, org_id, alert_id).await? else {
-        return Ok(());
-    };
-
-    match db::alerts::alert::delete_by_id(conn, org_id, alert_id).await {
-        Ok(_) => {
-            remove_ownership(org_id, "alerts", Authz::new(&alert.name)).await;
-            Ok(())
-        }
-        Err(e) => Err(e.into()),
-    }
+    db::alerts::alert::delete_by_id(conn, org_id, alert_id, user_id).await?;
+    Ok(())
 }
 
 pub async fn delete_by_name(
@@ -590,6 +677,7 @@ pub async fn delete_by_name(
     stream_type: StreamType,
     stream_name: &str,
     name: &str,
+    user_id: &str,
 ) -> Result<(), AlertError> {
     if db::alerts::alert::get_by_name(org_id, stream_type, stream_name, name)
         .await
@@ -597,27 +685,72 @@ pub async fn delete_by_name(
     {
         return Err(AlertError::AlertNotFound);
     }
-    match db::alerts::alert::delete_by_name(org_id, stream_type, stream_name, name).await {
-        Ok(_) => {
-            remove_ownership(org_id, "alerts", Authz::new(name)).await;
-            Ok(())
-        }
-        Err(e) => Err(e.into()),
+    db::alerts::alert::delete_by_name(org_id, stream_type, stream_name, name, user_id).await?;
+    Ok(())
+}
+
+/// Restores an alert from the trash into the folder it was deleted from.
+pub async fn restore_by_id<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+) -> Result<(Folder, Alert), AlertError> {
+    match db::alerts::alert::restore_by_id(conn, org_id, alert_id).await? {
+        Some(folder_and_alert) => Ok(folder_and_alert),
+        None => Err(AlertError::AlertNotFound),
     }
 }
 
-/// Enables an alert.
+/// Permanently deletes a trashed alert along with its scheduler trigger and
+/// ownership, if it was moved to the trash before `deleted_before`. Returns
+/// `false` if there was no such alert.
+pub async fn purge<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert: &Alert,
+    deleted_before: i64,
+) -> Result<bool, AlertError> {
+    if !db::alerts::alert::purge(conn, org_id, alert, deleted_before).await? {
+        return Ok(false);
+    }
+    remove_ownership(org_id, "alerts", Authz::new(&alert.name)).await;
+    Ok(true)
+}
+
+/// Enables or disables the alert. Returns `true` if the enabled state of the
+/// alert changed.
 pub async fn enable_by_id<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
     org_id: &str,
     alert_id: Ksuid,
     should_enable: bool,
-) -> Result<(), AlertError> {
+) -> Result<bool, AlertError> {
     let Some((_, mut alert)) = db::alerts::alert::get_by_id(conn, org_id, alert_id).await? else {
         return Err(AlertError::AlertNotFound);
     };
+    let changed = alert.enabled != should_enable;
     alert.enabled = should_enable;
     update(conn, org_id, None, alert).await?;
+    Ok(changed)
+}
+
+/// Sets the time until which notifications for the alert are suppressed.
+/// Passing `None` removes the silence.
+pub async fn silence_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+    silence_until: Option<i64>,
+) -> Result<(), AlertError> {
+    if !table::alerts::set_silence_until(conn, org_id, alert_id, silence_until).await? {
+        return Err(AlertError::AlertNotFound);
+    }
+    // updating with the stored alert, which now has the new silence, notifies
+    // the other nodes of the change
+    let Some((_, alert)) = db::alerts::alert::get_by_id(conn, org_id, alert_id).await? else {
+        return Err(AlertError::AlertNotFound);
+    };
+    update(conn, org_id, None, alert).await?;
     Ok(())
 }
 
@@ -650,8 +783,8 @@ pub async fn trigger_by_id<C: ConnectionTrait>(
         return Err(AlertError::AlertNotFound);
     };
     let now = Utc::now().timestamp_micros();
-    let (success_message, err_message) = alert.send_notification(&[], now, None, now).await?;
-    Ok((success_message, err_message))
+    let results = send_to_destinations(&alert, &[], now, None, now).await;
+    check_trigger_results(results)
 }
 
 pub async fn trigger_by_name(
@@ -667,8 +800,39 @@ pub async fn trigger_by_name(
         }
     };
     let now = Utc::now().timestamp_micros();
-    let (success_message, err_message) = alert.send_notification(&[], now, None, now).await?;
-    Ok((success_message, err_message))
+    let results = send_to_destinations(&alert, &[], now, None, now).await;
+    check_trigger_results(results)
+}
+
+/// Sends the notification for the alert unless it is silenced at `now`.
+///
+/// A suppressed notification is reported as sent so that callers still advance
+/// the trigger bookkeeping, and the matches seen while silenced are not sent
+/// all at once when the silence expires.
+pub async fn send_notification_unless_silenced(
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+    rows_end_time: i64,
+    start_time: Option<i64>,
+    now: i64,
+) -> Result<(String, String), AlertError> {
+    if let Some(silence_until) = alert.silence_until.filter(|_| alert.is_silenced_at(now)) {
+        log::info!(
+            "Alert notification suppressed for {}/{}/{}/{}, silenced until {}",
+            alert.org_id,
+            alert.stream_type,
+            alert.stream_name,
+            alert.name,
+            silence_until
+        );
+        return Ok((
+            format!("notification suppressed, alert is silenced until {silence_until}"),
+            String::new(),
+        ));
+    }
+    alert
+        .send_notification(rows, rows_end_time, start_time, now)
+        .await
 }
 
 #[async_trait]
@@ -727,53 +891,10 @@ impl AlertExt for Alert {
         start_time: Option<i64>,
         evaluation_timestamp: i64,
     ) -> Result<(String, String), AlertError> {
-        let mut err_message = "".to_string();
-        let mut success_message = "".to_string();
-        let mut no_of_error = 0;
-        for dest in self.destinations.iter() {
-            let (dest, template) = destinations::get_with_template(&self.org_id, dest).await?;
-            let Module::Alert {
-                destination_type, ..
-            } = dest.module
-            else {
-                return Err(AlertError::GetDestinationWithTemplateError(
-                    db::alerts::destinations::DestinationError::UnsupportedType,
-                ));
-            };
-            match send_notification(
-                self,
-                &destination_type,
-                &template,
-                rows,
-                rows_end_time,
-                start_time,
-                evaluation_timestamp,
-            )
-            .await
-            {
-                Ok(resp) => {
-                    success_message =
-                        format!("{success_message} destination {} {resp};", dest.name);
-                }
-                Err(e) => {
-                    log::error!(
-                        "Error sending notification for {}/{}/{}/{} for destination {} err: {}",
-                        self.org_id,
-                        self.stream_type,
-                        self.stream_name,
-                        self.name,
-                        dest.name,
-                        e
-                    );
-                    no_of_error += 1;
-                    err_message = format!(
-                        "{err_message} Error sending notification for destination {} err: {e};",
-                        dest.name
-                    );
-                }
-            }
-        }
-        if no_of_error == self.destinations.len() {
+        let results =
+            send_to_destinations(self, rows, rows_end_time, start_time, evaluation_timestamp).await;
+        let (success_message, err_message) = summarize_send_results(&results);
+        if results.iter().all(|r| r.result.is_err()) {
             Err(AlertError::SendNotificationError {
                 error_message: err_message,
             })
@@ -783,6 +904,120 @@ impl AlertExt for Alert {
     }
 }
 
+/// Sends the notification to every destination of the alert.
+///
+/// A destination that fails, or can no longer be resolved, does not prevent the
+/// notification from being sent to the remaining destinations.
+async fn send_to_destinations(
+    alert: &Alert,
+    rows: &[Map<String, Value>],
+    rows_end_time: i64,
+    start_time: Option<i64>,
+    evaluation_timestamp: i64,
+) -> Vec<DestinationSendResult> {
+    collect_send_results(&alert.destinations, |dest_name| async move {
+        let (dest, template) = match destinations::get_with_template(&alert.org_id, dest_name).await
+        {
+            Ok(dest_and_template) => dest_and_template,
+            Err(e) => {
+                log::error!(
+                    "Error getting destination {} for alert {}/{}/{}/{} err: {}",
+                    dest_name,
+                    alert.org_id,
+                    alert.stream_type,
+                    alert.stream_name,
+                    alert.name,
+                    e
+                );
+                return Err(format!("error getting destination: {e}"));
+            }
+        };
+        let Module::Alert {
+            destination_type, ..
+        } = dest.module
+        else {
+            return Err("not an alert destination".to_string());
+        };
+        send_notification(
+            alert,
+            &destination_type,
+            &template,
+            rows,
+            rows_end_time,
+            start_time,
+            evaluation_timestamp,
+        )
+        .await
+        .map_err(|e| {
+            log::error!(
+                "Error sending notification for {}/{}/{}/{} for destination {} err: {}",
+                alert.org_id,
+                alert.stream_type,
+                alert.stream_name,
+                alert.name,
+                dest_name,
+                e
+            );
+            e.to_string()
+        })
+    })
+    .await
+}
+
+async fn collect_send_results<'a, F, Fut>(
+    destinations: &'a [String],
+    send: F,
+) -> Vec<DestinationSendResult>
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: std::future::Future<Output = Result<String, String>>,
+{
+    let mut results = Vec::with_capacity(destinations.len());
+    for dest_name in destinations.iter() {
+        results.push(DestinationSendResult {
+            destination: dest_name.to_string(),
+            result: send(dest_name).await,
+        });
+    }
+    results
+}
+
+/// Returns the success and error messages of the sends, in destination order.
+fn summarize_send_results(results: &[DestinationSendResult]) -> (String, String) {
+    let mut success_message = "".to_string();
+    let mut err_message = "".to_string();
+    for r in results.iter() {
+        match &r.result {
+            Ok(resp) => {
+                success_message = format!("{success_message} destination {} {resp};", r.destination)
+            }
+            Err(e) => {
+                err_message = format!(
+                    "{err_message} Error sending notification for destination {} err: {e};",
+                    r.destination
+                )
+            }
+        }
+    }
+    (success_message, err_message)
+}
+
+/// A manually triggered alert reports any failed destination, unlike the
+/// scheduler which only fails when no destination was notified.
+fn check_trigger_results(
+    results: Vec<DestinationSendResult>,
+) -> Result<(String, String), AlertError> {
+    let failed = results.iter().filter(|r| r.result.is_err()).count();
+    if failed == results.len() {
+        let (_, error_message) = summarize_send_results(&results);
+        Err(AlertError::SendNotificationError { error_message })
+    } else if failed > 0 {
+        Err(AlertError::PartialSendNotificationError { results })
+    } else {
+        Ok(summarize_send_results(&results))
+    }
+}
+
 async fn send_notification(
     alert: &Alert,
     dest_type: &DestinationType,
@@ -838,13 +1073,7 @@ async fn send_notification(
 }
 
 async fn send_http_notification(endpoint: &Endpoint, msg: String) -> Result<String, anyhow::Error> {
-    let client = if endpoint.skip_tls_verify {
-        reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?
-    } else {
-        reqwest::Client::new()
-    };
+    let client = destinations::get_http_client(endpoint)?;
     let url = url::Url::parse(&endpoint.url)?;
     let mut req = match endpoint.method {
         HTTPType::POST => client.post(url),
@@ -1213,6 +1442,7 @@ async fn process_dest_template(
                     }
                 }
             }
+            QueryType::SchemaDrift => {}
             _ => unreachable!(),
         };
         // http://localhost:5080/web/logs?stream_type=logs&stream=test&from=1708416534519324&to=1708416597898186&sql_mode=true&query=U0VMRUNUICogRlJPTSAidGVzdCIgd2hlcmUgbGV2ZWwgPSAnaW5mbyc=&org_identifier=default
@@ -1471,6 +1701,38 @@ async fn permitted_alerts(
     Ok(None)
 }
 
+/// Returns true if the alert runs on a fixed frequency below the scheduler
+/// interval. Such values were usually saved in minutes instead of seconds.
+fn is_frequency_below_schedule_interval(
+    trigger_condition: &TriggerCondition,
+    schedule_interval: i64,
+) -> bool {
+    trigger_condition.frequency_type == FrequencyType::Minutes
+        && trigger_condition.frequency > 0
+        && trigger_condition.frequency < schedule_interval
+}
+
+/// Logs the stored alerts whose frequency is below the scheduler interval.
+/// They are left unchanged as the intended unit can't be told for sure, but
+/// they run at the scheduler interval rather than their frequency.
+pub async fn check_frequency_units() {
+    let schedule_interval = get_config().limit.alert_schedule_interval;
+    let cacher = STREAM_ALERTS.read().await;
+    for alert in cacher.values().flatten() {
+        if is_frequency_below_schedule_interval(&alert.trigger_condition, schedule_interval) {
+            log::warn!(
+                "Alert {}/{}/{}/{} has a frequency of {} seconds, below the alert schedule interval of {} seconds, it may have been saved in minutes",
+                alert.org_id,
+                alert.stream_type,
+                alert.stream_name,
+                alert.name,
+                alert.trigger_condition.frequency,
+                schedule_interval
+            );
+        }
+    }
+}
+
 #[cfg(feature = "enterprise")]
 async fn permitted_alerts(
     org_id: &str,
@@ -1502,4 +1764,122 @@ mod tests {
         // alert name should not contain /
         assert!(ret.is_err());
     }
+
+    #[tokio::test]
+    async fn test_trigger_reports_partial_send_failure() {
+        let destinations = vec!["broken_smtp".to_string(), "webhook".to_string()];
+        let results = collect_send_results(&destinations, |dest_name| async move {
+            if dest_name == "broken_smtp" {
+                Err("connection refused".to_string())
+            } else {
+                Ok("200 OK".to_string())
+            }
+        })
+        .await;
+        // the failing destination does not stop the next one from being notified
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].result, Err("connection refused".to_string()));
+        assert_eq!(results[1].result, Ok("200 OK".to_string()));
+
+        let err = check_trigger_results(results).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to send notification to 1 of 2 destinations"
+        );
+        assert!(matches!(
+            &err,
+            AlertError::PartialSendNotificationError { results } if results.len() == 2
+        ));
+        let resp: actix_web::HttpResponse = err.into();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::MULTI_STATUS);
+
+        let all_failed = vec![DestinationSendResult {
+            destination: "broken_smtp".to_string(),
+            result: Err("connection refused".to_string()),
+        }];
+        assert!(matches!(
+            check_trigger_results(all_failed),
+            Err(AlertError::SendNotificationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_baseline() {
+        let mut alert = Alert::default();
+        alert.query_condition.query_type = QueryType::SQL;
+        alert.trigger_condition.period = 10;
+        let baseline = BaselineCondition {
+            offset: "1h".to_string(),
+            operator: Operator::GreaterThan,
+            multiplier: 2.0,
+            column: "errors".to_string(),
+        };
+        assert!(validate_baseline(&alert, &baseline).is_ok());
+
+        let invalid = [
+            ("1x", Operator::GreaterThan, 2.0),
+            ("5m", Operator::GreaterThan, 2.0),
+            ("1h", Operator::GreaterThan, 0.0),
+            ("1h", Operator::GreaterThan, f64::NAN),
+            ("1h", Operator::Contains, 2.0),
+        ];
+        for (offset, operator, multiplier) in invalid {
+            let baseline = BaselineCondition {
+                offset: offset.to_string(),
+                operator,
+                multiplier,
+                column: "errors".to_string(),
+            };
+            assert!(validate_baseline(&alert, &baseline).is_err());
+        }
+
+        alert.is_real_time = true;
+        assert!(matches!(
+            validate_baseline(&alert, &baseline),
+            Err(AlertError::BaselineNotSupported)
+        ));
+        alert.is_real_time = false;
+        alert.query_condition.query_type = QueryType::PromQL;
+        assert!(matches!(
+            validate_baseline(&alert, &baseline),
+            Err(AlertError::BaselineNotSupported)
+        ));
+        // custom queries only have a value to compare with an aggregation
+        alert.query_condition.query_type = QueryType::Custom;
+        assert!(matches!(
+            validate_baseline(&alert, &baseline),
+            Err(AlertError::BaselineNotSupported)
+        ));
+    }
+
+    #[test]
+    fn test_is_frequency_below_schedule_interval() {
+        let trigger_condition = |frequency, frequency_type| TriggerCondition {
+            frequency,
+            frequency_type,
+            ..Default::default()
+        };
+        // 5 minutes stored as 5 instead of 300 seconds
+        assert!(is_frequency_below_schedule_interval(
+            &trigger_condition(5, FrequencyType::Minutes),
+            60
+        ));
+        assert!(!is_frequency_below_schedule_interval(
+            &trigger_condition(300, FrequencyType::Minutes),
+            60
+        ));
+        assert!(!is_frequency_below_schedule_interval(
+            &trigger_condition(60, FrequencyType::Minutes),
+            60
+        ));
+        // not set yet or not used
+        assert!(!is_frequency_below_schedule_interval(
+            &trigger_condition(0, FrequencyType::Minutes),
+            60
+        ));
+        assert!(!is_frequency_below_schedule_interval(
+            &trigger_condition(5, FrequencyType::Cron),
+            60
+        ));
+    }
 }
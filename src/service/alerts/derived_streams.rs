@@ -90,6 +90,11 @@ pub async fn save(
                 ));
             }
         }
+        QueryType::SchemaDrift => {
+            return Err(anyhow::anyhow!(
+                "DerivedStreams do not support schema drift mode"
+            ));
+        }
         _ => {}
     };
     // End input validation
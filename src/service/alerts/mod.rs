@@ -20,8 +20,11 @@ use chrono::{Duration, Utc};
 use config::{
     ider,
     meta::{
-        alerts::{AggFunction, Condition, Operator, QueryCondition, QueryType, TriggerCondition},
-        search::{SearchEventContext, SearchEventType, SqlQuery},
+        alerts::{
+            AggFunction, BaselineCondition, BaselineOutcome, Condition, Operator, QueryCondition,
+            QueryType, TriggerCondition,
+        },
+        search::{validate_time_range, SearchEventContext, SearchEventType, SqlQuery},
         sql::resolve_stream_names,
         stream::StreamType,
     },
@@ -39,6 +42,8 @@ pub mod alert;
 pub mod derived_streams;
 pub mod destinations;
 pub mod scheduler;
+pub mod schema_drift;
+pub mod state_events;
 pub mod templates;
 
 #[async_trait]
@@ -118,6 +123,11 @@ impl QueryConditionExt for QueryCondition {
                     v.to_string()
                 }
             }
+            QueryType::SchemaDrift => {
+                // Schema drift alerts are evaluated from the schema change
+                // events, there is nothing to search for.
+                return Ok((None, end_time));
+            }
             QueryType::PromQL => {
                 let Some(v) = self.promql.as_ref() else {
                     return Ok((None, end_time));
@@ -216,6 +226,7 @@ impl QueryConditionExt for QueryCondition {
 
         // SQL may contain multiple stream names, check for each stream
         // if the query period is greater than the max query range
+        let mut min_max_query_range = 0;
         for stream in stream_names.iter() {
             if let Some(settings) = infra::schema::get_settings(org_id, stream, stream_type).await {
                 let max_query_range = settings.max_query_range;
@@ -224,6 +235,11 @@ impl QueryConditionExt for QueryCondition {
                         "Query period is greater than max query range of {max_query_range} hours for stream \"{stream}\""
                     ));
                 }
+                if max_query_range > 0
+                    && (min_max_query_range == 0 || max_query_range < min_max_query_range)
+                {
+                    min_max_query_range = max_query_range;
+                }
             }
         }
 
@@ -237,12 +253,31 @@ impl QueryConditionExt for QueryCondition {
         } else {
             Some(end_time - time_diff)
         };
+
+        // Validate the range the same way searches do. A clamped range would
+        // evaluate the alert on partial data, so it is an error here.
+        let (mut query_start_time, mut query_end_time) = (start_time.unwrap(), end_time);
+        match validate_time_range(
+            &mut query_start_time,
+            &mut query_end_time,
+            min_max_query_range,
+        ) {
+            Ok(None) => {}
+            Ok(Some(range_error)) => return Err(anyhow::anyhow!("{range_error}")),
+            Err(e) => return Err(e),
+        }
+        let (start_time, end_time) = (Some(query_start_time), query_end_time);
         let size = if self.search_event_type.is_some() {
             -1
         } else {
             std::cmp::max(100, trigger_condition.threshold)
         };
         let trace_id = ider::uuid();
+        // the request of the current window, searched again over the baseline
+        // window by the alerts comparing against one. Like the threshold, the
+        // baseline only decides whether an alert fires, a derived stream
+        // (`search_event_type` is set) writes all its rows and ignores both.
+        let mut baseline_req = None;
 
         let resp = if self.multi_time_range.is_some()
             && !self.multi_time_range.as_ref().unwrap().is_empty()
@@ -365,6 +400,8 @@ impl QueryConditionExt for QueryCondition {
                     skip_wal: false,
                     streaming_output: false,
                     streaming_id: None,
+                    timezone: None,
+                    debug_cache: false,
                 },
                 encoding: config::meta::search::RequestEncoding::Empty,
                 regions: vec![],
@@ -373,12 +410,17 @@ impl QueryConditionExt for QueryCondition {
                 search_type,
                 search_event_context,
                 use_cache: None,
+                explain: false,
             };
             log::debug!(
                 "evaluate_scheduled begin to call SearchService::search, {:?}",
                 req
             );
-            SearchService::search(&trace_id, org_id, stream_type, None, &req).await
+            let resp = SearchService::search(&trace_id, org_id, stream_type, None, &req).await;
+            if trigger_condition.baseline.is_some() && self.search_event_type.is_none() {
+                baseline_req = Some(req);
+            }
+            resp
         };
 
         // Resp hits can be of two types -
@@ -414,6 +456,9 @@ impl QueryConditionExt for QueryCondition {
             }
         });
         log::debug!("alert resp hits len:{:#?}", records.len());
+        if let (Some(baseline), Some(req)) = (trigger_condition.baseline.as_ref(), baseline_req) {
+            return evaluate_baseline(org_id, stream_type, baseline, req, records, end_time).await;
+        }
         let records = Some(records);
         if self.search_event_type.is_none() {
             let threshold = trigger_condition.threshold as usize;
@@ -529,6 +574,76 @@ impl ConditionExt for Condition {
     }
 }
 
+/// Searches the window `offset` before the current one with the same query and
+/// compares the two aggregates. A current window without data counts as 0, a
+/// baseline window without data never fires.
+async fn evaluate_baseline(
+    org_id: &str,
+    stream_type: StreamType,
+    baseline: &BaselineCondition,
+    mut req: config::meta::search::Request,
+    records: Vec<Map<String, Value>>,
+    end_time: i64,
+) -> Result<(Option<Vec<Map<String, Value>>>, i64), anyhow::Error> {
+    let Some(offset) = baseline.offset_micros() else {
+        return Err(anyhow::anyhow!(
+            "Invalid baseline offset: {}",
+            baseline.offset
+        ));
+    };
+    req.query.start_time -= offset;
+    req.query.end_time -= offset;
+    let trace_id = ider::uuid();
+    let resp = match SearchService::search(&trace_id, org_id, stream_type, None, &req).await {
+        Ok(v) if v.is_partial => {
+            return Err(anyhow::anyhow!("Partial response: {}", v.function_error));
+        }
+        Ok(v) => v,
+        Err(infra::errors::Error::ErrorCode(e)) => {
+            return Err(anyhow::anyhow!("{}", e.get_message()));
+        }
+        Err(e) => return Err(anyhow::anyhow!("{}", e)),
+    };
+    let baseline_records = resp
+        .hits
+        .into_iter()
+        .filter_map(|hit| match hit {
+            Value::Object(hit) => Some(hit),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let current = baseline.aggregate(&records).unwrap_or_default();
+    match baseline.evaluate(current, baseline.aggregate(&baseline_records)) {
+        BaselineOutcome::Fire {
+            current,
+            baseline: baseline_value,
+        } => {
+            log::debug!(
+                "alert baseline fired, current {current} {} {} x baseline {baseline_value}",
+                baseline.operator,
+                baseline.multiplier
+            );
+            if !records.is_empty() {
+                return Ok((Some(records), end_time));
+            }
+            // the value fell to nothing, there is no row to notify with
+            let mut row = Map::with_capacity(2);
+            row.insert("alert_current_value".to_string(), current.into());
+            row.insert("alert_baseline_value".to_string(), baseline_value.into());
+            Ok((Some(vec![row]), end_time))
+        }
+        BaselineOutcome::NotFire { .. } => Ok((None, end_time)),
+        BaselineOutcome::NoBaseline => {
+            log::info!(
+                "alert baseline has no data {} before the window, not firing",
+                baseline.offset
+            );
+            Ok((None, end_time))
+        }
+    }
+}
+
 async fn build_sql(
     org_id: &str,
     stream_name: &str,
@@ -20,8 +20,9 @@ use config::{
     cluster::LOCAL_NODE,
     get_config,
     meta::{
-        alerts::FrequencyType,
+        alerts::{state::NotificationOutcome, FrequencyType, TriggerCondition},
         dashboards::reports::ReportFrequencyType,
+        pipeline::{components::DerivedStream, Pipeline},
         self_reporting::{
             error::{ErrorData, ErrorSource, PipelineError},
             usage::{TriggerData, TriggerDataStatus, TriggerDataType},
@@ -41,13 +42,20 @@ use proto::cluster_rpc;
 
 use crate::service::{
     alerts::{
-        alert::{get_alert_start_end_time, get_by_name, get_row_column_map, AlertExt},
+        alert::{
+            get_alert_start_end_time, get_by_name, get_row_column_map,
+            send_notification_unless_silenced, AlertExt,
+        },
         derived_streams::DerivedStreamExt,
+        state_events,
     },
-    dashboards::reports::SendReport,
+    dashboards::reports::{schedule_jitter, SendReport},
     db::{self, alerts::alert::set_without_updating_trigger},
     ingestion::ingestion_service,
-    pipeline::batch_execution::ExecutablePipeline,
+    pipeline::{
+        backfill::{BackfillJob, BACKFILL_MARKER_COL},
+        batch_execution::ExecutablePipeline,
+    },
     self_reporting::publish_triggers_usage,
 };
 
@@ -61,6 +69,7 @@ pub async fn handle_triggers(
         db::scheduler::TriggerModule::DerivedStream => {
             handle_derived_stream_triggers(trace_id, trigger).await
         }
+        db::scheduler::TriggerModule::Backfill => handle_backfill_triggers(trace_id, trigger).await,
     }
 }
 
@@ -78,6 +87,12 @@ fn get_max_considerable_delay(frequency: i64) -> i64 {
     std::cmp::min(max_delay, max_considerable_delay)
 }
 
+/// Returns the time between two runs of an alert with a fixed frequency in
+/// microseconds. Alert frequencies are stored in seconds.
+fn alert_frequency_micros(trigger_condition: &TriggerCondition) -> i64 {
+    second_micros(trigger_condition.frequency)
+}
+
 async fn handle_alert_triggers(
     trace_id: &str,
     trigger: db::scheduler::Trigger,
@@ -118,6 +133,23 @@ async fn handle_alert_triggers(
 
     let alert = match get_by_name(&org_id, stream_type, stream_name, alert_name).await? {
         Some(alert) => alert,
+        None if db::alerts::alert::is_trashed(&org_id, stream_type, stream_name, alert_name)
+            .await? =>
+        {
+            // Alerts in the trash are treated as disabled in case their trigger
+            // outlived the deletion, check on next week
+            let new_trigger = db::scheduler::Trigger {
+                next_run_at: Utc::now().timestamp_micros()
+                    + Duration::try_days(7).unwrap().num_microseconds().unwrap(),
+                is_realtime: trigger.is_realtime,
+                is_silenced: true,
+                status: db::scheduler::TriggerStatus::Waiting,
+                retries: 0,
+                ..trigger.clone()
+            };
+            db::scheduler::update_trigger(new_trigger).await?;
+            return Ok(());
+        }
         None => {
             return Err(anyhow::anyhow!(
                 "alert not found: {}/{}/{}/{}",
@@ -155,8 +187,12 @@ async fn handle_alert_triggers(
             period_end_time: None,
             tolerance: 0,
             last_satisfied_at: None,
+            first_period_start: None,
+            ..Default::default()
         }
     };
+    // State events persisted by a run that stopped before emitting them
+    emit_alert_state_events(trace_id, &new_trigger, trigger.retries, &mut trigger_data).await?;
 
     if trigger.retries >= max_retries {
         // It has been tried the maximum time, just update the
@@ -176,10 +212,7 @@ async fn handle_alert_triggers(
                 .unwrap()
                 .timestamp_micros();
         } else {
-            new_trigger.next_run_at += Duration::try_seconds(alert.trigger_condition.frequency)
-                .unwrap()
-                .num_microseconds()
-                .unwrap();
+            new_trigger.next_run_at += alert_frequency_micros(&alert.trigger_condition);
         }
         // Keep the last_satisfied_at field
         trigger_data.reset();
@@ -322,24 +355,23 @@ async fn handle_alert_triggers(
                     .unwrap()
                     .timestamp_micros();
             } else {
-                new_trigger.next_run_at += Duration::try_seconds(alert.trigger_condition.frequency)
-                    .unwrap()
-                    .num_microseconds()
-                    .unwrap();
+                new_trigger.next_run_at += alert_frequency_micros(&alert.trigger_condition);
             }
             trigger_data.reset();
             new_trigger.data = json::to_string(&trigger_data).unwrap();
             trigger_data_stream.next_run_at = new_trigger.next_run_at;
             db::scheduler::update_trigger(new_trigger).await?;
         } else {
-            // update its status and retries
+            // update its status and retries, the data only changes if state
+            // events were emitted
+            let trigger_data = json::to_string(&trigger_data).unwrap();
             db::scheduler::update_status(
                 &new_trigger.org,
                 new_trigger.module,
                 &new_trigger.module_key,
                 db::scheduler::TriggerStatus::Waiting,
                 trigger.retries + 1,
-                None,
+                Some(&trigger_data),
             )
             .await?;
         }
@@ -416,11 +448,7 @@ async fn handle_alert_triggers(
             .timestamp_micros()
             + tolerance;
     } else {
-        new_trigger.next_run_at += Duration::try_seconds(alert.trigger_condition.frequency)
-            .unwrap()
-            .num_microseconds()
-            .unwrap()
-            + tolerance;
+        new_trigger.next_run_at += alert_frequency_micros(&alert.trigger_condition) + tolerance;
     }
     trigger_data_stream.next_run_at = new_trigger.next_run_at;
 
@@ -428,6 +456,12 @@ async fn handle_alert_triggers(
         trigger_data.last_satisfied_at = Some(triggered_at);
     }
 
+    let previous_state = trigger_data.alert_state;
+    let alert_state = previous_state.evaluated(ret.is_some());
+    if let Some(event) = trigger_data.transition_alert(&alert, alert_state, now) {
+        event.set_observed(ret.as_deref().unwrap_or_default());
+    }
+
     // send notification
     if let Some(data) = ret {
         let vars = get_row_column_map(&data);
@@ -449,13 +483,35 @@ async fn handle_alert_triggers(
         );
         trigger_data_stream.start_time = alert_start_time;
         trigger_data_stream.end_time = alert_end_time;
-        match alert
-            .send_notification(&data, end_time, start_time, now)
-            .await
-        {
+        if trigger_data.alert_state != previous_state {
+            persist_alert_state(&new_trigger, trigger.retries, &trigger_data).await?;
+        }
+        match send_notification_unless_silenced(&alert, &data, end_time, start_time, now).await {
             Ok((success_msg, err_msg)) => {
                 let success_msg = success_msg.trim().to_owned();
                 let err_msg = err_msg.trim().to_owned();
+                let alert_state = trigger_data.alert_state.notified(true);
+                if let Some(event) = trigger_data.transition_alert(&alert, alert_state, now) {
+                    let outcome = if alert.is_silenced_at(now) {
+                        NotificationOutcome::Suppressed
+                    } else if !err_msg.is_empty() {
+                        NotificationOutcome::PartiallySent
+                    } else {
+                        NotificationOutcome::Sent
+                    };
+                    event.set_observed(&data);
+                    event.set_notification(outcome, &success_msg);
+                }
+                if !trigger_data.pending_state_events.is_empty() {
+                    persist_alert_state(&new_trigger, trigger.retries, &trigger_data).await?;
+                    emit_alert_state_events(
+                        trace_id,
+                        &new_trigger,
+                        trigger.retries,
+                        &mut trigger_data,
+                    )
+                    .await?;
+                }
                 if !err_msg.is_empty() {
                     log::error!(
                         "[SCHEDULER trace_id {trace_id}] Some notifications for alert {}/{} could not be sent: {err_msg}",
@@ -488,6 +544,10 @@ async fn handle_alert_triggers(
                     &new_trigger.org,
                     &new_trigger.module_key
                 );
+                // The state does not change when notifying fails, the transition
+                // of the evaluation was persisted before notifying
+                emit_alert_state_events(trace_id, &new_trigger, trigger.retries, &mut trigger_data)
+                    .await?;
                 if trigger.retries + 1 >= max_retries {
                     // It has been tried the maximum time, just update the
                     // next_run_at to the next expected trigger time
@@ -534,6 +594,11 @@ async fn handle_alert_triggers(
             &new_trigger.org,
             &new_trigger.module_key
         );
+        if !trigger_data.pending_state_events.is_empty() {
+            persist_alert_state(&new_trigger, trigger.retries, &trigger_data).await?;
+            emit_alert_state_events(trace_id, &new_trigger, trigger.retries, &mut trigger_data)
+                .await?;
+        }
         // Condition did not match, store the last used end_time in the triggers
         // In the next run, the alert will be checked from the last end_time
         trigger_data.period_end_time = if should_store_last_end_time {
@@ -567,6 +632,62 @@ async fn handle_alert_triggers(
     Ok(())
 }
 
+/// Persists the trigger data, with the alert state and the queued state
+/// events, while the trigger is still processing. Transitions are persisted
+/// before they are notified or emitted, so that a restarted scheduler emits
+/// them instead of losing them.
+async fn persist_alert_state(
+    trigger: &db::scheduler::Trigger,
+    retries: i32,
+    trigger_data: &ScheduledTriggerData,
+) -> Result<(), anyhow::Error> {
+    let trigger_data = json::to_string(trigger_data)?;
+    db::scheduler::update_status(
+        &trigger.org,
+        trigger.module.clone(),
+        &trigger.module_key,
+        db::scheduler::TriggerStatus::Processing,
+        retries,
+        Some(&trigger_data),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Emits the persisted state events of the trigger data, and persists the
+/// trigger data without them right away so that a restarted scheduler does
+/// not emit them again. The events that could not be emitted stay queued for
+/// the next run of the trigger.
+async fn emit_alert_state_events(
+    trace_id: &str,
+    trigger: &db::scheduler::Trigger,
+    retries: i32,
+    trigger_data: &mut ScheduledTriggerData,
+) -> Result<(), anyhow::Error> {
+    let events = trigger_data.pending_state_events.len();
+    if events == 0 {
+        return Ok(());
+    }
+    match state_events::emit(&trigger.org, trigger_data).await {
+        Ok(()) => {
+            log::debug!(
+                "[SCHEDULER trace_id {trace_id}] emitted {events} state events of alert {}/{}",
+                &trigger.org,
+                &trigger.module_key
+            );
+            persist_alert_state(trigger, retries, trigger_data).await
+        }
+        Err(e) => {
+            log::error!(
+                "[SCHEDULER trace_id {trace_id}] Failed to emit state events of alert {}/{}: {e}",
+                &trigger.org,
+                &trigger.module_key
+            );
+            Ok(())
+        }
+    }
+}
+
 async fn handle_report_triggers(
     trace_id: &str,
     trigger: db::scheduler::Trigger,
@@ -643,11 +764,14 @@ async fn handle_report_triggers(
             let schedule = Schedule::from_str(&report.frequency.cron)?;
             // tz_offset is in minutes
             let tz_offset = FixedOffset::east_opt(report.tz_offset * 60).unwrap();
+            // reports sharing the same cron would all launch chromium at once,
+            // so each report is shifted by its own offset
             new_trigger.next_run_at = schedule
                 .upcoming(tz_offset)
                 .next()
                 .unwrap()
-                .timestamp_micros();
+                .timestamp_micros()
+                + schedule_jitter(org_id, report_name);
         }
     }
 
@@ -808,18 +932,15 @@ async fn handle_derived_stream_triggers(
             pipeline_id,
         ));
     };
-    let start_time = if trigger.data.is_empty() {
+    let trigger_data: Option<ScheduledTriggerData> = if trigger.data.is_empty() {
         None
     } else {
-        let trigger_data: Option<ScheduledTriggerData> = json::from_str(&trigger.data).ok();
-        if let Some(trigger_data) = trigger_data {
-            trigger_data
-                .period_end_time
-                .map(|period_end_time| period_end_time + 1)
-        } else {
-            None
-        }
+        json::from_str(&trigger.data).ok()
     };
+    let start_time = trigger_data
+        .as_ref()
+        .and_then(|trigger_data| trigger_data.period_end_time)
+        .map(|period_end_time| period_end_time + 1);
 
     // in case the range [start_time, end_time] is greater than querying period, it needs to
     // evaluate and ingest 1 period at a time.
@@ -833,6 +954,11 @@ async fn handle_derived_stream_triggers(
     } else {
         (None, now)
     };
+    // remember where the live schedule started, backfills must stop there
+    let first_period_start = trigger_data
+        .and_then(|trigger_data| trigger_data.first_period_start)
+        .or(start)
+        .unwrap_or(end - period_num_microseconds);
 
     let mut new_trigger = db::scheduler::Trigger {
         next_run_at: Utc::now().timestamp_micros(),
@@ -873,194 +999,42 @@ async fn handle_derived_stream_triggers(
             source_node: Some(LOCAL_NODE.name.clone()),
         };
 
-        // evaluate trigger and configure trigger next run time
-        match derived_stream
-            .evaluate((start, end), &trigger.module_key)
-            .await
+        match process_derived_stream_period(
+            trace_id,
+            &pipeline,
+            &derived_stream,
+            &trigger.module_key,
+            (start, end),
+            None,
+        )
+        .await
         {
-            Err(e) => {
-                let err_msg = format!(
-                    "Source node DerivedStream QueryCondition error during query evaluation, caused by {}",
-                    e
-                );
-                log::error!("[SCHEDULER trace_id {trace_id}] pipeline org/name({}/{}): source node DerivedStream failed at QueryCondition evaluation with error: {}", pipeline.org, pipeline.name, e);
-
+            Err(err_msg) => {
                 // update TriggerData that's to be reported to _meta
                 trigger_data_stream.status = TriggerDataStatus::Failed;
                 trigger_data_stream.error = Some(err_msg.clone());
                 trigger_data_stream.retries += 1;
 
-                // report pipeline error
-                let pipeline_error = PipelineError {
-                    pipeline_id: pipeline.id.to_string(),
-                    pipeline_name: pipeline.name.to_string(),
-                    error: Some(err_msg),
-                    node_errors: HashMap::new(),
-                };
-                crate::service::self_reporting::publish_error(ErrorData {
-                    _timestamp: Utc::now().timestamp_micros(),
-                    stream_params: pipeline.get_source_stream_params(),
-                    error_source: ErrorSource::Pipeline(pipeline_error),
-                })
-                .await;
+                report_pipeline_error(&pipeline, err_msg).await;
 
                 // incr trigger retry count
                 new_trigger.retries += 1;
-                // set end to now to exit the loop below
+                // set end to now to exit the loop below but not moving time range forward
                 end = now + 1;
             }
-            Ok((ret, next)) => {
-                let is_satisfied = ret.as_ref().map_or(false, |ret| !ret.is_empty());
-
-                // ingest evaluation result into destination
-                if is_satisfied {
-                    log::info!(
-                        "[SCHEDULER trace_id {trace_id}] DerivedStream(org: {}/module_key: {}): query conditions satisfied. Result to be processed and ingested",
-                        new_trigger.org,
-                        new_trigger.module_key
-                    );
-
-                    let local_val = ret // checked is some
-                        .unwrap()
-                        .into_iter()
-                        .map(json::Value::Object)
-                        .collect::<Vec<_>>();
-
-                    // pass search results to pipeline to get modified results before ingesting
-                    let mut json_data_by_stream: HashMap<StreamParams, Vec<json::Value>> =
-                        HashMap::new();
-                    let mut ingestion_error_msg = None;
-
-                    match ExecutablePipeline::new(&pipeline).await {
-                        Err(e) => {
-                            let err_msg = format!(
-                                "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to initialize to ExecutablePipeline. Caused by: {}",
-                                org_id, pipeline_name, e
-                            );
-                            log::error!("{err_msg}");
-                            ingestion_error_msg = Some(err_msg);
-                        }
-                        Ok(exec_pl) => match exec_pl.process_batch(org_id, local_val).await {
-                            Err(e) => {
-                                let err_msg = format!(
-                                    "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to process DerivedStream query results. Caused by: {}",
-                                    org_id, pipeline_name, e
-                                );
-                                log::error!("{err_msg}");
-                                ingestion_error_msg = Some(err_msg);
-                            }
-                            Ok(pl_results) => {
-                                for (stream_params, stream_pl_results) in pl_results {
-                                    if matches!(
-                                        stream_params.stream_type,
-                                        StreamType::Logs
-                                            | StreamType::EnrichmentTables
-                                            | StreamType::Metrics
-                                            | StreamType::Traces
-                                    ) {
-                                        let (_, results): (Vec<_>, Vec<_>) =
-                                            stream_pl_results.into_iter().unzip();
-                                        json_data_by_stream
-                                            .entry(stream_params)
-                                            .or_default()
-                                            .extend(results);
-                                    }
-                                }
-                            }
-                        },
-                    };
-
-                    // Ingest result into destination stream
-                    if ingestion_error_msg.is_none() {
-                        for (dest_stream, records) in json_data_by_stream {
-                            // need to get the metadata from the destination node with the same
-                            // stream_params since this is a scheduled
-                            // pipeline, only the destination node can be of stream node.
-                            let request_metadata = pipeline
-                                .get_metadata_by_stream_params(&dest_stream)
-                                .map(|meta| cluster_rpc::IngestRequestMetadata { data: meta });
-                            let (org_id, stream_name, stream_type): (String, String, String) = {
-                                (
-                                    dest_stream.org_id.into(),
-                                    dest_stream.stream_name.into(),
-                                    dest_stream.stream_type.to_string(),
-                                )
-                            };
-                            let req = cluster_rpc::IngestionRequest {
-                                org_id: org_id.clone(),
-                                stream_name: stream_name.clone(),
-                                stream_type: stream_type.clone(),
-                                data: Some(cluster_rpc::IngestionData::from(records)),
-                                ingestion_type: Some(cluster_rpc::IngestionType::Json.into()),
-                                metadata: request_metadata,
-                            };
-                            match ingestion_service::ingest(req).await {
-                                Ok(resp) if resp.status_code == 200 => {
-                                    log::info!(
-                                        "[SCHEDULER trace_id {trace_id}] DerivedStream result ingested to destination {org_id}/{stream_name}/{stream_type}",
-                                    );
-                                }
-                                error => {
-                                    let err =
-                                        error.map_or_else(|e| e.to_string(), |resp| resp.message);
-                                    log::error!(
-                                        "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to ingest processed results to destination {}/{}/{}, caused by {}",
-                                        pipeline.org,
-                                        pipeline.name,
-                                        org_id,
-                                        stream_name,
-                                        stream_type,
-                                        err
-                                    );
-                                    ingestion_error_msg = Some(err);
-                                    break;
-                                }
-                            };
-                        }
-                    }
-
-                    if let Some(err) = ingestion_error_msg {
-                        // FAIL: update new_trigger, trigger_data_stream, and
-                        new_trigger.retries += 1;
-
-                        // trigger_data_stream
-                        trigger_data_stream.status = TriggerDataStatus::Failed;
-                        trigger_data_stream.error = Some(err.clone());
-                        trigger_data_stream.retries += 1;
-
-                        // report pipeline error
-                        let pipeline_error = PipelineError {
-                            pipeline_id: pipeline.id.to_string(),
-                            pipeline_name: pipeline.name.to_string(),
-                            error: Some(err),
-                            node_errors: HashMap::new(),
-                        };
-                        crate::service::self_reporting::publish_error(ErrorData {
-                            _timestamp: Utc::now().timestamp_micros(),
-                            stream_params: pipeline.get_source_stream_params(),
-                            error_source: ErrorSource::Pipeline(pipeline_error),
-                        })
-                        .await;
-
-                        // set end to now to exit the loop below but not moving time range forward
-                        end = now + 1;
-                    } else {
-                        // SUCCESS: move the time range forward by frequency and continue
-                        start = Some(next);
-                        end += period_num_microseconds + 1;
-                    }
-                } else {
+            Ok((is_satisfied, next)) => {
+                if !is_satisfied {
                     log::info!(
                         "[SCHEDULER trace_id {trace_id}] DerivedStream condition does not match any data for the period, org: {}, module_key: {}",
                         &new_trigger.org,
                         &new_trigger.module_key
                     );
                     trigger_data_stream.status = TriggerDataStatus::ConditionNotSatisfied;
-
-                    // move the time range forward by frequency and continue
-                    start = Some(next);
-                    end += period_num_microseconds + 1;
                 }
+
+                // move the time range forward by frequency and continue
+                start = Some(next);
+                end += period_num_microseconds + 1;
             }
         };
 
@@ -1072,6 +1046,8 @@ async fn handle_derived_stream_triggers(
                     period_end_time: Some(start_time), // updated start_time as end_time
                     tolerance: 0,
                     last_satisfied_at: None,
+                    first_period_start: Some(first_period_start),
+                    ..Default::default()
                 })
                 .unwrap();
             }
@@ -1121,18 +1097,7 @@ async fn handle_derived_stream_triggers(
         let err_msg = format!(
             "[SCHEDULER trace_id {trace_id}] DerivedStream has reached max retries of {max_retries}. Pipeline is being paused. Please fix reported errors before unpausing the pipeline."
         );
-        let pipeline_error = PipelineError {
-            pipeline_id: pipeline.id.to_string(),
-            pipeline_name: pipeline.name.to_string(),
-            error: Some(err_msg),
-            node_errors: HashMap::new(),
-        };
-        crate::service::self_reporting::publish_error(ErrorData {
-            _timestamp: Utc::now().timestamp_micros(),
-            stream_params: pipeline.get_source_stream_params(),
-            error_source: ErrorSource::Pipeline(pipeline_error),
-        })
-        .await;
+        report_pipeline_error(&pipeline, err_msg).await;
 
         // 3. pause the pipeline
         pipeline.enabled = false;
@@ -1155,3 +1120,329 @@ async fn handle_derived_stream_triggers(
 
     Ok(())
 }
+
+/// Evaluates the derived stream for the given period, passes the results
+/// through the pipeline and ingests them into the destination streams.
+///
+/// A backfill passes its `backfill_marker`, which is added to every ingested
+/// record so that the records of a window processed twice can be deduplicated.
+///
+/// Returns whether the query matched any data and the end of the evaluated
+/// period, or the error message of the failed step.
+async fn process_derived_stream_period(
+    trace_id: &str,
+    pipeline: &Pipeline,
+    derived_stream: &DerivedStream,
+    module_key: &str,
+    (start, end): (Option<i64>, i64),
+    backfill_marker: Option<&str>,
+) -> Result<(bool, i64), String> {
+    let org_id = &pipeline.org;
+    let pipeline_name = &pipeline.name;
+    // evaluate trigger and configure trigger next run time
+    let (ret, next) = match derived_stream.evaluate((start, end), module_key).await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("[SCHEDULER trace_id {trace_id}] pipeline org/name({}/{}): source node DerivedStream failed at QueryCondition evaluation with error: {}", pipeline.org, pipeline.name, e);
+            return Err(format!(
+                "Source node DerivedStream QueryCondition error during query evaluation, caused by {}",
+                e
+            ));
+        }
+    };
+    let Some(ret) = ret.filter(|ret| !ret.is_empty()) else {
+        return Ok((false, next));
+    };
+
+    // ingest evaluation result into destination
+    log::info!(
+        "[SCHEDULER trace_id {trace_id}] DerivedStream(org: {}/module_key: {}): query conditions satisfied. Result to be processed and ingested",
+        org_id,
+        module_key
+    );
+
+    let local_val = ret.into_iter().map(json::Value::Object).collect::<Vec<_>>();
+
+    // pass search results to pipeline to get modified results before ingesting
+    let mut json_data_by_stream: HashMap<StreamParams, Vec<json::Value>> = HashMap::new();
+    let exec_pl = match ExecutablePipeline::new(pipeline).await {
+        Ok(exec_pl) => exec_pl,
+        Err(e) => {
+            let err_msg = format!(
+                "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to initialize to ExecutablePipeline. Caused by: {}",
+                org_id, pipeline_name, e
+            );
+            log::error!("{err_msg}");
+            return Err(err_msg);
+        }
+    };
+    match exec_pl.process_batch(org_id, local_val).await {
+        Err(e) => {
+            let err_msg = format!(
+                "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to process DerivedStream query results. Caused by: {}",
+                org_id, pipeline_name, e
+            );
+            log::error!("{err_msg}");
+            return Err(err_msg);
+        }
+        Ok(pl_results) => {
+            for (stream_params, stream_pl_results) in pl_results {
+                if matches!(
+                    stream_params.stream_type,
+                    StreamType::Logs
+                        | StreamType::EnrichmentTables
+                        | StreamType::Metrics
+                        | StreamType::Traces
+                ) {
+                    let (_, results): (Vec<_>, Vec<_>) = stream_pl_results.into_iter().unzip();
+                    json_data_by_stream
+                        .entry(stream_params)
+                        .or_default()
+                        .extend(results);
+                }
+            }
+        }
+    };
+
+    // Ingest result into destination stream
+    for (dest_stream, mut records) in json_data_by_stream {
+        if let Some(marker) = backfill_marker {
+            for record in records.iter_mut() {
+                if let Some(record) = record.as_object_mut() {
+                    record.insert(BACKFILL_MARKER_COL.to_string(), marker.into());
+                }
+            }
+        }
+        // need to get the metadata from the destination node with the same
+        // stream_params since this is a scheduled
+        // pipeline, only the destination node can be of stream node.
+        let request_metadata = pipeline
+            .get_metadata_by_stream_params(&dest_stream)
+            .map(|meta| cluster_rpc::IngestRequestMetadata { data: meta });
+        let (org_id, stream_name, stream_type): (String, String, String) = {
+            (
+                dest_stream.org_id.into(),
+                dest_stream.stream_name.into(),
+                dest_stream.stream_type.to_string(),
+            )
+        };
+        let req = cluster_rpc::IngestionRequest {
+            org_id: org_id.clone(),
+            stream_name: stream_name.clone(),
+            stream_type: stream_type.clone(),
+            data: Some(cluster_rpc::IngestionData::from(records)),
+            ingestion_type: Some(cluster_rpc::IngestionType::Json.into()),
+            metadata: request_metadata,
+        };
+        match ingestion_service::ingest(req).await {
+            Ok(resp) if resp.status_code == 200 => {
+                log::info!(
+                    "[SCHEDULER trace_id {trace_id}] DerivedStream result ingested to destination {org_id}/{stream_name}/{stream_type}",
+                );
+            }
+            error => {
+                let err = error.map_or_else(|e| e.to_string(), |resp| resp.message);
+                log::error!(
+                    "[SCHEDULER trace_id {trace_id}] Pipeline org/name({}/{}) failed to ingest processed results to destination {}/{}/{}, caused by {}",
+                    pipeline.org,
+                    pipeline.name,
+                    org_id,
+                    stream_name,
+                    stream_type,
+                    err
+                );
+                return Err(err);
+            }
+        };
+    }
+
+    Ok((true, next))
+}
+
+/// Reports the error of a scheduled pipeline to the errors stream.
+async fn report_pipeline_error(pipeline: &Pipeline, err_msg: String) {
+    let pipeline_error = PipelineError {
+        pipeline_id: pipeline.id.to_string(),
+        pipeline_name: pipeline.name.to_string(),
+        error: Some(err_msg),
+        node_errors: HashMap::new(),
+    };
+    crate::service::self_reporting::publish_error(ErrorData {
+        _timestamp: Utc::now().timestamp_micros(),
+        stream_params: pipeline.get_source_stream_params(),
+        error_source: ErrorSource::Pipeline(pipeline_error),
+    })
+    .await;
+}
+
+/// Processes the windows of a backfill job until it is done or it has used
+/// half of the scheduler timeout. The progress is saved after every window, a
+/// job interrupted by a restart resumes after its last processed window once
+/// the scheduler times out the trigger.
+async fn handle_backfill_triggers(
+    trace_id: &str,
+    trigger: db::scheduler::Trigger,
+) -> Result<(), anyhow::Error> {
+    let (_, max_retries) = get_scheduler_max_retries();
+    let Ok(mut job) = json::from_str::<BackfillJob>(&trigger.data) else {
+        db::scheduler::delete(&trigger.org, trigger.module, &trigger.module_key).await?;
+        return Err(anyhow::anyhow!(
+            "[SCHEDULER trace_id {trace_id}] Invalid backfill job {}, deleting the trigger",
+            trigger.module_key
+        ));
+    };
+
+    let pipeline = match db::pipeline::get_by_id(&job.pipeline_id).await {
+        Ok(pipeline) if pipeline.enabled => pipeline.get_derived_stream().map(|ds| (pipeline, ds)),
+        Ok(_) => {
+            // wait for the pipeline to be enabled again
+            let new_trigger = db::scheduler::Trigger {
+                next_run_at: Utc::now().timestamp_micros()
+                    + second_micros(get_config().limit.derived_stream_schedule_interval),
+                status: db::scheduler::TriggerStatus::Waiting,
+                ..trigger
+            };
+            db::scheduler::update_trigger(new_trigger).await?;
+            return Ok(());
+        }
+        Err(_) => None,
+    };
+    let Some((pipeline, derived_stream)) = pipeline else {
+        job.fail("Scheduled pipeline of the backfill not found".to_string());
+        let new_trigger = db::scheduler::Trigger {
+            status: db::scheduler::TriggerStatus::Completed,
+            data: json::to_string(&job)?,
+            ..trigger
+        };
+        db::scheduler::update_trigger(new_trigger).await?;
+        return Ok(());
+    };
+
+    let deadline = Instant::now()
+        + std::time::Duration::from_secs(
+            (get_config().limit.report_schedule_timeout / 2).max(1) as u64
+        );
+    let mut retries = trigger.retries;
+    while let Some((start, end)) = job.next_window() {
+        let marker = job.window_marker(start);
+        match process_derived_stream_period(
+            trace_id,
+            &pipeline,
+            &derived_stream,
+            &trigger.module_key,
+            (Some(start), end),
+            Some(&marker),
+        )
+        .await
+        {
+            Ok(_) => {
+                job.complete_window(end);
+                retries = 0;
+            }
+            Err(err_msg) => {
+                retries += 1;
+                report_pipeline_error(&pipeline, err_msg.clone()).await;
+                if retries >= max_retries {
+                    job.fail(err_msg);
+                } else {
+                    job.error = Some(err_msg);
+                }
+                break;
+            }
+        }
+        if job.is_finished() || Instant::now() >= deadline {
+            break;
+        }
+        // save the progress, so that a restart resumes after this window
+        if let Err(e) = db::scheduler::update_status(
+            &trigger.org,
+            trigger.module.clone(),
+            &trigger.module_key,
+            db::scheduler::TriggerStatus::Processing,
+            retries,
+            Some(&json::to_string(&job)?),
+        )
+        .await
+        {
+            log::warn!(
+                "[SCHEDULER trace_id {trace_id}] Backfill {} failed to save its progress: {}",
+                trigger.module_key,
+                e
+            );
+        }
+    }
+
+    let new_trigger = db::scheduler::Trigger {
+        next_run_at: Utc::now().timestamp_micros()
+            + backfill_retry_delay(
+                retries,
+                second_micros(get_config().limit.derived_stream_schedule_interval),
+            ),
+        status: if job.is_finished() {
+            db::scheduler::TriggerStatus::Completed
+        } else {
+            db::scheduler::TriggerStatus::Waiting
+        },
+        retries,
+        data: json::to_string(&job)?,
+        ..trigger
+    };
+    db::scheduler::update_trigger(new_trigger).await?;
+    Ok(())
+}
+
+/// Delay before the next run of a backfill which failed `retries` times in a
+/// row, doubling from 10 seconds up to `max_delay`
+fn backfill_retry_delay(retries: i32, max_delay: i64) -> i64 {
+    if retries <= 0 {
+        return 0;
+    }
+    second_micros(10)
+        .saturating_mul(1i64 << (retries - 1).min(30))
+        .min(max_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::http::models::alerts as alert_models;
+
+    fn frequency_micros_of(body: &str) -> i64 {
+        let trigger_condition: alert_models::TriggerCondition = json::from_str(body).unwrap();
+        alert_frequency_micros(&trigger_condition.into())
+    }
+
+    #[test]
+    fn test_backfill_retry_delay() {
+        let max_delay = second_micros(300);
+        assert_eq!(backfill_retry_delay(0, max_delay), 0);
+        assert_eq!(backfill_retry_delay(1, max_delay), second_micros(10));
+        assert_eq!(backfill_retry_delay(2, max_delay), second_micros(20));
+        assert_eq!(backfill_retry_delay(3, max_delay), second_micros(40));
+        assert_eq!(backfill_retry_delay(6, max_delay), max_delay);
+        assert_eq!(backfill_retry_delay(100, max_delay), max_delay);
+    }
+
+    #[test]
+    fn test_alert_frequency_micros() {
+        let trigger_condition = TriggerCondition {
+            frequency: 300,
+            ..Default::default()
+        };
+        assert_eq!(
+            alert_frequency_micros(&trigger_condition),
+            second_micros(300)
+        );
+
+        // an alert saved through the v2 API runs every 5 minutes, whichever
+        // field name the client used
+        assert_eq!(
+            frequency_micros_of(r#"{"period":10,"frequency_seconds":300}"#),
+            second_micros(300)
+        );
+        assert_eq!(
+            frequency_micros_of(r#"{"period":10,"frequency":5}"#),
+            second_micros(300)
+        );
+    }
+}
@@ -0,0 +1,445 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Schema drift alerts.
+//!
+//! The schema update path emits a [`SchemaDriftEvent`] whenever fields are
+//! added to a stream or change type. Each ingester buffers its events in
+//! memory and stores them in the db once per `ZO_SCHEMA_DRIFT_ALERT_INTERVAL`,
+//! where a single alert manager, the leader, evaluates the events of all the
+//! ingesters. A burst of changes matching an alert is sent as a single
+//! notification.
+
+use arrow_schema::Schema;
+use chrono::Utc;
+use config::{
+    cluster::LOCAL_NODE,
+    meta::{
+        alerts::{alert::Alert, QueryType},
+        cluster::{Node, NodeStatus},
+        stream::StreamType,
+    },
+    utils::json::{Map, Value},
+    FxIndexMap, TIMESTAMP_COL_NAME,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::{
+    common::infra::{cluster::get_cached_nodes, config::STREAM_ALERTS},
+    service::{alerts::alert::send_notification_unless_silenced, db},
+};
+
+const CHANNEL_SIZE: usize = 10240;
+
+type DriftChannel = (
+    mpsc::Sender<SchemaDriftEvent>,
+    Mutex<mpsc::Receiver<SchemaDriftEvent>>,
+);
+
+static DRIFT_EVENTS: Lazy<DriftChannel> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+    (tx, Mutex::new(rx))
+});
+
+/// A field that was added to a stream schema or whose type changed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldDrift {
+    pub name: String,
+    /// `None` if the field is new.
+    pub old_type: Option<String>,
+    pub new_type: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SchemaDriftEvent {
+    pub org_id: String,
+    pub stream_type: StreamType,
+    pub stream_name: String,
+    pub fields: Vec<FieldDrift>,
+    /// Timestamp of the record that caused the schema change.
+    pub record_ts: i64,
+}
+
+/// Returns the fields of `new` that are missing in `old` or have another type.
+pub fn diff_schema(old: &Schema, new: &Schema) -> Vec<FieldDrift> {
+    new.fields()
+        .iter()
+        .filter_map(|field| {
+            let new_type = field.data_type();
+            match old.field_with_name(field.name()) {
+                Ok(old_field) if old_field.data_type() == new_type => None,
+                Ok(old_field) => Some(FieldDrift {
+                    name: field.name().to_string(),
+                    old_type: Some(old_field.data_type().to_string()),
+                    new_type: new_type.to_string(),
+                }),
+                Err(_) => Some(FieldDrift {
+                    name: field.name().to_string(),
+                    old_type: None,
+                    new_type: new_type.to_string(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Queues the schema changes of a stream for the schema drift alerts. The
+/// event is dropped if the queue is full, ingestion never waits on alerts.
+pub fn emit(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    fields: Vec<FieldDrift>,
+    record_ts: i64,
+) {
+    if fields.is_empty() {
+        return;
+    }
+    let event = SchemaDriftEvent {
+        org_id: org_id.to_string(),
+        stream_type,
+        stream_name: stream_name.to_string(),
+        fields,
+        record_ts,
+    };
+    if let Err(e) = DRIFT_EVENTS.0.try_send(event) {
+        log::warn!(
+            "[SCHEMA_DRIFT] dropped schema change event of [{}/{}/{}]: {}",
+            org_id,
+            stream_type,
+            stream_name,
+            e
+        );
+    }
+}
+
+/// Returns true if `stream_name` matches `pattern`, where `*` matches any
+/// sequence of characters.
+fn matches_stream(pattern: &str, stream_name: &str) -> bool {
+    if !pattern.contains('*') {
+        return pattern == stream_name;
+    }
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !stream_name.starts_with(first) || stream_name.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &stream_name[first.len()..stream_name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    stream_name.ends_with(last)
+}
+
+fn is_watching(alert: &Alert, event: &SchemaDriftEvent) -> bool {
+    alert.enabled
+        && alert.query_condition.query_type == QueryType::SchemaDrift
+        && alert.org_id == event.org_id
+        && alert.stream_type == event.stream_type
+        && matches_stream(&alert.stream_name, &event.stream_name)
+}
+
+/// Matches the events of one evaluation window against the schema drift
+/// alerts and returns the notification rows of every alert that matched,
+/// one row per changed field.
+///
+/// A field that changed several times in the window is reported once, from
+/// its type before the window to its latest type.
+pub fn collect_notifications(
+    alerts: &[Alert],
+    events: &[SchemaDriftEvent],
+) -> Vec<(Alert, Vec<Map<String, Value>>)> {
+    let mut notifications = Vec::new();
+    for alert in alerts {
+        let mut changes: FxIndexMap<(&str, &str), (Option<&str>, &str, i64)> =
+            FxIndexMap::default();
+        for event in events.iter().filter(|event| is_watching(alert, event)) {
+            for field in event.fields.iter() {
+                changes
+                    .entry((event.stream_name.as_str(), field.name.as_str()))
+                    .and_modify(|(_, new_type, record_ts)| {
+                        *new_type = field.new_type.as_str();
+                        *record_ts = event.record_ts;
+                    })
+                    .or_insert((
+                        field.old_type.as_deref(),
+                        field.new_type.as_str(),
+                        event.record_ts,
+                    ));
+            }
+        }
+        let rows = changes
+            .into_iter()
+            .filter(|(_, (old_type, new_type, _))| *old_type != Some(*new_type))
+            .map(|((stream_name, field), (old_type, new_type, record_ts))| {
+                let change = if old_type.is_some() {
+                    "type_changed"
+                } else {
+                    "added"
+                };
+                let mut row = Map::with_capacity(6);
+                row.insert("stream_name".to_string(), stream_name.into());
+                row.insert("field".to_string(), field.into());
+                row.insert("change".to_string(), change.into());
+                row.insert(
+                    "old_type".to_string(),
+                    old_type.map_or(Value::Null, Value::from),
+                );
+                row.insert("new_type".to_string(), new_type.into());
+                // reference to the record that caused the change
+                row.insert(TIMESTAMP_COL_NAME.to_string(), record_ts.into());
+                row
+            })
+            .collect::<Vec<_>>();
+        if !rows.is_empty() {
+            notifications.push((alert.clone(), rows));
+        }
+    }
+    notifications
+}
+
+/// Drains the queued schema change events of this node into the db, for the
+/// leader to evaluate. Returns the number of events stored.
+pub async fn flush_pending() -> usize {
+    let mut events = Vec::new();
+    {
+        let mut rx = DRIFT_EVENTS.1.lock().await;
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+    }
+    if events.is_empty() {
+        return 0;
+    }
+    if let Err(e) = db::schema_drift::push(&LOCAL_NODE.name, &events).await {
+        log::error!(
+            "[SCHEMA_DRIFT] dropped {} schema change events: {e}",
+            events.len()
+        );
+        return 0;
+    }
+    events.len()
+}
+
+/// Whether this node sends the schema drift notifications, the online alert
+/// manager with the smallest name does. Without the cluster nodes cached, e.g.
+/// in local mode, any alert manager does.
+pub async fn is_leader() -> bool {
+    if !LOCAL_NODE.is_alert_manager() {
+        return false;
+    }
+    let nodes =
+        get_cached_nodes(|node| node.is_alert_manager() && node.status == NodeStatus::Online).await;
+    is_leader_among(nodes.as_deref(), &LOCAL_NODE.name)
+}
+
+fn is_leader_among(nodes: Option<&[Node]>, name: &str) -> bool {
+    match nodes.and_then(|nodes| nodes.iter().map(|node| node.name.as_str()).min()) {
+        Some(leader) => leader == name,
+        None => true,
+    }
+}
+
+/// Takes the schema change events stored by all the ingesters and notifies the
+/// matching schema drift alerts, only on the leader. Returns the number of
+/// notifications sent.
+pub async fn evaluate_pending() -> usize {
+    if !is_leader().await {
+        return 0;
+    }
+    let events = match db::schema_drift::take().await {
+        Ok(events) => events,
+        Err(e) => {
+            log::error!("[SCHEMA_DRIFT] failed to load schema change events: {e}");
+            return 0;
+        }
+    };
+    if events.is_empty() {
+        return 0;
+    }
+
+    let alerts = STREAM_ALERTS
+        .read()
+        .await
+        .values()
+        .flatten()
+        .filter(|alert| alert.enabled && alert.query_condition.query_type == QueryType::SchemaDrift)
+        .cloned()
+        .collect::<Vec<_>>();
+    if alerts.is_empty() {
+        return 0;
+    }
+
+    let now = Utc::now().timestamp_micros();
+    let mut sent = 0;
+    for (alert, rows) in collect_notifications(&alerts, &events) {
+        match send_notification_unless_silenced(&alert, &rows, now, None, now).await {
+            Ok(_) => sent += 1,
+            Err(e) => log::error!(
+                "[SCHEMA_DRIFT] failed to notify alert {}/{}: {}",
+                alert.org_id,
+                alert.get_unique_key(),
+                e
+            ),
+        }
+    }
+    sent
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::{DataType, Field};
+
+    use super::*;
+
+    fn drift_alert(stream_name: &str) -> Alert {
+        let mut alert = Alert {
+            name: "drift".to_string(),
+            org_id: "default".to_string(),
+            stream_type: StreamType::Logs,
+            stream_name: stream_name.to_string(),
+            enabled: true,
+            destinations: vec!["ops".to_string()],
+            ..Default::default()
+        };
+        alert.query_condition.query_type = QueryType::SchemaDrift;
+        alert
+    }
+
+    fn event(stream_name: &str, old: &Schema, new: &Schema, record_ts: i64) -> SchemaDriftEvent {
+        SchemaDriftEvent {
+            org_id: "default".to_string(),
+            stream_type: StreamType::Logs,
+            stream_name: stream_name.to_string(),
+            fields: diff_schema(old, new),
+            record_ts,
+        }
+    }
+
+    #[test]
+    fn test_watched_stream_produces_one_notification() {
+        let v1 = Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("status", DataType::Int64, true),
+        ]);
+        let v2 = Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("status", DataType::Utf8, true),
+            Field::new("user", DataType::Utf8, true),
+        ]);
+        let v3 = Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("status", DataType::Utf8, true),
+            Field::new("user", DataType::Utf8, true),
+            Field::new("region", DataType::Utf8, true),
+        ]);
+        let events = vec![event("app_web", &v1, &v2, 1), event("app_web", &v2, &v3, 2)];
+
+        let notifications = collect_notifications(&[drift_alert("app_*")], &events);
+        assert_eq!(notifications.len(), 1);
+        let rows = &notifications[0].1;
+        let fields = rows
+            .iter()
+            .map(|row| {
+                (
+                    row["field"].as_str().unwrap(),
+                    row["old_type"].as_str(),
+                    row["new_type"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("status", Some("Int64"), "Utf8"),
+                ("user", None, "Utf8"),
+                ("region", None, "Utf8"),
+            ]
+        );
+        assert_eq!(rows[2][TIMESTAMP_COL_NAME], 2);
+    }
+
+    #[test]
+    fn test_unwatched_stream_produces_no_notification() {
+        let v1 = Schema::new(vec![Field::new("_timestamp", DataType::Int64, false)]);
+        let v2 = Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("user", DataType::Utf8, true),
+        ]);
+        let events = vec![event("billing", &v1, &v2, 1)];
+
+        assert!(collect_notifications(&[drift_alert("app_*")], &events).is_empty());
+        assert!(collect_notifications(&[drift_alert("billing_v2")], &events).is_empty());
+        // alerts of other types never see schema changes
+        let mut sql_alert = drift_alert("billing");
+        sql_alert.query_condition.query_type = QueryType::SQL;
+        assert!(collect_notifications(&[sql_alert], &events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stored_events_are_taken_once() {
+        infra::db::create_table().await.unwrap();
+        let v1 = Schema::new(vec![Field::new("_timestamp", DataType::Int64, false)]);
+        let v2 = Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("user", DataType::Utf8, true),
+        ]);
+        db::schema_drift::push("ingester-1", &[event("app_web", &v1, &v2, 1)])
+            .await
+            .unwrap();
+        db::schema_drift::push("ingester-2", &[event("app_api", &v1, &v2, 2)])
+            .await
+            .unwrap();
+
+        let events = db::schema_drift::take().await.unwrap();
+        let streams = events
+            .iter()
+            .map(|event| event.stream_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(streams, vec!["app_web", "app_api"]);
+        assert_eq!(events[0].fields, diff_schema(&v1, &v2));
+        assert!(db::schema_drift::take().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_leader_among() {
+        let node = |name: &str| Node {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let nodes = [node("alert-2"), node("alert-1"), node("alert-3")];
+        assert!(is_leader_among(Some(&nodes), "alert-1"));
+        assert!(!is_leader_among(Some(&nodes), "alert-2"));
+        // nothing cached, e.g. in local mode
+        assert!(is_leader_among(None, "alert-2"));
+        assert!(is_leader_among(Some(&[]), "alert-2"));
+    }
+
+    #[test]
+    fn test_matches_stream() {
+        assert!(matches_stream("app", "app"));
+        assert!(!matches_stream("app", "app_web"));
+        assert!(matches_stream("app_*", "app_web"));
+        assert!(matches_stream("*_web", "app_web"));
+        assert!(matches_stream("a*p*b", "a_p_b"));
+        assert!(!matches_stream("ab*ba", "aba"));
+        assert!(matches_stream("*", "anything"));
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Writes the state changes of alerts into the `alert_events` stream of their
+//! organization. The events go through the logs ingestion path, so the stream
+//! can feed pipelines and other alerts.
+
+use chrono::Utc;
+use config::{meta::triggers::ScheduledTriggerData, utils::json};
+
+use crate::common::meta::ingestion::IngestionRequest;
+
+pub const STREAM_NAME: &str = "alert_events";
+
+/// Ingests the state events queued in the trigger data and removes them from
+/// it. The events stay queued if they could not be ingested.
+///
+/// Events are emitted once their trigger data is persisted, if the scheduler
+/// stops right after ingesting them they are emitted again with the same
+/// `event_id`.
+pub async fn emit(
+    org_id: &str,
+    trigger_data: &mut ScheduledTriggerData,
+) -> Result<(), anyhow::Error> {
+    if trigger_data.pending_state_events.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now().timestamp_micros();
+    let records = trigger_data
+        .pending_state_events
+        .iter()
+        .map(|event| event.to_record(now))
+        .collect::<Vec<_>>();
+    let body = json::to_vec(&records)?.into();
+    let resp = crate::service::logs::ingest::ingest(
+        0,
+        org_id,
+        STREAM_NAME,
+        IngestionRequest::JSON(&body),
+        "",
+        None,
+    )
+    .await?;
+    if let Some(e) = resp.error.filter(|_| resp.code != 200) {
+        return Err(anyhow::anyhow!(e));
+    }
+    if let Some(status) = resp.status.iter().find(|s| s.status.failed > 0) {
+        return Err(anyhow::anyhow!(
+            "{} of {} events rejected: {}",
+            status.status.failed,
+            records.len(),
+            status.status.error
+        ));
+    }
+    trigger_data.pending_state_events.clear();
+    Ok(())
+}
@@ -0,0 +1,566 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use actix_web::HttpResponse;
+use base64::{engine::general_purpose, Engine};
+use chrono::Utc;
+use config::get_config;
+use once_cell::sync::Lazy;
+use quick_xml::{
+    events::{attributes::Attribute, BytesStart, Event},
+    Reader, Writer,
+};
+use regex::Regex;
+
+use crate::{
+    common::meta::{
+        branding::{Branding, BrandingLogo, BrandingRequest, BrandingResponse},
+        http::HttpResponse as MetaHttpResponse,
+    },
+    service::db,
+};
+
+/// Path the logo is served from, relative to the base uri
+pub const LOGO_PATH: &str = "/api/_settings/branding/logo";
+
+const PNG_CONTENT_TYPE: &str = "image/png";
+const SVG_CONTENT_TYPE: &str = "image/svg+xml";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const MAX_PRODUCT_NAME_LEN: usize = 64;
+const MAX_LOGIN_MESSAGE_LEN: usize = 4096;
+
+/// Elements an SVG logo can use, the others are removed with their content
+const SVG_ALLOWED_ELEMENTS: &[&str] = &[
+    "svg",
+    "g",
+    "defs",
+    "title",
+    "desc",
+    "symbol",
+    "use",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+    "linearGradient",
+    "radialGradient",
+    "stop",
+    "clipPath",
+    "mask",
+    "pattern",
+    "image",
+    "filter",
+    "feBlend",
+    "feColorMatrix",
+    "feComposite",
+    "feDropShadow",
+    "feFlood",
+    "feGaussianBlur",
+    "feMerge",
+    "feMergeNode",
+    "feOffset",
+];
+
+/// Attributes an SVG logo can use, the others are removed
+const SVG_ALLOWED_ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "style",
+    "xmlns",
+    "xmlns:xlink",
+    "xml:space",
+    "version",
+    "viewBox",
+    "preserveAspectRatio",
+    "width",
+    "height",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "fx",
+    "fy",
+    "dx",
+    "dy",
+    "d",
+    "points",
+    "transform",
+    "opacity",
+    "display",
+    "visibility",
+    "color",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-opacity",
+    "clip-path",
+    "clip-rule",
+    "clipPathUnits",
+    "mask",
+    "maskUnits",
+    "maskContentUnits",
+    "filter",
+    "filterUnits",
+    "primitiveUnits",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "gradientUnits",
+    "gradientTransform",
+    "spreadMethod",
+    "patternUnits",
+    "patternContentUnits",
+    "patternTransform",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "text-anchor",
+    "dominant-baseline",
+    "letter-spacing",
+    "in",
+    "in2",
+    "result",
+    "mode",
+    "operator",
+    "k1",
+    "k2",
+    "k3",
+    "k4",
+    "type",
+    "values",
+    "stdDeviation",
+    "flood-color",
+    "flood-opacity",
+    "href",
+    "xlink:href",
+];
+
+static RE_SVG_ROOT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<svg\b([^>]*)>").unwrap());
+static RE_SVG_WIDTH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)(?:^|\s)width\s*=\s*["']\s*([0-9.]+)\s*(px)?\s*["']"#).unwrap());
+static RE_SVG_HEIGHT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(?:^|\s)height\s*=\s*["']\s*([0-9.]+)\s*(px)?\s*["']"#).unwrap()
+});
+
+#[derive(Debug, thiserror::Error)]
+pub enum BrandingError {
+    #[error("Logo is not base64 encoded")]
+    LogoEncoding,
+    #[error("Logo should be a PNG or SVG image")]
+    LogoFormat,
+    #[error("Logo is larger than {0} KB")]
+    LogoTooLarge(usize),
+    #[error("Logo is larger than {0}x{0} pixels")]
+    LogoDimensions(u32),
+    #[error("{0} is longer than {1} characters")]
+    TooLong(&'static str, usize),
+    #[error(transparent)]
+    Db(#[from] anyhow::Error),
+}
+
+impl From<BrandingError> for HttpResponse {
+    fn from(value: BrandingError) -> Self {
+        match value {
+            BrandingError::Db(_) => MetaHttpResponse::internal_error(value),
+            _ => MetaHttpResponse::bad_request(value),
+        }
+    }
+}
+
+/// Returns the branding, empty until it is configured
+pub async fn get() -> Result<BrandingResponse, BrandingError> {
+    let branding = db::branding::get().await?.unwrap_or_default();
+    Ok(to_response(&branding))
+}
+
+/// Returns the logo, `None` when no logo is configured
+pub async fn get_logo() -> Result<Option<BrandingLogo>, BrandingError> {
+    Ok(db::branding::get()
+        .await?
+        .and_then(|branding| branding.logo))
+}
+
+/// Replaces the branding
+pub async fn set(req: BrandingRequest) -> Result<BrandingResponse, BrandingError> {
+    let product_name = non_empty(req.product_name);
+    let login_message = non_empty(req.login_message);
+    if product_name
+        .as_ref()
+        .is_some_and(|name| name.chars().count() > MAX_PRODUCT_NAME_LEN)
+    {
+        return Err(BrandingError::TooLong("product_name", MAX_PRODUCT_NAME_LEN));
+    }
+    if login_message
+        .as_ref()
+        .is_some_and(|message| message.chars().count() > MAX_LOGIN_MESSAGE_LEN)
+    {
+        return Err(BrandingError::TooLong(
+            "login_message",
+            MAX_LOGIN_MESSAGE_LEN,
+        ));
+    }
+    let logo = match non_empty(req.logo) {
+        Some(encoded) => {
+            let (content_type, data) = validate_logo(&encoded)?;
+            Some(BrandingLogo {
+                content_type: content_type.to_string(),
+                data: general_purpose::STANDARD.encode(data),
+                updated_at: Utc::now().timestamp_micros(),
+            })
+        }
+        None => None,
+    };
+    let branding = Branding {
+        product_name,
+        login_message,
+        logo,
+    };
+    db::branding::set(&branding).await?;
+    Ok(to_response(&branding))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn to_response(branding: &Branding) -> BrandingResponse {
+    let cfg = get_config();
+    BrandingResponse {
+        product_name: branding.product_name.clone(),
+        login_message: branding.login_message.clone(),
+        logo_url: branding
+            .logo
+            .as_ref()
+            .map(|logo| logo_url(&cfg.common.web_url, &cfg.common.base_uri, logo)),
+    }
+}
+
+/// The logo url is versioned, so the logo can be cached for long
+fn logo_url(web_url: &str, base_uri: &str, logo: &BrandingLogo) -> String {
+    format!("{web_url}{base_uri}{LOGO_PATH}?v={}", logo.updated_at)
+}
+
+/// Decodes the logo and checks it is a PNG or SVG image within the size
+/// limits, returns its content type and data with SVGs sanitized
+pub fn validate_logo(encoded: &str) -> Result<(&'static str, Vec<u8>), BrandingError> {
+    let cfg = get_config();
+    let data = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| BrandingError::LogoEncoding)?;
+    if data.len() > cfg.limit.branding_logo_max_size * 1024 {
+        return Err(BrandingError::LogoTooLarge(
+            cfg.limit.branding_logo_max_size,
+        ));
+    }
+    let max_dimension = cfg.limit.branding_logo_max_dimension;
+    let within = |(width, height): (u32, u32)| width <= max_dimension && height <= max_dimension;
+
+    if data.starts_with(PNG_SIGNATURE) {
+        let dimensions = png_dimensions(&data).ok_or(BrandingError::LogoFormat)?;
+        if !within(dimensions) {
+            return Err(BrandingError::LogoDimensions(max_dimension));
+        }
+        return Ok((PNG_CONTENT_TYPE, data));
+    }
+
+    let svg = String::from_utf8(data).map_err(|_| BrandingError::LogoFormat)?;
+    if !RE_SVG_ROOT.is_match(&svg) {
+        return Err(BrandingError::LogoFormat);
+    }
+    if svg_dimensions(&svg).is_some_and(|dimensions| !within(dimensions)) {
+        return Err(BrandingError::LogoDimensions(max_dimension));
+    }
+    let svg = sanitize_svg(&svg).ok_or(BrandingError::LogoFormat)?;
+    Ok((SVG_CONTENT_TYPE, svg.into_bytes()))
+}
+
+/// Width and height from the IHDR chunk, which has to be the first one
+fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 || &data[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+/// Width and height of the root element, only when both are given in pixels
+fn svg_dimensions(svg: &str) -> Option<(u32, u32)> {
+    let attrs = RE_SVG_ROOT.captures(svg)?.get(1)?.as_str();
+    let parse = |re: &Regex| -> Option<u32> {
+        let value = re.captures(attrs)?.get(1)?.as_str().parse::<f64>().ok()?;
+        Some(value.ceil() as u32)
+    };
+    Some((parse(&RE_SVG_WIDTH)?, parse(&RE_SVG_HEIGHT)?))
+}
+
+/// Rebuilds the SVG from the elements and attributes known to only draw,
+/// dropping scripts, event handlers, external references, comments and
+/// doctypes. Returns `None` when the SVG is not well-formed XML.
+pub fn sanitize_svg(svg: &str) -> Option<String> {
+    let mut reader = Reader::from_str(svg);
+    let mut writer = Writer::new(Vec::with_capacity(svg.len()));
+    loop {
+        let event = match reader.read_event().ok()? {
+            Event::Start(e) => {
+                if !is_allowed_element(&e) {
+                    // skip the element along with its content
+                    let end = e.to_end().into_owned();
+                    reader.read_to_end(end.name()).ok()?;
+                    continue;
+                }
+                Event::Start(sanitize_attributes(&e)?)
+            }
+            Event::Empty(e) => {
+                if !is_allowed_element(&e) {
+                    continue;
+                }
+                Event::Empty(sanitize_attributes(&e)?)
+            }
+            // only reached for allowed elements, the others are skipped whole
+            event @ (Event::End(_) | Event::Text(_) | Event::Decl(_)) => event,
+            Event::CData(_) | Event::Comment(_) | Event::PI(_) | Event::DocType(_) => continue,
+            Event::Eof => break,
+        };
+        writer.write_event(event).ok()?;
+    }
+    String::from_utf8(writer.into_inner()).ok()
+}
+
+fn is_allowed_element(e: &BytesStart) -> bool {
+    let name = e.name();
+    std::str::from_utf8(name.as_ref()).is_ok_and(|name| SVG_ALLOWED_ELEMENTS.contains(&name))
+}
+
+/// Copies the element keeping the allowed attributes, values are re-escaped so
+/// they can't close the attribute they are written in
+fn sanitize_attributes(e: &BytesStart) -> Option<BytesStart<'static>> {
+    let mut sanitized = BytesStart::new(std::str::from_utf8(e.name().as_ref()).ok()?.to_string());
+    for attr in e.attributes() {
+        let attr = attr.ok()?;
+        let Ok(key) = std::str::from_utf8(attr.key.as_ref()) else {
+            continue;
+        };
+        if !SVG_ALLOWED_ATTRIBUTES.contains(&key) {
+            continue;
+        }
+        let value = attr.unescape_value().ok()?;
+        if is_allowed_attribute_value(key, &value) {
+            sanitized.push_attribute(Attribute::from((key, value.as_ref())));
+        }
+    }
+    Some(sanitized)
+}
+
+/// References have to point into the SVG itself, except for embedded images
+fn is_allowed_attribute_value(key: &str, value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    if key == "href" || key == "xlink:href" {
+        return value.starts_with('#')
+            || ["data:image/png;", "data:image/jpeg;", "data:image/gif;"]
+                .iter()
+                .any(|prefix| value.starts_with(prefix));
+    }
+    if key == "style"
+        && ["expression", "@import", "javascript:", "behavior"]
+            .iter()
+            .any(|unsafe_css| value.contains(unsafe_css))
+    {
+        return false;
+    }
+    // url() may only reference elements of the SVG, such as gradients
+    value.split("url(").skip(1).all(|url| {
+        url.trim_start()
+            .trim_start_matches(['"', '\''])
+            .starts_with('#')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, len: usize) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.resize(len.max(data.len()), 0);
+        data
+    }
+
+    fn encode(data: &[u8]) -> String {
+        general_purpose::STANDARD.encode(data)
+    }
+
+    #[test]
+    fn test_sanitize_svg() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" onload="alert(1)"><script>alert(2)</script><SCRIPT src="x.js"/><a xlink:href="javascript:alert(3)"><rect width="1"/></a><rect onclick='alert(4)' width="10" height="10"/><foreignObject><iframe src="x"></iframe></foreignObject></svg>"#;
+        assert_eq!(
+            sanitize_svg(svg).unwrap(),
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="10" height="10"/></svg>"#
+        );
+        // removals can't form new markup
+        let nested = "<svg><scr<script></script>ipt>alert(1)</script></svg>";
+        assert!(!sanitize_svg(nested)
+            .unwrap_or_default()
+            .to_lowercase()
+            .contains("<script"));
+        assert_eq!(sanitize_svg("<svg><rect></svg>"), None);
+        // safe svgs are kept as they are
+        let safe = r#"<svg viewBox="0 0 10 10"><circle cx="5" cy="5" r="4"/></svg>"#;
+        assert_eq!(sanitize_svg(safe).unwrap(), safe);
+    }
+
+    #[test]
+    fn test_sanitize_svg_bypasses() {
+        for (svg, sanitized) in [
+            // event handlers in any case and through unknown elements
+            (r#"<svg><g ONLOAD="alert(1)"/></svg>"#, "<svg><g/></svg>"),
+            (r#"<svg><animate onbegin="alert(1)"/></svg>"#, "<svg></svg>"),
+            (
+                r#"<svg><set attributeName="href" to="javascript:alert(1)"/></svg>"#,
+                "<svg></svg>",
+            ),
+            // entity encoded script urls
+            (
+                r#"<svg><use href="&#106;avascript:alert(1)"/></svg>"#,
+                "<svg><use/></svg>",
+            ),
+            (
+                r##"<svg><use xlink:href="#logo"/></svg>"##,
+                r##"<svg><use xlink:href="#logo"/></svg>"##,
+            ),
+            (
+                r#"<svg><image href="https://example.com/track.png"/></svg>"#,
+                "<svg><image/></svg>",
+            ),
+            // quotes in values can't close the attribute
+            (
+                r#"<svg><rect fill='x" onload="alert(1)'/></svg>"#,
+                r#"<svg><rect fill="x&quot; onload=&quot;alert(1)"/></svg>"#,
+            ),
+            (
+                r#"<svg><rect fill="url(https://example.com/x)"/></svg>"#,
+                "<svg><rect/></svg>",
+            ),
+            (
+                r##"<svg><rect fill="url(#gradient)"/></svg>"##,
+                r##"<svg><rect fill="url(#gradient)"/></svg>"##,
+            ),
+            (
+                r#"<svg><rect style="background:url(javascript:alert(1))"/></svg>"#,
+                "<svg><rect/></svg>",
+            ),
+            // namespaced script elements
+            (
+                r#"<svg xmlns:h="http://www.w3.org/1999/xhtml"><h:script>alert(1)</h:script></svg>"#,
+                "<svg></svg>",
+            ),
+            // comments, processing instructions and doctypes
+            (
+                r#"<!DOCTYPE svg [<!ENTITY x "y">]><svg><!-- <script> --><?php echo 1 ?></svg>"#,
+                "<svg></svg>",
+            ),
+        ] {
+            assert_eq!(sanitize_svg(svg).unwrap(), sanitized, "{svg}");
+        }
+    }
+
+    #[test]
+    fn test_validate_logo_png() {
+        let (content_type, data) = validate_logo(&encode(&png(200, 50, 100))).unwrap();
+        assert_eq!(content_type, PNG_CONTENT_TYPE);
+        assert_eq!(data.len(), 100);
+        assert!(matches!(
+            validate_logo(&encode(&png(4096, 50, 100))),
+            Err(BrandingError::LogoDimensions(1024))
+        ));
+        // a png without its header
+        assert!(matches!(
+            validate_logo(&encode(PNG_SIGNATURE)),
+            Err(BrandingError::LogoFormat)
+        ));
+    }
+
+    #[test]
+    fn test_validate_logo_size_limit() {
+        assert!(validate_logo(&encode(&png(10, 10, 256 * 1024))).is_ok());
+        assert!(matches!(
+            validate_logo(&encode(&png(10, 10, 256 * 1024 + 1))),
+            Err(BrandingError::LogoTooLarge(256))
+        ));
+    }
+
+    #[test]
+    fn test_validate_logo_svg() {
+        let svg = r#"<svg width="120px" height="40"><script>alert(1)</script></svg>"#;
+        let (content_type, data) = validate_logo(&encode(svg.as_bytes())).unwrap();
+        assert_eq!(content_type, SVG_CONTENT_TYPE);
+        assert_eq!(data, br#"<svg width="120px" height="40"></svg>"#);
+        let svg = r#"<svg width="2000" height="40"></svg>"#;
+        assert!(matches!(
+            validate_logo(&encode(svg.as_bytes())),
+            Err(BrandingError::LogoDimensions(1024))
+        ));
+        // relative sizes scale with the page
+        let svg = r#"<svg width="100%" height="100%"></svg>"#;
+        assert!(validate_logo(&encode(svg.as_bytes())).is_ok());
+        assert!(matches!(
+            validate_logo(&encode(b"GIF89a")),
+            Err(BrandingError::LogoFormat)
+        ));
+        assert!(matches!(
+            validate_logo("not base64!"),
+            Err(BrandingError::LogoEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_logo_url_respects_base_uri() {
+        let logo = BrandingLogo {
+            content_type: PNG_CONTENT_TYPE.to_string(),
+            data: String::new(),
+            updated_at: 42,
+        };
+        assert_eq!(
+            logo_url("https://o2.example.com", "/o2", &logo),
+            "https://o2.example.com/o2/api/_settings/branding/logo?v=42"
+        );
+        assert_eq!(logo_url("", "", &logo), "/api/_settings/branding/logo?v=42");
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use chrono::Duration;
+use config::{
+    cluster::LOCAL_NODE,
+    get_config, is_local_disk_storage,
+    meta::{
+        cluster::Role,
+        stream::{FileKey, FileMeta, PartitionTimeLevel, StreamType, ALL_STREAM_TYPES},
+    },
+};
+use futures::StreamExt;
+use infra::{file_list as infra_file_list, storage};
+
+use crate::{common::infra::cluster::get_node_from_consistent_hash, service::db};
+
+/// What bucket lifecycle rules did with the object of a file list entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectState {
+    Available,
+    /// transitioned to a storage class that can't be read without a restore
+    Archived,
+    /// expired, the object is gone
+    Removed,
+}
+
+/// Classifies the result of reading an object. Errors other than a missing or
+/// archived object are considered transient and return `None`.
+pub fn object_state<T>(result: &object_store::Result<T>) -> Option<ObjectState> {
+    match result {
+        Ok(_) => Some(ObjectState::Available),
+        Err(object_store::Error::NotFound { .. }) => Some(ObjectState::Removed),
+        // S3 refuses to read objects in the Glacier storage classes until restored
+        Err(e) if e.to_string().contains("InvalidObjectState") => Some(ObjectState::Archived),
+        Err(_) => None,
+    }
+}
+
+/// File list updates for objects archived or expired by bucket lifecycle rules.
+#[derive(Debug, Default, PartialEq)]
+pub struct LifecycleChanges {
+    /// entries to keep in the file list history
+    pub archived: Vec<FileKey>,
+    /// entries to remove from the file list
+    pub removed: Vec<String>,
+}
+
+/// Archived entries are moved to the file list history, removed ones are
+/// dropped. Entries whose object is available or unknown are left alone.
+pub fn plan_changes(
+    files: impl IntoIterator<Item = (String, FileMeta, Option<ObjectState>)>,
+) -> LifecycleChanges {
+    let mut changes = LifecycleChanges::default();
+    for (file, meta, state) in files {
+        match state {
+            Some(ObjectState::Archived) => {
+                changes.removed.push(file.clone());
+                changes.archived.push(FileKey::new(file, meta, false));
+            }
+            Some(ObjectState::Removed) => changes.removed.push(file),
+            Some(ObjectState::Available) | None => {}
+        }
+    }
+    changes
+}
+
+/// Checks the objects of file list entries that became older than
+/// `ZO_S3_LIFECYCLE_MIN_AGE_DAYS` since the last run and updates the file list
+/// for the ones bucket lifecycle rules archived or expired, so queries skip
+/// them instead of failing.
+pub async fn run_reconcile() -> Result<(), anyhow::Error> {
+    let cfg = get_config();
+    if is_local_disk_storage() || cfg.s3.lifecycle_min_age_days <= 0 {
+        return Ok(());
+    }
+
+    let now = config::utils::time::now();
+    let time_max =
+        (now - Duration::try_days(cfg.s3.lifecycle_min_age_days).unwrap()).timestamp_micros();
+    let window = Duration::try_seconds(cfg.s3.lifecycle_reconcile_interval as i64)
+        .unwrap()
+        .num_microseconds()
+        .unwrap();
+
+    let orgs = db::schema::list_organizations_from_cache().await;
+    for org_id in orgs {
+        for stream_type in ALL_STREAM_TYPES {
+            let streams = db::schema::list_streams_from_cache(&org_id, stream_type).await;
+            for stream_name in streams {
+                let Some(node_name) =
+                    get_node_from_consistent_hash(&stream_name, &Role::Compactor, None).await
+                else {
+                    continue; // no compactor node
+                };
+                if LOCAL_NODE.name.ne(&node_name) {
+                    continue; // not this node
+                }
+                if let Err(e) =
+                    reconcile_stream(&org_id, stream_type, &stream_name, time_max, window).await
+                {
+                    log::error!(
+                        "[COMPACTOR] lifecycle: reconcile [{}/{}/{}] error: {}",
+                        org_id,
+                        stream_type,
+                        stream_name,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The next time range to reconcile after `offset`, at most `window` long and
+/// ending before `time_max`. Without an offset only the last window is
+/// reconciled.
+pub fn next_time_range(offset: i64, time_max: i64, window: i64) -> Option<(i64, i64)> {
+    let start = if offset > 0 {
+        offset
+    } else {
+        time_max - window
+    };
+    if start >= time_max {
+        return None;
+    }
+    let end = start.saturating_add(window).min(time_max);
+    Some((start, end - 1))
+}
+
+/// Reconciles the stream from its offset up to `time_max` one window at a
+/// time, moving the offset forward after each window, so that runs missed
+/// while the compactor was down or busy are caught up.
+async fn reconcile_stream(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    time_max: i64,
+    window: i64,
+) -> Result<(), anyhow::Error> {
+    let mut offset = db::compact::lifecycle::get_offset(org_id, stream_type, stream_name).await;
+    while let Some(time_range) = next_time_range(offset, time_max, window) {
+        reconcile_time_range(org_id, stream_type, stream_name, time_range).await?;
+        offset = time_range.1 + 1;
+        db::compact::lifecycle::set_offset(org_id, stream_type, stream_name, offset).await?;
+    }
+    Ok(())
+}
+
+async fn reconcile_time_range(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    time_range: (i64, i64),
+) -> Result<(), anyhow::Error> {
+    let files = infra_file_list::query(
+        org_id,
+        stream_type,
+        stream_name,
+        PartitionTimeLevel::Unset,
+        Some(time_range),
+        None,
+    )
+    .await?;
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let files = futures::stream::iter(files)
+        .map(|(file, meta)| async move {
+            let state = object_state(&storage::get_range(&file, 0..1).await);
+            (file, meta, state)
+        })
+        .buffer_unordered(get_config().limit.cpu_num)
+        .collect::<Vec<_>>()
+        .await;
+    let changes = plan_changes(files);
+    if changes.removed.is_empty() {
+        return Ok(());
+    }
+
+    log::info!(
+        "[COMPACTOR] lifecycle: [{}/{}/{}] archived files: {}, removed files: {}",
+        org_id,
+        stream_type,
+        stream_name,
+        changes.archived.len(),
+        changes.removed.len() - changes.archived.len()
+    );
+    infra_file_list::batch_add_history(&changes.archived).await?;
+    infra_file_list::batch_remove(&changes.removed).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(min_ts: i64) -> FileMeta {
+        FileMeta {
+            min_ts,
+            max_ts: min_ts + 1,
+            records: 10,
+            original_size: 100,
+            compressed_size: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_object_state() {
+        assert_eq!(object_state(&Ok(())), Some(ObjectState::Available));
+        assert_eq!(
+            object_state::<()>(&Err(object_store::Error::NotFound {
+                path: "files/a".to_string(),
+                source: "not found".into(),
+            })),
+            Some(ObjectState::Removed)
+        );
+        assert_eq!(
+            object_state::<()>(&Err(object_store::Error::Generic {
+                store: "S3",
+                source:
+                    "InvalidObjectState: The operation is not valid for the object's storage class"
+                        .into(),
+            })),
+            Some(ObjectState::Archived)
+        );
+        assert_eq!(
+            object_state::<()>(&Err(object_store::Error::Generic {
+                store: "S3",
+                source: "connection reset".into(),
+            })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_time_range() {
+        // the first run only reconciles the last window
+        assert_eq!(next_time_range(0, 1000, 100), Some((900, 999)));
+        // caught up
+        assert_eq!(next_time_range(1000, 1000, 100), None);
+        // missed runs are reconciled one window at a time
+        assert_eq!(next_time_range(700, 1000, 100), Some((700, 799)));
+        assert_eq!(next_time_range(800, 1000, 100), Some((800, 899)));
+        // a partial window up to time_max
+        assert_eq!(next_time_range(950, 1000, 100), Some((950, 999)));
+    }
+
+    #[test]
+    fn test_plan_changes() {
+        let changes = plan_changes(vec![
+            ("files/a".to_string(), meta(1), Some(ObjectState::Available)),
+            ("files/b".to_string(), meta(2), Some(ObjectState::Archived)),
+            ("files/c".to_string(), meta(3), Some(ObjectState::Removed)),
+            ("files/d".to_string(), meta(4), None),
+        ]);
+        assert_eq!(
+            changes,
+            LifecycleChanges {
+                archived: vec![FileKey::new("files/b".to_string(), meta(2), false)],
+                removed: vec!["files/b".to_string(), "files/c".to_string()],
+            }
+        );
+
+        assert_eq!(plan_changes(vec![]), LifecycleChanges::default());
+    }
+}
@@ -28,16 +28,19 @@ use config::{
         search::StorageType,
         stream::{
             FileKey, FileListDeleted, FileMeta, MergeStrategy, PartitionTimeLevel, StreamType,
+            TimeRange,
         },
     },
     metrics,
     utils::{
-        parquet::{get_recordbatch_reader_from_bytes, read_schema_from_bytes},
+        parquet::{
+            get_recordbatch_reader_from_bytes, read_column_size_from_bytes, read_schema_from_bytes,
+        },
         record_batch_ext::concat_batches,
         schema_ext::SchemaExt,
-        time::{day_micros, hour_micros},
+        time::{day_micros, hour_micros, now_micros},
     },
-    FILE_EXT_PARQUET, TIMESTAMP_COL_NAME,
+    FILE_EXT_PARQUET, ORIGINAL_DATA_COL_NAME, TIMESTAMP_COL_NAME,
 };
 use hashbrown::{HashMap, HashSet};
 use infra::{
@@ -64,7 +67,10 @@ use crate::{
         db, file_list,
         schema::generate_schema_for_defined_schema_fields,
         search::{
-            datafusion::exec::{self, MergeParquetResult},
+            datafusion::{
+                distributed_plan::streaming_aggs_exec,
+                exec::{self, MergeParquetResult},
+            },
             DATAFUSION_RUNTIME,
         },
         stream,
@@ -273,27 +279,46 @@ pub async fn generate_old_data_job_by_stream(
     )
     .await?;
 
+    // skip the hours that the retention is going to delete
+    let retention_end = if cfg.compact.old_data_retention_check {
+        Some(
+            super::retention::retention_end(
+                Utc::now(),
+                cfg.compact.data_retention_days,
+                stream_settings.data_retention,
+            )
+            .timestamp_micros(),
+        )
+    } else {
+        None
+    };
+    let (offsets, expired) = old_data_job_offsets(
+        &hours,
+        retention_end,
+        &stream_settings.extended_retention_days,
+    )?;
+    if !expired.is_empty() {
+        log::info!(
+            "[COMPACTOR] generate_old_data_job_by_stream [{}/{}/{}] skipped {} hours out of retention: {}",
+            org_id,
+            stream_type,
+            stream_name,
+            expired.len(),
+            expired_time_ranges(&expired)
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
     // generate merging job
-    for hour in hours {
-        let column = hour.split('/').collect::<Vec<_>>();
-        if column.len() != 4 {
-            return Err(anyhow::anyhow!(
-                "Unexpected hour format in {}, Expected format YYYY/MM/DD/HH",
-                hour
-            ));
-        }
-        let offset = DateTime::parse_from_rfc3339(&format!(
-            "{}-{}-{}T{}:00:00Z",
-            column[0], column[1], column[2], column[3]
-        ))?
-        .with_timezone(&Utc);
-        let offset = offset.timestamp_micros();
+    for offset in offsets {
         log::debug!(
-            "[COMPACTOR] generate_old_data_job_by_stream [{}/{}/{}] hours: {}, offset: {}",
+            "[COMPACTOR] generate_old_data_job_by_stream [{}/{}/{}] offset: {}",
             org_id,
             stream_type,
             stream_name,
-            hour,
             offset
         );
         if let Err(e) = infra_file_list::add_job(org_id, stream_type, stream_name, offset).await {
@@ -307,6 +332,58 @@ pub async fn generate_old_data_job_by_stream(
     Ok(())
 }
 
+/// Parses the `YYYY/MM/DD/HH` hours of old data into the offsets of the merge
+/// jobs. With a `retention_end`, the hours ending before it are returned apart
+/// as expired, unless they are in an extended retention range.
+fn old_data_job_offsets(
+    hours: &[String],
+    retention_end: Option<i64>,
+    extended_retentions: &[TimeRange],
+) -> Result<(Vec<i64>, Vec<i64>), anyhow::Error> {
+    let mut offsets = Vec::with_capacity(hours.len());
+    let mut expired = Vec::new();
+    for hour in hours {
+        let column = hour.split('/').collect::<Vec<_>>();
+        if column.len() != 4 {
+            return Err(anyhow::anyhow!(
+                "Unexpected hour format in {}, Expected format YYYY/MM/DD/HH",
+                hour
+            ));
+        }
+        let offset = DateTime::parse_from_rfc3339(&format!(
+            "{}-{}-{}T{}:00:00Z",
+            column[0], column[1], column[2], column[3]
+        ))?
+        .with_timezone(&Utc)
+        .timestamp_micros();
+        let hour_range = TimeRange::new(offset, offset + hour_micros(1));
+        let retained = retention_end.is_none_or(|end| hour_range.end > end)
+            || extended_retentions
+                .iter()
+                .any(|r| r.intersects(&hour_range));
+        if retained {
+            offsets.push(offset);
+        } else {
+            expired.push(offset);
+        }
+    }
+    Ok((offsets, expired))
+}
+
+/// Merges the consecutive expired hours into time ranges
+fn expired_time_ranges(offsets: &[i64]) -> Vec<TimeRange> {
+    let mut offsets = offsets.to_vec();
+    offsets.sort_unstable();
+    let mut ranges: Vec<TimeRange> = Vec::new();
+    for offset in offsets {
+        match ranges.last_mut() {
+            Some(last) if last.end >= offset => last.end = last.end.max(offset + hour_micros(1)),
+            _ => ranges.push(TimeRange::new(offset, offset + hour_micros(1))),
+        }
+    }
+    ranges
+}
+
 /// Generate downsampling job by stream and rule
 /// 1. get offset from db
 /// 2. check if other node is processing
@@ -458,6 +535,7 @@ pub async fn merge_by_stream(
     let stream_settings = unwrap_stream_settings(&schema).unwrap_or_default();
     let partition_time_level =
         unwrap_partition_time_level(stream_settings.partition_time_level, stream_type);
+    let original_data_retention_days = stream_settings.original_data_retention_days;
 
     log::debug!(
         "[COMPACTOR] merge_by_stream [{}/{}/{}] offset: {}",
@@ -544,7 +622,14 @@ pub async fn merge_by_stream(
             #[cfg(not(feature = "enterprise"))]
             let skip_group_files = false;
 
-            if files_with_size.len() <= 1 && !skip_group_files {
+            // the `_original` column of the files out of its retention is dropped
+            let drop_original = super::original_data::is_expired(
+                files_with_size.iter().map(|f| f.meta.max_ts).max().unwrap(),
+                original_data_retention_days,
+                now_micros(),
+            );
+
+            if files_with_size.len() <= 1 && !skip_group_files && !drop_original {
                 return Ok(());
             }
 
@@ -596,12 +681,32 @@ pub async fn merge_by_stream(
                         files: new_file_list.clone(),
                     });
                 }
+            }
 
-                if batch_groups.is_empty() {
-                    return Ok(()); // no files need to merge
+            // the files no batch merges are rewritten on their own
+            if drop_original {
+                let merged_files = batch_groups
+                    .iter()
+                    .flat_map(|batch| batch.files.iter().map(|f| f.key.as_str()))
+                    .collect::<HashSet<_>>();
+                for file in files_with_size
+                    .iter()
+                    .filter(|f| !merged_files.contains(f.key.as_str()))
+                {
+                    super::original_data::drop_file_column(
+                        &org_id,
+                        stream_type,
+                        &stream_name,
+                        file,
+                    )
+                    .await?;
                 }
             }
 
+            if batch_groups.is_empty() {
+                return Ok(()); // no files need to merge
+            }
+
             // send to worker
             let batch_group_len = batch_groups.len();
             let (inner_tx, mut inner_rx) = mpsc::channel(batch_group_len);
@@ -643,6 +748,11 @@ pub async fn merge_by_stream(
 
                 // delete small files keys & write big files keys, use transaction
                 let delete_file_list = batch_groups.get(batch_id).unwrap().files.as_slice();
+                let new_file_sizes = new_files
+                    .iter()
+                    .filter(|f| !f.key.is_empty())
+                    .map(|f| f.meta.compressed_size)
+                    .collect::<Vec<_>>();
                 let mut events = Vec::with_capacity(new_files.len() + delete_file_list.len());
                 for new_file in new_files {
                     if new_file.key.is_empty() {
@@ -672,6 +782,13 @@ pub async fn merge_by_stream(
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
+                observe_merge_file_sizes(
+                    &org_id,
+                    stream_type,
+                    &stream_name,
+                    delete_file_list,
+                    &new_file_sizes,
+                );
             }
             drop(permit);
             if let Some(e) = last_error {
@@ -700,6 +817,35 @@ pub async fn merge_by_stream(
     Ok(())
 }
 
+/// Records the sizes of the files merged and produced by a merge batch
+fn observe_merge_file_sizes(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    merged_files: &[FileKey],
+    new_file_sizes: &[i64],
+) {
+    if merged_files.is_empty() {
+        return;
+    }
+    let labels = [
+        org_id,
+        stream_type.as_str(),
+        metrics::file_size_stream_label(stream_name),
+    ];
+    let input_sizes = metrics::COMPACT_INPUT_FILE_SIZE_BYTES.with_label_values(&labels);
+    for file in merged_files {
+        input_sizes.observe(file.meta.compressed_size as f64);
+    }
+    let output_sizes = metrics::COMPACT_OUTPUT_FILE_SIZE_BYTES.with_label_values(&labels);
+    for size in new_file_sizes {
+        output_sizes.observe(*size as f64);
+    }
+    metrics::COMPACT_MERGE_RATIO
+        .with_label_values(&labels)
+        .observe(new_file_sizes.len() as f64 / merged_files.len() as f64);
+}
+
 /// merge small files into big file, upload to storage, returns the big file key and merged files
 pub async fn merge_files(
     thread_id: usize,
@@ -786,6 +932,15 @@ pub async fn merge_files(
     let bloom_filter_fields = get_stream_setting_bloom_filter_fields(&stream_settings);
     let full_text_search_fields = get_stream_setting_fts_fields(&stream_settings);
     let index_fields = get_stream_setting_index_fields(&stream_settings);
+    // the `_original` column of the files older than its retention is dropped
+    let drop_original = super::original_data::is_expired(
+        max_ts,
+        stream_settings
+            .as_ref()
+            .map(|s| s.original_data_retention_days)
+            .unwrap_or_default(),
+        now_micros(),
+    );
     let (defined_schema_fields, need_original) = match stream_settings {
         Some(s) => (
             s.defined_schema_fields.unwrap_or_default(),
@@ -809,10 +964,14 @@ pub async fn merge_files(
     let mut schemas = HashMap::new();
     let mut file_groups = HashMap::new();
     let mut fi = 0;
+    let mut original_data_size = 0;
     for file in new_file_list.iter() {
         fi += 1;
         log::info!("[COMPACT:{thread_id}:{fi}] merge small file: {}", &file.key);
         let buf = file_data::get(&file.key, None).await?;
+        if drop_original {
+            original_data_size += read_column_size_from_bytes(&buf, ORIGINAL_DATA_COL_NAME).await?;
+        }
         let schema = read_schema_from_bytes(&buf).await?;
         let schema = schema.as_ref().clone().with_metadata(Default::default());
         let schema_key = schema.hash_key();
@@ -825,10 +984,21 @@ pub async fn merge_files(
     }
 
     // generate the final schema
-    let all_fields = schemas
+    let mut all_fields = schemas
         .values()
         .flat_map(|s| s.fields().iter().map(|f| f.name().to_string()))
         .collect::<HashSet<_>>();
+    if drop_original && all_fields.remove(ORIGINAL_DATA_COL_NAME) {
+        new_file_meta.original_size = (new_file_meta.original_size - original_data_size).max(0);
+        log::info!(
+            "[COMPACT:{thread_id}] drop {} column of stream: {}/{}/{}, size: {}",
+            ORIGINAL_DATA_COL_NAME,
+            org_id,
+            stream_type,
+            stream_name,
+            original_data_size
+        );
+    }
     let latest_schema = Arc::new(latest_schema.retain(all_fields));
     let mut latest_schema_fields = HashMap::with_capacity(latest_schema.fields().len());
     for field in latest_schema.fields() {
@@ -1028,7 +1198,7 @@ pub async fn merge_files(
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn generate_inverted_index(
+pub async fn generate_inverted_index(
     org_id: &str,
     stream_type: StreamType,
     stream_name: &str,
@@ -1125,7 +1295,7 @@ async fn generate_inverted_index(
     Ok(())
 }
 
-async fn write_file_list(org_id: &str, events: &[FileKey]) -> Result<(), anyhow::Error> {
+pub async fn write_file_list(org_id: &str, events: &[FileKey]) -> Result<(), anyhow::Error> {
     if events.is_empty() {
         return Ok(());
     }
@@ -1172,7 +1342,7 @@ async fn write_file_list(org_id: &str, events: &[FileKey]) -> Result<(), anyhow:
             }
         };
         // send broadcast to other nodes
-        if get_config().memory_cache.cache_latest_files {
+        if cache_latest_files(events).await {
             if let Err(e) = db::file_list::broadcast::send(events, None).await {
                 log::error!("[COMPACT] send broadcast for file_list failed: {}", e);
             }
@@ -1184,10 +1354,22 @@ async fn write_file_list(org_id: &str, events: &[FileKey]) -> Result<(), anyhow:
     if !success {
         Err(anyhow::anyhow!("batch_write to db failed"))
     } else {
+        // the partial aggregations of the merged data are stale
+        streaming_aggs_exec::invalidate_files(events);
         Ok(())
     }
 }
 
+/// Whether the merged files should be broadcast for the queriers to cache
+async fn cache_latest_files(events: &[FileKey]) -> bool {
+    for event in events.iter().filter(|v| !v.deleted) {
+        if infra::cache::file_data::memory::cache_latest_files_by_key(&event.key).await {
+            return true;
+        }
+    }
+    false
+}
+
 pub fn generate_inverted_idx_recordbatch(
     schema: Arc<Schema>,
     batches: &[RecordBatch],
@@ -1356,3 +1538,167 @@ fn generate_schema_diff(
 
     Ok(diff_fields)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(hour: &str) -> i64 {
+        DateTime::parse_from_rfc3339(hour)
+            .unwrap()
+            .timestamp_micros()
+    }
+
+    #[test]
+    fn test_old_data_job_offsets_skips_hours_out_of_retention() {
+        // file list hours spanning the retention end at 2025-01-10T05:30
+        let hours = [
+            "2025/01/09/03",
+            "2025/01/09/04",
+            "2025/01/10/04",
+            "2025/01/10/05",
+            "2025/01/10/06",
+            "2025/01/11/00",
+        ]
+        .map(String::from);
+        let retention_end = offset("2025-01-10T05:30:00Z");
+
+        let (offsets, expired) = old_data_job_offsets(&hours, Some(retention_end), &[]).unwrap();
+        assert_eq!(
+            offsets,
+            vec![
+                offset("2025-01-10T05:00:00Z"),
+                offset("2025-01-10T06:00:00Z"),
+                offset("2025-01-11T00:00:00Z"),
+            ]
+        );
+        assert_eq!(
+            expired_time_ranges(&expired),
+            vec![
+                TimeRange::new(
+                    offset("2025-01-09T03:00:00Z"),
+                    offset("2025-01-09T05:00:00Z")
+                ),
+                TimeRange::new(
+                    offset("2025-01-10T04:00:00Z"),
+                    offset("2025-01-10T05:00:00Z")
+                ),
+            ]
+        );
+
+        // the extended retention keeps its hours
+        let extended = [TimeRange::new(
+            offset("2025-01-09T04:00:00Z"),
+            offset("2025-01-09T05:00:00Z"),
+        )];
+        let (offsets, expired) =
+            old_data_job_offsets(&hours, Some(retention_end), &extended).unwrap();
+        assert_eq!(offsets.len(), 4);
+        assert_eq!(offsets[0], offset("2025-01-09T04:00:00Z"));
+        assert_eq!(expired.len(), 2);
+
+        // without the retention check every hour gets a job
+        let (offsets, expired) = old_data_job_offsets(&hours, None, &[]).unwrap();
+        assert_eq!(offsets.len(), hours.len());
+        assert!(expired.is_empty());
+
+        assert!(old_data_job_offsets(&["2025/01/10".to_string()], None, &[]).is_err());
+    }
+
+    async fn downsampling_offset(org_id: &str, stream_name: &str, rule: (i64, i64)) -> i64 {
+        db::compact::downsampling::get_offset(org_id, StreamType::Metrics, stream_name, rule)
+            .await
+            .0
+    }
+
+    async fn pending_jobs(org_id: &str, stream_name: &str) -> i64 {
+        let stream_key = format!("{org_id}/{}/{stream_name}", StreamType::Metrics);
+        infra_file_list::get_jobs_stats()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|s| s.stream == stream_key)
+            .map(|s| s.pending)
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_generate_downsampling_job_advances_offset_by_day() {
+        infra_file_list::create_table().await.unwrap();
+        let org_id = "downsampling_test";
+        // the file list database is shared with the other tests and runs
+        let stream_name = format!("downsampling_{}", now_micros());
+        let rule = (0, 300);
+        infra::schema::STREAM_SCHEMAS_LATEST.write().await.insert(
+            format!("{org_id}/{}/{stream_name}", StreamType::Metrics),
+            infra::schema::SchemaCache::new(Schema::new(vec![Field::new(
+                TIMESTAMP_COL_NAME,
+                DataType::Int64,
+                false,
+            )])),
+        );
+        let now = now_micros();
+        let today = now - now % day_micros(1);
+        let start = today - day_micros(7) + hour_micros(5);
+        db::compact::downsampling::set_offset(
+            org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+            start,
+            Some(&LOCAL_NODE.uuid),
+        )
+        .await
+        .unwrap();
+
+        // a job for the day of the offset, then the offset moves to the next day
+        generate_downsampling_job_by_stream_and_rule(
+            org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending_jobs(org_id, &stream_name).await, 1);
+        assert_eq!(
+            downsampling_offset(org_id, &stream_name, rule).await,
+            today - day_micros(6)
+        );
+        generate_downsampling_job_by_stream_and_rule(
+            org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending_jobs(org_id, &stream_name).await, 2);
+        assert_eq!(
+            downsampling_offset(org_id, &stream_name, rule).await,
+            today - day_micros(5)
+        );
+
+        // the current day is not downsampled before it is over
+        db::compact::downsampling::set_offset(
+            org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+            today,
+            Some(&LOCAL_NODE.uuid),
+        )
+        .await
+        .unwrap();
+        generate_downsampling_job_by_stream_and_rule(
+            org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending_jobs(org_id, &stream_name).await, 2);
+        assert_eq!(downsampling_offset(org_id, &stream_name, rule).await, today);
+    }
+}
@@ -34,7 +34,9 @@ use crate::{common::infra::cluster::get_node_from_consistent_hash, service::db};
 
 pub mod deleted;
 pub mod flatten;
+pub mod lifecycle;
 pub mod merge;
+pub mod original_data;
 pub mod retention;
 pub mod stats;
 
@@ -47,7 +49,6 @@ pub async fn run_retention() -> Result<(), anyhow::Error> {
     }
 
     let now = config::utils::time::now();
-    let data_lifecycle_end = now - Duration::try_days(cfg.compact.data_retention_days).unwrap();
 
     let orgs = db::schema::list_organizations_from_cache().await;
     for org_id in orgs {
@@ -70,11 +71,11 @@ pub async fn run_retention() -> Result<(), anyhow::Error> {
                     infra::schema::get_settings(&org_id, &stream_name, stream_type)
                         .await
                         .unwrap_or_default();
-                let stream_data_retention_end = if stream_settings.data_retention > 0 {
-                    now - Duration::try_days(stream_settings.data_retention).unwrap()
-                } else {
-                    data_lifecycle_end
-                };
+                let stream_data_retention_end = retention::retention_end(
+                    now,
+                    cfg.compact.data_retention_days,
+                    stream_settings.data_retention,
+                );
 
                 let extended_retention_days = &stream_settings.extended_retention_days;
                 // creates jobs to delete data
@@ -117,17 +118,19 @@ pub async fn run_retention() -> Result<(), anyhow::Error> {
             continue; // not this node
         }
 
+        // retention is `all`, `start,end` or `hours:start_hour,end_hour`, the time is a date
+        // or a RFC3339 timestamp
         let ret = if retention.eq("all") {
             retention::delete_all(org_id, stream_type, stream_name).await
+        } else if let Some(hour_range) = retention
+            .strip_prefix(db::compact::retention::HOURS_JOB_PREFIX)
+            .and_then(|v| v.split_once(','))
+        {
+            retention::delete_by_hour(org_id, stream_type, stream_name, hour_range).await
+        } else if let Some(date_range) = retention.split_once(',') {
+            retention::delete_by_date(org_id, stream_type, stream_name, date_range).await
         } else {
-            let date_range = retention.split(',').collect::<Vec<&str>>();
-            retention::delete_by_date(
-                org_id,
-                stream_type,
-                stream_name,
-                (date_range[0], date_range[1]),
-            )
-            .await
+            Err(anyhow::anyhow!("invalid retention: {retention}"))
         };
 
         if let Err(e) = ret {
@@ -230,6 +233,23 @@ pub async fn run_generate_job(job_type: CompactionJobType) -> Result<(), anyhow:
                             );
                         }
                     }
+                    CompactionJobType::OriginalData => {
+                        if let Err(e) = original_data::generate_job_by_stream(
+                            &org_id,
+                            stream_type,
+                            &stream_name,
+                        )
+                        .await
+                        {
+                            log::error!(
+                                "[COMPACTOR] original_data generate_job_by_stream [{}/{}/{}] error: {}",
+                                org_id,
+                                stream_type,
+                                stream_name,
+                                e
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -241,6 +261,7 @@ pub async fn run_generate_job(job_type: CompactionJobType) -> Result<(), anyhow:
 /// Generate downsampling job for Metrics
 #[cfg(feature = "enterprise")]
 pub async fn run_generate_downsampling_job() -> Result<(), anyhow::Error> {
+    let mut rules = Vec::new();
     let orgs = db::schema::list_organizations_from_cache().await;
     for org_id in orgs {
         // check backlist
@@ -258,13 +279,14 @@ pub async fn run_generate_downsampling_job() -> Result<(), anyhow::Error> {
             };
             let downsampling_rules = get_matching_downsampling_rules(&stream_name);
             for rule in downsampling_rules {
+                let rule = (rule.offset, rule.step);
                 if LOCAL_NODE.name.ne(&node_name) {
                     // Check if this node holds the stream
                     if let Some((offset, _)) = db::compact::downsampling::get_offset_from_cache(
                         &org_id,
                         stream_type,
                         &stream_name,
-                        (rule.offset, rule.step),
+                        rule,
                     )
                     .await
                     {
@@ -273,12 +295,13 @@ pub async fn run_generate_downsampling_job() -> Result<(), anyhow::Error> {
                             &org_id,
                             stream_type,
                             &stream_name,
-                            (rule.offset, rule.step),
+                            rule,
                             offset,
                             None,
                         )
                         .await?;
                     }
+                    remove_downsampling_lag(&org_id, &stream_name, rule);
                     continue; // not this node
                 }
 
@@ -298,30 +321,96 @@ pub async fn run_generate_downsampling_job() -> Result<(), anyhow::Error> {
                     continue;
                 }
 
-                if let Err(e) = merge::generate_downsampling_job_by_stream_and_rule(
-                    &org_id,
-                    stream_type,
-                    &stream_name,
-                    (rule.offset, rule.step),
-                )
-                .await
-                {
-                    log::error!(
-                        "[DOWNSAMPLING] generate_downsampling_job_by_stream_and_rule [{}/{}/{}] rule: {:?} error: {}",
-                        org_id,
-                        stream_type,
-                        stream_name,
-                        rule,
-                        e
-                    );
-                }
+                rules.push((org_id.clone(), stream_name.clone(), rule));
             }
         }
     }
 
+    let concurrency = get_config().compact.downsampling_job_concurrency;
+    run_downsampling_rules(rules, concurrency, |org_id, stream_name, rule| async move {
+        if let Err(e) = merge::generate_downsampling_job_by_stream_and_rule(
+            &org_id,
+            StreamType::Metrics,
+            &stream_name,
+            rule,
+        )
+        .await
+        {
+            log::error!(
+                "[DOWNSAMPLING] generate_downsampling_job_by_stream_and_rule [{}/{}/{}] rule: {:?} error: {}",
+                org_id,
+                StreamType::Metrics,
+                stream_name,
+                rule,
+                e
+            );
+        }
+    })
+    .await;
+
     Ok(())
 }
 
+/// Runs `run` for every (org, stream, rule) with at most `concurrency` rules in flight, and
+/// reports the offset lag of each rule once it is processed.
+#[cfg(feature = "enterprise")]
+async fn run_downsampling_rules<F, Fut>(
+    rules: Vec<(String, String, (i64, i64))>,
+    concurrency: usize,
+    run: F,
+) where
+    F: Fn(String, String, (i64, i64)) -> Fut,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    let semaphore = std::sync::Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = Vec::with_capacity(rules.len());
+    for (org_id, stream_name, rule) in rules {
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let fut = run(org_id.clone(), stream_name.clone(), rule);
+        let task = tokio::task::spawn(async move {
+            fut.await;
+            set_downsampling_lag(&org_id, &stream_name, rule).await;
+            drop(permit);
+        });
+        tasks.push(task);
+    }
+    for task in tasks {
+        if let Err(e) = task.await {
+            log::error!("[DOWNSAMPLING] generate downsampling job task error: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "enterprise")]
+async fn set_downsampling_lag(org_id: &str, stream_name: &str, rule: (i64, i64)) {
+    let Some((offset, _)) = db::compact::downsampling::get_offset_from_cache(
+        org_id,
+        StreamType::Metrics,
+        stream_name,
+        rule,
+    )
+    .await
+    else {
+        return;
+    };
+    if offset <= 0 {
+        return; // not started yet
+    }
+    let lag = (config::utils::time::now_micros() - offset).max(0) / 1_000_000;
+    config::metrics::COMPACT_DOWNSAMPLING_OFFSET_LAG
+        .with_label_values(&[org_id, stream_name, &rule.1.to_string()])
+        .set(lag);
+}
+
+#[cfg(feature = "enterprise")]
+fn remove_downsampling_lag(org_id: &str, stream_name: &str, rule: (i64, i64)) {
+    let _ = config::metrics::COMPACT_DOWNSAMPLING_OFFSET_LAG.remove_label_values(&[
+        org_id,
+        stream_name,
+        &rule.1.to_string(),
+    ]);
+}
+
 /// compactor merging
 pub async fn run_merge(
     worker_tx: mpsc::Sender<(merge::MergeSender, merge::MergeBatch)>,
@@ -334,7 +423,6 @@ pub async fn run_merge(
     }
 
     let now = config::utils::time::now();
-    let data_lifecycle_end = now - Duration::try_days(cfg.compact.data_retention_days).unwrap();
 
     // check the stream, if the stream partition_time_level is daily or compact step secs less than
     // 1 hour, we only allow one compactor to working on it
@@ -352,11 +440,11 @@ pub async fn run_merge(
         let partition_time_level =
             unwrap_partition_time_level(stream_settings.partition_time_level, stream_type);
         // to avoid compacting conflict with retention, need check the data retention time
-        let stream_data_retention_end = if stream_settings.data_retention > 0 {
-            now - Duration::try_days(stream_settings.data_retention).unwrap()
-        } else {
-            data_lifecycle_end
-        };
+        let stream_data_retention_end = retention::retention_end(
+            now,
+            cfg.compact.data_retention_days,
+            stream_settings.data_retention,
+        );
         if job.offsets <= stream_data_retention_end.timestamp_micros() {
             need_done_ids.push(job.id); // the data will be deleted by retention, just skip
             continue;
@@ -0,0 +1,335 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Drops the `_original` column from the files older than the
+//! `original_data_retention_days` stream setting. Merges drop it when they
+//! rewrite old files, this job generates the merge jobs of the expired hours
+//! so the files no merge touches again are rewritten by the job owner too.
+
+use std::sync::Arc;
+
+use arrow::array::RecordBatch;
+use arrow_schema::Schema;
+use bytes::Bytes;
+use chrono::{TimeZone, Utc};
+use config::{
+    get_config, ider,
+    meta::stream::{FileKey, StreamType},
+    utils::{
+        parquet::{
+            read_column_size_from_bytes, read_recordbatch_from_bytes, write_recordbatch_to_parquet,
+        },
+        time::{day_micros, hour_micros, now_micros},
+    },
+    FILE_EXT_PARQUET, ORIGINAL_DATA_COL_NAME,
+};
+use infra::{
+    dist_lock, file_list as infra_file_list,
+    schema::{
+        get_stream_setting_bloom_filter_fields, get_stream_setting_fts_fields,
+        get_stream_setting_index_fields,
+    },
+    storage,
+};
+
+use crate::service::{compact::merge, db, file_list, stream};
+
+/// Hours of a stream processed by a single run, the job has a low priority
+const MAX_HOURS_PER_RUN: i64 = 24;
+
+/// Whether the `_original` column of a file whose newest record is `max_ts` is
+/// out of `retention_days`, 0 keeps the column as long as the records
+pub fn is_expired(max_ts: i64, retention_days: i64, now: i64) -> bool {
+    retention_days > 0 && max_ts < now - day_micros(retention_days)
+}
+
+/// Generates the merge jobs of the hours of the stream older than its
+/// `_original` retention, resuming from the last processed hour. The files
+/// are rewritten by `merge_by_stream` of the node which takes the job.
+pub async fn generate_job_by_stream(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+) -> Result<(), anyhow::Error> {
+    let schema = infra::schema::get(org_id, stream_name, stream_type).await?;
+    let stream_settings = infra::schema::unwrap_stream_settings(&schema);
+    let retention_days = stream_settings
+        .as_ref()
+        .map(|s| s.original_data_retention_days)
+        .unwrap_or_default();
+    if retention_days <= 0 {
+        return Ok(());
+    }
+
+    // the same lock as the merge job generators of the stream
+    let lock_key = format!("/compact/merge/{}/{}/{}", org_id, stream_type, stream_name);
+    let locker = dist_lock::lock(&lock_key, 0).await?;
+    let ret = generate_jobs(org_id, stream_type, stream_name, &schema, retention_days).await;
+    dist_lock::unlock(&locker).await?;
+    drop(locker);
+    ret
+}
+
+async fn generate_jobs(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    schema: &Schema,
+    retention_days: i64,
+) -> Result<(), anyhow::Error> {
+    let now = now_micros();
+    let cutoff = now - day_micros(retention_days);
+    let mut offset = db::compact::original_data::get_offset(org_id, stream_type, stream_name).await;
+    if offset == 0 {
+        offset = stream::stream_created(schema).unwrap_or_default();
+    }
+    if offset == 0 {
+        return Ok(()); // no data
+    }
+    offset -= offset % hour_micros(1);
+
+    for _ in 0..MAX_HOURS_PER_RUN {
+        if offset + hour_micros(1) > cutoff {
+            break; // the hour still has data in retention
+        }
+        let hour = Utc
+            .timestamp_nanos(offset * 1000)
+            .format("%Y/%m/%d/%H")
+            .to_string();
+        let files = file_list::query_by_date(org_id, stream_name, stream_type, &hour, &hour)
+            .await
+            .map_err(|e| anyhow::anyhow!("query file list failed: {}", e))?;
+        // a daily partition keeps the whole day in the first hour
+        if files
+            .iter()
+            .any(|f| !is_expired(f.meta.max_ts, retention_days, now))
+        {
+            break;
+        }
+
+        if !files.is_empty() {
+            log::debug!(
+                "[COMPACTOR] original_data generate_job_by_stream [{}/{}/{}] offset: {}",
+                org_id,
+                stream_type,
+                stream_name,
+                offset
+            );
+            if let Err(e) = infra_file_list::add_job(org_id, stream_type, stream_name, offset).await
+            {
+                return Err(anyhow::anyhow!(
+                    "[COMPACT] add file_list_jobs for original data failed: {}",
+                    e
+                ));
+            }
+        }
+
+        offset += hour_micros(1);
+        db::compact::original_data::set_offset(org_id, stream_type, stream_name, offset).await?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites `file` without the `_original` column and replaces it in the file
+/// list, it must only be called by the owner of the merge job of the file
+pub async fn drop_file_column(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    file: &FileKey,
+) -> Result<(), anyhow::Error> {
+    let stream_settings = infra::schema::get_settings(org_id, stream_name, stream_type).await;
+    let bloom_filter_fields = get_stream_setting_bloom_filter_fields(&stream_settings);
+    let full_text_search_fields = get_stream_setting_fts_fields(&stream_settings);
+    let index_fields = get_stream_setting_index_fields(&stream_settings);
+    let Some(new_file) = rewrite_file(
+        org_id,
+        stream_type,
+        stream_name,
+        file,
+        &bloom_filter_fields,
+        &full_text_search_fields,
+        &index_fields,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let mut old_file = file.clone();
+    old_file.deleted = true;
+    merge::write_file_list(org_id, &[new_file, old_file]).await
+}
+
+/// Writes a copy of `file` without the `_original` column, returns `None`
+/// when the file doesn't have it
+async fn rewrite_file(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    file: &FileKey,
+    bloom_filter_fields: &[String],
+    full_text_search_fields: &[String],
+    index_fields: &[String],
+) -> Result<Option<FileKey>, anyhow::Error> {
+    let start = std::time::Instant::now();
+    let data = storage::get(&file.key).await?;
+    let (schema, batches) = read_recordbatch_from_bytes(&data)
+        .await
+        .map_err(|e| anyhow::anyhow!("read_recordbatch_from_bytes error: {}", e))?;
+    let Some((schema, batches)) = drop_original_column(&schema, &batches)? else {
+        return Ok(None);
+    };
+    let original_data_size = read_column_size_from_bytes(&data, ORIGINAL_DATA_COL_NAME).await?;
+
+    let mut new_file_meta = file.meta.clone();
+    new_file_meta.original_size = (new_file_meta.original_size - original_data_size).max(0);
+    new_file_meta.index_size = 0;
+    let buf = write_recordbatch_to_parquet(schema, &batches, bloom_filter_fields, &new_file_meta)
+        .await
+        .map_err(|e| anyhow::anyhow!("write_recordbatch_to_parquet error: {}", e))?;
+    new_file_meta.compressed_size = buf.len() as i64;
+
+    let prefix = &file.key[..file.key.rfind('/').unwrap()];
+    let new_file_key = format!("{prefix}/{}{}", ider::generate(), FILE_EXT_PARQUET);
+    let buf = Bytes::from(buf);
+    storage::put(&new_file_key, buf.clone()).await?;
+
+    // the index of the old file goes away with it
+    if file.meta.index_size > 0 && get_config().common.inverted_index_enabled {
+        merge::generate_inverted_index(
+            org_id,
+            stream_type,
+            stream_name,
+            &new_file_key,
+            full_text_search_fields,
+            index_fields,
+            std::slice::from_ref(file),
+            &mut new_file_meta,
+            &buf,
+        )
+        .await?;
+    }
+
+    log::info!(
+        "[COMPACTOR] dropped {} column of file: {} into: {}, original_size: {} -> {}, took: {} ms",
+        ORIGINAL_DATA_COL_NAME,
+        file.key,
+        new_file_key,
+        file.meta.original_size,
+        new_file_meta.original_size,
+        start.elapsed().as_millis()
+    );
+
+    Ok(Some(FileKey {
+        key: new_file_key,
+        meta: new_file_meta,
+        deleted: false,
+        segment_ids: None,
+    }))
+}
+
+/// Projects the `_original` column out of the batches, `None` when the schema
+/// doesn't have it
+fn drop_original_column(
+    schema: &Schema,
+    batches: &[RecordBatch],
+) -> Result<Option<(Arc<Schema>, Vec<RecordBatch>)>, anyhow::Error> {
+    let Ok(idx) = schema.index_of(ORIGINAL_DATA_COL_NAME) else {
+        return Ok(None);
+    };
+    let projection = (0..schema.fields().len())
+        .filter(|i| *i != idx)
+        .collect::<Vec<_>>();
+    let schema = Arc::new(schema.project(&projection)?);
+    let batches = batches
+        .iter()
+        .map(|batch| batch.project(&projection))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some((schema, batches)))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field};
+    use config::{meta::stream::FileMeta, utils::parquet::read_schema_from_bytes};
+
+    use super::*;
+
+    #[test]
+    fn test_is_expired() {
+        let now = day_micros(100);
+        // older than the threshold
+        assert!(is_expired(day_micros(89), 10, now));
+        // newer files keep the column
+        assert!(!is_expired(day_micros(91), 10, now));
+        // disabled
+        assert!(!is_expired(day_micros(1), 0, now));
+    }
+
+    #[tokio::test]
+    async fn test_drop_original_column() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_timestamp", DataType::Int64, false),
+            Field::new("message", DataType::Utf8, true),
+            Field::new(ORIGINAL_DATA_COL_NAME, DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(StringArray::from(vec![
+                    r#"{"message":"a"}"#,
+                    r#"{"message":"b"}"#,
+                ])),
+            ],
+        )
+        .unwrap();
+        let meta = FileMeta {
+            min_ts: 1,
+            max_ts: 2,
+            records: 2,
+            ..Default::default()
+        };
+        let data = write_recordbatch_to_parquet(schema.clone(), &[batch.clone()], &[], &meta)
+            .await
+            .unwrap();
+        let data = Bytes::from(data);
+        assert!(
+            read_column_size_from_bytes(&data, ORIGINAL_DATA_COL_NAME)
+                .await
+                .unwrap()
+                > 0
+        );
+
+        let (new_schema, new_batches) = drop_original_column(&schema, &[batch]).unwrap().unwrap();
+        let new_data = write_recordbatch_to_parquet(new_schema, &new_batches, &[], &meta)
+            .await
+            .unwrap();
+        let new_schema = read_schema_from_bytes(&Bytes::from(new_data))
+            .await
+            .unwrap();
+        assert!(new_schema.field_with_name(ORIGINAL_DATA_COL_NAME).is_err());
+        assert!(new_schema.field_with_name("message").is_ok());
+        assert_eq!(new_batches[0].num_rows(), 2);
+
+        // nothing to drop the second time
+        assert!(drop_original_column(&new_schema, &new_batches)
+            .unwrap()
+            .is_none());
+    }
+}
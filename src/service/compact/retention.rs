@@ -19,15 +19,20 @@ use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 use config::{
     cluster::LOCAL_NODE,
     get_config, is_local_disk_storage,
-    meta::stream::{FileKey, FileListDeleted, FileMeta, PartitionTimeLevel, StreamType, TimeRange},
+    meta::stream::{
+        FileKey, FileListDeleted, FileMeta, PartitionTimeLevel, StreamSettings, StreamType,
+        TimeRange, ALL_STREAM_TYPES,
+    },
     utils::time::{hour_micros, BASE_TIME},
 };
 use infra::{cache, dist_lock, file_list as infra_file_list};
 use itertools::Itertools;
+use serde::Serialize;
+use utoipa::ToSchema;
 
 use crate::{
     common::infra::cluster::get_node_by_uuid,
-    service::{db, file_list},
+    service::{db, file_list, search::datafusion::distributed_plan::streaming_aggs_exec},
 };
 
 /// This function will split the original time range based on the exclude range
@@ -136,37 +141,34 @@ fn generate_time_ranges_for_deletion(
     time_ranges_for_deletion
 }
 
-/// Creates delete jobs for the stream based on the stream settings
-/// Returns the number of jobs created
-pub async fn delete_by_stream(
+/// Returns the time before which the data of a stream is deleted, the retention
+/// of the stream settings overrides the org retention
+pub fn retention_end(
+    now: DateTime<Utc>,
+    retention_days: i64,
+    stream_retention_days: i64,
+) -> DateTime<Utc> {
+    let days = if stream_retention_days > 0 {
+        stream_retention_days
+    } else {
+        retention_days
+    };
+    now - Duration::try_days(days).unwrap()
+}
+
+/// Returns the date ranges of the delete jobs for a stream whose oldest data is
+/// `created_at`, the extended retention ranges are kept
+pub fn deletion_date_ranges(
+    created_at: i64,
     lifecycle_end: &DateTime<Utc>,
-    org_id: &str,
-    stream_type: StreamType,
-    stream_name: &str,
     extended_retentions: &[TimeRange],
-) -> Result<u32, anyhow::Error> {
-    // get schema
-    let stats = cache::stats::get_stream_stats(org_id, stream_name, stream_type);
-    let created_at = stats.doc_time_min;
+) -> Vec<(String, String)> {
     if created_at == 0 {
-        return Ok(0); // no data, just skip
+        return vec![]; // no data, just skip
     }
     let created_at: DateTime<Utc> = Utc.timestamp_nanos(created_at * 1000);
     if created_at >= *lifecycle_end {
-        return Ok(0); // created_at is after lifecycle end, just skip
-    }
-
-    log::debug!(
-        "[COMPACT] delete_by_stream {}/{}/{}/{},{}",
-        org_id,
-        stream_type,
-        stream_name,
-        created_at.format("%Y-%m-%d").to_string().as_str(),
-        lifecycle_end.format("%Y-%m-%d").to_string().as_str(),
-    );
-
-    if created_at.ge(lifecycle_end) {
-        return Ok(0); // created_at is after lifecycle end, just skip
+        return vec![]; // created_at is after lifecycle end, just skip
     }
 
     // last extended retention time
@@ -191,21 +193,40 @@ pub async fn delete_by_stream(
         final_deletion_time_ranges.iter().join(", ")
     );
 
-    let job_nos = final_deletion_time_ranges.len();
-
-    for time_range in final_deletion_time_ranges {
-        let time_range_start = Utc
-            .timestamp_nanos(time_range.start * 1000)
-            .format("%Y-%m-%d")
-            .to_string();
-        let time_range_end = Utc
-            .timestamp_nanos(time_range.end * 1000)
-            .format("%Y-%m-%d")
-            .to_string();
-        if time_range_start >= time_range_end {
-            continue;
-        }
+    final_deletion_time_ranges
+        .into_iter()
+        .filter_map(|time_range| {
+            let time_range_start = Utc
+                .timestamp_nanos(time_range.start * 1000)
+                .format("%Y-%m-%d")
+                .to_string();
+            let time_range_end = Utc
+                .timestamp_nanos(time_range.end * 1000)
+                .format("%Y-%m-%d")
+                .to_string();
+            if time_range_start >= time_range_end {
+                None
+            } else {
+                Some((time_range_start, time_range_end))
+            }
+        })
+        .collect()
+}
 
+/// Creates delete jobs for the stream based on the stream settings
+/// Returns the number of jobs created
+pub async fn delete_by_stream(
+    lifecycle_end: &DateTime<Utc>,
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    extended_retentions: &[TimeRange],
+) -> Result<u32, anyhow::Error> {
+    // get schema
+    let stats = cache::stats::get_stream_stats(org_id, stream_name, stream_type);
+    let date_ranges = deletion_date_ranges(stats.doc_time_min, lifecycle_end, extended_retentions);
+
+    for (time_range_start, time_range_end) in date_ranges.iter() {
         log::debug!(
             "[COMPACT] delete_by_stream {}/{}/{}/{},{}",
             org_id,
@@ -224,7 +245,125 @@ pub async fn delete_by_stream(
         .await?;
     }
 
-    Ok(job_nos as u32)
+    Ok(date_ranges.len() as u32)
+}
+
+/// The data the retention would delete from a stream
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct RetentionPreview {
+    pub stream_type: StreamType,
+    pub stream_name: String,
+    /// The retention days applied to the stream
+    pub retention_days: i64,
+    /// The retention of the stream settings overrides the org retention
+    pub stream_retention_override: bool,
+    /// Number of extended retention ranges of the stream
+    pub extended_retention_ranges: usize,
+    /// The `start,end` date ranges of the delete jobs
+    pub date_ranges: Vec<String>,
+    pub files: i64,
+    pub records: i64,
+    pub original_size: i64,
+    pub compressed_size: i64,
+}
+
+/// A page of retention previews, one entry per stream
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct RetentionPreviewPage {
+    /// Number of streams matching the request
+    pub total: usize,
+    pub list: Vec<RetentionPreview>,
+}
+
+/// Computes what the retention would delete from the org if the retention was
+/// `retention_days`, no delete job is created
+pub async fn preview(
+    org_id: &str,
+    retention_days: i64,
+    stream_name: Option<&str>,
+    page_size: usize,
+    page_idx: usize,
+) -> Result<RetentionPreviewPage, anyhow::Error> {
+    let mut streams = vec![];
+    for stream_type in ALL_STREAM_TYPES {
+        if stream_type == StreamType::EnrichmentTables {
+            continue; // skip data retention for enrichment tables
+        }
+        for name in db::schema::list_streams_from_cache(org_id, stream_type).await {
+            if stream_name.is_none_or(|v| v == name) {
+                streams.push((stream_type, name));
+            }
+        }
+    }
+    streams.sort_by(|a, b| (a.0.as_str(), &a.1).cmp(&(b.0.as_str(), &b.1)));
+
+    let now = Utc::now();
+    let total = streams.len();
+    let mut list = Vec::with_capacity(page_size.min(total));
+    for (stream_type, name) in streams
+        .into_iter()
+        .skip(page_size * page_idx)
+        .take(page_size)
+    {
+        let settings = infra::schema::get_settings(org_id, &name, stream_type)
+            .await
+            .unwrap_or_default();
+        list.push(
+            preview_stream(now, retention_days, org_id, stream_type, &name, &settings).await?,
+        );
+    }
+    Ok(RetentionPreviewPage { total, list })
+}
+
+/// Computes the delete jobs `delete_by_stream` would create for the stream and
+/// sums the files they would remove
+pub async fn preview_stream(
+    now: DateTime<Utc>,
+    retention_days: i64,
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    settings: &StreamSettings,
+) -> Result<RetentionPreview, anyhow::Error> {
+    let lifecycle_end = retention_end(now, retention_days, settings.data_retention);
+    let stats = cache::stats::get_stream_stats(org_id, stream_name, stream_type);
+    let date_ranges = deletion_date_ranges(
+        stats.doc_time_min,
+        &lifecycle_end,
+        &settings.extended_retention_days,
+    );
+    let level =
+        infra::schema::unwrap_partition_time_level(settings.partition_time_level, stream_type);
+
+    let mut preview = RetentionPreview {
+        stream_type,
+        stream_name: stream_name.to_string(),
+        retention_days: if settings.data_retention > 0 {
+            settings.data_retention
+        } else {
+            retention_days
+        },
+        stream_retention_override: settings.data_retention > 0,
+        extended_retention_ranges: settings.extended_retention_days.len(),
+        ..Default::default()
+    };
+    for (start, end) in date_ranges {
+        let (date_start, date_end) = parse_retention_range((&start, &end))?;
+        let (date_start, date_end) = align_to_partition(date_start, date_end, level);
+        preview.date_ranges.push(format!("{start},{end}"));
+        if date_start >= date_end {
+            continue;
+        }
+        let time_range = file_list_time_range(date_start, date_end);
+        let stats =
+            infra_file_list::stats_by_time_range(org_id, stream_type, stream_name, time_range)
+                .await?;
+        preview.files += stats.file_num;
+        preview.records += stats.doc_num;
+        preview.original_size += stats.storage_size as i64;
+        preview.compressed_size += stats.compressed_size as i64;
+    }
+    Ok(preview)
 }
 
 pub async fn delete_all(
@@ -316,6 +455,44 @@ pub async fn delete_by_date(
     stream_type: StreamType,
     stream_name: &str,
     date_range: (&str, &str),
+) -> Result<(), anyhow::Error> {
+    let range = parse_retention_range(date_range);
+    delete_by_range(org_id, stream_type, stream_name, date_range, range).await
+}
+
+/// Deletes the hours `[start_hour, end_hour]` of the stream, e.g. to purge a bad
+/// ingest burst without losing the rest of the day. The job is listed as
+/// `hours:{start_hour},{end_hour}`.
+pub async fn delete_by_hour(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    hour_range: (&str, &str),
+) -> Result<(), anyhow::Error> {
+    let job_start = format!(
+        "{}{}",
+        db::compact::retention::HOURS_JOB_PREFIX,
+        hour_range.0
+    );
+    let range = parse_hour_range(hour_range);
+    delete_by_range(
+        org_id,
+        stream_type,
+        stream_name,
+        (&job_start, hour_range.1),
+        range,
+    )
+    .await
+}
+
+/// Deletes the end exclusive `range` of the stream, `job_range` is the range of
+/// the delete job as listed.
+async fn delete_by_range(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    date_range: (&str, &str),
+    range: Result<(DateTime<Utc>, DateTime<Utc>), anyhow::Error>,
 ) -> Result<(), anyhow::Error> {
     let lock_key = format!("/compact/retention/{org_id}/{stream_type}/{stream_name}");
     let locker = dist_lock::lock(&lock_key, 0).await?;
@@ -345,8 +522,42 @@ pub async fn delete_by_date(
     drop(locker);
     ret?;
 
-    // same date, just mark delete done
-    if date_range.0 == date_range.1 {
+    let (date_start, date_end) = match range {
+        Ok((start, end)) => {
+            let level = infra::schema::unwrap_partition_time_level(
+                infra::schema::get_settings(org_id, stream_name, stream_type)
+                    .await
+                    .unwrap_or_default()
+                    .partition_time_level,
+                stream_type,
+            );
+            let aligned = align_to_partition(start, end, level);
+            if aligned != (start, end) {
+                log::warn!(
+                    "[COMPACT] stream {org_id}/{stream_type}/{stream_name}/{:?} range shrunk to the {level} partitions",
+                    date_range
+                );
+            }
+            aligned
+        }
+        Err(e) => {
+            log::error!(
+                "[COMPACT] stream {org_id}/{stream_type}/{stream_name}/{:?} has invalid range: {e}",
+                date_range
+            );
+            // the job can never run, mark delete done
+            return db::compact::retention::delete_stream_done(
+                org_id,
+                stream_type,
+                stream_name,
+                Some(date_range),
+            )
+            .await;
+        }
+    };
+
+    // empty range, just mark delete done
+    if date_start >= date_end {
         // mark delete done
         return db::compact::retention::delete_stream_done(
             org_id,
@@ -357,20 +568,7 @@ pub async fn delete_by_date(
         .await;
     }
 
-    let mut date_start =
-        DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", date_range.0))?.with_timezone(&Utc);
-    // Hack for 1970-01-01
-    if date_range.0 == "1970-01-01" {
-        date_start += Duration::try_milliseconds(1).unwrap();
-    }
-    let date_end =
-        DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", date_range.1))?.with_timezone(&Utc);
-    let time_range = {
-        (
-            date_start.timestamp_micros(),
-            date_end.timestamp_micros() - 1,
-        )
-    };
+    let time_range = file_list_time_range(date_start, date_end);
 
     let cfg = get_config();
     if is_local_disk_storage() {
@@ -492,6 +690,9 @@ async fn delete_from_file_list(
     // write file list to storage
     write_file_list(org_id, &hours_files).await?;
 
+    // the partial aggregations of the deleted data are stale
+    streaming_aggs_exec::invalidate(org_id, stream_type, stream_name, time_range);
+
     Ok(())
 }
 
@@ -552,93 +753,160 @@ fn generate_local_dirs(
     org_id: &str,
     stream_type: StreamType,
     stream_name: &str,
-    mut date_start: DateTime<Utc>,
+    date_start: DateTime<Utc>,
     date_end: DateTime<Utc>,
 ) -> Vec<PathBuf> {
     let cfg = get_config();
     let mut dirs_to_delete = Vec::new();
-    while date_start <= date_end {
+    for prefix in generate_date_prefixes(date_start, date_end) {
+        // stream data
+        let dir = format!(
+            "{}files/{org_id}/{stream_type}/{stream_name}/{prefix}",
+            cfg.common.data_stream_dir
+        );
+        let path = std::path::Path::new(&dir);
+        if path.exists() {
+            dirs_to_delete.push(path.to_path_buf());
+        }
+        // index data
+        let dir = format!(
+            "{}files/{org_id}/index/{stream_name}_{stream_type}/{prefix}",
+            cfg.common.data_stream_dir
+        );
+        let path = std::path::Path::new(&dir);
+        if path.exists() {
+            dirs_to_delete.push(path.to_path_buf());
+        }
+    }
+
+    dirs_to_delete
+}
+
+/// Generate the date directory prefixes which fully covered by `[date_start, date_end)`,
+/// it uses the biggest chunk possible: year, month, day and then hour.
+fn generate_date_prefixes(mut date_start: DateTime<Utc>, date_end: DateTime<Utc>) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    while date_start < date_end {
+        let is_day_start = date_start.timestamp() % 86400 == 0;
+        let next_day = date_start + Duration::days(1);
+        let next_month = date_start
+            .date_naive()
+            .checked_add_months(chrono::Months::new(1))
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        let next_year = date_start
+            .date_naive()
+            .checked_add_months(chrono::Months::new(12))
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc());
+
         // Handle yearly chunks
-        if date_start.month() == 1
-            && date_start.day() == 1
-            && (date_start + Duration::days(365)).year() <= date_end.year()
-        {
-            // stream data
-            let year_dir = format!(
-                "{}files/{org_id}/{stream_type}/{stream_name}/{}",
-                cfg.common.data_stream_dir,
-                date_start.format("%Y")
-            );
-            let year_path = std::path::Path::new(&year_dir);
-            if year_path.exists() {
-                dirs_to_delete.push(year_path.to_path_buf());
-            }
-            // index data
-            let year_dir = format!(
-                "{}files/{org_id}/index/{stream_name}_{stream_type}/{}",
-                cfg.common.data_stream_dir,
-                date_start.format("%Y")
-            );
-            let year_path = std::path::Path::new(&year_dir);
-            if year_path.exists() {
-                dirs_to_delete.push(year_path.to_path_buf());
+        if is_day_start && date_start.month() == 1 && date_start.day() == 1 {
+            if let Some(next_year) = next_year.filter(|t| *t <= date_end) {
+                prefixes.push(date_start.format("%Y").to_string());
+                date_start = next_year;
+                continue;
             }
-            date_start += Duration::days(365);
-            continue;
         }
 
         // Handle monthly chunks
-        if date_start.day() == 1 && (date_start + Duration::days(30)).month() != date_start.month()
-        {
-            // stream data
-            let month_dir = format!(
-                "{}files/{org_id}/{stream_type}/{stream_name}/{}",
-                cfg.common.data_stream_dir,
-                date_start.format("%Y/%m")
-            );
-            let month_path = std::path::Path::new(&month_dir);
-            if month_path.exists() {
-                dirs_to_delete.push(month_path.to_path_buf());
-            }
-            // index data
-            let month_dir = format!(
-                "{}files/{org_id}/index/{stream_name}_{stream_type}/{}",
-                cfg.common.data_stream_dir,
-                date_start.format("%Y/%m")
-            );
-            let month_path = std::path::Path::new(&month_dir);
-            if month_path.exists() {
-                dirs_to_delete.push(month_path.to_path_buf());
+        if is_day_start && date_start.day() == 1 {
+            if let Some(next_month) = next_month.filter(|t| *t <= date_end) {
+                prefixes.push(date_start.format("%Y/%m").to_string());
+                date_start = next_month;
+                continue;
             }
-            date_start += Duration::days(30); // Move to the next month
-            continue;
         }
 
-        // Handle leftover day ranges
-        // stream data
-        let day_dir = format!(
-            "{}files/{org_id}/{stream_type}/{stream_name}/{}",
-            cfg.common.data_stream_dir,
-            date_start.format("%Y/%m/%d")
-        );
-        let day_path = std::path::Path::new(&day_dir);
-        if day_path.exists() {
-            dirs_to_delete.push(day_path.to_path_buf());
-        }
-        // index data
-        let day_dir = format!(
-            "{}files/{org_id}/index/{stream_name}_{stream_type}/{}",
-            cfg.common.data_stream_dir,
-            date_start.format("%Y/%m/%d")
-        );
-        let day_path = std::path::Path::new(&day_dir);
-        if day_path.exists() {
-            dirs_to_delete.push(day_path.to_path_buf());
+        // Handle daily chunks
+        if is_day_start && next_day <= date_end {
+            prefixes.push(date_start.format("%Y/%m/%d").to_string());
+            date_start = next_day;
+            continue;
         }
-        date_start += Duration::days(1); // Move to the next day
+
+        // Handle leftover hour ranges
+        prefixes.push(date_start.format("%Y/%m/%d/%H").to_string());
+        date_start += Duration::hours(1);
     }
+    prefixes
+}
 
-    dirs_to_delete
+/// Parse a retention time, it accepts a date `2024-11-03` for the start of the day or
+/// a RFC3339 timestamp with hour precision `2024-11-03T14:00:00Z`.
+pub fn parse_retention_time(value: &str) -> Result<DateTime<Utc>, anyhow::Error> {
+    let value = value.trim();
+    let time = if value.len() == 10 {
+        DateTime::parse_from_rfc3339(&format!("{value}T00:00:00Z"))?.with_timezone(&Utc)
+    } else {
+        DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc)
+    };
+    if time.timestamp_subsec_nanos() != 0 || time.timestamp() % 3600 != 0 {
+        return Err(anyhow::anyhow!("time {value} must be aligned to the hour"));
+    }
+    Ok(time)
+}
+
+/// Parse the `(start, end)` of a retention job, the end is exclusive.
+pub fn parse_retention_range(
+    date_range: (&str, &str),
+) -> Result<(DateTime<Utc>, DateTime<Utc>), anyhow::Error> {
+    let start = parse_retention_time(date_range.0)?;
+    let end = parse_retention_time(date_range.1)?;
+    if start > end {
+        return Err(anyhow::anyhow!(
+            "start time {} is after end time {}",
+            date_range.0,
+            date_range.1
+        ));
+    }
+    Ok((start, end))
+}
+
+/// Parse the inclusive `(start_hour, end_hour)` of an hours delete job, the
+/// returned end is exclusive.
+pub fn parse_hour_range(
+    hour_range: (&str, &str),
+) -> Result<(DateTime<Utc>, DateTime<Utc>), anyhow::Error> {
+    let (start, end) = parse_retention_range(hour_range)?;
+    Ok((start, end + Duration::hours(1)))
+}
+
+/// Format a retention time in the job format, whole days keep the old date format.
+pub fn format_retention_time(time: DateTime<Utc>) -> String {
+    if time.timestamp() % 86400 == 0 {
+        time.format("%Y-%m-%d").to_string()
+    } else {
+        time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+}
+
+/// Shrink the range to the partition boundaries, the files of a partition can hold
+/// data for the whole partition, so we can only delete whole partitions.
+pub fn align_to_partition(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    level: PartitionTimeLevel,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let step = match level {
+        PartitionTimeLevel::Daily => 86400,
+        _ => 3600,
+    };
+    let start_ts = start.timestamp();
+    let start_ts = start_ts + (step - start_ts.rem_euclid(step)) % step;
+    let end_ts = end.timestamp();
+    let end_ts = end_ts - end_ts.rem_euclid(step);
+    (
+        Utc.timestamp_opt(start_ts, 0).unwrap(),
+        Utc.timestamp_opt(end_ts, 0).unwrap(),
+    )
+}
+
+/// Returns the file_list time range a delete job of the aligned range removes
+pub fn file_list_time_range(start: DateTime<Utc>, end: DateTime<Utc>) -> (i64, i64) {
+    (
+        // Hack for 1970-01-01
+        start.timestamp_micros().max(1000),
+        end.timestamp_micros() - 1,
+    )
 }
 
 #[cfg(test)]
@@ -661,6 +929,160 @@ mod tests {
             .unwrap();
     }
 
+    async fn seed_preview_files(org_id: &str, stream_name: &str, days: &[(u32, i64)]) {
+        let mut files = vec![];
+        for (day, records) in days {
+            let ts = Utc
+                .with_ymd_and_hms(2024, 2, *day, 10, 0, 0)
+                .unwrap()
+                .timestamp_micros();
+            files.push(FileKey {
+                key: format!("files/{org_id}/logs/{stream_name}/2024/02/{day:02}/10/{day}.parquet"),
+                meta: FileMeta {
+                    min_ts: ts,
+                    max_ts: ts + hour_micros(1) / 2,
+                    records: *records,
+                    original_size: records * 100,
+                    compressed_size: records * 10,
+                    ..Default::default()
+                },
+                deleted: false,
+                segment_ids: None,
+            });
+        }
+        infra_file_list::batch_add(&files).await.unwrap();
+        let doc_time_min = Utc
+            .with_ymd_and_hms(2024, 2, days[0].0, 10, 0, 0)
+            .unwrap()
+            .timestamp_micros();
+        cache::stats::set_stream_stats(
+            org_id,
+            stream_name,
+            StreamType::Logs,
+            config::meta::stream::StreamStats {
+                doc_time_min,
+                ..Default::default()
+            },
+        );
+    }
+
+    // sums the files the delete jobs `delete_by_stream` would create remove
+    async fn delete_targets(
+        org_id: &str,
+        stream_name: &str,
+        lifecycle_end: &DateTime<Utc>,
+        extended_retentions: &[TimeRange],
+    ) -> (Vec<String>, i64, i64, i64) {
+        let stats = cache::stats::get_stream_stats(org_id, stream_name, StreamType::Logs);
+        let date_ranges =
+            deletion_date_ranges(stats.doc_time_min, lifecycle_end, extended_retentions);
+        let (mut files, mut records, mut original_size) = (0, 0, 0);
+        for (start, end) in date_ranges.iter() {
+            let (start, end) = parse_retention_range((start, end)).unwrap();
+            let time_range = file_list_time_range(start, end);
+            let keys = file_list::query(
+                org_id,
+                stream_name,
+                StreamType::Logs,
+                PartitionTimeLevel::Unset,
+                time_range.0,
+                time_range.1,
+            )
+            .await
+            .unwrap();
+            files += keys.len() as i64;
+            records += keys.iter().map(|f| f.meta.records).sum::<i64>();
+            original_size += keys.iter().map(|f| f.meta.original_size).sum::<i64>();
+        }
+        let date_ranges = date_ranges
+            .into_iter()
+            .map(|(start, end)| format!("{start},{end}"))
+            .collect();
+        (date_ranges, files, records, original_size)
+    }
+
+    #[tokio::test]
+    async fn test_retention_preview() {
+        infra_file_list::create_table().await.unwrap();
+        let (org_id, stream_name) = ("preview_org", "preview_logs");
+        seed_preview_files(
+            org_id,
+            stream_name,
+            &[(1, 10), (10, 20), (15, 30), (25, 40)],
+        )
+        .await;
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let settings = StreamSettings::default();
+        let preview = preview_stream(now, 10, org_id, StreamType::Logs, stream_name, &settings)
+            .await
+            .unwrap();
+        let lifecycle_end = retention_end(now, 10, 0);
+        let (date_ranges, files, records, original_size) =
+            delete_targets(org_id, stream_name, &lifecycle_end, &[]).await;
+        assert_eq!(preview.date_ranges, date_ranges);
+        assert_eq!(
+            preview.date_ranges,
+            vec!["2024-02-01,2024-02-20".to_string()]
+        );
+        assert_eq!((preview.files, preview.records), (files, records));
+        assert_eq!(preview.original_size, original_size);
+        assert_eq!((preview.files, preview.records), (3, 60));
+        assert_eq!(preview.compressed_size, 600);
+        assert!(!preview.stream_retention_override);
+
+        // the stream retention overrides the proposed org retention
+        let settings = StreamSettings {
+            data_retention: 20,
+            ..Default::default()
+        };
+        let preview = preview_stream(now, 10, org_id, StreamType::Logs, stream_name, &settings)
+            .await
+            .unwrap();
+        assert!(preview.stream_retention_override);
+        assert_eq!(preview.retention_days, 20);
+        assert_eq!((preview.files, preview.records), (1, 10));
+    }
+
+    #[tokio::test]
+    async fn test_retention_preview_extended_retention() {
+        infra_file_list::create_table().await.unwrap();
+        let (org_id, stream_name) = ("preview_ext_org", "preview_logs");
+        seed_preview_files(
+            org_id,
+            stream_name,
+            &[(1, 10), (10, 20), (15, 30), (25, 40)],
+        )
+        .await;
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let extended = vec![TimeRange::new(
+            Utc.with_ymd_and_hms(2024, 2, 10, 0, 0, 0)
+                .unwrap()
+                .timestamp_micros(),
+            Utc.with_ymd_and_hms(2024, 2, 10, 12, 0, 0)
+                .unwrap()
+                .timestamp_micros(),
+        )];
+        let settings = StreamSettings {
+            extended_retention_days: extended.clone(),
+            ..Default::default()
+        };
+        let preview = preview_stream(now, 10, org_id, StreamType::Logs, stream_name, &settings)
+            .await
+            .unwrap();
+        let lifecycle_end = retention_end(now, 10, 0);
+        let (date_ranges, files, records, original_size) =
+            delete_targets(org_id, stream_name, &lifecycle_end, &extended).await;
+        assert_eq!(preview.date_ranges, date_ranges);
+        assert_eq!(preview.date_ranges.len(), 2);
+        assert_eq!((preview.files, preview.records), (files, records));
+        assert_eq!(preview.original_size, original_size);
+        // the file of the extended retention day is kept
+        assert_eq!((preview.files, preview.records), (2, 40));
+        assert_eq!(preview.extended_retention_ranges, 1);
+    }
+
     #[tokio::test]
     async fn test_delete_all() {
         infra_file_list::create_table().await.unwrap();
@@ -767,4 +1189,136 @@ mod tests {
         println!("res time ranges : {}", res_time_ranges.iter().join(", "));
         assert_eq!(res_time_ranges.len(), 2);
     }
+
+    #[test]
+    fn test_parse_retention_range_formats() {
+        // old jobs only have dates
+        let (start, end) = parse_retention_range(("2024-11-03", "2024-11-05")).unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-11-03T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-11-05T00:00:00+00:00");
+        assert_eq!(format_retention_time(start), "2024-11-03");
+
+        let (start, end) =
+            parse_retention_range(("2024-11-03T14:00:00Z", "2024-11-03T16:00:00+00:00")).unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-11-03T14:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-11-03T16:00:00+00:00");
+        assert_eq!(format_retention_time(start), "2024-11-03T14:00:00Z");
+
+        // hours only
+        assert!(parse_retention_time("2024-11-03T14:30:00Z").is_err());
+        assert!(parse_retention_time("2024-11-03 14:00").is_err());
+        assert!(parse_retention_range(("2024-11-04", "2024-11-03")).is_err());
+    }
+
+    #[test]
+    fn test_delete_hour_range_keeps_adjacent_hours() {
+        let (start, end) =
+            parse_retention_range(("2024-11-03T14:00:00Z", "2024-11-03T16:00:00Z")).unwrap();
+        let (start, end) = align_to_partition(start, end, PartitionTimeLevel::Hourly);
+        assert_eq!(
+            generate_date_prefixes(start, end),
+            vec!["2024/11/03/14", "2024/11/03/15"]
+        );
+        // the file list range stops right before the next hour
+        assert_eq!(
+            end.timestamp_micros() - 1,
+            DateTime::parse_from_rfc3339("2024-11-03T15:59:59.999999Z")
+                .unwrap()
+                .timestamp_micros()
+        );
+
+        // daily partitions can't be deleted by hours
+        let (start, end) = align_to_partition(start, end, PartitionTimeLevel::Daily);
+        assert!(start >= end);
+        assert!(generate_date_prefixes(start, end).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_hour_keeps_other_hours() {
+        infra_file_list::create_table().await.unwrap();
+        let org_id = "test_delete_by_hour";
+        let stream_name = "burst";
+        let mut files = vec![];
+        for hour in 12..17 {
+            let ts = Utc
+                .with_ymd_and_hms(2024, 11, 3, hour, 10, 0)
+                .unwrap()
+                .timestamp_micros();
+            files.push(FileKey {
+                key: format!("files/{org_id}/logs/{stream_name}/2024/11/03/{hour}/{hour}.parquet"),
+                meta: FileMeta {
+                    min_ts: ts,
+                    max_ts: ts + hour_micros(1) / 2,
+                    records: 10,
+                    original_size: 1000,
+                    compressed_size: 100,
+                    ..Default::default()
+                },
+                deleted: false,
+                segment_ids: None,
+            });
+        }
+        infra_file_list::batch_add(&files).await.unwrap();
+
+        delete_by_hour(
+            org_id,
+            StreamType::Logs,
+            stream_name,
+            ("2024-11-03T14:00:00Z", "2024-11-03T15:00:00Z"),
+        )
+        .await
+        .unwrap();
+
+        let (start, end) = parse_retention_range(("2024-11-03", "2024-11-04")).unwrap();
+        let left = file_list::query(
+            org_id,
+            stream_name,
+            StreamType::Logs,
+            PartitionTimeLevel::Unset,
+            start.timestamp_micros(),
+            end.timestamp_micros(),
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|f| f.key)
+        .sorted()
+        .collect::<Vec<_>>();
+        assert_eq!(
+            left,
+            vec![
+                format!("files/{org_id}/logs/{stream_name}/2024/11/03/12/12.parquet"),
+                format!("files/{org_id}/logs/{stream_name}/2024/11/03/13/13.parquet"),
+                format!("files/{org_id}/logs/{stream_name}/2024/11/03/16/16.parquet"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_hour_range() {
+        let (start, end) =
+            parse_hour_range(("2024-11-03T14:00:00Z", "2024-11-03T14:00:00Z")).unwrap();
+        assert_eq!(end - start, Duration::hours(1));
+        assert!(parse_hour_range(("2024-11-03T15:00:00Z", "2024-11-03T14:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn test_generate_date_prefixes_days() {
+        let (start, end) = parse_retention_range(("2024-01-01", "2025-03-02")).unwrap();
+        assert_eq!(
+            generate_date_prefixes(start, end),
+            vec!["2024", "2025/01", "2025/02", "2025/03/01"]
+        );
+        let (start, end) =
+            parse_retention_range(("2024-11-02T22:00:00Z", "2024-11-04T01:00:00Z")).unwrap();
+        assert_eq!(
+            generate_date_prefixes(start, end),
+            vec![
+                "2024/11/02/22",
+                "2024/11/02/23",
+                "2024/11/03",
+                "2024/11/04/00"
+            ]
+        );
+    }
 }
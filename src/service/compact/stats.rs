@@ -13,9 +13,15 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use config::{cluster::LOCAL_NODE, meta::stream::StreamStats};
+use config::{
+    cluster::LOCAL_NODE, get_config, meta::stream::StreamStats, metrics, utils::time::now_micros,
+};
 use hashbrown::HashMap;
-use infra::{dist_lock, file_list as infra_file_list};
+use infra::{
+    dist_lock,
+    file_list::{self as infra_file_list, StreamJobsStats},
+};
+use serde::Serialize;
 
 use crate::{common::infra::cluster::get_node_by_uuid, service::db};
 
@@ -94,3 +100,303 @@ async fn update_stats_lock_node() -> Result<Option<i64>, anyhow::Error> {
         Ok(Some(offset))
     }
 }
+
+/// Label of the series adding up the streams left out of the jobs metrics
+const OTHER_LABEL: &str = "other";
+
+/// Merge jobs of an organization and stream type that are not done yet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobsBacklog {
+    pub pending: i64,
+    pub running: i64,
+    /// When the oldest pending job was queued, 0 without pending jobs
+    pub oldest_pending_at: i64,
+}
+
+impl JobsBacklog {
+    fn add(&mut self, other: &JobsBacklog) {
+        self.pending += other.pending;
+        self.running += other.running;
+        if other.oldest_pending_at > 0
+            && (self.oldest_pending_at == 0 || other.oldest_pending_at < self.oldest_pending_at)
+        {
+            self.oldest_pending_at = other.oldest_pending_at;
+        }
+    }
+}
+
+/// Groups the jobs by organization and stream type. Only the `max_series`
+/// groups with the largest backlog are kept, the rest are added up under the
+/// `other` organization and stream type to bound the metrics cardinality.
+pub fn jobs_backlog(
+    stats: Vec<StreamJobsStats>,
+    max_series: usize,
+) -> Vec<((String, String), JobsBacklog)> {
+    let mut groups: HashMap<(String, String), JobsBacklog> = HashMap::new();
+    for stat in stats {
+        let mut parts = stat.stream.splitn(3, '/');
+        let (Some(org_id), Some(stream_type)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        groups
+            .entry((org_id.to_string(), stream_type.to_string()))
+            .or_default()
+            .add(&JobsBacklog {
+                pending: stat.pending,
+                running: stat.running,
+                oldest_pending_at: stat.oldest_pending_at,
+            });
+    }
+
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    // most pending jobs first, then the oldest backlog
+    let oldest = |b: &JobsBacklog| match b.oldest_pending_at {
+        0 => i64::MAX,
+        queued_at => queued_at,
+    };
+    groups.sort_by(|(a_key, a), (b_key, b)| {
+        b.pending
+            .cmp(&a.pending)
+            .then_with(|| oldest(a).cmp(&oldest(b)))
+            .then_with(|| a_key.cmp(b_key))
+    });
+    if groups.len() > max_series {
+        let mut other = JobsBacklog::default();
+        for (_, backlog) in groups.drain(max_series..) {
+            other.add(&backlog);
+        }
+        groups.push(((OTHER_LABEL.to_string(), OTHER_LABEL.to_string()), other));
+    }
+    groups
+}
+
+/// Reports the compactor jobs backlog as prometheus metrics
+pub async fn update_jobs_metrics() -> Result<(), anyhow::Error> {
+    let stats = infra_file_list::get_jobs_stats().await?;
+    let backlog = jobs_backlog(stats, get_config().compact.jobs_metric_max_series);
+
+    // drop the series of the streams whose jobs are all done
+    metrics::COMPACT_PENDING_JOBS.reset();
+    metrics::COMPACT_PENDING_JOBS_OLDEST_AGE.reset();
+    metrics::COMPACT_RUNNING_JOBS.reset();
+    let now = now_micros();
+    for ((org_id, stream_type), backlog) in backlog {
+        let labels = [org_id.as_str(), stream_type.as_str()];
+        let oldest_age = if backlog.oldest_pending_at > 0 {
+            (now - backlog.oldest_pending_at).max(0) / 1_000_000
+        } else {
+            0
+        };
+        metrics::COMPACT_PENDING_JOBS
+            .with_label_values(&labels)
+            .set(backlog.pending);
+        metrics::COMPACT_PENDING_JOBS_OLDEST_AGE
+            .with_label_values(&labels)
+            .set(oldest_age);
+        metrics::COMPACT_RUNNING_JOBS
+            .with_label_values(&labels)
+            .set(backlog.running);
+    }
+    Ok(())
+}
+
+/// Pending merge jobs of a stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingJobs {
+    pub org_id: String,
+    pub stream_type: String,
+    pub stream_name: String,
+    pub pending_jobs: i64,
+    /// Seconds since the oldest pending job was queued
+    pub oldest_pending_age: i64,
+}
+
+/// The streams with pending jobs, the largest backlog first and then the
+/// oldest one
+fn pending_jobs(stats: Vec<StreamJobsStats>, now: i64) -> Vec<PendingJobs> {
+    let mut stats = stats
+        .into_iter()
+        .filter(|stat| stat.pending > 0)
+        .collect::<Vec<_>>();
+    let oldest = |s: &StreamJobsStats| match s.oldest_pending_at {
+        0 => i64::MAX,
+        queued_at => queued_at,
+    };
+    stats.sort_by(|a, b| {
+        b.pending
+            .cmp(&a.pending)
+            .then_with(|| oldest(a).cmp(&oldest(b)))
+            .then_with(|| a.stream.cmp(&b.stream))
+    });
+    stats
+        .into_iter()
+        .filter_map(|stat| {
+            let mut parts = stat.stream.splitn(3, '/');
+            let (Some(org_id), Some(stream_type), Some(stream_name)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return None;
+            };
+            let oldest_pending_age = if stat.oldest_pending_at > 0 {
+                (now - stat.oldest_pending_at).max(0) / 1_000_000
+            } else {
+                0
+            };
+            Some(PendingJobs {
+                org_id: org_id.to_string(),
+                stream_type: stream_type.to_string(),
+                stream_name: stream_name.to_string(),
+                pending_jobs: stat.pending,
+                oldest_pending_age,
+            })
+        })
+        .collect()
+}
+
+/// Lists the streams with pending merge jobs, the largest backlog first
+pub async fn pending_jobs_by_stream(
+    limit: Option<usize>,
+) -> Result<Vec<PendingJobs>, anyhow::Error> {
+    let stats = infra_file_list::get_jobs_stats().await?;
+    let mut jobs = pending_jobs(stats, now_micros());
+    if let Some(limit) = limit {
+        jobs.truncate(limit);
+    }
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(stream: &str, pending: i64, running: i64, queued_at: i64) -> StreamJobsStats {
+        StreamJobsStats {
+            stream: stream.to_string(),
+            pending,
+            running,
+            oldest_pending_at: queued_at,
+        }
+    }
+
+    fn key(org_id: &str, stream_type: &str) -> (String, String) {
+        (org_id.to_string(), stream_type.to_string())
+    }
+
+    #[test]
+    fn test_jobs_backlog_groups_by_org_and_stream_type() {
+        let backlog = jobs_backlog(
+            vec![
+                stats("org1/logs/a", 3, 1, 300),
+                stats("org1/logs/b", 2, 0, 100),
+                stats("org1/traces/c", 0, 2, 0),
+                stats("invalid", 9, 9, 1),
+            ],
+            10,
+        );
+        assert_eq!(
+            backlog,
+            vec![
+                (
+                    key("org1", "logs"),
+                    JobsBacklog {
+                        pending: 5,
+                        running: 1,
+                        oldest_pending_at: 100,
+                    }
+                ),
+                (
+                    key("org1", "traces"),
+                    JobsBacklog {
+                        pending: 0,
+                        running: 2,
+                        oldest_pending_at: 0,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jobs_backlog_caps_series() {
+        let backlog = jobs_backlog(
+            vec![
+                stats("org1/logs/a", 1, 0, 500),
+                stats("org2/logs/a", 8, 0, 400),
+                stats("org3/metrics/a", 1, 0, 200),
+                stats("org4/traces/a", 0, 3, 0),
+                stats("org5/logs/a", 5, 1, 300),
+            ],
+            2,
+        );
+        assert_eq!(
+            backlog,
+            vec![
+                (
+                    key("org2", "logs"),
+                    JobsBacklog {
+                        pending: 8,
+                        running: 0,
+                        oldest_pending_at: 400,
+                    }
+                ),
+                (
+                    key("org5", "logs"),
+                    JobsBacklog {
+                        pending: 5,
+                        running: 1,
+                        oldest_pending_at: 300,
+                    }
+                ),
+                (
+                    key(OTHER_LABEL, OTHER_LABEL),
+                    JobsBacklog {
+                        pending: 2,
+                        running: 3,
+                        oldest_pending_at: 200,
+                    }
+                ),
+            ]
+        );
+
+        // ties on pending jobs are broken by the oldest backlog
+        let backlog = jobs_backlog(
+            vec![
+                stats("org1/logs/a", 1, 0, 500),
+                stats("org3/metrics/a", 1, 0, 200),
+            ],
+            1,
+        );
+        assert_eq!(backlog[0].0, key("org3", "metrics"));
+        assert_eq!(backlog.len(), 2);
+        assert!(jobs_backlog(vec![], 2).is_empty());
+    }
+
+    #[test]
+    fn test_pending_jobs_splits_stream_key_and_ages() {
+        let jobs = pending_jobs(
+            vec![
+                stats("org2/traces/c", 1, 0, 9_000_000),
+                stats("org1/logs/a/b", 5, 2, 1_000_000),
+                stats("invalid", 3, 0, 0),
+                stats("org1/logs/running", 0, 4, 0),
+                stats("org3/logs/d", 1, 0, 2_000_000),
+            ],
+            7_000_000,
+        );
+        let job = |org_id: &str, stream_type: &str, stream_name: &str, pending, age| PendingJobs {
+            org_id: org_id.to_string(),
+            stream_type: stream_type.to_string(),
+            stream_name: stream_name.to_string(),
+            pending_jobs: pending,
+            oldest_pending_age: age,
+        };
+        assert_eq!(
+            jobs,
+            vec![
+                job("org1", "logs", "a/b", 5, 6),
+                job("org3", "logs", "d", 1, 5),
+                job("org2", "traces", "c", 1, 0),
+            ]
+        );
+    }
+}
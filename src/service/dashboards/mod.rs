@@ -16,7 +16,7 @@
 use config::{
     ider,
     meta::{
-        dashboards::{Dashboard, ListDashboardsParams},
+        dashboards::{v6, Dashboard, ListDashboardsParams},
         folder::{Folder, FolderType, DEFAULT_FOLDER},
         stream::{DistinctField, StreamType},
     },
@@ -26,14 +26,20 @@ use infra::table::{
     self,
     distinct_values::{DistinctFieldRecord, OriginType},
 };
+use serde_json::Value as JsonValue;
 
-use super::{db::distinct_values, folders, stream::save_stream_settings};
+use super::{
+    db::{self, distinct_values},
+    folders,
+    stream::save_stream_settings,
+};
 use crate::common::{
     meta::authz::Authz,
     utils::auth::{remove_ownership, set_ownership},
 };
 pub mod reports;
 pub mod timed_annotations;
+pub mod validate;
 
 #[cfg(feature = "enterprise")]
 use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config;
@@ -43,6 +49,32 @@ use o2_openfga::{
     config::get_config as get_openfga_config,
 };
 
+/// The stored version of a dashboard that an update using a stale hash
+/// conflicted with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DashboardConflict {
+    /// The current hash of the stored dashboard.
+    pub hash: String,
+    /// When the stored dashboard was last updated, in microseconds.
+    pub updated_at: i64,
+    /// The owner of the stored dashboard. Dashboards do not record who last
+    /// edited them.
+    pub owner: Option<String>,
+}
+
+impl From<&Dashboard> for DashboardConflict {
+    fn from(dashboard: &Dashboard) -> Self {
+        Self {
+            hash: dashboard.hash.clone(),
+            updated_at: dashboard.updated_at,
+            owner: dashboard
+                .owner()
+                .filter(|owner| !owner.is_empty())
+                .map(str::to_string),
+        }
+    }
+}
+
 /// An error that occurs interacting with dashboards.
 #[derive(Debug, thiserror::Error)]
 pub enum DashboardError {
@@ -64,8 +96,8 @@ pub enum DashboardError {
     /// Error that occurs when trying to update a dashboard using a stale hash.
     /// This occurs when the two clients attempt to update the dashboard
     /// concurrently.
-    #[error("tried to update dashboard using conflicting hash")]
-    UpdateConflictingHash,
+    #[error("tried to update dashboard using conflicting hash, current hash is {}", .0.hash)]
+    UpdateConflictingHash(DashboardConflict),
 
     /// Error that occurs when trying to create an update a dashboard but not
     /// title is provided.
@@ -106,6 +138,27 @@ pub enum DashboardError {
     /// get.
     #[error(transparent)]
     ListPermittedDashboardsError(actix_web::Error),
+
+    /// Error that occurs when a dashboard JSON document cannot be parsed or
+    /// converted to the latest dashboard version.
+    #[error("invalid dashboard: {0}")]
+    InvalidDashboard(serde_json::Error),
+}
+
+/// The outcome of importing a single dashboard with [import_dashboards].
+#[derive(Debug)]
+pub enum DashboardImportResult {
+    /// A new dashboard was created.
+    Created(Dashboard),
+
+    /// An existing dashboard with the same ID was overwritten.
+    Updated(Dashboard),
+
+    /// The dashboard could not be imported.
+    Failed {
+        title: Option<String>,
+        error: DashboardError,
+    },
 }
 
 async fn add_distinct_field_entry(
@@ -271,8 +324,12 @@ pub fn get_query_variables(
             let dash = dashboard.v5.as_ref().unwrap();
             _get_variables!(map, dash);
         }
+        6 => {
+            let dash = dashboard.v6.as_ref().unwrap();
+            _get_variables!(map, dash);
+        }
         _ => {
-            unreachable!("we only have 5 dashboard versions")
+            unreachable!("we only have 6 dashboard versions")
         }
     }
     map
@@ -363,15 +420,35 @@ pub async fn update_dashboard(
     Ok(dashboard)
 }
 
+/// Lists the dashboards the user is permitted to get, along with the total
+/// number of permitted dashboards matching the filters of the parameters.
+///
+/// Dashboards are paginated after filtering the permitted ones, so that the
+/// pages and the total only count the dashboards the user can see.
 #[tracing::instrument]
 pub async fn list_dashboards(
     user_id: &str,
-    params: ListDashboardsParams,
-) -> Result<Vec<(Folder, Dashboard)>, DashboardError> {
+    mut params: ListDashboardsParams,
+) -> Result<(Vec<(Folder, Dashboard)>, u64), DashboardError> {
     let org_id = params.org_id.clone();
+    let page_size_and_idx = params.page_size_and_idx.take();
     let dashboards = table::dashboards::list(params).await?;
     let dashboards = filter_permitted_dashboards(&org_id, user_id, dashboards).await?;
-    Ok(dashboards)
+    let total = dashboards.len() as u64;
+    Ok((paginate(dashboards, page_size_and_idx), total))
+}
+
+/// Returns the page of the items at the given page size and page index.
+fn paginate<T>(items: Vec<T>, page_size_and_idx: Option<(u64, u64)>) -> Vec<T> {
+    let Some((page_size, page_idx)) = page_size_and_idx else {
+        return items;
+    };
+    let offset = page_size.saturating_mul(page_idx);
+    items
+        .into_iter()
+        .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+        .take(usize::try_from(page_size).unwrap_or(usize::MAX))
+        .collect()
 }
 
 #[tracing::instrument]
@@ -382,13 +459,158 @@ pub async fn get_dashboard(org_id: &str, dashboard_id: &str) -> Result<Dashboard
         .map(|(_f, d)| d)
 }
 
+/// Gets a dashboard from the folder and converts it to the latest dashboard
+/// version.
+///
+/// Returns the converted dashboard along with the hash of the stored
+/// dashboard, which must be sent back when the exported dashboard is imported
+/// again to overwrite the stored one.
 #[tracing::instrument]
-pub async fn delete_dashboard(org_id: &str, dashboard_id: &str) -> Result<(), DashboardError> {
+pub async fn export_dashboard(
+    org_id: &str,
+    folder_id: &str,
+    dashboard_id: &str,
+) -> Result<(v6::Dashboard, String), DashboardError> {
+    let Some(dashboard) =
+        table::dashboards::get_from_folder(org_id, folder_id, dashboard_id).await?
+    else {
+        return Err(DashboardError::DashboardNotFound);
+    };
+    let exported =
+        db::dashboards::to_latest_version(&dashboard).map_err(DashboardError::InvalidDashboard)?;
+    Ok((exported, dashboard.hash))
+}
+
+/// Imports dashboard JSON documents of any supported version into the folder.
+///
+/// Each dashboard is converted to the latest dashboard version and created
+/// with a new dashboard ID. When `overwrite` is set and a dashboard with the
+/// same ID already exists in the folder, that dashboard is updated instead
+/// using the `hash` field of the document for conflict detection.
+///
+/// A failure to import one dashboard does not stop the others from being
+/// imported, the returned results are in the same order as the input.
+#[tracing::instrument(skip(dashboards))]
+pub async fn import_dashboards(
+    org_id: &str,
+    folder_id: &str,
+    dashboards: Vec<JsonValue>,
+    overwrite: bool,
+) -> Vec<DashboardImportResult> {
+    let mut results = Vec::with_capacity(dashboards.len());
+    for value in dashboards {
+        let hash = value
+            .get("hash")
+            .and_then(|h| h.as_str())
+            .map(|h| h.to_string());
+        let dashboard = match db::dashboards::parse_dashboard(value) {
+            Ok(dashboard) => dashboard,
+            Err(e) => {
+                results.push(DashboardImportResult::Failed {
+                    title: None,
+                    error: DashboardError::InvalidDashboard(e),
+                });
+                continue;
+            }
+        };
+        let title = Some(dashboard.title.clone());
+        let result = import_dashboard(org_id, folder_id, dashboard, hash.as_deref(), overwrite)
+            .await
+            .unwrap_or_else(|error| DashboardImportResult::Failed { title, error });
+        results.push(result);
+    }
+    results
+}
+
+async fn import_dashboard(
+    org_id: &str,
+    folder_id: &str,
+    dashboard: v6::Dashboard,
+    hash: Option<&str>,
+    overwrite: bool,
+) -> Result<DashboardImportResult, DashboardError> {
+    let dashboard_id = dashboard.dashboard_id.clone();
+    if overwrite
+        && !dashboard_id.is_empty()
+        && table::dashboards::get_from_folder(org_id, folder_id, &dashboard_id)
+            .await?
+            .is_some()
+    {
+        let saved =
+            update_dashboard(org_id, &dashboard_id, folder_id, dashboard.into(), hash).await?;
+        return Ok(DashboardImportResult::Updated(saved));
+    }
+    let saved = create_dashboard(org_id, folder_id, dashboard.into()).await?;
+    Ok(DashboardImportResult::Created(saved))
+}
+
+/// Moves a dashboard to the trash, from where it can be restored until it is
+/// purged after the trash retention period.
+#[tracing::instrument]
+pub async fn delete_dashboard(
+    org_id: &str,
+    dashboard_id: &str,
+    user_id: &str,
+) -> Result<(), DashboardError> {
     let Some((folder, _dashboard)) = table::dashboards::get_by_id(org_id, dashboard_id).await?
     else {
         return Err(DashboardError::DashboardNotFound);
     };
-    table::dashboards::delete_from_folder(org_id, &folder.folder_id, dashboard_id).await?;
+    let deleted_at = chrono::Utc::now().timestamp_micros();
+    table::dashboards::soft_delete(org_id, &folder.folder_id, dashboard_id, user_id, deleted_at)
+        .await?;
+
+    // The trash is local to each cluster, other clusters delete the dashboard
+    // right away and get it back through a put if it is restored here.
+    #[cfg(feature = "enterprise")]
+    if get_o2_config().super_cluster.enabled {
+        let _ = o2_enterprise::enterprise::super_cluster::queue::dashboards_delete(
+            org_id,
+            &folder.folder_id,
+            dashboard_id,
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+/// Restores a dashboard from the trash into the folder it was deleted from.
+#[tracing::instrument]
+pub async fn restore_dashboard(
+    org_id: &str,
+    dashboard_id: &str,
+) -> Result<(Folder, Dashboard), DashboardError> {
+    let Some((folder, dashboard)) = table::dashboards::restore(org_id, dashboard_id).await? else {
+        return Err(DashboardError::DashboardNotFound);
+    };
+
+    #[cfg(feature = "enterprise")]
+    if get_o2_config().super_cluster.enabled {
+        let _ = o2_enterprise::enterprise::super_cluster::queue::dashboards_put(
+            org_id,
+            &folder.folder_id,
+            dashboard.clone(),
+        )
+        .await;
+    }
+
+    Ok((folder, dashboard))
+}
+
+/// Permanently deletes a trashed dashboard along with its distinct value
+/// fields and ownership, if it was moved to the trash before `deleted_before`.
+/// Returns `false` if there was no such dashboard.
+#[tracing::instrument]
+pub async fn purge_dashboard(
+    org_id: &str,
+    folder_id: &str,
+    dashboard_id: &str,
+    deleted_before: i64,
+) -> Result<bool, DashboardError> {
+    if !table::dashboards::delete_trashed(org_id, folder_id, dashboard_id, deleted_before).await? {
+        return Ok(false);
+    }
     distinct_values::batch_remove(OriginType::Dashboard, dashboard_id).await?;
     remove_ownership(
         org_id,
@@ -396,21 +618,41 @@ pub async fn delete_dashboard(org_id: &str, dashboard_id: &str) -> Result<(), Da
         Authz {
             obj_id: dashboard_id.to_owned(),
             parent_type: "folders".to_owned(),
-            parent: folder.folder_id.clone(),
+            parent: folder_id.to_owned(),
         },
     )
     .await;
+    Ok(true)
+}
 
+/// Moves the trashed dashboards of a folder that is about to be deleted into
+/// `to_folder`, so that they can still be restored.
+#[tracing::instrument]
+pub(crate) async fn move_trashed_dashboards(
+    org_id: &str,
+    from_folder: &str,
+    to_folder: &str,
+) -> Result<(), DashboardError> {
+    let _dashboard_ids = table::dashboards::move_trashed(org_id, from_folder, to_folder).await?;
     #[cfg(feature = "enterprise")]
-    if get_o2_config().super_cluster.enabled {
-        let _ = o2_enterprise::enterprise::super_cluster::queue::dashboards_delete(
-            org_id,
-            &folder.folder_id,
-            dashboard_id,
-        )
-        .await;
+    if get_openfga_config().enabled {
+        for dashboard_id in _dashboard_ids {
+            set_parent_relation(
+                &dashboard_id,
+                &get_ofga_type("dashboards"),
+                to_folder,
+                &get_ofga_type("folders"),
+            )
+            .await;
+            remove_parent_relation(
+                &dashboard_id,
+                &get_ofga_type("dashboards"),
+                from_folder,
+                &get_ofga_type("folders"),
+            )
+            .await;
+        }
     }
-
     Ok(())
 }
 
@@ -478,6 +720,58 @@ pub async fn move_dashboard(
     Ok(())
 }
 
+/// Copies a dashboard into `to_folder`, or into its own folder when no
+/// destination is given.
+///
+/// The copy is converted to the latest dashboard version and saved under a new
+/// dashboard ID with the given title, it shares nothing with the original
+/// after that.
+#[tracing::instrument]
+pub async fn duplicate_dashboard(
+    org_id: &str,
+    folder_id: &str,
+    dashboard_id: &str,
+    title: &str,
+    to_folder: Option<&str>,
+) -> Result<Dashboard, DashboardError> {
+    let title = validate_title(Some(title))?;
+    let Some(dashboard) =
+        table::dashboards::get_from_folder(org_id, folder_id, dashboard_id).await?
+    else {
+        return Err(DashboardError::DashboardNotFound);
+    };
+
+    let mut copy =
+        db::dashboards::to_latest_version(&dashboard).map_err(DashboardError::InvalidDashboard)?;
+    copy.dashboard_id = String::new();
+    copy.title = title;
+    copy.created = chrono::Utc::now().fixed_offset();
+    copy.updated_at = chrono::Utc::now().timestamp_micros();
+
+    let to_folder = to_folder.filter(|f| !f.is_empty()).unwrap_or(folder_id);
+    create_dashboard(org_id, to_folder, copy.into()).await
+}
+
+/// Checks that the hash given for an update matches the stored dashboard.
+fn check_hash(existing_dash: &Dashboard, hash: Option<&str>) -> Result<(), DashboardError> {
+    let Some(Ok(hash_val)) = hash.map(|hash_str| hash_str.parse::<u64>()) else {
+        return Err(DashboardError::UpdateMissingHash);
+    };
+    if hash_val.to_string() != existing_dash.hash {
+        return Err(DashboardError::UpdateConflictingHash(existing_dash.into()));
+    }
+    Ok(())
+}
+
+/// Trims the dashboard title, returning an error if nothing is left of it.
+fn validate_title(title: Option<&str>) -> Result<String, DashboardError> {
+    title
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .ok_or(DashboardError::PutMissingTitle)
+}
+
 #[tracing::instrument(skip(dashboard))]
 async fn put(
     org_id: &str,
@@ -489,14 +783,7 @@ async fn put(
 ) -> Result<Dashboard, DashboardError> {
     let old_version = table::dashboards::get_from_folder(org_id, folder_id, dashboard_id).await?;
     if let Some(existing_dash) = &old_version {
-        let existing_dash_hash = &existing_dash.hash;
-
-        let Some(Ok(hash_val)) = hash.map(|hash_str| hash_str.parse::<u64>()) else {
-            return Err(DashboardError::UpdateMissingHash);
-        };
-        if hash_val.to_string() != *existing_dash_hash {
-            return Err(DashboardError::UpdateConflictingHash);
-        }
+        check_hash(existing_dash, hash)?;
     };
 
     match update_distinct_variables(org_id, old_version, &dashboard).await {
@@ -507,11 +794,7 @@ async fn put(
         }
     }
 
-    let title = dashboard
-        .title()
-        .map(|t| t.trim().to_string())
-        .and_then(|t| if t.is_empty() { None } else { Some(t) })
-        .ok_or_else(|| DashboardError::PutMissingTitle)?;
+    let title = validate_title(dashboard.title())?;
     dashboard.set_title(title);
 
     dashboard.set_dashboard_id(dashboard_id.to_owned());
@@ -582,3 +865,71 @@ async fn filter_permitted_dashboards(
         .collect();
     Ok(permitted_dashboards)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let items = (0..5).collect::<Vec<_>>();
+        assert_eq!(paginate(items.clone(), None), items);
+        assert_eq!(paginate(items.clone(), Some((2, 0))), vec![0, 1]);
+        assert_eq!(paginate(items.clone(), Some((2, 2))), vec![4]);
+        assert!(paginate(items.clone(), Some((2, 3))).is_empty());
+        assert!(paginate(items, Some((0, 0))).is_empty());
+    }
+
+    #[test]
+    fn test_validate_title() {
+        assert_eq!(validate_title(Some("  Copy of b2 ")).unwrap(), "Copy of b2");
+        assert!(matches!(
+            validate_title(Some(" \t\n")),
+            Err(DashboardError::PutMissingTitle)
+        ));
+        assert!(matches!(
+            validate_title(None),
+            Err(DashboardError::PutMissingTitle)
+        ));
+    }
+
+    #[test]
+    fn test_check_hash() {
+        let dashboard: v6::Dashboard = serde_json::from_value(serde_json::json!({
+            "version": 6,
+            "dashboardId": "7208792649849905562",
+            "title": "b2",
+            "description": "",
+            "owner": "root@example.com",
+        }))
+        .unwrap();
+        let existing = Dashboard {
+            hash: "1234".to_string(),
+            updated_at: 1_700_000_000_000_000,
+            ..dashboard.into()
+        };
+        assert!(check_hash(&existing, Some("1234")).is_ok());
+        assert!(matches!(
+            check_hash(&existing, None),
+            Err(DashboardError::UpdateMissingHash)
+        ));
+        assert!(matches!(
+            check_hash(&existing, Some("not a hash")),
+            Err(DashboardError::UpdateMissingHash)
+        ));
+
+        let Err(DashboardError::UpdateConflictingHash(conflict)) =
+            check_hash(&existing, Some("5678"))
+        else {
+            panic!("expected a conflicting hash error");
+        };
+        assert_eq!(
+            conflict,
+            DashboardConflict {
+                hash: "1234".to_string(),
+                updated_at: 1_700_000_000_000_000,
+                owner: Some("root@example.com".to_string()),
+            }
+        );
+    }
+}
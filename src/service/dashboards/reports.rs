@@ -28,6 +28,10 @@ use config::{
             ReportFrequencyType, ReportListFilters, ReportTimerangeType,
         },
     },
+    utils::{
+        hash::{fnv, Sum64},
+        render_queue::{RenderContext, RenderError, RenderStatus, REPORT_RENDER_QUEUE},
+    },
     SMTP_CLIENT,
 };
 use cron::Schedule;
@@ -265,7 +269,57 @@ pub async fn trigger(org_id: &str, name: &str) -> Result<(), (http::StatusCode,
     report
         .send_subscribers()
         .await
-        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
+        .map_err(|e| match e.downcast_ref::<RenderError>() {
+            Some(RenderError::QueueFull(_)) => (http::StatusCode::SERVICE_UNAVAILABLE, e),
+            _ => (http::StatusCode::INTERNAL_SERVER_ERROR, e),
+        })
+}
+
+/// Returns the render queue status of the report, asking the report server
+/// when rendering is delegated to it
+pub async fn render_status(
+    org_id: &str,
+    name: &str,
+) -> Result<RenderStatus, (http::StatusCode, anyhow::Error)> {
+    if db::dashboards::reports::get(org_id, name).await.is_err() {
+        return Err((
+            http::StatusCode::NOT_FOUND,
+            anyhow::anyhow!("Report not found"),
+        ));
+    }
+
+    let cfg = get_config();
+    if cfg.common.report_server_url.is_empty() {
+        return Ok(REPORT_RENDER_QUEUE.status(&format!("{org_id}/{name}")));
+    }
+
+    let url = url::Url::parse(&format!(
+        "{}/api/{org_id}/reports/{name}/status",
+        &cfg.common.report_server_url
+    ))
+    .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e.into()))?;
+    let resp = Client::builder()
+        .danger_accept_invalid_certs(cfg.common.report_server_skip_tls_verify)
+        .build()
+        .unwrap()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| {
+            (
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow::anyhow!("Error contacting report server: {e}"),
+            )
+        })?;
+    if !resp.status().is_success() {
+        return Err((
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            anyhow::anyhow!("report status error status: {}", resp.status()),
+        ));
+    }
+    resp.json::<RenderStatus>()
+        .await
+        .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e.into()))
 }
 
 pub async fn enable(
@@ -288,6 +342,26 @@ pub async fn enable(
         .map_err(|e| (http::StatusCode::INTERNAL_SERVER_ERROR, e))
 }
 
+/// Offset in microseconds added to the next run of a cron scheduled report.
+///
+/// The offset is derived from the report key, so reports sharing a cron are
+/// spread over `ZO_REPORT_SCHEDULE_JITTER` seconds while each report keeps
+/// the same offset on every run.
+pub fn schedule_jitter(org_id: &str, name: &str) -> i64 {
+    jitter_micros(
+        &format!("{org_id}/{name}"),
+        get_config().report_server.schedule_jitter,
+    )
+}
+
+fn jitter_micros(key: &str, max_secs: u64) -> i64 {
+    if max_secs == 0 {
+        return 0;
+    }
+    let max_micros = max_secs * 1_000_000;
+    (fnv::new().sum64(key) % max_micros) as i64
+}
+
 #[async_trait]
 pub trait SendReport {
     /// Sends the report to subscribers
@@ -355,16 +429,20 @@ impl SendReport for Report {
         } else {
             // Currently only one `ReportDashboard` can be captured and sent
             let dashboard = &self.dashboards[0];
-            let report = generate_report(
-                dashboard,
-                &self.org_id,
-                &cfg.common.report_user_name,
-                &cfg.common.report_user_password,
-                &self.timezone,
-                no_of_recipients,
-                &self.name,
-            )
-            .await?;
+            let report = REPORT_RENDER_QUEUE
+                .run(&format!("{}/{}", self.org_id, self.name), |ctx| {
+                    generate_report(
+                        dashboard,
+                        &self.org_id,
+                        &cfg.common.report_user_name,
+                        &cfg.common.report_user_password,
+                        &self.timezone,
+                        no_of_recipients,
+                        &self.name,
+                        ctx,
+                    )
+                })
+                .await?;
             send_email(self, &report.0, report.1).await
         }
     }
@@ -440,6 +518,7 @@ async fn generate_report(
     timezone: &str,
     no_of_recipients: usize,
     report_name: &str,
+    ctx: RenderContext,
 ) -> Result<(Vec<u8>, String), anyhow::Error> {
     let cfg = get_config();
     // Check if Chrome is enabled, otherwise don't save the report
@@ -464,6 +543,10 @@ async fn generate_report(
     let (mut browser, mut handler) =
         Browser::launch(get_chrome_launch_options().await.as_ref().unwrap().clone()).await?;
     log::info!("browser launched");
+    // register chromium so the render queue can kill it on timeout
+    if let Some(pid) = browser.get_mut_child().and_then(|child| child.id()) {
+        ctx.set_pid(pid);
+    }
 
     let handle = tokio::task::spawn(async move {
         while let Some(h) = handler.next().await {
@@ -680,3 +763,25 @@ fn sanitize_filename(filename: &str) -> String {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_micros() {
+        assert_eq!(jitter_micros("default/report", 0), 0);
+
+        let jitters = (0..20)
+            .map(|i| jitter_micros(&format!("default/report{i}"), 60))
+            .collect::<Vec<_>>();
+        assert!(jitters.iter().all(|j| (0..60_000_000).contains(j)));
+        // the same report always gets the same offset
+        assert_eq!(jitters[3], jitter_micros("default/report3", 60));
+        // reports sharing a cron are spread out
+        let mut distinct = jitters.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert!(distinct.len() > 1);
+    }
+}
@@ -0,0 +1,412 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks the SQL of dashboard panel queries against the current stream
+//! schemas, only planning the queries, without reading the file list or
+//! executing them.
+
+use config::{
+    get_config,
+    meta::{sql::TableReferenceExt, stream::StreamType},
+    utils::time::{now_micros, second_micros},
+    ID_COL_NAME, ORIGINAL_DATA_COL_NAME, TIMESTAMP_COL_NAME,
+};
+use datafusion::{common::SchemaError, error::DataFusionError};
+use futures::StreamExt;
+use infra::schema::{get_stream_setting_defined_schema_fields, unwrap_stream_settings};
+use proto::cluster_rpc::SearchQuery;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::service::{
+    ingestion::compile_vrl_function,
+    search::{
+        cluster::flight::register_table,
+        datafusion::exec::{prepare_datafusion_context, register_udf},
+        sql::{Sql, RE_ONLY_SELECT},
+        RESULT_ARRAY,
+    },
+};
+
+/// The query of a dashboard panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelQuery {
+    pub sql: String,
+    pub stream_type: StreamType,
+    /// The VRL function applied to the query results.
+    pub vrl: Option<String>,
+}
+
+/// The kind of problem found in a panel query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryIssueKind {
+    /// The SQL can't be parsed or planned.
+    InvalidSql,
+    /// The VRL function doesn't compile.
+    InvalidVrl,
+    /// The query reads a stream that doesn't exist.
+    UnknownStream,
+    /// The query references a field that isn't in the stream schema.
+    UnknownField,
+    /// The query selects a field that is neither aggregated nor grouped by.
+    InvalidAggregation,
+    /// The query selects all the fields of a stream with a user defined
+    /// schema, only the defined fields are returned.
+    SelectAll,
+    /// The query references a field outside of the user defined schema of the
+    /// stream, which new records don't store.
+    UndefinedField,
+}
+
+/// A problem found in a panel query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QueryIssue {
+    pub kind: QueryIssueKind,
+    pub message: String,
+}
+
+impl QueryIssue {
+    fn new(kind: QueryIssueKind, message: impl ToString) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// The outcome of validating a panel query. Errors make the query fail when
+/// the panel renders, warnings don't.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryValidation {
+    pub errors: Vec<QueryIssue>,
+    pub warnings: Vec<QueryIssue>,
+}
+
+impl QueryValidation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Validates the panel queries, in the same order as `queries`, planning up to
+/// `cpu_num` of them at a time. A query failing validation doesn't affect the
+/// others.
+pub async fn validate_queries(org_id: &str, queries: &[PanelQuery]) -> Vec<QueryValidation> {
+    futures::stream::iter(queries.iter().map(|query| validate_query(org_id, query)))
+        .buffered(get_config().limit.cpu_num)
+        .collect()
+        .await
+}
+
+/// Validates a panel query by planning it the way a search would.
+pub async fn validate_query(org_id: &str, query: &PanelQuery) -> QueryValidation {
+    let mut validation = QueryValidation::default();
+    if let Some(vrl) = query
+        .vrl
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        let vrl = RESULT_ARRAY.replace(vrl, "");
+        if let Err(e) = compile_vrl_function(&vrl, org_id) {
+            validation
+                .errors
+                .push(QueryIssue::new(QueryIssueKind::InvalidVrl, e));
+        }
+    }
+
+    // the time range only matters to the histogram interval
+    let end_time = now_micros();
+    let search_query = SearchQuery {
+        sql: query.sql.clone(),
+        start_time: end_time - second_micros(60),
+        end_time,
+        size: 1,
+        ..Default::default()
+    };
+    let sql = match Sql::new(&search_query, org_id, query.stream_type).await {
+        Ok(sql) => sql,
+        Err(e) => {
+            validation
+                .errors
+                .push(QueryIssue::new(QueryIssueKind::InvalidSql, e));
+            return validation;
+        }
+    };
+
+    let streams = sql
+        .stream_names
+        .iter()
+        .map(|stream| {
+            (
+                stream.stream_name(),
+                stream.get_stream_type(query.stream_type),
+            )
+        })
+        .collect::<Vec<_>>();
+    let schemas = infra::schema::get_many(org_id, &streams).await;
+    let is_select_all = RE_ONLY_SELECT.is_match(&query.sql);
+    for ((stream_name, stream_type), schema) in streams.iter().zip(schemas) {
+        let schema = match schema {
+            Ok(schema) if !schema.fields().is_empty() => schema,
+            Ok(_) => {
+                validation.errors.push(QueryIssue::new(
+                    QueryIssueKind::UnknownStream,
+                    format!("stream {stream_type}/{stream_name} not found"),
+                ));
+                continue;
+            }
+            Err(e) => {
+                validation
+                    .errors
+                    .push(QueryIssue::new(QueryIssueKind::UnknownStream, e));
+                continue;
+            }
+        };
+        let settings = unwrap_stream_settings(&schema);
+        let defined_fields = get_stream_setting_defined_schema_fields(&settings);
+        if defined_fields.is_empty() {
+            continue;
+        }
+        if is_select_all {
+            validation.warnings.push(QueryIssue::new(
+                QueryIssueKind::SelectAll,
+                format!(
+                    "stream {stream_type}/{stream_name} has a user defined schema, SELECT * only returns its {} defined fields",
+                    defined_fields.len()
+                ),
+            ));
+        }
+        let columns = sql
+            .columns
+            .iter()
+            .filter(|(table, _)| table.stream_name() == *stream_name)
+            .flat_map(|(_, columns)| columns.iter());
+        let mut undefined = columns
+            .filter(|column| !is_user_defined_field(column, &defined_fields))
+            .collect::<Vec<_>>();
+        undefined.sort();
+        for column in undefined {
+            validation.warnings.push(QueryIssue::new(
+                QueryIssueKind::UndefinedField,
+                format!(
+                    "field {column} is not in the user defined schema of stream {stream_type}/{stream_name}, new records don't store it"
+                ),
+            ));
+        }
+    }
+    if !validation.errors.is_empty() {
+        return validation;
+    }
+
+    if let Err(e) = plan(org_id, &sql).await {
+        validation.errors.push(plan_error_issue(&e));
+    }
+    validation
+}
+
+fn is_user_defined_field(field: &str, defined_fields: &[String]) -> bool {
+    field == TIMESTAMP_COL_NAME
+        || field == ID_COL_NAME
+        || field == ORIGINAL_DATA_COL_NAME
+        || field == get_config().common.column_all
+        || defined_fields.iter().any(|f| f == field)
+}
+
+/// Creates the logical plan of the query against the schemas the search would
+/// use, without any data.
+async fn plan(org_id: &str, sql: &Sql) -> Result<(), DataFusionError> {
+    let mut ctx = prepare_datafusion_context(None, vec![], false, 1).await?;
+    register_udf(&ctx, org_id)?;
+    datafusion_functions_json::register_all(&mut ctx)?;
+    register_table(&ctx, sql).await?;
+    ctx.state().create_logical_plan(&sql.sql).await?;
+    Ok(())
+}
+
+fn plan_error_issue(e: &DataFusionError) -> QueryIssue {
+    let root = e.find_root();
+    match root {
+        DataFusionError::SchemaError(SchemaError::FieldNotFound { field, .. }, _) => {
+            QueryIssue::new(
+                QueryIssueKind::UnknownField,
+                format!("field {} not found in the stream schema", field.name),
+            )
+        }
+        DataFusionError::Plan(msg) if msg.contains("non-aggregate values") => {
+            QueryIssue::new(QueryIssueKind::InvalidAggregation, root)
+        }
+        _ => QueryIssue::new(QueryIssueKind::InvalidSql, root),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::{DataType, Field, Schema};
+    use config::{meta::stream::StreamSettings, utils::json};
+    use infra::schema::{SchemaCache, STREAM_SCHEMAS_LATEST};
+
+    use super::*;
+
+    const ORG_ID: &str = "validate_queries_test";
+
+    async fn set_schema(stream_name: &str, schema: Schema) {
+        STREAM_SCHEMAS_LATEST.write().await.insert(
+            format!("{ORG_ID}/{}/{stream_name}", StreamType::Logs),
+            SchemaCache::new(schema),
+        );
+    }
+
+    fn fields(names: &[&str]) -> Vec<Field> {
+        let mut fields = vec![Field::new(TIMESTAMP_COL_NAME, DataType::Int64, false)];
+        fields.extend(
+            names
+                .iter()
+                .map(|name| Field::new(*name, DataType::Utf8, true)),
+        );
+        fields
+    }
+
+    async fn set_fixture_schemas() {
+        set_schema("app", Schema::new(fields(&["service", "level", "message"]))).await;
+        let settings = StreamSettings {
+            defined_schema_fields: Some(vec!["service".to_string(), "level".to_string()]),
+            ..Default::default()
+        };
+        set_schema(
+            "app_uds",
+            Schema::new(fields(&["service", "level", "message"])).with_metadata(
+                [("settings".to_string(), json::to_string(&settings).unwrap())].into(),
+            ),
+        )
+        .await;
+        // a stream cached as not found
+        set_schema("missing", Schema::empty()).await;
+    }
+
+    async fn validate(sql: &str) -> QueryValidation {
+        set_fixture_schemas().await;
+        validate_query(
+            ORG_ID,
+            &PanelQuery {
+                sql: sql.to_string(),
+                stream_type: StreamType::Logs,
+                vrl: None,
+            },
+        )
+        .await
+    }
+
+    fn kinds(issues: &[QueryIssue]) -> Vec<QueryIssueKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_valid() {
+        let validation =
+            validate("SELECT histogram(_timestamp) AS ts, count(*) AS cnt FROM app GROUP BY ts")
+                .await;
+        assert_eq!(validation, QueryValidation::default());
+        assert!(validation.is_valid());
+
+        let validation = validate("SELECT service, level FROM app WHERE level = 'error'").await;
+        assert!(validation.is_valid(), "{validation:?}");
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_invalid_sql() {
+        let validation = validate("SELECT FROM WHERE app").await;
+        assert_eq!(kinds(&validation.errors), vec![QueryIssueKind::InvalidSql]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_unknown_stream() {
+        let validation = validate("SELECT count(*) FROM missing").await;
+        assert_eq!(
+            kinds(&validation.errors),
+            vec![QueryIssueKind::UnknownStream]
+        );
+        assert!(validation.errors[0].message.contains("logs/missing"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_unknown_field() {
+        let validation = validate("SELECT service, dropped_field FROM app").await;
+        assert_eq!(
+            kinds(&validation.errors),
+            vec![QueryIssueKind::UnknownField]
+        );
+        assert!(validation.errors[0].message.contains("dropped_field"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_invalid_aggregation() {
+        let validation =
+            validate("SELECT service, level, count(*) FROM app GROUP BY service").await;
+        assert_eq!(
+            kinds(&validation.errors),
+            vec![QueryIssueKind::InvalidAggregation]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_user_defined_schema_warnings() {
+        let validation = validate("SELECT * FROM app_uds").await;
+        assert!(validation.is_valid(), "{validation:?}");
+        assert_eq!(kinds(&validation.warnings), vec![QueryIssueKind::SelectAll]);
+
+        let validation = validate("SELECT service, message FROM app_uds").await;
+        assert!(validation.is_valid(), "{validation:?}");
+        assert_eq!(
+            kinds(&validation.warnings),
+            vec![QueryIssueKind::UndefinedField]
+        );
+        assert!(validation.warnings[0].message.contains("message"));
+
+        // the same query on a stream without a user defined schema
+        let validation = validate("SELECT * FROM app").await;
+        assert!(validation.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_queries_keeps_going_after_a_bad_query() {
+        set_fixture_schemas().await;
+        let query = |sql: &str, vrl: Option<&str>| PanelQuery {
+            sql: sql.to_string(),
+            stream_type: StreamType::Logs,
+            vrl: vrl.map(str::to_string),
+        };
+        let validations = validate_queries(
+            ORG_ID,
+            &[
+                query("SELECT count(*) FROM app", Some(".a = ")),
+                query("SELECT nope FROM app", None),
+                query("SELECT count(*) FROM app", Some(".cnt = 1\n.")),
+            ],
+        )
+        .await;
+        assert_eq!(validations.len(), 3);
+        assert_eq!(
+            kinds(&validations[0].errors),
+            vec![QueryIssueKind::InvalidVrl]
+        );
+        assert_eq!(
+            kinds(&validations[1].errors),
+            vec![QueryIssueKind::UnknownField]
+        );
+        assert!(validations[2].is_valid(), "{:?}", validations[2]);
+    }
+}
@@ -185,6 +185,12 @@ pub async fn update<C: ConnectionTrait + TransactionTrait>(
     #[cfg(feature = "enterprise")]
     super_cluster::emit_update_event(org_id, folder_id, alert.clone()).await?;
 
+    put_trigger(org_id, &alert).await;
+    Ok(alert)
+}
+
+/// Schedules the alert to run now, keeping the data of its existing trigger.
+async fn put_trigger(org_id: &str, alert: &Alert) {
     let schedule_key = scheduler_key(alert.stream_type, &alert.stream_name, &alert.name);
     let mut trigger = db::scheduler::Trigger {
         org: org_id.to_string(),
@@ -209,21 +215,35 @@ pub async fn update<C: ConnectionTrait + TransactionTrait>(
             e
         });
     }
-
-    Ok(alert)
 }
 
+/// Moves the alert to the trash, removes it from the alerts cache and deletes
+/// its scheduler trigger. The trigger is pushed again if the alert is
+/// restored.
 pub async fn delete_by_id<C: ConnectionTrait>(
     conn: &C,
     org_id: &str,
     alert_id: Ksuid,
+    user_id: &str,
 ) -> Result<(), infra::errors::Error> {
-    let Some((_folder, alert)) = table::get_by_id(conn, org_id, alert_id).await? else {
+    let deleted_at = chrono::Utc::now().timestamp_micros();
+    let Some(alert) = table::soft_delete_by_id(conn, org_id, alert_id, user_id, deleted_at).await?
+    else {
         return Ok(());
     };
+    on_trashed(org_id, alert_id, &alert).await
+}
 
-    table::delete_by_id(conn, org_id, alert_id).await?;
+/// Removes an alert that was moved to the trash from the alerts cache and
+/// deletes its scheduler trigger.
+pub async fn on_trashed(
+    org_id: &str,
+    alert_id: Ksuid,
+    alert: &Alert,
+) -> Result<(), infra::errors::Error> {
     cluster::emit_delete_event(org_id, alert.stream_type, &alert.stream_name, &alert.name).await?;
+    // The trash is local to each cluster, other clusters delete the alert
+    // right away and get it back through a create if it is restored here.
     #[cfg(feature = "enterprise")]
     super_cluster::emit_delete_event(
         org_id,
@@ -234,24 +254,19 @@ pub async fn delete_by_id<C: ConnectionTrait>(
     )
     .await?;
 
-    let schedule_key = scheduler_key(alert.stream_type, &alert.stream_name, &alert.name);
-    if let Err(e) =
-        db::scheduler::delete(org_id, db::scheduler::TriggerModule::Alert, &schedule_key).await
-    {
-        log::error!("Failed to delete trigger: {}", e);
-    };
+    delete_trigger(org_id, alert).await;
     Ok(())
 }
 
+/// Moves the alert with the given name to the trash.
 pub async fn delete_by_name(
     org_id: &str,
     stream_type: StreamType,
     stream_name: &str,
     name: &str,
+    user_id: &str,
 ) -> Result<(), infra::errors::Error> {
     let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
-
-    #[cfg(feature = "enterprise")]
     let Some(alert_id) =
         table::get_by_name(client, org_id, "default", stream_type, stream_name, name)
             .await?
@@ -259,20 +274,66 @@ pub async fn delete_by_name(
     else {
         return Ok(());
     };
+    delete_by_id(client, org_id, alert_id, user_id).await
+}
 
-    table::delete_by_name(client, org_id, "default", stream_type, stream_name, name).await?;
-    cluster::emit_delete_event(org_id, stream_type, stream_name, name).await?;
+/// Restores the alert from the trash, puts it back in the alerts cache and
+/// schedules it to run now.
+pub async fn restore_by_id<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert_id: Ksuid,
+) -> Result<Option<(Folder, Alert)>, infra::errors::Error> {
+    let Some((folder, alert)) = table::restore_by_id(conn, org_id, alert_id).await? else {
+        return Ok(None);
+    };
 
+    cluster::emit_put_event(org_id, &alert).await?;
     #[cfg(feature = "enterprise")]
-    super_cluster::emit_delete_event(org_id, stream_type, stream_name, name, alert_id).await?;
+    super_cluster::emit_create_event(org_id, &folder.folder_id, alert.clone()).await?;
+
+    put_trigger(org_id, &alert).await;
+    Ok(Some((folder, alert)))
+}
 
-    let schedule_key = scheduler_key(stream_type, stream_name, name);
+/// Permanently deletes a trashed alert and its scheduler trigger if it was
+/// moved to the trash before `deleted_before`. Returns `false` if there was no
+/// such alert.
+pub async fn purge<C: ConnectionTrait>(
+    conn: &C,
+    org_id: &str,
+    alert: &Alert,
+    deleted_before: i64,
+) -> Result<bool, infra::errors::Error> {
+    let Some(alert_id) = alert.id else {
+        return Ok(false);
+    };
+    if !table::delete_trashed_by_id(conn, org_id, alert_id, deleted_before).await? {
+        return Ok(false);
+    }
+    delete_trigger(org_id, alert).await;
+    Ok(true)
+}
+
+/// Deletes the scheduler trigger of the alert.
+async fn delete_trigger(org_id: &str, alert: &Alert) {
+    let schedule_key = scheduler_key(alert.stream_type, &alert.stream_name, &alert.name);
     if let Err(e) =
         db::scheduler::delete(org_id, db::scheduler::TriggerModule::Alert, &schedule_key).await
     {
         log::error!("Failed to delete trigger: {}", e);
     };
-    Ok(())
+}
+
+/// Returns `true` if the alert with the given name is in the trash.
+pub async fn is_trashed(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    name: &str,
+) -> Result<bool, infra::errors::Error> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    table::is_trashed_by_name(client, org_id, stream_type, stream_name, name).await
 }
 
 pub async fn list(
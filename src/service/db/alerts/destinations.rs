@@ -38,6 +38,10 @@ pub enum DestinationError {
     InvalidName,
     #[error("HTTP destination must have a url")]
     EmptyUrl,
+    #[error("Invalid CA certificate: {0}")]
+    InvalidCaCert(String),
+    #[error("Invalid proxy url: {0}")]
+    InvalidProxyUrl(String),
     #[error("SNS destination must have Topic ARN and Region")]
     InvalidSns,
     #[error("Email destination must have at least one email recipient")]
@@ -0,0 +1,34 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use config::utils::json;
+use infra::errors::{DbError, Error};
+
+use crate::{common::meta::branding::Branding, service::db};
+
+pub const BRANDING_KEY: &str = "/branding/settings";
+
+/// Returns the branding, `None` until it is configured
+pub async fn get() -> Result<Option<Branding>, anyhow::Error> {
+    match db::get(BRANDING_KEY).await {
+        Ok(val) => Ok(Some(json::from_slice(&val)?)),
+        Err(Error::DbError(DbError::KeyNotExists(_))) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn set(branding: &Branding) -> Result<(), anyhow::Error> {
+    let val = json::to_vec(branding)?;
+    Ok(db::put(BRANDING_KEY, val.into(), db::NO_NEED_WATCH, None).await?)
+}
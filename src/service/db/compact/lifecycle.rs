@@ -0,0 +1,41 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::meta::stream::StreamType;
+
+use crate::service::db;
+
+fn mk_key(org_id: &str, stream_type: StreamType, stream_name: &str) -> String {
+    format!("/compact/lifecycle/{org_id}/{stream_type}/{stream_name}")
+}
+
+/// The end of the time range of the stream already reconciled, 0 if none
+pub async fn get_offset(org_id: &str, stream_type: StreamType, stream_name: &str) -> i64 {
+    let key = mk_key(org_id, stream_type, stream_name);
+    match db::get(&key).await {
+        Ok(ret) => String::from_utf8_lossy(&ret).parse().unwrap_or_default(),
+        Err(_) => 0,
+    }
+}
+
+pub async fn set_offset(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    offset: i64,
+) -> Result<(), anyhow::Error> {
+    let key = mk_key(org_id, stream_type, stream_name);
+    Ok(db::put(&key, offset.to_string().into(), db::NO_NEED_WATCH, None).await?)
+}
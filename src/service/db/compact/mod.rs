@@ -16,6 +16,8 @@
 pub mod downsampling;
 pub mod file_list;
 pub mod files;
+pub mod lifecycle;
 pub mod organization;
+pub mod original_data;
 pub mod retention;
 pub mod stats;
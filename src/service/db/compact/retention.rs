@@ -26,6 +26,9 @@ use crate::service::db;
 
 static CACHE: Lazy<RwHashMap<String, i64>> = Lazy::new(Default::default);
 
+/// Marks the start of the range of a job deleting whole hours
+pub const HOURS_JOB_PREFIX: &str = "hours:";
+
 #[inline]
 fn mk_key(
     org_id: &str,
@@ -63,6 +66,24 @@ pub async fn delete_stream(
     Ok(db::put(&db_key, "OK".into(), db::NEED_WATCH, None).await?)
 }
 
+// delete the hours `[start_hour, end_hour]` from stream
+// hour_range is a tuple of (start, end), eg: (2023-01-02T10:00:00Z, 2023-01-02T11:00:00Z)
+pub async fn delete_stream_hours(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    hour_range: (&str, &str),
+) -> Result<(), anyhow::Error> {
+    let start = format!("{HOURS_JOB_PREFIX}{}", hour_range.0);
+    delete_stream(
+        org_id,
+        stream_type,
+        stream_name,
+        Some((&start, hour_range.1)),
+    )
+    .await
+}
+
 // set the stream is processing by the node
 pub async fn process_stream(
     org_id: &str,
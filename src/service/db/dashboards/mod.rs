@@ -13,4 +13,450 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use config::meta::dashboards::{v6, Dashboard};
+use serde_json::{json, Map, Value};
+
 pub mod reports;
+
+/// The most recent dashboard schema version that [parse_dashboard] converts
+/// dashboards into.
+pub const LATEST_DASHBOARD_VERSION: i64 = 6;
+
+/// Parses a dashboard JSON document of any supported version (v1-v6) and
+/// up-converts it into the latest v6 schema.
+///
+/// The conversion follows the same steps as the UI applies when loading an
+/// old dashboard, so a dashboard that was converted here looks exactly like
+/// one that was opened and saved again in the UI.
+pub fn parse_dashboard(value: Value) -> Result<v6::Dashboard, serde_json::Error> {
+    let Value::Object(mut data) = value else {
+        return Err(serde::de::Error::custom("dashboard must be a JSON object"));
+    };
+
+    // dashboards without a version field are always v1
+    let mut version = data.get("version").and_then(|v| v.as_i64()).unwrap_or(1);
+    if version > LATEST_DASHBOARD_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported dashboard version: {version}"
+        )));
+    }
+    if version <= 1 {
+        convert_v1_to_v2(&mut data);
+        version = 2;
+    }
+    if version == 2 {
+        convert_v2_to_v3(&mut data);
+        version = 3;
+    }
+    if version == 3 {
+        convert_v3_to_v4(&mut data);
+        version = 4;
+    }
+    if version == 4 {
+        convert_v4_to_v5(&mut data);
+    }
+    // v5 -> v6 doesn't change the JSON document
+    data.insert("version".to_string(), json!(LATEST_DASHBOARD_VERSION));
+
+    serde_json::from_value(Value::Object(data))
+}
+
+/// Returns the v6 representation of a stored dashboard, converting older
+/// versions as needed.
+pub fn to_latest_version(dashboard: &Dashboard) -> Result<v6::Dashboard, serde_json::Error> {
+    let value = match dashboard.version {
+        1 => serde_json::to_value(&dashboard.v1)?,
+        2 => serde_json::to_value(&dashboard.v2)?,
+        3 => serde_json::to_value(&dashboard.v3)?,
+        4 => serde_json::to_value(&dashboard.v4)?,
+        5 => serde_json::to_value(&dashboard.v5)?,
+        6 => serde_json::to_value(&dashboard.v6)?,
+        v => {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported dashboard version: {v}"
+            )));
+        }
+    };
+    let mut dash = parse_dashboard(value)?;
+    // `updated_at` is never serialized, so carry it over explicitly
+    dash.updated_at = dashboard.updated_at;
+    Ok(dash)
+}
+
+/// v1 -> v2: panels get their layout inlined and a single query each.
+fn convert_v1_to_v2(data: &mut Map<String, Value>) {
+    let mut layouts = Map::new();
+    if let Some(Value::Array(items)) = data.remove("layouts") {
+        for layout in items {
+            let Some(panel_id) = layout.get("panelId").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            layouts.insert(
+                panel_id.to_string(),
+                json!({
+                    "x": layout.get("x"),
+                    "y": layout.get("y"),
+                    "w": layout.get("w"),
+                    "h": layout.get("h"),
+                    "i": layout.get("i"),
+                }),
+            );
+        }
+    }
+
+    let panels = match data.remove("panels") {
+        Some(Value::Array(panels)) => panels,
+        _ => vec![],
+    };
+    let panels = panels
+        .into_iter()
+        .map(|panel| {
+            let id = panel.get("id").cloned().unwrap_or_default();
+            let config = panel.get("config").cloned().unwrap_or_default();
+            let fields = panel.get("fields").cloned().unwrap_or_default();
+            let layout = id
+                .as_str()
+                .and_then(|id| layouts.get(id).cloned())
+                .unwrap_or(Value::Null);
+            json!({
+                "id": id,
+                "type": panel.get("type"),
+                "title": config.get("title").cloned().unwrap_or_else(|| json!("")),
+                "description": config.get("description").cloned().unwrap_or_else(|| json!("")),
+                "config": {
+                    "show_legends": config.get("show_legends").cloned().unwrap_or(json!(true)),
+                    "legends_position": config.get("legends_position"),
+                    "unit": config.get("unit"),
+                    "unit_custom": config.get("unit_custom"),
+                },
+                "queryType": panel.get("queryType").cloned().unwrap_or_else(|| json!("")),
+                "queries": [{
+                    "query": panel.get("query"),
+                    "customQuery": panel.get("customQuery").cloned().unwrap_or(json!(false)),
+                    "fields": {
+                        "stream_type": fields.get("stream_type").cloned().unwrap_or_else(|| json!("logs")),
+                        "stream": fields.get("stream").cloned().unwrap_or_else(|| json!("")),
+                        "x": fields.get("x").cloned().unwrap_or_else(|| json!([])),
+                        "y": fields.get("y").cloned().unwrap_or_else(|| json!([])),
+                        "z": [],
+                        "filter": fields.get("filter").cloned().unwrap_or_else(|| json!([])),
+                    },
+                    "config": {
+                        "promql_legend": config
+                            .get("promql_legend")
+                            .filter(|v| !v.is_null())
+                            .cloned()
+                            .unwrap_or_else(|| json!("")),
+                    },
+                }],
+                "layout": layout,
+            })
+        })
+        .collect::<Vec<_>>();
+    data.insert("panels".to_string(), Value::Array(panels));
+}
+
+/// v2 -> v3: the layout grid goes from 12 to 48 columns and panels move into a
+/// default tab.
+fn convert_v2_to_v3(data: &mut Map<String, Value>) {
+    let mut panels = match data.remove("panels") {
+        Some(Value::Array(panels)) => panels,
+        _ => vec![],
+    };
+    for panel in panels.iter_mut() {
+        let Some(layout) = panel.get_mut("layout").and_then(|l| l.as_object_mut()) else {
+            continue;
+        };
+        for key in ["w", "x"] {
+            if let Some(v) = layout.get(key).and_then(|v| v.as_i64()) {
+                layout.insert(key.to_string(), json!(v * 4));
+            }
+        }
+    }
+    data.insert(
+        "tabs".to_string(),
+        json!([{
+            "panels": panels,
+            "name": "Default",
+            "tabId": "default",
+        }]),
+    );
+}
+
+/// v3 -> v4: excess x-axis fields of non-table panels move to `breakdown`.
+fn convert_v3_to_v4(data: &mut Map<String, Value>) {
+    for_each_query(data, |panel_type, query| {
+        let Some(fields) = query.get_mut("fields").and_then(|f| f.as_object_mut()) else {
+            return;
+        };
+        let mut breakdown = match fields.remove("breakdown") {
+            Some(Value::Array(b)) => b,
+            _ => vec![],
+        };
+        if panel_type != "table" {
+            if let Some(Value::Array(x)) = fields.get_mut("x") {
+                if x.len() > 1 {
+                    breakdown.extend(x.drain(1..));
+                }
+            }
+        }
+        fields.insert("breakdown".to_string(), Value::Array(breakdown));
+    });
+}
+
+/// v4 -> v5: the flat filter list becomes a group of `AND`-ed conditions.
+fn convert_v4_to_v5(data: &mut Map<String, Value>) {
+    for_each_query(data, |_, query| {
+        let Some(fields) = query.get_mut("fields").and_then(|f| f.as_object_mut()) else {
+            return;
+        };
+        let conditions = match fields.remove("filter") {
+            Some(Value::Array(filters)) => filters
+                .into_iter()
+                .map(|f| {
+                    json!({
+                        "type": f.get("type"),
+                        "values": f.get("values").cloned().unwrap_or_else(|| json!([])),
+                        "column": f.get("column"),
+                        "operator": f.get("operator"),
+                        "value": f.get("value"),
+                        "logicalOperator": "AND",
+                        "filterType": "condition",
+                    })
+                })
+                .collect(),
+            // already converted, keep it as it is
+            Some(filter @ Value::Object(_)) => {
+                fields.insert("filter".to_string(), filter);
+                return;
+            }
+            _ => vec![],
+        };
+        fields.insert(
+            "filter".to_string(),
+            json!({
+                "filterType": "group",
+                "logicalOperator": "AND",
+                "conditions": conditions,
+            }),
+        );
+    });
+}
+
+/// Calls `f` with the panel type and each query of every panel in every tab.
+fn for_each_query(data: &mut Map<String, Value>, mut f: impl FnMut(&str, &mut Value)) {
+    let Some(Value::Array(tabs)) = data.get_mut("tabs") else {
+        return;
+    };
+    for tab in tabs.iter_mut() {
+        let Some(Value::Array(panels)) = tab.get_mut("panels") else {
+            continue;
+        };
+        for panel in panels.iter_mut() {
+            let panel_type = panel
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let Some(Value::Array(queries)) = panel.get_mut("queries") else {
+                continue;
+            };
+            for query in queries.iter_mut() {
+                f(&panel_type, query);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use config::meta::dashboards::{v2, v5};
+
+    use super::*;
+
+    fn v2_dashboard_json() -> Value {
+        json!({
+            "version": 2,
+            "dashboardId": "7211342181",
+            "title": "v2 dashboard",
+            "description": "",
+            "role": "",
+            "owner": "root@example.com",
+            "created": "2023-10-03T06:41:01.245+00:00",
+            "panels": [{
+                "id": "Panel_ID4468610",
+                "type": "line",
+                "title": "p1",
+                "description": "",
+                "config": {
+                    "show_legends": true,
+                    "legends_position": null
+                },
+                "queryType": "sql",
+                "queries": [{
+                    "query": "SELECT histogram(_timestamp) as \"x_axis_1\", count(_timestamp) as \"y_axis_1\", k8s_namespace_name as \"x_axis_2\" FROM \"default\" WHERE code = '200' GROUP BY x_axis_1, x_axis_2",
+                    "customQuery": false,
+                    "fields": {
+                        "stream": "default",
+                        "stream_type": "logs",
+                        "x": [
+                            {
+                                "label": "Timestamp",
+                                "alias": "x_axis_1",
+                                "column": "_timestamp",
+                                "color": null,
+                                "aggregationFunction": "histogram"
+                            },
+                            {
+                                "label": "Namespace",
+                                "alias": "x_axis_2",
+                                "column": "k8s_namespace_name",
+                                "color": null
+                            }
+                        ],
+                        "y": [{
+                            "label": "Timestamp",
+                            "alias": "y_axis_1",
+                            "column": "_timestamp",
+                            "color": "#5960b2",
+                            "aggregationFunction": "count"
+                        }],
+                        "filter": [{
+                            "type": "condition",
+                            "values": [],
+                            "column": "code",
+                            "operator": "=",
+                            "value": "200"
+                        }]
+                    },
+                    "config": {
+                        "promql_legend": ""
+                    }
+                }],
+                "layout": {
+                    "x": 3,
+                    "y": 0,
+                    "w": 6,
+                    "h": 9,
+                    "i": 1
+                }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_parse_dashboard_v2() {
+        let dash = parse_dashboard(v2_dashboard_json()).unwrap();
+        assert_eq!(dash.dashboard_id, "7211342181");
+        assert_eq!(dash.title, "v2 dashboard");
+        assert_eq!(dash.tabs.len(), 1);
+
+        let tab = &dash.tabs[0];
+        assert_eq!(tab.tab_id, "default");
+        assert_eq!(tab.panels.len(), 1);
+
+        let panel = &tab.panels[0];
+        assert_eq!((panel.layout.x, panel.layout.w), (12, 24));
+
+        let fields = &panel.queries[0].fields;
+        assert_eq!(fields.x.len(), 1);
+        assert_eq!(fields.x[0].alias, "x_axis_1");
+        let breakdown = fields.breakdown.as_ref().unwrap();
+        assert_eq!(breakdown.len(), 1);
+        assert_eq!(breakdown[0].alias, "x_axis_2");
+
+        let v6::PanelFilter::Group(group) = &fields.filter else {
+            panic!("filter should be converted into a group");
+        };
+        assert_eq!(group.logical_operator, "AND");
+        assert_eq!(group.conditions.len(), 1);
+        let v6::PanelFilter::Condition(cond) = &group.conditions[0] else {
+            panic!("filter group should contain a condition");
+        };
+        assert_eq!(cond.column, "code");
+        assert_eq!(cond.value.as_deref(), Some("200"));
+    }
+
+    #[test]
+    fn test_stored_v2_dashboard_round_trips_to_v6() {
+        let stored: v2::Dashboard = serde_json::from_value(v2_dashboard_json()).unwrap();
+        let stored: Dashboard = stored.into();
+        assert_eq!(stored.version, 2);
+
+        let exported = to_latest_version(&stored).unwrap();
+        assert_eq!(exported, parse_dashboard(v2_dashboard_json()).unwrap());
+
+        // importing the exported dashboard again must not change it
+        let exported_json = serde_json::to_value(&exported).unwrap();
+        assert_eq!(exported_json["version"], json!(6));
+        let imported = parse_dashboard(exported_json).unwrap();
+        assert_eq!(imported, exported);
+
+        let imported: Dashboard = imported.into();
+        assert_eq!(imported.version, 6);
+    }
+
+    #[test]
+    fn test_stored_v5_dashboard_upgrades_to_v6() {
+        let mut v5_json =
+            serde_json::to_value(parse_dashboard(v2_dashboard_json()).unwrap()).unwrap();
+        v5_json["version"] = json!(5);
+        let stored: v5::Dashboard = serde_json::from_value(v5_json.clone()).unwrap();
+        let upgraded = v6::Dashboard::from(stored.clone());
+
+        let stored: Dashboard = stored.into();
+        assert_eq!(stored.version, 5);
+        assert_eq!(to_latest_version(&stored).unwrap(), upgraded);
+        assert_eq!(parse_dashboard(v5_json).unwrap(), upgraded);
+        assert_eq!(Dashboard::from(upgraded).version, 6);
+    }
+
+    #[test]
+    fn test_parse_dashboard_v1() {
+        let dash = parse_dashboard(json!({
+            "title": "v1 dashboard",
+            "dashboardId": "1501078512",
+            "description": "",
+            "panels": [{
+                "id": "Panel_ID7857010",
+                "type": "bar",
+                "fields": {
+                    "stream": "default",
+                    "stream_type": "logs",
+                    "x": [],
+                    "y": [],
+                    "filter": []
+                },
+                "config": {
+                    "title": "p5",
+                    "description": "",
+                    "show_legends": true,
+                    "legends_position": "bottom",
+                    "promql_legend": null
+                },
+                "query": "SELECT * FROM \"default\"",
+                "customQuery": true
+            }],
+            "layouts": [{
+                "x": 1,
+                "y": 0,
+                "w": 12,
+                "h": 13,
+                "i": 1,
+                "panelId": "Panel_ID7857010",
+                "static": false
+            }]
+        }))
+        .unwrap();
+        let panel = &dash.tabs[0].panels[0];
+        assert_eq!(panel.title, "p5");
+        assert_eq!((panel.layout.x, panel.layout.w), (4, 48));
+        assert!(panel.queries[0].custom_query);
+    }
+
+    #[test]
+    fn test_parse_dashboard_unsupported_version() {
+        assert!(parse_dashboard(json!({ "version": 99, "title": "t" })).is_err());
+        assert!(parse_dashboard(json!([])).is_err());
+    }
+}
@@ -53,6 +53,7 @@ pub async fn get(org_id: &str, name: &str) -> Result<Vec<vrl::value::Value>, any
         search_type: None,
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     // do search
     match SearchService::search("", org_id, StreamType::EnrichmentTables, None, &req).await {
@@ -106,6 +107,11 @@ pub async fn notify_update(org_id: &str, name: &str) -> Result<(), infra::errors
 }
 
 pub async fn delete(org_id: &str, name: &str) -> Result<(), infra::errors::Error> {
+    let key = format!("/enrichment_table_columns/{org_id}/{name}");
+    if let Err(e) = super::delete_if_exists(&key, false, super::NO_NEED_WATCH).await {
+        log::error!("Error deleting enrichment table {org_id}/{name} columns: {e}");
+    }
+
     let cluster_coordinator = db::get_coordinator().await;
     let key: String = format!(
         "/enrichment_table/{org_id}/{}/{}",
@@ -115,6 +121,30 @@ pub async fn delete(org_id: &str, name: &str) -> Result<(), infra::errors::Error
     cluster_coordinator.delete(&key, false, false, None).await
 }
 
+/// Returns the columns of the table in the order they were uploaded in
+pub async fn get_columns(org_id: &str, name: &str) -> Vec<String> {
+    let key = format!("/enrichment_table_columns/{org_id}/{name}");
+    match super::get(&key).await {
+        Ok(v) => json::from_slice(&v).unwrap_or_default(),
+        Err(_) => vec![],
+    }
+}
+
+pub async fn set_columns(
+    org_id: &str,
+    name: &str,
+    columns: &[String],
+) -> Result<(), infra::errors::Error> {
+    let key = format!("/enrichment_table_columns/{org_id}/{name}");
+    super::put(
+        &key,
+        json::to_vec(columns).unwrap().into(),
+        super::NO_NEED_WATCH,
+        None,
+    )
+    .await
+}
+
 pub async fn watch() -> Result<(), anyhow::Error> {
     let key = "/enrichment_table/";
     let cluster_coordinator = db::get_coordinator().await;
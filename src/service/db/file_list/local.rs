@@ -35,10 +35,8 @@ pub async fn set(key: &str, meta: Option<FileMeta>, deleted: bool) -> Result<(),
         }
     }
 
-    let cfg = config::get_config();
-
     // notify other nodes
-    if cfg.memory_cache.cache_latest_files {
+    if infra::cache::file_data::memory::cache_latest_files_by_key(key).await {
         let mut q = BROADCAST_QUEUE.write().await;
         q.push(file_data);
     }
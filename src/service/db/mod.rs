@@ -23,6 +23,7 @@ use {
 };
 
 pub mod alerts;
+pub mod branding;
 pub mod compact;
 pub mod dashboards;
 pub mod distinct_values;
@@ -41,9 +42,11 @@ pub mod pipeline;
 pub mod saved_view;
 pub mod scheduler;
 pub mod schema;
+pub mod schema_drift;
 pub mod search_job;
 pub mod session;
 pub mod short_url;
+pub mod synthetic;
 pub mod syslog;
 pub mod user;
 pub mod version;
@@ -47,6 +47,7 @@ pub async fn set_org_setting(org_name: &str, setting: &OrganizationSetting) -> e
         .write()
         .await
         .insert(key.to_string(), setting.clone());
+    crate::service::search::concurrency::invalidate_limit(org_name);
     Ok(())
 }
 
@@ -111,11 +112,17 @@ pub async fn watch() -> Result<(), anyhow::Error> {
                     continue;
                 }
             };
+            let org_id = item_key
+                .strip_prefix(&format!("{key}/"))
+                .map(|v| v.to_string());
             ORGANIZATION_SETTING
                 .clone()
                 .write()
                 .await
                 .insert(item_key, json_val);
+            if let Some(org_id) = org_id {
+                crate::service::search::concurrency::invalidate_limit(&org_id);
+            }
         }
     }
 }
@@ -51,6 +51,14 @@ pub enum PipelineError {
     InvalidDerivedStream(String),
     #[error("Error deleting previous DerivedStream: {0}")]
     DeleteDerivedStream(String),
+    #[error("Invalid backfill request: {0}")]
+    InvalidBackfill(String),
+    // not found
+    #[error("Backfill job {0} not found.")]
+    BackfillNotFound(String),
+    // conflict
+    #[error("Backfill overlaps the time range of backfill job {0}.")]
+    BackfillOverlap(String),
 }
 
 /// Stores a new pipeline to database.
@@ -285,6 +285,11 @@ pub async fn watch() -> Result<(), anyhow::Error> {
                 };
 
                 let item_key = ev_key.strip_prefix(key).unwrap();
+                infra::schema::forget_not_found(item_key);
+                let keys = item_key.split('/').collect::<Vec<&str>>();
+                let org_id = keys[0];
+                let stream_type = StreamType::from(keys[1]);
+                let stream_name = keys[2];
                 let r = STREAM_SCHEMAS.read().await;
                 let prev_start_dt = if let Some(schemas) = r.get(&item_key.to_owned()) {
                     let idx = if schemas.len() >= 2 {
@@ -341,11 +346,22 @@ pub async fn watch() -> Result<(), anyhow::Error> {
                 w.insert(item_key.to_string(), settings);
                 drop(w);
                 let mut w = STREAM_SCHEMAS_LATEST.write().await;
-                w.insert(
-                    item_key.to_string(),
-                    SchemaCache::new(latest_schema.clone()),
-                );
+                let is_new_stream = w
+                    .insert(
+                        item_key.to_string(),
+                        SchemaCache::new(latest_schema.clone()),
+                    )
+                    .is_none();
                 drop(w);
+                if is_new_stream {
+                    infra::schema::observe_time_to_queryable(
+                        org_id,
+                        stream_type,
+                        stream_name,
+                        &latest_schema,
+                        "watch",
+                    );
+                }
                 let cfg = get_config();
                 if cfg.common.schema_cache_compress_enabled {
                     let schema_versions = schema_versions
@@ -394,11 +410,6 @@ pub async fn watch() -> Result<(), anyhow::Error> {
                     drop(w);
                 }
 
-                let keys = item_key.split('/').collect::<Vec<&str>>();
-                let org_id = keys[0];
-                let stream_type = StreamType::from(keys[1]);
-                let stream_name = keys[2];
-
                 if stream_type.eq(&StreamType::EnrichmentTables) {
                     let data = super::enrichment_table::get(org_id, stream_name)
                         .await
@@ -453,6 +464,12 @@ pub async fn watch() -> Result<(), anyhow::Error> {
                 {
                     log::error!("del_offset: {}", e);
                 }
+                if let Err(e) =
+                    super::compact::original_data::del_offset(org_id, stream_type, stream_name)
+                        .await
+                {
+                    log::error!("del_offset: {}", e);
+                }
 
                 if stream_type.eq(&StreamType::EnrichmentTables) && is_local_disk_storage() {
                     let data_dir = format!(
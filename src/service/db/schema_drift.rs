@@ -0,0 +1,46 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::utils::{json, time::now_micros};
+
+use crate::service::{alerts::schema_drift::SchemaDriftEvent, db};
+
+pub const SCHEMA_DRIFT_KEY: &str = "/schema_drift/events/";
+
+/// Stores the schema change events an ingester saw, for the node sending the
+/// schema drift notifications
+pub async fn push(node: &str, events: &[SchemaDriftEvent]) -> Result<(), anyhow::Error> {
+    let key = format!("{SCHEMA_DRIFT_KEY}{}/{node}", now_micros());
+    let val = json::to_vec(events)?;
+    Ok(db::put(&key, val.into(), db::NO_NEED_WATCH, None).await?)
+}
+
+/// Removes and returns the stored events, oldest first
+pub async fn take() -> Result<Vec<SchemaDriftEvent>, anyhow::Error> {
+    let mut batches = db::list(SCHEMA_DRIFT_KEY)
+        .await?
+        .into_iter()
+        .collect::<Vec<_>>();
+    batches.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut events = Vec::new();
+    for (key, val) in batches {
+        db::delete_if_exists(&key, false, db::NO_NEED_WATCH).await?;
+        match json::from_slice::<Vec<SchemaDriftEvent>>(&val) {
+            Ok(batch) => events.extend(batch),
+            Err(e) => log::error!("[SCHEMA_DRIFT] invalid events at {key}: {e}"),
+        }
+    }
+    Ok(events)
+}
@@ -17,8 +17,9 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
 use chrono::Utc;
-use config::get_config;
+use config::{get_config, RwHashMap};
 use infra::{db::Event, table::short_urls};
+use once_cell::sync::Lazy;
 
 use crate::{common::infra::config::SHORT_URLS, service::db};
 
@@ -27,6 +28,12 @@ pub const SHORT_URL_KEY: &str = "/short_urls/";
 // GC interval for `SHORT_URLS` cache in days
 const SHORT_URL_GC_INTERVAL: i64 = 1; // days
 const SHORT_URL_CACHE_LIMIT: i64 = 10_000; // records
+                                           // Minimum interval between writes of the last access time of a short URL
+const LAST_ACCESSED_UPDATE_INTERVAL: i64 = 3600 * 1_000_000; // micros
+
+// The last access time of each short URL written to the DB by this node, at
+// most SHORT_URL_CACHE_LIMIT entries
+static LAST_ACCESSED: Lazy<RwHashMap<String, i64>> = Lazy::new(Default::default);
 
 pub async fn get(short_id: &str) -> Result<String, anyhow::Error> {
     if let Some(v) = SHORT_URLS.get(short_id) {
@@ -41,8 +48,12 @@ pub async fn get(short_id: &str) -> Result<String, anyhow::Error> {
     Ok(original_url)
 }
 
-pub async fn set(short_id: &str, entry: short_urls::ShortUrlRecord) -> Result<(), anyhow::Error> {
-    if let Err(e) = short_urls::add(short_id, &entry.original_url).await {
+pub async fn set(
+    org_id: &str,
+    short_id: &str,
+    entry: short_urls::ShortUrlRecord,
+) -> Result<(), anyhow::Error> {
+    if let Err(e) = short_urls::add(org_id, short_id, &entry.original_url).await {
         log::error!("Failed to add short URL to DB : {}", e);
         return Err(e).context("Failed to add short URL to DB");
     }
@@ -51,11 +62,84 @@ pub async fn set(short_id: &str, entry: short_urls::ShortUrlRecord) -> Result<()
     cluster::emit_put_event(short_id).await?;
     // trigger watch event super cluster
     #[cfg(feature = "enterprise")]
-    super_cluster::emit_put_event(short_id, entry).await?;
+    super_cluster::emit_put_event(org_id, short_id, entry).await?;
 
     Ok(())
 }
 
+/// Deletes the short URL of the org, returning whether it existed.
+pub async fn delete(org_id: &str, short_id: &str) -> Result<bool, anyhow::Error> {
+    if !short_urls::remove_by_org(org_id, short_id).await? {
+        return Ok(false);
+    }
+    emit_delete_events(&[short_id.to_string()]).await?;
+    Ok(true)
+}
+
+/// Deletes the short URLs of the org that are past the retention period,
+/// returning how many were deleted.
+pub async fn purge_expired(org_id: &str) -> Result<usize, anyhow::Error> {
+    let retention_period = chrono::Duration::days(get_config().limit.short_url_retention_days);
+    let expired_before = Utc::now() - retention_period;
+    let expired_short_ids =
+        short_urls::get_expired_by_org(org_id, expired_before.timestamp_micros()).await?;
+    if expired_short_ids.is_empty() {
+        return Ok(0);
+    }
+    short_urls::batch_remove(expired_short_ids.clone()).await?;
+    emit_delete_events(&expired_short_ids).await?;
+    Ok(expired_short_ids.len())
+}
+
+/// Records an access to the short URL. The access time is written to the DB at
+/// most once per [LAST_ACCESSED_UPDATE_INTERVAL] to avoid a write per redirect.
+pub async fn record_access(short_id: &str) -> Result<(), anyhow::Error> {
+    let now = Utc::now().timestamp_micros();
+    if !should_record_access(&LAST_ACCESSED, short_id, now) {
+        return Ok(());
+    }
+    short_urls::set_last_accessed(short_id, now, now - LAST_ACCESSED_UPDATE_INTERVAL).await?;
+    Ok(())
+}
+
+fn should_record_access(
+    last_accessed_map: &RwHashMap<String, i64>,
+    short_id: &str,
+    now: i64,
+) -> bool {
+    if last_accessed_map.len() >= SHORT_URL_CACHE_LIMIT as usize {
+        // entries past the interval are recorded again anyway
+        last_accessed_map
+            .retain(|_, last_accessed| now - *last_accessed < LAST_ACCESSED_UPDATE_INTERVAL);
+        if last_accessed_map.len() >= SHORT_URL_CACHE_LIMIT as usize {
+            last_accessed_map.clear();
+        }
+    }
+    let mut should_record = true;
+    last_accessed_map
+        .entry(short_id.to_string())
+        .and_modify(|last_accessed| {
+            if now - *last_accessed < LAST_ACCESSED_UPDATE_INTERVAL {
+                should_record = false;
+            } else {
+                *last_accessed = now;
+            }
+        })
+        .or_insert(now);
+    should_record
+}
+
+// Removes the deleted short URLs from the caches of the cluster and the super
+// cluster.
+async fn emit_delete_events(short_ids: &[String]) -> Result<(), anyhow::Error> {
+    for short_id in short_ids {
+        cluster::emit_delete_event(short_id).await?;
+        #[cfg(feature = "enterprise")]
+        super_cluster::emit_delete_event(short_id).await?;
+    }
+    Ok(())
+}
+
 pub async fn watch() -> Result<(), anyhow::Error> {
     let key = SHORT_URL_KEY;
     let cluster_coordinator = db::get_coordinator().await;
@@ -94,6 +178,7 @@ pub async fn watch() -> Result<(), anyhow::Error> {
             Event::Delete(ev) => {
                 let item_key = ev.key.strip_prefix(key).unwrap();
                 SHORT_URLS.remove(item_key);
+                LAST_ACCESSED.remove(item_key);
             }
             Event::Empty => {}
         }
@@ -147,11 +232,7 @@ pub async fn gc_cache(retention_period_minutes: i64) -> Result<(), anyhow::Error
             short_urls::batch_remove(expired_short_ids.clone()).await?;
 
             // delete from cache & notify super cluster
-            for short_id in expired_short_ids {
-                cluster::emit_delete_event(&short_id).await?;
-                #[cfg(feature = "enterprise")]
-                super_cluster::emit_delete_event(&short_id).await?;
-            }
+            emit_delete_events(&expired_short_ids).await?;
         }
     }
 
@@ -207,10 +288,11 @@ mod super_cluster {
 
     /// Sends event to super cluster queue for a new short URL entry.
     pub async fn emit_put_event(
+        org_id: &str,
         short_id: &str,
         entry: short_urls::ShortUrlRecord,
     ) -> Result<(), infra::errors::Error> {
-        let key = short_url_key(short_id);
+        let key = format!("{}/{org_id}", short_url_key(short_id));
         if get_o2_config().super_cluster.enabled {
             o2_enterprise::enterprise::super_cluster::queue::short_url_put(
                 &key,
@@ -243,3 +325,49 @@ mod super_cluster {
         format!("{SHORT_URL_KEY}{short_id}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_record_access() {
+        let map = RwHashMap::default();
+        let now = Utc::now().timestamp_micros();
+        assert!(should_record_access(&map, "test_record_access", now));
+        assert!(!should_record_access(&map, "test_record_access", now + 1));
+        assert!(!should_record_access(
+            &map,
+            "test_record_access",
+            now + LAST_ACCESSED_UPDATE_INTERVAL - 1
+        ));
+        assert!(should_record_access(
+            &map,
+            "test_record_access",
+            now + LAST_ACCESSED_UPDATE_INTERVAL
+        ));
+        // throttled per short URL
+        assert!(should_record_access(
+            &map,
+            "test_record_access_other",
+            now + 1
+        ));
+    }
+
+    #[test]
+    fn test_last_accessed_is_bounded() {
+        let map = RwHashMap::default();
+        let now = Utc::now().timestamp_micros();
+        should_record_access(&map, "stale", now - LAST_ACCESSED_UPDATE_INTERVAL);
+        for i in 1..SHORT_URL_CACHE_LIMIT {
+            should_record_access(&map, &i.to_string(), now);
+        }
+        assert_eq!(map.len(), SHORT_URL_CACHE_LIMIT as usize);
+        // the stale entry makes room
+        assert!(should_record_access(&map, "new", now));
+        assert!(!map.contains_key("stale"));
+        // all entries are recent, start over
+        assert!(should_record_access(&map, "newer", now));
+        assert_eq!(map.len(), 1);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::{meta::synthetic::SyntheticGenerator, utils::json};
+use infra::errors::{DbError, Error};
+
+use crate::service::db;
+
+pub const SYNTHETIC_KEY: &str = "/synthetic/generators/";
+
+fn mk_key(id: &str) -> String {
+    format!("{SYNTHETIC_KEY}{id}")
+}
+
+/// Returns the generator, `None` once it was canceled or finished
+pub async fn get(id: &str) -> Result<Option<SyntheticGenerator>, anyhow::Error> {
+    match db::get(&mk_key(id)).await {
+        Ok(val) => Ok(Some(json::from_slice(&val)?)),
+        Err(Error::DbError(DbError::KeyNotExists(_))) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn set(generator: &SyntheticGenerator) -> Result<(), anyhow::Error> {
+    let key = mk_key(&generator.id);
+    let val = json::to_vec(generator)?;
+    Ok(db::put(&key, val.into(), db::NO_NEED_WATCH, None).await?)
+}
+
+pub async fn delete(id: &str) -> Result<(), anyhow::Error> {
+    db::delete_if_exists(&mk_key(id), false, db::NO_NEED_WATCH)
+        .await
+        .map_err(Into::into)
+}
+
+pub async fn list() -> Result<Vec<SyntheticGenerator>, anyhow::Error> {
+    let mut list = Vec::new();
+    for val in db::list_values(SYNTHETIC_KEY).await? {
+        list.push(json::from_slice(&val)?);
+    }
+    Ok(list)
+}
@@ -0,0 +1,302 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//! Exports an enrichment table as it is stored, keeping the column order of
+//! the uploaded file.
+
+use std::str::FromStr;
+
+use config::{
+    meta::stream::StreamType,
+    utils::{
+        hash::{gxhash, Sum64},
+        json,
+    },
+    TIMESTAMP_COL_NAME,
+};
+
+use crate::{
+    common::infra::config::ENRICHMENT_TABLES,
+    service::{db::enrichment_table, format_stream_name},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DownloadFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl DownloadFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DownloadFormat::Csv => "text/csv",
+            DownloadFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+impl FromStr for DownloadFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(DownloadFormat::Csv),
+            "ndjson" => Ok(DownloadFormat::Ndjson),
+            _ => Err(format!("unsupported format [{s}], must be csv or ndjson")),
+        }
+    }
+}
+
+/// The rows of an enrichment table in a stable order: by `_timestamp`, which
+/// is the upload time of the rows, then by their values.
+pub struct TableExport {
+    columns: Vec<String>,
+    rows: Vec<Vec<json::Value>>,
+    version: String,
+}
+
+impl TableExport {
+    pub fn new(columns: Vec<String>, records: Vec<json::Map<String, json::Value>>) -> Self {
+        let mut rows = records
+            .into_iter()
+            .map(|mut record| {
+                let ts = record
+                    .get(TIMESTAMP_COL_NAME)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or_default();
+                let row = columns
+                    .iter()
+                    .map(|col| record.remove(col).unwrap_or(json::Value::Null))
+                    .collect::<Vec<_>>();
+                let line = csv_line(row.iter().map(csv_cell));
+                (ts, line, row)
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+
+        let mut content = csv_line(columns.iter().cloned());
+        for (_, line, _) in rows.iter() {
+            content.extend_from_slice(line);
+        }
+        let version = format!("{:016x}", gxhash::new().sum64(&content));
+        Self {
+            columns,
+            rows: rows.into_iter().map(|(_, _, row)| row).collect(),
+            version,
+        }
+    }
+
+    /// Changes whenever the columns or rows of the table change
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Renders `limit` rows starting at `from_row`. The CSV header is only
+    /// part of the first chunk, so that the chunks of a download concatenate
+    /// to the full table. Returns the row to resume from when rows are left.
+    pub fn render(
+        &self,
+        format: DownloadFormat,
+        from_row: usize,
+        limit: Option<usize>,
+    ) -> (Vec<u8>, Option<usize>) {
+        let from_row = from_row.min(self.rows.len());
+        let to_row = match limit {
+            Some(limit) => from_row.saturating_add(limit).min(self.rows.len()),
+            None => self.rows.len(),
+        };
+        let mut buf = Vec::new();
+        if format == DownloadFormat::Csv && from_row == 0 {
+            buf.extend_from_slice(&csv_line(self.columns.iter().cloned()));
+        }
+        for row in &self.rows[from_row..to_row] {
+            match format {
+                DownloadFormat::Csv => buf.extend_from_slice(&csv_line(row.iter().map(csv_cell))),
+                DownloadFormat::Ndjson => {
+                    buf.extend_from_slice(ndjson_line(&self.columns, row).as_bytes())
+                }
+            }
+        }
+        let next_row = (to_row < self.rows.len()).then_some(to_row);
+        (buf, next_row)
+    }
+}
+
+/// Loads the table from the enrichment table cache, in the column order of
+/// the uploaded files. Returns `None` when the table doesn't exist.
+pub async fn export(org_id: &str, table_name: &str) -> Result<Option<TableExport>, anyhow::Error> {
+    let stream_name = format_stream_name(table_name.trim());
+    let schema = infra::schema::get(org_id, &stream_name, StreamType::EnrichmentTables).await?;
+    if schema.fields().is_empty() {
+        return Ok(None);
+    }
+
+    let key = format!("{org_id}/{}/{stream_name}", StreamType::EnrichmentTables);
+    let cached = ENRICHMENT_TABLES.get(&key).map(|table| table.data.clone());
+    let data = match cached {
+        Some(data) => data,
+        None => enrichment_table::get(org_id, &stream_name).await?,
+    };
+    let records = data
+        .iter()
+        .filter_map(|v| match json::to_value(v) {
+            Ok(json::Value::Object(record)) => Some(record),
+            _ => None,
+        })
+        .collect();
+
+    let stored_columns = enrichment_table::get_columns(org_id, &stream_name).await;
+    let schema_columns = schema
+        .fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect::<Vec<_>>();
+    let columns = table_columns(stored_columns, &schema_columns);
+    Ok(Some(TableExport::new(columns, records)))
+}
+
+/// The uploaded columns in their order, followed by the columns only known
+/// from the schema, eg: from tables uploaded through the internal gRPC API.
+fn table_columns(stored_columns: Vec<String>, schema_columns: &[String]) -> Vec<String> {
+    let mut columns = stored_columns
+        .into_iter()
+        .filter(|col| schema_columns.contains(col))
+        .collect::<Vec<_>>();
+    for col in schema_columns {
+        if col != TIMESTAMP_COL_NAME && !columns.contains(col) {
+            columns.push(col.to_string());
+        }
+    }
+    columns
+}
+
+/// The uploaded columns after appending `columns` to the `stored` ones, or
+/// replacing them.
+pub(crate) fn merge_columns(stored: Vec<String>, columns: &[String], append: bool) -> Vec<String> {
+    if !append || stored.is_empty() {
+        return columns.to_vec();
+    }
+    let mut merged = stored;
+    for col in columns {
+        if !merged.contains(col) {
+            merged.push(col.to_string());
+        }
+    }
+    merged
+}
+
+fn csv_cell(value: &json::Value) -> String {
+    match value {
+        json::Value::Null => String::new(),
+        json::Value::String(s) => s.to_string(),
+        v => v.to_string(),
+    }
+}
+
+fn csv_line(cells: impl IntoIterator<Item = String>) -> Vec<u8> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    // writing to a Vec can't fail
+    let _ = wtr.write_record(cells);
+    wtr.into_inner().unwrap_or_default()
+}
+
+fn ndjson_line(columns: &[String], row: &[json::Value]) -> String {
+    // built by hand, a json object doesn't keep the column order
+    let fields = columns
+        .iter()
+        .zip(row)
+        .map(|(col, value)| format!("{}:{}", json::to_string(col).unwrap(), value))
+        .collect::<Vec<_>>();
+    format!("{{{}}}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> TableExport {
+        let columns = vec!["zone".to_string(), "city".to_string(), "code".to_string()];
+        let records = [
+            json::json!({"_timestamp": 2, "zone": "eu", "city": "Berlin, DE", "code": 10}),
+            json::json!({"_timestamp": 1, "zone": "us", "city": "Austin", "code": 20}),
+            json::json!({"_timestamp": 1, "zone": "ap", "city": "Tokyo", "code": null}),
+        ]
+        .into_iter()
+        .map(|v| v.as_object().unwrap().clone())
+        .collect();
+        TableExport::new(columns, records)
+    }
+
+    #[test]
+    fn test_csv_keeps_column_order() {
+        let (body, next_row) = table().render(DownloadFormat::Csv, 0, None);
+        assert_eq!(next_row, None);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "zone,city,code\nap,Tokyo,\nus,Austin,20\neu,\"Berlin, DE\",10\n"
+        );
+
+        let (body, _) = table().render(DownloadFormat::Ndjson, 0, Some(1));
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"zone\":\"ap\",\"city\":\"Tokyo\",\"code\":null}\n"
+        );
+    }
+
+    #[test]
+    fn test_resumed_download_is_identical() {
+        let table = table();
+        for format in [DownloadFormat::Csv, DownloadFormat::Ndjson] {
+            let (full, _) = table.render(format, 0, None);
+            let mut resumed = Vec::new();
+            let mut from_row = Some(0);
+            while let Some(row) = from_row {
+                let (chunk, next_row) = table.render(format, row, Some(2));
+                resumed.extend(chunk);
+                from_row = next_row;
+            }
+            assert_eq!(resumed, full);
+        }
+    }
+
+    #[test]
+    fn test_version_follows_content() {
+        assert_eq!(table().version(), table().version());
+        let other = TableExport::new(vec!["zone".to_string()], vec![]);
+        assert_ne!(table().version(), other.version());
+    }
+
+    #[test]
+    fn test_columns() {
+        let schema = ["_timestamp", "city", "code", "extra", "zone"].map(String::from);
+        let stored = vec!["zone".to_string(), "gone".to_string(), "city".to_string()];
+        assert_eq!(
+            table_columns(stored, &schema),
+            vec!["zone", "city", "code", "extra"]
+        );
+
+        let stored = vec!["zone".to_string(), "city".to_string()];
+        let columns = ["code".to_string(), "zone".to_string()];
+        assert_eq!(
+            merge_columns(stored.clone(), &columns, true),
+            vec!["zone", "city", "code"]
+        );
+        assert_eq!(merge_columns(stored, &columns, false), vec!["code", "zone"]);
+    }
+}
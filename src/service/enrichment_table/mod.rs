@@ -53,12 +53,16 @@ use crate::{
     },
 };
 
+pub mod download;
 pub mod geoip;
 
+/// Saves the records of an enrichment table. `columns` is the header of the
+/// uploaded file, kept to download the table with the same column order.
 pub async fn save_enrichment_data(
     org_id: &str,
     table_name: &str,
     payload: Vec<json::Map<String, json::Value>>,
+    columns: &[String],
     append_data: bool,
 ) -> Result<HttpResponse, Error> {
     let start = std::time::Instant::now();
@@ -219,6 +223,18 @@ pub async fn save_enrichment_data(
             }
         };
 
+    if !columns.is_empty() {
+        let stored = if append_data {
+            enrichment_table::get_columns(org_id, stream_name).await
+        } else {
+            vec![]
+        };
+        let columns = download::merge_columns(stored, columns, append_data);
+        if let Err(e) = enrichment_table::set_columns(org_id, stream_name, &columns).await {
+            log::error!("Error saving enrichment table {org_id}/{stream_name} columns: {e}");
+        }
+    }
+
     // notify update
     if stream_schema.has_fields {
         if let Err(e) = super::db::enrichment_table::notify_update(org_id, stream_name).await {
@@ -294,9 +310,11 @@ async fn delete_enrichment_table(org_id: &str, stream_name: &str, stream_type: S
     log::info!("deleted enrichment table  {stream_name}");
 }
 
+/// Returns the header of the uploaded CSV files along with their records
 pub async fn extract_multipart(
     mut payload: Multipart,
-) -> Result<Vec<json::Map<String, json::Value>>, Error> {
+) -> Result<(Vec<String>, Vec<json::Map<String, json::Value>>), Error> {
+    let mut columns: Vec<String> = Vec::new();
     let mut records = Vec::new();
     while let Ok(Some(mut field)) = payload.try_next().await {
         let Some(content_disposition) = field.content_disposition() else {
@@ -323,6 +341,11 @@ pub async fn extract_multipart(
             })
             .collect::<Vec<_>>()
             .into();
+        for header in headers.iter() {
+            if !columns.iter().any(|c| c == header) {
+                columns.push(header.to_string());
+            }
+        }
 
         for result in rdr.records() {
             // The iterator yields Result<StringRecord, Error>, so we check the
@@ -341,5 +364,5 @@ pub async fn extract_multipart(
         }
     }
 
-    Ok(records)
+    Ok((columns, records))
 }
@@ -13,12 +13,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::future::Future;
+
 use config::{
     ider,
     meta::{
         alerts::alert::ListAlertsParams,
         dashboards::ListDashboardsParams,
-        folder::{Folder, FolderType, DEFAULT_FOLDER},
+        folder::{Folder, FolderContents, FolderType, DEFAULT_FOLDER},
     },
 };
 use infra::{
@@ -26,6 +28,10 @@ use infra::{
     table,
 };
 
+use super::{
+    db,
+    trash::{self, TrashError},
+};
 use crate::common::{
     meta::authz::Authz,
     utils::auth::{remove_ownership, set_ownership},
@@ -52,18 +58,45 @@ pub enum FolderError {
     #[error("Can't update default folder")]
     UpdateDefaultFolder,
 
-    /// An error that occurs when trying to delete a folder that contains dashboards.
-    #[error("Folder contains dashboards. Please move/delete dashboards from folder.")]
-    DeleteWithDashboards,
+    /// An error that occurs when trying to delete a folder that still contains
+    /// dashboards, alerts, or reports without forcing the deletion.
+    #[error("Folder is not empty. Please move/delete its contents or delete it with force.")]
+    NotEmpty(FolderContents),
+
+    /// An error that occurs when force deleting a folder that contains objects
+    /// the user is not permitted to delete.
+    #[error("Not permitted to delete some of the folder contents")]
+    ContentsForbidden(FolderContents),
+
+    /// An error that occurs when dashboards or alerts are added to a folder
+    /// while it is force deleted, after its contents were checked.
+    #[error("Folder contents changed while deleting it")]
+    ContentsChanged(FolderContents),
+
+    /// An error that occurs when the folder was deleted but some of the
+    /// reports of its dashboards or the triggers of its alerts could not be
+    /// cleaned up.
+    #[error("Failed to clean up some of the deleted folder contents")]
+    ContentsCleanupFailed(FolderContents),
+
+    /// An error that occurs when trying to force delete the special "default"
+    /// folder, which is where the contents of deleted folders are trashed.
+    #[error("Can't force delete default folder")]
+    ForceDeleteDefaultFolder,
 
-    /// An error that occurs when trying to delete a folder that contains alerts.
-    #[error("Folder contains alerts. Please move/delete alerts from folder.")]
-    DeleteWithAlerts,
+    /// An error that occurs while looking up the reports of a folder.
+    #[error("ReportError# {0}")]
+    ReportError(anyhow::Error),
 
     /// An error that occurs when trying to delete a folder that cannot be found.
     #[error("Folder not found")]
     NotFound,
 
+    /// An error that occurs while moving the trashed items of a folder that is
+    /// being deleted.
+    #[error("TrashError# {0}")]
+    TrashError(#[from] TrashError),
+
     /// An error occured trying to get the list of permitted folders in
     /// enterprise mode because no user_id was provided.
     #[error("user_id required to get permitted folders in enterprise mode")]
@@ -170,23 +203,29 @@ pub async fn list_folders(
 ) -> Result<Vec<Folder>, FolderError> {
     let permitted_folders = permitted_folders(org_id, user_id).await?;
     let folders = table::folders::list_folders(org_id, folder_type).await?;
-    let filtered = match permitted_folders {
+    let filtered = folders
+        .into_iter()
+        .filter(|folder| {
+            is_folder_permitted(permitted_folders.as_deref(), org_id, &folder.folder_id)
+        })
+        .collect::<Vec<_>>();
+    Ok(filtered)
+}
+
+/// Returns whether the folder is one of the `permitted_folders` returned by
+/// [permitted_folders], `None` permits every folder.
+pub(crate) fn is_folder_permitted(
+    permitted_folders: Option<&[String]>,
+    org_id: &str,
+    folder_id: &str,
+) -> bool {
+    match permitted_folders {
         Some(permitted_folders) => {
-            if permitted_folders.contains(&format!("{}:_all_{}", "dfolder", org_id)) {
-                folders
-            } else {
-                folders
-                    .into_iter()
-                    .filter(|folder_loc| {
-                        permitted_folders
-                            .contains(&format!("{}:{}", "dfolder", folder_loc.folder_id))
-                    })
-                    .collect::<Vec<_>>()
-            }
+            permitted_folders.contains(&format!("{}:_all_{}", "dfolder", org_id))
+                || permitted_folders.contains(&format!("{}:{}", "dfolder", folder_id))
         }
-        None => folders,
-    };
-    Ok(filtered)
+        None => true,
+    }
 }
 
 #[tracing::instrument()]
@@ -211,35 +250,86 @@ pub async fn get_folder_by_name(
         .ok_or(FolderError::NotFound)
 }
 
+/// Lists the dashboards or alerts in the folder, along with the reports that
+/// include one of the folder's dashboards.
 #[tracing::instrument()]
-pub async fn delete_folder(
+pub async fn get_folder_contents(
     org_id: &str,
     folder_id: &str,
     folder_type: FolderType,
-) -> Result<(), FolderError> {
+) -> Result<FolderContents, FolderError> {
+    if !table::folders::exists(org_id, folder_id, folder_type).await? {
+        return Err(FolderError::NotFound);
+    }
+
+    let mut contents = FolderContents::default();
     match folder_type {
         FolderType::Dashboards => {
             let params = ListDashboardsParams::new(org_id).with_folder_id(folder_id);
-            let dashboards = table::dashboards::list(params).await?;
-            if !dashboards.is_empty() {
-                return Err(FolderError::DeleteWithDashboards);
+            contents.dashboards = table::dashboards::list(params)
+                .await?
+                .into_iter()
+                .filter_map(|(_, d)| d.dashboard_id().map(str::to_owned))
+                .collect();
+            if !contents.dashboards.is_empty() {
+                contents.reports = db::dashboards::reports::list(org_id)
+                    .await
+                    .map_err(FolderError::ReportError)?
+                    .into_iter()
+                    .filter(|r| r.dashboards.iter().any(|d| d.folder == folder_id))
+                    .map(|r| r.name)
+                    .collect();
             }
         }
         FolderType::Alerts => {
             let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
             let params = ListAlertsParams::new(org_id).in_folder(folder_id);
-            let alerts = table::alerts::list(client, params).await?;
-            if !alerts.is_empty() {
-                return Err(FolderError::DeleteWithAlerts);
-            }
+            contents.alerts = table::alerts::list(client, params)
+                .await?
+                .into_iter()
+                .filter_map(|(_, a)| a.id.map(|id| id.to_string()))
+                .collect();
         }
     };
+    Ok(contents)
+}
 
-    if !table::folders::exists(org_id, folder_id, folder_type).await? {
-        return Err(FolderError::NotFound);
+/// Deletes the folder and returns the contents that were removed with it.
+///
+/// A folder that is not empty is only deleted when `force` is set, in which
+/// case its dashboards or alerts are moved to the trash and the reports that
+/// use its dashboards are deleted. The user must be permitted to delete every
+/// one of those objects.
+#[tracing::instrument()]
+pub async fn delete_folder(
+    org_id: &str,
+    folder_id: &str,
+    folder_type: FolderType,
+    force: bool,
+    user_id: &str,
+) -> Result<FolderContents, FolderError> {
+    let contents = get_folder_contents(org_id, folder_id, folder_type).await?;
+    let mut failed = FolderContents::default();
+    if contents.is_empty() {
+        trash::release_folder(org_id, folder_id, folder_type).await?;
+        table::folders::delete(org_id, folder_id, folder_type).await?;
+    } else if !force {
+        return Err(FolderError::NotEmpty(contents));
+    } else {
+        if folder_id == DEFAULT_FOLDER {
+            return Err(FolderError::ForceDeleteDefaultFolder);
+        }
+        let denied = denied_contents(&contents, |object_type, object_id| {
+            is_delete_permitted(org_id, user_id, object_type, object_id)
+        })
+        .await;
+        if !denied.is_empty() {
+            return Err(FolderError::ContentsForbidden(denied));
+        }
+        failed =
+            delete_folder_with_contents(org_id, folder_id, folder_type, &contents, user_id).await?;
     }
 
-    table::folders::delete(org_id, folder_id, folder_type).await?;
     remove_ownership(org_id, "folders", Authz::new(folder_id)).await;
 
     #[cfg(feature = "enterprise")]
@@ -255,11 +345,145 @@ pub async fn delete_folder(
         .await;
     }
 
-    Ok(())
+    if !failed.is_empty() {
+        return Err(FolderError::ContentsCleanupFailed(failed));
+    }
+    Ok(contents)
+}
+
+/// Trashes the checked `contents` of the folder and deletes the folder in one
+/// transaction, then deletes the reports of the folder and the triggers of its
+/// alerts. Returns the reports and alerts that could not be cleaned up.
+async fn delete_folder_with_contents(
+    org_id: &str,
+    folder_id: &str,
+    folder_type: FolderType,
+    contents: &FolderContents,
+    user_id: &str,
+) -> Result<FolderContents, FolderError> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    let (expected_ids, alerts) = match folder_type {
+        FolderType::Dashboards => (&contents.dashboards, vec![]),
+        FolderType::Alerts => {
+            let params = ListAlertsParams::new(org_id).in_folder(folder_id);
+            let alerts = table::alerts::list(client, params)
+                .await?
+                .into_iter()
+                .filter_map(|(_, alert)| {
+                    let alert_id = alert.id?;
+                    contents
+                        .alerts
+                        .contains(&alert_id.to_string())
+                        .then_some((alert_id, alert))
+                })
+                .collect::<Vec<_>>();
+            (&contents.alerts, alerts)
+        }
+    };
+
+    // Makes sure the default folder exists to receive the trashed contents.
+    trash::release_folder(org_id, folder_id, folder_type).await?;
+    let deleted_at = chrono::Utc::now().timestamp_micros();
+    let deleted = table::folders::delete_with_contents(
+        org_id,
+        folder_id,
+        folder_type,
+        DEFAULT_FOLDER,
+        user_id,
+        deleted_at,
+        expected_ids,
+    )
+    .await?;
+    if !deleted {
+        let contents = get_folder_contents(org_id, folder_id, folder_type).await?;
+        return Err(FolderError::ContentsChanged(contents));
+    }
+
+    let mut failed = FolderContents::default();
+    for (alert_id, alert) in alerts {
+        if let Err(e) = db::alerts::alert::on_trashed(org_id, alert_id, &alert).await {
+            log::error!("Failed to clean up trashed alert {alert_id}: {e}");
+            failed.alerts.push(alert_id.to_string());
+        }
+    }
+
+    // The trash is local to each cluster, other clusters delete the
+    // dashboards right away and get them back through a put if restored here.
+    #[cfg(feature = "enterprise")]
+    if o2_enterprise::enterprise::common::infra::config::get_config()
+        .super_cluster
+        .enabled
+    {
+        for dashboard_id in &contents.dashboards {
+            let _ = o2_enterprise::enterprise::super_cluster::queue::dashboards_delete(
+                org_id,
+                folder_id,
+                dashboard_id,
+            )
+            .await;
+        }
+    }
+
+    for name in &contents.reports {
+        if let Err((_, e)) = super::dashboards::reports::delete(org_id, name).await {
+            log::error!("Failed to delete report {name} of deleted folder {folder_id}: {e}");
+            failed.reports.push(name.clone());
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Returns the contents that `is_permitted` does not allow to be deleted.
+async fn denied_contents<F, Fut>(contents: &FolderContents, is_permitted: F) -> FolderContents
+where
+    F: Fn(&'static str, String) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut denied = FolderContents::default();
+    for (object_type, ids, denied_ids) in [
+        ("dashboards", &contents.dashboards, &mut denied.dashboards),
+        ("alerts", &contents.alerts, &mut denied.alerts),
+        ("reports", &contents.reports, &mut denied.reports),
+    ] {
+        for id in ids {
+            if !is_permitted(object_type, id.clone()).await {
+                denied_ids.push(id.clone());
+            }
+        }
+    }
+    denied
 }
 
 #[cfg(not(feature = "enterprise"))]
-async fn permitted_folders(
+async fn is_delete_permitted(
+    _org_id: &str,
+    _user_id: &str,
+    _object_type: &str,
+    _object_id: String,
+) -> bool {
+    true
+}
+
+#[cfg(feature = "enterprise")]
+async fn is_delete_permitted(
+    org_id: &str,
+    user_id: &str,
+    object_type: &str,
+    object_id: String,
+) -> bool {
+    crate::common::utils::auth::check_permissions(
+        Some(object_id),
+        org_id,
+        user_id,
+        object_type,
+        "DELETE",
+    )
+    .await
+}
+
+#[cfg(not(feature = "enterprise"))]
+pub(crate) async fn permitted_folders(
     _org_id: &str,
     _user_id: Option<&str>,
 ) -> Result<Option<Vec<String>>, FolderError> {
@@ -267,7 +491,7 @@ async fn permitted_folders(
 }
 
 #[cfg(feature = "enterprise")]
-async fn permitted_folders(
+pub(crate) async fn permitted_folders(
     org_id: &str,
     user_id: Option<&str>,
 ) -> Result<Option<Vec<String>>, FolderError> {
@@ -281,3 +505,55 @@ async fn permitted_folders(
     .map_err(|err| FolderError::PermittedFoldersValidator(err.to_string()))?;
     Ok(stream_list)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed_contents() -> FolderContents {
+        FolderContents {
+            dashboards: vec!["dash1".to_string(), "dash2".to_string()],
+            alerts: vec!["alert1".to_string(), "alert2".to_string()],
+            reports: vec!["report1".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_is_folder_permitted() {
+        assert!(is_folder_permitted(None, "org", "f1"));
+
+        let permitted = vec!["dfolder:f1".to_string()];
+        assert!(is_folder_permitted(Some(&permitted), "org", "f1"));
+        assert!(!is_folder_permitted(Some(&permitted), "org", "f2"));
+        assert!(!is_folder_permitted(Some(&[]), "org", "f1"));
+
+        let permitted = vec!["dfolder:_all_org".to_string()];
+        assert!(is_folder_permitted(Some(&permitted), "org", "f2"));
+        assert!(!is_folder_permitted(Some(&permitted), "other", "f2"));
+    }
+
+    #[tokio::test]
+    async fn test_denied_contents_all_permitted() {
+        let contents = mixed_contents();
+        assert!(!contents.is_empty());
+
+        let denied = denied_contents(&contents, |_, _| async { true }).await;
+        assert!(denied.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_denied_contents_one_alert_denied() {
+        let contents = mixed_contents();
+        let denied = denied_contents(&contents, |object_type, object_id| async move {
+            !(object_type == "alerts" && object_id == "alert2")
+        })
+        .await;
+        assert_eq!(
+            denied,
+            FolderContents {
+                alerts: vec!["alert2".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -52,7 +52,7 @@ use crate::{
         meta::{ingestion::IngestionRequest, stream::SchemaRecords},
         utils::functions::get_vrl_compiler_config,
     },
-    service::{alerts::alert::AlertExt, db, logs::bulk::TRANSFORM_FAILED},
+    service::{alerts::alert::send_notification_unless_silenced, db, logs::bulk::TRANSFORM_FAILED},
 };
 
 pub mod grpc;
@@ -243,7 +243,7 @@ pub async fn evaluate_trigger(triggers: TriggerAlertData) {
             evaluation_took_in_secs: None,
             source_node: Some(LOCAL_NODE.name.clone()),
         };
-        match alert.send_notification(val, now, None, now).await {
+        match send_notification_unless_silenced(alert, val, now, None, now).await {
             Err(e) => {
                 log::error!("Failed to send notification: {}", e);
                 trigger_data_stream.status = TriggerDataStatus::Failed;
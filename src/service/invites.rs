@@ -0,0 +1,413 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use std::str::FromStr;
+
+use actix_web::{http::StatusCode, HttpResponse};
+use config::{get_config, utils::rand::generate_random_string, SMTP_CLIENT};
+use infra::table::org_invites::{self, OrgInvite};
+use lettre::{AsyncTransport, Message};
+use rand::{rngs::OsRng, RngCore};
+
+use crate::{
+    common::{
+        meta::{
+            http::HttpResponse as MetaHttpResponse,
+            invite::{
+                AcceptInviteRequest, AcceptInviteResponse, CreateInviteRequest,
+                CreateInviteResponse, InviteResponse,
+            },
+            user::{UserRequest, UserRole},
+        },
+        utils::auth::get_role,
+    },
+    service::{db, users},
+};
+
+/// Bytes of randomness in an invite token
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InviteError {
+    #[error("Invalid email")]
+    InvalidEmail,
+    #[error("Users can't be invited with the role {0}")]
+    RoleNotAllowed(UserRole),
+    #[error("Not Allowed")]
+    NotAllowed,
+    #[error("Invite not found")]
+    NotFound,
+    #[error("Invite was already accepted")]
+    AlreadyAccepted,
+    #[error("Invite was revoked")]
+    Revoked,
+    #[error("Invite expired")]
+    Expired,
+    #[error("A password is required to accept the invite")]
+    PasswordRequired,
+    #[error(transparent)]
+    Db(#[from] infra::errors::Error),
+    #[error("Failed to provision the user: {0}")]
+    Provision(#[from] anyhow::Error),
+}
+
+impl From<InviteError> for HttpResponse {
+    fn from(value: InviteError) -> Self {
+        match value {
+            InviteError::InvalidEmail
+            | InviteError::RoleNotAllowed(_)
+            | InviteError::PasswordRequired => MetaHttpResponse::bad_request(value),
+            InviteError::NotAllowed => MetaHttpResponse::forbidden(value),
+            InviteError::NotFound => MetaHttpResponse::not_found(value),
+            InviteError::AlreadyAccepted => MetaHttpResponse::conflict(value),
+            InviteError::Revoked | InviteError::Expired => HttpResponse::Gone().json(
+                MetaHttpResponse::error(StatusCode::GONE.into(), value.to_string()),
+            ),
+            InviteError::Db(_) | InviteError::Provision(_) => {
+                MetaHttpResponse::internal_error(value)
+            }
+        }
+    }
+}
+
+/// A random, url safe invite token
+fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Only the hash of a token is stored, so a leaked table can't be used to
+/// accept invites
+fn hash_token(token: &str) -> String {
+    sha256::digest(token)
+}
+
+/// The role the invitee gets in the org
+fn invite_role(role: UserRole) -> Result<UserRole, InviteError> {
+    if role.eq(&UserRole::Root) || role.eq(&UserRole::ServiceAccount) {
+        return Err(InviteError::RoleNotAllowed(role));
+    }
+    Ok(get_role(role))
+}
+
+/// Whether the invite can still be accepted at `now`
+fn check_invite(invite: &OrgInvite, now: i64) -> Result<(), InviteError> {
+    if invite.revoked_at.is_some() {
+        Err(InviteError::Revoked)
+    } else if invite.accepted_at.is_some() {
+        Err(InviteError::AlreadyAccepted)
+    } else if invite.expires_at <= now {
+        Err(InviteError::Expired)
+    } else {
+        Ok(())
+    }
+}
+
+fn sso_enabled() -> bool {
+    #[cfg(feature = "enterprise")]
+    return o2_dex::config::get_config().dex_enabled;
+    #[cfg(not(feature = "enterprise"))]
+    return false;
+}
+
+fn invite_link(token: &str) -> String {
+    let cfg = get_config();
+    format!(
+        "{}{}/web/invites/accept?token={token}",
+        cfg.common.web_url, cfg.common.base_uri
+    )
+}
+
+pub async fn create_invite(
+    org_id: &str,
+    req: CreateInviteRequest,
+    initiator_id: &str,
+) -> Result<CreateInviteResponse, InviteError> {
+    let email = req.email.trim().to_lowercase();
+    if !users::is_valid_email(&email) {
+        return Err(InviteError::InvalidEmail);
+    }
+    let role = invite_role(req.role)?;
+    if !users::can_manage_users(org_id, initiator_id).await {
+        return Err(InviteError::NotAllowed);
+    }
+
+    let token = generate_token();
+    let now = chrono::Utc::now().timestamp_micros();
+    let expires_at = now + get_config().auth.invite_expiry_hours * 3600 * 1_000_000;
+    let id = org_invites::add(
+        org_id,
+        &email,
+        &role.to_string(),
+        &hash_token(&token),
+        initiator_id,
+        now,
+        expires_at,
+    )
+    .await?;
+
+    let email_sent = if req.send_email {
+        match send_invite_email(org_id, &email, &token).await {
+            Ok(()) => true,
+            Err(e) => {
+                log::error!("[INVITES] Failed to email the invite {id} to {email}: {e}");
+                false
+            }
+        }
+    } else {
+        false
+    };
+
+    Ok(CreateInviteResponse {
+        id,
+        token,
+        expires_at,
+        email_sent,
+    })
+}
+
+async fn send_invite_email(org_id: &str, email: &str, token: &str) -> Result<(), anyhow::Error> {
+    let cfg = get_config();
+    if !cfg.smtp.smtp_enabled {
+        return Err(anyhow::anyhow!("SMTP configuration not enabled"));
+    }
+
+    let mut message = Message::builder()
+        .from(cfg.smtp.smtp_from_email.parse()?)
+        .to(email.parse()?)
+        .subject(format!("You are invited to join {org_id} on OpenObserve"));
+    if !cfg.smtp.smtp_reply_to.is_empty() {
+        message = message.reply_to(cfg.smtp.smtp_reply_to.parse()?);
+    }
+    let message = message.body(format!(
+        "You are invited to join the organization {org_id}.\n\nAccept the invite: {}\n\nThe link expires in {} hours.",
+        invite_link(token),
+        cfg.auth.invite_expiry_hours
+    ))?;
+
+    match SMTP_CLIENT.as_ref().unwrap().send(message).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow::anyhow!("Error sending email: {e}")),
+    }
+}
+
+pub async fn list_invites(
+    org_id: &str,
+    initiator_id: &str,
+) -> Result<Vec<InviteResponse>, InviteError> {
+    if !users::can_manage_users(org_id, initiator_id).await {
+        return Err(InviteError::NotAllowed);
+    }
+    let now = chrono::Utc::now().timestamp_micros();
+    let invites = org_invites::list_pending(org_id, now).await?;
+    Ok(invites
+        .into_iter()
+        .map(|invite| InviteResponse {
+            role: UserRole::from_str(&invite.role).unwrap_or_default(),
+            id: invite.id,
+            email: invite.email,
+            created_by: invite.created_by,
+            created_at: invite.created_at,
+            expires_at: invite.expires_at,
+        })
+        .collect())
+}
+
+pub async fn revoke_invite(
+    org_id: &str,
+    invite_id: &str,
+    initiator_id: &str,
+) -> Result<(), InviteError> {
+    if !users::can_manage_users(org_id, initiator_id).await {
+        return Err(InviteError::NotAllowed);
+    }
+    let now = chrono::Utc::now().timestamp_micros();
+    if org_invites::revoke(org_id, invite_id, now).await? {
+        Ok(())
+    } else {
+        Err(InviteError::NotFound)
+    }
+}
+
+/// Accepts an invite, creating the user if needed and adding them to the org
+/// with the invited role. An invite can only be claimed once, a concurrent or
+/// repeated acceptance of the same token is rejected without touching the user.
+pub async fn accept_invite(req: AcceptInviteRequest) -> Result<AcceptInviteResponse, InviteError> {
+    let token_hash = hash_token(req.token.trim());
+    let Some(invite) = org_invites::get_by_token_hash(&token_hash).await? else {
+        return Err(InviteError::NotFound);
+    };
+    let now = chrono::Utc::now().timestamp_micros();
+    check_invite(&invite, now)?;
+
+    let existing_user = db::user::get_db_user(&invite.email).await.ok();
+    let password = req.password.filter(|p| !p.is_empty());
+    if existing_user.is_none() && password.is_none() && !sso_enabled() {
+        return Err(InviteError::PasswordRequired);
+    }
+
+    if !org_invites::claim(&invite.id, now).await? {
+        // somebody else got there first, tell why the invite is gone
+        return match org_invites::get_by_token_hash(&token_hash).await? {
+            Some(invite) => check_invite(&invite, now).and(Err(InviteError::AlreadyAccepted)),
+            None => Err(InviteError::NotFound),
+        };
+    }
+
+    let role = UserRole::from_str(&invite.role).unwrap_or_default();
+    let ret = match existing_user {
+        Some(mut db_user) => {
+            users::set_user_org_role(&mut db_user, &invite.org, role.clone()).await
+        }
+        None => {
+            let is_external = password.is_none();
+            let usr_req = UserRequest {
+                email: invite.email.clone(),
+                first_name: req.first_name,
+                last_name: req.last_name,
+                // SSO users never log in with a password
+                password: password.unwrap_or_else(|| generate_random_string(32)),
+                role: role.clone(),
+                is_external,
+            };
+            users::create_user(&invite.org, &usr_req).await
+        }
+    };
+    if let Err(e) = ret {
+        // let the invitee try again
+        if let Err(e) = org_invites::release(&invite.id).await {
+            log::error!("[INVITES] Failed to release the invite {}: {e}", invite.id);
+        }
+        return Err(e.into());
+    }
+
+    log::info!(
+        "[INVITES] {} accepted the invite {} to {} as {}",
+        invite.email,
+        invite.id,
+        invite.org,
+        role
+    );
+    Ok(AcceptInviteResponse {
+        org: invite.org,
+        email: invite.email,
+        role,
+    })
+}
+
+/// Deletes the invites that expired, accepted and revoked ones included
+pub async fn purge_expired() -> Result<u64, InviteError> {
+    let now = chrono::Utc::now().timestamp_micros();
+    Ok(org_invites::purge_expired(now).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::meta::user::DBUser;
+
+    const NOW: i64 = 1_700_000_000_000_000;
+
+    fn pending_invite() -> OrgInvite {
+        OrgInvite {
+            id: "2bxUIKQsfIfMzbmvmMdmjAyvHhP".to_string(),
+            org: "default".to_string(),
+            email: "invitee@example.com".to_string(),
+            role: UserRole::Admin.to_string(),
+            created_by: "root@example.com".to_string(),
+            created_at: NOW,
+            expires_at: NOW + 3600 * 1_000_000,
+            accepted_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_token_is_random_and_stored_hashed() {
+        let token = generate_token();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_ne!(token, generate_token());
+
+        let hash = hash_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, token);
+        assert_eq!(hash, hash_token(&token));
+        assert_ne!(hash, hash_token(&generate_token()));
+    }
+
+    #[test]
+    fn test_invite_lifecycle() {
+        // created
+        let mut invite = pending_invite();
+        assert!(check_invite(&invite, NOW).is_ok());
+
+        // accepted, the token can't be used again
+        invite.accepted_at = Some(NOW + 1);
+        assert!(matches!(
+            check_invite(&invite, NOW + 2),
+            Err(InviteError::AlreadyAccepted)
+        ));
+
+        // expired
+        let invite_expired = pending_invite();
+        assert!(matches!(
+            check_invite(&invite_expired, invite_expired.expires_at),
+            Err(InviteError::Expired)
+        ));
+
+        // revoked, even before it expires
+        let mut invite_revoked = pending_invite();
+        invite_revoked.revoked_at = Some(NOW + 1);
+        assert!(matches!(
+            check_invite(&invite_revoked, NOW + 2),
+            Err(InviteError::Revoked)
+        ));
+    }
+
+    #[test]
+    fn test_invite_role_assignment() {
+        assert!(matches!(
+            invite_role(UserRole::Root),
+            Err(InviteError::RoleNotAllowed(UserRole::Root))
+        ));
+        assert!(matches!(
+            invite_role(UserRole::ServiceAccount),
+            Err(InviteError::RoleNotAllowed(_))
+        ));
+        let role = invite_role(UserRole::Admin).unwrap();
+        assert_eq!(role, UserRole::Admin);
+
+        let mut db_user = DBUser {
+            email: "invitee@example.com".to_string(),
+            first_name: String::new(),
+            last_name: String::new(),
+            password: String::new(),
+            salt: String::new(),
+            organizations: vec![],
+            is_external: false,
+            password_ext: None,
+        };
+        users::assign_org_role(&mut db_user, "default", UserRole::Member);
+        users::assign_org_role(&mut db_user, "other", UserRole::Member);
+        // accepting an invite to an org the user is part of replaces the role
+        users::assign_org_role(&mut db_user, "default", role);
+        assert_eq!(db_user.organizations.len(), 2);
+        let user = db_user.get_user("default".to_string()).unwrap();
+        assert_eq!(user.role, UserRole::Admin);
+        assert_eq!(user.token.len(), 16);
+        let user = db_user.get_user("other".to_string()).unwrap();
+        assert_eq!(user.role, UserRole::Member);
+    }
+}
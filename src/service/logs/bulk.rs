@@ -47,12 +47,14 @@ pub const TRANSFORM_FAILED: &str = "document_failed_transform";
 pub const TS_PARSE_FAILED: &str = "timestamp_parsing_failed";
 pub const SCHEMA_CONFORMANCE_FAILED: &str = "schema_conformance_failed";
 pub const PIPELINE_EXEC_FAILED: &str = "pipeline_execution_failed";
+pub const STREAM_BLOCKED: &str = "stream_blocked";
 
 pub async fn ingest(
     thread_id: usize,
     org_id: &str,
     body: web::Bytes,
     user_email: &str,
+    positional: bool,
 ) -> Result<BulkResponse, anyhow::Error> {
     let start = std::time::Instant::now();
     let started_at = Utc::now().timestamp_micros();
@@ -61,11 +63,7 @@ pub async fn ingest(
     check_ingestion_allowed(org_id, None)?;
 
     // let mut errors = false;
-    let mut bulk_res = BulkResponse {
-        took: 0,
-        errors: false,
-        items: vec![],
-    };
+    let mut bulk_res = BulkResponse::default();
 
     let cfg = get_config();
     let min_ts = (Utc::now() - Duration::try_hours(cfg.limit.ingest_allowed_upto).unwrap())
@@ -75,17 +73,22 @@ pub async fn ingest(
     let mut action = String::from("");
     let mut stream_name = String::from("");
     let mut doc_id = None;
+    // index of the current action in the payload, tracked in positional mode
+    let mut position = None;
+    let mut action_count = 0;
 
     let mut blocked_stream_warnings: HashMap<String, bool> = HashMap::new();
 
     let mut stream_executable_pipelines: HashMap<String, Option<ExecutablePipeline>> =
         HashMap::new();
     let mut stream_pipeline_inputs: HashMap<String, ExecutablePipelineBulkInputs> = HashMap::new();
+    let mut stream_pipeline_positions: HashMap<String, Vec<usize>> = HashMap::new();
 
     let mut user_defined_schema_map: HashMap<String, HashSet<String>> = HashMap::new();
     let mut streams_need_original_set: HashSet<String> = HashSet::new();
 
     let mut json_data_by_stream = HashMap::new();
+    let mut record_positions: HashMap<String, Vec<usize>> = HashMap::new();
     let mut next_line_is_data = false;
     let reader = BufReader::new(body.as_ref());
     for line in reader.lines() {
@@ -103,6 +106,8 @@ pub async fn ingest(
                 continue; // skip
             }
             (action, stream_name, doc_id) = ret.unwrap();
+            position = positional.then_some(action_count);
+            action_count += 1;
 
             if stream_name.is_empty() || stream_name == "_" || stream_name == "/" {
                 let err_msg = format!("Invalid stream name: {}", line);
@@ -114,17 +119,16 @@ pub async fn ingest(
                     err_msg,
                     "0".to_string(),
                 );
-                let mut item = HashMap::new();
-                item.insert(
-                    action.clone(),
-                    BulkResponseItem::new_failed(
-                        stream_name.clone(),
-                        doc_id.clone().unwrap_or_default(),
-                        err,
-                        Some(value),
-                        stream_name.clone(),
-                    ),
+                let mut res_item = BulkResponseItem::new_failed(
+                    stream_name.clone(),
+                    doc_id.clone().unwrap_or_default(),
+                    err,
+                    Some(value),
+                    stream_name.clone(),
                 );
+                res_item.position = position;
+                let mut item = HashMap::new();
+                item.insert(action.clone(), res_item);
                 bulk_res.items.push(item);
                 continue; // skip
             }
@@ -141,6 +145,19 @@ pub async fn ingest(
                     log::warn!("stream [{stream_name}] is blocked from ingestion");
                     true
                 });
+                if position.is_some() {
+                    bulk_res.errors = true;
+                    add_record_status(
+                        stream_name.clone(),
+                        &doc_id,
+                        action.clone(),
+                        None,
+                        &mut bulk_res,
+                        Some(STREAM_BLOCKED.to_string()),
+                        Some(format!("stream [{stream_name}] is blocked from ingestion")),
+                        position,
+                    );
+                }
                 continue; // skip
             }
 
@@ -224,6 +241,7 @@ pub async fn ingest(
                         &mut bulk_res,
                         Some(TS_PARSE_FAILED.to_string()),
                         Some(TS_PARSE_FAILED.to_string()),
+                        position,
                     );
                     continue;
                 };
@@ -249,6 +267,7 @@ pub async fn ingest(
                                 &mut bulk_res,
                                 Some(TS_PARSE_FAILED.to_string()),
                                 Some(TS_PARSE_FAILED.to_string()),
+                                position,
                             );
                             continue;
                         }
@@ -265,6 +284,12 @@ pub async fn ingest(
                     .entry(stream_name.clone())
                     .or_default();
                 inputs.add_input(value, doc_id.to_owned(), original_data);
+                if let Some(position) = position {
+                    stream_pipeline_positions
+                        .entry(stream_name.clone())
+                        .or_default()
+                        .push(position);
+                }
             } else {
                 // JSON Flattening
                 value = flatten::flatten_with_level(value, cfg.limit.ingest_flatten_level)?;
@@ -324,6 +349,7 @@ pub async fn ingest(
                                 &mut bulk_res,
                                 Some(TS_PARSE_FAILED.to_string()),
                                 Some(TS_PARSE_FAILED.to_string()),
+                                position,
                             );
                             continue;
                         }
@@ -352,6 +378,7 @@ pub async fn ingest(
                         &mut bulk_res,
                         Some(TS_PARSE_FAILED.to_string()),
                         failure_reason,
+                        position,
                     );
                     continue;
                 }
@@ -365,6 +392,12 @@ pub async fn ingest(
                     .or_insert((Vec::new(), None));
                 ts_data.push((timestamp, local_val));
                 *fn_num = Some(0); // no pl -> no func
+                if let Some(position) = position {
+                    record_positions
+                        .entry(stream_name.clone())
+                        .or_default()
+                        .push(position);
+                }
             }
         }
     }
@@ -380,6 +413,9 @@ pub async fn ingest(
                 continue;
             };
             let (records, doc_ids, originals) = pipeline_inputs.into_parts();
+            let input_positions = stream_pipeline_positions
+                .remove(&stream_name)
+                .unwrap_or_default();
             match exec_pl.process_batch(org_id, records).await {
                 Err(e) => {
                     log::error!(
@@ -397,15 +433,24 @@ pub async fn ingest(
                             TRANSFORM_FAILED,
                         ])
                         .inc();
-                    add_record_status(
-                        stream_name.clone(),
-                        &None,
-                        action.clone(),
-                        None,
-                        &mut bulk_res,
-                        Some(PIPELINE_EXEC_FAILED.to_string()),
-                        Some(PIPELINE_EXEC_FAILED.to_string()),
-                    );
+                    // in positional mode every buffered record gets its own failed item
+                    let failed_positions = if input_positions.is_empty() {
+                        vec![None]
+                    } else {
+                        input_positions.iter().copied().map(Some).collect()
+                    };
+                    for position in failed_positions {
+                        add_record_status(
+                            stream_name.clone(),
+                            &None,
+                            action.clone(),
+                            None,
+                            &mut bulk_res,
+                            Some(PIPELINE_EXEC_FAILED.to_string()),
+                            Some(PIPELINE_EXEC_FAILED.to_string()),
+                            position,
+                        );
+                    }
                     continue;
                 }
                 Ok(pl_results) => {
@@ -416,6 +461,7 @@ pub async fn ingest(
                         }
 
                         for (idx, mut res) in stream_pl_results {
+                            let position = input_positions.get(idx).copied();
                             // get json object
                             let mut local_val = match res.take() {
                                 json::Value::Object(v) => v,
@@ -481,6 +527,7 @@ pub async fn ingest(
                                     &mut bulk_res,
                                     Some(TS_PARSE_FAILED.to_string()),
                                     Some(TS_PARSE_FAILED.to_string()),
+                                    position,
                                 );
                                 continue;
                             };
@@ -507,6 +554,7 @@ pub async fn ingest(
                                     &mut bulk_res,
                                     Some(TS_PARSE_FAILED.to_string()),
                                     failure_reason,
+                                    position,
                                 );
                                 continue;
                             }
@@ -519,7 +567,13 @@ pub async fn ingest(
                                 .entry(stream_params.stream_name.to_string())
                                 .or_insert((Vec::new(), None));
                             ts_data.push((timestamp, local_val));
-                            *fn_num = Some(function_no)
+                            *fn_num = Some(function_no);
+                            if let Some(position) = position {
+                                record_positions
+                                    .entry(stream_params.stream_name.to_string())
+                                    .or_default()
+                                    .push(position);
+                            }
                         }
                     }
                 }
@@ -529,9 +583,14 @@ pub async fn ingest(
 
     // drop memory-intensive variables
     drop(stream_pipeline_inputs);
+    drop(stream_pipeline_positions);
     drop(streams_need_original_set);
     drop(user_defined_schema_map);
 
+    if positional {
+        bulk_res.positions = Some(record_positions);
+    }
+
     let (metric_rpt_status_code, response_body) = {
         let mut status = IngestionStatus::Bulk(bulk_res);
         let write_result = super::write_logs_by_stream(
@@ -548,6 +607,9 @@ pub async fn ingest(
             unreachable!();
         };
         bulk_res.took = start.elapsed().as_millis();
+        if bulk_res.positions.take().is_some() {
+            sort_by_position(&mut bulk_res.items);
+        }
         match write_result {
             Ok(()) => ("200", bulk_res),
             Err(e) => {
@@ -582,6 +644,7 @@ pub async fn ingest(
     Ok(response_body)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_record_status(
     stream_name: String,
     doc_id: &Option<String>,
@@ -590,6 +653,7 @@ pub fn add_record_status(
     bulk_res: &mut BulkResponse,
     failure_type: Option<String>,
     failure_reason: Option<String>,
+    position: Option<usize>,
 ) {
     let mut item = HashMap::new();
     let action = if action.is_empty() {
@@ -612,42 +676,44 @@ pub fn add_record_status(
                 "0".to_owned(),
             );
 
-            item.insert(
-                action,
-                BulkResponseItem::new_failed(
-                    stream_name.clone(),
-                    doc_id,
-                    bulk_err,
-                    value,
-                    stream_name,
-                ),
+            let mut res_item = BulkResponseItem::new_failed(
+                stream_name.clone(),
+                doc_id,
+                bulk_err,
+                value,
+                stream_name,
             );
+            res_item.position = position;
+            item.insert(action, res_item);
 
             bulk_res.items.push(item);
         }
         None => {
-            item.insert(
-                action,
-                BulkResponseItem::new(stream_name.clone(), doc_id, value, stream_name),
-            );
-            if !get_config().common.bulk_api_response_errors_only {
+            let mut res_item =
+                BulkResponseItem::new(stream_name.clone(), doc_id, value, stream_name);
+            res_item.position = position;
+            item.insert(action, res_item);
+            // positional mode reports every action, regardless of the errors-only setting
+            if position.is_some() || !get_config().common.bulk_api_response_errors_only {
                 bulk_res.items.push(item);
             }
         }
     }
 }
 
+/// Restores the payload order of the items, which are collected per stream.
+/// The sort is stable, so records fanned out by a pipeline keep their order.
+fn sort_by_position(items: &mut [HashMap<String, BulkResponseItem>]) {
+    items.sort_by_key(|item| item.values().next().and_then(|i| i.position));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_add_record_status() {
-        let mut bulk_res = BulkResponse {
-            took: 0,
-            errors: false,
-            items: vec![],
-        };
+        let mut bulk_res = BulkResponse::default();
         add_record_status(
             "olympics".to_string(),
             &Some("1".to_string()),
@@ -656,7 +722,85 @@ mod tests {
             &mut bulk_res,
             None,
             None,
+            None,
         );
         assert!(bulk_res.items.len() == 1);
     }
+
+    #[test]
+    fn test_sort_by_position() {
+        let mut bulk_res = BulkResponse::default();
+        // statuses arrive grouped by stream, not in payload order
+        add_record_status(
+            "blocked".to_string(),
+            &None,
+            "index".to_string(),
+            None,
+            &mut bulk_res,
+            Some(STREAM_BLOCKED.to_string()),
+            Some("stream [blocked] is blocked from ingestion".to_string()),
+            Some(2),
+        );
+        add_record_status(
+            "olympics".to_string(),
+            &Some("1".to_string()),
+            "create".to_string(),
+            None,
+            &mut bulk_res,
+            Some(TS_PARSE_FAILED.to_string()),
+            Some(get_upto_discard_error().to_string()),
+            Some(1),
+        );
+        add_record_status(
+            "olympics".to_string(),
+            &None,
+            "".to_string(),
+            None,
+            &mut bulk_res,
+            Some(SCHEMA_CONFORMANCE_FAILED.to_string()),
+            Some("Failed to cast year to type Int64".to_string()),
+            Some(3),
+        );
+        add_record_status(
+            "olympics".to_string(),
+            &None,
+            "".to_string(),
+            None,
+            &mut bulk_res,
+            None,
+            None,
+            Some(0),
+        );
+
+        sort_by_position(&mut bulk_res.items);
+        let items = bulk_res
+            .items
+            .iter()
+            .map(|item| item.iter().next().unwrap())
+            .collect::<Vec<_>>();
+        let positions = items.iter().map(|(_, i)| i.position).collect::<Vec<_>>();
+        assert_eq!(positions, vec![Some(0), Some(1), Some(2), Some(3)]);
+        assert_eq!(items[0].1.status, 200);
+        assert!(items[0].1.error.is_none());
+        assert_eq!(items[1].0, "create");
+        let reasons = items[1..]
+            .iter()
+            .map(|(_, i)| i.error.as_ref().unwrap())
+            .map(|e| (e.err_type.as_str(), e.reason.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                (TS_PARSE_FAILED, get_upto_discard_error().to_string()),
+                (
+                    STREAM_BLOCKED,
+                    "stream [blocked] is blocked from ingestion".to_string()
+                ),
+                (
+                    SCHEMA_CONFORMANCE_FAILED,
+                    "Failed to cast year to type Int64".to_string()
+                ),
+            ]
+        );
+    }
 }
@@ -35,7 +35,6 @@ use config::{
         json::{estimate_json_bytes, get_string_value, pickup_string_value, Map, Value},
         schema_ext::SchemaExt,
     },
-    DISTINCT_FIELDS,
 };
 use infra::schema::{unwrap_partition_time_level, SchemaCache};
 
@@ -49,7 +48,10 @@ use super::{
     schema::stream_schema_exists,
 };
 use crate::{
-    common::meta::{ingestion::IngestionStatus, stream::SchemaRecords},
+    common::meta::{
+        ingestion::{IngestionStatus, RecordStatus},
+        stream::SchemaRecords,
+    },
     service::{
         alerts::alert::AlertExt, db, ingestion::get_write_partition_key, schema::check_for_schema,
         self_reporting::report_request_usage_stats,
@@ -60,6 +62,7 @@ pub mod bulk;
 pub mod ingest;
 pub mod otlp_grpc;
 pub mod otlp_http;
+mod sampling;
 pub mod syslog;
 
 static BULK_OPERATORS: [&str; 3] = ["create", "index", "update"];
@@ -213,7 +216,8 @@ async fn write_logs_by_stream(
         }
 
         // write json data by stream
-        let mut req_stats = write_logs(thread_id, org_id, &stream_name, status, json_data).await?;
+        let (mut req_stats, sample) =
+            write_logs(thread_id, org_id, &stream_name, status, json_data).await?;
 
         let time_took = time_stats.1.elapsed().as_secs_f64();
         req_stats.response_time = time_took;
@@ -253,17 +257,77 @@ async fn write_logs_by_stream(
             )
             .await;
         }
+
+        // write the sample taken from the accepted records
+        let Some(sample) = sample.filter(|s| !s.records.is_empty()) else {
+            continue;
+        };
+        let destination = sample.destination.clone();
+        if let Some(mut sample_stats) = write_sample(thread_id, org_id, &stream_name, sample).await
+        {
+            sample_stats.response_time = time_stats.1.elapsed().as_secs_f64();
+            sample_stats.user_email = if user_email.is_empty() {
+                None
+            } else {
+                Some(user_email.to_string())
+            };
+            if fn_num.is_some() {
+                report_request_usage_stats(
+                    sample_stats,
+                    org_id,
+                    &destination,
+                    StreamType::Logs,
+                    usage_type,
+                    0,
+                    time_stats.0,
+                )
+                .await;
+            }
+        }
     }
     Ok(())
 }
 
+/// Writes the records sampled from `stream_name` into its companion stream,
+/// creating the stream on first use. A failing sample is logged and never
+/// fails the request it was taken from.
+async fn write_sample(
+    thread_id: usize,
+    org_id: &str,
+    stream_name: &str,
+    sample: sampling::Sample,
+) -> Option<RequestStats> {
+    let destination = sample.destination;
+    if db::compact::retention::is_deleting_stream(org_id, StreamType::Logs, &destination, None) {
+        log::warn!("sampling stream [{destination}] is being deleted");
+        return None;
+    }
+    if let Err(e) =
+        sampling::ensure_companion_stream(org_id, &destination, &sample.source_settings).await
+    {
+        log::error!("Error while creating sampling stream {org_id}/{destination}: {e}");
+        return None;
+    }
+    let mut status = IngestionStatus::Record(RecordStatus::default());
+    match write_logs(thread_id, org_id, &destination, &mut status, sample.records).await {
+        Ok((mut req_stats, _)) => {
+            req_stats.sampled_from = Some(stream_name.to_string());
+            Some(req_stats)
+        }
+        Err(e) => {
+            log::error!("Error while writing sample of {stream_name} to {destination}: {e}");
+            None
+        }
+    }
+}
+
 async fn write_logs(
     thread_id: usize,
     org_id: &str,
     stream_name: &str,
     status: &mut IngestionStatus,
     json_data: Vec<(i64, Map<String, Value>)>,
-) -> Result<RequestStats> {
+) -> Result<(RequestStats, Option<sampling::Sample>)> {
     let cfg = get_config();
     let log_ingest_errors = ingestion_log_enabled().await;
     // get schema and stream settings
@@ -290,7 +354,7 @@ async fn write_logs(
     let mut partition_keys: Vec<StreamPartition> = vec![];
     let mut partition_time_level = PartitionTimeLevel::from(cfg.limit.logs_file_retention.as_str());
     if stream_schema.has_partition_keys {
-        partition_keys = stream_settings.partition_keys;
+        partition_keys = stream_settings.partition_keys.clone();
         partition_time_level =
             unwrap_partition_time_level(stream_settings.partition_time_level, StreamType::Logs);
     }
@@ -344,9 +408,12 @@ async fn write_logs(
 
     let mut distinct_values = Vec::with_capacity(16);
 
+    // records are sampled after pipelines, so the sample reflects stored data
+    let mut sample = sampling::Sample::new(stream_name, &stream_settings);
+
     let mut write_buf: HashMap<String, SchemaRecords> = HashMap::new();
 
-    for (timestamp, mut record_val) in json_data {
+    for (idx, (timestamp, mut record_val)) in json_data.into_iter().enumerate() {
         let doc_id = record_val
             .get("_id")
             .map(|v| v.as_str().unwrap().to_string());
@@ -399,6 +466,7 @@ async fn write_logs(
                             ])
                             .inc();
                         log_failed_record(log_ingest_errors, &record_val, &e.to_string());
+                        let position = bulk_res.record_position(stream_name, idx);
                         bulk::add_record_status(
                             stream_name.to_string(),
                             &doc_id,
@@ -407,6 +475,7 @@ async fn write_logs(
                             bulk_res,
                             Some(bulk::SCHEMA_CONFORMANCE_FAILED.to_string()),
                             Some(e.to_string()),
+                            position,
                         );
                     }
                 }
@@ -443,25 +512,10 @@ async fn write_logs(
         // end check for alert triggers
 
         // get distinct_value items
-        let mut map = Map::new();
-        for field in DISTINCT_FIELDS.iter().chain(
-            stream_settings
-                .distinct_value_fields
-                .iter()
-                .map(|f| &f.name),
-        ) {
-            if let Some(val) = record_val.get(field) {
-                map.insert(field.clone(), val.clone());
-            }
-        }
-
-        if !map.is_empty() {
-            // add distinct values
-            distinct_values.push(MetadataItem::DistinctValues(DvItem {
-                stream_type: StreamType::Logs,
-                stream_name: stream_name.to_string(),
-                value: map,
-            }));
+        if let Some(item) =
+            DvItem::from_record(StreamType::Logs, stream_name, &stream_settings, &record_val)
+        {
+            distinct_values.push(MetadataItem::DistinctValues(item));
         }
 
         // get hour key
@@ -479,6 +533,10 @@ async fn write_logs(
             records: vec![],
             records_size: 0,
         });
+        if let Some(sample) = sample.as_mut() {
+            sample.add(timestamp, &record_val);
+        }
+
         let record_val = Value::Object(record_val);
         let record_size = estimate_json_bytes(&record_val);
         hour_buf.records.push(Arc::new(record_val));
@@ -490,6 +548,7 @@ async fn write_logs(
                 status.successful += 1;
             }
             IngestionStatus::Bulk(bulk_res) => {
+                let position = bulk_res.record_position(stream_name, idx);
                 bulk::add_record_status(
                     stream_name.to_string(),
                     &doc_id,
@@ -498,6 +557,7 @@ async fn write_logs(
                     bulk_res,
                     None,
                     None,
+                    position,
                 );
             }
         }
@@ -524,7 +584,7 @@ async fn write_logs(
     // only one trigger per request
     evaluate_trigger(triggers).await;
 
-    Ok(req_stats)
+    Ok((req_stats, sample))
 }
 
 pub fn refactor_map(
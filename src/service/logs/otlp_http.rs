@@ -60,7 +60,20 @@ pub async fn logs_proto_handler(
     in_stream_name: Option<&str>,
     user_email: &str,
 ) -> Result<HttpResponse> {
-    let request = ExportLogsServiceRequest::decode(body).expect("Invalid protobuf");
+    let request = match ExportLogsServiceRequest::decode(body) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!(
+                "[LOGS:OTLP] Invalid proto: org_id: {}, error: {}",
+                org_id,
+                e
+            );
+            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                format!("Invalid proto: {}", e),
+            )));
+        }
+    };
     match super::otlp_grpc::handle_grpc_request(
         thread_id,
         org_id,
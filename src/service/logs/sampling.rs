@@ -0,0 +1,153 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use config::{
+    meta::stream::{StreamSampling, StreamSettings, StreamType},
+    utils::json::{self, Map, Value},
+};
+
+use crate::service::db;
+
+/// Records of one request picked for the companion stream of a sampled stream.
+pub(super) struct Sample {
+    pub destination: String,
+    pub source_settings: StreamSettings,
+    pub records: Vec<(i64, Map<String, Value>)>,
+    sampling: StreamSampling,
+}
+
+impl Sample {
+    /// Returns `None` when the stream isn't sampled.
+    pub fn new(stream_name: &str, settings: &StreamSettings) -> Option<Self> {
+        let sampling = settings.sampling.clone()?;
+        Some(Self {
+            destination: sampling.destination(stream_name),
+            source_settings: settings.clone(),
+            records: vec![],
+            sampling,
+        })
+    }
+
+    pub fn add(&mut self, timestamp: i64, record: &Map<String, Value>) {
+        if self.sampling.is_sampled(record) {
+            self.records.push((timestamp, record.clone()));
+        }
+    }
+}
+
+/// The companion stream gets the settings of the stream it samples, without
+/// sampling of its own.
+pub(super) fn companion_settings(source: &StreamSettings) -> StreamSettings {
+    StreamSettings {
+        sampling: None,
+        ..source.clone()
+    }
+}
+
+/// Creates the companion stream on first use. An existing stream keeps its
+/// settings, so it can be tuned independently of the sampled one.
+pub(super) async fn ensure_companion_stream(
+    org_id: &str,
+    destination: &str,
+    source: &StreamSettings,
+) -> Result<()> {
+    if infra::schema::get_settings(org_id, destination, StreamType::Logs)
+        .await
+        .is_some()
+    {
+        return Ok(());
+    }
+    let mut metadata = HashMap::with_capacity(1);
+    metadata.insert(
+        "settings".to_string(),
+        json::to_string(&companion_settings(source))?,
+    );
+    db::schema::update_setting(org_id, destination, StreamType::Logs, metadata).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use config::meta::stream::{StreamPartition, TimeRange};
+
+    use super::*;
+
+    #[test]
+    fn test_companion_settings_drop_sampling_only() {
+        let source = StreamSettings {
+            partition_keys: vec![StreamPartition::new("service")],
+            full_text_search_keys: vec!["message".to_string()],
+            data_retention: 7,
+            extended_retention_days: vec![TimeRange::new(1, 2)],
+            sampling: Some(StreamSampling {
+                rate: 0.01,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let settings = companion_settings(&source);
+        assert!(settings.sampling.is_none());
+        assert_eq!(settings.partition_keys, source.partition_keys);
+        assert_eq!(settings.full_text_search_keys, source.full_text_search_keys);
+        assert_eq!(settings.data_retention, 7);
+        assert_eq!(
+            settings.extended_retention_days,
+            source.extended_retention_days
+        );
+
+        // the stored settings of the companion don't carry sampling either
+        let stored = StreamSettings::from(json::to_string(&settings).unwrap().as_str());
+        assert!(stored.sampling.is_none());
+        assert_eq!(stored.data_retention, 7);
+    }
+
+    #[test]
+    fn test_sample_takes_the_same_records() {
+        assert!(Sample::new("app", &StreamSettings::default()).is_none());
+
+        let settings = StreamSettings {
+            sampling: Some(StreamSampling {
+                rate: 0.5,
+                field: Some("user".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let records = (0..100)
+            .map(|i| {
+                json::json!({ "user": format!("u{}", i % 10), "n": i })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        let take = || {
+            let mut sample = Sample::new("app", &settings).unwrap();
+            for (i, record) in records.iter().enumerate() {
+                sample.add(i as i64, record);
+            }
+            sample
+        };
+        let first = take();
+        let second = take();
+        assert_eq!(first.destination, "app_sampled");
+        assert_eq!(first.records, second.records);
+        // whole users are either sampled or not
+        assert_eq!(first.records.len() % 10, 0);
+    }
+}
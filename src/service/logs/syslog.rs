@@ -122,7 +122,7 @@ pub async fn ingest(msg: &str, addr: SocketAddr) -> Result<HttpResponse> {
 
     // parse msg to json::Value
     let parsed_msg = syslog_loose::parse_message(msg);
-    let mut value = message_to_value(parsed_msg);
+    let mut value = message_to_value(parsed_msg, msg);
 
     // store a copy of original data before it's modified, when
     // 1. original data is an object
@@ -398,11 +398,14 @@ async fn get_org_for_ip(ip: std::net::IpAddr) -> Option<SyslogRoute> {
 }
 
 /// Create a `Value::Map` from the fields of the given syslog message.
-fn message_to_value(message: Message<&str>) -> json::Value {
+///
+/// The STRUCTURED-DATA of an RFC 5424 message is parsed from the `raw` message
+/// into `sd.<sd-id>.<param>` fields. When it is malformed the rest of the raw
+/// message after the header is stored as the message, and a `parse_error`
+/// field describes the problem.
+fn message_to_value(message: Message<&str>, raw: &str) -> json::Value {
     let mut result = json::Map::new();
 
-    result.insert("message".to_string(), message.msg.to_string().into());
-
     if let Some(host) = message.hostname {
         result.insert("hostname".to_string(), host.to_string().into());
     }
@@ -442,17 +445,176 @@ fn message_to_value(message: Message<&str>) -> json::Value {
         result.insert("procid".to_string(), value);
     }
 
-    for element in message.structured_data {
-        let mut sdata = json::Map::new();
-        for (name, value) in element.params() {
-            sdata.insert(name.to_string(), value.into());
+    // the header of a message with malformed structured data may not be
+    // recognized as RFC 5424, so the version is checked on the raw message
+    if is_rfc5424(raw) {
+        let structured_data = rfc5424_structured_data(raw);
+        match structured_data.and_then(parse_structured_data) {
+            Ok((sdata, msg)) => {
+                result.insert("message".to_string(), msg.to_string().into());
+                if !sdata.is_empty() {
+                    result.insert("sd".to_string(), sdata.into());
+                }
+            }
+            Err(e) => {
+                let msg = structured_data.unwrap_or(raw);
+                result.insert("message".to_string(), msg.to_string().into());
+                result.insert("parse_error".to_string(), e.to_string().into());
+            }
+        }
+    } else {
+        result.insert("message".to_string(), message.msg.to_string().into());
+        for element in message.structured_data {
+            let mut sdata = json::Map::new();
+            for (name, value) in element.params() {
+                sdata.insert(name.to_string(), value.into());
+            }
+            result.insert(element.id.to_string(), sdata.into());
         }
-        result.insert(element.id.to_string(), sdata.into());
     }
 
     result.into()
 }
 
+/// Errors in the STRUCTURED-DATA of an RFC 5424 message.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+enum StructuredDataError {
+    #[error("missing structured data")]
+    Missing,
+    #[error("expected '[' or '-' at the start of the structured data")]
+    ExpectedElement,
+    #[error("invalid SD-ID or PARAM-NAME in the structured data")]
+    InvalidName,
+    #[error("expected '=\"' after a PARAM-NAME in the structured data")]
+    ExpectedValue,
+    #[error("unterminated PARAM-VALUE in the structured data")]
+    UnterminatedValue,
+    #[error("unterminated SD-ELEMENT in the structured data")]
+    UnterminatedElement,
+    #[error("expected a space between the structured data and the message")]
+    ExpectedSpace,
+}
+
+/// Checks for the `<PRI>1 ` start of an RFC 5424 message.
+fn is_rfc5424(raw: &str) -> bool {
+    raw.trim_start()
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .is_some_and(|(pri, rest)| {
+            !pri.is_empty() && pri.bytes().all(|b| b.is_ascii_digit()) && rest.starts_with("1 ")
+        })
+}
+
+/// Returns the part of a raw RFC 5424 message starting at its STRUCTURED-DATA,
+/// which follows the `<PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID`
+/// header.
+fn rfc5424_structured_data(raw: &str) -> Result<&str, StructuredDataError> {
+    raw.trim_start()
+        .splitn(7, ' ')
+        .nth(6)
+        .ok_or(StructuredDataError::Missing)
+}
+
+/// Parses the STRUCTURED-DATA at the start of `input` into a map of SD-ID to
+/// the map of its params, and returns it along with the MSG that follows.
+///
+/// `"`, `\` and `]` are unescaped in param values, a backslash before any
+/// other character is kept. A repeated param name collects its values in an
+/// array.
+fn parse_structured_data(
+    input: &str,
+) -> Result<(json::Map<String, json::Value>, &str), StructuredDataError> {
+    let mut elements = json::Map::new();
+    if let Some(rest) = input.strip_prefix('-') {
+        return Ok((elements, structured_data_msg(rest)?));
+    }
+    if !input.starts_with('[') {
+        return Err(StructuredDataError::ExpectedElement);
+    }
+
+    let mut rest = input;
+    while let Some(element) = rest.strip_prefix('[') {
+        let (id, mut remaining) = parse_sd_name(element)?;
+        let params = elements
+            .entry(id.to_string())
+            .or_insert_with(|| json::Value::Object(json::Map::new()))
+            .as_object_mut()
+            .unwrap();
+        loop {
+            if let Some(after) = remaining.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+            let param = remaining
+                .strip_prefix(' ')
+                .ok_or(StructuredDataError::UnterminatedElement)?;
+            let (name, after_name) = parse_sd_name(param)?;
+            let value = after_name
+                .strip_prefix("=\"")
+                .ok_or(StructuredDataError::ExpectedValue)?;
+            let (value, after_value) = parse_param_value(value)?;
+            match params.get_mut(name) {
+                Some(json::Value::Array(values)) => values.push(value.into()),
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = json::Value::Array(vec![first, value.into()]);
+                }
+                None => {
+                    params.insert(name.to_string(), value.into());
+                }
+            }
+            remaining = after_value;
+        }
+    }
+    Ok((elements, structured_data_msg(rest)?))
+}
+
+/// Parses an SD-ID or PARAM-NAME, 1 to 32 printable US-ASCII characters other
+/// than `=`, space, `]` and `"`.
+fn parse_sd_name(input: &str) -> Result<(&str, &str), StructuredDataError> {
+    let len = input
+        .bytes()
+        .take_while(|b| b.is_ascii_graphic() && !matches!(b, b'=' | b']' | b'"'))
+        .count();
+    if len == 0 || len > 32 {
+        return Err(StructuredDataError::InvalidName);
+    }
+    Ok(input.split_at(len))
+}
+
+/// Parses a PARAM-VALUE up to its closing quote, returning the unescaped value
+/// and the input following the quote.
+fn parse_param_value(input: &str) -> Result<(String, &str), StructuredDataError> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &input[i + 1..])),
+            '\\' => match input[i + 1..].chars().next() {
+                Some(escaped @ ('"' | '\\' | ']')) => {
+                    value.push(escaped);
+                    chars.next();
+                }
+                _ => value.push(c),
+            },
+            _ => value.push(c),
+        }
+    }
+    Err(StructuredDataError::UnterminatedValue)
+}
+
+/// Returns the MSG following the STRUCTURED-DATA, without its separating space
+/// and UTF-8 BOM.
+fn structured_data_msg(input: &str) -> Result<&str, StructuredDataError> {
+    if input.is_empty() {
+        return Ok(input);
+    }
+    let msg = input
+        .strip_prefix(' ')
+        .ok_or(StructuredDataError::ExpectedSpace)?;
+    Ok(msg.strip_prefix('\u{feff}').unwrap_or(msg))
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr};
@@ -465,4 +627,111 @@ mod tests {
         let raw = r#"<190>2019-02-13T21:53:30.605850+00:00 74794bfb6795 liblogging-stdlog: [origin software="rsyslogd" swVersion="8.24.0" x-pid="9043" x-info="http://www.rsyslog.com"] This is a test message"#;
         ingest(raw, addr).await.unwrap();
     }
+
+    fn parse(raw: &str) -> json::Value {
+        message_to_value(syslog_loose::parse_message(raw), raw)
+    }
+
+    #[test]
+    fn test_structured_data_rfc_examples() {
+        let raw = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+            [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"] \
+            \u{feff}An application event log entry...";
+        let value = parse(raw);
+        assert_eq!(value["message"], "An application event log entry...");
+        assert_eq!(value["appname"], "evntslog");
+        assert_eq!(value["msgid"], "ID47");
+        assert_eq!(
+            value["sd"],
+            json::json!({
+                "exampleSDID@32473": {
+                    "iut": "3",
+                    "eventSource": "Application",
+                    "eventID": "1011",
+                },
+            })
+        );
+        assert!(value.get("parse_error").is_none());
+
+        let flattened = flatten::flatten_with_level(value, 3).unwrap();
+        assert_eq!(flattened["sd_examplesdid_32473_eventsource"], "Application");
+
+        // multiple elements and no message
+        let raw = "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+            [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"]\
+            [examplePriority@32473 class=\"high\"]";
+        let value = parse(raw);
+        assert_eq!(value["message"], "");
+        assert_eq!(value["sd"]["exampleSDID@32473"]["eventID"], "1011");
+        assert_eq!(value["sd"]["examplePriority@32473"]["class"], "high");
+
+        // no structured data
+        let raw = "<165>1 2003-08-24T05:14:15.000003-07:00 192.0.2.1 myproc 8710 - - \
+            %% It's time to make the do-nuts.";
+        let value = parse(raw);
+        assert_eq!(value["message"], "%% It's time to make the do-nuts.");
+        assert_eq!(value["procid"], 8710);
+        assert!(value.get("sd").is_none());
+        assert!(value.get("parse_error").is_none());
+    }
+
+    #[test]
+    fn test_structured_data_escapes() {
+        let raw = r#"<165>1 2003-10-11T22:14:15.003Z host app - - [meta@1 path="C:\\logs\"x\]" re="a\d" tag="a" tag="b"] msg"#;
+        let value = parse(raw);
+        assert_eq!(value["message"], "msg");
+        assert_eq!(
+            value["sd"]["meta@1"],
+            json::json!({
+                "path": r#"C:\logs"x]"#,
+                "re": r"a\d",
+                "tag": ["a", "b"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_structured_data_malformed() {
+        for (sd, error) in [
+            (
+                r#"[exampleSDID@32473 iut="3 msg"#,
+                StructuredDataError::UnterminatedValue,
+            ),
+            (
+                r#"[exampleSDID@32473 iut="3""#,
+                StructuredDataError::UnterminatedElement,
+            ),
+            (
+                r#"[exampleSDID@32473 iut=3] msg"#,
+                StructuredDataError::ExpectedValue,
+            ),
+            (r#"[ iut="3"] msg"#, StructuredDataError::InvalidName),
+            (
+                r#"[exampleSDID@32473]msg"#,
+                StructuredDataError::ExpectedSpace,
+            ),
+            ("msg", StructuredDataError::ExpectedElement),
+        ] {
+            let raw = format!("<165>1 2003-10-11T22:14:15.003Z host app - - {sd}");
+            let value = parse(&raw);
+            assert_eq!(value["message"], sd);
+            assert_eq!(value["parse_error"], error.to_string());
+            assert!(value.get("sd").is_none());
+        }
+    }
+
+    #[test]
+    fn test_rfc3164_unchanged() {
+        let raw = "<34>Oct 11 22:14:15 mymachine su: 'su root' failed for lonvick on /dev/pts/8";
+        let value = parse(raw);
+        assert_eq!(
+            value["message"],
+            "'su root' failed for lonvick on /dev/pts/8"
+        );
+        assert_eq!(value["hostname"], "mymachine");
+        assert_eq!(value["appname"], "su");
+        assert!(value.get("version").is_none());
+        assert!(value.get("sd").is_none());
+        assert!(value.get("parse_error").is_none());
+    }
 }
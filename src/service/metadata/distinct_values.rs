@@ -24,9 +24,9 @@ use std::{
 use arrow_schema::{DataType, Field, Schema};
 use config::{
     get_config,
-    meta::stream::StreamType,
+    meta::stream::{StreamSettings, StreamType},
     utils::{json, schema::infer_json_schema_from_map},
-    FxIndexMap, TIMESTAMP_COL_NAME,
+    FxIndexMap, DISTINCT_FIELDS, TIMESTAMP_COL_NAME,
 };
 use infra::{
     errors::{Error, Result},
@@ -54,6 +54,9 @@ pub const DISTINCT_STREAM_PREFIX: &str = "distinct_values";
 
 pub(crate) static INSTANCE: Lazy<DistinctValues> = Lazy::new(DistinctValues::new);
 
+/// Distinct values received since the last flush, by org. Every flush swaps
+/// the whole table out, so a value does not outlive one
+/// `ZO_DISTINCT_VALUES_INTERVAL` in memory and needs no TTL of its own.
 type MemTable = FxIndexMap<String, FxIndexMap<DvItem, u32>>;
 
 pub struct DistinctValues {
@@ -69,6 +72,38 @@ pub struct DvItem {
     pub value: Map<String, Value>,
 }
 
+impl DvItem {
+    /// Picks the values of the distinct value fields of the stream out of
+    /// `record`, those are the fields tracked for every stream plus the
+    /// `distinct_value_fields` of the stream settings.
+    ///
+    /// Returns `None` if the record has none of these fields.
+    pub fn from_record(
+        stream_type: StreamType,
+        stream_name: &str,
+        settings: &StreamSettings,
+        record: &Map<String, Value>,
+    ) -> Option<Self> {
+        let mut value = Map::new();
+        for field in DISTINCT_FIELDS
+            .iter()
+            .chain(settings.distinct_value_fields.iter().map(|f| &f.name))
+        {
+            if let Some(val) = record.get(field) {
+                value.insert(field.clone(), val.clone());
+            }
+        }
+        if value.is_empty() {
+            return None;
+        }
+        Some(Self {
+            stream_type,
+            stream_name: stream_name.to_string(),
+            value,
+        })
+    }
+}
+
 #[derive(Debug)]
 enum DvEventType {
     Add,
@@ -207,9 +242,12 @@ impl Metadata for DistinctValues {
                 stream_name
             );
             // check for schema
-            let db_schema =
-                infra::schema::get_cache(&org_id, &distinct_stream_name, StreamType::Metadata)
-                    .await?;
+            let db_schema = infra::schema::get_cache_for_ingestion(
+                &org_id,
+                &distinct_stream_name,
+                StreamType::Metadata,
+            )
+            .await?;
             let mut is_new = false;
             if db_schema.fields_map().is_empty() {
                 is_new = true;
@@ -348,3 +386,71 @@ async fn run_flush() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use config::meta::stream::DistinctField;
+    use serde_json::json;
+
+    use super::*;
+
+    fn settings(fields: &[&str]) -> StreamSettings {
+        StreamSettings {
+            distinct_value_fields: fields
+                .iter()
+                .map(|f| DistinctField {
+                    name: f.to_string(),
+                    added_ts: 0,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dv_item_from_record_uses_stream_fields() {
+        let record = json!({
+            "service_name": "checkout",
+            "k8s_namespace": "prod",
+            "k8s_pod": "checkout-1",
+            "message": "paid",
+        });
+        let record = record.as_object().unwrap();
+
+        let item = DvItem::from_record(
+            StreamType::Logs,
+            "app",
+            &settings(&["k8s_namespace"]),
+            record,
+        )
+        .unwrap();
+        assert_eq!(item.stream_type, StreamType::Logs);
+        assert_eq!(item.stream_name, "app");
+        assert_eq!(
+            Value::Object(item.value),
+            json!({"service_name": "checkout", "k8s_namespace": "prod"})
+        );
+
+        // the same record in another stream follows the settings of that stream
+        let item = DvItem::from_record(StreamType::Logs, "infra", &settings(&["k8s_pod"]), record)
+            .unwrap();
+        assert_eq!(
+            Value::Object(item.value),
+            json!({"service_name": "checkout", "k8s_pod": "checkout-1"})
+        );
+    }
+
+    #[test]
+    fn test_dv_item_from_record_without_fields() {
+        let record = json!({"message": "paid"});
+        let record = record.as_object().unwrap();
+        assert!(DvItem::from_record(StreamType::Logs, "app", &settings(&[]), record).is_none());
+        assert!(DvItem::from_record(
+            StreamType::Logs,
+            "app",
+            &settings(&["k8s_namespace"]),
+            record
+        )
+        .is_none());
+    }
+}
@@ -20,9 +20,14 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use tokio::try_join;
 
-use crate::service::metadata::{distinct_values::DvItem, trace_list_index::TraceListItem};
+use crate::service::metadata::{
+    distinct_values::DvItem, schema_evolution::SchemaEvolutionItem,
+    service_latency_index::ServiceLatencyItem, trace_list_index::TraceListItem,
+};
 
 pub mod distinct_values;
+pub mod schema_evolution;
+pub mod service_latency_index;
 pub mod trace_list_index;
 
 static METADATA_MANAGER: Lazy<MetadataManager> = Lazy::new(MetadataManager::new);
@@ -31,11 +36,15 @@ static METADATA_MANAGER: Lazy<MetadataManager> = Lazy::new(MetadataManager::new)
 pub enum MetadataItem {
     TraceListIndexer(TraceListItem),
     DistinctValues(DvItem),
+    ServiceLatencyIndex(ServiceLatencyItem),
+    SchemaEvolution(SchemaEvolutionItem),
 }
 
 pub enum MetadataType {
     TraceListIndexer,
     DistinctValues,
+    ServiceLatencyIndex,
+    SchemaEvolution,
 }
 
 pub struct MetadataManager {}
@@ -65,7 +74,9 @@ impl MetadataManager {
     pub async fn close(&self) -> infra::errors::Result<()> {
         match try_join!(
             trace_list_index::INSTANCE.stop(),
-            distinct_values::INSTANCE.stop()
+            distinct_values::INSTANCE.stop(),
+            service_latency_index::INSTANCE.stop(),
+            schema_evolution::INSTANCE.stop()
         ) {
             Ok(_) => {}
             Err(e) => {
@@ -85,6 +96,10 @@ pub async fn write(
     match mt {
         MetadataType::TraceListIndexer => trace_list_index::INSTANCE.write(org_id, data).await,
         MetadataType::DistinctValues => distinct_values::INSTANCE.write(org_id, data).await,
+        MetadataType::ServiceLatencyIndex => {
+            service_latency_index::INSTANCE.write(org_id, data).await
+        }
+        MetadataType::SchemaEvolution => schema_evolution::INSTANCE.write(org_id, data).await,
     }
 }
 
@@ -0,0 +1,404 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Schema evolution audit log.
+//!
+//! Every schema change made by the ingest path is recorded as one row of the
+//! `schema_evolution` metadata stream of the organization, so the changes can
+//! be searched like any other stream. The rows are buffered in memory and
+//! written once per `ZO_SCHEMA_EVOLUTION_AUDIT_INTERVAL`.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use arrow_schema::{DataType, Field, Schema};
+use config::{
+    get_config,
+    meta::stream::StreamType,
+    utils::{json, schema_ext::SchemaExt},
+    FxIndexMap, TIMESTAMP_COL_NAME,
+};
+use infra::{
+    errors::{Error, Result},
+    schema::unwrap_partition_time_level,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::{sync::RwLock, time};
+
+use crate::{
+    common::meta::stream::SchemaRecords,
+    service::{
+        db, ingestion,
+        metadata::{Metadata, MetadataItem},
+    },
+};
+
+pub const STREAM_NAME: &str = "schema_evolution";
+
+/// Record fields holding the trace or request id of a record, in order of preference.
+const RECORD_ID_FIELDS: [&str; 4] = ["trace_id", "traceId", "request_id", "requestId"];
+
+pub(crate) static INSTANCE: Lazy<SchemaEvolutionAudit> = Lazy::new(SchemaEvolutionAudit::new);
+
+/// Audit rows received since the last flush, by org.
+type MemTable = FxIndexMap<String, Vec<SchemaEvolutionItem>>;
+
+pub struct SchemaEvolutionAudit {
+    schema: Arc<Schema>,
+    shutdown: Arc<AtomicBool>,
+    mem_table: Arc<RwLock<MemTable>>,
+}
+
+/// One schema change of a stream.
+#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SchemaEvolutionItem {
+    pub _timestamp: i64,
+    pub stream_type: StreamType,
+    pub stream_name: String,
+    /// Comma separated names of the new fields
+    pub fields_added: String,
+    /// Comma separated names of the fields no longer in the schema
+    pub fields_removed: String,
+    /// JSON array of `{name, old_type, new_type}` for every added, removed or
+    /// retyped field, the missing side is null
+    pub field_changes: String,
+    /// Trace or request id of the first record carrying a changed field
+    pub trace_id: String,
+    /// Timestamp of the record batch that caused the change
+    pub record_ts: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct FieldChange<'a> {
+    name: &'a str,
+    old_type: Option<String>,
+    new_type: Option<String>,
+}
+
+impl SchemaEvolutionItem {
+    /// Describes the change from `old` to `new`, taking the trace id from the
+    /// first of `records` that carries a changed field.
+    ///
+    /// Returns `None` if the schemas have the same fields and types.
+    pub fn new(
+        stream_type: StreamType,
+        stream_name: &str,
+        old: &Schema,
+        new: &Schema,
+        records: &[&Map<String, Value>],
+        record_ts: i64,
+    ) -> Option<Self> {
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        let mut changes = Vec::new();
+        for field in new.fields() {
+            match old.field_with_name(field.name()) {
+                Ok(old_field) if old_field.data_type() == field.data_type() => {}
+                Ok(old_field) => changes.push(FieldChange {
+                    name: field.name(),
+                    old_type: Some(old_field.data_type().to_string()),
+                    new_type: Some(field.data_type().to_string()),
+                }),
+                Err(_) => {
+                    added.push(field.name().as_str());
+                    changes.push(FieldChange {
+                        name: field.name(),
+                        old_type: None,
+                        new_type: Some(field.data_type().to_string()),
+                    });
+                }
+            }
+        }
+        for field in old.fields() {
+            if new.field_with_name(field.name()).is_err() {
+                removed.push(field.name().as_str());
+                changes.push(FieldChange {
+                    name: field.name(),
+                    old_type: Some(field.data_type().to_string()),
+                    new_type: None,
+                });
+            }
+        }
+        if changes.is_empty() {
+            return None;
+        }
+
+        let trace_id = records
+            .iter()
+            .find(|record| changes.iter().any(|c| record.contains_key(c.name)))
+            .and_then(|record| {
+                RECORD_ID_FIELDS
+                    .iter()
+                    .find_map(|f| record.get(*f).and_then(|v| v.as_str()))
+            })
+            .unwrap_or_default()
+            .to_string();
+
+        Some(Self {
+            _timestamp: chrono::Utc::now().timestamp_micros(),
+            stream_type,
+            stream_name: stream_name.to_string(),
+            fields_added: added.join(","),
+            fields_removed: removed.join(","),
+            field_changes: json::to_string(&changes).unwrap_or_default(),
+            trace_id,
+            record_ts,
+        })
+    }
+}
+
+impl Default for SchemaEvolutionAudit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaEvolutionAudit {
+    pub fn new() -> Self {
+        tokio::task::spawn(async move { run_flush().await });
+        Self {
+            schema: audit_schema(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            mem_table: Arc::new(RwLock::new(FxIndexMap::default())),
+        }
+    }
+
+    async fn set_db_schema(&self, org_id: &str, timestamp: i64) -> Result<bool> {
+        let db_schema =
+            infra::schema::get_cache_for_ingestion(org_id, STREAM_NAME, StreamType::Metadata)
+                .await?;
+        if !db_schema.fields_map().is_empty() {
+            return Ok(false);
+        }
+
+        let schema = self.schema.as_ref().clone();
+        if let Err(e) = db::schema::merge(
+            org_id,
+            STREAM_NAME,
+            StreamType::Metadata,
+            &schema,
+            Some(timestamp),
+        )
+        .await
+        {
+            log::error!("[SCHEMA_EVOLUTION] error while setting schema: {}", e);
+            return Err(Error::Message(e.to_string()));
+        }
+        Ok(true)
+    }
+
+    /// Returns the rows of the org waiting for the next flush.
+    #[cfg(test)]
+    pub(crate) async fn pending(&self, org_id: &str) -> Vec<SchemaEvolutionItem> {
+        self.mem_table
+            .read()
+            .await
+            .get(org_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn audit_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new(TIMESTAMP_COL_NAME, DataType::Int64, false),
+        Field::new("stream_type", DataType::Utf8, false),
+        Field::new("stream_name", DataType::Utf8, false),
+        Field::new("fields_added", DataType::Utf8, false),
+        Field::new("fields_removed", DataType::Utf8, false),
+        Field::new("field_changes", DataType::Utf8, false),
+        Field::new("trace_id", DataType::Utf8, false),
+        Field::new("record_ts", DataType::Int64, false),
+    ]))
+}
+
+impl Metadata for SchemaEvolutionAudit {
+    fn generate_schema(&self) -> Arc<Schema> {
+        audit_schema()
+    }
+
+    async fn write(&self, org_id: &str, data: Vec<MetadataItem>) -> Result<()> {
+        if self.shutdown.load(Ordering::Relaxed) {
+            return Err(Error::Message(
+                "schema evolution audit is shut down".to_string(),
+            ));
+        }
+        let items = data
+            .into_iter()
+            .filter_map(|item| match item {
+                MetadataItem::SchemaEvolution(item) => Some(item),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return Ok(());
+        }
+        let mut mem_table = self.mem_table.write().await;
+        mem_table
+            .entry(org_id.to_string())
+            .or_default()
+            .extend(items);
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let cfg = get_config();
+        let mut mem_table = self.mem_table.write().await;
+        let mut new_table: MemTable = FxIndexMap::default();
+        std::mem::swap(&mut new_table, &mut *mem_table);
+        drop(mem_table);
+
+        let timestamp = chrono::Utc::now().timestamp_micros();
+        let schema_key = self.schema.hash_key();
+        for (org_id, items) in new_table {
+            if items.is_empty() {
+                continue;
+            }
+
+            let _is_new = self.set_db_schema(&org_id, timestamp).await?;
+
+            let mut buf: HashMap<String, SchemaRecords> = HashMap::new();
+            for item in items {
+                let mut data = json::to_value(item).unwrap();
+                let data = data.as_object_mut().unwrap();
+                let ts = data
+                    .get(TIMESTAMP_COL_NAME)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(timestamp);
+                let hour_key = ingestion::get_write_partition_key(
+                    ts,
+                    &vec![],
+                    unwrap_partition_time_level(None, StreamType::Metadata),
+                    data,
+                    Some(&schema_key),
+                );
+                let data = json::Value::Object(data.clone());
+                let data_size = json::to_vec(&data).unwrap_or_default().len();
+
+                let hour_buf = buf.entry(hour_key).or_insert_with(|| SchemaRecords {
+                    schema_key: schema_key.clone(),
+                    schema: self.schema.clone(),
+                    records: vec![],
+                    records_size: 0,
+                });
+                hour_buf.records.push(Arc::new(data));
+                hour_buf.records_size += data_size;
+            }
+
+            let writer =
+                ingester::get_writer(0, &org_id, StreamType::Metadata.as_str(), STREAM_NAME).await;
+            _ = ingestion::write_file(&writer, STREAM_NAME, buf, !cfg.common.wal_fsync_disabled)
+                .await;
+
+            #[cfg(feature = "enterprise")]
+            {
+                use o2_openfga::{
+                    authorizer::authz::set_ownership_if_not_exists,
+                    config::get_config as get_openfga_config,
+                };
+
+                // set ownership only in the first time
+                if _is_new && get_openfga_config().enabled {
+                    set_ownership_if_not_exists(
+                        &org_id,
+                        &format!("{}:{}", StreamType::Metadata, STREAM_NAME),
+                    )
+                    .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.shutdown.store(true, Ordering::Release);
+        if let Err(e) = self.flush().await {
+            log::error!("[SCHEMA_EVOLUTION] flush error: {}", e);
+        }
+        Ok(())
+    }
+}
+
+async fn run_flush() {
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().limit.schema_evolution_audit_interval,
+    ));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        if let Err(e) = INSTANCE.flush().await {
+            log::error!("[SCHEMA_EVOLUTION] error flush data to wal: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema(fields: &[(&str, DataType)]) -> Schema {
+        Schema::new(
+            fields
+                .iter()
+                .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_schema_evolution_item() {
+        let old = schema(&[("a", DataType::Int64), ("b", DataType::Int64)]);
+        let new = schema(&[
+            ("a", DataType::Int64),
+            ("b", DataType::Utf8),
+            ("c", DataType::Boolean),
+        ]);
+        let r1 = json!({"a": 1, "trace_id": "t1"});
+        let r2 = json!({"a": 2, "c": true, "trace_id": "t2"});
+        let records = [r1.as_object().unwrap(), r2.as_object().unwrap()];
+
+        let item =
+            SchemaEvolutionItem::new(StreamType::Logs, "app", &old, &new, &records, 10).unwrap();
+        assert_eq!(item.stream_name, "app");
+        assert_eq!(item.fields_added, "c");
+        assert_eq!(item.fields_removed, "");
+        assert_eq!(item.trace_id, "t2");
+        assert_eq!(item.record_ts, 10);
+        assert_eq!(
+            json::from_str::<Value>(&item.field_changes).unwrap(),
+            json!([
+                {"name": "b", "old_type": "Int64", "new_type": "Utf8"},
+                {"name": "c", "old_type": null, "new_type": "Boolean"},
+            ])
+        );
+
+        // a field no longer in the schema is reported as removed
+        let item = SchemaEvolutionItem::new(StreamType::Logs, "app", &new, &old, &[], 10).unwrap();
+        assert_eq!(item.fields_added, "");
+        assert_eq!(item.fields_removed, "c");
+        assert_eq!(item.trace_id, "");
+
+        assert!(SchemaEvolutionItem::new(StreamType::Logs, "app", &old, &old, &[], 10).is_none());
+    }
+}
@@ -0,0 +1,724 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use arrow_schema::{DataType, Field, Schema};
+use config::{
+    get_config,
+    meta::{
+        search,
+        stream::{StreamPartition, StreamSettings, StreamType},
+    },
+    utils::{json, schema_ext::SchemaExt},
+    FxIndexMap, TIMESTAMP_COL_NAME,
+};
+use infra::{
+    errors::{Error, Result},
+    schema::unwrap_partition_time_level,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{mpsc, RwLock},
+    time,
+};
+
+use crate::{
+    common::meta::stream::SchemaRecords,
+    service::{
+        db, ingestion,
+        metadata::{Metadata, MetadataItem},
+        search as SearchService, stream,
+    },
+};
+
+const CHANNEL_SIZE: usize = 10240;
+pub const STREAM_NAME: &str = "service_latency_index";
+const HOUR_MICROS: i64 = 3600 * 1_000_000;
+
+static PARTITION_KEYS: Lazy<[StreamPartition; 1]> =
+    Lazy::new(|| [StreamPartition::new("service_name")]);
+
+pub(crate) static INSTANCE: Lazy<ServiceLatencyIndex> = Lazy::new(ServiceLatencyIndex::new);
+
+type MemTable = FxIndexMap<String, FxIndexMap<LatencyKey, LatencyStats>>;
+
+pub struct ServiceLatencyIndex {
+    schema: Arc<Schema>,
+    channel: Arc<mpsc::Sender<LatencyEvent>>,
+    shutdown: Arc<AtomicBool>,
+    mem_table: Arc<RwLock<MemTable>>,
+}
+
+/// A single span as seen by the latency index.
+#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ServiceLatencyItem {
+    pub _timestamp: i64,
+    pub stream_name: String,
+    pub service_name: String,
+    pub operation_name: String,
+    /// span duration in microseconds
+    pub duration: i64,
+    pub is_error: bool,
+}
+
+/// The aggregation key, `_timestamp` is the start of the hour of the spans.
+#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Serialize)]
+struct LatencyKey {
+    #[serde(rename = "_timestamp")]
+    hour: i64,
+    stream_name: String,
+    service_name: String,
+    operation_name: String,
+}
+
+impl From<&ServiceLatencyItem> for LatencyKey {
+    fn from(item: &ServiceLatencyItem) -> Self {
+        Self {
+            hour: item._timestamp - item._timestamp.rem_euclid(HOUR_MICROS),
+            stream_name: item.stream_name.clone(),
+            service_name: item.service_name.clone(),
+            operation_name: item.operation_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub span_count: i64,
+    pub error_count: i64,
+    pub duration_sum: i64,
+    pub duration_max: i64,
+}
+
+impl LatencyStats {
+    fn add(&mut self, duration: i64, is_error: bool) {
+        self.span_count += 1;
+        if is_error {
+            self.error_count += 1;
+        }
+        self.duration_sum += duration;
+        self.duration_max = self.duration_max.max(duration);
+    }
+
+    fn merge(&mut self, other: &LatencyStats) {
+        self.span_count += other.span_count;
+        self.error_count += other.error_count;
+        self.duration_sum += other.duration_sum;
+        self.duration_max = self.duration_max.max(other.duration_max);
+    }
+}
+
+#[derive(Debug)]
+enum LatencyEventType {
+    Add,
+    Shutdown,
+}
+
+#[derive(Debug)]
+struct LatencyEvent {
+    org_id: String,
+    key: LatencyKey,
+    stats: LatencyStats,
+    ev_type: LatencyEventType,
+}
+
+impl LatencyEvent {
+    fn new(org_id: &str, key: LatencyKey, stats: LatencyStats) -> Self {
+        Self {
+            org_id: org_id.to_string(),
+            key,
+            stats,
+            ev_type: LatencyEventType::Add,
+        }
+    }
+    fn shutdown() -> Self {
+        Self {
+            org_id: String::from(""),
+            key: LatencyKey::default(),
+            stats: LatencyStats::default(),
+            ev_type: LatencyEventType::Shutdown,
+        }
+    }
+}
+
+impl Default for ServiceLatencyIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceLatencyIndex {
+    pub fn new() -> Self {
+        tokio::task::spawn(async move { run_flush().await });
+        Self {
+            schema: latency_schema(),
+            channel: handle_channel(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            mem_table: Arc::new(RwLock::new(FxIndexMap::default())),
+        }
+    }
+
+    async fn set_db_schema(&self, org_id: &str, timestamp: i64) -> Result<bool> {
+        let db_schema =
+            infra::schema::get_cache_for_ingestion(org_id, STREAM_NAME, StreamType::Metadata)
+                .await?;
+        if !db_schema.fields_map().is_empty() {
+            return Ok(false);
+        }
+
+        let schema = self.schema.as_ref().clone();
+        if let Err(e) = db::schema::merge(
+            org_id,
+            STREAM_NAME,
+            StreamType::Metadata,
+            &schema,
+            Some(timestamp),
+        )
+        .await
+        {
+            log::error!("[SERVICE_LATENCY_INDEX] error while setting schema: {}", e);
+            return Err(Error::Message(e.to_string()));
+        }
+
+        let settings = StreamSettings {
+            partition_time_level: None,
+            partition_keys: PARTITION_KEYS.to_vec(),
+            full_text_search_keys: vec![],
+            index_fields: vec![],
+            bloom_filter_fields: vec![],
+            data_retention: 0,
+            flatten_level: None,
+            max_query_range: 0,
+            defined_schema_fields: None,
+            store_original_data: false,
+            original_data_retention_days: 0,
+            approx_partition: false,
+            distinct_value_fields: vec![],
+            index_updated_at: 0,
+            extended_retention_days: vec![],
+            sampling: None,
+            cache_latest_files: None,
+            memory_cache_ttl: None,
+            settings_version: 0,
+        };
+        stream::save_stream_settings(org_id, STREAM_NAME, StreamType::Metadata, settings).await?;
+
+        Ok(true)
+    }
+}
+
+fn latency_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new(TIMESTAMP_COL_NAME, DataType::Int64, false),
+        Field::new("stream_name", DataType::Utf8, false),
+        Field::new("service_name", DataType::Utf8, false),
+        Field::new("operation_name", DataType::Utf8, false),
+        Field::new("span_count", DataType::Int64, false),
+        Field::new("error_count", DataType::Int64, false),
+        Field::new("duration_sum", DataType::Int64, false),
+        Field::new("duration_max", DataType::Int64, false),
+    ]))
+}
+
+/// Aggregates the spans of one request per (service, operation, hour).
+fn aggregate(items: Vec<MetadataItem>) -> FxIndexMap<LatencyKey, LatencyStats> {
+    let mut group_items: FxIndexMap<LatencyKey, LatencyStats> = FxIndexMap::default();
+    for item in items {
+        if let MetadataItem::ServiceLatencyIndex(item) = item {
+            group_items
+                .entry(LatencyKey::from(&item))
+                .or_default()
+                .add(item.duration, item.is_error);
+        }
+    }
+    group_items
+}
+
+fn to_record(key: &LatencyKey, stats: &LatencyStats) -> json::Map<String, json::Value> {
+    let mut data = match json::to_value(key) {
+        Ok(json::Value::Object(data)) => data,
+        _ => json::Map::new(),
+    };
+    if let Ok(json::Value::Object(stats)) = json::to_value(stats) {
+        data.extend(stats);
+    }
+    data
+}
+
+fn handle_channel() -> Arc<mpsc::Sender<LatencyEvent>> {
+    let (tx, mut rx) = mpsc::channel::<LatencyEvent>(CHANNEL_SIZE);
+    tokio::task::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Some(v) => v,
+                None => {
+                    log::info!("[SERVICE_LATENCY_INDEX] event channel closed");
+                    break;
+                }
+            };
+            if let LatencyEventType::Shutdown = event.ev_type {
+                if let Err(e) = INSTANCE.flush().await {
+                    log::error!("[SERVICE_LATENCY_INDEX] flush error: {}", e);
+                }
+                INSTANCE.shutdown.store(true, Ordering::Release);
+                break;
+            }
+            let mut mem_table = INSTANCE.mem_table.write().await;
+            mem_table
+                .entry(event.org_id)
+                .or_default()
+                .entry(event.key)
+                .or_default()
+                .merge(&event.stats);
+        }
+        log::info!("[SERVICE_LATENCY_INDEX] event loop exit");
+    });
+    Arc::new(tx)
+}
+
+impl Metadata for ServiceLatencyIndex {
+    fn generate_schema(&self) -> Arc<Schema> {
+        latency_schema()
+    }
+
+    async fn write(&self, org_id: &str, data: Vec<MetadataItem>) -> Result<()> {
+        for (key, stats) in aggregate(data) {
+            self.channel
+                .send(LatencyEvent::new(org_id, key, stats))
+                .await
+                .map_err(|v| Error::Message(v.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let cfg = get_config();
+        let mut mem_table = self.mem_table.write().await;
+        let mut new_table: MemTable = FxIndexMap::default();
+        std::mem::swap(&mut new_table, &mut *mem_table);
+        drop(mem_table);
+
+        // every flush writes the partial aggregates of the hour, the readers
+        // sum them up
+        let timestamp = chrono::Utc::now().timestamp_micros();
+        let schema_key = self.schema.hash_key();
+        for (org_id, items) in new_table {
+            if items.is_empty() {
+                continue;
+            }
+
+            let _is_new = self.set_db_schema(&org_id, timestamp).await?;
+
+            let mut buf: HashMap<String, SchemaRecords> = HashMap::new();
+            for (key, stats) in items.iter() {
+                let data = to_record(key, stats);
+                let hour_key = ingestion::get_write_partition_key(
+                    key.hour,
+                    PARTITION_KEYS.to_vec().as_ref(),
+                    unwrap_partition_time_level(None, StreamType::Metadata),
+                    &data,
+                    Some(&schema_key),
+                );
+                let data = json::Value::Object(data);
+                let data_size = json::to_vec(&data).unwrap_or_default().len();
+
+                let hour_buf = buf.entry(hour_key).or_insert_with(|| SchemaRecords {
+                    schema_key: schema_key.clone(),
+                    schema: self.schema.clone(),
+                    records: vec![],
+                    records_size: 0,
+                });
+                hour_buf.records.push(Arc::new(data));
+                hour_buf.records_size += data_size;
+            }
+
+            let writer =
+                ingester::get_writer(0, &org_id, StreamType::Metadata.as_str(), STREAM_NAME).await;
+            _ = ingestion::write_file(&writer, STREAM_NAME, buf, !cfg.common.wal_fsync_disabled)
+                .await;
+
+            #[cfg(feature = "enterprise")]
+            {
+                use o2_openfga::{
+                    authorizer::authz::set_ownership_if_not_exists,
+                    config::get_config as get_openfga_config,
+                };
+
+                // set ownership only in the first time
+                if _is_new && get_openfga_config().enabled {
+                    set_ownership_if_not_exists(
+                        &org_id,
+                        &format!("{}:{}", StreamType::Metadata, STREAM_NAME),
+                    )
+                    .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        let tx = self.channel.clone();
+        tx.send(LatencyEvent::shutdown())
+            .await
+            .map_err(|e| Error::Message(e.to_string()))?;
+        let mut i = 0;
+        while i < 10 {
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            log::info!("[SERVICE_LATENCY_INDEX] shutting down");
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+async fn run_flush() {
+    let mut interval = time::interval(time::Duration::from_secs(
+        get_config().limit.service_latency_index_interval,
+    ));
+    interval.tick().await; // trigger the first run
+    loop {
+        interval.tick().await;
+        if let Err(e) = INSTANCE.flush().await {
+            log::error!("[SERVICE_LATENCY_INDEX] error flush data to wal: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct OperationLatency {
+    pub operation_name: String,
+    #[serde(flatten)]
+    pub stats: LatencyStats,
+    /// average span duration in microseconds
+    pub duration_avg: i64,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ServiceLatency {
+    pub service_name: String,
+    #[serde(flatten)]
+    pub stats: LatencyStats,
+    /// average span duration in microseconds
+    pub duration_avg: i64,
+    pub operations: Vec<OperationLatency>,
+}
+
+fn duration_avg(stats: &LatencyStats) -> i64 {
+    if stats.span_count == 0 {
+        0
+    } else {
+        stats.duration_sum / stats.span_count
+    }
+}
+
+/// Returns the span count, error count and duration of the services of a
+/// traces stream and of their operations, busiest first.
+pub async fn query(
+    trace_id: &str,
+    org_id: &str,
+    stream_name: &str,
+    start_time: i64,
+    end_time: i64,
+    user_id: Option<String>,
+) -> Result<Vec<ServiceLatency>> {
+    let sql = format!(
+        "SELECT service_name, operation_name, sum(span_count) AS span_count, sum(error_count) AS error_count, sum(duration_sum) AS duration_sum, max(duration_max) AS duration_max FROM \"{STREAM_NAME}\" WHERE stream_name = '{}' GROUP BY service_name, operation_name",
+        stream_name.replace('\'', "''")
+    );
+    let mut req = search::Request {
+        query: search::Query {
+            sql,
+            from: 0,
+            size: get_config().limit.query_default_limit,
+            start_time,
+            end_time,
+            quick_mode: false,
+            query_type: "".to_string(),
+            track_total_hits: false,
+            uses_zo_fn: false,
+            query_fn: None,
+            action_id: None,
+            skip_wal: false,
+            streaming_output: false,
+            streaming_id: None,
+            timezone: None,
+            debug_cache: false,
+        },
+        encoding: search::RequestEncoding::Empty,
+        regions: vec![],
+        clusters: vec![],
+        timeout: 0,
+        search_type: None,
+        search_event_context: None,
+        use_cache: None,
+        explain: false,
+    };
+    // the aggregates are written with the start of their hour
+    req.query.start_time -= req.query.start_time.rem_euclid(HOUR_MICROS);
+
+    let res = SearchService::search(trace_id, org_id, StreamType::Metadata, user_id, &req).await?;
+    let rows = res
+        .hits
+        .iter()
+        .map(|hit| {
+            let operation_name = hit
+                .get("operation_name")
+                .map(json::get_string_value)
+                .unwrap_or_default();
+            let service_name = hit
+                .get("service_name")
+                .map(json::get_string_value)
+                .unwrap_or_default();
+            let int_value =
+                |field: &str| hit.get(field).map(json::get_int_value).unwrap_or_default();
+            let stats = LatencyStats {
+                span_count: int_value("span_count"),
+                error_count: int_value("error_count"),
+                duration_sum: int_value("duration_sum"),
+                duration_max: int_value("duration_max"),
+            };
+            (service_name, operation_name, stats)
+        })
+        .collect();
+    Ok(group_by_service(rows))
+}
+
+fn group_by_service(rows: Vec<(String, String, LatencyStats)>) -> Vec<ServiceLatency> {
+    let mut services: FxIndexMap<String, ServiceLatency> = FxIndexMap::default();
+    for (service_name, operation_name, stats) in rows {
+        let service = services
+            .entry(service_name.clone())
+            .or_insert_with(|| ServiceLatency {
+                service_name,
+                ..Default::default()
+            });
+        service.stats.merge(&stats);
+        service.operations.push(OperationLatency {
+            operation_name,
+            stats,
+            duration_avg: duration_avg(&stats),
+        });
+    }
+    let mut services: Vec<ServiceLatency> = services.into_values().collect();
+    for service in services.iter_mut() {
+        service.duration_avg = duration_avg(&service.stats);
+        service
+            .operations
+            .sort_by(|a, b| b.stats.span_count.cmp(&a.stats.span_count));
+    }
+    services.sort_by(|a, b| b.stats.span_count.cmp(&a.stats.span_count));
+    services
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(timestamp: i64, operation_name: &str, duration: i64, is_error: bool) -> MetadataItem {
+        MetadataItem::ServiceLatencyIndex(ServiceLatencyItem {
+            _timestamp: timestamp,
+            stream_name: "default".to_string(),
+            service_name: "checkout".to_string(),
+            operation_name: operation_name.to_string(),
+            duration,
+            is_error,
+        })
+    }
+
+    fn merge_into(table: &mut MemTable, org_id: &str, items: Vec<MetadataItem>) {
+        for (key, stats) in aggregate(items) {
+            table
+                .entry(org_id.to_string())
+                .or_default()
+                .entry(key)
+                .or_default()
+                .merge(&stats);
+        }
+    }
+
+    #[test]
+    fn test_generate_schema() {
+        let schema = latency_schema();
+        let fields: Vec<(&str, &DataType)> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (TIMESTAMP_COL_NAME, &DataType::Int64),
+                ("stream_name", &DataType::Utf8),
+                ("service_name", &DataType::Utf8),
+                ("operation_name", &DataType::Utf8),
+                ("span_count", &DataType::Int64),
+                ("error_count", &DataType::Int64),
+                ("duration_sum", &DataType::Int64),
+                ("duration_max", &DataType::Int64),
+            ]
+        );
+
+        // the records carry exactly the fields of the schema
+        let record = to_record(&LatencyKey::default(), &LatencyStats::default());
+        assert_eq!(record.len(), schema.fields().len());
+        for field in schema.fields() {
+            assert!(record.contains_key(field.name()), "{}", field.name());
+        }
+    }
+
+    #[test]
+    fn test_aggregate_per_hour() {
+        let hour = 1_700_000_000_000_000 - 1_700_000_000_000_000 % HOUR_MICROS;
+        let items = vec![
+            span(hour + 10, "GET /cart", 100, false),
+            span(hour + HOUR_MICROS - 1, "GET /cart", 300, true),
+            span(hour + HOUR_MICROS, "GET /cart", 50, false),
+            span(hour + 20, "POST /pay", 70, false),
+        ];
+        let groups = aggregate(items);
+        assert_eq!(groups.len(), 3);
+
+        let (key, stats) = groups.get_index(0).unwrap();
+        assert_eq!(key.hour, hour);
+        assert_eq!(key.operation_name, "GET /cart");
+        assert_eq!(
+            stats,
+            &LatencyStats {
+                span_count: 2,
+                error_count: 1,
+                duration_sum: 400,
+                duration_max: 300,
+            }
+        );
+        let (key, stats) = groups.get_index(1).unwrap();
+        assert_eq!(key.hour, hour + HOUR_MICROS);
+        assert_eq!(stats.span_count, 1);
+        let record = to_record(key, stats);
+        assert_eq!(
+            record.get(TIMESTAMP_COL_NAME),
+            Some(&json::Value::from(hour + HOUR_MICROS))
+        );
+    }
+
+    #[test]
+    fn test_aggregate_across_flush_boundary() {
+        let hour = 1_700_000_000_000_000 - 1_700_000_000_000_000 % HOUR_MICROS;
+        let mut mem_table = MemTable::default();
+        merge_into(
+            &mut mem_table,
+            "org1",
+            vec![
+                span(hour + 1, "GET /cart", 100, false),
+                span(hour + 2, "GET /cart", 400, true),
+            ],
+        );
+        merge_into(
+            &mut mem_table,
+            "org1",
+            vec![span(hour + 3, "GET /cart", 200, false)],
+        );
+
+        // flush, the writes that arrive later start a new aggregate
+        let first = std::mem::take(&mut mem_table);
+        merge_into(
+            &mut mem_table,
+            "org1",
+            vec![span(hour + 4, "GET /cart", 600, true)],
+        );
+        let second = std::mem::take(&mut mem_table);
+
+        let first = first.get("org1").unwrap();
+        let second = second.get("org1").unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        let (key, first) = first.get_index(0).unwrap();
+        assert_eq!(
+            first,
+            &LatencyStats {
+                span_count: 3,
+                error_count: 1,
+                duration_sum: 700,
+                duration_max: 400,
+            }
+        );
+        let second = second.get(key).unwrap();
+        assert_eq!(second.span_count, 1);
+
+        // the query adds up the partial aggregates of the hour
+        let rows = vec![
+            ("checkout".to_string(), "GET /cart".to_string(), *first),
+            ("checkout".to_string(), "GET /cart".to_string(), *second),
+        ];
+        let mut total = LatencyStats::default();
+        for (_, _, stats) in rows.iter() {
+            total.merge(stats);
+        }
+        assert_eq!(
+            total,
+            LatencyStats {
+                span_count: 4,
+                error_count: 2,
+                duration_sum: 1300,
+                duration_max: 600,
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_by_service() {
+        let stats = |span_count, duration_sum, duration_max| LatencyStats {
+            span_count,
+            error_count: 0,
+            duration_sum,
+            duration_max,
+        };
+        let services = group_by_service(vec![
+            (
+                "cart".to_string(),
+                "GET /cart".to_string(),
+                stats(2, 200, 150),
+            ),
+            (
+                "pay".to_string(),
+                "POST /pay".to_string(),
+                stats(10, 1000, 300),
+            ),
+            (
+                "cart".to_string(),
+                "PUT /cart".to_string(),
+                stats(4, 200, 90),
+            ),
+        ]);
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].service_name, "pay");
+        assert_eq!(services[1].service_name, "cart");
+        assert_eq!(services[1].stats, stats(6, 400, 150));
+        assert_eq!(services[1].duration_avg, 66);
+        assert_eq!(services[1].operations[0].operation_name, "PUT /cart");
+        assert_eq!(services[1].operations[0].duration_avg, 50);
+    }
+}
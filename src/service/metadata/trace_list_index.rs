@@ -211,10 +211,15 @@ impl TraceListIndex {
                 max_query_range: 0,
                 defined_schema_fields: None,
                 store_original_data: false,
+                original_data_retention_days: 0,
                 approx_partition: false,
                 distinct_value_fields: vec![],
                 index_updated_at: 0,
                 extended_retention_days: vec![],
+                sampling: None,
+                cache_latest_files: None,
+                memory_cache_ttl: None,
+                settings_version: 0,
             };
 
             stream::save_stream_settings(org_id, STREAM_NAME, StreamType::Metadata, settings)
@@ -748,6 +748,7 @@ pub(crate) async fn get_series(
         search_type: None,
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     let series = match search_service::search("", org_id, StreamType::Metrics, None, &req).await {
         Err(err) => {
@@ -891,6 +892,7 @@ pub(crate) async fn get_label_values(
         search_type: None,
         search_event_context: None,
         use_cache: None,
+        explain: false,
     };
     let mut label_values = match search_service::search("", org_id, stream_type, None, &req).await {
         Ok(resp) => resp
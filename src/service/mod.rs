@@ -16,6 +16,7 @@
 use config::{meta::stream::StreamParams, utils::schema::format_stream_name};
 use infra::errors::Result;
 pub mod alerts;
+pub mod branding;
 pub mod circuit_breaker;
 pub mod compact;
 pub mod dashboards;
@@ -28,6 +29,7 @@ pub mod folders;
 pub mod functions;
 pub mod grpc;
 pub mod ingestion;
+pub mod invites;
 pub mod kv;
 pub mod logs;
 pub mod metadata;
@@ -43,9 +45,11 @@ pub mod self_reporting;
 pub mod session;
 pub mod short_url;
 pub mod stream;
+pub mod synthetic;
 pub mod syslogs_route;
 pub mod tls;
 pub mod traces;
+pub mod trash;
 pub mod users;
 
 // format stream name
@@ -0,0 +1,337 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Backfill of scheduled pipelines.
+//!
+//! A backfill runs the query of a scheduled pipeline over a past time range,
+//! in windows of `ZO_DERIVED_STREAM_SCHEDULE_INTERVAL`. The job is a trigger
+//! of the scheduler whose data holds the progress of the job.
+
+use config::{
+    get_config, ider,
+    meta::{
+        pipeline::components::PipelineSource,
+        triggers::{ScheduledTriggerData, Trigger, TriggerModule},
+    },
+    utils::{
+        json,
+        time::{now_micros, second_micros},
+    },
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::service::{
+    alerts::derived_streams::DerivedStreamExt,
+    db::{self, pipeline::PipelineError},
+};
+
+/// Field added to the records ingested by a backfill, the same window of the
+/// same job always gets the same marker.
+pub const BACKFILL_MARKER_COL: &str = "_backfill";
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct BackfillRequest {
+    /// Start of the time range, in microseconds
+    pub start_time: i64,
+    /// End of the time range, in microseconds
+    pub end_time: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStatus {
+    #[default]
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BackfillJob {
+    pub job_id: String,
+    pub pipeline_id: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Size of the windows in microseconds
+    pub window_size: i64,
+    /// The windows before this time are processed
+    pub processed_until: i64,
+    pub status: BackfillStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl BackfillJob {
+    pub fn new(pipeline_id: &str, start_time: i64, end_time: i64, window_size: i64) -> Self {
+        let now = now_micros();
+        Self {
+            job_id: ider::generate(),
+            pipeline_id: pipeline_id.to_string(),
+            start_time,
+            end_time,
+            window_size: window_size.max(1),
+            processed_until: start_time,
+            status: BackfillStatus::Pending,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Returns the next window to process as `[start, end)`, `None` once the
+    /// job is finished.
+    pub fn next_window(&self) -> Option<(i64, i64)> {
+        if self.is_finished() || self.processed_until >= self.end_time {
+            return None;
+        }
+        let start = self.processed_until;
+        Some((
+            start,
+            std::cmp::min(start.saturating_add(self.window_size), self.end_time),
+        ))
+    }
+
+    /// Records that the window ending at `end` is processed.
+    pub fn complete_window(&mut self, end: i64) {
+        self.processed_until = end;
+        self.error = None;
+        self.status = if end >= self.end_time {
+            BackfillStatus::Completed
+        } else {
+            BackfillStatus::Running
+        };
+        self.updated_at = now_micros();
+    }
+
+    /// Stops the job, the windows after `processed_until` are not processed.
+    pub fn fail(&mut self, error: String) {
+        self.error = Some(error);
+        self.status = BackfillStatus::Failed;
+        self.updated_at = now_micros();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            BackfillStatus::Completed | BackfillStatus::Failed
+        )
+    }
+
+    /// Deduplication marker of the records ingested for the window starting
+    /// at `window_start`.
+    pub fn window_marker(&self, window_start: i64) -> String {
+        format!("{}/{}", self.job_id, window_start)
+    }
+
+    fn overlaps(&self, start_time: i64, end_time: i64) -> bool {
+        self.start_time < end_time && start_time < self.end_time
+    }
+}
+
+fn module_key(pipeline_id: &str, job_id: &str) -> String {
+    format!("{pipeline_id}/{job_id}")
+}
+
+/// Clamps the backfill range so that it ends where the live schedule of the
+/// pipeline started. `live_start` is `None` if the schedule hasn't run yet.
+fn clamp_to_live_schedule(
+    (start_time, end_time): (i64, i64),
+    live_start: Option<i64>,
+    now: i64,
+) -> Result<(i64, i64), PipelineError> {
+    if start_time >= end_time {
+        return Err(PipelineError::InvalidBackfill(
+            "start_time must be before end_time".to_string(),
+        ));
+    }
+    let end_time = end_time.min(live_start.unwrap_or(now)).min(now);
+    if start_time >= end_time {
+        return Err(PipelineError::InvalidBackfill(
+            "the time range is already processed by the pipeline schedule".to_string(),
+        ));
+    }
+    Ok((start_time, end_time))
+}
+
+async fn list_jobs(org_id: &str, pipeline_id: &str) -> Result<Vec<BackfillJob>, PipelineError> {
+    let prefix = format!("{pipeline_id}/");
+    let triggers = db::scheduler::list_by_org(org_id, Some(TriggerModule::Backfill)).await?;
+    Ok(triggers
+        .into_iter()
+        .filter(|t| t.module_key.starts_with(&prefix))
+        .filter_map(|t| json::from_str(&t.data).ok())
+        .collect())
+}
+
+/// Enqueues a backfill of the scheduled pipeline over the given time range.
+///
+/// The range is cut where the live schedule of the pipeline started, and must
+/// not overlap the range of another backfill of the pipeline that has not
+/// failed.
+pub async fn create_backfill(
+    org_id: &str,
+    pipeline_id: &str,
+    req: BackfillRequest,
+) -> Result<BackfillJob, PipelineError> {
+    let pipeline = match db::pipeline::get_by_id(pipeline_id).await {
+        Ok(pipeline) if pipeline.org == org_id => pipeline,
+        _ => return Err(PipelineError::NotFound(pipeline_id.to_string())),
+    };
+    let PipelineSource::Scheduled(derived_stream) = &pipeline.source else {
+        return Err(PipelineError::InvalidBackfill(
+            "only scheduled pipelines can be backfilled".to_string(),
+        ));
+    };
+
+    let live_key = derived_stream.get_scheduler_module_key(&pipeline.name, &pipeline.id);
+    let live_start = match db::scheduler::get(org_id, TriggerModule::DerivedStream, &live_key).await
+    {
+        Ok(trigger) => json::from_str::<ScheduledTriggerData>(&trigger.data)
+            .ok()
+            .and_then(|data| data.first_period_start.or(data.period_end_time)),
+        Err(_) => None,
+    };
+    let (start_time, end_time) =
+        clamp_to_live_schedule((req.start_time, req.end_time), live_start, now_micros())?;
+
+    if let Some(job) = list_jobs(org_id, pipeline_id)
+        .await?
+        .into_iter()
+        .find(|job| job.status != BackfillStatus::Failed && job.overlaps(start_time, end_time))
+    {
+        return Err(PipelineError::BackfillOverlap(job.job_id));
+    }
+
+    let window_size = second_micros(get_config().limit.derived_stream_schedule_interval.max(1));
+    let job = BackfillJob::new(pipeline_id, start_time, end_time, window_size);
+    let trigger = Trigger {
+        org: org_id.to_string(),
+        module: TriggerModule::Backfill,
+        module_key: module_key(pipeline_id, &job.job_id),
+        next_run_at: now_micros(),
+        data: json::to_string(&job).unwrap(),
+        ..Default::default()
+    };
+    db::scheduler::push(trigger).await?;
+    Ok(job)
+}
+
+/// Returns the backfill job with its progress.
+pub async fn get_backfill(
+    org_id: &str,
+    pipeline_id: &str,
+    job_id: &str,
+) -> Result<BackfillJob, PipelineError> {
+    let trigger = db::scheduler::get(
+        org_id,
+        TriggerModule::Backfill,
+        &module_key(pipeline_id, job_id),
+    )
+    .await
+    .map_err(|_| PipelineError::BackfillNotFound(job_id.to_string()))?;
+    json::from_str(&trigger.data).map_err(|_| PipelineError::BackfillNotFound(job_id.to_string()))
+}
+
+/// Deletes the backfill jobs of a deleted pipeline.
+pub async fn delete_backfills(org_id: &str, pipeline_id: &str) -> Result<(), PipelineError> {
+    for job in list_jobs(org_id, pipeline_id).await? {
+        db::scheduler::delete(
+            org_id,
+            TriggerModule::Backfill,
+            &module_key(pipeline_id, &job.job_id),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(job: &mut BackfillJob) -> Vec<(i64, i64)> {
+        let mut windows = vec![];
+        while let Some((start, end)) = job.next_window() {
+            windows.push((start, end));
+            job.complete_window(end);
+        }
+        windows
+    }
+
+    #[test]
+    fn test_backfill_windows() {
+        let mut job = BackfillJob::new("pipeline", 100, 350, 100);
+        assert_eq!(windows(&mut job), vec![(100, 200), (200, 300), (300, 350)]);
+        assert_eq!(job.status, BackfillStatus::Completed);
+        assert_eq!(job.next_window(), None);
+
+        let mut job = BackfillJob::new("pipeline", 0, 200, 100);
+        assert_eq!(windows(&mut job), vec![(0, 100), (100, 200)]);
+    }
+
+    #[test]
+    fn test_backfill_resume_after_failure() {
+        let mut job = BackfillJob::new("pipeline", 0, 400, 100);
+        let (start, end) = job.next_window().unwrap();
+        job.complete_window(end);
+        assert_eq!((start, end), (0, 100));
+        assert_eq!(job.status, BackfillStatus::Running);
+
+        // the second window fails, the progress is saved in the trigger data
+        job.error = Some("ingestion failed".to_string());
+        let saved = json::to_string(&job).unwrap();
+
+        // after a restart the job retries the failed window, the same marker
+        // lets the records of a partly ingested window be deduplicated
+        let mut resumed: BackfillJob = json::from_str(&saved).unwrap();
+        assert_eq!(resumed, job);
+        assert_eq!(resumed.next_window(), Some((100, 200)));
+        assert_eq!(resumed.window_marker(100), job.window_marker(100));
+        assert_eq!(
+            windows(&mut resumed),
+            vec![(100, 200), (200, 300), (300, 400)]
+        );
+        assert_eq!(resumed.error, None);
+
+        // a failed job processes no more windows
+        let mut job = BackfillJob::new("pipeline", 0, 400, 100);
+        job.complete_window(100);
+        job.fail("max retries reached".to_string());
+        assert_eq!(job.next_window(), None);
+        assert_eq!(job.processed_until, 100);
+    }
+
+    #[test]
+    fn test_backfill_stops_at_live_schedule() {
+        assert_eq!(
+            clamp_to_live_schedule((100, 500), Some(300), 1000).unwrap(),
+            (100, 300)
+        );
+        assert_eq!(
+            clamp_to_live_schedule((100, 500), None, 400).unwrap(),
+            (100, 400)
+        );
+        assert!(clamp_to_live_schedule((300, 500), Some(300), 1000).is_err());
+        assert!(clamp_to_live_schedule((500, 100), None, 1000).is_err());
+
+        let job = BackfillJob::new("pipeline", 100, 300, 100);
+        assert!(job.overlaps(200, 400));
+        assert!(!job.overlaps(300, 400));
+    }
+}
@@ -13,7 +13,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
@@ -36,6 +39,7 @@ use o2_enterprise::enterprise::pipeline::pipeline_wal_writer::get_pipeline_wal_w
 use once_cell::sync::Lazy;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
+use super::stats::{self, StageInfo, StageStats};
 use crate::{
     common::infra::config::QUERY_FUNCTIONS,
     service::{
@@ -370,6 +374,17 @@ impl ExecutableNode {
     pub fn node_type(&self) -> String {
         self.to_string()
     }
+
+    /// Returns a human readable name of the node for the pipeline stats.
+    pub fn node_name(&self) -> String {
+        match &self.node_data {
+            NodeData::Stream(stream_params) => stream_params.stream_name.to_string(),
+            NodeData::Query(derived_stream) => derived_stream.stream_type.to_string(),
+            NodeData::Function(func_params) => func_params.name.clone(),
+            NodeData::Condition(_) => self.node_type(),
+            NodeData::RemoteStream(remote_stream) => remote_stream.destination_name.to_string(),
+        }
+    }
 }
 
 impl std::fmt::Display for ExecutableNode {
@@ -488,6 +503,16 @@ async fn process_node(
 ) -> Result<()> {
     let cfg = config::get_config();
     let mut count: usize = 0;
+    let stage = StageInfo {
+        index: node_idx,
+        node_id: node.id.to_string(),
+        node_type: node.node_type(),
+        name: node.node_name(),
+    };
+    let mut stage_stats = StageStats {
+        executions: 1,
+        ..Default::default()
+    };
     match &node.node_data {
         NodeData::Stream(stream_params) => {
             if node.children.is_empty() {
@@ -496,6 +521,8 @@ async fn process_node(
                 // send received results directly via `result_sender` for collection
                 let result_sender = result_sender.unwrap();
                 while let Some((idx, mut record, flattened)) = receiver.recv().await {
+                    stage_stats.records_in += 1;
+                    let start = Instant::now();
                     if !flattened {
                         record = match flatten::flatten_with_level(
                             record,
@@ -504,6 +531,8 @@ async fn process_node(
                             Ok(flattened) => flattened,
                             Err(e) => {
                                 let err_msg = format!("LeafNode error with flattening: {}", e);
+                                stage_stats.errors += 1;
+                                stage_stats.add_time(start.elapsed());
                                 if let Err(send_err) = error_sender
                                     .send((node.id.to_string(), node.node_type(), err_msg))
                                     .await
@@ -532,6 +561,8 @@ async fn process_node(
                                         .to_string()
                                 };
                                 log::warn!("{err_msg}");
+                                stage_stats.errors += 1;
+                                stage_stats.add_time(start.elapsed());
                                 if let Err(send_err) = error_sender
                                     .send((node.id.to_string(), node.node_type(), err_msg))
                                     .await
@@ -546,6 +577,7 @@ async fn process_node(
                         }
                     }
 
+                    stage_stats.add_time(start.elapsed());
                     if let Err(send_err) =
                         result_sender.send((idx, destination_stream, record)).await
                     {
@@ -561,6 +593,7 @@ async fn process_node(
                 log::debug!("[Pipeline]: source node {node_idx} starts processing");
                 // source stream node: send received record to all its children
                 while let Some(item) = receiver.recv().await {
+                    stage_stats.records_in += 1;
                     send_to_children(&mut child_senders, item, "StreamNode").await;
                     count += 1;
                 }
@@ -570,6 +603,8 @@ async fn process_node(
         NodeData::Condition(condition_params) => {
             log::debug!("[Pipeline]: cond node {node_idx} starts processing");
             while let Some((idx, mut record, mut flattened)) = receiver.recv().await {
+                stage_stats.records_in += 1;
+                let start = Instant::now();
                 // value must be flattened before condition params can take effect
                 if !flattened {
                     record = match flatten::flatten_with_level(
@@ -579,6 +614,8 @@ async fn process_node(
                         Ok(flattened) => flattened,
                         Err(e) => {
                             let err_msg = format!("ConditionNode error with flattening: {}", e);
+                            stage_stats.errors += 1;
+                            stage_stats.add_time(start.elapsed());
                             if let Err(send_err) = error_sender
                                 .send((node.id.to_string(), node.node_type(), err_msg))
                                 .await
@@ -594,11 +631,12 @@ async fn process_node(
                     flattened = true;
                 }
                 // only send to children when passing all condition evaluations
-                if condition_params
+                let passed = condition_params
                     .conditions
                     .iter()
-                    .all(|cond| cond.evaluate(record.as_object().unwrap()))
-                {
+                    .all(|cond| cond.evaluate(record.as_object().unwrap()));
+                stage_stats.add_time(start.elapsed());
+                if passed {
                     send_to_children(
                         &mut child_senders,
                         (idx, record, flattened),
@@ -614,6 +652,8 @@ async fn process_node(
             log::debug!("[Pipeline]: func node {node_idx} starts processing");
             let mut runtime = crate::service::ingestion::init_functions_runtime();
             while let Some((idx, mut record, mut flattened)) = receiver.recv().await {
+                stage_stats.records_in += 1;
+                let start = Instant::now();
                 if let Some(vrl_runtime) = &vrl_runtime {
                     if func_params.after_flatten && !flattened {
                         record = match flatten::flatten_with_level(
@@ -623,6 +663,8 @@ async fn process_node(
                             Ok(flattened) => flattened,
                             Err(e) => {
                                 let err_msg = format!("FunctionNode error with flattening: {}", e);
+                                stage_stats.errors += 1;
+                                stage_stats.add_time(start.elapsed());
                                 if let Err(send_err) = error_sender
                                     .send((node.id.to_string(), node.node_type(), err_msg))
                                     .await
@@ -646,6 +688,7 @@ async fn process_node(
                         (res, None) => res,
                         (res, Some(error)) => {
                             let err_msg = format!("FunctionNode error: {}", error);
+                            stage_stats.errors += 1;
                             if let Err(send_err) = error_sender
                                 .send((node.id.to_string(), node.node_type(), err_msg))
                                 .await
@@ -660,6 +703,7 @@ async fn process_node(
                     };
                     flattened = false; // since apply_vrl_fn can produce unflattened data
                 }
+                stage_stats.add_time(start.elapsed());
                 send_to_children(&mut child_senders, (idx, record, flattened), "FunctionNode")
                     .await;
                 count += 1;
@@ -670,6 +714,7 @@ async fn process_node(
             // source node for Scheduled pipeline. Directly send to children nodes
             log::debug!("[Pipeline]: query node {node_idx} starts processing");
             while let Some(item) = receiver.recv().await {
+                stage_stats.records_in += 1;
                 send_to_children(&mut child_senders, item, "QueryNode").await;
                 count += 1;
             }
@@ -688,11 +733,18 @@ async fn process_node(
                 records.push(record);
                 count += 1;
             }
+            stage_stats.records_in = count as u64;
 
+            // destination processes the whole batch at once
+            let start = Instant::now();
             let mut remote_stream = remote_stream.clone();
-            remote_stream.org_id = org_id.into();
+            remote_stream.org_id = org_id.clone().into();
             let writer = get_pipeline_wal_writer(&pipeline_id, remote_stream.clone()).await?;
-            if let Err(e) = writer.write_wal(records).await {
+            let write_res = writer.write_wal(records).await;
+            stage_stats.add_time(start.elapsed());
+            if let Err(e) = write_res {
+                stage_stats.errors += 1;
+                count = 0;
                 let err_msg = format!(
                     "DestinationNode error persisting data to be ingested externally: {}",
                     e
@@ -713,6 +765,7 @@ async fn process_node(
         NodeData::RemoteStream(_) => {
             let err_msg = "[Pipeline]: remote destination is not supported in open source version. Records dropped".to_string();
             log::error!("{err_msg}");
+            stage_stats.errors += 1;
             if let Err(send_err) = error_sender
                 .send((node.id.to_string(), node.node_type(), err_msg))
                 .await
@@ -724,6 +777,9 @@ async fn process_node(
         }
     }
 
+    stage_stats.records_out = count as u64;
+    stats::record_stage(&org_id, &pipeline_id, &stage, &stage_stats);
+
     // all cloned senders dropped when function goes out of scope -> close the channel
 
     Ok(())
@@ -873,4 +929,91 @@ mod tests {
         let err1 = resolve_stream_name("{{eulav}}", &record);
         assert!(err1.is_err());
     }
+
+    #[tokio::test]
+    async fn test_process_batch_stage_stats() {
+        let org_id = "default";
+        let stream_node = |id: &str, stream: &str, children: Vec<String>| ExecutableNode {
+            id: id.to_string(),
+            node_data: NodeData::Stream(StreamParams::new(org_id, stream, StreamType::Logs)),
+            children,
+        };
+        let condition = json::from_value::<NodeData>(json::json!({
+            "node_type": "condition",
+            "conditions": [{
+                "column": "level",
+                "operator": "=",
+                "value": "error",
+            }]
+        }))
+        .unwrap();
+        let node_map = HashMap::from([
+            (
+                "source".to_string(),
+                stream_node("source", "app", vec!["cond".to_string()]),
+            ),
+            (
+                "cond".to_string(),
+                ExecutableNode {
+                    id: "cond".to_string(),
+                    node_data: condition,
+                    children: vec!["dest".to_string()],
+                },
+            ),
+            (
+                "dest".to_string(),
+                stream_node("dest", "app_errors", vec![]),
+            ),
+        ]);
+        let sorted_nodes = topological_sort(&node_map).unwrap();
+        assert_eq!(sorted_nodes, vec!["source", "cond", "dest"]);
+        let pipeline = ExecutablePipeline {
+            id: "test_process_batch_stage_stats".to_string(),
+            name: "test".to_string(),
+            source_node_id: sorted_nodes[0].clone(),
+            sorted_nodes,
+            vrl_map: HashMap::new(),
+            node_map,
+        };
+
+        let records = (0..10)
+            .map(|i| {
+                let level = if i % 3 == 0 { "error" } else { "info" };
+                json::json!({ "level": level, "i": i })
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..2 {
+            let results = pipeline
+                .process_batch(org_id, records.clone())
+                .await
+                .unwrap();
+            let dest = StreamParams::new(org_id, "app_errors", StreamType::Logs);
+            assert_eq!(results.get(&dest).map(|r| r.len()), Some(4));
+        }
+
+        let snapshot = stats::get_snapshot(org_id, &pipeline.id).unwrap();
+        let counts = snapshot
+            .stages
+            .iter()
+            .map(|s| {
+                (
+                    s.node_type.as_str(),
+                    s.executions,
+                    s.records_in,
+                    s.records_out,
+                    s.records_dropped,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                ("stream", 2, 20, 20, 0),
+                ("condition", 2, 20, 8, 12),
+                ("stream", 2, 8, 8, 0),
+            ]
+        );
+        assert!(snapshot.stages.iter().all(|s| s.errors == 0));
+        stats::remove(&pipeline.id);
+    }
 }
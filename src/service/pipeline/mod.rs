@@ -25,7 +25,9 @@ use crate::common::{
     utils::auth::{remove_ownership, set_ownership},
 };
 
+pub mod backfill;
 pub mod batch_execution;
+pub mod stats;
 
 #[tracing::instrument(skip(pipeline))]
 pub async fn save_pipeline(mut pipeline: Pipeline) -> Result<(), PipelineError> {
@@ -198,6 +200,31 @@ pub async fn enable_pipeline(
     Ok(())
 }
 
+/// Returns the rolling per-stage execution stats of the pipeline on this node.
+///
+/// Pipelines that exist but have not been executed recently have empty stats.
+#[tracing::instrument]
+pub async fn get_pipeline_stats(
+    org_id: &str,
+    pipeline_id: &str,
+) -> Result<stats::PipelineStatsSnapshot, PipelineError> {
+    let Ok(pipeline) = pipeline::get_by_id(pipeline_id).await else {
+        return Err(PipelineError::NotFound(pipeline_id.to_string()));
+    };
+    if pipeline.org != org_id {
+        return Err(PipelineError::NotFound(pipeline_id.to_string()));
+    }
+    let now = config::utils::time::now_micros();
+    Ok(
+        stats::get_snapshot(org_id, pipeline_id).unwrap_or(stats::PipelineStatsSnapshot {
+            pipeline_id: pipeline_id.to_string(),
+            start_time: now,
+            end_time: now,
+            stages: vec![],
+        }),
+    )
+}
+
 #[tracing::instrument]
 pub async fn delete_pipeline(pipeline_id: &str) -> Result<(), PipelineError> {
     let Ok(existing_pipeline) = pipeline::get_by_id(pipeline_id).await else {
@@ -218,6 +245,10 @@ pub async fn delete_pipeline(pipeline_id: &str) -> Result<(), PipelineError> {
     }
 
     pipeline::delete(pipeline_id).await?;
+    stats::remove(pipeline_id);
+    if let Err(e) = backfill::delete_backfills(&existing_pipeline.org, pipeline_id).await {
+        log::error!("Error deleting backfill jobs of pipeline {pipeline_id}: {e}");
+    }
     remove_ownership(
         &existing_pipeline.org,
         "pipelines",
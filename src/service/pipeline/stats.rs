@@ -0,0 +1,384 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-stage execution stats of ingestion pipelines.
+//!
+//! Every node task of [super::batch_execution::ExecutablePipeline] accumulates
+//! a [StageStats] while processing a batch and reports it once the batch is
+//! done. The stats are exported as Prometheus metrics and kept in memory for a
+//! rolling window so that the stats API can return per-stage averages.
+
+use std::time::Duration;
+
+use config::{metrics, utils::time::now_micros};
+use hashbrown::HashMap;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Length of a single stats window. The snapshot covers the current window and
+/// the previous one, i.e. between one and two windows of history.
+const STATS_WINDOW_MICROS: i64 = 5 * 60 * 1_000_000;
+
+static PIPELINE_STATS: Lazy<RwLock<HashMap<String, PipelineStatsWindows>>> =
+    Lazy::new(Default::default);
+
+/// Identifies a stage (node) of a pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageInfo {
+    pub index: usize,
+    pub node_id: String,
+    pub node_type: String,
+    pub name: String,
+}
+
+/// Counters accumulated by a single stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageStats {
+    /// Number of batches the stage processed.
+    pub executions: u64,
+    /// Time spent executing the stage, excluding the time spent waiting for
+    /// records from upstream stages.
+    pub time_micros: u64,
+    pub errors: u64,
+    pub records_in: u64,
+    pub records_out: u64,
+}
+
+impl StageStats {
+    pub fn add_time(&mut self, elapsed: Duration) {
+        self.time_micros += elapsed.as_micros() as u64;
+    }
+
+    fn merge(&mut self, other: &StageStats) {
+        self.executions += other.executions;
+        self.time_micros += other.time_micros;
+        self.errors += other.errors;
+        self.records_in += other.records_in;
+        self.records_out += other.records_out;
+    }
+}
+
+#[derive(Debug, Default)]
+struct PipelineStatsWindows {
+    org_id: String,
+    window_start: i64,
+    current: HashMap<usize, (StageInfo, StageStats)>,
+    previous: HashMap<usize, (StageInfo, StageStats)>,
+}
+
+impl PipelineStatsWindows {
+    fn rotate(&mut self, now: i64) {
+        if now - self.window_start < STATS_WINDOW_MICROS {
+            return;
+        }
+        if now - self.window_start < 2 * STATS_WINDOW_MICROS {
+            self.previous = std::mem::take(&mut self.current);
+        } else {
+            // nothing was recorded during the last window
+            self.previous.clear();
+            self.current.clear();
+        }
+        self.window_start = now;
+    }
+}
+
+/// Rolling snapshot of the execution stats of a pipeline on this node.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PipelineStatsSnapshot {
+    pub pipeline_id: String,
+    /// Start of the period covered by this snapshot, in microseconds.
+    pub start_time: i64,
+    /// End of the period covered by this snapshot, in microseconds.
+    pub end_time: i64,
+    pub stages: Vec<StageStatsSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct StageStatsSnapshot {
+    pub index: usize,
+    pub node_id: String,
+    pub node_type: String,
+    pub name: String,
+    pub executions: u64,
+    pub time_micros: u64,
+    pub errors: u64,
+    pub records_in: u64,
+    pub records_out: u64,
+    /// Records that entered the stage but were not passed on, e.g. dropped by a
+    /// condition.
+    pub records_dropped: u64,
+    pub avg_time_micros_per_execution: f64,
+    pub avg_time_micros_per_record: f64,
+}
+
+impl StageStatsSnapshot {
+    fn new(info: &StageInfo, stats: &StageStats) -> Self {
+        let avg = |total: u64, count: u64| {
+            if count == 0 {
+                0.0
+            } else {
+                total as f64 / count as f64
+            }
+        };
+        Self {
+            index: info.index,
+            node_id: info.node_id.clone(),
+            node_type: info.node_type.clone(),
+            name: info.name.clone(),
+            executions: stats.executions,
+            time_micros: stats.time_micros,
+            errors: stats.errors,
+            records_in: stats.records_in,
+            records_out: stats.records_out,
+            records_dropped: stats.records_in.saturating_sub(stats.records_out),
+            avg_time_micros_per_execution: avg(stats.time_micros, stats.executions),
+            avg_time_micros_per_record: avg(stats.time_micros, stats.records_in),
+        }
+    }
+}
+
+/// Records the stats of one execution of a pipeline stage.
+///
+/// Updates the Prometheus metrics, the in-memory rolling stats, and logs a
+/// warning when the stage took longer than `ZO_PIPELINE_SLOW_STAGE_THRESHOLD`.
+pub fn record_stage(org_id: &str, pipeline_id: &str, stage: &StageInfo, stats: &StageStats) {
+    let index = stage.index.to_string();
+    let labels = [
+        org_id,
+        pipeline_id,
+        index.as_str(),
+        stage.node_type.as_str(),
+    ];
+    metrics::PIPELINE_STAGE_EXECUTIONS
+        .with_label_values(&labels)
+        .inc_by(stats.executions);
+    metrics::PIPELINE_STAGE_TIME
+        .with_label_values(&labels)
+        .inc_by(stats.time_micros as f64 / 1_000_000.0);
+    metrics::PIPELINE_STAGE_ERRORS
+        .with_label_values(&labels)
+        .inc_by(stats.errors);
+    metrics::PIPELINE_STAGE_RECORDS_IN
+        .with_label_values(&labels)
+        .inc_by(stats.records_in);
+    metrics::PIPELINE_STAGE_RECORDS_OUT
+        .with_label_values(&labels)
+        .inc_by(stats.records_out);
+
+    let threshold = config::get_config().pipeline.slow_stage_threshold;
+    if threshold > 0 && stats.time_micros > threshold * 1000 {
+        log::warn!(
+            "[Pipeline({pipeline_id})]: slow stage {} ({} {}) took {} ms for {} records",
+            stage.index,
+            stage.node_type,
+            stage.name,
+            stats.time_micros / 1000,
+            stats.records_in,
+        );
+    }
+
+    accumulate(org_id, pipeline_id, stage, stats, now_micros());
+}
+
+fn accumulate(org_id: &str, pipeline_id: &str, stage: &StageInfo, stats: &StageStats, now: i64) {
+    let mut w = PIPELINE_STATS.write();
+    let entry = w
+        .entry(pipeline_id.to_string())
+        .or_insert_with(|| PipelineStatsWindows {
+            org_id: org_id.to_string(),
+            window_start: now,
+            ..Default::default()
+        });
+    entry.rotate(now);
+    let (info, acc) = entry
+        .current
+        .entry(stage.index)
+        .or_insert_with(|| (stage.clone(), StageStats::default()));
+    if info != stage {
+        // the pipeline was updated and the stage at this index changed
+        *info = stage.clone();
+        *acc = StageStats::default();
+    }
+    acc.merge(stats);
+}
+
+/// Returns the rolling stats snapshot of the pipeline, if it was executed on
+/// this node recently.
+pub fn get_snapshot(org_id: &str, pipeline_id: &str) -> Option<PipelineStatsSnapshot> {
+    snapshot_at(org_id, pipeline_id, now_micros())
+}
+
+fn snapshot_at(org_id: &str, pipeline_id: &str, now: i64) -> Option<PipelineStatsSnapshot> {
+    let r = PIPELINE_STATS.read();
+    let entry = r.get(pipeline_id).filter(|e| e.org_id == org_id)?;
+    if now - entry.window_start >= 2 * STATS_WINDOW_MICROS {
+        return None;
+    }
+
+    let mut stages: HashMap<usize, (StageInfo, StageStats)> = entry.current.clone();
+    let mut start_time = entry.window_start;
+    if now - entry.window_start < STATS_WINDOW_MICROS {
+        for (index, (info, stats)) in entry.previous.iter() {
+            match stages.get_mut(index) {
+                Some((cur_info, cur_stats)) if cur_info == info => cur_stats.merge(stats),
+                Some(_) => {}
+                None => {
+                    stages.insert(*index, (info.clone(), *stats));
+                }
+            }
+        }
+        if !entry.previous.is_empty() {
+            start_time -= STATS_WINDOW_MICROS;
+        }
+    }
+
+    let mut stages = stages
+        .values()
+        .map(|(info, stats)| StageStatsSnapshot::new(info, stats))
+        .collect::<Vec<_>>();
+    stages.sort_by_key(|s| s.index);
+    Some(PipelineStatsSnapshot {
+        pipeline_id: pipeline_id.to_string(),
+        start_time,
+        end_time: now,
+        stages,
+    })
+}
+
+/// Removes the in-memory stats of the pipeline.
+pub fn remove(pipeline_id: &str) {
+    PIPELINE_STATS.write().remove(pipeline_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(index: usize, node_type: &str) -> StageInfo {
+        StageInfo {
+            index,
+            node_id: format!("node_{index}"),
+            node_type: node_type.to_string(),
+            name: format!("{node_type}_{index}"),
+        }
+    }
+
+    fn stats(time_micros: u64, errors: u64, records_in: u64, records_out: u64) -> StageStats {
+        StageStats {
+            executions: 1,
+            time_micros,
+            errors,
+            records_in,
+            records_out,
+        }
+    }
+
+    #[test]
+    fn test_stats_accumulation() {
+        let (org_id, pipeline_id) = ("default", "test_stats_accumulation");
+        let now = now_micros();
+        // source -> condition (drops some) -> function -> destination
+        let stages = [
+            stage(0, "stream"),
+            stage(1, "condition"),
+            stage(2, "function"),
+            stage(3, "stream"),
+        ];
+        for (records_in, passed, time) in [(10, 6, 100), (20, 11, 300)] {
+            accumulate(
+                org_id,
+                pipeline_id,
+                &stages[0],
+                &stats(0, 0, records_in, records_in),
+                now,
+            );
+            accumulate(
+                org_id,
+                pipeline_id,
+                &stages[1],
+                &stats(time, 0, records_in, passed),
+                now,
+            );
+            accumulate(
+                org_id,
+                pipeline_id,
+                &stages[2],
+                &stats(time, 1, passed, passed),
+                now,
+            );
+            accumulate(
+                org_id,
+                pipeline_id,
+                &stages[3],
+                &stats(0, 0, passed, passed),
+                now,
+            );
+        }
+
+        let snapshot = snapshot_at(org_id, pipeline_id, now).unwrap();
+        assert_eq!(snapshot.stages.len(), 4);
+
+        let source = &snapshot.stages[0];
+        assert_eq!((source.records_in, source.records_out), (30, 30));
+        assert_eq!(source.records_dropped, 0);
+
+        let condition = &snapshot.stages[1];
+        assert_eq!(condition.executions, 2);
+        assert_eq!((condition.records_in, condition.records_out), (30, 17));
+        assert_eq!(condition.records_dropped, 13);
+        assert_eq!(condition.time_micros, 400);
+        assert_eq!(condition.avg_time_micros_per_execution, 200.0);
+
+        let function = &snapshot.stages[2];
+        assert_eq!(function.errors, 2);
+        assert_eq!(function.records_in, condition.records_out);
+        assert_eq!(function.records_out, 17);
+
+        let destination = &snapshot.stages[3];
+        assert_eq!(destination.records_out, 17);
+
+        // unknown org must not see the stats
+        assert!(snapshot_at("other", pipeline_id, now).is_none());
+        remove(pipeline_id);
+        assert!(snapshot_at(org_id, pipeline_id, now).is_none());
+    }
+
+    #[test]
+    fn test_stats_rolling_window() {
+        let (org_id, pipeline_id) = ("default", "test_stats_rolling_window");
+        let start = now_micros();
+        let s = stage(0, "function");
+        accumulate(org_id, pipeline_id, &s, &stats(10, 0, 5, 5), start);
+        // next window keeps the previous one in the snapshot
+        let next = start + STATS_WINDOW_MICROS;
+        accumulate(org_id, pipeline_id, &s, &stats(10, 0, 5, 5), next);
+        let snapshot = snapshot_at(org_id, pipeline_id, next).unwrap();
+        assert_eq!(snapshot.stages[0].records_in, 10);
+        assert_eq!(snapshot.start_time, start);
+
+        // the oldest window falls out of the snapshot
+        let later = next + STATS_WINDOW_MICROS;
+        accumulate(org_id, pipeline_id, &s, &stats(10, 0, 5, 5), later);
+        let snapshot = snapshot_at(org_id, pipeline_id, later).unwrap();
+        assert_eq!(snapshot.stages[0].records_in, 10);
+        assert_eq!(snapshot.stages[0].avg_time_micros_per_record, 2.0);
+
+        // idle pipelines have no stats
+        assert!(snapshot_at(org_id, pipeline_id, later + 2 * STATS_WINDOW_MICROS).is_none());
+        remove(pipeline_id);
+    }
+}
@@ -32,9 +32,12 @@ use hashbrown::HashMap;
 use infra::errors::{Error, Result};
 use once_cell::sync::Lazy;
 use prost::Message;
+use serde::Serialize;
 use tokio::sync::RwLock;
+use utoipa::ToSchema;
 
 use super::{RangeValue, Value};
+use crate::common::utils::compression::{compress_cache_data, decompress_cache_data};
 
 const METRICS_INDEX_CACHE_GC_TRIGGER_NUM: usize = 10;
 const METRICS_INDEX_CACHE_GC_PERCENT: usize = 10; // 10% of the items will be removed
@@ -90,6 +93,7 @@ pub async fn init() -> Result<()> {
 /// This function will return the samples from the cache if the samples are found.
 /// If the samples are not found, it will return None.
 pub async fn get(
+    org_id: &str,
     query: &str,
     start: i64,
     end: i64,
@@ -110,6 +114,9 @@ pub async fn get(
         );
         return Ok(None);
     }
+    if !index.org_id.is_empty() && index.org_id != org_id {
+        return Ok(None); // cached for another org
+    }
 
     // get the best key
     let mut best_key = String::new();
@@ -142,6 +149,13 @@ pub async fn get(
         drop(w);
         return Ok(None);
     };
+    let data = match decompress_cache_data(data) {
+        Ok(data) => data,
+        Err(e) => {
+            log::error!("decompress metrics query response error: {}", e);
+            return Ok(None);
+        }
+    };
     let mut resp = match proto::cluster_rpc::MetricsQueryResponse::decode(data) {
         Ok(resp) => resp,
         Err(e) => {
@@ -216,6 +230,7 @@ pub async fn get(
 
 pub async fn set(
     trace_id: &str,
+    org_id: &str,
     query: &str,
     start: i64,
     end: i64,
@@ -244,6 +259,9 @@ pub async fn set(
             );
             return Ok(());
         }
+        if !index.org_id.is_empty() && index.org_id != org_id {
+            return Ok(()); // cached for another org
+        }
         // check if the cache already converted
         if index
             .entries
@@ -332,7 +350,8 @@ pub async fn set(
 
     // store the series to disk cache
     let cache_key = get_cache_item_key(&key, start, new_end);
-    infra::cache::file_data::disk::set(trace_id, &cache_key, bytes_data.into())
+    let bytes_data = compress_cache_data(bytes_data.into());
+    infra::cache::file_data::disk::set(trace_id, &cache_key, bytes_data)
         .await
         .map_err(|e| Error::Message(e.to_string()))?;
 
@@ -340,7 +359,10 @@ pub async fn set(
     let cache_item = MetricsIndexCacheItem::new(&cache_key, start, new_end);
     let mut w = GLOBAL_CACHE[bucket_id].write().await;
     w.cacher.push_back(key.to_string());
-    let index = w.data.entry(key).or_insert(MetricsIndexCache::new(query));
+    let index = w
+        .data
+        .entry(key)
+        .or_insert(MetricsIndexCache::new(org_id, query));
     if index.entries.len() >= METRICS_INDEX_CACHE_MAX_ITEMS {
         // remove the first half items
         index.entries.drain(0..METRICS_INDEX_CACHE_MAX_ITEMS / 2);
@@ -364,13 +386,184 @@ pub async fn load(cache_key: &str) -> Result<()> {
     let cache_item = MetricsIndexCacheItem::new(cache_key, start, end);
     let mut w = GLOBAL_CACHE[bucket_id].write().await;
     w.cacher.push_back(key.to_string());
-    let index = w.data.entry(key).or_insert(MetricsIndexCache::new(""));
+    let index = w.data.entry(key).or_insert(MetricsIndexCache::new("", ""));
     index.entries.push(Arc::new(cache_item));
     drop(w);
 
     Ok(())
 }
 
+/// Summary of the metrics result cache of this node.
+#[derive(Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct CacheStatus {
+    /// number of cached entries in each bucket
+    pub buckets: Vec<usize>,
+    pub total_entries: usize,
+    /// size of the cached results on disk
+    pub total_bytes: usize,
+    /// queries with the most cached entries
+    pub top_queries: Vec<CachedQuery>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct CachedQuery {
+    pub query_hash: String,
+    /// empty for entries loaded from the disk cache on start up
+    pub org_id: String,
+    /// empty for entries loaded from the disk cache on start up
+    pub query: String,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// Returns the cache status of the queries of `org_id`, or of every org if
+/// `None`, with the `top` queries with most entries.
+pub async fn status(org_id: Option<&str>, top: usize) -> CacheStatus {
+    buckets_status(&GLOBAL_CACHE, org_id, top).await
+}
+
+async fn buckets_status(
+    buckets: &[RwLock<MetricsIndex>],
+    org_id: Option<&str>,
+    top: usize,
+) -> CacheStatus {
+    let mut status = CacheStatus {
+        buckets: Vec::with_capacity(buckets.len()),
+        ..Default::default()
+    };
+    let mut queries = Vec::new();
+    for bucket in buckets.iter() {
+        // copy the keys out so the bucket isn't locked while reading the file sizes
+        let r = bucket.read().await;
+        let items = r
+            .data
+            .iter()
+            .filter(|(_, index)| index.belongs_to(org_id))
+            .map(|(key, index)| {
+                let files = index
+                    .entries
+                    .iter()
+                    .map(|entry| entry.key.clone())
+                    .collect::<Vec<_>>();
+                (
+                    key.clone(),
+                    index.org_id.clone(),
+                    index.query.clone(),
+                    files,
+                )
+            })
+            .collect::<Vec<_>>();
+        drop(r);
+
+        let mut bucket_entries = 0;
+        for (query_hash, query_org_id, query, files) in items {
+            let mut bytes = 0;
+            for file in files.iter() {
+                bytes += infra::cache::file_data::disk::get_size(file)
+                    .await
+                    .unwrap_or_default();
+            }
+            bucket_entries += files.len();
+            status.total_bytes += bytes;
+            queries.push(CachedQuery {
+                query_hash,
+                org_id: query_org_id,
+                query,
+                entries: files.len(),
+                bytes,
+            });
+        }
+        status.buckets.push(bucket_entries);
+        status.total_entries += bucket_entries;
+    }
+    queries.sort_by(|a, b| {
+        b.entries
+            .cmp(&a.entries)
+            .then_with(|| a.query_hash.cmp(&b.query_hash))
+    });
+    queries.truncate(top);
+    status.top_queries = queries;
+    status
+}
+
+/// Removes the cached results of the query with hash key `query_hash` of
+/// `org_id`, or of any org if `None`, and returns the number of entries
+/// removed.
+pub async fn delete_by_hash(
+    trace_id: &str,
+    org_id: Option<&str>,
+    query_hash: &str,
+) -> Result<usize> {
+    remove_keys(
+        trace_id,
+        org_id,
+        get_bucket_id(query_hash),
+        &[query_hash.to_string()],
+    )
+    .await
+}
+
+/// Removes the cached results of `query` of `org_id`, or of any org if
+/// `None`, for the given `step` or for every step if `None`, and returns the
+/// number of entries removed.
+pub async fn delete_by_query(
+    trace_id: &str,
+    org_id: Option<&str>,
+    query: &str,
+    step: Option<i64>,
+) -> Result<usize> {
+    if let Some(step) = step {
+        return delete_by_hash(trace_id, org_id, &get_hash_key(query, step)).await;
+    }
+    let mut removed = 0;
+    for (bucket_id, bucket) in GLOBAL_CACHE.iter().enumerate() {
+        let r = bucket.read().await;
+        let keys = r
+            .data
+            .iter()
+            .filter(|(_, index)| index.query == query && index.belongs_to(org_id))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        drop(r);
+        if !keys.is_empty() {
+            removed += remove_keys(trace_id, org_id, bucket_id, &keys).await?;
+        }
+    }
+    Ok(removed)
+}
+
+async fn remove_keys(
+    trace_id: &str,
+    org_id: Option<&str>,
+    bucket_id: usize,
+    keys: &[String],
+) -> Result<usize> {
+    let mut w = GLOBAL_CACHE[bucket_id].write().await;
+    let keys = keys
+        .iter()
+        .filter(|key| {
+            w.data
+                .get(*key)
+                .is_some_and(|index| index.belongs_to(org_id))
+        })
+        .collect::<Vec<_>>();
+    let mut files = Vec::new();
+    for key in keys.iter() {
+        if let Some(index) = w.data.remove(*key) {
+            files.extend(index.entries.iter().map(|entry| entry.key.clone()));
+        }
+    }
+    w.cacher.retain(|key| !keys.contains(&key));
+    drop(w);
+
+    for file in files.iter() {
+        infra::cache::file_data::disk::remove(trace_id, file)
+            .await
+            .map_err(|e| Error::Message(e.to_string()))?;
+    }
+    Ok(files.len())
+}
+
 async fn gc(bucket_id: usize) -> Result<()> {
     log::warn!("MetricsIndexCache is full, releasing 10% of the cache");
     let mut w = GLOBAL_CACHE[bucket_id].write().await;
@@ -446,17 +639,24 @@ impl MetricsIndex {
 }
 
 struct MetricsIndexCache {
+    org_id: String, // empty for the entries loaded from disk
     query: String,
     entries: Vec<Arc<MetricsIndexCacheItem>>,
 }
 
 impl MetricsIndexCache {
-    fn new(query: &str) -> Self {
+    fn new(org_id: &str, query: &str) -> Self {
         Self {
+            org_id: org_id.to_string(),
             query: query.to_string(),
             entries: Vec::new(),
         }
     }
+
+    /// Whether the index is of `org_id`, `None` matches every org
+    fn belongs_to(&self, org_id: Option<&str>) -> bool {
+        org_id.is_none_or(|org_id| self.org_id == org_id)
+    }
 }
 
 struct MetricsIndexCacheItem {
@@ -536,11 +736,11 @@ mod tests {
         let expected_value = range_values.first().unwrap().clone();
 
         // Test setting cache
-        let set_result = set(trace_id, query, start, end, step, range_values).await;
+        let set_result = set(trace_id, "default", query, start, end, step, range_values).await;
         assert!(set_result.is_ok());
 
         // Test getting cache
-        let get_result = get(query, start, end, step).await;
+        let get_result = get("default", query, start, end, step).await;
         assert!(get_result.is_ok());
 
         if let Ok(Some((new_start, cached_range_values))) = get_result {
@@ -577,7 +777,16 @@ mod tests {
                 time_window: None,
             }];
 
-            let set_result = set(trace_id, query, start, end, step, range_values.clone()).await;
+            let set_result = set(
+                trace_id,
+                "default",
+                query,
+                start,
+                end,
+                step,
+                range_values.clone(),
+            )
+            .await;
             assert!(set_result.is_ok());
         }
 
@@ -616,4 +825,160 @@ mod tests {
             assert!(parse_cache_item_key(invalid_key).is_none());
         }
     }
+
+    #[tokio::test]
+    async fn test_promql_cache_status_empty() {
+        let buckets = (0..3)
+            .map(|_| RwLock::new(MetricsIndex::new(10)))
+            .collect::<Vec<_>>();
+        let status = buckets_status(&buckets, None, 10).await;
+        assert_eq!(
+            status,
+            CacheStatus {
+                buckets: vec![0, 0, 0],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promql_cache_delete_query() {
+        let trace_id = "test_trace3";
+        let end = now_micros();
+        let start = end - second_micros(3600);
+        let step = second_micros(15);
+        let (start, end) = adjust_start_end(start, end, step, false);
+        let range_values = vec![RangeValue {
+            labels: Labels::new(),
+            samples: (0..((end - start) / step))
+                .map(|i| Sample {
+                    timestamp: start + step * i,
+                    value: i as f64,
+                })
+                .collect(),
+            exemplars: None,
+            time_window: None,
+        }];
+        for query in ["test_query3", "test_query4"] {
+            set(
+                trace_id,
+                "default",
+                query,
+                start,
+                end,
+                step,
+                range_values.clone(),
+            )
+            .await
+            .unwrap();
+            assert!(get("default", query, start, end, step)
+                .await
+                .unwrap()
+                .is_some());
+        }
+
+        let removed = delete_by_query(trace_id, Some("default"), "test_query3", Some(step))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert!(get("default", "test_query3", start, end, step)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(get("default", "test_query4", start, end, step)
+            .await
+            .unwrap()
+            .is_some());
+        let key = get_hash_key("test_query3", step);
+        let r = GLOBAL_CACHE[get_bucket_id(&key)].read().await;
+        assert!(!r.data.contains_key(&key));
+        assert!(!r.cacher.contains(&key));
+        drop(r);
+
+        // without a step every cached step of the query is removed
+        let removed = delete_by_query(trace_id, None, "test_query4", None)
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert!(get("default", "test_query4", start, end, step)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            delete_by_hash(trace_id, None, &get_hash_key("test_query4", step))
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promql_cache_scoped_to_org() {
+        let trace_id = "test_trace4";
+        let query = "test_query5";
+        let end = now_micros();
+        let start = end - second_micros(3600);
+        let step = second_micros(15);
+        let (start, end) = adjust_start_end(start, end, step, false);
+        let range_values = vec![RangeValue {
+            labels: Labels::new(),
+            samples: (0..((end - start) / step))
+                .map(|i| Sample {
+                    timestamp: start + step * i,
+                    value: i as f64,
+                })
+                .collect(),
+            exemplars: None,
+            time_window: None,
+        }];
+        set(trace_id, "org1", query, start, end, step, range_values)
+            .await
+            .unwrap();
+        let query_hash = get_hash_key(query, step);
+
+        // other orgs neither read nor see the results of org1
+        assert!(get("org2", query, start, end, step)
+            .await
+            .unwrap()
+            .is_none());
+        let status = status(Some("org2"), 1000).await;
+        assert!(status
+            .top_queries
+            .iter()
+            .all(|q| q.query_hash != query_hash));
+        let status = status_of("org1", &query_hash).await;
+        assert_eq!(status.map(|q| q.org_id), Some("org1".to_string()));
+
+        // nor remove them
+        assert_eq!(
+            delete_by_hash(trace_id, Some("org2"), &query_hash)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_by_query(trace_id, Some("org2"), query, None)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(get("org1", query, start, end, step)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            delete_by_hash(trace_id, Some("org1"), &query_hash)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    async fn status_of(org_id: &str, query_hash: &str) -> Option<CachedQuery> {
+        status(Some(org_id), 1000)
+            .await
+            .top_queries
+            .into_iter()
+            .find(|q| q.query_hash == query_hash)
+    }
 }
@@ -176,6 +176,7 @@ async fn get_wal_batches(
             plan: vec![],          // set in RemoteScanNode
             file_id_list: vec![],  // not needed for wal
             idx_file_list: vec![], // not needed for wal
+            seam_files: vec![],    // not needed for wal
             start_time: time_range.0,
             end_time: time_range.1,
             timeout: cfg.limit.query_timeout as u64,
@@ -50,7 +50,7 @@ use crate::{
     },
 };
 
-mod cache;
+pub mod cache;
 pub mod grpc;
 
 pub async fn init() -> Result<()> {
@@ -141,7 +141,7 @@ async fn search_in_cluster(
             .with_label_values(&[])
             .inc();
         let start_time = std::time::Instant::now();
-        match cache::get(query, start, end, step).await {
+        match cache::get(&req.org_id, query, start, end, step).await {
             Ok(Some((new_start, values))) => {
                 let took = start_time.elapsed().as_millis() as i32;
                 config::metrics::QUERY_METRICS_CACHE_HITS
@@ -358,8 +358,16 @@ async fn search_in_cluster(
     // cache the result
     if !cache_disabled {
         if let Some(matrix) = values.get_ref_matrix_values() {
-            if let Err(err) =
-                cache::set(trace_id, query, original_start, end, step, matrix.to_vec()).await
+            if let Err(err) = cache::set(
+                trace_id,
+                &req.org_id,
+                query,
+                original_start,
+                end,
+                step,
+                matrix.to_vec(),
+            )
+            .await
             {
                 log::error!(
                     "[trace_id {trace_id}] promql->search->cache: set cache err: {:?}",
@@ -36,7 +36,11 @@ use serde_json::{Map, Value};
 use super::logs::bulk::SCHEMA_CONFORMANCE_FAILED;
 use crate::{
     common::meta::{authz::Authz, ingestion::StreamSchemaChk, stream::SchemaEvolution},
-    service::db,
+    service::{
+        alerts::schema_drift,
+        db,
+        metadata::{self, schema_evolution::SchemaEvolutionItem, MetadataItem, MetadataType},
+    },
 };
 
 pub(crate) fn get_upto_discard_error() -> anyhow::Error {
@@ -65,14 +69,15 @@ pub async fn check_for_schema(
     record_ts: i64,
 ) -> Result<(SchemaEvolution, Option<Schema>)> {
     if !stream_schema_map.contains_key(stream_name) {
-        let schema = infra::schema::get_cache(org_id, stream_name, stream_type).await?;
+        let schema =
+            infra::schema::get_cache_for_ingestion(org_id, stream_name, stream_type).await?;
         stream_schema_map.insert(stream_name.to_string(), schema);
     }
     let cfg = get_config();
     let schema = stream_schema_map.get(stream_name).unwrap();
 
     // get infer schema
-    let value_iter = record_vals.into_iter();
+    let value_iter = record_vals.iter().copied();
     let inferred_schema = infer_json_schema_from_map(value_iter, stream_type)?;
 
     // fast path
@@ -151,6 +156,7 @@ pub async fn check_for_schema(
         stream_type,
         is_new,
         &inferred_schema,
+        &record_vals,
         record_ts,
         stream_schema_map,
     )
@@ -168,6 +174,7 @@ pub async fn check_for_schema(
             stream_type,
             is_new,
             &inferred_schema,
+            &record_vals,
             chrono::Utc::now().timestamp_micros(),
             stream_schema_map,
         )
@@ -213,6 +220,7 @@ async fn handle_diff_schema(
     stream_type: StreamType,
     is_new: bool,
     inferred_schema: &Schema,
+    records: &[&Map<String, Value>],
     record_ts: i64,
     stream_schema_map: &mut HashMap<String, SchemaCache>,
 ) -> Result<Option<SchemaEvolution>> {
@@ -234,12 +242,13 @@ async fn handle_diff_schema(
 
     // check if the schema has been updated by another thread
     let read_cache = STREAM_SCHEMAS_LATEST.read().await;
-    if let Some(updated_schema) = read_cache.get(&cache_key) {
+    let latest_schema = read_cache.get(&cache_key).cloned();
+    drop(read_cache);
+    if let Some(updated_schema) = latest_schema.as_ref() {
         if let (false, _) = get_schema_changes(updated_schema, inferred_schema) {
             return Ok(None);
         }
     }
-    drop(read_cache);
 
     // first update thread cache
     if is_new {
@@ -380,6 +389,44 @@ async fn handle_diff_schema(
     let mut w = STREAM_SCHEMAS_LATEST.write().await;
     w.insert(cache_key.clone(), final_schema.clone());
     drop(w);
+
+    // record the change into the schema evolution audit stream
+    if cfg.common.schema_evolution_audit_enabled {
+        let empty_schema = Schema::empty();
+        let old_schema = latest_schema
+            .as_ref()
+            .map_or(&empty_schema, |s| s.schema().as_ref());
+        if let Some(item) = SchemaEvolutionItem::new(
+            stream_type,
+            stream_name,
+            old_schema,
+            final_schema.schema(),
+            records,
+            record_ts,
+        ) {
+            if let Err(e) = metadata::write(
+                org_id,
+                MetadataType::SchemaEvolution,
+                vec![MetadataItem::SchemaEvolution(item)],
+            )
+            .await
+            {
+                log::error!(
+                    "[SCHEMA_EVOLUTION] audit [{}/{}/{}] error: {}",
+                    org_id,
+                    stream_type,
+                    stream_name,
+                    e
+                );
+            }
+        }
+    }
+
+    // notify the schema drift alerts, a new stream has nothing to drift from
+    if let Some(latest_schema) = latest_schema {
+        let drift = schema_drift::diff_schema(latest_schema.schema(), final_schema.schema());
+        schema_drift::emit(org_id, stream_type, stream_name, drift, record_ts);
+    }
     let need_original = stream_setting.store_original_data;
     if need_original {
         if let dashmap::Entry::Vacant(entry) = STREAM_RECORD_ID_GENERATOR.entry(cache_key.clone()) {
@@ -519,9 +566,10 @@ pub async fn stream_schema_exists(
     let schema = match stream_schema_map.get(stream_name) {
         Some(schema) => schema.schema().clone(),
         None => {
-            let schema_cache = infra::schema::get_cache(org_id, stream_name, stream_type)
-                .await
-                .unwrap();
+            let schema_cache =
+                infra::schema::get_cache_for_ingestion(org_id, stream_name, stream_type)
+                    .await
+                    .unwrap();
             let db_schema = schema_cache.schema().clone();
             stream_schema_map.insert(stream_name.to_string(), schema_cache);
             db_schema
@@ -580,6 +628,65 @@ mod tests {
         assert!(!result.is_schema_changed);
     }
 
+    #[tokio::test]
+    async fn test_check_for_schema_audits_each_evolution_once() {
+        infra::db::create_table().await.unwrap();
+        let org_id = "schema_evolution_audit";
+        let stream_name = "audited";
+        let mut map: HashMap<String, SchemaCache> = HashMap::new();
+        let records = [
+            json::json!({"city": "Athens", "trace_id": "t0", "_timestamp": 1714857600000000i64}),
+            json::json!({"city": "Paris", "trace_id": "t1", "_timestamp": 1714857600000001i64}),
+        ];
+        let new_records = [
+            json::json!({"city": "Rome", "trace_id": "t2", "_timestamp": 1714857600000002i64}),
+            json::json!({"city": "Oslo", "year": 1952, "trace_id": "t3", "_timestamp": 1714857600000003i64}),
+            json::json!({"city": "Rome", "year": 1960, "trace_id": "t4", "_timestamp": 1714857600000004i64}),
+        ];
+
+        // the stream creation and the new field are one evolution each, however
+        // many records bring them
+        for batch in [&records[..], &new_records[..], &new_records[..]] {
+            for record in batch {
+                check_for_schema(
+                    org_id,
+                    stream_name,
+                    StreamType::Logs,
+                    &mut map,
+                    vec![record.as_object().unwrap()],
+                    1714857600000000,
+                )
+                .await
+                .unwrap();
+            }
+            check_for_schema(
+                org_id,
+                stream_name,
+                StreamType::Logs,
+                &mut map,
+                batch.iter().map(|r| r.as_object().unwrap()).collect(),
+                1714857600000000,
+            )
+            .await
+            .unwrap();
+        }
+
+        let items = metadata::schema_evolution::INSTANCE
+            .pending(org_id)
+            .await
+            .into_iter()
+            .filter(|item| item.stream_name == stream_name)
+            .collect::<Vec<_>>();
+        assert_eq!(items.len(), 2);
+        let mut created = items[0].fields_added.split(',').collect::<Vec<_>>();
+        created.sort();
+        assert_eq!(created, vec!["_timestamp", "city", "trace_id"]);
+        assert_eq!(items[0].trace_id, "t0");
+        assert_eq!(items[1].fields_added, "year");
+        assert_eq!(items[1].fields_removed, "");
+        assert_eq!(items[1].trace_id, "t3");
+    }
+
     #[tokio::test]
     async fn test_infer_schema() {
         let mut record_val: Vec<&Map<String, Value>> = vec![];
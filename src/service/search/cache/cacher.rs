@@ -25,16 +25,33 @@ use infra::cache::{
     file_data::disk::{self, QUERY_RESULT_CACHE},
     meta::ResultCacheMeta,
 };
-use proto::cluster_rpc::SearchQuery;
+use sqlparser::{
+    ast::{GroupByExpr, SetExpr, Statement, TableFactor},
+    dialect::PostgreSqlDialect,
+    parser::Parser,
+};
 
 use crate::{
-    common::meta::search::{CacheQueryRequest, CachedQueryResponse, QueryDelta},
+    common::{
+        meta::search::{
+            CacheDisqualification, CacheQueryRequest, CachedQueryResponse, QueryDelta,
+            ResultCacheSelectionStrategy,
+        },
+        utils::compression::{compress_cache_data, decompress_cache_data},
+    },
     service::search::{
         cache::{
-            result_utils::{get_ts_value, round_down_to_nearest_minute},
+            multi::{is_in_discard_window, select_best_meta},
+            result_utils::{
+                get_ts_value, histogram_bucket_start, next_histogram_bucket_start,
+                round_down_to_nearest_minute,
+            },
             MultiCachedQueryResponse,
         },
-        sql::{generate_histogram_interval, Sql, RE_HISTOGRAM, RE_SELECT_FROM},
+        sql::{
+            convert_histogram_interval_to_seconds, generate_histogram_interval, Sql, RE_HISTOGRAM,
+            RE_SELECT_FROM,
+        },
     },
 };
 
@@ -96,8 +113,7 @@ pub async fn check_cache(
 ) -> MultiCachedQueryResponse {
     let start = std::time::Instant::now();
 
-    let query: SearchQuery = req.query.clone().into();
-    let sql = match Sql::new(&query, org_id, stream_type).await {
+    let sql = match Sql::new(&req.query, org_id, stream_type).await {
         Ok(v) => v,
         Err(e) => {
             log::error!("Error parsing sql: {:?}", e);
@@ -105,80 +121,21 @@ pub async fn check_cache(
         }
     };
 
-    // skip the queries with no timestamp column
-    let ts_result = get_ts_col_order_by(&sql, TIMESTAMP_COL_NAME, is_aggregate);
-    let mut result_ts_col = ts_result.map(|(ts_col, _)| ts_col);
-    if result_ts_col.is_none() && (is_aggregate || !sql.group_by.is_empty()) {
-        return MultiCachedQueryResponse::default();
-    }
-
-    // skip the count queries & queries first order by is not _timestamp field
-    let order_by = sql.order_by;
-    if req.query.track_total_hits
-        || (!order_by.is_empty()
-            && order_by.first().as_ref().unwrap().0 != TIMESTAMP_COL_NAME
-            && (result_ts_col.is_none()
-                || (result_ts_col.is_some()
-                    && result_ts_col.as_ref().unwrap() != &order_by.first().as_ref().unwrap().0)))
-    {
-        return MultiCachedQueryResponse::default();
-    }
-
-    // Hack select for _timestamp
-    if !is_aggregate && sql.group_by.is_empty() && order_by.is_empty() && !origin_sql.contains('*')
-    {
-        let caps = RE_SELECT_FROM.captures(origin_sql.as_str()).unwrap();
-        let cap_str = caps.get(1).unwrap().as_str();
-        if !cap_str.contains(TIMESTAMP_COL_NAME) {
-            *origin_sql =
-                origin_sql.replacen(cap_str, &format!("{}, {}", TIMESTAMP_COL_NAME, cap_str), 1);
-        }
-        req.query.sql = origin_sql.clone();
-        result_ts_col = Some(TIMESTAMP_COL_NAME.to_string());
-    }
-    if !is_aggregate && origin_sql.contains('*') {
-        result_ts_col = Some(TIMESTAMP_COL_NAME.to_string());
-    }
-
-    let result_ts_col = result_ts_col.unwrap();
-    let mut discard_interval = -1;
-    if let Some(interval) = sql.histogram_interval {
-        *file_path = format!("{}_{}_{}", file_path, interval, result_ts_col);
-
-        let mut req_time_range = (req.query.start_time, req.query.end_time);
-        if req_time_range.1 == 0 {
-            req_time_range.1 = chrono::Utc::now().timestamp_micros();
+    let CacheQueryPlan {
+        ts_column: result_ts_col,
+        is_descending,
+        discard_interval,
+        is_count,
+        calendar_months,
+    } = match plan_cache_query(&sql, req, origin_sql, file_path, is_aggregate) {
+        Ok(v) => v,
+        Err(e) => {
+            log::debug!("[trace_id {trace_id}] query is not cacheable: {:?}", e);
+            return MultiCachedQueryResponse::default();
         }
-
-        let meta_time_range_is_empty = sql.time_range.is_none() || sql.time_range == Some((0, 0));
-        let q_time_range =
-            if meta_time_range_is_empty && (req_time_range.0 > 0 || req_time_range.1 > 0) {
-                Some(req_time_range)
-            } else {
-                sql.time_range
-            };
-        handle_histogram(origin_sql, q_time_range);
-        req.query.sql = origin_sql.clone();
-        discard_interval = interval * 1000 * 1000; // in microseconds
-    }
-    // if req.query.size >= 0 {
-    //     *file_path = format!("{}", file_path, req.query.from, req.query.size);
-    // }
+    };
     let query_key = file_path.replace('/', "_");
 
-    let mut is_descending = true;
-
-    if !order_by.is_empty() {
-        for (field, order) in &order_by {
-            if field.eq(&result_ts_col) || field.replace("\"", "").eq(&result_ts_col) {
-                is_descending = order == &OrderBy::Desc;
-                break;
-            }
-        }
-    }
-    if is_aggregate && order_by.is_empty() && result_ts_col.is_empty() {
-        return MultiCachedQueryResponse::default();
-    }
     let mut multi_resp = MultiCachedQueryResponse::default();
     if discard_interval > -1 {
         multi_resp.histogram_interval = discard_interval / 1000 / 1000;
@@ -196,9 +153,13 @@ pub async fn check_cache(
                     ts_column: result_ts_col.clone(),
                     discard_interval,
                     is_descending,
+                    is_count,
+                    calendar_months,
                 },
             )
             .await;
+        cached_responses
+            .retain(|meta| has_histogram_interval(&meta.cached_response, discard_interval));
         if is_descending {
             cached_responses.sort_by_key(|meta| meta.response_end_time);
         } else {
@@ -221,7 +182,18 @@ pub async fn check_cache(
             .map(|v| v.cached_response.total)
             .sum::<usize>();
 
-        let deltas = if total_hits == (sql.limit as usize) {
+        let deltas = if is_count {
+            let deltas =
+                calculate_count_deltas(&cached_responses, req.query.start_time, req.query.end_time);
+            if deltas.is_empty() {
+                *should_exec_query = false;
+            }
+            multi_resp.total_cache_duration = cached_responses
+                .iter()
+                .map(|r| r.response_end_time - r.response_start_time)
+                .sum::<i64>() as usize;
+            deltas
+        } else if total_hits == (sql.limit as usize) {
             *should_exec_query = false;
             vec![]
         } else {
@@ -230,6 +202,7 @@ pub async fn check_cache(
                 req.query.start_time,
                 req.query.end_time,
                 discard_interval,
+                calendar_months,
             );
             multi_resp.total_cache_duration = cache_duration as usize;
             if let Some(start_time) = updated_start_time {
@@ -277,9 +250,12 @@ pub async fn check_cache(
                 ts_column: result_ts_col.clone(),
                 discard_interval,
                 is_descending,
+                is_count,
+                calendar_months,
             },
         )
         .await
+        .filter(|meta| has_histogram_interval(&meta.cached_response, discard_interval))
         {
             Some(mut cached_resp) => {
                 // remove the cached response older than stream min ts
@@ -297,17 +273,25 @@ pub async fn check_cache(
                 }
 
                 let mut deltas = vec![];
-                calculate_deltas_v1(
-                    &(ResultCacheMeta {
-                        start_time: cached_resp.response_start_time,
-                        end_time: cached_resp.response_end_time,
-                        is_aggregate,
-                        is_descending,
-                    }),
-                    req.query.start_time,
-                    req.query.end_time,
-                    &mut deltas,
-                );
+                if is_count {
+                    deltas = calculate_count_deltas(
+                        std::slice::from_ref(&cached_resp),
+                        req.query.start_time,
+                        req.query.end_time,
+                    );
+                } else {
+                    calculate_deltas_v1(
+                        &(ResultCacheMeta {
+                            start_time: cached_resp.response_start_time,
+                            end_time: cached_resp.response_end_time,
+                            is_aggregate,
+                            is_descending,
+                        }),
+                        req.query.start_time,
+                        req.query.end_time,
+                        &mut deltas,
+                    );
+                }
 
                 let search_delta: Vec<QueryDelta> = deltas
                     .iter()
@@ -319,7 +303,8 @@ pub async fn check_cache(
                     *should_exec_query = false;
                 }
 
-                if cached_resp.cached_response.total == (sql.limit as usize)
+                if !is_count
+                    && cached_resp.cached_response.total == (sql.limit as usize)
                     && cached_resp.response_end_time == req.query.end_time
                 {
                     *should_exec_query = false;
@@ -353,6 +338,178 @@ pub async fn check_cache(
     }
 }
 
+/// Time column, ordering and histogram interval the cached results of a query
+/// are stored and trimmed by.
+#[derive(Debug)]
+pub(crate) struct CacheQueryPlan {
+    pub ts_column: String,
+    pub is_descending: bool,
+    /// Histogram interval in microseconds, or -1 for non-histogram queries.
+    pub discard_interval: i64,
+    /// The query only counts the hits, see [`calculate_count_deltas`].
+    pub is_count: bool,
+    /// Width in months of calendar histogram buckets, 0 for fixed width ones.
+    pub calendar_months: i64,
+}
+
+/// Whether the `track_total_hits` rewrite of the sql counts the records of a
+/// single stream, whose counts over disjoint time windows add up. The counts of
+/// grouped, distinct and set operation queries don't.
+fn is_plain_count_query(sql: &str) -> bool {
+    let Ok(mut statements) = Parser::parse_sql(&PostgreSqlDialect {}, sql) else {
+        return false;
+    };
+    if statements.len() != 1 {
+        return false;
+    }
+    let Some(Statement::Query(query)) = statements.pop() else {
+        return false;
+    };
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return false;
+    };
+    query.with.is_none()
+        && select.distinct.is_none()
+        && matches!(&select.group_by, GroupByExpr::Expressions(exprs, _) if exprs.is_empty())
+        && select.having.is_none()
+        && select.from.len() == 1
+        && select.from[0].joins.is_empty()
+        && matches!(select.from[0].relation, TableFactor::Table { .. })
+}
+
+/// Checks whether the query can be served from the result cache and, if so,
+/// rewrites the query and cache key for caching.
+pub(crate) fn plan_cache_query(
+    sql: &Sql,
+    req: &mut config::meta::search::Request,
+    origin_sql: &mut String,
+    file_path: &mut String,
+    is_aggregate: bool,
+) -> Result<CacheQueryPlan, CacheDisqualification> {
+    // an OFFSET in the sql pages the results like `from` does
+    if sql.offset > 0 {
+        return Err(CacheDisqualification::PaginatedQuery);
+    }
+
+    // count queries are rewritten to a single `count(*)` row, which is cached
+    // for the whole time range it covers
+    if req.query.track_total_hits {
+        if sql.stream_names.len() != 1 || !is_plain_count_query(origin_sql) {
+            return Err(CacheDisqualification::TrackTotalHits);
+        }
+        return Ok(CacheQueryPlan {
+            ts_column: TIMESTAMP_COL_NAME.to_string(),
+            is_descending: true,
+            discard_interval: -1,
+            is_count: true,
+            calendar_months: 0,
+        });
+    }
+
+    // skip the queries with no timestamp column
+    let ts_result = get_ts_col_order_by(sql, TIMESTAMP_COL_NAME, is_aggregate);
+    let mut result_ts_col = ts_result.map(|(ts_col, _)| ts_col);
+    if result_ts_col.is_none() && (is_aggregate || !sql.group_by.is_empty()) {
+        return Err(CacheDisqualification::NoTimestampColumn);
+    }
+
+    // skip the queries first order by is not _timestamp field
+    let order_by = &sql.order_by;
+    if let Some((first_order_by, _)) = order_by.first() {
+        if first_order_by != TIMESTAMP_COL_NAME
+            && !result_ts_col
+                .as_ref()
+                .is_some_and(|ts_col| ts_col == first_order_by)
+        {
+            return Err(CacheDisqualification::OrderByNotTimestamp {
+                field: first_order_by.to_string(),
+            });
+        }
+    }
+
+    // Hack select for _timestamp
+    if !is_aggregate && sql.group_by.is_empty() && order_by.is_empty() && !origin_sql.contains('*')
+    {
+        let caps = RE_SELECT_FROM.captures(origin_sql.as_str()).unwrap();
+        let cap_str = caps.get(1).unwrap().as_str();
+        if !cap_str.contains(TIMESTAMP_COL_NAME) {
+            *origin_sql =
+                origin_sql.replacen(cap_str, &format!("{}, {}", TIMESTAMP_COL_NAME, cap_str), 1);
+        }
+        req.query.sql = origin_sql.clone();
+        result_ts_col = Some(TIMESTAMP_COL_NAME.to_string());
+    }
+    if !is_aggregate && origin_sql.contains('*') {
+        result_ts_col = Some(TIMESTAMP_COL_NAME.to_string());
+    }
+
+    let Some(result_ts_col) = result_ts_col else {
+        return Err(CacheDisqualification::NoTimestampColumn);
+    };
+    // cached results are split and merged at UTC aligned boundaries
+    if sql.has_local_day_histogram() {
+        return Err(CacheDisqualification::LocalDayHistogram {
+            timezone: sql.histogram_timezone.clone().unwrap_or_default(),
+        });
+    }
+    // cached results are split at the boundaries of a single interval
+    if sql.histogram_intervals.len() > 1 {
+        return Err(CacheDisqualification::MultipleHistogramIntervals {
+            intervals: sql.histogram_intervals.clone(),
+        });
+    }
+    let mut discard_interval = -1;
+    let calendar_months = sql.histogram_calendar_months().unwrap_or_default();
+    if sql.histogram_interval.is_some() {
+        let mut req_time_range = (req.query.start_time, req.query.end_time);
+        if req_time_range.1 == 0 {
+            req_time_range.1 = chrono::Utc::now().timestamp_micros();
+        }
+
+        let meta_time_range_is_empty = sql.time_range.is_none() || sql.time_range == Some((0, 0));
+        let q_time_range =
+            if meta_time_range_is_empty && (req_time_range.0 > 0 || req_time_range.1 > 0) {
+                Some(req_time_range)
+            } else {
+                sql.time_range
+            };
+        // key the cache by the interval the query actually runs with, so
+        // responses bucketed at different intervals are never merged
+        let interval = handle_histogram(origin_sql, q_time_range);
+        // calendar intervals are keyed by their months, `1 month` must not
+        // share the cache of `30 day`
+        *file_path = if calendar_months > 0 {
+            format!("{}_{}mon_{}", file_path, calendar_months, result_ts_col)
+        } else {
+            format!("{}_{}_{}", file_path, interval, result_ts_col)
+        };
+        req.query.sql = origin_sql.clone();
+        discard_interval = interval * 1000 * 1000; // in microseconds
+    }
+
+    let mut is_descending = true;
+
+    if !order_by.is_empty() {
+        for (field, order) in order_by {
+            if field.eq(&result_ts_col) || field.replace("\"", "").eq(&result_ts_col) {
+                is_descending = order == &OrderBy::Desc;
+                break;
+            }
+        }
+    }
+    if is_aggregate && order_by.is_empty() && result_ts_col.is_empty() {
+        return Err(CacheDisqualification::NoTimestampColumn);
+    }
+
+    Ok(CacheQueryPlan {
+        ts_column: result_ts_col,
+        is_descending,
+        discard_interval,
+        is_count: false,
+        calendar_months,
+    })
+}
+
 pub async fn get_cached_results(
     file_path: &str,
     trace_id: &str,
@@ -364,49 +521,44 @@ pub async fn get_cached_results(
     drop(r);
 
     if let Some(cache_metas) = is_cached {
-        match
-            cache_metas
-                .iter()
-                .filter(|cache_meta| {
-                    // to make sure there is overlap between cache time range and query time range
-                    log::info!(
-                        "[CACHE CANDIDATES {trace_id}] Got caches :get_cached_results: cache_meta.response_start_time: {}, cache_meta.response_end_time: {}",
-                        cache_meta.start_time,
-                        cache_meta.end_time
-                    );
-                    cache_meta.start_time <= cache_req.q_end_time &&
-                        cache_meta.end_time >= cache_req.q_start_time
-                })
-                .max_by_key(|result| { result.end_time - result.start_time })
-        {
+        for cache_meta in cache_metas.iter() {
+            // to make sure there is overlap between cache time range and query time range
+            log::info!(
+                "[CACHE CANDIDATES {trace_id}] Got caches :get_cached_results: cache_meta.response_start_time: {}, cache_meta.response_end_time: {}",
+                cache_meta.start_time,
+                cache_meta.end_time
+            );
+        }
+        if cache_req.is_count {
+            return get_cached_counts(file_path, trace_id, &cache_req, &cache_metas)
+                .await
+                .into_iter()
+                .max_by_key(|r| r.response_end_time - r.response_start_time);
+        }
+        match select_best_meta(
+            &cache_metas,
+            &cache_req,
+            &ResultCacheSelectionStrategy::Duration,
+        ) {
             Some(matching_meta) => {
                 let file_name = format!(
                     "{}_{}_{}_{}.json",
                     matching_meta.start_time,
                     matching_meta.end_time,
-                    if cache_req.is_aggregate {
-                        1
-                    } else {
-                        0
-                    },
-                    if cache_req.is_descending {
-                        1
-                    } else {
-                        0
-                    }
+                    if cache_req.is_aggregate { 1 } else { 0 },
+                    if cache_req.is_descending { 1 } else { 0 }
                 );
                 let mut matching_cache_meta = matching_meta.clone();
                 // calculate delta time range to fetch the delta data using search query
                 let cfg = get_config();
                 let discard_duration = cfg.common.result_cache_discard_duration * 1000 * 1000;
 
-                let cache_duration = matching_cache_meta.end_time - matching_cache_meta.start_time;
                 // return None if cache duration is less than 2 * discard_duration
-                if
-                    cache_duration <= discard_duration &&
-                    matching_cache_meta.start_time >
-                        Utc::now().timestamp_micros() - discard_duration
-                {
+                if is_in_discard_window(
+                    &matching_cache_meta,
+                    discard_duration,
+                    Utc::now().timestamp_micros(),
+                ) {
                     return None;
                 }
 
@@ -424,27 +576,32 @@ pub async fn get_cached_results(
                         };
                         let first_ts = get_ts_value(
                             &cache_req.ts_column,
-                            cached_response.hits.first().unwrap()
+                            cached_response.hits.first().unwrap(),
                         );
 
                         let last_ts = get_ts_value(
                             &cache_req.ts_column,
-                            cached_response.hits.last().unwrap()
+                            cached_response.hits.last().unwrap(),
                         );
 
-                        let (hits_allowed_start_time, hits_allowed_end_time) = if
-                            cache_req.discard_interval > 0
-                        {
-                            // calculation in line with date bin of datafusion
-                            (
-                                cache_req.q_start_time -
-                                    (cache_req.q_start_time % cache_req.discard_interval),
-                                cache_req.q_end_time -
-                                    (cache_req.q_end_time % cache_req.discard_interval),
-                            )
-                        } else {
-                            (cache_req.q_start_time, cache_req.q_end_time)
-                        };
+                        let (hits_allowed_start_time, hits_allowed_end_time) =
+                            if cache_req.discard_interval > 0 {
+                                // calculation in line with date bin of datafusion
+                                (
+                                    histogram_bucket_start(
+                                        cache_req.q_start_time,
+                                        cache_req.discard_interval,
+                                        cache_req.calendar_months,
+                                    ),
+                                    histogram_bucket_start(
+                                        cache_req.q_end_time,
+                                        cache_req.discard_interval,
+                                        cache_req.calendar_months,
+                                    ),
+                                )
+                            } else {
+                                (cache_req.q_start_time, cache_req.q_end_time)
+                            };
                         let discard_ts = if cache_req.is_descending {
                             if cache_req.discard_interval > 0 {
                                 first_ts
@@ -471,9 +628,9 @@ pub async fn get_cached_results(
 
                         cached_response.hits.retain(|hit| {
                             let hit_ts = get_ts_value(&cache_req.ts_column, hit);
-                            hit_ts <= hits_allowed_end_time &&
-                                hit_ts >= hits_allowed_start_time &&
-                                hit_ts < discard_ts
+                            hit_ts <= hits_allowed_end_time
+                                && hit_ts >= hits_allowed_start_time
+                                && hit_ts < discard_ts
                         });
 
                         cached_response.total = cached_response.hits.len();
@@ -500,7 +657,10 @@ pub async fn get_cached_results(
                         })
                     }
                     Err(e) => {
-                        log::error!("[trace_id {trace_id}] Get results from disk failed : {:?}", e);
+                        log::error!(
+                            "[trace_id {trace_id}] Get results from disk failed : {:?}",
+                            e
+                        );
                         None
                     }
                 }
@@ -567,6 +727,112 @@ pub fn calculate_deltas_v1(
     has_pre_cache_delta
 }
 
+/// Cached windows a count query can be served from. Unlike hits, a cached
+/// count can't be trimmed to the query time range, so only windows lying
+/// entirely within it qualify. Longer windows win over the ones they overlap,
+/// so no record is counted twice.
+pub(crate) fn select_count_metas(
+    cache_metas: &[ResultCacheMeta],
+    start_time: i64,
+    end_time: i64,
+) -> Vec<ResultCacheMeta> {
+    let mut candidates = cache_metas
+        .iter()
+        .filter(|m| {
+            m.start_time < m.end_time && m.start_time >= start_time && m.end_time <= end_time
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|m| (std::cmp::Reverse(m.end_time - m.start_time), m.start_time));
+    let mut selected: Vec<ResultCacheMeta> = Vec::with_capacity(candidates.len());
+    for meta in candidates {
+        if selected
+            .iter()
+            .all(|s| meta.end_time <= s.start_time || meta.start_time >= s.end_time)
+        {
+            selected.push(meta);
+        }
+    }
+    selected.sort_by_key(|m| m.start_time);
+    selected
+}
+
+/// Time ranges of a count query left to search next to the cached counts in
+/// `cached`, which are sorted by start time and don't overlap.
+pub(crate) fn calculate_count_deltas(
+    cached: &[CachedQueryResponse],
+    start_time: i64,
+    end_time: i64,
+) -> Vec<QueryDelta> {
+    let mut deltas = Vec::new();
+    let mut current_end_time = start_time;
+    for meta in cached {
+        if meta.response_start_time > current_end_time {
+            deltas.push(QueryDelta {
+                delta_start_time: current_end_time,
+                delta_end_time: meta.response_start_time,
+                delta_removed_hits: false,
+            });
+        }
+        current_end_time = current_end_time.max(meta.response_end_time);
+    }
+    if current_end_time < end_time {
+        deltas.push(QueryDelta {
+            delta_start_time: current_end_time,
+            delta_end_time: end_time,
+            delta_removed_hits: false,
+        });
+    }
+    deltas
+}
+
+/// Loads the cached counts selected by [`select_count_metas`].
+pub(crate) async fn get_cached_counts(
+    file_path: &str,
+    trace_id: &str,
+    cache_req: &CacheQueryRequest,
+    cache_metas: &[ResultCacheMeta],
+) -> Vec<CachedQueryResponse> {
+    let mut results = vec![];
+    for meta in select_count_metas(cache_metas, cache_req.q_start_time, cache_req.q_end_time) {
+        let file_name = format!(
+            "{}_{}_{}_{}.json",
+            meta.start_time,
+            meta.end_time,
+            if cache_req.is_aggregate { 1 } else { 0 },
+            if cache_req.is_descending { 1 } else { 0 }
+        );
+        let cached_response = match get_results(file_path, &file_name).await {
+            Ok(v) => match json::from_str::<Response>(&v) {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("[trace_id {trace_id}] Error parsing cached count: {:?}", e);
+                    continue;
+                }
+            },
+            Err(e) => {
+                log::error!(
+                    "[trace_id {trace_id}] Get cached count from disk failed: {:?}",
+                    e
+                );
+                continue;
+            }
+        };
+        results.push(CachedQueryResponse {
+            cached_response,
+            deltas: vec![],
+            has_cached_data: true,
+            cache_query_response: true,
+            response_start_time: meta.start_time,
+            response_end_time: meta.end_time,
+            ts_column: cache_req.ts_column.to_string(),
+            is_descending: cache_req.is_descending,
+            limit: -1,
+        });
+    }
+    results
+}
+
 pub async fn cache_results_to_disk(
     trace_id: &str,
     file_path: &str,
@@ -574,7 +840,8 @@ pub async fn cache_results_to_disk(
     data: String,
 ) -> std::io::Result<()> {
     let file = format!("results/{}/{}", file_path, file_name);
-    match disk::set(trace_id, &file, Bytes::from(data)).await {
+    let data = compress_cache_data(Bytes::from(data));
+    match disk::set(trace_id, &file, data).await {
         Ok(_) => (),
         Err(e) => {
             log::error!("Error caching results to disk: {:?}", e);
@@ -590,7 +857,11 @@ pub async fn cache_results_to_disk(
 pub async fn get_results(file_path: &str, file_name: &str) -> std::io::Result<String> {
     let file = format!("results/{}/{}", file_path, file_name);
     match disk::get(&file, None).await {
-        Some(v) => Ok(String::from_utf8(v.to_vec()).unwrap()),
+        Some(v) => {
+            let v = decompress_cache_data(v)?;
+            String::from_utf8(v.to_vec())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }
         None => Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "File not found",
@@ -608,7 +879,7 @@ pub fn get_ts_col_order_by(
     let mut result_ts_col = String::new();
 
     for (original, alias) in &parsed_sql.aliases {
-        if original == ts_col || original.contains("histogram") {
+        if original.contains("histogram") {
             result_ts_col = alias.clone();
         }
     }
@@ -621,10 +892,23 @@ pub fn get_ts_col_order_by(
     {
         result_ts_col = ts_col.to_string();
     }
+    // the hits carry the column under the last alias it is selected as
+    let names = resolve_alias_chain(
+        &parsed_sql.aliases,
+        order_by,
+        if result_ts_col.is_empty() {
+            ts_col
+        } else {
+            &result_ts_col
+        },
+    );
+    if names.len() > 1 || !result_ts_col.is_empty() {
+        result_ts_col = names.last().unwrap().clone();
+    }
 
     if !order_by.is_empty() && !result_ts_col.is_empty() {
         for (field, order) in order_by {
-            if field.eq(&result_ts_col) || field.replace("\"", "").eq(&result_ts_col) {
+            if names.contains(field) || names.contains(&field.replace("\"", "")) {
                 is_descending = order == &OrderBy::Desc;
                 break;
             }
@@ -637,16 +921,63 @@ pub fn get_ts_col_order_by(
     }
 }
 
+/// Follows the aliases a column is selected under, e.g. `[_timestamp, ts]` for
+/// `SELECT _timestamp AS ts`. When a column has several aliases the one the
+/// query is ordered by is followed.
+fn resolve_alias_chain(
+    aliases: &[(String, String)],
+    order_by: &[(String, OrderBy)],
+    column: &str,
+) -> Vec<String> {
+    let unquote = |name: &str| {
+        name.rsplit('.')
+            .next()
+            .unwrap_or(name)
+            .trim_matches('"')
+            .to_string()
+    };
+    let mut names = vec![column.to_string()];
+    loop {
+        let current = names.last().unwrap();
+        let mut candidates = aliases
+            .iter()
+            .filter(|(original, alias)| {
+                (original == current || unquote(original) == *current) && !names.contains(alias)
+            })
+            .map(|(_, alias)| alias)
+            .collect::<Vec<_>>();
+        candidates.sort();
+        let next = candidates
+            .iter()
+            .find(|alias| order_by.iter().any(|(field, _)| field == **alias))
+            .or(candidates.first());
+        match next {
+            Some(alias) => names.push(alias.to_string()),
+            None => break,
+        }
+    }
+    names
+}
+
+/// Deletes the cached results under the path, only the ones whose time range
+/// overlaps with the given one when there is a time range.
 #[tracing::instrument]
-pub async fn delete_cache(path: &str) -> std::io::Result<bool> {
+pub async fn delete_cache(path: &str, time_range: Option<(i64, i64)>) -> std::io::Result<bool> {
     let root_dir = disk::get_dir().await;
     let pattern = format!("{}/results/{}", root_dir, path);
     let prefix = format!("{}/", root_dir);
     let files = scan_files(&pattern, "json", None).unwrap_or_default();
-    let mut remove_files: Vec<String> = vec![];
+    let mut removed = vec![];
     for file in files {
-        match disk::remove("", file.strip_prefix(&prefix).unwrap()).await {
-            Ok(_) => remove_files.push(file),
+        let file = file.strip_prefix(&prefix).unwrap();
+        let Some((query_key, start_time, end_time)) = parse_cache_file(file) else {
+            continue;
+        };
+        if !in_time_range(time_range, start_time, end_time) {
+            continue;
+        }
+        match disk::remove("", file).await {
+            Ok(_) => removed.push((query_key, start_time, end_time)),
             Err(e) => {
                 log::error!("Error deleting cache: {:?}", e);
                 return Err(std::io::Error::new(
@@ -656,24 +987,51 @@ pub async fn delete_cache(path: &str) -> std::io::Result<bool> {
             }
         }
     }
-    for file in remove_files {
-        let columns = file
-            .strip_prefix(&prefix)
-            .unwrap()
-            .split('/')
-            .collect::<Vec<&str>>();
+    remove_result_metas(&removed).await;
+    Ok(true)
+}
 
-        let query_key = format!(
-            "{}_{}_{}_{}",
-            columns[1], columns[2], columns[3], columns[4]
-        );
-        let mut r = QUERY_RESULT_CACHE.write().await;
-        r.remove(&query_key);
+/// Returns the query key and the time range of a cached results file, which is
+/// `results/{org_id}/{stream_type}/{stream_name}/{hash}/{start}_{end}_{agg}_{desc}.json`
+fn parse_cache_file(file: &str) -> Option<(String, i64, i64)> {
+    let columns = file.split('/').collect::<Vec<&str>>();
+    if columns.len() != 6 || columns[0] != "results" {
+        return None;
     }
-    Ok(true)
+    let query_key = columns[1..5].join("_");
+    let mut meta = columns[5].split('_');
+    let start_time = meta.next()?.parse().ok()?;
+    let end_time = meta.next()?.parse().ok()?;
+    Some((query_key, start_time, end_time))
+}
+
+/// Whether the cached time range overlaps with the time range being deleted,
+/// everything is deleted without a time range.
+fn in_time_range(time_range: Option<(i64, i64)>, start_time: i64, end_time: i64) -> bool {
+    time_range.map_or(true, |(start, end)| start_time <= end && end_time >= start)
 }
 
-fn handle_histogram(origin_sql: &mut String, q_time_range: Option<(i64, i64)>) {
+/// Drops the metas of the deleted cached results, the queries without any
+/// cached results left are searched again.
+async fn remove_result_metas(removed: &[(String, i64, i64)]) {
+    if removed.is_empty() {
+        return;
+    }
+    let mut w = QUERY_RESULT_CACHE.write().await;
+    for (query_key, start_time, end_time) in removed {
+        let Some(metas) = w.get_mut(query_key) else {
+            continue;
+        };
+        metas.retain(|meta| meta.start_time != *start_time || meta.end_time != *end_time);
+        if metas.is_empty() {
+            w.remove(query_key);
+        }
+    }
+}
+
+/// Injects the resolved histogram interval into `origin_sql` and returns it in
+/// seconds.
+fn handle_histogram(origin_sql: &mut String, q_time_range: Option<(i64, i64)>) -> i64 {
     let caps = RE_HISTOGRAM.captures(origin_sql.as_str()).unwrap();
     let attrs = caps
         .get(1)
@@ -690,11 +1048,23 @@ fn handle_histogram(origin_sql: &mut String, q_time_range: Option<(i64, i64)>) {
         },
         None => generate_histogram_interval(q_time_range, 0),
     };
+    let histogram = match attrs.get(2) {
+        Some(timezone) => format!("histogram(_timestamp,'{}','{}')", interval, timezone),
+        None => format!("histogram(_timestamp,'{}')", interval),
+    };
 
-    *origin_sql = origin_sql.replace(
-        caps.get(0).unwrap().as_str(),
-        &format!("histogram(_timestamp,'{}')", interval),
-    );
+    *origin_sql = origin_sql.replace(caps.get(0).unwrap().as_str(), &histogram);
+    convert_histogram_interval_to_seconds(&interval).unwrap_or_default()
+}
+
+/// Whether `response` was bucketed at `discard_interval` (in microseconds).
+/// Responses without a histogram interval and queries whose interval can't
+/// be expressed in seconds always match.
+pub(crate) fn has_histogram_interval(response: &Response, discard_interval: i64) -> bool {
+    discard_interval <= 0
+        || response
+            .histogram_interval
+            .map_or(true, |interval| interval * 1000 * 1000 == discard_interval)
 }
 
 fn calculate_deltas_multi(
@@ -702,6 +1072,7 @@ fn calculate_deltas_multi(
     start_time: i64,
     end_time: i64,
     histogram_interval: i64,
+    calendar_months: i64,
 ) -> (Vec<QueryDelta>, Option<i64>, i64) {
     let mut deltas = Vec::new();
     let mut cache_duration = 0_i64;
@@ -719,8 +1090,10 @@ fn calculate_deltas_multi(
         let delta_end_time = if histogram_interval > 0 && !meta.cached_response.hits.is_empty() {
             // If histogram interval > 0, we need to adjust the end time to the nearest interval
             let mut end_time = meta.response_start_time;
-            if end_time % histogram_interval != 0 {
-                end_time = end_time - (end_time % histogram_interval);
+            let bucket_start =
+                histogram_bucket_start(end_time, histogram_interval, calendar_months);
+            if end_time != bucket_start {
+                end_time = bucket_start;
                 if end_time < start_time {
                     end_time = start_time;
                 }
@@ -750,7 +1123,11 @@ fn calculate_deltas_multi(
         deltas.push(QueryDelta {
             // Adding histogram interval to the current end time to ensure the next query
             // fetches the data after the last cache result timestamp, thereby avoiding duplicates
-            delta_start_time: current_end_time + histogram_interval.abs(),
+            delta_start_time: next_histogram_bucket_start(
+                current_end_time,
+                histogram_interval,
+                calendar_months,
+            ),
             delta_end_time: end_time,
             delta_removed_hits: false,
         });
@@ -764,3 +1141,241 @@ fn calculate_deltas_multi(
 
     (deltas, None, cache_duration)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::search::sql::SECONDS_PER_CALENDAR_MONTH;
+
+    #[test]
+    fn test_parse_cache_file() {
+        assert_eq!(
+            parse_cache_file("results/default/logs/k8s/1234/100_200_0_1.json"),
+            Some(("default_logs_k8s_1234".to_string(), 100, 200))
+        );
+        assert_eq!(parse_cache_file("results/default/logs/k8s/1234"), None);
+        assert_eq!(
+            parse_cache_file("metrics_results/default/logs/k8s/1234/100_200_0_1.json"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_in_time_range() {
+        assert!(in_time_range(None, 100, 200));
+        assert!(in_time_range(Some((150, 160)), 100, 200));
+        assert!(in_time_range(Some((50, 100)), 100, 200));
+        assert!(in_time_range(Some((200, 300)), 100, 200));
+        assert!(!in_time_range(Some((201, 300)), 100, 200));
+        assert!(!in_time_range(Some((0, 99)), 100, 200));
+    }
+
+    #[tokio::test]
+    async fn test_invalidated_cache_is_searched_again() {
+        let file_path = "invalidate_org/logs/k8s/1234";
+        let query_key = file_path.replace('/', "_");
+        let stale = ResultCacheMeta {
+            start_time: 100,
+            end_time: 200,
+            is_aggregate: false,
+            is_descending: true,
+        };
+        let other = ResultCacheMeta {
+            start_time: 1000,
+            end_time: 2000,
+            ..stale.clone()
+        };
+        QUERY_RESULT_CACHE
+            .write()
+            .await
+            .insert(query_key.clone(), vec![stale.clone(), other.clone()]);
+        for file_name in ["100_200_0_1.json", "1000_2000_0_1.json"] {
+            cache_results_to_disk("trace", file_path, file_name, "{}".to_string())
+                .await
+                .unwrap();
+        }
+
+        // the data of 150..160 is re-ingested corrected, only the overlapping
+        // results are invalidated
+        assert!(delete_cache(file_path, Some((150, 160))).await.unwrap());
+        assert!(get_results(file_path, "100_200_0_1.json").await.is_err());
+        assert!(get_results(file_path, "1000_2000_0_1.json").await.is_ok());
+        assert_eq!(
+            QUERY_RESULT_CACHE.read().await.get(&query_key),
+            Some(&vec![other])
+        );
+
+        // once nothing is cached the next query executes again
+        assert!(delete_cache(file_path, None).await.unwrap());
+        assert!(get_results(file_path, "1000_2000_0_1.json").await.is_err());
+        assert!(!QUERY_RESULT_CACHE.read().await.contains_key(&query_key));
+        let cache_req = CacheQueryRequest {
+            q_start_time: 100,
+            q_end_time: 200,
+            ts_column: TIMESTAMP_COL_NAME.to_string(),
+            is_descending: true,
+            ..Default::default()
+        };
+        assert!(get_cached_results(file_path, "trace", cache_req)
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn test_handle_histogram_returns_injected_interval() {
+        let hour = 3600 * 1_000_000;
+        let mut sql =
+            "SELECT histogram(_timestamp) AS ts, count(*) FROM default GROUP BY ts".to_string();
+        let interval = handle_histogram(&mut sql, Some((0, hour)));
+        assert_eq!(interval, 30);
+        assert!(sql.contains("histogram(_timestamp,'30 second')"));
+
+        let mut sql =
+            "SELECT histogram(_timestamp) AS ts, count(*) FROM default GROUP BY ts".to_string();
+        let interval = handle_histogram(&mut sql, Some((0, 6 * hour)));
+        assert_eq!(interval, 3600);
+        assert!(sql.contains("histogram(_timestamp,'1 hour')"));
+    }
+
+    #[test]
+    fn test_resolve_alias_chain() {
+        let aliases = vec![
+            ("\"_timestamp\"".to_string(), "ts".to_string()),
+            ("ts".to_string(), "t".to_string()),
+            ("msg".to_string(), "m".to_string()),
+        ];
+        assert_eq!(
+            resolve_alias_chain(&aliases, &[], TIMESTAMP_COL_NAME),
+            vec![TIMESTAMP_COL_NAME, "ts", "t"]
+        );
+        assert_eq!(resolve_alias_chain(&aliases, &[], "code"), vec!["code"]);
+
+        // of several aliases the one the query is ordered by is followed
+        let aliases = vec![
+            (TIMESTAMP_COL_NAME.to_string(), "a".to_string()),
+            (TIMESTAMP_COL_NAME.to_string(), "b".to_string()),
+        ];
+        let order_by = vec![("b".to_string(), OrderBy::Asc)];
+        assert_eq!(
+            resolve_alias_chain(&aliases, &order_by, TIMESTAMP_COL_NAME),
+            vec![TIMESTAMP_COL_NAME, "b"]
+        );
+    }
+
+    #[test]
+    fn test_has_histogram_interval() {
+        let mut response = Response::default();
+        assert!(has_histogram_interval(&response, 30_000_000));
+
+        response.histogram_interval = Some(30);
+        assert!(has_histogram_interval(&response, 30_000_000));
+        assert!(!has_histogram_interval(&response, 300_000_000));
+        // non-histogram queries and intervals that can't be expressed in seconds
+        assert!(has_histogram_interval(&response, -1));
+        assert!(has_histogram_interval(&response, 0));
+    }
+
+    #[test]
+    fn test_is_plain_count_query() {
+        assert!(is_plain_count_query("SELECT * FROM t"));
+        assert!(is_plain_count_query(
+            "SELECT count(*) FROM t WHERE code = 500"
+        ));
+        assert!(!is_plain_count_query(
+            "SELECT code, count(*) FROM t GROUP BY code"
+        ));
+        assert!(!is_plain_count_query("SELECT DISTINCT code FROM t"));
+        assert!(!is_plain_count_query(
+            "SELECT * FROM t UNION SELECT * FROM u"
+        ));
+        assert!(!is_plain_count_query(
+            "SELECT * FROM t JOIN u ON t.id = u.id"
+        ));
+        assert!(!is_plain_count_query(
+            "SELECT * FROM (SELECT code FROM t GROUP BY code)"
+        ));
+        assert!(!is_plain_count_query("SELECT * FROM"));
+    }
+
+    #[test]
+    fn test_select_count_metas_and_deltas() {
+        let meta = |start_time, end_time| ResultCacheMeta {
+            start_time,
+            end_time,
+            is_aggregate: false,
+            is_descending: true,
+        };
+        // [50, 150) sticks out of the query and [10, 20) overlaps the longer [0, 30)
+        let metas = vec![meta(10, 20), meta(60, 90), meta(0, 30), meta(50, 150)];
+        let selected = select_count_metas(&metas, 0, 100);
+        assert_eq!(
+            selected
+                .iter()
+                .map(|m| (m.start_time, m.end_time))
+                .collect::<Vec<_>>(),
+            vec![(0, 30), (60, 90)]
+        );
+
+        let cached = selected
+            .iter()
+            .map(|m| CachedQueryResponse {
+                response_start_time: m.start_time,
+                response_end_time: m.end_time,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let deltas = calculate_count_deltas(&cached, 0, 100);
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|d| (d.delta_start_time, d.delta_end_time))
+                .collect::<Vec<_>>(),
+            vec![(30, 60), (90, 100)]
+        );
+        assert!(calculate_count_deltas(&cached[..1], 0, 30).is_empty());
+    }
+
+    #[test]
+    fn test_calculate_deltas_multi_calendar_buckets() {
+        let micros = |s: &str| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .unwrap()
+                .timestamp_micros()
+        };
+        let mut cached_response = Response::default();
+        cached_response.hits = vec![
+            json::json!({ "_timestamp": micros("2024-02-01T00:00:00Z") }),
+            json::json!({ "_timestamp": micros("2024-02-01T00:00:00Z") }),
+        ];
+        let cached = vec![CachedQueryResponse {
+            cached_response,
+            response_start_time: micros("2024-02-10T00:00:00Z"),
+            response_end_time: micros("2024-02-29T12:00:00Z"),
+            ..Default::default()
+        }];
+        let (deltas, ..) = calculate_deltas_multi(
+            &cached,
+            micros("2024-01-01T00:00:00Z"),
+            micros("2024-04-10T00:00:00Z"),
+            SECONDS_PER_CALENDAR_MONTH * 1_000_000,
+            1,
+        );
+        // the deltas start and end at month boundaries, not at 30 day ones
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|d| (d.delta_start_time, d.delta_end_time))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    micros("2024-01-01T00:00:00Z"),
+                    micros("2024-02-01T00:00:00Z")
+                ),
+                (
+                    micros("2024-03-01T00:00:00Z"),
+                    micros("2024-04-10T00:00:00Z")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,421 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use chrono::Utc;
+use config::{
+    get_config,
+    meta::{search, sql::resolve_stream_names, stream::StreamType},
+    utils::{base64, sql::is_aggregate_query},
+};
+use infra::cache::{file_data::disk::QUERY_RESULT_CACHE, meta::ResultCacheMeta};
+
+use crate::{
+    common::meta::search::{
+        CacheDisqualification, CacheExplainOutcome, CacheExplainReport, CacheHashInputs,
+        CacheQueryRequest, CacheWindowExplain, CachedQueryResponse, QueryDelta,
+        ResultCacheSelectionStrategy,
+    },
+    service::search::{
+        cache::{
+            cacher::{
+                calculate_count_deltas, calculate_deltas_v1, plan_cache_query, select_count_metas,
+                CacheQueryPlan,
+            },
+            get_cache_file_path,
+            multi::{is_in_discard_window, overlaps_query, select_best_meta, select_cache_meta},
+        },
+        sql::Sql,
+    },
+};
+
+/// Runs the cache check portion of a search and reports the decisions taken
+/// along the way. The search is not executed and the cache is not written.
+pub async fn explain_cache(
+    trace_id: &str,
+    org_id: &str,
+    stream_type: StreamType,
+    in_req: &search::Request,
+) -> CacheExplainReport {
+    let cfg = get_config();
+    let mut req = in_req.clone();
+    let mut origin_sql = req.query.sql.replace('\n', " ");
+
+    let mut report = CacheExplainReport {
+        trace_id: trace_id.to_string(),
+        hash_inputs: CacheHashInputs {
+            sql: origin_sql.clone(),
+            has_vrl: req
+                .query
+                .query_fn
+                .as_ref()
+                .and_then(|v| base64::decode_url(v).ok())
+                .is_some(),
+            track_total_hits: req.query.track_total_hits,
+            regions: req.regions.clone(),
+            clusters: req.clusters.clone(),
+        },
+        is_aggregate: is_aggregate_query(&origin_sql).unwrap_or_default(),
+        query_start_time: req.query.start_time,
+        query_end_time: req.query.end_time,
+        selection_strategy: if cfg.common.use_multi_result_cache {
+            ResultCacheSelectionStrategy::from_str(&cfg.common.result_cache_selection_strategy)
+                .unwrap_or_default()
+        } else {
+            // the single result cache always picks the longest window
+            ResultCacheSelectionStrategy::Duration
+        },
+        ..Default::default()
+    };
+
+    let stream_names = match resolve_stream_names(&origin_sql) {
+        Ok(v) => v,
+        Err(e) => {
+            return disqualified(
+                report,
+                CacheDisqualification::ParseError {
+                    error: e.to_string(),
+                },
+            );
+        }
+    };
+    report.file_path =
+        get_cache_file_path(org_id, stream_type, &stream_names[0], &origin_sql, &req);
+
+    if !cfg.common.result_cache_enabled {
+        return disqualified(report, CacheDisqualification::CacheDisabled);
+    }
+    // result cache can be enable only when its from the start
+    if req.query.from != 0 {
+        return disqualified(report, CacheDisqualification::PaginatedQuery);
+    }
+    // the cache key only covers the first stream of the query
+    if stream_names.len() > 1 {
+        return disqualified(
+            report,
+            CacheDisqualification::MultiStream {
+                streams: stream_names,
+            },
+        );
+    }
+
+    let sql = match Sql::new(&req.query, org_id, stream_type).await {
+        Ok(v) => v,
+        Err(e) => {
+            return disqualified(
+                report,
+                CacheDisqualification::ParseError {
+                    error: e.to_string(),
+                },
+            );
+        }
+    };
+    let mut file_path = report.file_path.clone();
+    let plan = match plan_cache_query(
+        &sql,
+        &mut req,
+        &mut origin_sql,
+        &mut file_path,
+        report.is_aggregate,
+    ) {
+        Ok(v) => v,
+        Err(e) => return disqualified(report, e),
+    };
+    report.file_path = file_path;
+    report.ts_column = Some(plan.ts_column.clone());
+    report.is_descending = Some(plan.is_descending);
+    if plan.discard_interval > 0 {
+        report.histogram_interval = Some(plan.discard_interval / 1000 / 1000);
+    }
+
+    let query_key = report.file_path.replace('/', "_");
+    let cache_metas = QUERY_RESULT_CACHE
+        .read()
+        .await
+        .get(&query_key)
+        .cloned()
+        .unwrap_or_default();
+    explain_windows(
+        &mut report,
+        &cache_metas,
+        &plan,
+        cfg.common.result_cache_discard_duration * 1000 * 1000,
+        Utc::now().timestamp_micros(),
+    );
+    report
+}
+
+/// Scores the cached windows of the query, selects one the way the cache check
+/// does and derives the outcome from the remaining deltas.
+fn explain_windows(
+    report: &mut CacheExplainReport,
+    cache_metas: &[ResultCacheMeta],
+    plan: &CacheQueryPlan,
+    discard_duration: i64,
+    now: i64,
+) {
+    let cache_req = CacheQueryRequest {
+        q_start_time: report.query_start_time,
+        q_end_time: report.query_end_time,
+        is_aggregate: report.is_aggregate,
+        ts_column: plan.ts_column.clone(),
+        discard_interval: plan.discard_interval,
+        is_descending: plan.is_descending,
+        is_count: plan.is_count,
+        calendar_months: plan.calendar_months,
+    };
+
+    let mut cache_metas = cache_metas.to_vec();
+    cache_metas.sort_by_key(|m| m.start_time);
+    let selected = if plan.is_count {
+        // the single cached count covering most of the query, see `get_cached_results`
+        select_count_metas(&cache_metas, cache_req.q_start_time, cache_req.q_end_time)
+            .into_iter()
+            .max_by_key(|m| m.end_time - m.start_time)
+            .and_then(|best| cache_metas.iter().find(|m| **m == best))
+    } else {
+        select_best_meta(&cache_metas, &cache_req, &report.selection_strategy)
+    };
+    report.windows = cache_metas
+        .iter()
+        .map(|meta| CacheWindowExplain {
+            start_time: meta.start_time,
+            end_time: meta.end_time,
+            overlaps_query: overlaps_query(meta, &cache_req),
+            overlap_score: select_cache_meta(
+                meta,
+                &cache_req,
+                &ResultCacheSelectionStrategy::Overlap,
+            ),
+            duration_score: select_cache_meta(
+                meta,
+                &cache_req,
+                &ResultCacheSelectionStrategy::Duration,
+            ),
+            both_score: select_cache_meta(meta, &cache_req, &ResultCacheSelectionStrategy::Both),
+            selected: selected.is_some_and(|s| std::ptr::eq(s, meta)),
+        })
+        .collect();
+
+    let Some(selected) = selected else {
+        report.selection_reason = Some(if cache_metas.is_empty() {
+            "no cached windows exist for the cache key".to_string()
+        } else if plan.is_count {
+            "no cached count lies within the query time range".to_string()
+        } else {
+            "no cached window overlaps the query time range".to_string()
+        });
+        report.outcome = CacheExplainOutcome::Miss;
+        return;
+    };
+    report.selection_reason = Some(format!(
+        "window {}-{} has the highest {:?} score among the windows overlapping the query time range",
+        selected.start_time, selected.end_time, report.selection_strategy
+    ));
+
+    if is_in_discard_window(selected, discard_duration, now) {
+        report.disqualification = Some(CacheDisqualification::DiscardWindow {
+            start_time: selected.start_time,
+            end_time: selected.end_time,
+        });
+        report.outcome = CacheExplainOutcome::Disqualified;
+        return;
+    }
+
+    let mut deltas = vec![];
+    if plan.is_count {
+        deltas = calculate_count_deltas(
+            &[CachedQueryResponse {
+                response_start_time: selected.start_time,
+                response_end_time: selected.end_time,
+                ..Default::default()
+            }],
+            cache_req.q_start_time,
+            cache_req.q_end_time,
+        );
+    } else {
+        calculate_deltas_v1(
+            selected,
+            cache_req.q_start_time,
+            cache_req.q_end_time,
+            &mut deltas,
+        );
+    }
+    report.deltas = deltas
+        .into_iter()
+        .filter(|d| !d.delta_removed_hits)
+        .collect::<Vec<QueryDelta>>();
+    report.outcome = if report.deltas.is_empty() {
+        CacheExplainOutcome::Hit
+    } else {
+        CacheExplainOutcome::PartialHit
+    };
+}
+
+fn disqualified(
+    mut report: CacheExplainReport,
+    reason: CacheDisqualification,
+) -> CacheExplainReport {
+    report.disqualification = Some(reason);
+    report.outcome = CacheExplainOutcome::Disqualified;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use config::utils::json;
+
+    use super::*;
+
+    const MINUTE: i64 = 60 * 1000 * 1000;
+
+    fn meta(start_time: i64, end_time: i64) -> ResultCacheMeta {
+        ResultCacheMeta {
+            start_time,
+            end_time,
+            is_aggregate: false,
+            is_descending: true,
+        }
+    }
+
+    fn plan() -> CacheQueryPlan {
+        CacheQueryPlan {
+            ts_column: "_timestamp".to_string(),
+            is_descending: true,
+            discard_interval: -1,
+            is_count: false,
+            calendar_months: 0,
+        }
+    }
+
+    fn new_report(start_time: i64, end_time: i64) -> CacheExplainReport {
+        CacheExplainReport {
+            query_start_time: start_time,
+            query_end_time: end_time,
+            selection_strategy: ResultCacheSelectionStrategy::Overlap,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_explain_full_hit() {
+        let mut report = new_report(10 * MINUTE, 20 * MINUTE);
+        explain_windows(
+            &mut report,
+            &[meta(10 * MINUTE, 20 * MINUTE)],
+            &plan(),
+            MINUTE,
+            100 * MINUTE,
+        );
+        assert_eq!(report.outcome, CacheExplainOutcome::Hit);
+        assert!(report.deltas.is_empty());
+        assert!(report.windows[0].selected);
+
+        let value = json::to_value(&report).unwrap();
+        assert_eq!(value["outcome"], "hit");
+        assert_eq!(value["windows"][0]["overlap_score"], 10 * MINUTE);
+        assert!(value["disqualification"].is_null());
+    }
+
+    #[test]
+    fn test_explain_partial_hit() {
+        let mut report = new_report(10 * MINUTE, 30 * MINUTE);
+        explain_windows(
+            &mut report,
+            &[
+                meta(5 * MINUTE, 15 * MINUTE),
+                meta(12 * MINUTE, 25 * MINUTE),
+            ],
+            &plan(),
+            MINUTE,
+            100 * MINUTE,
+        );
+        assert_eq!(report.outcome, CacheExplainOutcome::PartialHit);
+        // the second window overlaps the query for longer
+        assert!(!report.windows[0].selected);
+        assert!(report.windows[1].selected);
+        assert_eq!(
+            report.deltas,
+            vec![
+                QueryDelta {
+                    delta_start_time: 25 * MINUTE,
+                    delta_end_time: 30 * MINUTE,
+                    delta_removed_hits: false,
+                },
+                QueryDelta {
+                    delta_start_time: 10 * MINUTE,
+                    delta_end_time: 12 * MINUTE,
+                    delta_removed_hits: false,
+                },
+            ]
+        );
+
+        let value = json::to_value(&report).unwrap();
+        assert_eq!(value["outcome"], "partial_hit");
+        assert_eq!(value["deltas"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_explain_miss() {
+        let mut report = new_report(10 * MINUTE, 20 * MINUTE);
+        explain_windows(
+            &mut report,
+            &[meta(30 * MINUTE, 40 * MINUTE)],
+            &plan(),
+            MINUTE,
+            100 * MINUTE,
+        );
+        assert_eq!(report.outcome, CacheExplainOutcome::Miss);
+        assert!(!report.windows[0].overlaps_query);
+        assert!(!report.windows[0].selected);
+
+        let mut report = new_report(10 * MINUTE, 20 * MINUTE);
+        explain_windows(&mut report, &[], &plan(), MINUTE, 100 * MINUTE);
+        let value = json::to_value(&report).unwrap();
+        assert_eq!(value["outcome"], "miss");
+        assert_eq!(
+            value["selection_reason"],
+            "no cached windows exist for the cache key"
+        );
+    }
+
+    #[test]
+    fn test_explain_disqualified() {
+        let mut report = new_report(10 * MINUTE, 20 * MINUTE);
+        explain_windows(
+            &mut report,
+            &[meta(10 * MINUTE, 10 * MINUTE + MINUTE / 2)],
+            &plan(),
+            MINUTE,
+            10 * MINUTE + MINUTE / 2,
+        );
+        assert_eq!(report.outcome, CacheExplainOutcome::Disqualified);
+        let value = json::to_value(&report).unwrap();
+        assert_eq!(value["outcome"], "disqualified");
+        assert_eq!(value["disqualification"]["reason"], "discard_window");
+
+        let report = disqualified(
+            CacheExplainReport::default(),
+            CacheDisqualification::MultiStream {
+                streams: vec!["a".to_string(), "b".to_string()],
+            },
+        );
+        let value = json::to_value(&report).unwrap();
+        assert_eq!(value["outcome"], "disqualified");
+        assert_eq!(value["disqualification"]["reason"], "multi_stream");
+        assert_eq!(value["disqualification"]["streams"][1], "b");
+    }
+}
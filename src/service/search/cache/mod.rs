@@ -13,15 +13,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::str::FromStr;
+use std::{future::Future, str::FromStr, time::Duration};
 
 use chrono::{TimeZone, Utc};
 use config::{
     get_config,
     meta::{
-        search::{self, ResponseTook},
+        search::{
+            self, CacheDetail, CacheSegment, CacheSegmentSource, ClampedTimeRange, FunctionError,
+            PruningStats, ResponseTook,
+        },
         self_reporting::usage::{RequestStats, UsageType},
-        sql::resolve_stream_names,
+        sql::{resolve_stream_names, resolve_stream_names_with_types},
         stream::StreamType,
     },
     metrics,
@@ -30,24 +33,29 @@ use config::{
 };
 use infra::{
     cache::{file_data::disk::QUERY_RESULT_CACHE, meta::ResultCacheMeta},
-    errors::Error,
+    errors::{Error, ErrorCodes},
 };
-use proto::cluster_rpc::SearchQuery;
 use result_utils::get_ts_value;
 use tracing::Instrument;
 
 use crate::{
     common::{
         meta::search::{CachedQueryResponse, MultiCachedQueryResponse, QueryDelta},
-        utils::{functions, http::get_work_group},
+        utils::{functions, http::get_work_group, stream::get_settings_max_query_range},
     },
     service::{
-        search::{self as SearchService, cache::cacher::check_cache},
+        search::{
+            self as SearchService,
+            cache::cacher::check_cache,
+            data_range, routing,
+            sql::{normalize_histogram_timezone, RE_HISTOGRAM},
+        },
         self_reporting::{http_report_metrics, report_request_usage_stats},
     },
 };
 
 pub mod cacher;
+pub mod explain;
 pub mod multi;
 pub mod result_utils;
 
@@ -58,36 +66,73 @@ pub async fn search(
     stream_type: StreamType,
     user_id: Option<String>,
     in_req: &search::Request,
-    range_error: String,
 ) -> Result<search::Response, Error> {
+    // released on every return, including errors and cancellation
+    let _permit = SearchService::concurrency::acquire(org_id).await?;
     let start = std::time::Instant::now();
     let started_at = Utc::now().timestamp_micros();
     let cfg = get_config();
-    // result cache can be enable only when its from the start
-    let use_cache = if in_req.query.from == 0 {
-        in_req.use_cache.unwrap_or(false)
-    } else {
-        false
-    };
-
     // Result caching check start
     let mut origin_sql = in_req.query.sql.clone();
     origin_sql = origin_sql.replace('\n', " ");
+    let (use_cache, limit_warnings) = use_result_cache(in_req, &origin_sql);
     let is_aggregate = is_aggregate_query(&origin_sql).unwrap_or_default();
-    let (stream_name, all_streams) = match resolve_stream_names(&origin_sql) {
-        // TODO: cache don't not support multiple stream names
-        Ok(v) => (v[0].clone(), v.join(",")),
-        Err(e) => {
-            return Err(Error::Message(e.to_string()));
-        }
-    };
+    // streams qualified with a stream type, e.g. `"traces"."spans"`, keep their own type
+    let (stream_names, stream_name, all_streams) =
+        match resolve_stream_names_with_types(&origin_sql, stream_type) {
+            // TODO: cache don't not support multiple stream names
+            Ok(v) => (
+                v.clone(),
+                v[0].0.clone(),
+                v.iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            Err(e) => {
+                return Err(Error::Message(e.to_string()));
+            }
+        };
 
     let mut req = in_req.clone();
+    let timeout = SearchService::get_query_timeout(req.timeout);
+    req.timeout = timeout as i64;
+    // validate the requested regions and clusters and route to the effective set
+    let routing = if req.regions.is_empty() && req.clusters.is_empty() {
+        None
+    } else {
+        let routing = routing::resolve(&req.regions, &req.clusters).await?;
+        req.regions = routing.regions.clone();
+        req.clusters = routing.clusters.clone();
+        Some(routing)
+    };
+    // SQL may contain multiple stream names, apply the max query range of each
+    let mut range_error = String::new();
+    let stream_settings =
+        futures::future::join_all(stream_names.iter().map(|(stream, stream_type)| {
+            infra::schema::get_settings(org_id, stream, *stream_type)
+        }))
+        .await;
+    for settings in stream_settings {
+        let max_query_range = match settings {
+            Some(settings) => {
+                get_settings_max_query_range(settings.max_query_range, org_id, user_id.as_deref())
+                    .await
+            }
+            None => 0,
+        };
+        match req.query.validate_time_range(max_query_range) {
+            Ok(Some(err)) => range_error = err,
+            Ok(None) => {}
+            Err(e) => return Err(Error::ErrorCode(ErrorCodes::InvalidParams(e.to_string()))),
+        }
+    }
     let mut query_fn = req
         .query
         .query_fn
         .as_ref()
         .and_then(|v| base64::decode_url(v).ok());
+    let function_names = functions::get_used_transform_keys(org_id, &req.query.sql).await;
     let action = req
         .query
         .action_id
@@ -102,12 +147,19 @@ pub async fn search(
     if let Some(action_id) = action {
         hash_body.push(action_id.to_string());
     }
-    if !req.regions.is_empty() {
-        hash_body.extend(req.regions.clone());
+    // the count of a query is cached apart from its hits
+    if req.query.track_total_hits {
+        hash_body.push("track_total_hits".to_string());
     }
-    if !req.clusters.is_empty() {
-        hash_body.extend(req.clusters.clone());
+    if let Some(timezone) = req
+        .query
+        .timezone
+        .as_deref()
+        .and_then(normalize_histogram_timezone)
+    {
+        hash_body.push(timezone);
     }
+    hash_body.extend(routing::cache_hash_values(&req.regions, &req.clusters));
     let mut h = config::utils::hash::gxhash::new();
     let hashed_query = h.sum64(&hash_body.join(","));
 
@@ -118,6 +170,7 @@ pub async fn search(
         "{}/{}/{}/{}",
         org_id, stream_type, stream_name, hashed_query
     );
+    let (query_start_time, query_end_time) = (req.query.start_time, req.query.end_time);
     let mut c_resp: MultiCachedQueryResponse = if use_cache {
         // cache layer
         check_cache(
@@ -132,8 +185,7 @@ pub async fn search(
         )
         .await
     } else {
-        let query: SearchQuery = req.query.clone().into();
-        match crate::service::search::Sql::new(&query, org_id, stream_type).await {
+        match crate::service::search::Sql::new(&req.query, org_id, stream_type).await {
             Ok(v) => {
                 let (ts_column, is_descending) =
                     cacher::get_ts_col_order_by(&v, TIMESTAMP_COL_NAME, is_aggregate)
@@ -171,10 +223,42 @@ pub async fn search(
         );
     }
 
+    // skip the trailing range without data, the results are still
+    // cached for the requested range. The buckets of an automatic histogram
+    // interval depend on the searched range, those are clamped by their partitions.
+    let range_clamped_to_data = if RE_HISTOGRAM.is_match(&origin_sql) {
+        None
+    } else {
+        data_range::clamp_to_streams(org_id, &stream_names, (query_start_time, query_end_time), 1)
+    };
+    if let Some(range) = range_clamped_to_data {
+        c_resp.deltas = clamp_deltas(c_resp.deltas, range);
+        if c_resp.deltas.is_empty() && !c_resp.has_cached_data {
+            c_resp.deltas.push(QueryDelta {
+                delta_start_time: range.start,
+                delta_end_time: range.end,
+                delta_removed_hits: false,
+            });
+        }
+    }
+
     // Result caching check ends, start search
     let mut results = Vec::new();
+    let mut timed_out = Vec::new();
+    let mut search_segments = Vec::new();
     let mut work_group_set = Vec::new();
-    let mut res = if !should_exec_query {
+    let is_count = use_cache && req.query.track_total_hits;
+    let mut res = if !should_exec_query && is_count {
+        merge_count_response(
+            &c_resp
+                .cached_response
+                .iter()
+                .map(|r| r.cached_response.clone())
+                .collect::<Vec<_>>(),
+            &[],
+            c_resp.took,
+        )
+    } else if !should_exec_query {
         merge_response(
             trace_id,
             &mut c_resp
@@ -187,7 +271,7 @@ pub async fn search(
             c_resp.limit,
             c_resp.is_descending,
             c_resp.took,
-        )
+        )?
     } else {
         if let Some(vrl_function) = &query_fn {
             if !vrl_function.trim().ends_with('.') {
@@ -196,11 +280,8 @@ pub async fn search(
         }
         req.query.query_fn = query_fn;
 
-        for fn_name in functions::get_all_transform_keys(org_id).await {
-            if req.query.sql.contains(&format!("{}(", fn_name)) {
-                req.query.uses_zo_fn = true;
-                break;
-            }
+        if !function_names.is_empty() {
+            req.query.uses_zo_fn = true;
         }
 
         metrics::QUERY_PENDING_NUMS
@@ -209,6 +290,13 @@ pub async fn search(
 
         // get a local search queue lock
         #[cfg(not(feature = "enterprise"))]
+        let queued = SearchService::queue::enqueue(
+            trace_id,
+            org_id,
+            user_id.as_deref(),
+            &hashed_query.to_string(),
+        );
+        #[cfg(not(feature = "enterprise"))]
         let locker = SearchService::QUEUE_LOCKER.clone();
         #[cfg(not(feature = "enterprise"))]
         let locker = locker.lock().await;
@@ -217,6 +305,8 @@ pub async fn search(
             drop(locker);
         }
         #[cfg(not(feature = "enterprise"))]
+        queued.start();
+        #[cfg(not(feature = "enterprise"))]
         let took_wait = start.elapsed().as_millis() as usize;
         #[cfg(feature = "enterprise")]
         let took_wait = 0;
@@ -230,29 +320,27 @@ pub async fn search(
             .with_label_values(&[org_id])
             .dec();
 
-        let mut tasks = Vec::new();
-
         log::info!("[trace_id {trace_id}] deltas are : {:?}", c_resp.deltas);
         c_resp.deltas.sort();
         c_resp.deltas.dedup();
 
-        for (i, delta) in c_resp.deltas.into_iter().enumerate() {
-            let mut req = req.clone();
-            let org_id = org_id.to_string();
-            let trace_id = format!("{}-{}", trace_id, i);
-            let user_id = user_id.clone();
-
-            let enter_span = tracing::span::Span::current();
-            let task = tokio::task::spawn(
-                (async move {
-                    let trace_id = trace_id.clone();
+        let has_cached_data = c_resp.has_cached_data;
+        let (searched, delta_timed_out) = search_deltas(
+            std::mem::take(&mut c_resp.deltas),
+            Duration::from_secs(timeout),
+            |i, delta| {
+                let mut req = req.clone();
+                let org_id = org_id.to_string();
+                let trace_id = format!("{}-{}", trace_id, i);
+                let user_id = user_id.clone();
+                async move {
                     req.query.start_time = delta.delta_start_time;
                     req.query.end_time = delta.delta_end_time;
 
                     let cfg = get_config();
                     if cfg.common.result_cache_enabled
                         && cfg.common.print_key_sql
-                        && c_resp.has_cached_data
+                        && has_cached_data
                     {
                         log::info!(
                             "[trace_id {trace_id}] Query new start time: {}, end time : {}",
@@ -262,19 +350,49 @@ pub async fn search(
                     }
 
                     SearchService::search(&trace_id, &org_id, stream_type, user_id, &req).await
-                })
-                .instrument(enter_span),
+                }
+            },
+        )
+        .await?;
+        timed_out = delta_timed_out;
+        if !timed_out.is_empty() {
+            log::warn!(
+                "[trace_id {trace_id}] search of deltas {:?} timed out after {timeout}s",
+                timed_out
             );
-            tasks.push(task);
-        }
-
-        for task in tasks {
-            results.push(task.await.map_err(|e| Error::Message(e.to_string()))??);
+            if searched.is_empty() && !has_cached_data {
+                return Err(SearchService::search_timeout_error(timeout));
+            }
         }
+        let (delta_ranges, searched): (Vec<_>, Vec<_>) = searched.into_iter().unzip();
+        results = searched;
         for res in &results {
             work_group_set.push(res.work_group.clone());
         }
-        if c_resp.has_cached_data {
+        if req.query.debug_cache {
+            search_segments = delta_ranges
+                .into_iter()
+                .zip(results.iter())
+                .map(|((start_time, end_time), res)| CacheSegment {
+                    start_time,
+                    end_time,
+                    hits: res.hits.len(),
+                    source: CacheSegmentSource::Search,
+                })
+                .collect();
+        }
+        let merged = c_resp.has_cached_data.then(|| {
+            if is_count {
+                return Ok(merge_count_response(
+                    &c_resp
+                        .cached_response
+                        .iter()
+                        .map(|r| r.cached_response.clone())
+                        .collect::<Vec<_>>(),
+                    &results,
+                    c_resp.took,
+                ));
+            }
             merge_response(
                 trace_id,
                 &mut c_resp
@@ -288,21 +406,64 @@ pub async fn search(
                 c_resp.is_descending,
                 c_resp.took,
             )
-        } else {
-            let mut reps = results[0].clone();
-            sort_response(c_resp.is_descending, &mut reps, &c_resp.ts_column);
-            reps
+        });
+        match merged {
+            Some(Ok(merged)) => merged,
+            Some(Err(e)) => {
+                // cached and fresh buckets don't line up, search the whole range instead
+                log::warn!("[trace_id {trace_id}] {e}, falling back to full query");
+                req.query.start_time = query_start_time;
+                req.query.end_time = query_end_time;
+                let mut reps = tokio::time::timeout(
+                    Duration::from_secs(timeout),
+                    SearchService::search(trace_id, org_id, stream_type, user_id.clone(), &req),
+                )
+                .await
+                .map_err(|_| SearchService::search_timeout_error(timeout))??;
+                c_resp.cached_response.clear();
+                if req.query.debug_cache {
+                    search_segments = vec![CacheSegment {
+                        start_time: query_start_time,
+                        end_time: query_end_time,
+                        hits: reps.hits.len(),
+                        source: CacheSegmentSource::Search,
+                    }];
+                }
+                results = vec![reps.clone()];
+                sort_response(c_resp.is_descending, &mut reps, &c_resp.ts_column);
+                reps
+            }
+            None => {
+                let mut reps = results[0].clone();
+                sort_response(c_resp.is_descending, &mut reps, &c_resp.ts_column);
+                reps
+            }
         }
     };
 
+    if !timed_out.is_empty() {
+        set_search_timeout(
+            &mut res,
+            timeout,
+            (query_start_time, query_end_time),
+            &timed_out,
+        );
+    }
+
     // do search
     let time = start.elapsed().as_secs_f64();
     http_report_metrics(start, org_id, stream_type, "", "200", "_search");
     res.set_trace_id(trace_id.to_string());
     res.set_local_took(start.elapsed().as_millis() as usize, ext_took_wait);
+    if req.query.debug_cache {
+        res.cache_detail = Some(build_cache_detail(&c_resp.cached_response, search_segments));
+    }
 
+    // the cached interval is the single interval of the query, it must not
+    // replace the intervals of a query with several histograms
     if is_aggregate
         && res.histogram_interval.is_none()
+        && res.histogram_intervals.is_empty()
         && !c_resp.ts_column.is_empty()
         && c_resp.histogram_interval > -1
     {
@@ -311,12 +472,13 @@ pub async fn search(
 
     let work_group = get_work_group(work_group_set);
     let num_fn = req.query.query_fn.is_some() as u16;
-    let req_stats = RequestStats {
+    let mut req_stats = RequestStats {
         records: res.hits.len() as i64,
         response_time: time,
         size: res.scan_size as f64,
         request_body: Some(req.query.sql),
         function: req.query.query_fn,
+        function_names: (!function_names.is_empty()).then_some(function_names),
         user_email: user_id,
         min_ts: Some(req.query.start_time),
         max_ts: Some(req.query.end_time),
@@ -335,6 +497,7 @@ pub async fn search(
         result_cache_ratio: Some(res.result_cache_ratio),
         ..Default::default()
     };
+    req_stats.set_search_resources(&res);
     report_request_usage_stats(
         req_stats,
         org_id,
@@ -355,14 +518,11 @@ pub async fn search(
         };
     }
     if !range_error.is_empty() {
-        res.is_partial = true;
-        res.function_error = if res.function_error.is_empty() {
-            range_error
-        } else {
-            format!("{} \n {}", range_error, res.function_error)
-        };
-        res.new_start_time = Some(req.query.start_time);
-        res.new_end_time = Some(req.query.end_time);
+        res.set_range_error(range_error, req.query.start_time, req.query.end_time);
+    }
+    // responses served from the result cache don't pass the sql planner
+    for warning in limit_warnings {
+        res.add_warning(warning);
     }
 
     // There are 3 types of partial responses:
@@ -372,10 +532,28 @@ pub async fn search(
     // Cache partial results only if there is a range error
     let skip_cache_results = (res.is_partial
         && (res.new_start_time.is_none() || res.new_end_time.is_none()))
-        || (!res.function_error.is_empty() && res.function_error.contains("vrl"));
+        || !res.function_errors.is_empty()
+        || !timed_out.is_empty();
 
     // result cache save changes start
     if cfg.common.result_cache_enabled
+        && should_exec_query
+        && c_resp.cache_query_response
+        && !skip_cache_results
+        && is_count
+        && !results.is_empty()
+    {
+        write_count_result(
+            trace_id,
+            query_start_time,
+            query_end_time,
+            &res,
+            file_path,
+            is_aggregate,
+            c_resp.is_descending,
+        )
+        .await;
+    } else if cfg.common.result_cache_enabled
         && should_exec_query
         && c_resp.cache_query_response
         && !skip_cache_results
@@ -396,9 +574,106 @@ pub async fn search(
     }
     // result cache save changes Ends
 
+    res.routing = routing;
+    res.range_clamped_to_data = range_clamped_to_data;
     Ok(res)
 }
 
+/// Searches the deltas concurrently, each one for at most `timeout`. Returns the
+/// responses along with the time range of each, and the time ranges which timed out.
+async fn search_deltas<F, Fut>(
+    deltas: Vec<QueryDelta>,
+    timeout: Duration,
+    search: F,
+) -> Result<(Vec<((i64, i64), search::Response)>, Vec<(i64, i64)>), Error>
+where
+    F: Fn(usize, QueryDelta) -> Fut,
+    Fut: Future<Output = Result<search::Response, Error>> + Send + 'static,
+{
+    let mut tasks = Vec::with_capacity(deltas.len());
+    for (i, delta) in deltas.into_iter().enumerate() {
+        let range = (delta.delta_start_time, delta.delta_end_time);
+        let enter_span = tracing::span::Span::current();
+        let task = tokio::task::spawn(
+            tokio::time::timeout(timeout, search(i, delta)).instrument(enter_span),
+        );
+        tasks.push((range, task));
+    }
+
+    let mut searched = Vec::with_capacity(tasks.len());
+    let mut timed_out = Vec::new();
+    for (range, task) in tasks {
+        match task.await.map_err(|e| Error::Message(e.to_string()))? {
+            Ok(res) => searched.push((range, res?)),
+            Err(_) => timed_out.push(range),
+        }
+    }
+    Ok((searched, timed_out))
+}
+
+/// Marks the response as partial because the search of some of its time ranges
+/// timed out, with the time range which was searched if it's a single one.
+fn set_search_timeout(
+    res: &mut search::Response,
+    timeout: u64,
+    (start_time, end_time): (i64, i64),
+    timed_out: &[(i64, i64)],
+) {
+    let ranges = timed_out
+        .iter()
+        .map(|(start, end)| format!("{start} - {end}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    res.set_partial(
+        true,
+        format!("Search query timed out after {timeout}s for the time ranges {ranges}"),
+    );
+
+    let mut timed_out = timed_out.to_vec();
+    timed_out.sort();
+    let mut completed = Vec::new();
+    let mut from = start_time;
+    for (start, end) in timed_out {
+        if start > from {
+            completed.push((from, start));
+        }
+        from = from.max(end);
+    }
+    if from < end_time {
+        completed.push((from, end_time));
+    }
+    if let [(start, end)] = completed[..] {
+        res.new_start_time = Some(start);
+        res.new_end_time = Some(end);
+    }
+}
+
+/// Narrows the deltas to the time range with data, dropping the deltas outside of it.
+fn clamp_deltas(deltas: Vec<QueryDelta>, range: ClampedTimeRange) -> Vec<QueryDelta> {
+    deltas
+        .into_iter()
+        .filter_map(|delta| {
+            let (delta_start_time, delta_end_time) =
+                range.intersect(delta.delta_start_time, delta.delta_end_time)?;
+            Some(QueryDelta {
+                delta_start_time,
+                delta_end_time,
+                ..delta
+            })
+        })
+        .collect()
+}
+
+/// Whether the result cache can serve the request and the warnings about the
+/// size and from of the request the LIMIT and OFFSET of the sql override. The
+/// result cache can be enabled only when the search is from the start.
+fn use_result_cache(req: &search::Request, sql: &str) -> (bool, Vec<String>) {
+    let (_, offset, warnings) =
+        SearchService::sql::get_search_limit(sql, req.query.size, req.query.from);
+    let use_cache = offset == 0 && req.use_cache.unwrap_or(false);
+    (use_cache, warnings)
+}
+
 // based on _timestamp of first record in config::meta::search::Response either add it in start
 // or end to cache response
 pub fn merge_response(
@@ -409,16 +684,33 @@ pub fn merge_response(
     limit: i64,
     is_descending: bool,
     cache_took: usize,
-) -> config::meta::search::Response {
+) -> Result<config::meta::search::Response, Error> {
+    // responses without hits can still carry function errors
+    let fn_errors =
+        MergedFunctionErrors::collect(cache_responses.iter().chain(search_response.iter()));
+    let warnings = merge_warnings(cache_responses.iter().chain(search_response.iter()));
+    let pruning_stats = merge_pruning_stats(search_response);
+    let (file_count, peak_memory) = merge_searched_resources(search_response);
+
     cache_responses.retain(|res| !res.hits.is_empty());
 
     search_response.retain(|res| !res.hits.is_empty());
 
     if cache_responses.is_empty() && search_response.is_empty() {
-        return config::meta::search::Response::default();
+        let mut res = config::meta::search::Response::default();
+        fn_errors.apply(&mut res);
+        res.warnings = warnings;
+        res.pruning_stats = pruning_stats;
+        res.file_count = file_count;
+        res.peak_memory = peak_memory;
+        return Ok(res);
+    }
+    if let Some((cached, searched)) = histogram_interval_mismatch(cache_responses, search_response)
+    {
+        return Err(Error::Message(format!(
+            "can't merge responses with histogram interval {cached}s and {searched}s"
+        )));
     }
-    let mut fn_error = String::new();
-
     let mut cache_response = if cache_responses.is_empty() {
         config::meta::search::Response::default()
     } else {
@@ -434,9 +726,9 @@ pub fn merge_response(
             }
             resp.hits.extend(res.hits.clone());
             resp.histogram_interval = res.histogram_interval;
-            if !res.function_error.is_empty() {
-                fn_error = res.function_error.clone();
-            }
+            resp.histogram_intervals = res.histogram_intervals.clone();
+            resp.histogram_timezone = res.histogram_timezone.clone();
+            resp.histogram_calendar_interval = res.histogram_calendar_interval.clone();
         }
         resp.took = cache_took;
         resp
@@ -456,18 +748,21 @@ pub fn merge_response(
             cache_response.scan_size += res.scan_size;
             cache_response.took += res.took;
             cache_response.histogram_interval = res.histogram_interval;
-            if !res.function_error.is_empty() {
-                fn_error = res.function_error.clone();
-            }
+            cache_response.histogram_intervals = res.histogram_intervals.clone();
+            cache_response.histogram_timezone = res.histogram_timezone.clone();
+            cache_response.histogram_calendar_interval = res.histogram_calendar_interval.clone();
         }
-        cache_response.function_error = fn_error;
-        return cache_response;
+        fn_errors.apply(&mut cache_response);
+        cache_response.warnings = warnings;
+        cache_response.pruning_stats = pruning_stats;
+        cache_response.file_count = file_count;
+        cache_response.peak_memory = peak_memory;
+        return Ok(cache_response);
     }
     let cache_hits_len = cache_response.hits.len();
 
     cache_response.scan_size = 0;
 
-    let mut files_cache_ratio = 0;
     let mut result_cache_len = 0;
 
     let mut res_took = ResponseTook::default();
@@ -476,8 +771,10 @@ pub fn merge_response(
         cache_response.total += res.total;
         cache_response.scan_size += res.scan_size;
         cache_response.took += res.took;
-        files_cache_ratio += res.cached_ratio;
         cache_response.histogram_interval = res.histogram_interval;
+        cache_response.histogram_intervals = res.histogram_intervals.clone();
+        cache_response.histogram_timezone = res.histogram_timezone.clone();
+        cache_response.histogram_calendar_interval = res.histogram_calendar_interval.clone();
 
         result_cache_len += res.total;
 
@@ -495,9 +792,6 @@ pub fn merge_response(
             res_took.total += took_details.total;
             res_took.nodes.append(&mut took_details.nodes);
         }
-        if !res.function_error.is_empty() {
-            fn_error = res.function_error.clone();
-        }
 
         cache_response.hits.extend(res.hits.clone());
     }
@@ -510,9 +804,7 @@ pub fn merge_response(
         cache_response.total = cache_response.hits.len();
     }
 
-    if !search_response.is_empty() {
-        cache_response.cached_ratio = files_cache_ratio / search_response.len();
-    }
+    cache_response.cached_ratio = weighted_cached_ratio(search_response);
     cache_response.size = cache_response.hits.len() as i64;
     log::info!(
         "[trace_id {trace_id}] cache_response.hits.len: {}, Result cache len: {}",
@@ -524,10 +816,184 @@ pub fn merge_response(
     cache_response.result_cache_ratio = (((cache_hits_len as f64) * 100_f64)
         / ((result_cache_len + cache_hits_len) as f64))
         as usize;
-    if !fn_error.is_empty() {
-        cache_response.function_error = fn_error;
+    fn_errors.apply(&mut cache_response);
+    cache_response.warnings = warnings;
+    cache_response.pruning_stats = pruning_stats;
+    cache_response.file_count = file_count;
+    cache_response.peak_memory = peak_memory;
+    Ok(cache_response)
+}
+
+/// Merges the responses of a count query. Each one holds the count of its own
+/// time range, so the counts are summed rather than their hits concatenated.
+pub fn merge_count_response(
+    cache_responses: &[search::Response],
+    search_responses: &[search::Response],
+    cache_took: usize,
+) -> search::Response {
+    let cached_total = cache_responses.iter().map(|res| res.total).sum::<usize>();
+    let searched_total = search_responses.iter().map(|res| res.total).sum::<usize>();
+    let total = cached_total + searched_total;
+
+    let mut res = search::Response::default();
+    res.add_hit(&json::json!({ "zo_sql_num": total }));
+    res.total = total;
+    res.took = cache_took;
+    let mut res_took = ResponseTook::default();
+    for search_res in search_responses {
+        res.took += search_res.took;
+        res.scan_size += search_res.scan_size;
+        res.scan_records += search_res.scan_records;
+        if let Some(took_details) = &search_res.took_detail {
+            res_took.cluster_total += took_details.cluster_total;
+            res_took.cluster_wait_queue += took_details.cluster_wait_queue;
+            res_took.idx_took += took_details.idx_took;
+            res_took.wait_queue += took_details.wait_queue;
+            res_took.total += took_details.total;
+            res_took.nodes.extend(took_details.nodes.iter().cloned());
+        }
+        if search_res.is_partial {
+            res.is_partial = true;
+        }
+    }
+    MergedFunctionErrors::collect(cache_responses.iter().chain(search_responses)).apply(&mut res);
+    res.warnings = merge_warnings(cache_responses.iter().chain(search_responses));
+    res.took_detail = Some(res_took);
+    res.pruning_stats = merge_pruning_stats(search_responses);
+    (res.file_count, res.peak_memory) = merge_searched_resources(search_responses);
+    res.cached_ratio = weighted_cached_ratio(search_responses);
+    res.result_cache_ratio = if search_responses.is_empty() {
+        100
+    } else if total == 0 {
+        0
+    } else {
+        cached_total * 100 / total
+    };
+    res
+}
+
+/// Adds up the files scanned and the peak memory of the searched time ranges,
+/// which are searched in parallel.
+fn merge_searched_resources(search_responses: &[search::Response]) -> (usize, usize) {
+    search_responses
+        .iter()
+        .fold((0, 0), |(file_count, peak_memory), res| {
+            (file_count + res.file_count, peak_memory + res.peak_memory)
+        })
+}
+
+/// Adds up the parquet pruning of the searched time ranges, the cached ones
+/// didn't scan any files.
+fn merge_pruning_stats(search_responses: &[search::Response]) -> Option<PruningStats> {
+    search_responses
+        .iter()
+        .filter_map(|res| res.pruning_stats.as_ref())
+        .fold(None, |merged: Option<PruningStats>, stats| {
+            let mut merged = merged.unwrap_or_default();
+            merged.merge(stats);
+            Some(merged)
+        })
+}
+
+/// The distinct warnings of several responses being merged.
+fn merge_warnings<'a>(responses: impl IntoIterator<Item = &'a search::Response>) -> Vec<String> {
+    let mut merged = search::Response::default();
+    for warning in responses.into_iter().flat_map(|res| res.warnings.iter()) {
+        merged.add_warning(warning.clone());
+    }
+    merged.warnings
+}
+
+/// The query function errors of several responses being merged.
+#[derive(Default)]
+struct MergedFunctionErrors {
+    errors: Vec<FunctionError>,
+    /// errors reported without a time range, e.g. by a partial cluster search
+    messages: Vec<String>,
+}
+
+impl MergedFunctionErrors {
+    fn collect<'a>(responses: impl IntoIterator<Item = &'a search::Response>) -> Self {
+        let mut merged = Self::default();
+        for res in responses {
+            for err in &res.function_errors {
+                if !merged.errors.contains(err) {
+                    merged.errors.push(err.clone());
+                }
+            }
+            if res.function_errors.is_empty()
+                && !res.function_error.is_empty()
+                && !merged.messages.contains(&res.function_error)
+            {
+                merged.messages.push(res.function_error.clone());
+            }
+        }
+        merged
+    }
+
+    /// Sets the errors on the merged response, which is partial if any of the
+    /// responses had a function error.
+    fn apply(self, res: &mut search::Response) {
+        if !self.errors.is_empty() {
+            res.is_partial = true;
+        }
+        let mut messages = self.messages;
+        messages.extend(self.errors.iter().map(ToString::to_string));
+        if !messages.is_empty() {
+            res.function_error = messages.join(" \n ");
+        }
+        res.function_errors = self.errors;
+    }
+}
+
+/// Returns the first two differing histogram intervals found across
+/// `cache_responses` and `search_responses`, if any.
+fn histogram_interval_mismatch(
+    cache_responses: &[search::Response],
+    search_responses: &[search::Response],
+) -> Option<(i64, i64)> {
+    let mut intervals = cache_responses
+        .iter()
+        .chain(search_responses)
+        .filter_map(|res| res.histogram_interval);
+    let first = intervals.next()?;
+    intervals
+        .find(|interval| *interval != first)
+        .map(|interval| (first, interval))
+}
+
+/// Returns the files cache ratio of `responses` weighted by the number of hits
+/// each of them contributed.
+fn weighted_cached_ratio(responses: &[search::Response]) -> usize {
+    let total_hits = responses.iter().map(|res| res.hits.len()).sum::<usize>();
+    if total_hits == 0 {
+        return 0;
     }
-    cache_response
+    responses
+        .iter()
+        .map(|res| res.cached_ratio * res.hits.len())
+        .sum::<usize>()
+        / total_hits
+}
+
+/// Lists the time ranges served from the result cache together with the ones
+/// searched, ordered by start time.
+fn build_cache_detail(
+    cached_responses: &[CachedQueryResponse],
+    search_segments: Vec<CacheSegment>,
+) -> CacheDetail {
+    let mut segments = cached_responses
+        .iter()
+        .map(|c| CacheSegment {
+            start_time: c.response_start_time,
+            end_time: c.response_end_time,
+            hits: c.cached_response.hits.len(),
+            source: CacheSegmentSource::Cache,
+        })
+        .chain(search_segments)
+        .collect::<Vec<_>>();
+    segments.sort_by_key(|s| (s.start_time, s.end_time));
+    CacheDetail { segments }
 }
 
 fn sort_response(is_descending: bool, cache_response: &mut search::Response, ts_column: &str) {
@@ -577,20 +1043,15 @@ pub async fn _write_results(
         return;
     }
 
-    let last_rec_ts = get_ts_value(ts_column, local_resp.hits.last().unwrap());
-    let first_rec_ts = get_ts_value(ts_column, local_resp.hits.first().unwrap());
-
-    let smallest_ts = std::cmp::min(first_rec_ts, last_rec_ts);
+    let (smallest_ts, largest_ts) = hits_time_bounds(ts_column, &local_resp.hits);
     let discard_duration = get_config().common.result_cache_discard_duration * 1000 * 1000;
 
-    if (last_rec_ts - first_rec_ts).abs() < discard_duration
+    if largest_ts - smallest_ts < discard_duration
         && smallest_ts > Utc::now().timestamp_micros() - discard_duration
     {
         return;
     }
 
-    let largest_ts = std::cmp::max(first_rec_ts, last_rec_ts);
-
     let cache_end_time = if largest_ts > 0 && largest_ts < req_query_end_time {
         largest_ts
     } else {
@@ -647,34 +1108,27 @@ pub async fn _write_results(
 /// Caches search results to disk after applying filtering and validation strategies.
 ///
 /// # Caching Strategy
-/// 1. **Select Hit for Deduplication**:
-///    - Selects either the first or last record based on the `is_descending` flag.
-///      - `is_descending = true`: Selects the last record.
-///      - `is_descending = false`: Selects the first record.
+/// 1. **Remove the Boundary Second of Time Ordered Hits**: see [`discard_boundary_hits`].
+///    - Only applies when the hits are sorted by a timestamp `ts_column`. Other responses, like
+///      aggregates grouped by something else than time, can't be trimmed and are cached whole.
 ///
-/// 2. **Remove All Hits Within the Same Date, Hour, Minute, and Second for Deduplication**:
-///    - Identifies the timestamp of the hit to remove (based on the `is_descending` flag, either
-///      the first or the last record is chosen).
-///    - Removes all hits that match the exact same date, hour, minute, and second
-///      (`YYYY-MM-DDTHH:MM:SS`) as the identified hit.
-///      - Example: If the timestamp to remove is `2024-12-06T04:15:30`, only hits with the exact
-///        timestamp `2024-12-06T04:15:30` are removed, while others with different seconds (e.g.,
-///        `2024-12-06T04:15:29` or `2024-12-06T04:15:31`) are retained.
-///
-/// 3. **Skip Caching for Empty or Insufficient Hits**:
+/// 2. **Skip Caching for Empty or Insufficient Hits**:
 ///    - If no hits remain after removing one, caching is skipped.
 ///    - Logs a message: `"No hits found for caching, skipping caching."`
 ///
-/// 4. **Discard Short Time Ranges**:
+/// 3. **Discard Short Time Ranges**:
 ///    - Skips caching if the difference between the first and last record timestamps is smaller
 ///      than the configured `discard_duration`.
+///    - A response cached whole is skipped when the query range reaches into the discard window.
 ///
-/// 5. **Adjust Cache Time Range**:
-///    - Adjusts the cache start and end times based on the smallest and largest timestamps:
+/// 4. **Adjust Cache Time Range**:
+///    - Adjusts the cache start and end times of time ordered hits based on the smallest and
+///      largest timestamps:
 ///      - `start_time = max(smallest_ts, req_query_start_time)`
 ///      - `end_time = min(largest_ts, req_query_end_time)`
+///    - A response cached whole covers the query range.
 ///
-/// 6. **Cache to Disk**:
+/// 5. **Cache to Disk**:
 ///    - Saves the filtered response to a file named:
 ///      `"<start_time>_<end_time>_<is_aggregate>_<is_descending>.json"`.
 #[allow(clippy::too_many_arguments)]
@@ -689,38 +1143,9 @@ pub async fn write_results_v2(
     is_descending: bool,
 ) {
     let mut local_resp = res.clone();
-    let remove_hit = if is_descending {
-        local_resp.hits.last()
-    } else {
-        local_resp.hits.first()
-    };
-
-    if !local_resp.hits.is_empty() && remove_hit.is_some() {
-        let ts_value_to_remove = remove_hit.unwrap().get(ts_column).cloned();
-
-        if let Some(ts_value) = ts_value_to_remove {
-            // Extract the date, hour, minute from the timestamp
-            if let Some(ts_datetime) = convert_ts_value_to_datetime(&ts_value) {
-                // Extract the target date, hour, minute, and second (e.g., "2024-12-06T04:15:23")
-                let target_date_hour_minute_second =
-                    ts_datetime.format("%Y-%m-%dT%H:%M:%S").to_string();
-
-                // Retain only the hits that do NOT fall within the
-                // same date, hour, minute as the hit to remove
-                local_resp.hits.retain(|hit| {
-                    if let Some(hit_ts) = hit.get(ts_column) {
-                        if let Some(hit_ts_datetime) = convert_ts_value_to_datetime(hit_ts) {
-                            // Extract the date, hour, minute, and second for the current hit
-                            let hit_date_hour_minute_second =
-                                hit_ts_datetime.format("%Y-%m-%dT%H:%M:%S").to_string();
-                            return hit_date_hour_minute_second != target_date_hour_minute_second;
-                        }
-                    }
-                    false
-                });
-            }
-        }
-
+    let is_time_ordered =
+        discard_boundary_hits(ts_column, &mut local_resp.hits, is_aggregate, is_descending);
+    if is_time_ordered {
         local_resp.total = local_resp.hits.len();
         local_resp.size = local_resp.hits.len() as i64;
     }
@@ -733,30 +1158,34 @@ pub async fn write_results_v2(
         return;
     }
 
-    let last_rec_ts = get_ts_value(ts_column, local_resp.hits.last().unwrap());
-    let first_rec_ts = get_ts_value(ts_column, local_resp.hits.first().unwrap());
-
-    let smallest_ts = std::cmp::min(first_rec_ts, last_rec_ts);
     let discard_duration = get_config().common.result_cache_discard_duration * 1000 * 1000;
+    let (cache_start_time, cache_end_time) = if is_time_ordered {
+        let (smallest_ts, largest_ts) = hits_time_bounds(ts_column, &local_resp.hits);
+        if largest_ts - smallest_ts < discard_duration
+            && smallest_ts > Utc::now().timestamp_micros() - discard_duration
+        {
+            return;
+        }
 
-    if (last_rec_ts - first_rec_ts).abs() < discard_duration
-        && smallest_ts > Utc::now().timestamp_micros() - discard_duration
-    {
-        return;
-    }
-
-    let largest_ts = std::cmp::max(first_rec_ts, last_rec_ts);
-
-    let cache_end_time = if largest_ts > 0 && largest_ts < req_query_end_time {
-        largest_ts
-    } else {
-        req_query_end_time
-    };
-
-    let cache_start_time = if smallest_ts > 0 && smallest_ts > req_query_start_time {
-        smallest_ts
+        let cache_end_time = if largest_ts > 0 && largest_ts < req_query_end_time {
+            largest_ts
+        } else {
+            req_query_end_time
+        };
+        let cache_start_time = if smallest_ts > 0 && smallest_ts > req_query_start_time {
+            smallest_ts
+        } else {
+            req_query_start_time
+        };
+        (cache_start_time, cache_end_time)
     } else {
-        req_query_start_time
+        // nothing was trimmed, so like a count the response may miss records
+        // that are still arriving
+        if req_query_end_time > Utc::now().timestamp_micros() - discard_duration {
+            log::info!("[trace_id {trace_id}] unordered range is too recent for caching, skipping");
+            return;
+        }
+        (req_query_start_time, req_query_end_time)
     };
 
     let file_name = format!(
@@ -800,6 +1229,140 @@ pub async fn write_results_v2(
     });
 }
 
+/// Removes the hits of the second that may still be receiving records from
+/// hits sorted by `ts_column`, to avoid caching a partial final bucket.
+///
+/// - The last hit is selected if `is_descending`, otherwise the first one.
+/// - All hits within the same date, hour, minute, and second (`YYYY-MM-DDTHH:MM:SS`) as the
+///   selected hit are removed.
+///   - Example: If the timestamp to remove is `2024-12-06T04:15:30`, only hits with the exact
+///     timestamp `2024-12-06T04:15:30` are removed, while others with different seconds (e.g.,
+///     `2024-12-06T04:15:29` or `2024-12-06T04:15:31`) are retained.
+///
+/// The hits are left untouched when they aren't sorted by a timestamp
+/// `ts_column` in the `is_descending` order, as the boundary hit could then be
+/// from any bucket. A single aggregate row has no order either. Returns whether
+/// the hits are time ordered.
+fn discard_boundary_hits(
+    ts_column: &str,
+    hits: &mut Vec<json::Value>,
+    is_aggregate: bool,
+    is_descending: bool,
+) -> bool {
+    if ts_column.is_empty() || (is_aggregate && hits.len() < 2) {
+        return false;
+    }
+    let Some(timestamps) = hits
+        .iter()
+        .map(|hit| hit.get(ts_column).and_then(convert_ts_value_to_datetime))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+    let is_sorted = timestamps.windows(2).all(|w| {
+        if is_descending {
+            w[0] >= w[1]
+        } else {
+            w[0] <= w[1]
+        }
+    });
+    if !is_sorted {
+        return false;
+    }
+
+    let remove_ts = if is_descending {
+        timestamps.last()
+    } else {
+        timestamps.first()
+    };
+    if let Some(remove_ts) = remove_ts {
+        // Extract the target date, hour, minute, and second (e.g., "2024-12-06T04:15:23")
+        let target_date_hour_minute_second = remove_ts.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+        // Retain only the hits that do NOT fall within the
+        // same date, hour, minute, and second as the hit to remove
+        let mut timestamps = timestamps.iter();
+        hits.retain(|_| {
+            timestamps.next().is_some_and(|ts| {
+                ts.format("%Y-%m-%dT%H:%M:%S").to_string() != target_date_hour_minute_second
+            })
+        });
+    }
+    true
+}
+
+/// Returns the smallest and the largest timestamp of the sorted hits, read from
+/// the first and the last hit.
+fn hits_time_bounds(ts_column: &str, hits: &[json::Value]) -> (i64, i64) {
+    let (Some(first), Some(last)) = (hits.first(), hits.last()) else {
+        return (0, 0);
+    };
+    let first_rec_ts = get_ts_value(ts_column, first);
+    let last_rec_ts = get_ts_value(ts_column, last);
+    (
+        std::cmp::min(first_rec_ts, last_rec_ts),
+        std::cmp::max(first_rec_ts, last_rec_ts),
+    )
+}
+
+/// Caches the response of a count query for the time range it covers. A
+/// count can't be trimmed to drop records that may still arrive, so ranges
+/// reaching into the discard window aren't cached.
+pub async fn write_count_result(
+    trace_id: &str,
+    req_query_start_time: i64,
+    req_query_end_time: i64,
+    res: &search::Response,
+    file_path: String,
+    is_aggregate: bool,
+    is_descending: bool,
+) {
+    let discard_duration = get_config().common.result_cache_discard_duration * 1000 * 1000;
+    if req_query_start_time >= req_query_end_time
+        || req_query_end_time > Utc::now().timestamp_micros() - discard_duration
+    {
+        log::info!("[trace_id {trace_id}] count range is too recent for caching, skipping");
+        return;
+    }
+
+    let mut local_resp = res.clone();
+    local_resp.took_detail = None;
+    local_resp.cache_detail = None;
+    let file_name = format!(
+        "{}_{}_{}_{}.json",
+        req_query_start_time,
+        req_query_end_time,
+        if is_aggregate { 1 } else { 0 },
+        if is_descending { 1 } else { 0 }
+    );
+    let res_cache = json::to_string(&local_resp).unwrap();
+    let query_key = file_path.replace('/', "_");
+    let trace_id = trace_id.to_string();
+    tokio::spawn(async move {
+        match SearchService::cache::cacher::cache_results_to_disk(
+            &trace_id, &file_path, &file_name, res_cache,
+        )
+        .await
+        {
+            Ok(_) => {
+                let mut w = QUERY_RESULT_CACHE.write().await;
+                w.entry(query_key)
+                    .or_insert_with(Vec::new)
+                    .push(ResultCacheMeta {
+                        start_time: req_query_start_time,
+                        end_time: req_query_end_time,
+                        is_aggregate,
+                        is_descending,
+                    });
+                drop(w);
+            }
+            Err(e) => {
+                log::error!("Cache results to disk failed: {:?}", e);
+            }
+        }
+    });
+}
+
 #[tracing::instrument(name = "service:search:cacher:check_cache_v2", skip_all)]
 pub async fn check_cache_v2(
     trace_id: &str,
@@ -821,32 +1384,20 @@ pub async fn check_cache_v2(
     };
 
     let mut req = in_req.clone();
-    let query_fn = req
-        .query
-        .query_fn
-        .as_ref()
-        .and_then(|v| base64::decode_url(v).ok());
-
-    // calculate hash for the query
-    let mut hash_body = vec![origin_sql.to_string()];
-    if let Some(vrl_function) = &query_fn {
-        hash_body.push(vrl_function.to_string());
-    }
-    if !req.regions.is_empty() {
-        hash_body.extend(req.regions.clone());
+    if !req.regions.is_empty() || !req.clusters.is_empty() {
+        let routing = routing::resolve(&req.regions, &req.clusters).await?;
+        req.regions = routing.regions;
+        req.clusters = routing.clusters;
     }
-    if !req.clusters.is_empty() {
-        hash_body.extend(req.clusters.clone());
+    // the max query range is applied per partition by the caller
+    if let Err(e) = req.query.validate_time_range(0) {
+        return Err(Error::ErrorCode(ErrorCodes::InvalidParams(e.to_string())));
     }
-    let mut h = config::utils::hash::gxhash::new();
-    let hashed_query = h.sum64(&hash_body.join(","));
-
     let mut should_exec_query = true;
-
-    let mut file_path = format!(
-        "{}/{}/{}/{}",
-        org_id, stream_type, stream_name, hashed_query
-    );
+    let mut file_path = get_cache_file_path(org_id, stream_type, &stream_name, &origin_sql, &req);
+    // partitioned searches stream every partition's count separately, so
+    // cached counts are only merged by `search`
+    let use_cache = use_cache && !req.query.track_total_hits;
     Ok(if use_cache {
         let mut resp = check_cache(
             trace_id,
@@ -864,8 +1415,7 @@ pub async fn check_cache_v2(
         resp.file_path = file_path;
         resp
     } else {
-        let query = req.query.into();
-        match crate::service::search::Sql::new(&query, org_id, stream_type).await {
+        match crate::service::search::Sql::new(&req.query, org_id, stream_type).await {
             Ok(v) => {
                 let (ts_column, is_descending) =
                     cacher::get_ts_col_order_by(&v, TIMESTAMP_COL_NAME, is_aggregate)
@@ -886,6 +1436,52 @@ pub async fn check_cache_v2(
     })
 }
 
+/// Returns the values hashed into the result cache key of a query.
+fn get_cache_hash_body(origin_sql: &str, req: &search::Request) -> Vec<String> {
+    let query_fn = req
+        .query
+        .query_fn
+        .as_ref()
+        .and_then(|v| base64::decode_url(v).ok());
+
+    let mut hash_body = vec![origin_sql.to_string()];
+    if let Some(vrl_function) = &query_fn {
+        hash_body.push(vrl_function.to_string());
+    }
+    if req.query.track_total_hits {
+        hash_body.push("track_total_hits".to_string());
+    }
+    if let Some(timezone) = req
+        .query
+        .timezone
+        .as_deref()
+        .and_then(normalize_histogram_timezone)
+    {
+        hash_body.push(timezone);
+    }
+    hash_body.extend(routing::cache_hash_values(&req.regions, &req.clusters));
+    hash_body
+}
+
+/// Returns the path the cached results of a query are stored under.
+pub(crate) fn get_cache_file_path(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    origin_sql: &str,
+    req: &search::Request,
+) -> String {
+    // calculate hash for the query
+    let hash_body = get_cache_hash_body(origin_sql, req);
+    let mut h = config::utils::hash::gxhash::new();
+    let hashed_query = h.sum64(&hash_body.join(","));
+
+    format!(
+        "{}/{}/{}/{}",
+        org_id, stream_type, stream_name, hashed_query
+    )
+}
+
 fn convert_ts_value_to_datetime(ts_value: &serde_json::Value) -> Option<chrono::DateTime<Utc>> {
     match ts_value {
         // Handle the case where ts_value is a number (microseconds)
@@ -911,3 +1507,523 @@ fn convert_ts_value_to_datetime(ts_value: &serde_json::Value) -> Option<chrono::
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(timestamps: &[i64], cached_ratio: usize) -> search::Response {
+        let mut res = search::Response::default();
+        for ts in timestamps {
+            res.add_hit(&json::json!({ TIMESTAMP_COL_NAME: ts }));
+        }
+        res.cached_ratio = cached_ratio;
+        res
+    }
+
+    fn cached(start_time: i64, end_time: i64, timestamps: &[i64]) -> CachedQueryResponse {
+        CachedQueryResponse {
+            cached_response: response(timestamps, 0),
+            response_start_time: start_time,
+            response_end_time: end_time,
+            ts_column: TIMESTAMP_COL_NAME.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn cache_request(size: i64, from: i64) -> search::Request {
+        let mut req = search::Request {
+            use_cache: Some(true),
+            ..Default::default()
+        };
+        req.query.size = size;
+        req.query.from = from;
+        req
+    }
+
+    #[test]
+    fn test_clamp_deltas_to_stream_data() {
+        let hour = 3_600_000_000;
+        let now = Utc::now().timestamp_micros();
+        let (org_id, stream_name) = ("default", "test_clamp_deltas_to_stream_data");
+        infra::cache::stats::set_stream_stats(
+            org_id,
+            stream_name,
+            StreamType::Logs,
+            config::meta::stream::StreamStats {
+                doc_time_min: now - 6 * hour,
+                doc_time_max: now - 2 * hour,
+                doc_num: 1000,
+                ..Default::default()
+            },
+        );
+        let start_time = now - 7 * 24 * hour;
+        let range = data_range::clamp_to_streams(
+            org_id,
+            &[(stream_name.to_string(), StreamType::Logs)],
+            (start_time, now),
+            1,
+        )
+        .unwrap();
+        let margin = get_config().limit.query_range_clamp_margin as i64 * 1_000_000;
+        assert_eq!(range.start, start_time);
+        assert_eq!(range.end, now - 2 * hour + 1 + margin);
+
+        // a delta after the data is dropped, the one ending after it is narrowed
+        let delta = |delta_start_time, delta_end_time| QueryDelta {
+            delta_start_time,
+            delta_end_time,
+            delta_removed_hits: false,
+        };
+        let deltas = clamp_deltas(
+            vec![
+                delta(start_time, now - 4 * hour),
+                delta(now - 3 * hour, now),
+                delta(range.end, now),
+            ],
+            range,
+        );
+        assert_eq!(
+            deltas,
+            vec![
+                delta(start_time, now - 4 * hour),
+                delta(now - 3 * hour, range.end),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_deltas_timeout() {
+        let delta = |delta_start_time, delta_end_time| QueryDelta {
+            delta_start_time,
+            delta_end_time,
+            delta_removed_hits: false,
+        };
+        let (searched, timed_out) = search_deltas(
+            vec![delta(0, 30), delta(30, 60)],
+            Duration::from_millis(50),
+            |_, delta| async move {
+                // the search of the older delta hangs
+                if delta.delta_start_time == 0 {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+                Ok(response(&[delta.delta_start_time + 10], 0))
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(timed_out, vec![(0, 30)]);
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].0, (30, 60));
+
+        let mut res = merge_response(
+            "trace",
+            &mut vec![],
+            &mut searched.into_iter().map(|(_, res)| res).collect(),
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        set_search_timeout(&mut res, 1, (0, 60), &timed_out);
+        assert!(res.is_partial);
+        assert_eq!(res.hits.len(), 1);
+        assert_eq!(
+            res.function_error,
+            "Search query timed out after 1s for the time ranges 0 - 30"
+        );
+        assert_eq!((res.new_start_time, res.new_end_time), (Some(30), Some(60)));
+
+        // the searched time range isn't a single one
+        let mut res = search::Response::default();
+        set_search_timeout(&mut res, 1, (0, 90), &[(30, 60)]);
+        assert!(res.is_partial);
+        assert_eq!((res.new_start_time, res.new_end_time), (None, None));
+    }
+
+    #[test]
+    fn test_use_result_cache_sql_only() {
+        let req = cache_request(0, 0);
+        assert_eq!(
+            use_result_cache(&req, "SELECT * FROM t LIMIT 10"),
+            (true, vec![])
+        );
+        // paginating in the sql skips the result cache like the from does
+        assert_eq!(
+            use_result_cache(&req, "SELECT * FROM t LIMIT 10 OFFSET 10"),
+            (false, vec![])
+        );
+    }
+
+    #[test]
+    fn test_use_result_cache_request_only() {
+        let sql = "SELECT * FROM t";
+        assert_eq!(use_result_cache(&cache_request(10, 0), sql), (true, vec![]));
+        assert_eq!(
+            use_result_cache(&cache_request(10, 10), sql),
+            (false, vec![])
+        );
+    }
+
+    #[test]
+    fn test_use_result_cache_both_agreeing() {
+        let sql = "SELECT * FROM t LIMIT 10 OFFSET 10";
+        assert_eq!(
+            use_result_cache(&cache_request(10, 10), sql),
+            (false, vec![])
+        );
+        let sql = "SELECT * FROM t LIMIT 10";
+        assert_eq!(use_result_cache(&cache_request(10, 0), sql), (true, vec![]));
+    }
+
+    #[test]
+    fn test_use_result_cache_both_conflicting() {
+        // the OFFSET 0 of the sql wins over the from of the request
+        let (use_cache, warnings) =
+            use_result_cache(&cache_request(100, 20), "SELECT * FROM t LIMIT 10 OFFSET 0");
+        assert!(use_cache);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("larger than the LIMIT 10"));
+        assert!(warnings[1].contains("OFFSET 0"));
+    }
+
+    #[test]
+    fn test_weighted_cached_ratio() {
+        // 1 hit fully served from the files cache, 3 hits not cached at all
+        let responses = vec![response(&[1], 100), response(&[2, 3, 4], 0)];
+        assert_eq!(weighted_cached_ratio(&responses), 25);
+        assert_eq!(weighted_cached_ratio(&[]), 0);
+        assert_eq!(weighted_cached_ratio(&[response(&[], 100)]), 0);
+    }
+
+    #[test]
+    fn test_merge_response_cached_ratio_weighted_by_hits() {
+        let mut cached_responses = vec![response(&[10, 20], 0), response(&[70, 80], 0)];
+        let mut search_responses = vec![response(&[40, 50, 60], 100), response(&[90], 0)];
+        let res = merge_response(
+            "trace",
+            &mut cached_responses,
+            &mut search_responses,
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(res.hits.len(), 8);
+        assert_eq!(res.cached_ratio, 75);
+        assert_eq!(res.result_cache_ratio, 50);
+    }
+
+    #[test]
+    fn test_merge_response_aliased_timestamp_desc() {
+        use hashbrown::{HashMap, HashSet};
+
+        use crate::service::search::sql::Sql;
+
+        let sql = Sql {
+            sql: "SELECT _timestamp AS ts, msg FROM t ORDER BY ts DESC".to_string(),
+            org_id: "default".to_string(),
+            stream_type: StreamType::Logs,
+            stream_names: vec!["t".into()],
+            match_items: None,
+            equal_items: HashMap::new(),
+            prefix_items: HashMap::new(),
+            columns: HashMap::from([(
+                "t".into(),
+                HashSet::from([TIMESTAMP_COL_NAME.to_string(), "msg".to_string()]),
+            )]),
+            aliases: vec![(TIMESTAMP_COL_NAME.to_string(), "ts".to_string())],
+            schemas: HashMap::new(),
+            limit: 100,
+            offset: 0,
+            time_range: Some((0, 100)),
+            group_by: vec![],
+            order_by: vec![("ts".to_string(), config::meta::sql::OrderBy::Desc)],
+            histogram_interval: None,
+            histogram_intervals: vec![],
+            histogram_timezone: None,
+            histogram_calendar_interval: None,
+            has_calendar_histogram: false,
+            sorted_by_time: false,
+            use_inverted_index: false,
+            index_condition: None,
+            index_optimize_mode: None,
+            warnings: vec![],
+        };
+        let (ts_column, is_descending) =
+            cacher::get_ts_col_order_by(&sql, TIMESTAMP_COL_NAME, false).unwrap();
+        assert_eq!(ts_column, "ts");
+        assert!(is_descending);
+
+        let aliased = |timestamps: &[i64]| {
+            let mut res = search::Response::default();
+            for ts in timestamps {
+                res.add_hit(&json::json!({ "ts": ts, "msg": "m" }));
+            }
+            res
+        };
+        let mut cached_responses = vec![aliased(&[80, 70])];
+        let mut search_responses = vec![aliased(&[40, 30]), aliased(&[60, 50])];
+        let res = merge_response(
+            "trace",
+            &mut cached_responses,
+            &mut search_responses,
+            &ts_column,
+            100,
+            is_descending,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            res.hits
+                .iter()
+                .map(|hit| get_ts_value(&ts_column, hit))
+                .collect::<Vec<_>>(),
+            vec![80, 70, 60, 50, 40, 30]
+        );
+        // the cache meta covers the data, the unaliased column isn't in the hits
+        assert_eq!(hits_time_bounds(&ts_column, &res.hits), (30, 80));
+        assert_eq!(hits_time_bounds(TIMESTAMP_COL_NAME, &res.hits), (0, 0));
+    }
+
+    fn count(total: usize) -> search::Response {
+        let mut res = search::Response::default();
+        res.add_hit(&json::json!({ "zo_sql_num": total }));
+        res.total = total;
+        res
+    }
+
+    #[test]
+    fn test_merge_count_response_sums_counts() {
+        let res = merge_count_response(&[count(30), count(10)], &[count(60)], 0);
+        assert_eq!(res.total, 100);
+        assert_eq!(res.hits, vec![json::json!({ "zo_sql_num": 100 })]);
+        assert_eq!(res.result_cache_ratio, 40);
+    }
+
+    #[test]
+    fn test_merge_count_response_repeated_query_fully_cached() {
+        // the same count over an unchanged historical range is served from cache
+        let res = merge_count_response(&[count(42)], &[], 3);
+        assert_eq!(res.total, 42);
+        assert_eq!(res.hits, vec![json::json!({ "zo_sql_num": 42 })]);
+        assert_eq!(res.result_cache_ratio, 100);
+        assert_eq!(res.took, 3);
+    }
+
+    #[test]
+    fn test_build_cache_detail() {
+        let cached_responses = vec![cached(60, 90, &[70, 80]), cached(0, 30, &[10, 20])];
+        let search_segments = vec![CacheSegment {
+            start_time: 30,
+            end_time: 60,
+            hits: 3,
+            source: CacheSegmentSource::Search,
+        }];
+        let detail = build_cache_detail(&cached_responses, search_segments);
+        assert_eq!(
+            detail.segments,
+            vec![
+                CacheSegment {
+                    start_time: 0,
+                    end_time: 30,
+                    hits: 2,
+                    source: CacheSegmentSource::Cache,
+                },
+                CacheSegment {
+                    start_time: 30,
+                    end_time: 60,
+                    hits: 3,
+                    source: CacheSegmentSource::Search,
+                },
+                CacheSegment {
+                    start_time: 60,
+                    end_time: 90,
+                    hits: 2,
+                    source: CacheSegmentSource::Cache,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_response_rejects_misaligned_histogram_buckets() {
+        // cached at a 30s interval, the delta was searched at 5m
+        let mut cached = response(&[0, 30_000_000, 60_000_000], 0);
+        cached.histogram_interval = Some(30);
+        let mut searched = response(&[300_000_000, 600_000_000], 0);
+        searched.histogram_interval = Some(300);
+
+        let res = merge_response(
+            "trace",
+            &mut vec![cached.clone()],
+            &mut vec![searched.clone()],
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        );
+        assert!(res.is_err());
+
+        searched.histogram_interval = Some(30);
+        let res = merge_response(
+            "trace",
+            &mut vec![cached],
+            &mut vec![searched],
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(res.hits.len(), 5);
+        assert_eq!(res.histogram_interval, Some(30));
+    }
+
+    fn failing(timestamps: &[i64], start_time: i64, end_time: i64) -> search::Response {
+        let mut res = response(timestamps, 0);
+        res.function_error = "vrl: undefined variable".to_string();
+        res.function_errors.push(FunctionError {
+            start_time,
+            end_time,
+            error: "vrl: undefined variable".to_string(),
+        });
+        res
+    }
+
+    #[test]
+    fn test_merge_response_one_failing_delta() {
+        let res = merge_response(
+            "trace",
+            &mut vec![response(&[10, 20], 0)],
+            &mut vec![response(&[40, 50], 0), failing(&[70], 60, 80)],
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(res.hits.len(), 5);
+        assert!(res.is_partial);
+        assert_eq!(res.function_errors.len(), 1);
+        assert_eq!(
+            res.function_error,
+            "vrl: undefined variable (time range 60 - 80)"
+        );
+
+        // the clean delta merged last doesn't hide the error
+        let res = merge_response(
+            "trace",
+            &mut vec![],
+            &mut vec![failing(&[70], 60, 80), response(&[90], 0)],
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        assert!(res.is_partial);
+        assert_eq!(res.function_errors.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_response_all_failing_deltas() {
+        let res = merge_response(
+            "trace",
+            &mut vec![response(&[40, 50], 0)],
+            &mut vec![
+                failing(&[10], 0, 30),
+                failing(&[70], 60, 80),
+                failing(&[], 60, 80),
+            ],
+            TIMESTAMP_COL_NAME,
+            100,
+            false,
+            0,
+        )
+        .unwrap();
+        assert!(res.is_partial);
+        assert_eq!(res.function_errors.len(), 2);
+        assert!(res.function_error.contains("(time range 0 - 30)"));
+        assert!(res.function_error.contains("(time range 60 - 80)"));
+
+        let res = merge_count_response(&[count(2)], &[failing(&[], 0, 30)], 0);
+        assert!(res.is_partial);
+        assert_eq!(
+            res.function_error,
+            "vrl: undefined variable (time range 0 - 30)"
+        );
+    }
+
+    #[test]
+    fn test_discard_boundary_hits_descending_logs() {
+        let sec = 1_000_000;
+        let mut hits = response(&[30 * sec, 20 * sec, 10 * sec + 5, 10 * sec], 0).hits;
+        assert!(discard_boundary_hits(
+            TIMESTAMP_COL_NAME,
+            &mut hits,
+            false,
+            true
+        ));
+        assert_eq!(hits, response(&[30 * sec, 20 * sec], 0).hits);
+    }
+
+    #[test]
+    fn test_discard_boundary_hits_ascending_logs() {
+        let sec = 1_000_000;
+        let mut hits = response(&[10 * sec, 10 * sec + 5, 20 * sec, 30 * sec], 0).hits;
+        assert!(discard_boundary_hits(
+            TIMESTAMP_COL_NAME,
+            &mut hits,
+            false,
+            false
+        ));
+        assert_eq!(hits, response(&[20 * sec, 30 * sec], 0).hits);
+
+        // hits out of the order of the query are left as they are
+        let mut hits = response(&[30 * sec, 10 * sec, 20 * sec], 0).hits;
+        assert!(!discard_boundary_hits(
+            TIMESTAMP_COL_NAME,
+            &mut hits,
+            false,
+            false
+        ));
+        assert_eq!(hits.len(), 3);
+    }
+
+    #[test]
+    fn test_discard_boundary_hits_group_by_aggregate() {
+        // SELECT histogram(_timestamp) AS ts, ns, count(*) ... GROUP BY ts, ns
+        let row =
+            |ts: &str, ns: &str, count: i64| json::json!({ "ts": ts, "ns": ns, "count": count });
+        let hits = vec![
+            row("2024-12-06T04:15:00", "ns1", 10),
+            row("2024-12-06T04:16:00", "ns1", 20),
+            row("2024-12-06T04:15:00", "ns2", 30),
+        ];
+        let mut cached = hits.clone();
+        assert!(!discard_boundary_hits("ts", &mut cached, true, true));
+        assert_eq!(cached, hits);
+
+        // a single aggregate row is cached whole
+        let hits = vec![row("2024-12-06T04:15:00", "ns1", 10)];
+        let mut cached = hits.clone();
+        assert!(!discard_boundary_hits("ts", &mut cached, true, true));
+        assert_eq!(cached, hits);
+
+        // aggregates without a timestamp column aren't trimmed
+        let mut cached = vec![json::json!({ "count": 60 })];
+        assert!(!discard_boundary_hits("", &mut cached, true, true));
+        assert_eq!(cached.len(), 1);
+
+        // histogram buckets in time order drop the partial last bucket
+        let mut cached = vec![
+            row("2024-12-06T04:16:00", "ns1", 20),
+            row("2024-12-06T04:15:00", "ns1", 10),
+        ];
+        assert!(discard_boundary_hits("ts", &mut cached, true, true));
+        assert_eq!(cached, vec![row("2024-12-06T04:16:00", "ns1", 20)]);
+    }
+}
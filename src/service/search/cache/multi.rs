@@ -20,11 +20,14 @@ use chrono::Utc;
 use config::{get_config, meta::search::Response, utils::json};
 use infra::cache::{file_data::disk::QUERY_RESULT_CACHE, meta::ResultCacheMeta};
 
-use super::{cacher::get_results, sort_response};
+use super::{
+    cacher::{get_cached_counts, get_results},
+    sort_response,
+};
 use crate::{
     common::meta::search::{CacheQueryRequest, ResultCacheSelectionStrategy},
     service::search::cache::{
-        result_utils::{get_ts_value, round_down_to_nearest_minute},
+        result_utils::{get_ts_value, histogram_bucket_start, round_down_to_nearest_minute},
         CachedQueryResponse,
     },
 };
@@ -48,6 +51,9 @@ pub async fn get_cached_results(
     }
 
     if let Some(cache_metas) = is_cached {
+        if cache_req.is_count {
+            return get_cached_counts(file_path, trace_id, &cache_req, &cache_metas).await;
+        }
         let _ = recursive_process_multiple_metas(
             &cache_metas,
             trace_id,
@@ -90,7 +96,7 @@ async fn recursive_process_multiple_metas(
     // Filter relevant metas that are within the overall query range
     let relevant_metas: Vec<ResultCacheMeta> = cache_metas
         .iter()
-        .filter(|m| m.start_time <= cache_req.q_end_time && m.end_time >= cache_req.q_start_time)
+        .filter(|m| overlaps_query(m, &cache_req))
         .cloned()
         .collect();
 
@@ -106,34 +112,20 @@ async fn recursive_process_multiple_metas(
     let mut sorted_metas = relevant_metas;
     sorted_metas.sort_by_key(|m| m.start_time);
 
-    if
-        let Some(largest_meta) = sorted_metas
-            .iter()
-            .filter(|cache_meta| {
-                log::info!(
-                    "[CACHE CANDIDATES {trace_id}] Got caches: cache_meta.response_start_time: {}, cache_meta.response_end_time: {}",
-                    cache_meta.start_time,
-                    cache_meta.end_time
-                );
-                cache_meta.start_time <= cache_req.q_end_time &&
-                    cache_meta.end_time >= cache_req.q_start_time
-            })
-            .max_by_key(|result| select_cache_meta(result, &cache_req, &selection_strategy))
-    {
+    for cache_meta in sorted_metas.iter() {
+        log::info!(
+            "[CACHE CANDIDATES {trace_id}] Got caches: cache_meta.response_start_time: {}, cache_meta.response_end_time: {}",
+            cache_meta.start_time,
+            cache_meta.end_time
+        );
+    }
+    if let Some(largest_meta) = select_best_meta(&sorted_metas, &cache_req, &selection_strategy) {
         let file_name = format!(
             "{}_{}_{}_{}.json",
             largest_meta.start_time,
             largest_meta.end_time,
-            if cache_req.is_aggregate {
-                1
-            } else {
-                0
-            },
-            if cache_req.is_descending {
-                1
-            } else {
-                0
-            }
+            if cache_req.is_aggregate { 1 } else { 0 },
+            if cache_req.is_descending { 1 } else { 0 }
         );
 
         let mut matching_cache_meta = largest_meta.clone();
@@ -142,36 +134,47 @@ async fn recursive_process_multiple_metas(
         let cfg = get_config();
         let discard_duration = cfg.common.result_cache_discard_duration * 1000 * 1000;
 
-        let cache_duration = matching_cache_meta.end_time - matching_cache_meta.start_time;
-        if
-            cache_duration <= discard_duration &&
-            matching_cache_meta.start_time > Utc::now().timestamp_micros() - discard_duration
-        {
+        if is_in_discard_window(
+            &matching_cache_meta,
+            discard_duration,
+            Utc::now().timestamp_micros(),
+        ) {
             return Ok(());
         }
 
         let result = match get_results(file_path, &file_name).await {
-            Ok(v) => {
-                match json::from_str::<Response>(&v) {
-                    Ok(v) => Some(v),
-                    Err(e) => {
-                        log::error!("[trace_id {trace_id}] Error parsing cached response: {:?}", e);
-                        None
-                    }
+            Ok(v) => match json::from_str::<Response>(&v) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    log::error!(
+                        "[trace_id {trace_id}] Error parsing cached response: {:?}",
+                        e
+                    );
+                    None
                 }
-            }
+            },
             Err(e) => {
-                log::error!("[trace_id {trace_id}] Get results from disk failed: {:?}", e);
+                log::error!(
+                    "[trace_id {trace_id}] Get results from disk failed: {:?}",
+                    e
+                );
                 None
             }
         };
         if let Some(mut cached_response) = result {
-            let (hits_allowed_start_time, hits_allowed_end_time) = if
-                cache_req.discard_interval > 0
+            let (hits_allowed_start_time, hits_allowed_end_time) = if cache_req.discard_interval > 0
             {
                 (
-                    cache_req.q_start_time - (cache_req.q_start_time % cache_req.discard_interval),
-                    cache_req.q_end_time - (cache_req.q_end_time % cache_req.discard_interval),
+                    histogram_bucket_start(
+                        cache_req.q_start_time,
+                        cache_req.discard_interval,
+                        cache_req.calendar_months,
+                    ),
+                    histogram_bucket_start(
+                        cache_req.q_end_time,
+                        cache_req.discard_interval,
+                        cache_req.calendar_months,
+                    ),
                 )
             } else {
                 (cache_req.q_start_time, cache_req.q_end_time)
@@ -179,21 +182,27 @@ async fn recursive_process_multiple_metas(
             let discard_ts = get_allowed_up_to(&cached_response, &cache_req, discard_duration);
             cached_response.hits.retain(|hit| {
                 let hit_ts = get_ts_value(&cache_req.ts_column, hit);
-                hit_ts < hits_allowed_end_time &&
-                    hit_ts > hits_allowed_start_time &&
-                    hit_ts < discard_ts
+                hit_ts < hits_allowed_end_time
+                    && hit_ts > hits_allowed_start_time
+                    && hit_ts < discard_ts
             });
 
             // Sort the hits by the order
-            sort_response(cache_req.is_descending, &mut cached_response, &cache_req.ts_column);
+            sort_response(
+                cache_req.is_descending,
+                &mut cached_response,
+                &cache_req.ts_column,
+            );
 
             cached_response.total = cached_response.hits.len();
             if cache_req.discard_interval < 0 {
                 matching_cache_meta.end_time = discard_ts;
             }
             if !cached_response.hits.is_empty() {
-                let last_rec_ts = get_ts_value(&cache_req.ts_column, cached_response.hits.last().unwrap());
-                let first_rec_ts = get_ts_value(&cache_req.ts_column, cached_response.hits.first().unwrap());
+                let last_rec_ts =
+                    get_ts_value(&cache_req.ts_column, cached_response.hits.last().unwrap());
+                let first_rec_ts =
+                    get_ts_value(&cache_req.ts_column, cached_response.hits.first().unwrap());
                 let response_start_time = if cache_req.is_descending {
                     last_rec_ts
                 } else {
@@ -229,9 +238,9 @@ async fn recursive_process_multiple_metas(
             .clone()
             .into_iter()
             .filter(|meta| {
-                !largest_meta.eq(meta) &&
-                    (meta.end_time <= largest_meta.start_time ||
-                        meta.start_time >= largest_meta.end_time)
+                !largest_meta.eq(meta)
+                    && (meta.end_time <= largest_meta.start_time
+                        || meta.start_time >= largest_meta.end_time)
             })
             .collect();
         if !remaining_metas.is_empty() {
@@ -243,12 +252,41 @@ async fn recursive_process_multiple_metas(
             cache_req,
             results,
             query_key,
-            file_path
-        ).await;
+            file_path,
+        )
+        .await;
     }
     Ok(())
 }
 
+/// Whether the cached window overlaps the query time range.
+pub(crate) fn overlaps_query(meta: &ResultCacheMeta, req: &CacheQueryRequest) -> bool {
+    meta.start_time <= req.q_end_time && meta.end_time >= req.q_start_time
+}
+
+/// Picks the cached window that best serves the query according to the
+/// selection strategy, see [`select_cache_meta`].
+pub(crate) fn select_best_meta<'a>(
+    metas: &'a [ResultCacheMeta],
+    req: &CacheQueryRequest,
+    strategy: &ResultCacheSelectionStrategy,
+) -> Option<&'a ResultCacheMeta> {
+    metas
+        .iter()
+        .filter(|meta| overlaps_query(meta, req))
+        .max_by_key(|meta| select_cache_meta(meta, req, strategy))
+}
+
+/// Whether the cached window is too short and too recent to be used, as its
+/// data may still change within the discard duration.
+pub(crate) fn is_in_discard_window(
+    meta: &ResultCacheMeta,
+    discard_duration: i64,
+    now: i64,
+) -> bool {
+    meta.end_time - meta.start_time <= discard_duration && meta.start_time > now - discard_duration
+}
+
 /// Cache selection strategies determine how to choose the best cached result when multiple caches
 /// exist:
 ///
@@ -264,7 +302,7 @@ async fn recursive_process_multiple_metas(
 ///    10:00-11:00 Cache1: 10:00-10:30 (duration: 30min, overlap: 30min) = (30/30)*100 = 100%
 ///    Cache2: 10:15-11:15 (duration: 60min, overlap: 45min) = (45/60)*100 = 75% Chooses Cache1
 ///    because 100% of its duration is useful for the query
-fn select_cache_meta(
+pub(crate) fn select_cache_meta(
     meta: &ResultCacheMeta,
     req: &CacheQueryRequest,
     strategy: &ResultCacheSelectionStrategy,
@@ -13,8 +13,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use arrow::datatypes::IntervalMonthDayNano;
 use config::utils::{json, time::parse_str_to_timestamp_micros_as_option};
 
+use crate::service::search::datafusion::udf::date_bin_tz_udf::LocalBinner;
+
 pub fn get_ts_value(ts_column: &str, record: &json::Value) -> i64 {
     match record.get(ts_column) {
         None => 0_i64,
@@ -40,3 +43,31 @@ pub fn round_down_to_nearest_minute(microseconds: i64) -> i64 {
     // Convert the adjusted time back to microseconds
     adjusted_seconds * microseconds_per_second
 }
+
+/// Start of the histogram bucket `ts` falls into, for buckets of
+/// `discard_interval` microseconds or of `calendar_months` months in UTC.
+pub fn histogram_bucket_start(ts: i64, discard_interval: i64, calendar_months: i64) -> i64 {
+    match calendar_binner(calendar_months) {
+        Some(binner) => binner.bin(ts),
+        None => ts - (ts % discard_interval),
+    }
+}
+
+/// Start of the histogram bucket following the one `ts` falls into.
+pub fn next_histogram_bucket_start(ts: i64, discard_interval: i64, calendar_months: i64) -> i64 {
+    match calendar_binner(calendar_months) {
+        Some(binner) => binner.next_bin(ts),
+        None => ts + discard_interval.abs(),
+    }
+}
+
+fn calendar_binner(calendar_months: i64) -> Option<LocalBinner> {
+    if calendar_months <= 0 {
+        return None;
+    }
+    LocalBinner::try_new(
+        IntervalMonthDayNano::new(calendar_months as i32, 0, 0),
+        "UTC",
+    )
+    .ok()
+}
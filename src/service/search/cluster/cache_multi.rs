@@ -84,6 +84,8 @@ pub async fn get_cached_results(
                     trace_id:trace_id.clone(),
                     discard_interval:cache_req.discard_interval,
                     is_descending:cache_req.is_descending,
+                    is_count: cache_req.is_count,
+                    calendar_months: cache_req.calendar_months,
                 };
 
                 let mut request = tonic::Request::new(req);
@@ -213,6 +215,8 @@ pub async fn get_cached_results(
             ts_column: ts_column.to_string(),
             discard_interval: cache_req.discard_interval,
             is_descending: cache_req.is_descending,
+            is_count: cache_req.is_count,
+            calendar_months: cache_req.calendar_months,
         },
     )
     .await;
@@ -83,6 +83,8 @@ pub async fn get_cached_results(
                     trace_id:trace_id.clone(),
                     discard_interval:cache_req.discard_interval,
                     is_descending:cache_req.is_descending,
+                    is_count: cache_req.is_count,
+                    calendar_months: cache_req.calendar_months,
                 };
 
                 let mut request = tonic::Request::new(req);
@@ -217,6 +219,8 @@ pub async fn get_cached_results(
             ts_column,
             discard_interval: cache_req.discard_interval,
             is_descending: cache_req.is_descending,
+            is_count: cache_req.is_count,
+            calendar_months: cache_req.calendar_months,
         },
     )
     .await
@@ -253,7 +257,7 @@ pub async fn get_cached_results(
     }
 }
 
-pub async fn delete_cached_results(path: String) -> bool {
+pub async fn delete_cached_results(path: String, time_range: Option<(i64, i64)>) -> bool {
     let trace_id = path.clone();
     let mut delete_response = true;
     // get nodes from cluster
@@ -295,7 +299,9 @@ pub async fn delete_cached_results(path: String) -> bool {
         let task = tokio::task::spawn(
             async move {
                 let req = DeleteResultCacheRequest {
-                   path: local_path.clone(),
+                    path: local_path.clone(),
+                    start_time: time_range.map(|(start, _)| start),
+                    end_time: time_range.map(|(_, end)| end),
                 };
 
                 let request = tonic::Request::new(req);
@@ -352,7 +358,7 @@ pub async fn delete_cached_results(path: String) -> bool {
         );
         tasks.push(task);
     }
-    match crate::service::search::cache::cacher::delete_cache(&path).await {
+    match crate::service::search::cache::cacher::delete_cache(&path, time_range).await {
         Ok(_) => {
             log::info!(
                 "[trace_id {trace_id}] delete_cached_results->grpc: local node delete success"
@@ -22,7 +22,7 @@ use config::{
     meta::{
         bitvec::BitVec,
         cluster::{IntoArcVec, Node, Role, RoleGroup},
-        search::{ScanStats, SearchEventType},
+        search::{ExecutionStats, ScanStats, SearchEventType},
         sql::TableReferenceExt,
         stream::{FileKey, QueryPartitionStrategy, StreamType},
     },
@@ -43,6 +43,7 @@ use infra::{
     file_list::FileId,
 };
 use itertools::Itertools;
+use once_cell::sync::Lazy;
 use proto::cluster_rpc::{self, SearchQuery};
 use tracing::{info_span, Instrument};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
@@ -58,12 +59,14 @@ use crate::{
             },
             exec::{prepare_datafusion_context, register_udf},
             optimizer::generate_optimizer_rules,
+            peak_memory::get_peak_memory,
+            pruning::collect_pruning_stats,
             table_provider::{catalog::StreamTypeProvider, empty_table::NewEmptyTable},
         },
-        generate_filter_from_equal_items,
+        generate_filter_from_equal_items, get_query_timeout,
         request::Request,
         sql::Sql,
-        utils::{AsyncDefer, ScanStatsVisitor},
+        utils::{AbortOnDrop, AsyncDefer, ScanStatsVisitor},
         DATAFUSION_RUNTIME,
     },
 };
@@ -79,16 +82,19 @@ pub async fn search(
     sql: Arc<Sql>,
     mut req: Request,
     query: SearchQuery,
-) -> Result<(Vec<RecordBatch>, ScanStats, usize, bool, usize, String)> {
+) -> Result<(
+    Vec<RecordBatch>,
+    ScanStats,
+    usize,
+    bool,
+    usize,
+    String,
+    ExecutionStats,
+)> {
     let start = std::time::Instant::now();
-    let cfg = get_config();
     log::info!("[trace_id {trace_id}] flight->search: start {}", sql);
 
-    let timeout = if req.timeout > 0 {
-        req.timeout as u64
-    } else {
-        cfg.limit.query_timeout
-    };
+    let timeout = get_query_timeout(req.timeout);
     req.timeout = timeout as _;
 
     if sql
@@ -96,7 +102,15 @@ pub async fn search(
         .iter()
         .any(|(_, schema)| schema.schema().fields().is_empty())
     {
-        return Ok((vec![], ScanStats::new(), 0, false, 0, "".to_string()));
+        return Ok((
+            vec![],
+            ScanStats::new(),
+            0,
+            false,
+            0,
+            "".to_string(),
+            ExecutionStats::default(),
+        ));
     }
 
     // 1. get file id list
@@ -121,6 +135,22 @@ pub async fn search(
         ..Default::default()
     };
 
+    // get the recently uploaded files, ingesters skip their wal copies
+    let mut seam_files = HashMap::with_capacity(file_id_list.len());
+    for (stream, file_id_list) in file_id_list.iter() {
+        let files = get_seam_files(
+            &sql.org_id,
+            stream.get_stream_type(sql.stream_type),
+            &stream.stream_name(),
+            sql.time_range,
+            file_id_list,
+        )
+        .await?;
+        if !files.is_empty() {
+            seam_files.insert(stream.clone(), files);
+        }
+    }
+
     // 2. get inverted index file list
     let (use_ttv_inverted_index, idx_file_list, idx_scan_size, idx_took) =
         get_inverted_index_file_lists(trace_id, &req, &sql, &query).await?;
@@ -220,7 +250,14 @@ pub async fn search(
     });
 
     // 5. partition file list
-    let partitioned_file_lists = partition_file_lists(file_id_list, &nodes, node_group).await?;
+    let partitioned_file_lists = partition_file_lists(
+        &sql.org_id,
+        sql.stream_type,
+        file_id_list,
+        &nodes,
+        node_group,
+    )
+    .await?;
 
     #[cfg(feature = "enterprise")]
     super::super::SEARCH_SERVER
@@ -274,10 +311,13 @@ pub async fn search(
             nodes,
             partitioned_file_lists,
             idx_file_list,
+            seam_files,
         )
         .instrument(datafusion_span)
         .await
     });
+    // stop the datafusion task if this search is dropped, e.g. the client cancelled it
+    let _query_task_guard = AbortOnDrop::new(query_task.abort_handle());
     tokio::pin!(query_task);
 
     // 8. execute physical plan
@@ -312,7 +352,7 @@ pub async fn search(
     drop(_defer);
 
     // 9. get data from datafusion
-    let (data, mut scan_stats, partial_err): (Vec<RecordBatch>, ScanStats, String) = match task {
+    let (data, mut scan_stats, partial_err, execution_stats) = match task {
         Ok(Ok(data)) => Ok(data),
         Ok(Err(err)) => Err(err),
         Err(err) => match err {
@@ -333,6 +373,7 @@ pub async fn search(
         !partial_err.is_empty(),
         idx_took,
         partial_err,
+        execution_stats,
     ))
 }
 
@@ -344,7 +385,8 @@ pub async fn run_datafusion(
     nodes: Vec<Node>,
     partitioned_file_lists: HashMap<TableReference, Vec<Vec<i64>>>,
     idx_file_list: Vec<FileKey>,
-) -> Result<(Vec<RecordBatch>, ScanStats, String)> {
+    seam_files: HashMap<TableReference, Vec<String>>,
+) -> Result<(Vec<RecordBatch>, ScanStats, String, ExecutionStats)> {
     let cfg = get_config();
     let ctx = generate_context(&req, &sql, cfg.limit.cpu_num).await?;
 
@@ -397,6 +439,7 @@ pub async fn run_datafusion(
         nodes.into_arc_vec(),
         partitioned_file_lists,
         idx_file_list,
+        seam_files,
         equal_keys,
         match_all_keys,
         sql.index_condition.clone(),
@@ -429,7 +472,12 @@ pub async fn run_datafusion(
         ));
     }
     if visitor.get_data().is_some() {
-        return Ok((vec![], ScanStats::default(), "".to_string()));
+        return Ok((
+            vec![],
+            ScanStats::default(),
+            "".to_string(),
+            ExecutionStats::default(),
+        ));
     }
 
     if cfg.common.print_key_sql {
@@ -440,12 +488,14 @@ pub async fn run_datafusion(
     let ret = datafusion::physical_plan::collect(physical_plan.clone(), ctx.task_ctx()).await;
     let mut visit = ScanStatsVisitor::new();
     let _ = visit_execution_plan(physical_plan.as_ref(), &mut visit);
+    let (_, mut execution_stats) = collect_pruning_stats(physical_plan.as_ref());
+    execution_stats.peak_memory += get_peak_memory(&ctx);
     if let Err(e) = ret {
         log::error!("[trace_id {trace_id}] flight->search: datafusion collect error: {e}");
         Err(e.into())
     } else {
         log::info!("[trace_id {trace_id}] flight->search: datafusion collect done");
-        ret.map(|data| (data, visit.scan_stats, visit.partial_err))
+        ret.map(|data| (data, visit.scan_stats, visit.partial_err, execution_stats))
             .map_err(|e| e.into())
     }
 }
@@ -607,13 +657,22 @@ pub async fn check_work_group(
 
 #[tracing::instrument(name = "service:search:cluster:flight:partition_file_lists", skip_all)]
 pub async fn partition_file_lists(
+    org_id: &str,
+    stream_type: StreamType,
     file_id_lists: HashMap<TableReference, Vec<FileId>>,
     nodes: &[Node],
     group: Option<RoleGroup>,
 ) -> Result<HashMap<TableReference, Vec<Vec<i64>>>> {
     let mut file_partitions = HashMap::with_capacity(file_id_lists.len());
     for (stream_name, file_id_list) in file_id_lists {
-        let partitions = partition_filt_list(file_id_list, nodes, group).await?;
+        let cache_latest_files = infra::cache::file_data::memory::cache_latest_files(
+            org_id,
+            stream_name.get_stream_type(stream_type),
+            &stream_name.stream_name(),
+        )
+        .await;
+        let partitions =
+            partition_filt_list(file_id_list, nodes, group, cache_latest_files).await?;
         file_partitions.insert(stream_name, partitions);
     }
     Ok(file_partitions)
@@ -623,12 +682,14 @@ pub async fn partition_filt_list(
     file_id_list: Vec<FileId>,
     nodes: &[Node],
     group: Option<RoleGroup>,
+    cache_latest_files: bool,
 ) -> Result<Vec<Vec<i64>>> {
     let cfg = get_config();
     let querier_num = nodes.iter().filter(|node| node.is_querier()).count();
     let mut partition_strategy =
         QueryPartitionStrategy::from(&cfg.common.feature_query_partition_strategy);
-    if cfg.memory_cache.cache_latest_files {
+    // the latest files are cached on the querier that owns the file hash
+    if cache_latest_files {
         partition_strategy = QueryPartitionStrategy::FileHash;
     }
     let partitions = match partition_strategy {
@@ -819,6 +880,85 @@ pub async fn get_file_id_lists(
     Ok(file_lists)
 }
 
+/// Keys of the recently uploaded storage files by file id, with the time they
+/// were cached. The key of a file id never changes, so an entry only expires
+/// once the file is out of the ingest window.
+static RECENT_FILE_KEYS: Lazy<parking_lot::RwLock<HashMap<i64, (String, i64)>>> =
+    Lazy::new(Default::default);
+
+/// Get the storage files which may still have their wal copy on the ingesters.
+///
+/// Ingesters keep the merged wal files on disk until the searches using them are done,
+/// so the wal search skips the copies of the files we already scan from storage. Only
+/// the files inside the ingest window are checked, older data can't be in the wal.
+/// The keys are cached by file id, so the file list is only queried for the
+/// files uploaded since the previous search.
+#[tracing::instrument(name = "service:search:cluster:flight:get_seam_files", skip_all)]
+pub async fn get_seam_files(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    time_range: Option<(i64, i64)>,
+    file_id_list: &[FileId],
+) -> Result<Vec<String>> {
+    let cfg = get_config();
+    if cfg.common.feature_query_skip_wal || file_id_list.is_empty() {
+        return Ok(vec![]);
+    }
+    let now = chrono::Utc::now().timestamp_micros();
+    let window_start = now
+        - (cfg.limit.ingest_allowed_upto * 3600 + cfg.limit.max_file_retention_time as i64)
+            * 1_000_000;
+    let (start, end) = time_range.unwrap_or((window_start, now));
+    if end < window_start {
+        return Ok(vec![]);
+    }
+    // only the files in the snapshot are searched from storage, and all of them
+    // are recent when the whole time range is inside the ingest window
+    let ids = if start >= window_start {
+        file_id_list.iter().map(|f| f.id).collect::<Vec<_>>()
+    } else {
+        let recent_files = crate::service::file_list::query_ids(
+            org_id,
+            stream_type,
+            stream_name,
+            Some((window_start, end)),
+        )
+        .await?;
+        let snapshot_ids = file_id_list.iter().map(|f| f.id).collect::<HashSet<_>>();
+        recent_files
+            .into_iter()
+            .filter_map(|f| snapshot_ids.contains(&f.id).then_some(f.id))
+            .collect::<Vec<_>>()
+    };
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut keys = Vec::with_capacity(ids.len());
+    let mut missing_ids = Vec::new();
+    {
+        let cache = RECENT_FILE_KEYS.read();
+        for id in ids {
+            match cache.get(&id) {
+                Some((key, _)) => keys.push(key.clone()),
+                None => missing_ids.push(id),
+            }
+        }
+    }
+    if missing_ids.is_empty() {
+        return Ok(keys);
+    }
+    let files = infra::file_list::query_by_ids(&missing_ids).await?;
+    let mut cache = RECENT_FILE_KEYS.write();
+    cache.retain(|_, (_, cached_at)| *cached_at >= window_start);
+    for (id, key, _) in files {
+        cache.insert(id, (key.clone(), now));
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
 #[tracing::instrument(
     name = "service:search:cluster:flight:get_inverted_index_file_list",
     skip_all
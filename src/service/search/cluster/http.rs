@@ -22,9 +22,14 @@ use config::{
         arrow::record_batches_to_json_rows,
         flatten,
         json::{self, get_int_value},
+        time::now_micros,
     },
+    ORIGINAL_DATA_COL_NAME,
+};
+use infra::{
+    errors::{Error, ErrorCodes, Result},
+    schema::unwrap_stream_settings,
 };
-use infra::errors::{Error, ErrorCodes, Result};
 use itertools::Itertools;
 #[cfg(feature = "enterprise")]
 use o2_enterprise::enterprise::actions::{
@@ -34,7 +39,10 @@ use o2_enterprise::enterprise::actions::{
 use proto::cluster_rpc::SearchQuery;
 use vector_enrichment::TableRegistry;
 
-use crate::service::search::{cluster::flight, request::Request, sql::Sql};
+use crate::service::{
+    compact::original_data::is_expired,
+    search::{cluster::flight, request::Request, sql::Sql},
+};
 
 #[tracing::instrument(name = "service:search:cluster", skip_all)]
 pub async fn search(
@@ -89,13 +97,14 @@ pub async fn search(
     #[cfg(not(feature = "enterprise"))]
     let ret = flight::search(&trace_id, sql.clone(), req, query).await;
 
-    let (merge_batches, scan_stats, took_wait, is_partial, idx_took, partial_err) = match ret {
-        Ok(v) => v,
-        Err(e) => {
-            log::error!("[trace_id {trace_id}] http->search: err: {:?}", e);
-            return Err(e);
-        }
-    };
+    let (merge_batches, scan_stats, took_wait, is_partial, idx_took, partial_err, execution_stats) =
+        match ret {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("[trace_id {trace_id}] http->search: err: {:?}", e);
+                return Err(e);
+            }
+        };
 
     // final result
     let mut result = search::Response::new(sql.offset, sql.limit);
@@ -130,7 +139,13 @@ pub async fn search(
                     }
                     Err(err) => {
                         log::error!("[trace_id {trace_id}] search->vrl: compile err: {:?}", err);
+                        let (start_time, end_time) = sql.time_range.unwrap_or_default();
                         result.function_error = err.to_string();
+                        result.function_errors.push(search::FunctionError {
+                            start_time,
+                            end_time,
+                            error: err.to_string(),
+                        });
                         None
                     }
                 };
@@ -255,8 +270,18 @@ pub async fn search(
 
     result.set_total(total);
     result.set_histogram_interval(sql.histogram_interval);
+    result.set_histogram_intervals(sql.histogram_intervals.clone());
+    result.set_histogram_timezone(sql.histogram_timezone.clone());
+    result.set_histogram_calendar_interval(sql.histogram_calendar_interval.clone());
     result.set_partial(is_partial, partial_err);
+    result.peak_memory = execution_stats.peak_memory;
+    if !execution_stats.pruning.is_empty() {
+        result.pruning_stats = Some(execution_stats.pruning);
+    }
     result.set_cluster_took(start.elapsed().as_millis() as usize, took_wait);
+    if let Some(took_detail) = result.took_detail.as_mut() {
+        took_detail.nodes = execution_stats.nodes;
+    }
     result.set_file_count(scan_stats.files as usize);
     result.set_scan_size(scan_stats.original_size as usize);
     result.set_scan_records(scan_stats.records as usize);
@@ -284,6 +309,13 @@ pub async fn search(
         result.set_order_by(Some(order_by.1));
     }
 
+    for warning in sql.warnings.iter() {
+        result.add_warning(warning.clone());
+    }
+    for warning in original_data_warnings(&sql) {
+        result.add_warning(warning);
+    }
+
     log::info!(
         "[trace_id {trace_id}] search->result: total: {}, scan_size: {} mb, took: {} ms",
         result.total,
@@ -293,3 +325,61 @@ pub async fn search(
 
     Ok(result)
 }
+
+/// Warns about the streams whose `_original` column is used by the query over
+/// data older than the `original_data_retention_days` of the stream, the
+/// compactor dropped it there. `SELECT *` leaves the column out, so only the
+/// queries naming it are warned.
+fn original_data_warnings(sql: &Sql) -> Vec<String> {
+    let Some((start_time, _)) = sql.time_range else {
+        return vec![];
+    };
+    let now = now_micros();
+    sql.schemas
+        .iter()
+        .filter(|(stream, _)| {
+            sql.columns
+                .get(*stream)
+                .is_some_and(|columns| columns.contains(ORIGINAL_DATA_COL_NAME))
+        })
+        .filter_map(|(stream, schema)| {
+            let retention_days = unwrap_stream_settings(schema.schema())
+                .map(|s| s.original_data_retention_days)
+                .unwrap_or_default();
+            original_data_warning(&stream.stream_name(), retention_days, start_time, now)
+        })
+        .sorted()
+        .collect()
+}
+
+fn original_data_warning(
+    stream_name: &str,
+    retention_days: i64,
+    start_time: i64,
+    now: i64,
+) -> Option<String> {
+    is_expired(start_time, retention_days, now).then(|| {
+        format!(
+            "{ORIGINAL_DATA_COL_NAME} of stream [{stream_name}] is only kept for {retention_days} days, it is null for the older data"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use config::utils::time::day_micros;
+
+    use super::*;
+
+    #[test]
+    fn test_original_data_warning() {
+        let now = day_micros(100);
+        assert_eq!(
+            original_data_warning("app", 10, day_micros(80), now).unwrap(),
+            "_original of stream [app] is only kept for 10 days, it is null for the older data"
+        );
+        // the searched data still has the column
+        assert!(original_data_warning("app", 10, day_micros(95), now).is_none());
+        assert!(original_data_warning("app", 0, day_micros(1), now).is_none());
+    }
+}
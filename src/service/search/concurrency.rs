@@ -0,0 +1,277 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits the searches an organization can run at the same time on a node, so
+//! that a single organization can not starve the others.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use config::{get_config, metrics, RwHashMap};
+use infra::errors::{Error, ErrorCodes};
+use once_cell::sync::Lazy;
+use tokio::{sync::Notify, time::Instant};
+
+use crate::service::db::organization::get_org_setting;
+
+static ORG_SEARCHES: Lazy<OrgSearchLimiter> = Lazy::new(Default::default);
+/// The `search_max_concurrency` setting of each organization, `None` when it
+/// is not set, so organizations without settings don't hit the db per search
+static ORG_LIMITS: Lazy<RwHashMap<String, Option<usize>>> = Lazy::new(Default::default);
+
+/// Waits for the organization to run less searches than its limit, see
+/// `ZO_SEARCH_MAX_CONCURRENCY_PER_ORG`. The search is counted until the
+/// returned permit is dropped.
+pub async fn acquire(org_id: &str) -> Result<SearchPermit, Error> {
+    let cfg = get_config();
+    let limit = org_limit(org_id)
+        .await
+        .unwrap_or(cfg.limit.search_max_concurrency_per_org);
+    let wait = Duration::from_secs(cfg.limit.search_concurrency_wait_timeout);
+    ORG_SEARCHES.acquire(org_id, limit, wait).await
+}
+
+/// Forgets the cached limit of the organization, called when its settings
+/// change.
+pub fn invalidate_limit(org_id: &str) {
+    ORG_LIMITS.remove(org_id);
+}
+
+async fn org_limit(org_id: &str) -> Option<usize> {
+    if let Some(limit) = ORG_LIMITS.get(org_id) {
+        return *limit;
+    }
+    let limit = match get_org_setting(org_id).await {
+        Ok(settings) => settings.search_max_concurrency,
+        Err(_) => None,
+    };
+    ORG_LIMITS.insert(org_id.to_string(), limit);
+    limit
+}
+
+/// Seconds a rejected search is advised to wait before it is retried.
+pub fn retry_after_secs() -> u64 {
+    get_config().limit.search_concurrency_wait_timeout.max(1)
+}
+
+#[derive(Default)]
+struct OrgSearches {
+    running: AtomicUsize,
+    released: Notify,
+}
+
+impl OrgSearches {
+    fn try_acquire(&self, limit: usize) -> bool {
+        self.running
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                (running < limit).then_some(running + 1)
+            })
+            .is_ok()
+    }
+}
+
+#[derive(Default)]
+struct OrgSearchLimiter {
+    orgs: RwHashMap<String, Arc<OrgSearches>>,
+}
+
+impl OrgSearchLimiter {
+    async fn acquire(
+        &self,
+        org_id: &str,
+        limit: usize,
+        wait: Duration,
+    ) -> Result<SearchPermit, Error> {
+        if limit == 0 {
+            return Ok(SearchPermit { searches: None });
+        }
+        let searches = self
+            .orgs
+            .entry(org_id.to_string())
+            .or_default()
+            .value()
+            .clone();
+        let deadline = Instant::now() + wait;
+        loop {
+            if searches.try_acquire(limit) {
+                return Ok(SearchPermit {
+                    searches: Some(searches),
+                });
+            }
+            if tokio::time::timeout_at(deadline, searches.released.notified())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        metrics::QUERY_REJECTED_NUMS
+            .with_label_values(&[org_id])
+            .inc();
+        Err(Error::ErrorCode(ErrorCodes::SearchTooManyRequests(
+            format!("organization {org_id} is already running {limit} searches"),
+        )))
+    }
+
+    #[cfg(test)]
+    fn running(&self, org_id: &str) -> usize {
+        self.orgs
+            .get(org_id)
+            .map_or(0, |v| v.running.load(Ordering::Acquire))
+    }
+}
+
+/// A running search of an organization, released when dropped, whether the
+/// search completed, failed or was cancelled.
+pub struct SearchPermit {
+    searches: Option<Arc<OrgSearches>>,
+}
+
+impl Drop for SearchPermit {
+    fn drop(&mut self) {
+        if let Some(searches) = self.searches.take() {
+            searches.running.fetch_sub(1, Ordering::AcqRel);
+            searches.released.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_rejects_at_limit() {
+        let limiter = OrgSearchLimiter::default();
+        let first = limiter.acquire("org1", 2, Duration::ZERO).await.unwrap();
+        let _second = limiter.acquire("org1", 2, Duration::ZERO).await.unwrap();
+        let err = limiter
+            .acquire("org1", 2, Duration::ZERO)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::ErrorCode(ErrorCodes::SearchTooManyRequests(_))
+        ));
+        assert_eq!(limiter.running("org1"), 2);
+
+        // other orgs are not limited by org1
+        let _other = limiter.acquire("org2", 2, Duration::ZERO).await.unwrap();
+
+        drop(first);
+        assert_eq!(limiter.running("org1"), 1);
+        let _third = limiter.acquire("org1", 2, Duration::ZERO).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_org_limit_is_cached_until_invalidated() {
+        let org_id = "test_org_limit_is_cached_until_invalidated";
+        ORG_LIMITS.insert(org_id.to_string(), Some(5));
+        assert_eq!(org_limit(org_id).await, Some(5));
+        invalidate_limit(org_id);
+        assert!(!ORG_LIMITS.contains_key(org_id));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_without_limit() {
+        let limiter = OrgSearchLimiter::default();
+        let permits =
+            futures::future::join_all((0..100).map(|_| limiter.acquire("org1", 0, Duration::ZERO)))
+                .await;
+        assert!(permits.iter().all(|permit| permit.is_ok()));
+        assert_eq!(limiter.running("org1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_release() {
+        let limiter = Arc::new(OrgSearchLimiter::default());
+        let permit = limiter.acquire("org1", 1, Duration::ZERO).await.unwrap();
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                limiter
+                    .acquire("org1", 1, Duration::from_secs(5))
+                    .await
+                    .is_ok()
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(permit);
+        assert!(waiting.await.unwrap());
+        assert_eq!(limiter.running("org1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_times_out() {
+        let limiter = OrgSearchLimiter::default();
+        let _permit = limiter.acquire("org1", 1, Duration::ZERO).await.unwrap();
+        let start = std::time::Instant::now();
+        assert!(limiter
+            .acquire("org1", 1, Duration::from_millis(100))
+            .await
+            .is_err());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_search_releases_permit() {
+        let limiter = Arc::new(OrgSearchLimiter::default());
+        let search = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire("org1", 1, Duration::ZERO).await.unwrap();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(limiter.running("org1"), 1);
+        search.abort();
+        assert!(search.await.unwrap_err().is_cancelled());
+        assert_eq!(limiter.running("org1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_searches_hit_the_cap() {
+        let limiter = Arc::new(OrgSearchLimiter::default());
+        let peak = Arc::new(AtomicUsize::new(0));
+        let tasks = (0..20).map(|_| {
+            let limiter = limiter.clone();
+            let peak = peak.clone();
+            tokio::spawn(async move {
+                let Ok(_permit) = limiter.acquire("org1", 3, Duration::ZERO).await else {
+                    return false;
+                };
+                peak.fetch_max(limiter.running("org1"), Ordering::AcqRel);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                true
+            })
+        });
+        let accepted = futures::future::join_all(tasks)
+            .await
+            .into_iter()
+            .filter(|accepted| *accepted.as_ref().unwrap())
+            .count();
+        assert!((1..=3).contains(&peak.load(Ordering::Acquire)));
+        assert!(accepted >= 3 && accepted < 20);
+        // failed and cancelled searches release their permits too
+        assert_eq!(limiter.running("org1"), 0);
+    }
+}
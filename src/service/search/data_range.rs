@@ -0,0 +1,168 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::{
+    get_config,
+    meta::{
+        search::ClampedTimeRange,
+        stream::{StreamStats, StreamType},
+    },
+};
+use infra::cache::stats;
+
+/// Returns the time range of the query with its end clamped to the latest data
+/// of its streams from their stats, or `None` if it can't be narrowed. The
+/// extent of several streams is the union of their extents.
+///
+/// Only the end is clamped: records older than `doc_time_min` can be ingested
+/// at any time and are missing from the stats until their next refresh, while
+/// the records newer than `doc_time_max` are covered by moving the end by
+/// `ZO_QUERY_RANGE_CLAMP_MARGIN`. The end is widened to a multiple of `align`,
+/// so that the last histogram bucket is whole.
+pub(crate) fn clamp_to_streams(
+    org_id: &str,
+    streams: &[(String, StreamType)],
+    time_range: (i64, i64),
+    align: i64,
+) -> Option<ClampedTimeRange> {
+    let cfg = get_config();
+    if !cfg.common.query_range_clamp_enabled {
+        return None;
+    }
+    let stats = streams
+        .iter()
+        .map(|(stream_name, stream_type)| {
+            stats::get_stream_stats(org_id, stream_name, *stream_type)
+        })
+        .collect::<Vec<_>>();
+    let margin = cfg.limit.query_range_clamp_margin as i64 * 1_000_000;
+    clamp_to_stats(&stats, time_range, margin, align)
+}
+
+fn clamp_to_stats(
+    stats: &[StreamStats],
+    (start_time, end_time): (i64, i64),
+    margin: i64,
+    align: i64,
+) -> Option<ClampedTimeRange> {
+    // a stream without stats can still have data which isn't uploaded yet
+    if stats.is_empty()
+        || stats
+            .iter()
+            .any(|s| s.doc_time_min <= 0 || s.doc_time_max < s.doc_time_min)
+    {
+        return None;
+    }
+    let align = align.max(1);
+    // `doc_time_max` is the timestamp of a record, the end of a range is exclusive
+    let data_end = stats.iter().map(|s| s.doc_time_max).max()? + 1 + margin;
+    let end = end_time.min(data_end + (align - data_end.rem_euclid(align)) % align);
+    // without any data in the range there is nothing to search either way
+    if start_time >= end || end == end_time {
+        return None;
+    }
+    Some(ClampedTimeRange {
+        start: start_time,
+        end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000_000;
+    const DAY: i64 = 24 * HOUR;
+
+    fn stats(doc_time_min: i64, doc_time_max: i64) -> StreamStats {
+        StreamStats {
+            doc_time_min,
+            doc_time_max,
+            doc_num: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clamp_to_stats() {
+        let now = 100 * DAY;
+        // the last 7 days of a stream with 6 hours of data
+        let range = (now - 7 * DAY, now);
+        let six_hours = [stats(now - 6 * HOUR, now - HOUR)];
+        assert_eq!(
+            clamp_to_stats(&six_hours, range, 0, 1),
+            Some(ClampedTimeRange {
+                start: range.0,
+                end: now - HOUR + 1,
+            })
+        );
+        // widened to whole histogram buckets
+        let minute = 60_000_000;
+        let unaligned = [stats(now - 6 * HOUR + 10, now - HOUR + 10)];
+        assert_eq!(
+            clamp_to_stats(&unaligned, range, 0, minute),
+            Some(ClampedTimeRange {
+                start: range.0,
+                end: now - HOUR + minute,
+            })
+        );
+        // several streams use the union of their extents
+        let two_streams = [
+            stats(now - 6 * HOUR, now - 5 * HOUR),
+            stats(now - 2 * DAY, now - DAY),
+        ];
+        assert_eq!(
+            clamp_to_stats(&two_streams, range, 0, 1),
+            Some(ClampedTimeRange {
+                start: range.0,
+                end: now - 5 * HOUR + 1,
+            })
+        );
+        // a range ending within the data is not clamped, nor is its start
+        assert_eq!(
+            clamp_to_stats(&six_hours, (now - 3 * DAY, now - 2 * HOUR), 0, 1),
+            None
+        );
+        // a range after the data is left as it is
+        assert_eq!(
+            clamp_to_stats(&six_hours, (now - 30 * 60_000_000, now), 0, 1),
+            None
+        );
+        // nor are streams without stats
+        assert_eq!(clamp_to_stats(&[], range, 0, 1), None);
+        assert_eq!(
+            clamp_to_stats(&[six_hours[0].clone(), StreamStats::default()], range, 0, 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clamp_to_stats_margin() {
+        let now = 100 * DAY;
+        let range = (now - 7 * DAY, now);
+        let six_hours = [stats(now - 6 * HOUR, now - HOUR)];
+        let margin = 10 * 60 * 1_000_000;
+        assert_eq!(
+            clamp_to_stats(&six_hours, range, margin, 1),
+            Some(ClampedTimeRange {
+                start: range.0,
+                end: now - HOUR + 1 + margin,
+            })
+        );
+        // recent data which isn't in the stats yet stays in the range
+        let fresh = [stats(now - 6 * HOUR, now - 60_000_000)];
+        assert_eq!(clamp_to_stats(&fresh, range, margin, 1), None);
+    }
+}
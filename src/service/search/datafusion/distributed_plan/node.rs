@@ -35,6 +35,7 @@ pub struct RemoteScanNodes {
     pub nodes: Vec<Arc<dyn NodeInfo>>,
     pub file_id_lists: HashMap<TableReference, Vec<Vec<i64>>>,
     pub idx_file_list: Vec<FileKey>,
+    pub seam_files: HashMap<TableReference, Vec<String>>,
     pub equal_keys: HashMap<TableReference, Vec<KvItem>>,
     pub match_all_keys: Vec<String>,
     pub index_condition: Option<IndexCondition>,
@@ -50,6 +51,7 @@ impl RemoteScanNodes {
         nodes: Vec<Arc<dyn NodeInfo>>,
         file_id_lists: HashMap<TableReference, Vec<Vec<i64>>>,
         idx_file_list: Vec<FileKey>,
+        seam_files: HashMap<TableReference, Vec<String>>,
         equal_keys: HashMap<TableReference, Vec<KvItem>>,
         match_all_keys: Vec<String>,
         index_condition: Option<IndexCondition>,
@@ -62,6 +64,7 @@ impl RemoteScanNodes {
             nodes,
             file_id_lists,
             idx_file_list,
+            seam_files,
             equal_keys,
             match_all_keys,
             index_condition,
@@ -88,6 +91,7 @@ impl RemoteScanNodes {
                 .unwrap_or(&vec![])
                 .clone(),
             idx_file_list: self.idx_file_list.clone(),
+            seam_files: self.seam_files.get(table_name).unwrap_or(&vec![]).clone(),
             start_time: self.req.time_range.as_ref().map(|x| x.0).unwrap_or(0),
             end_time: self.req.time_range.as_ref().map(|x| x.1).unwrap_or(0),
             timeout: self.req.timeout as u64,
@@ -190,6 +194,7 @@ pub struct SearchInfos {
     pub plan: Vec<u8>,
     pub file_id_list: Vec<Vec<i64>>,
     pub idx_file_list: Vec<FileKey>,
+    pub seam_files: Vec<String>,
     pub start_time: i64,
     pub end_time: i64,
     pub timeout: u64,
@@ -217,6 +222,7 @@ impl SearchInfos {
             plan: self.plan.clone(),
             file_id_list,
             idx_file_list,
+            seam_files: self.seam_files.clone(),
             start_time: self.start_time,
             end_time: self.end_time,
             timeout: self.timeout as i64,
@@ -27,7 +27,7 @@ use arrow_flight::{
 };
 use arrow_schema::{Schema, SchemaRef};
 use config::{
-    meta::search::{ScanStats, SearchEventType},
+    meta::search::{ExecutionStats, ResponseNodeTook, ScanStats, SearchEventType},
     utils::rand::generate_random_string,
 };
 use datafusion::{
@@ -65,6 +65,9 @@ pub struct RemoteScanExec {
     cache: PlanProperties,
     pub scan_stats: Arc<Mutex<ScanStats>>,
     pub partial_err: Arc<Mutex<String>>,
+    /// row group pruning and peak memory of the remote nodes, sent after the
+    /// last batch
+    pub execution_stats: Arc<Mutex<ExecutionStats>>,
 }
 
 impl RemoteScanExec {
@@ -91,6 +94,7 @@ impl RemoteScanExec {
             cache,
             scan_stats: Arc::new(Mutex::new(ScanStats::default())),
             partial_err: Arc::new(Mutex::new(String::new())),
+            execution_stats: Arc::new(Mutex::new(ExecutionStats::default())),
         })
     }
 
@@ -167,6 +171,7 @@ impl ExecutionPlan for RemoteScanExec {
             self.input.schema().clone(),
             self.scan_stats.clone(),
             self.partial_err.clone(),
+            self.execution_stats.clone(),
         );
         let stream = futures::stream::once(fut).try_flatten();
         Ok(Box::pin(RecordBatchStreamAdapter::new(
@@ -186,6 +191,7 @@ async fn get_remote_batch(
     schema: SchemaRef,
     scan_stats: Arc<Mutex<ScanStats>>,
     partial_err: Arc<Mutex<String>>,
+    execution_stats: Arc<Mutex<ExecutionStats>>,
 ) -> Result<SendableRecordBatchStream> {
     let start = std::time::Instant::now();
     let cfg = config::get_config();
@@ -339,6 +345,10 @@ async fn get_remote_batch(
     };
     // convert FlightData to a stream
     let schema = Arc::new(Schema::try_from(&flight_data)?);
+    // a response without any batch carries the execution stats in the schema message
+    if !flight_data.app_metadata.is_empty() {
+        merge_execution_stats(&execution_stats, &flight_data);
+    }
 
     let mut files = 0;
     let mut scan_size = 0;
@@ -358,6 +368,7 @@ async fn get_remote_batch(
         files,
         scan_size,
         partial_err,
+        execution_stats,
         start,
         timeout,
     )))
@@ -404,6 +415,7 @@ struct FlightStream {
     files: i64,
     scan_size: i64,
     partial_err: Arc<Mutex<String>>,
+    execution_stats: Arc<Mutex<ExecutionStats>>,
     start: std::time::Instant,
     timeout: u64,
 }
@@ -419,6 +431,7 @@ impl FlightStream {
         files: i64,
         scan_size: i64,
         partial_err: Arc<Mutex<String>>,
+        execution_stats: Arc<Mutex<ExecutionStats>>,
         start: std::time::Instant,
         timeout: u64,
     ) -> Self {
@@ -431,6 +444,7 @@ impl FlightStream {
             files,
             scan_size,
             partial_err,
+            execution_stats,
             start,
             timeout,
         }
@@ -461,6 +475,13 @@ impl Stream for FlightStream {
         let dictionaries_by_field = HashMap::new();
         match self.stream.poll_next_unpin(cx) {
             Poll::Ready(Some(Ok(flight_data))) => {
+                // the last message also carries the execution stats of the node
+                if !flight_data.app_metadata.is_empty() {
+                    merge_execution_stats(&self.execution_stats, &flight_data);
+                }
+                if flight_data.data_header.is_empty() {
+                    return self.poll_next(cx);
+                }
                 let record_batch = flight_data_to_arrow_batch(
                     &flight_data,
                     self.schema.clone(),
@@ -486,8 +507,19 @@ impl Stream for FlightStream {
     }
 }
 
+fn merge_execution_stats(execution_stats: &Mutex<ExecutionStats>, flight_data: &FlightData) {
+    if let Ok(stats) = serde_json::from_slice::<ExecutionStats>(&flight_data.app_metadata) {
+        execution_stats.lock().merge(&stats);
+    }
+}
+
 impl Drop for FlightStream {
     fn drop(&mut self) {
+        self.execution_stats.lock().nodes.push(ResponseNodeTook {
+            node: self.node_addr.clone(),
+            is_ingester: !self.is_querier,
+            took: self.start.elapsed().as_millis() as usize,
+        });
         log::info!(
             "[trace_id {}] flight->search: response node: {}, is_querier: {}, files: {}, scan_size: {} mb, took: {} ms",
             self.trace_id,
@@ -50,6 +50,7 @@ impl RemoteScanRewriter {
         nodes: Vec<Arc<dyn NodeInfo>>,
         file_id_lists: HashMap<TableReference, Vec<Vec<i64>>>,
         idx_file_list: Vec<FileKey>,
+        seam_files: HashMap<TableReference, Vec<String>>,
         equal_keys: HashMap<TableReference, Vec<KvItem>>,
         match_all_keys: Vec<String>,
         index_condition: Option<IndexCondition>,
@@ -63,6 +64,7 @@ impl RemoteScanRewriter {
                 nodes,
                 file_id_lists,
                 idx_file_list,
+                seam_files,
                 equal_keys,
                 match_all_keys,
                 index_condition,
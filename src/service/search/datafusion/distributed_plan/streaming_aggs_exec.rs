@@ -22,6 +22,14 @@ use std::{
 };
 
 use arrow::{array::RecordBatch, datatypes::SchemaRef};
+use config::{
+    get_config,
+    meta::stream::{FileKey, StreamType},
+    utils::{
+        parquet::parse_file_key_columns,
+        time::{now_micros, second_micros},
+    },
+};
 use dashmap::DashMap;
 use datafusion::{
     common::{Result, Statistics},
@@ -34,6 +42,7 @@ use datafusion::{
 };
 use futures::{Stream, StreamExt};
 use once_cell::sync::Lazy;
+use serde::Serialize;
 
 pub static GLOBAL_CACHE: Lazy<Arc<StreamingAggsCache>> =
     Lazy::new(|| Arc::new(StreamingAggsCache::default()));
@@ -41,15 +50,55 @@ pub static GLOBAL_CACHE: Lazy<Arc<StreamingAggsCache>> =
 pub static GLOBAL_ID_CACHE: Lazy<Arc<StreamingIdCache>> =
     Lazy::new(|| Arc::new(StreamingIdCache::default()));
 
-// init streaming cache for the id
-pub fn init_cache(id: &str, start_time: i64, end_time: i64) {
-    GLOBAL_ID_CACHE.insert(id.to_string(), start_time, end_time);
+/// The streams and the time range the partial aggregations of a streaming id
+/// are computed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamingAggsScope {
+    pub org_id: String,
+    pub stream_type: StreamType,
+    pub streams: Vec<String>,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// A streaming aggregation cache entry as listed by the node cache endpoint.
+#[derive(Clone, Debug, Serialize)]
+pub struct StreamingAggsEntry {
+    pub id: String,
+    pub org_id: String,
+    pub stream_type: StreamType,
+    pub streams: Vec<String>,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub batches: usize,
+    pub hits: u64,
+    pub created_at: i64,
+    /// seconds since the entry was created
+    pub age: i64,
+}
+
+// init streaming cache for the id, returns false when the time range can't be cached
+pub fn init_cache(id: &str, scope: StreamingAggsScope) -> bool {
+    // the data newer than max_file_retention_time is still being written, the
+    // partial aggregations of it would be stale before the query finishes
+    let max_ts = now_micros() - second_micros(get_config().limit.max_file_retention_time as i64);
+    if scope.end_time > max_ts {
+        log::debug!(
+            "[StreamingAggs] init_cache: id={}, end_time={} is newer than {}, skip",
+            id,
+            scope.end_time,
+            max_ts
+        );
+        return false;
+    }
     log::debug!(
         "[StreamingAggs] init_cache: id={}, start_time={}, end_time={}",
         id,
-        start_time,
-        end_time
+        scope.start_time,
+        scope.end_time
     );
+    GLOBAL_ID_CACHE.insert(id.to_string(), scope);
+    true
 }
 
 // remove streaming cache for the id
@@ -59,6 +108,99 @@ pub fn remove_cache(id: &str) {
     log::debug!("[StreamingAggs] remove_cache: id={}", id);
 }
 
+/// Evicts the entry of the streaming id, returns false when there is no such entry.
+pub fn evict(id: &str) -> bool {
+    if !GLOBAL_ID_CACHE.contains(id) {
+        return false;
+    }
+    remove_cache(id);
+    true
+}
+
+/// Lists the entries of the streaming aggregation cache.
+pub fn list_cache() -> Vec<StreamingAggsEntry> {
+    let now = now_micros();
+    let mut entries = GLOBAL_ID_CACHE
+        .data
+        .iter()
+        .map(|item| {
+            let v = item.value();
+            StreamingAggsEntry {
+                id: item.key().clone(),
+                org_id: v.scope.org_id.clone(),
+                stream_type: v.scope.stream_type,
+                streams: v.scope.streams.clone(),
+                start_time: v.scope.start_time,
+                end_time: v.scope.end_time,
+                batches: GLOBAL_CACHE.data.get(item.key()).map_or(0, |d| d.len()),
+                hits: v.hits,
+                created_at: v.created_at,
+                age: (now - v.created_at) / 1_000_000,
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    entries
+}
+
+/// Evicts the entries computed from data of the stream in the time range, called
+/// when the data is rewritten by the compactor or deleted by the retention.
+/// Returns the evicted ids.
+pub fn invalidate(
+    org_id: &str,
+    stream_type: StreamType,
+    stream_name: &str,
+    time_range: (i64, i64),
+) -> Vec<String> {
+    let ids = GLOBAL_ID_CACHE
+        .data
+        .iter()
+        .filter(|item| {
+            let scope = &item.value().scope;
+            scope.org_id == org_id
+                && scope.stream_type == stream_type
+                && scope.streams.iter().any(|s| s == stream_name)
+                && scope.start_time <= time_range.1
+                && time_range.0 <= scope.end_time
+        })
+        .map(|item| item.key().clone())
+        .collect::<Vec<_>>();
+    for id in ids.iter() {
+        log::info!(
+            "[StreamingAggs] invalidate: id={}, stream={}/{}/{}, time_range={:?}",
+            id,
+            org_id,
+            stream_type,
+            stream_name,
+            time_range
+        );
+        remove_cache(id);
+    }
+    ids
+}
+
+/// Evicts the entries overlapping the time ranges of the added or deleted files.
+pub fn invalidate_files(files: &[FileKey]) {
+    if GLOBAL_ID_CACHE.data.is_empty() {
+        return;
+    }
+    for file in files {
+        let Ok((stream_key, ..)) = parse_file_key_columns(&file.key) else {
+            continue;
+        };
+        let columns = stream_key.splitn(3, '/').collect::<Vec<_>>();
+        if columns.len() < 3 {
+            continue;
+        }
+        invalidate(
+            columns[0],
+            StreamType::from(columns[1]),
+            columns[2],
+            (file.meta.min_ts, file.meta.max_ts),
+        );
+    }
+}
+
 #[derive(Debug)]
 pub struct StreamingAggsExec {
     id: String,
@@ -225,7 +367,8 @@ impl Stream for MonitorStream {
             Poll::Ready(Some(Ok(record_batch))) => {
                 let streaming_done =
                     GLOBAL_ID_CACHE.check_time(&self.id, self.start_time, self.end_time);
-                if !streaming_done {
+                // the entry may have been invalidated while the query is running
+                if !streaming_done && GLOBAL_ID_CACHE.contains(&self.id) {
                     GLOBAL_CACHE.insert(self.id.clone(), record_batch.clone());
                 }
                 Poll::Ready(Some(Ok(record_batch)))
@@ -276,7 +419,11 @@ impl StreamingAggsCache {
     }
 
     pub fn get(&self, k: &str) -> Option<Vec<Arc<RecordBatch>>> {
-        self.data.get(k).map(|v| v.value().clone())
+        let v = self.data.get(k).map(|v| v.value().clone());
+        if v.is_some() {
+            GLOBAL_ID_CACHE.hit(k);
+        }
+        v
     }
 
     pub fn insert(&self, k: String, v: RecordBatch) {
@@ -303,7 +450,7 @@ impl Default for StreamingAggsCache {
         Self::new(
             config::get_config()
                 .limit
-                .datafusion_file_stat_cache_max_entries,
+                .datafusion_streaming_aggs_cache_max_entries,
         )
     }
 }
@@ -319,9 +466,18 @@ impl StreamingIdCache {
         }
     }
 
-    pub fn insert(&self, k: String, start_time: i64, end_time: i64) {
-        self.data
-            .insert(k, StreamingIdItem::new(start_time, end_time));
+    pub fn insert(&self, k: String, scope: StreamingAggsScope) {
+        self.data.insert(k, StreamingIdItem::new(scope));
+    }
+
+    pub fn contains(&self, k: &str) -> bool {
+        self.data.contains_key(k)
+    }
+
+    pub fn hit(&self, k: &str) {
+        if let Some(mut v) = self.data.get_mut(k) {
+            v.hits += 1;
+        }
     }
 
     pub fn check_time(&self, k: &str, start_time: i64, end_time: i64) -> bool {
@@ -343,29 +499,114 @@ impl Default for StreamingIdCache {
 }
 
 struct StreamingIdItem {
-    start_time: i64,
-    end_time: i64,
+    scope: StreamingAggsScope,
     start_ok: bool,
     end_ok: bool,
+    hits: u64,
+    created_at: i64,
 }
 
 impl StreamingIdItem {
-    pub fn new(start_time: i64, end_time: i64) -> Self {
+    pub fn new(scope: StreamingAggsScope) -> Self {
         Self {
-            start_time,
-            end_time,
+            scope,
             start_ok: false,
             end_ok: false,
+            hits: 0,
+            created_at: now_micros(),
         }
     }
 
     pub fn check_time(&mut self, start_time: i64, end_time: i64) -> bool {
-        if start_time == self.start_time {
+        if start_time == self.scope.start_time {
             self.start_ok = true;
         }
-        if end_time == self.end_time {
+        if end_time == self.scope.end_time {
             self.end_ok = true;
         }
         self.start_ok && self.end_ok
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use config::meta::stream::FileMeta;
+
+    use super::*;
+
+    fn scope(stream: &str, start_time: i64, end_time: i64) -> StreamingAggsScope {
+        StreamingAggsScope {
+            org_id: "default".to_string(),
+            stream_type: StreamType::Logs,
+            streams: vec![stream.to_string()],
+            start_time,
+            end_time,
+        }
+    }
+
+    fn batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("cnt", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1]))]).unwrap()
+    }
+
+    #[test]
+    fn test_streaming_aggs_invalidate_overlapping() {
+        let hour = second_micros(3600);
+        let end_time = now_micros() - 24 * hour;
+        assert!(init_cache(
+            "test_invalidate_a",
+            scope("invalidate_logs", end_time - hour, end_time)
+        ));
+        assert!(init_cache(
+            "test_invalidate_b",
+            scope("invalidate_logs", end_time - 3 * hour, end_time - 2 * hour)
+        ));
+        GLOBAL_CACHE.insert("test_invalidate_a".to_string(), batch());
+        assert!(GLOBAL_CACHE.get("test_invalidate_a").is_some());
+        let entry = list_cache()
+            .into_iter()
+            .find(|e| e.id == "test_invalidate_a")
+            .unwrap();
+        assert_eq!((entry.batches, entry.hits), (1, 1));
+
+        // other streams and time ranges are kept
+        assert!(invalidate(
+            "default",
+            StreamType::Logs,
+            "other_logs",
+            (end_time - hour, end_time)
+        )
+        .is_empty());
+        let file = FileKey::new(
+            "files/default/logs/invalidate_logs/2024/01/01/00/7000000000000000000.parquet"
+                .to_string(),
+            FileMeta {
+                min_ts: end_time - hour / 2,
+                max_ts: end_time - hour / 4,
+                ..Default::default()
+            },
+            false,
+        );
+        invalidate_files(&[file]);
+        assert!(!GLOBAL_ID_CACHE.contains("test_invalidate_a"));
+        assert!(GLOBAL_CACHE.get("test_invalidate_a").is_none());
+        assert!(GLOBAL_ID_CACHE.contains("test_invalidate_b"));
+
+        assert!(evict("test_invalidate_b"));
+        assert!(!evict("test_invalidate_b"));
+    }
+
+    #[test]
+    fn test_streaming_aggs_refuse_recent_range() {
+        let now = now_micros();
+        assert!(!init_cache(
+            "test_recent",
+            scope("recent_logs", now - second_micros(3600), now)
+        ));
+        assert!(!GLOBAL_ID_CACHE.contains("test_recent"));
+    }
+}
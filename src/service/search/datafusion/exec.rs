@@ -38,7 +38,7 @@ use datafusion::{
     execution::{
         cache::cache_manager::{CacheManagerConfig, FileStatisticsCache},
         context::SessionConfig,
-        memory_pool::{FairSpillPool, GreedyMemoryPool},
+        memory_pool::{FairSpillPool, GreedyMemoryPool, MemoryPool, UnboundedMemoryPool},
         runtime_env::{RuntimeConfig, RuntimeEnv},
         session_state::SessionStateBuilder,
     },
@@ -63,6 +63,7 @@ use {
 use super::{
     file_type::{FileType, GetExt},
     optimizer::join_reorder::JoinReorderRule,
+    peak_memory::{PeakMemory, PeakMemoryPool},
     planner::extension_planner::OpenobserveQueryPlanner,
     storage::file_list,
     table_provider::{uniontable::NewUnionTable, NewListingTable},
@@ -361,7 +362,10 @@ pub fn create_session_config(
     Ok(config)
 }
 
-pub async fn create_runtime_env(memory_limit: usize) -> Result<RuntimeEnv> {
+pub async fn create_runtime_env(
+    memory_limit: usize,
+    peak_memory: Arc<PeakMemory>,
+) -> Result<RuntimeEnv> {
     let object_store_registry = DefaultObjectStoreRegistry::new();
 
     let memory = super::storage::memory::FS::new();
@@ -392,15 +396,12 @@ pub async fn create_runtime_env(memory_limit: usize) -> Result<RuntimeEnv> {
         .map_err(|e| {
             DataFusionError::Execution(format!("Invalid datafusion memory pool type: {}", e))
         })?;
-    match mem_pool {
-        super::MemoryPoolType::Greedy => {
-            rn_config = rn_config.with_memory_pool(Arc::new(GreedyMemoryPool::new(memory_size)))
-        }
-        super::MemoryPoolType::Fair => {
-            rn_config = rn_config.with_memory_pool(Arc::new(FairSpillPool::new(memory_size)))
-        }
-        super::MemoryPoolType::None => {}
+    let memory_pool: Arc<dyn MemoryPool> = match mem_pool {
+        super::MemoryPoolType::Greedy => Arc::new(GreedyMemoryPool::new(memory_size)),
+        super::MemoryPoolType::Fair => Arc::new(FairSpillPool::new(memory_size)),
+        super::MemoryPoolType::None => Arc::new(UnboundedMemoryPool::default()),
     };
+    rn_config = rn_config.with_memory_pool(Arc::new(PeakMemoryPool::new(memory_pool, peak_memory)));
     RuntimeEnv::try_new(rn_config)
 }
 
@@ -419,8 +420,10 @@ pub async fn prepare_datafusion_context(
     let (target_partition, memory_size) =
         get_cpu_and_mem_limit(_work_group.clone(), target_partition, memory_size).await?;
 
-    let session_config = create_session_config(sorted_by_time, target_partition)?;
-    let runtime_env = Arc::new(create_runtime_env(memory_size).await?);
+    let peak_memory = Arc::new(PeakMemory::default());
+    let session_config = create_session_config(sorted_by_time, target_partition)?
+        .with_extension(peak_memory.clone());
+    let runtime_env = Arc::new(create_runtime_env(memory_size, peak_memory).await?);
     let mut builder = SessionStateBuilder::new()
         .with_config(session_config)
         .with_runtime_env(runtime_env)
@@ -457,6 +460,7 @@ pub fn register_udf(ctx: &SessionContext, org_id: &str) -> Result<()> {
     ctx.register_udf(super::udf::spath_udf::SPATH_UDF.clone());
     ctx.register_udf(super::udf::to_arr_string_udf::TO_ARR_STRING.clone());
     ctx.register_udf(super::udf::histogram_udf::HISTOGRAM_UDF.clone());
+    ctx.register_udf(super::udf::date_bin_tz_udf::DATE_BIN_TZ_UDF.clone());
     ctx.register_udf(super::udf::match_all_udf::MATCH_ALL_RAW_UDF.clone());
     ctx.register_udf(super::udf::match_all_udf::MATCH_ALL_RAW_IGNORE_CASE_UDF.clone());
     ctx.register_udf(super::udf::match_all_udf::MATCH_ALL_UDF.clone());
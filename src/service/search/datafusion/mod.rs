@@ -19,8 +19,10 @@ pub mod distributed_plan;
 pub mod exec;
 pub mod file_type;
 pub mod optimizer;
+pub mod peak_memory;
 pub mod plan;
 pub mod planner;
+pub mod pruning;
 pub mod storage;
 pub mod table_provider;
 pub mod udaf;
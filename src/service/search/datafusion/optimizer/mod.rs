@@ -109,7 +109,11 @@ pub fn generate_optimizer_rules(sql: &Sql) -> Vec<Arc<dyn OptimizerRule + Send +
     rules.push(Arc::new(EliminateOuterJoin::new()));
 
     // *********** custom rules ***********
-    rules.push(Arc::new(RewriteHistogram::new(start_time, end_time)));
+    rules.push(Arc::new(RewriteHistogram::new(
+        start_time,
+        end_time,
+        sql.histogram_timezone.clone(),
+    )));
     if let Some(limit) = limit {
         rules.push(Arc::new(AddSortAndLimitRule::new(limit, offset)));
     };
@@ -15,6 +15,7 @@
 
 use std::sync::Arc;
 
+use arrow::datatypes::IntervalMonthDayNano;
 use arrow_schema::{DataType, IntervalUnit};
 use datafusion::{
     common::{
@@ -32,22 +33,27 @@ use datafusion::{
 };
 
 use crate::service::search::{
-    datafusion::udf::histogram_udf::HISTOGRAM_UDF_NAME, sql::generate_histogram_interval,
+    datafusion::udf::{date_bin_tz_udf::DATE_BIN_TZ_UDF, histogram_udf::HISTOGRAM_UDF_NAME},
+    sql::{generate_histogram_interval, normalize_histogram_timezone, parse_calendar_interval},
 };
 
-/// Optimization rule that rewrite histogram to date_bin()
+/// Optimization rule that rewrite histogram to date_bin(), or to date_bin_tz()
+/// when the buckets are aligned to a time zone or are calendar months
 #[derive(Default, Debug)]
 pub struct RewriteHistogram {
     start_time: i64,
     end_time: i64,
+    /// time zone used when histogram() has no time zone argument
+    timezone: Option<String>,
 }
 
 impl RewriteHistogram {
     #[allow(missing_docs)]
-    pub fn new(start_time: i64, end_time: i64) -> Self {
+    pub fn new(start_time: i64, end_time: i64, timezone: Option<String>) -> Self {
         Self {
             start_time,
             end_time,
+            timezone,
         }
     }
 }
@@ -76,7 +82,8 @@ impl OptimizerRule for RewriteHistogram {
             .map(|expr| expr.exists(|expr| Ok(is_histogram(expr))).unwrap())
             .any(|x| x)
         {
-            let mut expr_rewriter = HistogramToDatebin::new(self.start_time, self.end_time);
+            let mut expr_rewriter =
+                HistogramToDatebin::new(self.start_time, self.end_time, self.timezone.clone());
 
             let name_preserver = NamePreserver::new(&plan);
             plan.map_expressions(|expr| {
@@ -99,13 +106,15 @@ fn is_histogram(expr: &Expr) -> bool {
 pub struct HistogramToDatebin {
     start_time: i64,
     end_time: i64,
+    timezone: Option<String>,
 }
 
 impl HistogramToDatebin {
-    pub fn new(start_time: i64, end_time: i64) -> Self {
+    pub fn new(start_time: i64, end_time: i64, timezone: Option<String>) -> Self {
         Self {
             start_time,
             end_time,
+            timezone,
         }
     }
 }
@@ -118,8 +127,8 @@ impl TreeNodeRewriter for HistogramToDatebin {
             Expr::ScalarFunction(ScalarFunction { func, args }) => {
                 let name = func.name();
                 if name == HISTOGRAM_UDF_NAME {
-                    let new_func = Arc::new(ScalarUDF::from(DateBinFunc::new()));
-                    // construct interval
+                    // construct interval, calendar intervals are binned by date_bin_tz()
+                    let mut is_calendar = false;
                     let arg1 = if args.len() == 1 {
                         let interval =
                             generate_histogram_interval(Some((self.start_time, self.end_time)), 0);
@@ -127,7 +136,7 @@ impl TreeNodeRewriter for HistogramToDatebin {
                             Expr::Literal(ScalarValue::from(interval)),
                             DataType::Interval(IntervalUnit::MonthDayNano),
                         )
-                    } else if args.len() == 2 {
+                    } else if args.len() == 2 || args.len() == 3 {
                         if let Expr::Literal(ScalarValue::Int64(Some(num))) = &args[1] {
                             let interval = generate_histogram_interval(
                                 Some((self.start_time, self.end_time)),
@@ -137,6 +146,19 @@ impl TreeNodeRewriter for HistogramToDatebin {
                                 Expr::Literal(ScalarValue::from(interval)),
                                 DataType::Interval(IntervalUnit::MonthDayNano),
                             )
+                        } else if let Expr::Literal(ScalarValue::Utf8(Some(interval))) = &args[1] {
+                            match parse_calendar_interval(interval) {
+                                Some(months) => {
+                                    is_calendar = true;
+                                    Expr::Literal(ScalarValue::IntervalMonthDayNano(Some(
+                                        IntervalMonthDayNano::new(months as i32, 0, 0),
+                                    )))
+                                }
+                                None => cast(
+                                    args[1].clone(),
+                                    DataType::Interval(IntervalUnit::MonthDayNano),
+                                ),
+                            }
                         } else if let Expr::Literal(ScalarValue::Utf8(_)) = &args[1] {
                             cast(
                                 args[1].clone(),
@@ -159,13 +181,36 @@ impl TreeNodeRewriter for HistogramToDatebin {
                         func: Arc::new(ScalarUDF::from(ToTimestampMicrosFunc::new())),
                         args: vec![args[0].clone()],
                     });
+                    // construct time zone, the argument takes precedence over the request's one
+                    let timezone = match args.get(2) {
+                        Some(Expr::Literal(ScalarValue::Utf8(Some(tz)))) => {
+                            normalize_histogram_timezone(tz)
+                        }
+                        Some(arg) => {
+                            return Err(DataFusionError::Plan(format!(
+                                "The time zone of histogram function must be a string, got: {:?}",
+                                arg
+                            )));
+                        }
+                        None => self.timezone.clone(),
+                    };
+                    let timezone = match timezone {
+                        None if is_calendar => Some("UTC".to_string()),
+                        timezone => timezone,
+                    };
+                    if let Some(timezone) = timezone {
+                        return Ok(Transformed::yes(Expr::ScalarFunction(ScalarFunction {
+                            func: Arc::new(DATE_BIN_TZ_UDF.clone()),
+                            args: vec![arg1, arg2, Expr::Literal(ScalarValue::from(timezone))],
+                        })));
+                    }
                     // construct optional origin-timestamp
                     let arg3 = Expr::ScalarFunction(ScalarFunction {
                         func: Arc::new(ScalarUDF::from(ToTimestampFunc::new())),
                         args: vec![Expr::Literal(ScalarValue::from("2001-01-01T00:00:00"))],
                     });
                     return Ok(Transformed::yes(Expr::ScalarFunction(ScalarFunction {
-                        func: new_func,
+                        func: Arc::new(ScalarUDF::from(DateBinFunc::new())),
                         args: vec![arg1, arg2, arg3],
                     })));
                 }
@@ -269,7 +314,7 @@ mod tests {
         let provider = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
         ctx.register_table("t", Arc::new(provider)).unwrap();
         ctx.register_udf(histogram_udf::HISTOGRAM_UDF.clone());
-        ctx.add_optimizer_rule(Arc::new(RewriteHistogram::new(0, 5)));
+        ctx.add_optimizer_rule(Arc::new(RewriteHistogram::new(0, 5, None)));
 
         for item in sqls {
             let df = ctx.sql(item.0).await.unwrap();
@@ -277,4 +322,164 @@ mod tests {
             assert_batches_eq!(item.1, &data);
         }
     }
+
+    #[tokio::test]
+    async fn test_rewrite_histogram_timezone() {
+        // (sql, default time zone of the request, expected)
+        let sqls = [
+            (
+                "select histogram(_timestamp, '1 day', 'Asia/Kolkata') from t",
+                None,
+                vec![
+                    "+------------------------------------------------------------+",
+                    "| histogram(t._timestamp,Utf8(\"1 day\"),Utf8(\"Asia/Kolkata\")) |",
+                    "+------------------------------------------------------------+",
+                    "| 1969-12-31T18:30:00                                        |",
+                    "| 1969-12-31T18:30:00                                        |",
+                    "| 1969-12-31T18:30:00                                        |",
+                    "| 1969-12-31T18:30:00                                        |",
+                    "| 1969-12-31T18:30:00                                        |",
+                    "+------------------------------------------------------------+",
+                ],
+            ),
+            (
+                "select histogram(_timestamp, '1 day') from t",
+                Some("Asia/Kolkata"),
+                vec![
+                    "+---------------------------------------+",
+                    "| histogram(t._timestamp,Utf8(\"1 day\")) |",
+                    "+---------------------------------------+",
+                    "| 1969-12-31T18:30:00                   |",
+                    "| 1969-12-31T18:30:00                   |",
+                    "| 1969-12-31T18:30:00                   |",
+                    "| 1969-12-31T18:30:00                   |",
+                    "| 1969-12-31T18:30:00                   |",
+                    "+---------------------------------------+",
+                ],
+            ),
+            (
+                "select histogram(_timestamp, '1 day', 'UTC') from t",
+                Some("Asia/Kolkata"),
+                vec![
+                    "+---------------------------------------------------+",
+                    "| histogram(t._timestamp,Utf8(\"1 day\"),Utf8(\"UTC\")) |",
+                    "+---------------------------------------------------+",
+                    "| 1970-01-01T00:00:00                               |",
+                    "| 1970-01-01T00:00:00                               |",
+                    "| 1970-01-01T00:00:00                               |",
+                    "| 1970-01-01T00:00:00                               |",
+                    "| 1970-01-01T00:00:00                               |",
+                    "+---------------------------------------------------+",
+                ],
+            ),
+        ];
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "_timestamp",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5]))],
+        )
+        .unwrap();
+
+        for (sql, timezone, expected) in sqls {
+            let ctx = SessionContext::new();
+            let provider = MemTable::try_new(schema.clone(), vec![vec![batch.clone()]]).unwrap();
+            ctx.register_table("t", Arc::new(provider)).unwrap();
+            ctx.register_udf(histogram_udf::HISTOGRAM_UDF.clone());
+            ctx.add_optimizer_rule(Arc::new(RewriteHistogram::new(
+                0,
+                5,
+                timezone.map(|v| v.to_string()),
+            )));
+
+            let df = ctx.sql(sql).await.unwrap();
+            let data = df.collect().await.unwrap();
+            assert_batches_eq!(expected, &data);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_histogram_calendar_interval() {
+        // one record per hour from 2024-01-01 to 2024-07-01, across the leap
+        // February and the first quarter boundary
+        let start = 1_704_067_200_000_000_i64;
+        let end = 1_719_792_000_000_000_i64;
+        let hour = 3_600_000_000_i64;
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "_timestamp",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(
+                (start..end).step_by(hour as usize).collect::<Vec<_>>(),
+            ))],
+        )
+        .unwrap();
+
+        let ctx = SessionContext::new();
+        let provider = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+        ctx.register_table("t", Arc::new(provider)).unwrap();
+        ctx.register_udf(histogram_udf::HISTOGRAM_UDF.clone());
+        ctx.add_optimizer_rule(Arc::new(RewriteHistogram::new(start, end, None)));
+
+        let sqls = [
+            (
+                "select histogram(_timestamp, '1 month') as k, count(*) as cnt from t group by k order by k",
+                vec![
+                    "+---------------------+-----+",
+                    "| k                   | cnt |",
+                    "+---------------------+-----+",
+                    "| 2024-01-01T00:00:00 | 744 |",
+                    "| 2024-02-01T00:00:00 | 696 |",
+                    "| 2024-03-01T00:00:00 | 744 |",
+                    "| 2024-04-01T00:00:00 | 720 |",
+                    "| 2024-05-01T00:00:00 | 744 |",
+                    "| 2024-06-01T00:00:00 | 720 |",
+                    "+---------------------+-----+",
+                ],
+            ),
+            (
+                "select histogram(_timestamp, '1 quarter') as k, count(*) as cnt from t group by k order by k",
+                vec![
+                    "+---------------------+------+",
+                    "| k                   | cnt  |",
+                    "+---------------------+------+",
+                    "| 2024-01-01T00:00:00 | 2184 |",
+                    "| 2024-04-01T00:00:00 | 2184 |",
+                    "+---------------------+------+",
+                ],
+            ),
+            (
+                "select histogram(_timestamp, '1 year') as k, count(*) as cnt from t group by k order by k",
+                vec![
+                    "+---------------------+------+",
+                    "| k                   | cnt  |",
+                    "+---------------------+------+",
+                    "| 2024-01-01T00:00:00 | 4368 |",
+                    "+---------------------+------+",
+                ],
+            ),
+            (
+                "select histogram(_timestamp, '1 month', 'Asia/Kolkata') as k, count(*) as cnt from t group by k order by k limit 2",
+                vec![
+                    "+---------------------+-----+",
+                    "| k                   | cnt |",
+                    "+---------------------+-----+",
+                    "| 2023-12-31T18:30:00 | 739 |",
+                    "| 2024-01-31T18:30:00 | 696 |",
+                    "+---------------------+-----+",
+                ],
+            ),
+        ];
+        for (sql, expected) in sqls {
+            let data = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+            assert_batches_eq!(expected, &data);
+        }
+    }
 }
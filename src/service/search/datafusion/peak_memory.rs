@@ -0,0 +1,124 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use datafusion::{
+    error::Result,
+    execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation},
+    prelude::SessionContext,
+};
+
+/// The most memory a query reserved at once, shared between its memory pool
+/// and its session config so it can be read once the query was executed.
+#[derive(Debug, Default)]
+pub struct PeakMemory(AtomicUsize);
+
+impl PeakMemory {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn update(&self, reserved: usize) {
+        self.0.fetch_max(reserved, Ordering::Relaxed);
+    }
+}
+
+/// Wraps the memory pool of a query to record its peak reservation
+#[derive(Debug)]
+pub struct PeakMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    peak: Arc<PeakMemory>,
+}
+
+impl PeakMemoryPool {
+    pub fn new(inner: Arc<dyn MemoryPool>, peak: Arc<PeakMemory>) -> Self {
+        Self { inner, peak }
+    }
+}
+
+impl MemoryPool for PeakMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        self.peak.update(self.inner.reserved());
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink)
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        self.inner.try_grow(reservation, additional)?;
+        self.peak.update(self.inner.reserved());
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner.reserved()
+    }
+}
+
+/// Peak memory in bytes reserved by the queries executed with `ctx`
+pub fn get_peak_memory(ctx: &SessionContext) -> usize {
+    ctx.copied_config()
+        .get_extension::<PeakMemory>()
+        .map(|peak| peak.get())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::execution::memory_pool::GreedyMemoryPool;
+
+    use super::*;
+
+    #[test]
+    fn test_peak_memory_pool() {
+        let peak = Arc::new(PeakMemory::default());
+        let pool: Arc<dyn MemoryPool> = Arc::new(PeakMemoryPool::new(
+            Arc::new(GreedyMemoryPool::new(1000)),
+            peak.clone(),
+        ));
+
+        let mut r1 = MemoryConsumer::new("r1").register(&pool);
+        let mut r2 = MemoryConsumer::new("r2").register(&pool);
+        r1.try_grow(300).unwrap();
+        r2.grow(200);
+        r1.shrink(250);
+        r2.try_grow(100).unwrap();
+        assert_eq!(pool.reserved(), 350);
+        assert_eq!(peak.get(), 500);
+
+        // a rejected reservation is not counted
+        assert!(r1.try_grow(800).is_err());
+        assert_eq!(peak.get(), 500);
+
+        drop(r1);
+        drop(r2);
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(peak.get(), 500);
+    }
+}
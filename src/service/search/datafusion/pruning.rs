@@ -0,0 +1,293 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Row group pruning of the parquet files scanned by a query, read from the
+//! metrics of the [`ParquetExec`]s once the plan was executed. Nodes send the
+//! pruning of their files to the node that queried them after the last batch.
+
+use config::{
+    meta::search::{ExecutionStats, FilePruning, PruningStats, RowGroupPruning},
+    metrics,
+    utils::parquet::parse_file_key_columns,
+    PARQUET_MAX_ROW_GROUP_SIZE,
+};
+use datafusion::{
+    common::{stats::Precision, DataFusionError},
+    datasource::{
+        listing::PartitionedFile,
+        physical_plan::{parquet::ParquetAccessPlan, ParquetExec},
+    },
+    physical_plan::{
+        metrics::MetricsSet, visit_execution_plan, ExecutionPlan, ExecutionPlanVisitor,
+    },
+};
+
+use super::distributed_plan::remote_scan::RemoteScanExec;
+
+/// Collects the row group pruning of the parquet files scanned by `plan`,
+/// returning the pruning of the files scanned by this node and the execution
+/// stats sent by the remote nodes it queried.
+pub fn collect_pruning_stats(plan: &dyn ExecutionPlan) -> (PruningStats, ExecutionStats) {
+    let mut visitor = PruningStatsVisitor::default();
+    let _ = visit_execution_plan(plan, &mut visitor);
+    (visitor.scanned, visitor.remote)
+}
+
+/// Exports the pruning of a query as metrics, labeled by stream type only to
+/// keep their cardinality bounded.
+pub fn report_pruning_metrics(org_id: &str, stats: &PruningStats) {
+    for (stream, pruning) in stats.streams.iter() {
+        let stream_type = stream.split('/').next().unwrap_or_default();
+        for (result, row_groups) in [
+            ("pruned_by_index", pruning.row_groups_pruned_by_index),
+            (
+                "pruned_by_statistics",
+                pruning.row_groups_pruned_by_statistics,
+            ),
+            (
+                "pruned_by_bloom_filter",
+                pruning.row_groups_pruned_by_bloom_filter,
+            ),
+            ("read", pruning.row_groups_read),
+        ] {
+            metrics::QUERY_PARQUET_ROW_GROUPS
+                .with_label_values(&[org_id, stream_type, result])
+                .inc_by(row_groups as u64);
+        }
+        for (result, bytes) in [
+            ("scanned", pruning.bytes_scanned),
+            ("skipped", pruning.bytes_skipped),
+        ] {
+            metrics::QUERY_PARQUET_BYTES
+                .with_label_values(&[org_id, stream_type, result])
+                .inc_by(bytes as u64);
+        }
+    }
+}
+
+#[derive(Default)]
+struct PruningStatsVisitor {
+    scanned: PruningStats,
+    remote: ExecutionStats,
+}
+
+impl ExecutionPlanVisitor for PruningStatsVisitor {
+    type Error = DataFusionError;
+
+    fn pre_visit(&mut self, plan: &dyn ExecutionPlan) -> Result<bool, Self::Error> {
+        if let Some(remote_scan_exec) = plan.as_any().downcast_ref::<RemoteScanExec>() {
+            self.remote.merge(&remote_scan_exec.execution_stats.lock());
+            // its input was executed by the remote nodes
+            return Ok(false);
+        }
+        if let Some(parquet_exec) = plan.as_any().downcast_ref::<ParquetExec>() {
+            let metrics = parquet_exec.metrics().unwrap_or_else(MetricsSet::new);
+            for file in parquet_exec.base_config().file_groups.iter().flatten() {
+                self.scanned.add_file(file_pruning(file, &metrics));
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn file_pruning(file: &PartitionedFile, metrics: &MetricsSet) -> FilePruning {
+    let path = file.object_meta.location.as_ref();
+    let mut opened = false;
+    let mut pruned_by_statistics = 0;
+    let mut matched_statistics = 0;
+    let mut pruned_by_bloom_filter = 0;
+    let mut bytes_scanned = 0;
+    for metric in metrics.iter() {
+        if !metric
+            .labels()
+            .iter()
+            .any(|label| label.name() == "filename" && label.value() == path)
+        {
+            continue;
+        }
+        opened = true;
+        let value = metric.value().as_usize();
+        match metric.value().name() {
+            "row_groups_pruned_statistics" => pruned_by_statistics += value,
+            "row_groups_matched_statistics" => matched_statistics += value,
+            "row_groups_pruned_bloom_filter" => pruned_by_bloom_filter += value,
+            "bytes_scanned" => bytes_scanned += value,
+            _ => {}
+        }
+    }
+
+    // the inverted index already skipped the row groups missing from the access plan, the
+    // statistics and bloom filters are only checked for the remaining ones
+    let (row_groups, pruned_by_index) = match file
+        .extensions
+        .as_ref()
+        .and_then(|ext| ext.downcast_ref::<ParquetAccessPlan>())
+    {
+        Some(access_plan) => (
+            access_plan.len(),
+            access_plan.len() - access_plan.row_group_indexes().len(),
+        ),
+        None => (estimate_row_groups(file), 0),
+    };
+    let row_groups_total =
+        row_groups.max(pruned_by_index + pruned_by_statistics + matched_statistics);
+    // files never opened, e.g. once the limit was reached, have no metrics
+    let row_groups_read = if opened {
+        row_groups_total
+            .saturating_sub(pruned_by_index + pruned_by_statistics + pruned_by_bloom_filter)
+    } else {
+        0
+    };
+
+    FilePruning {
+        file: file_key(path).to_string(),
+        stream: stream_key(path),
+        pruning: RowGroupPruning {
+            row_groups_total,
+            row_groups_pruned_by_index: pruned_by_index,
+            row_groups_pruned_by_statistics: pruned_by_statistics,
+            row_groups_pruned_by_bloom_filter: pruned_by_bloom_filter,
+            row_groups_read,
+            bytes_scanned,
+            bytes_skipped: file.object_meta.size.saturating_sub(bytes_scanned),
+        },
+    }
+}
+
+// files are written with row groups of `PARQUET_MAX_ROW_GROUP_SIZE` rows
+fn estimate_row_groups(file: &PartitionedFile) -> usize {
+    match file.statistics.as_ref().map(|stats| stats.num_rows) {
+        Some(Precision::Exact(num_rows)) => num_rows.div_ceil(PARQUET_MAX_ROW_GROUP_SIZE),
+        _ => 0,
+    }
+}
+
+// eg: {trace_id}/schema={schema_key}/$$/files/default/logs/olympics/2022/10/03/10/1.parquet
+fn file_key(path: &str) -> &str {
+    path.split_once("/$$/").map_or(path, |(_, key)| key)
+}
+
+// eg: logs/olympics
+fn stream_key(path: &str) -> String {
+    let key = file_key(path);
+    if !key.starts_with("files/") {
+        return "unknown".to_string();
+    }
+    // the stream key is {org_id}/{stream_type}/{stream_name}
+    match parse_file_key_columns(key) {
+        Ok((stream_key, ..)) => match stream_key.split_once('/') {
+            Some((_, stream)) => stream.to_string(),
+            None => stream_key,
+        },
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::{
+        physical_plan::collect,
+        prelude::{ParquetReadOptions, SessionContext},
+    };
+    use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+
+    use super::*;
+
+    // 3 row groups, with ts 1..=2, 3..=4, 5..=6 and name a..=c, d..=f, g..=i
+    async fn query_pruning(sql: &str) -> PruningStats {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.parquet");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("ts", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5, 6])),
+                Arc::new(StringArray::from(vec!["a", "c", "d", "f", "g", "i"])),
+            ],
+        )
+        .unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .set_bloom_filter_enabled(true)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(std::fs::File::create(&path).unwrap(), schema, Some(props))
+                .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let ctx = SessionContext::new();
+        ctx.register_parquet("t", path.to_str().unwrap(), ParquetReadOptions::default())
+            .await
+            .unwrap();
+        let plan = ctx
+            .sql(sql)
+            .await
+            .unwrap()
+            .create_physical_plan()
+            .await
+            .unwrap();
+        collect(plan.clone(), ctx.task_ctx()).await.unwrap();
+        let (scanned, remote) = collect_pruning_stats(plan.as_ref());
+        assert!(remote.pruning.is_empty());
+        assert_eq!(remote.peak_memory, 0);
+        scanned
+    }
+
+    #[tokio::test]
+    async fn test_pruning_by_statistics() {
+        let stats = query_pruning("SELECT * FROM t WHERE ts > 4").await;
+        let pruning = stats.streams["unknown"];
+        assert_eq!(pruning.row_groups_total, 3);
+        assert_eq!(pruning.row_groups_pruned_by_index, 0);
+        assert_eq!(pruning.row_groups_pruned_by_statistics, 2);
+        assert_eq!(pruning.row_groups_pruned_by_bloom_filter, 0);
+        assert_eq!(pruning.row_groups_read, 1);
+        assert!(pruning.bytes_scanned > 0);
+        assert_eq!(stats.top_files.len(), 1);
+        assert!(stats.top_files[0].file.ends_with("fixture.parquet"));
+    }
+
+    #[tokio::test]
+    async fn test_pruning_by_bloom_filter() {
+        // "b" is within the min/max of the first row group only, but not in its bloom filter
+        let stats = query_pruning("SELECT * FROM t WHERE name = 'b'").await;
+        let pruning = stats.streams["unknown"];
+        assert_eq!(pruning.row_groups_total, 3);
+        assert_eq!(pruning.row_groups_pruned_by_statistics, 2);
+        assert_eq!(pruning.row_groups_pruned_by_bloom_filter, 1);
+        assert_eq!(pruning.row_groups_read, 0);
+    }
+
+    #[test]
+    fn test_stream_key() {
+        assert_eq!(
+            stream_key("trace/schema=abc/$$/files/default/logs/olympics/2022/10/03/10/1.parquet"),
+            "logs/olympics"
+        );
+        assert_eq!(
+            file_key("trace/schema=abc/$$/files/default/logs/olympics/2022/10/03/10/1.parquet"),
+            "files/default/logs/olympics/2022/10/03/10/1.parquet"
+        );
+        assert_eq!(stream_key("tmp/fixture.parquet"), "unknown");
+    }
+}
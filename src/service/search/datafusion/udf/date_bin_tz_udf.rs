@@ -0,0 +1,482 @@
+// Copyright 2024 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{any::Any, str::FromStr, sync::Arc};
+
+use arrow::{
+    array::{timezone::Tz, AsArray, TimestampMicrosecondArray},
+    datatypes::{
+        DataType, DataType::Timestamp, IntervalMonthDayNano, TimeUnit::Microsecond,
+        TimestampMicrosecondType,
+    },
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use datafusion::{
+    common::{exec_err, Result},
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility},
+    scalar::ScalarValue,
+};
+use once_cell::sync::Lazy;
+
+/// The name of the date_bin_tz UDF given to DataFusion.
+pub const DATE_BIN_TZ_UDF_NAME: &str = "date_bin_tz";
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// The origin buckets are aligned to, the same one histogram() uses for
+/// date_bin(). It is a Monday, so weekly buckets start on Mondays.
+static ORIGIN: Lazy<NaiveDateTime> = Lazy::new(|| {
+    NaiveDate::from_ymd_opt(2001, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+});
+
+/// Implementation of date_bin_tz(interval, timestamp, timezone)
+///
+/// Like date_bin() but buckets are aligned to the wall clock of `timezone`:
+/// intervals of whole days start at local midnight, intervals of whole months
+/// start at local midnight of the first day of the month, shorter intervals
+/// are aligned using the UTC offset in effect at each timestamp. The result is
+/// the UTC start of the bucket, so buckets around DST transitions can be
+/// 23h/25h long but every timestamp falls into exactly one of them.
+pub(crate) static DATE_BIN_TZ_UDF: Lazy<ScalarUDF> =
+    Lazy::new(|| ScalarUDF::from(DateBinTzUdf::new()));
+
+#[derive(Debug, Clone)]
+struct DateBinTzUdf {
+    signature: Signature,
+}
+
+impl DateBinTzUdf {
+    fn new() -> Self {
+        Self {
+            signature: Signature::any(3, Volatility::Immutable),
+        }
+    }
+}
+
+impl ScalarUDFImpl for DateBinTzUdf {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        DATE_BIN_TZ_UDF_NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(Timestamp(Microsecond, None))
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let [interval, timestamps, timezone] = args else {
+            return exec_err!("date_bin_tz expects 3 arguments, got {}", args.len());
+        };
+        let ColumnarValue::Scalar(ScalarValue::IntervalMonthDayNano(Some(interval))) = interval
+        else {
+            return exec_err!("date_bin_tz expects a constant interval as first argument");
+        };
+        let ColumnarValue::Scalar(ScalarValue::Utf8(Some(timezone))) = timezone else {
+            return exec_err!("date_bin_tz expects a constant time zone as third argument");
+        };
+        let binner = LocalBinner::try_new(*interval, timezone)?;
+
+        match timestamps {
+            ColumnarValue::Scalar(ScalarValue::TimestampMicrosecond(v, _)) => {
+                Ok(ColumnarValue::Scalar(ScalarValue::TimestampMicrosecond(
+                    v.map(|v| binner.bin(v)),
+                    None,
+                )))
+            }
+            ColumnarValue::Array(array) => {
+                let Some(array) = array.as_primitive_opt::<TimestampMicrosecondType>() else {
+                    return exec_err!(
+                        "date_bin_tz expects microsecond timestamps, got {}",
+                        array.data_type()
+                    );
+                };
+                let result: TimestampMicrosecondArray = array.unary(|v| binner.bin(v));
+                Ok(ColumnarValue::Array(Arc::new(result)))
+            }
+            other => exec_err!(
+                "date_bin_tz expects microsecond timestamps, got {}",
+                other.data_type()
+            ),
+        }
+    }
+}
+
+/// Computes the start of the local time bucket a timestamp falls into.
+#[derive(Debug, Clone)]
+pub(crate) struct LocalBinner {
+    tz: Tz,
+    /// bucket width in microseconds, 0 for calendar month buckets
+    stride: i64,
+    /// bucket width in calendar months, 0 for fixed width buckets
+    months: i64,
+}
+
+impl LocalBinner {
+    pub(crate) fn try_new(interval: IntervalMonthDayNano, timezone: &str) -> Result<Self> {
+        let months = interval.months as i64;
+        let stride = interval.days as i64 * MICROS_PER_DAY + interval.nanoseconds / 1000;
+        if months != 0 && stride != 0 {
+            return Err(DataFusionError::Execution(
+                "histogram interval can't mix months with days or time".to_string(),
+            ));
+        }
+        if months < 0 || (months == 0 && stride <= 0) {
+            return Err(DataFusionError::Execution(format!(
+                "histogram interval must be positive, got {months} months {stride} microseconds"
+            )));
+        }
+        let tz = Tz::from_str(timezone).map_err(|e| {
+            DataFusionError::Execution(format!("invalid time zone {timezone}: {e}"))
+        })?;
+        Ok(Self { tz, stride, months })
+    }
+
+    /// Returns the UTC start, in microseconds, of the bucket `ts` falls into.
+    pub(crate) fn bin(&self, ts: i64) -> i64 {
+        let Some(utc) = DateTime::from_timestamp_micros(ts) else {
+            return ts;
+        };
+        let offset = self
+            .tz
+            .offset_from_utc_datetime(&utc.naive_utc())
+            .fix()
+            .local_minus_utc() as i64
+            * 1_000_000;
+        let local = utc.naive_utc() + Duration::microseconds(offset);
+
+        if self.months > 0 {
+            let since_origin =
+                (local.year() as i64 - ORIGIN.year() as i64) * 12 + local.month0() as i64;
+            let start = since_origin - since_origin.rem_euclid(self.months);
+            let year = ORIGIN.year() + start.div_euclid(12) as i32;
+            let month = start.rem_euclid(12) as u32 + 1;
+            let Some(midnight) =
+                NaiveDate::from_ymd_opt(year, month, 1).and_then(|date| date.and_hms_opt(0, 0, 0))
+            else {
+                return ts;
+            };
+            return self.resolve_local(midnight, offset);
+        }
+
+        let since_origin = (local - *ORIGIN).num_microseconds().unwrap_or_default();
+        let local_start = since_origin - since_origin.rem_euclid(self.stride);
+
+        if self.stride % MICROS_PER_DAY != 0 {
+            return ts - (since_origin - local_start);
+        }
+        // whole days start at local midnight, which is resolved in the zone
+        // so that it can have a different offset than `ts` (DST change)
+        self.resolve_local(*ORIGIN + Duration::microseconds(local_start), offset)
+    }
+
+    /// Returns the UTC start, in microseconds, of the bucket following the one
+    /// `ts` falls into.
+    pub(crate) fn next_bin(&self, ts: i64) -> i64 {
+        let start = self.bin(ts);
+        if self.months > 0 {
+            // a month is at most 31 days, so this lands in the next bucket
+            self.bin(start + self.months * 31 * MICROS_PER_DAY)
+        } else {
+            self.bin(start + self.stride)
+        }
+    }
+
+    /// Converts the local midnight a bucket starts at to UTC microseconds,
+    /// falling back to `offset` when midnight is skipped by a DST change.
+    fn resolve_local(&self, midnight: NaiveDateTime, offset: i64) -> i64 {
+        match self.tz.from_local_datetime(&midnight).earliest() {
+            Some(start) => start.timestamp_micros(),
+            None => midnight.and_utc().timestamp_micros() - offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use arrow::array::Int64Array;
+    use datafusion::{
+        arrow::{
+            datatypes::{Field, Schema},
+            record_batch::RecordBatch,
+        },
+        assert_batches_eq,
+        datasource::MemTable,
+        prelude::SessionContext,
+    };
+
+    use super::*;
+
+    const HOUR: i64 = 3_600_000_000;
+
+    fn micros(s: &str) -> i64 {
+        DateTime::parse_from_rfc3339(s).unwrap().timestamp_micros()
+    }
+
+    fn day() -> IntervalMonthDayNano {
+        IntervalMonthDayNano::new(0, 1, 0)
+    }
+
+    /// Buckets one record per `step` in `[start, end)` and returns the count
+    /// per bucket.
+    fn bucket_counts(binner: &LocalBinner, start: i64, end: i64, step: i64) -> BTreeMap<i64, i64> {
+        let mut counts = BTreeMap::new();
+        let mut ts = start;
+        while ts < end {
+            let bucket = binner.bin(ts);
+            assert!(bucket <= ts, "bucket {bucket} starts after {ts}");
+            *counts.entry(bucket).or_default() += 1;
+            ts += step;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_daily_buckets_start_at_local_midnight() {
+        let binner = LocalBinner::try_new(day(), "Asia/Kolkata").unwrap();
+        assert_eq!(
+            binner.bin(micros("2024-03-05T20:00:00Z")),
+            micros("2024-03-05T18:30:00Z")
+        );
+        assert_eq!(
+            binner.bin(micros("2024-03-05T17:00:00Z")),
+            micros("2024-03-04T18:30:00Z")
+        );
+
+        let binner = LocalBinner::try_new(day(), "UTC").unwrap();
+        assert_eq!(
+            binner.bin(micros("2024-03-05T17:00:00Z")),
+            micros("2024-03-05T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_weekly_buckets_start_on_local_monday() {
+        let binner =
+            LocalBinner::try_new(IntervalMonthDayNano::new(0, 7, 0), "America/Los_Angeles")
+                .unwrap();
+        // Thursday 2024-03-07 10:00 PST
+        assert_eq!(
+            binner.bin(micros("2024-03-07T18:00:00Z")),
+            micros("2024-03-04T08:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_dst_spring_forward_day() {
+        // 2024-03-10 is 23h long in Los Angeles
+        let binner = LocalBinner::try_new(day(), "America/Los_Angeles").unwrap();
+        let start = micros("2024-03-09T08:00:00Z");
+        let end = micros("2024-03-12T07:00:00Z");
+        let counts = bucket_counts(&binner, start, end, HOUR / 4);
+
+        assert_eq!(counts.values().sum::<i64>(), (end - start) / (HOUR / 4));
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (micros("2024-03-09T08:00:00Z"), 24 * 4),
+                (micros("2024-03-10T08:00:00Z"), 23 * 4),
+                (micros("2024-03-11T07:00:00Z"), 24 * 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dst_fall_back_day() {
+        // 2024-11-03 is 25h long in Los Angeles
+        let binner = LocalBinner::try_new(day(), "America/Los_Angeles").unwrap();
+        let start = micros("2024-11-02T07:00:00Z");
+        let end = micros("2024-11-05T08:00:00Z");
+        let counts = bucket_counts(&binner, start, end, HOUR / 4);
+
+        assert_eq!(counts.values().sum::<i64>(), (end - start) / (HOUR / 4));
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (micros("2024-11-02T07:00:00Z"), 24 * 4),
+                (micros("2024-11-03T07:00:00Z"), 25 * 4),
+                (micros("2024-11-04T08:00:00Z"), 24 * 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hourly_buckets_across_dst() {
+        let binner = LocalBinner::try_new(
+            IntervalMonthDayNano::new(0, 0, HOUR * 1000),
+            "Europe/Berlin",
+        )
+        .unwrap();
+        for (start, end) in [
+            ("2024-03-30T22:00:00Z", "2024-03-31T04:00:00Z"),
+            ("2024-10-26T22:00:00Z", "2024-10-27T04:00:00Z"),
+        ] {
+            let (start, end) = (micros(start), micros(end));
+            let counts = bucket_counts(&binner, start, end, 60_000_000);
+            assert_eq!(counts.values().sum::<i64>(), (end - start) / 60_000_000);
+            assert!(counts.values().all(|count| *count == 60));
+        }
+    }
+
+    #[test]
+    fn test_monthly_buckets_across_leap_february() {
+        let binner = LocalBinner::try_new(IntervalMonthDayNano::new(1, 0, 0), "UTC").unwrap();
+        let start = micros("2024-01-01T00:00:00Z");
+        let end = micros("2024-04-01T00:00:00Z");
+        let counts = bucket_counts(&binner, start, end, HOUR);
+
+        assert_eq!(counts.values().sum::<i64>(), (end - start) / HOUR);
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (micros("2024-01-01T00:00:00Z"), 31 * 24),
+                (micros("2024-02-01T00:00:00Z"), 29 * 24),
+                (micros("2024-03-01T00:00:00Z"), 31 * 24),
+            ]
+        );
+        assert_eq!(
+            binner.next_bin(micros("2024-02-29T23:59:59Z")),
+            micros("2024-03-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_quarterly_and_yearly_buckets() {
+        let binner = LocalBinner::try_new(IntervalMonthDayNano::new(3, 0, 0), "UTC").unwrap();
+        let start = micros("2024-02-01T00:00:00Z");
+        let end = micros("2024-05-01T00:00:00Z");
+        let counts = bucket_counts(&binner, start, end, HOUR);
+
+        assert_eq!(counts.values().sum::<i64>(), (end - start) / HOUR);
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (micros("2024-01-01T00:00:00Z"), (29 + 31) * 24),
+                (micros("2024-04-01T00:00:00Z"), 30 * 24),
+            ]
+        );
+        assert_eq!(
+            binner.next_bin(micros("2024-03-31T23:00:00Z")),
+            micros("2024-04-01T00:00:00Z")
+        );
+
+        let binner = LocalBinner::try_new(IntervalMonthDayNano::new(12, 0, 0), "UTC").unwrap();
+        assert_eq!(
+            binner.bin(micros("2000-06-15T12:00:00Z")),
+            micros("2000-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            binner.bin(micros("2024-12-31T23:59:59Z")),
+            micros("2024-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_monthly_buckets_start_at_local_midnight() {
+        let binner =
+            LocalBinner::try_new(IntervalMonthDayNano::new(1, 0, 0), "America/Los_Angeles")
+                .unwrap();
+        // still February in Los Angeles
+        assert_eq!(
+            binner.bin(micros("2024-03-01T05:00:00Z")),
+            micros("2024-02-01T08:00:00Z")
+        );
+        // March starts in PST, April in PDT
+        let start = micros("2024-03-01T08:00:00Z");
+        let end = micros("2024-05-01T07:00:00Z");
+        let counts = bucket_counts(&binner, start, end, HOUR);
+        assert_eq!(counts.values().sum::<i64>(), (end - start) / HOUR);
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            vec![
+                (micros("2024-03-01T08:00:00Z"), 31 * 24 - 1),
+                (micros("2024-04-01T07:00:00Z"), 30 * 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(LocalBinner::try_new(day(), "Mars/Olympus_Mons").is_err());
+        assert!(LocalBinner::try_new(IntervalMonthDayNano::new(1, 1, 0), "UTC").is_err());
+        assert!(LocalBinner::try_new(IntervalMonthDayNano::new(-1, 0, 0), "UTC").is_err());
+        assert!(LocalBinner::try_new(IntervalMonthDayNano::new(0, 0, 0), "UTC").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_date_bin_tz_udf() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "_timestamp",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![
+                micros("2024-11-03T06:30:00Z"),
+                micros("2024-11-03T08:30:00Z"),
+                micros("2024-11-03T09:30:00Z"),
+                micros("2024-11-04T07:30:00Z"),
+                micros("2024-11-04T08:30:00Z"),
+            ]))],
+        )
+        .unwrap();
+
+        let ctx = SessionContext::new();
+        let provider = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+        ctx.register_table("t", Arc::new(provider)).unwrap();
+        ctx.register_udf(DATE_BIN_TZ_UDF.clone());
+
+        let sql = "select date_bin_tz(interval '1 day', to_timestamp_micros(_timestamp), 'America/Los_Angeles') as day, count(*) as cnt from t group by day order by day";
+        let data = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        assert_batches_eq!(
+            vec![
+                "+---------------------+-----+",
+                "| day                 | cnt |",
+                "+---------------------+-----+",
+                "| 2024-11-02T07:00:00 | 1   |",
+                "| 2024-11-03T07:00:00 | 3   |",
+                "| 2024-11-04T08:00:00 | 1   |",
+                "+---------------------+-----+",
+            ],
+            &data
+        );
+
+        let sql = "select date_bin_tz(interval '1 month', to_timestamp_micros(_timestamp), 'UTC') as month, count(*) as cnt from t group by month order by month";
+        let data = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        assert_batches_eq!(
+            vec![
+                "+---------------------+-----+",
+                "| month               | cnt |",
+                "+---------------------+-----+",
+                "| 2024-11-01T00:00:00 | 5   |",
+                "+---------------------+-----+",
+            ],
+            &data
+        );
+    }
+}
@@ -25,6 +25,7 @@ pub(crate) mod cast_to_arr_udf;
 pub(crate) mod cast_to_timestamp_udf;
 #[cfg(feature = "enterprise")]
 pub(crate) mod cipher_udf;
+pub(crate) mod date_bin_tz_udf;
 pub(crate) mod date_format_udf;
 pub(crate) mod fuzzy_match_udf;
 pub(crate) mod histogram_udf;
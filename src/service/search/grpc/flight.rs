@@ -279,6 +279,7 @@ pub async fn search(
             file_stats_cache.clone(),
             index_condition.clone(),
             fst_fields.clone(),
+            &req.search_info.seam_files,
         )
         .await
         {
@@ -33,7 +33,7 @@ use datafusion::{
     execution::cache::cache_manager::FileStatisticsCache,
 };
 use futures::StreamExt;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use infra::errors::{Error, ErrorCodes};
 use ingester::WAL_PARQUET_METADATA;
 
@@ -60,6 +60,7 @@ pub async fn search_parquet(
     file_stat_cache: Option<FileStatisticsCache>,
     index_condition: Option<IndexCondition>,
     fst_fields: Vec<String>,
+    seam_files: &[String],
 ) -> super::SearchTable {
     // get file list
     let stream_settings =
@@ -77,6 +78,20 @@ pub async fn search_parquet(
         return Ok((vec![], ScanStats::new()));
     }
 
+    // skip the files already uploaded, the querier scans them from storage
+    let (files, skipped_files) = filter_seam_files(files, seam_files);
+    if !skipped_files.is_empty() {
+        log::debug!(
+            "[trace_id {}] skip wal parquet files already searched in storage: {:?}",
+            query.trace_id,
+            skipped_files
+        );
+        wal::release_files(&skipped_files);
+    }
+    if files.is_empty() {
+        return Ok((vec![], ScanStats::new()));
+    }
+
     let mut scan_stats = ScanStats::new();
     let mut lock_files = files.iter().map(|f| f.key.clone()).collect::<Vec<_>>();
     let cfg = get_config();
@@ -460,6 +475,27 @@ pub async fn search_memtable(
     Ok((tables, scan_stats))
 }
 
+/// split out the wal files whose merged storage file is in `seam_files`, the storage
+/// search already reads those records
+fn filter_seam_files(files: Vec<FileKey>, seam_files: &[String]) -> (Vec<FileKey>, Vec<String>) {
+    if seam_files.is_empty() {
+        return (files, vec![]);
+    }
+    let seam_files = seam_files.iter().collect::<HashSet<_>>();
+    let mut skipped = Vec::new();
+    let files = files
+        .into_iter()
+        .filter(|file| match wal::get_uploaded_file(&file.key) {
+            Some(storage_file) if seam_files.contains(&storage_file) => {
+                skipped.push(file.key.clone());
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    (files, skipped)
+}
+
 #[tracing::instrument(name = "service:search:grpc:wal:get_file_list_inner", skip_all, fields(org_id = query.org_id, stream_name = query.stream_name))]
 async fn get_file_list_inner(
     query: Arc<super::QueryParams>,
@@ -591,3 +627,65 @@ pub fn adapt_batch(latest_schema: Arc<Schema>, batch: &RecordBatch) -> RecordBat
     let schema = Arc::new(Schema::new(fields));
     RecordBatch::try_new(schema, cols).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use config::meta::stream::FileMeta;
+
+    use super::*;
+
+    #[test]
+    fn test_filter_seam_files_reads_records_once() {
+        // wal files with their records, two distinct records share a timestamp
+        let wal_records = [
+            (
+                "files/seam_org/logs/seam/0/w1.parquet",
+                vec![(1, "a"), (2, "b")],
+            ),
+            ("files/seam_org/logs/seam/0/w2.parquet", vec![(2, "c")]),
+            ("files/seam_org/logs/seam/0/w3.parquet", vec![(3, "d")]),
+            ("files/seam_org/logs/seam/0/w4.parquet", vec![(3, "e")]),
+        ];
+        // w1 and w2 merged into s1, which is in the querier snapshot,
+        // w3 merged into s2 after the snapshot, w4 not uploaded yet
+        let s1 = "files/seam_org/logs/seam/2025/01/01/00/s1.parquet";
+        let s2 = "files/seam_org/logs/seam/2025/01/01/00/s2.parquet";
+        wal::set_uploaded_files(
+            &[wal_records[0].0.to_string(), wal_records[1].0.to_string()],
+            s1,
+        );
+        wal::set_uploaded_files(&[wal_records[2].0.to_string()], s2);
+        let storage_records = [(1, "a"), (2, "b"), (2, "c")];
+
+        let files = wal_records
+            .iter()
+            .map(|(key, _)| FileKey::new(key.to_string(), FileMeta::default(), false))
+            .collect::<Vec<_>>();
+        let (files, skipped) = filter_seam_files(files, &[s1.to_string()]);
+        assert_eq!(skipped, vec![wal_records[0].0, wal_records[1].0]);
+
+        let mut records = storage_records.to_vec();
+        for file in files.iter() {
+            let (_, file_records) = wal_records.iter().find(|(k, _)| *k == file.key).unwrap();
+            records.extend(file_records.iter().cloned());
+        }
+        records.sort();
+        assert_eq!(
+            records,
+            vec![(1, "a"), (2, "b"), (2, "c"), (3, "d"), (3, "e")]
+        );
+
+        // without seam files every wal file is searched
+        let files = wal_records
+            .iter()
+            .map(|(key, _)| FileKey::new(key.to_string(), FileMeta::default(), false))
+            .collect::<Vec<_>>();
+        let (files, skipped) = filter_seam_files(files, &[]);
+        assert_eq!(files.len(), wal_records.len());
+        assert!(skipped.is_empty());
+
+        for (key, _) in wal_records.iter() {
+            wal::remove_uploaded_file(key);
+        }
+    }
+}
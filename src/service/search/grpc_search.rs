@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use config::{
     meta::{search, stream::StreamType},
@@ -25,7 +25,10 @@ use tracing::{info_span, Instrument};
 
 use crate::{
     common::infra::cluster as infra_cluster,
-    service::{grpc::make_grpc_search_client, search::server_internal_error},
+    service::{
+        grpc::make_grpc_search_client,
+        search::{get_query_timeout, search_timeout_error, server_internal_error},
+    },
 };
 
 #[tracing::instrument(name = "service:search:grpc_search", skip_all)]
@@ -59,7 +62,9 @@ pub async fn grpc_search(
     let trace_id = trace_id.to_string();
     let org_id = org_id.to_string();
     let stream_type = stream_type.as_str();
-    let in_req = in_req.clone();
+    let mut in_req = in_req.clone();
+    let timeout = get_query_timeout(in_req.timeout);
+    in_req.timeout = timeout as i64;
     let task = tokio::task::spawn(
         async move {
             let req = bytes::Bytes::from(json::to_string(&in_req)?).to_vec();
@@ -72,9 +77,23 @@ pub async fn grpc_search(
             });
             let node = Arc::new(node) as _;
             let mut client = make_grpc_search_client(&mut request, &node).await?;
-            let response = match client.search(request).await {
-                Ok(res) => res.into_inner(),
-                Err(err) => {
+            // the client uses ZO_QUERY_TIMEOUT, the request can ask for more or less
+            let timeout = Duration::from_secs(timeout);
+            request.set_timeout(timeout);
+            let response = match tokio::time::timeout(timeout, client.search(request)).await {
+                Err(_) => {
+                    log::error!(
+                        "search->grpc: node: {}, search timed out after {}s",
+                        &node.get_grpc_addr(),
+                        timeout.as_secs()
+                    );
+                    return Err(search_timeout_error(timeout.as_secs()));
+                }
+                Ok(Ok(res)) => res.into_inner(),
+                Ok(Err(err)) if err.code() == tonic::Code::DeadlineExceeded => {
+                    return Err(search_timeout_error(timeout.as_secs()));
+                }
+                Ok(Err(err)) => {
                     log::error!(
                         "search->grpc: node: {}, search err: {err:?}",
                         &node.get_grpc_addr(),
@@ -31,7 +31,7 @@ use datafusion::{
 };
 use hashbrown::HashSet;
 use serde::{Deserialize, Serialize};
-use sqlparser::ast::{BinaryOperator, Expr, FunctionArguments};
+use sqlparser::ast::{BinaryOperator, Expr, FunctionArguments, Value};
 use tantivy::{
     query::{BooleanQuery, FuzzyTermQuery, Occur, PhrasePrefixQuery, Query, RegexQuery, TermQuery},
     schema::{Field, IndexRecordOption, Schema},
@@ -99,6 +99,8 @@ pub enum Condition {
     Equal(String, String),
     Regex(String, String),
     In(String, Vec<String>),
+    // field, pattern of a `LIKE` with a leading and/or trailing `%`
+    Like(String, String),
     MatchAll(String),
     FuzzyMatchAll(String, u8),
     Or(Box<Condition>, Box<Condition>),
@@ -182,6 +184,7 @@ impl Condition {
             Condition::Equal(field, value) => format!("{}={}", field, value),
             Condition::Regex(field, value) => format!("{}=~{}", field, value),
             Condition::In(field, values) => format!("{} IN ({})", field, values.join(",")),
+            Condition::Like(field, pattern) => format!("{} LIKE '{}'", field, pattern),
             Condition::MatchAll(value) => format!("{}:{}", INDEX_FIELD_NAME_FOR_ALL, value),
             Condition::FuzzyMatchAll(value, distance) => format!(
                 "{}:fuzzy({}, {})",
@@ -219,6 +222,9 @@ impl Condition {
                 let values = list.iter().map(get_value).collect();
                 Condition::In(field, values)
             }
+            Expr::Like { expr, pattern, .. } => {
+                Condition::Like(get_field_name(expr), get_like_pattern(pattern).unwrap())
+            }
             Expr::Function(func) => {
                 let fn_name = func.name.to_string().to_lowercase();
                 if fn_name == "match_all" {
@@ -294,6 +300,25 @@ impl Condition {
                     .collect();
                 Box::new(BooleanQuery::union(terms))
             }
+            Condition::Like(field, pattern) => {
+                let field = schema.get_field(field)?;
+                let Some((leading, value, trailing)) = parse_like_pattern(pattern) else {
+                    return Err(anyhow::anyhow!("Unsupported LIKE pattern: {pattern}"));
+                };
+                if !leading && !trailing {
+                    let term = Term::from_field_text(field, &value);
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+                } else {
+                    // index fields use the raw tokenizer, the regex matches the whole value
+                    let value = format!(
+                        "{}{}{}",
+                        if leading { ".*" } else { "" },
+                        regex::escape(&value),
+                        if trailing { ".*" } else { "" }
+                    );
+                    Box::new(RegexQuery::from_pattern(&value, field)?)
+                }
+            }
             Condition::MatchAll(value) => {
                 let default_field = default_field.ok_or_else(|| {
                     anyhow::anyhow!("There's no FullTextSearch field for match_all() function")
@@ -366,7 +391,7 @@ impl Condition {
             Condition::Regex(field, _) => {
                 fields.insert(field.clone());
             }
-            Condition::In(field, _) => {
+            Condition::In(field, _) | Condition::Like(field, _) => {
                 fields.insert(field.clone());
             }
             Condition::MatchAll(_) => {
@@ -392,7 +417,7 @@ impl Condition {
             Condition::Regex(field, _) => {
                 fields.insert(field.clone());
             }
-            Condition::In(field, _) => {
+            Condition::In(field, _) | Condition::Like(field, _) => {
                 fields.insert(field.clone());
             }
             Condition::MatchAll(_) => {
@@ -435,6 +460,12 @@ impl Condition {
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(Arc::new(InListExpr::new(left, values, false, None)))
             }
+            Condition::Like(name, pattern) => {
+                let index = schema.index_of(name).unwrap();
+                let left = Arc::new(Column::new(name, index));
+                let right = Arc::new(Literal::new(ScalarValue::Utf8(Some(pattern.clone()))));
+                Ok(Arc::new(LikeExpr::new(false, false, left, right)))
+            }
             Condition::MatchAll(value) => {
                 let value = value
                     .trim_start_matches("re:") // regex
@@ -539,6 +570,23 @@ fn is_expr_valid_for_index(expr: &Expr, index_fields: &HashSet<String>) -> bool
                 }
             }
         }
+        Expr::Like {
+            negated,
+            expr,
+            pattern,
+            escape_char,
+        } => {
+            // only the default `\` escape is understood by the LIKE expression
+            if *negated || escape_char.as_ref().is_some_and(|c| c.to_string() != "\\") {
+                return false;
+            }
+            if !is_field(expr) || !index_fields.contains(&get_field_name(expr)) {
+                return false;
+            }
+            if get_like_pattern(pattern).is_none_or(|v| parse_like_pattern(&v).is_none()) {
+                return false;
+            }
+        }
         Expr::BinaryOp {
             left,
             op: BinaryOperator::And | BinaryOperator::Or,
@@ -591,6 +639,41 @@ fn get_value(expr: &Expr) -> String {
     }
 }
 
+fn get_like_pattern(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Value(Value::SingleQuotedString(pattern)) => Some(pattern.clone()),
+        _ => None,
+    }
+}
+
+/// Splits a LIKE pattern into the leading `%`, the literal value and the
+/// trailing `%`. Returns `None` for the patterns the index can't answer, a `%`
+/// in the middle or a `_`, escaped wildcards are part of the value.
+fn parse_like_pattern(pattern: &str) -> Option<(bool, String, bool)> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut value = String::with_capacity(pattern.len());
+    let (mut leading, mut trailing) = (false, false);
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                i += 1;
+                value.push(*chars.get(i)?);
+            }
+            '%' if i == 0 => leading = true,
+            '%' if i == chars.len() - 1 => trailing = true,
+            '%' | '_' => return None,
+            c => value.push(c),
+        }
+        i += 1;
+    }
+    if value.is_empty() {
+        None
+    } else {
+        Some((leading, value, trailing))
+    }
+}
+
 fn disjunction(exprs: Vec<Arc<dyn PhysicalExpr>>) -> Arc<dyn PhysicalExpr> {
     if exprs.len() == 1 {
         exprs[0].clone()
@@ -630,3 +713,75 @@ fn get_scalar_value(value: &str, data_type: &DataType) -> Result<Arc<Literal>, a
         _ => unimplemented!(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{
+        collector::Count,
+        doc,
+        schema::{TextFieldIndexing, TextOptions},
+        Index, IndexWriter,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_parse_like_pattern() {
+        assert_eq!(
+            parse_like_pattern("err%"),
+            Some((false, "err".to_string(), true))
+        );
+        assert_eq!(
+            parse_like_pattern("%err"),
+            Some((true, "err".to_string(), false))
+        );
+        assert_eq!(
+            parse_like_pattern("%err%"),
+            Some((true, "err".to_string(), true))
+        );
+        assert_eq!(
+            parse_like_pattern(r"%50\%"),
+            Some((true, "50%".to_string(), false))
+        );
+        assert_eq!(
+            parse_like_pattern(r"a\_b%"),
+            Some((false, "a_b".to_string(), true))
+        );
+        assert_eq!(parse_like_pattern("e%r"), None);
+        assert_eq!(parse_like_pattern("e_r"), None);
+        assert_eq!(parse_like_pattern("%"), None);
+        assert_eq!(parse_like_pattern(r"err\"), None);
+    }
+
+    #[test]
+    fn test_like_condition_tantivy_query() {
+        let mut builder = Schema::builder();
+        let opts = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::Basic)
+                .set_tokenizer("raw"),
+        );
+        let name = builder.add_text_field("name", opts);
+        let schema = builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for value in ["error", "server error", "error.log", "warn", "100%"] {
+            writer.add_document(doc!(name => value)).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let count = |pattern: &str| {
+            let condition = Condition::Like("name".to_string(), pattern.to_string());
+            let query = condition.to_tantivy_query(&schema, None).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("error%"), 2);
+        assert_eq!(count("%error"), 2);
+        assert_eq!(count("%error%"), 3);
+        // regex meta characters of the value are matched literally
+        assert_eq!(count("%.log"), 1);
+        assert_eq!(count(r"%0\%"), 1);
+        assert_eq!(count("warn"), 1);
+    }
+}
@@ -66,11 +66,16 @@ use crate::{
 
 pub(crate) mod cache;
 pub(crate) mod cluster;
+pub(crate) mod concurrency;
+pub(crate) mod data_range;
 pub(crate) mod datafusion;
 pub(crate) mod grpc;
 pub(crate) mod grpc_search;
 pub(crate) mod index;
+#[cfg(not(feature = "enterprise"))]
+pub(crate) mod queue;
 pub(crate) mod request;
+pub(crate) mod routing;
 pub(crate) mod sql;
 #[cfg(feature = "enterprise")]
 pub(crate) mod super_cluster;
@@ -209,6 +214,9 @@ pub async fn search(
     match res {
         Ok(mut res) => {
             res.set_work_group(_work_group.clone());
+            if !in_req.query.debug_pruning {
+                res.pruning_stats = None;
+            }
             let time = start.elapsed().as_secs_f64();
             let (report_usage, search_type, search_event_context) = match in_req.search_type {
                 Some(search_type) => {
@@ -239,7 +247,7 @@ pub async fn search(
                         "".to_string()
                     }
                 };
-                let req_stats = RequestStats {
+                let mut req_stats = RequestStats {
                     records: res.hits.len() as i64,
                     response_time: time,
                     size: res.scan_size as f64,
@@ -267,6 +275,7 @@ pub async fn search(
                     result_cache_ratio: Some(res.result_cache_ratio),
                     ..Default::default()
                 };
+                req_stats.set_search_resources(&res);
                 let num_fn = if req_query.query_fn.is_empty() { 0 } else { 1 };
                 report_request_usage_stats(
                     req_stats,
@@ -285,6 +294,17 @@ pub async fn search(
     }
 }
 
+/// Plans the query of the request the same way [`search`] does, without
+/// running it.
+pub async fn explain(
+    org_id: &str,
+    stream_type: StreamType,
+    req: &search::Request,
+) -> Result<search::ExplainResponse, Error> {
+    let sql = Sql::new(&req.query, org_id, stream_type).await?;
+    Ok(Arc::new(sql).explain())
+}
+
 /// Returns Error if the first query is failed, otherwise returns the partial results.
 /// In case one query fails, the remaining queries are not executed.
 #[tracing::instrument(name = "service:search_multi:enter", skip(multi_req))]
@@ -559,6 +579,7 @@ pub async fn search_partition(
         start_time: req.start_time,
         end_time: req.end_time,
         sql: req.sql.to_string(),
+        timezone: req.timezone.clone().unwrap_or_default(),
         ..Default::default()
     };
     let sql = Sql::new(&query, org_id, stream_type).await?;
@@ -583,26 +604,66 @@ pub async fn search_partition(
     let is_streaming_aggregate = ts_column.is_none()
         && is_simple_aggregate_query(&req.sql).unwrap_or(false)
         && cfg.common.feature_query_streaming_aggs;
-    let mut skip_get_file_list = ts_column.is_none() || apply_over_hits;
+    // local day and calendar buckets don't line up with the UTC aligned partitions
+    let mut skip_get_file_list = ts_column.is_none()
+        || apply_over_hits
+        || sql.has_local_day_histogram()
+        || sql.has_calendar_histogram;
 
-    // if need streaming output and is simple query, we shouldn't skip file list
-    if skip_get_file_list && req.streaming_output && is_streaming_aggregate {
-        skip_get_file_list = false;
-    }
-
-    // check if we need to use streaming_output
+    // check if we need to use streaming_output, the cache refuses recent time ranges
     let streaming_id = if req.streaming_output && is_streaming_aggregate {
-        Some(ider::uuid())
+        let id = ider::uuid();
+        let scope = streaming_aggs_exec::StreamingAggsScope {
+            org_id: org_id.to_string(),
+            stream_type,
+            streams: sql.stream_names.iter().map(|s| s.stream_name()).collect(),
+            start_time: query.start_time,
+            end_time: query.end_time,
+        };
+        streaming_aggs_exec::init_cache(&id, scope).then_some(id)
     } else {
         None
     };
-    if let Some(id) = &streaming_id {
+    if streaming_id.is_some() {
         log::info!(
             "[trace_id {trace_id}] search_partition: using streaming_output with streaming_aggregate"
         );
-        streaming_aggs_exec::init_cache(id, query.start_time, query.end_time);
+        // if need streaming output and is simple query, we shouldn't skip file list
+        skip_get_file_list = false;
+    }
+
+    let mut min_step = Duration::try_seconds(1)
+        .unwrap()
+        .num_microseconds()
+        .unwrap();
+    if is_aggregate && ts_column.is_some() {
+        let hist_int = sql.histogram_partition_interval().unwrap_or(1);
+        // add a check if histogram interval is greater than 0 to avoid panic with min_step being 0
+        if hist_int > 0 {
+            min_step *= hist_int;
+        }
     }
 
+    // skip the trailing range without data, the histogram interval is still the
+    // one of the requested range
+    let streams = sql
+        .stream_names
+        .iter()
+        .map(|s| (s.stream_name(), s.get_stream_type(stream_type)))
+        .collect::<Vec<_>>();
+    let range_clamped_to_data =
+        data_range::clamp_to_streams(org_id, &streams, (req.start_time, req.end_time), min_step);
+    let (start_time, end_time) = match range_clamped_to_data {
+        Some(range) => {
+            log::info!(
+                "[trace_id {trace_id}] search_partition: time range clamped to data: {:?}",
+                (range.start, range.end)
+            );
+            (range.start, range.end)
+        }
+        None => (req.start_time, req.end_time),
+    };
+
     let mut files = Vec::new();
 
     let mut max_query_range = 0;
@@ -618,7 +679,9 @@ pub async fn search_partition(
                 &sql.org_id,
                 stream_type,
                 &stream_name,
-                sql.time_range,
+                range_clamped_to_data
+                    .map(|range| (range.start, range.end))
+                    .or(sql.time_range),
             )
             .await?;
             max_query_range = max(
@@ -638,7 +701,7 @@ pub async fn search_partition(
             // data retention in seconds
             let mut data_retention = stream_settings.data_retention * 24 * 60 * 60;
             // data duration in seconds
-            let query_duration = (req.end_time - req.start_time) / 1000 / 1000;
+            let query_duration = (end_time - start_time) / 1000 / 1000;
             let stats = stats::get_stream_stats(org_id, &stream_name, stream_type);
             let data_end_time = std::cmp::min(Utc::now().timestamp_micros(), stats.doc_time_max);
             let data_retention_based_on_stats = (data_end_time - stats.doc_time_min) / 1000 / 1000;
@@ -669,16 +732,19 @@ pub async fn search_partition(
     let file_list_took = start.elapsed().as_millis() as usize;
     log::info!(
         "[trace_id {trace_id}] search_partition: get file_list time_range: {:?}, files: {}, took: {} ms",
-        (req.start_time, req.end_time),
+        (start_time, end_time),
         files.len(),
         file_list_took,
     );
 
     if skip_get_file_list {
         let mut response = search::SearchPartitionResponse::default();
-        response.partitions.push([req.start_time, req.end_time]);
+        response.partitions.push([start_time, end_time]);
         response.max_query_range = max_query_range_in_hour;
+        response.range_clamped_to_data = range_clamped_to_data;
         response.histogram_interval = sql.histogram_interval;
+        response.histogram_timezone = sql.histogram_timezone.clone();
+        response.histogram_calendar_interval = sql.histogram_calendar_interval.clone();
         return Ok(response);
     };
 
@@ -702,25 +768,16 @@ pub async fn search_partition(
         compressed_size: 0, // there is no compressed size in file list
         max_query_range: max_query_range_in_hour,
         histogram_interval: sql.histogram_interval,
+        histogram_timezone: sql.histogram_timezone.clone(),
+        histogram_calendar_interval: sql.histogram_calendar_interval.clone(),
         partitions: vec![],
         order_by: OrderBy::Desc,
         streaming_output: req.streaming_output,
-        streaming_aggs: req.streaming_output && is_streaming_aggregate,
+        streaming_aggs: streaming_id.is_some(),
         streaming_id: streaming_id.clone(),
+        range_clamped_to_data,
     };
 
-    let mut min_step = Duration::try_seconds(1)
-        .unwrap()
-        .num_microseconds()
-        .unwrap();
-    if is_aggregate && ts_column.is_some() {
-        let hist_int = sql.histogram_interval.unwrap_or(1);
-        // add a check if histogram interval is greater than 0 to avoid panic with min_step being 0
-        if hist_int > 0 {
-            min_step *= hist_int;
-        }
-    }
-
     let mut total_secs = resp.original_size / cfg.limit.query_group_base_speed / cpu_cores;
     if total_secs * cfg.limit.query_group_base_speed * cpu_cores < resp.original_size {
         total_secs += 1;
@@ -733,7 +790,7 @@ pub async fn search_partition(
     if part_num > 1000 {
         part_num = 1000;
     }
-    let mut step = (req.end_time - req.start_time) / part_num as i64;
+    let mut step = (end_time - start_time) / part_num as i64;
     // step must be times of min_step
     if step < min_step {
         step = min_step;
@@ -752,24 +809,24 @@ pub async fn search_partition(
 
     // Generate partitions by DESC order
     let mut partitions = Vec::with_capacity(part_num);
-    let mut end = req.end_time;
+    let mut end = end_time;
     let mut last_partition_step = end % min_step;
-    let duration = req.end_time - req.start_time;
-    while end > req.start_time {
-        let mut start = max(end - step, req.start_time);
+    let duration = end_time - start_time;
+    while end > start_time {
+        let mut start = max(end - step, start_time);
         if last_partition_step > 0 && duration > min_step && part_num > 1 {
             partitions.push([end - last_partition_step, end]);
             start -= last_partition_step;
             end -= last_partition_step;
         } else {
-            start = max(start - last_partition_step, req.start_time);
+            start = max(start - last_partition_step, start_time);
         }
         partitions.push([start, end]);
         end = start;
         last_partition_step = 0;
     }
     if partitions.is_empty() {
-        partitions.push([req.start_time, req.end_time]);
+        partitions.push([start_time, end_time]);
     }
 
     // We need to reverse partitions if query is ASC order
@@ -1098,6 +1155,24 @@ pub fn server_internal_error(error: impl ToString) -> Error {
     Error::ErrorCode(ErrorCodes::ServerInternalError(error.to_string()))
 }
 
+pub fn search_timeout_error(timeout: u64) -> Error {
+    Error::ErrorCode(ErrorCodes::SearchTimeout(format!(
+        "Search query timed out after {timeout}s"
+    )))
+}
+
+/// Returns the timeout of a search in seconds, the one the request asks for or
+/// `ZO_QUERY_TIMEOUT` if it doesn't, capped by `ZO_QUERY_MAX_TIMEOUT`.
+pub fn get_query_timeout(req_timeout: i64) -> u64 {
+    let cfg = get_config();
+    let timeout = if req_timeout > 0 {
+        req_timeout as u64
+    } else {
+        cfg.limit.query_timeout
+    };
+    timeout.min(cfg.limit.query_max_timeout)
+}
+
 #[tracing::instrument(name = "service:search_partition_multi", skip(req))]
 pub async fn search_partition_multi(
     trace_id: &str,
@@ -1123,6 +1198,7 @@ pub async fn search_partition_multi(
                 clusters: req.clusters.clone(),
                 query_fn: req.query_fn.clone(),
                 streaming_output: req.streaming_output,
+                timezone: None,
             },
             false,
         )
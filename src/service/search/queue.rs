@@ -0,0 +1,203 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keeps track of the searches waiting in or running after the local search
+//! queue, see [`super::QUEUE_LOCKER`].
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use chrono::Utc;
+use config::{
+    meta::search::{QueueStatusResponse, QueuedSearch},
+    metrics, RwHashMap,
+};
+use once_cell::sync::Lazy;
+
+/// Searches tracked at most, searches enqueued beyond it are not listed.
+const MAX_QUEUED_SEARCHES: usize = 10_000;
+
+static SEARCH_QUEUE: Lazy<SearchQueue> = Lazy::new(|| SearchQueue::new(MAX_QUEUED_SEARCHES));
+
+/// Tracks a search entering the queue, until the returned entry is dropped.
+pub(crate) fn enqueue(
+    trace_id: &str,
+    org_id: &str,
+    user_id: Option<&str>,
+    sql_hash: &str,
+) -> QueueEntry<'static> {
+    SEARCH_QUEUE.enqueue(trace_id, org_id, user_id, sql_hash)
+}
+
+/// Lists the searches of an org waiting in or running after the queue.
+pub(crate) fn status(org_id: &str) -> QueueStatusResponse {
+    SEARCH_QUEUE.status(org_id)
+}
+
+struct SearchQueue {
+    searches: RwHashMap<u64, QueuedSearch>,
+    next_id: AtomicU64,
+    max_searches: usize,
+}
+
+impl SearchQueue {
+    fn new(max_searches: usize) -> Self {
+        Self {
+            searches: Default::default(),
+            next_id: AtomicU64::new(0),
+            max_searches,
+        }
+    }
+
+    fn enqueue(
+        &self,
+        trace_id: &str,
+        org_id: &str,
+        user_id: Option<&str>,
+        sql_hash: &str,
+    ) -> QueueEntry<'_> {
+        let id = if self.searches.len() < self.max_searches {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            self.searches.insert(
+                id,
+                QueuedSearch {
+                    trace_id: trace_id.to_string(),
+                    org_id: org_id.to_string(),
+                    user_id: user_id.map(|v| v.to_string()),
+                    sql_hash: sql_hash.to_string(),
+                    enqueued_at: Utc::now().timestamp_micros(),
+                    started_at: None,
+                },
+            );
+            Some(id)
+        } else {
+            None
+        };
+        QueueEntry {
+            queue: self,
+            id,
+            org_id: org_id.to_string(),
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    fn status(&self, org_id: &str) -> QueueStatusResponse {
+        let mut searches = self
+            .searches
+            .iter()
+            .filter(|v| v.org_id == org_id)
+            .map(|v| (*v.key(), v.value().clone()))
+            .collect::<Vec<_>>();
+        // ids are handed out in the order searches are enqueued
+        searches.sort_by_key(|(id, _)| *id);
+        let status = searches.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+        let running = status.iter().filter(|v| v.started_at.is_some()).count();
+        QueueStatusResponse {
+            waiting: status.len() - running,
+            running,
+            status,
+        }
+    }
+}
+
+/// A search in the queue, removed from it when dropped, whether the search
+/// completed, failed or was cancelled.
+pub(crate) struct QueueEntry<'a> {
+    queue: &'a SearchQueue,
+    id: Option<u64>,
+    org_id: String,
+    enqueued_at: Instant,
+}
+
+impl QueueEntry<'_> {
+    /// Marks the search as having left the queue, returns the milliseconds it
+    /// waited.
+    pub(crate) fn start(&self) -> usize {
+        let took_wait = self.enqueued_at.elapsed();
+        metrics::QUERY_QUEUE_WAIT_TIME
+            .with_label_values(&[&self.org_id])
+            .observe(took_wait.as_secs_f64());
+        if let Some(mut search) = self.id.and_then(|id| self.queue.searches.get_mut(&id)) {
+            search.started_at = Some(Utc::now().timestamp_micros());
+        }
+        took_wait.as_millis() as usize
+    }
+}
+
+impl Drop for QueueEntry<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.queue.searches.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_queue_status() {
+        let queue = SearchQueue::new(10);
+        let first = queue.enqueue("trace_1", "org1", Some("root@example.com"), "123");
+        let second = queue.enqueue("trace_2", "org1", None, "456");
+        let _other = queue.enqueue("trace_3", "org2", None, "789");
+
+        let status = queue.status("org1");
+        assert_eq!(status.waiting, 2);
+        assert_eq!(status.running, 0);
+        assert_eq!(status.status[0].trace_id, "trace_1");
+        assert_eq!(
+            status.status[0].user_id.as_deref(),
+            Some("root@example.com")
+        );
+        assert_eq!(status.status[1].sql_hash, "456");
+
+        first.start();
+        let status = queue.status("org1");
+        assert_eq!(status.waiting, 1);
+        assert_eq!(status.running, 1);
+        assert!(status.status[0].started_at.is_some());
+
+        // completed and cancelled searches are removed
+        drop(first);
+        drop(second);
+        let status = queue.status("org1");
+        assert!(status.status.is_empty());
+        assert_eq!(queue.status("org2").waiting, 1);
+    }
+
+    #[test]
+    fn test_search_queue_is_bounded() {
+        let queue = SearchQueue::new(2);
+        let _first = queue.enqueue("trace_1", "org1", None, "1");
+        let second = queue.enqueue("trace_2", "org1", None, "2");
+        let third = queue.enqueue("trace_3", "org1", None, "3");
+        assert_eq!(queue.status("org1").status.len(), 2);
+
+        // searches beyond the bound still run, they are just not listed
+        third.start();
+        drop(third);
+        assert_eq!(queue.status("org1").status.len(), 2);
+
+        drop(second);
+        let _fourth = queue.enqueue("trace_4", "org1", None, "4");
+        let status = queue.status("org1");
+        assert_eq!(status.status.len(), 2);
+        assert_eq!(status.status[1].trace_id, "trace_4");
+    }
+}
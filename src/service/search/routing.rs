@@ -0,0 +1,328 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Resolves the `regions` and `clusters` of a search request against the known
+//! super cluster topology before the request is routed or hashed.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use config::meta::search::SearchRouting;
+use infra::errors::{Error, ErrorCodes};
+#[cfg(feature = "enterprise")]
+use o2_enterprise::enterprise::common::infra::config::get_config as get_o2_config;
+
+/// The identifier of the local region and cluster.
+const LOCAL: &str = "local";
+
+/// region -> clusters of the super cluster
+type Topology = BTreeMap<String, BTreeSet<String>>;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum RoutingError {
+    #[error("Unknown regions {unknown:?}, valid regions are {valid:?}")]
+    UnknownRegions {
+        unknown: Vec<String>,
+        valid: Vec<String>,
+    },
+    #[error("Unknown clusters {unknown:?}, valid clusters are {valid:?}")]
+    UnknownClusters {
+        unknown: Vec<String>,
+        valid: Vec<String>,
+    },
+    #[error(
+        "Clusters {clusters:?} don't belong to regions {regions:?}, valid clusters are {valid:?}"
+    )]
+    ClustersOutsideRegions {
+        clusters: Vec<String>,
+        regions: Vec<String>,
+        valid: Vec<String>,
+    },
+}
+
+impl From<RoutingError> for Error {
+    fn from(value: RoutingError) -> Self {
+        Error::ErrorCode(ErrorCodes::InvalidParams(value.to_string()))
+    }
+}
+
+/// Validates the requested regions and clusters and returns the effective
+/// routing, region only requests are expanded to the clusters of the regions.
+pub async fn resolve(regions: &[String], clusters: &[String]) -> Result<SearchRouting, Error> {
+    let topology = get_topology().await?;
+    let cluster_name = config::get_cluster_name();
+    Ok(resolve_with_topology(
+        topology.as_ref(),
+        &cluster_name,
+        regions,
+        clusters,
+    )?)
+}
+
+#[cfg(feature = "enterprise")]
+async fn get_topology() -> Result<Option<Topology>, Error> {
+    if !get_o2_config().super_cluster.enabled {
+        return Ok(None);
+    }
+    let clusters = o2_enterprise::enterprise::super_cluster::kv::cluster::list()
+        .await
+        .map_err(|e| Error::Message(e.to_string()))?;
+    let mut topology = Topology::new();
+    for c in clusters {
+        topology.entry(c.region).or_default().insert(c.name);
+    }
+    Ok(Some(topology))
+}
+
+#[cfg(not(feature = "enterprise"))]
+async fn get_topology() -> Result<Option<Topology>, Error> {
+    Ok(None)
+}
+
+fn resolve_with_topology(
+    topology: Option<&Topology>,
+    cluster_name: &str,
+    regions: &[String],
+    clusters: &[String],
+) -> Result<SearchRouting, RoutingError> {
+    let regions = normalize(regions);
+    let clusters = normalize(clusters);
+    let is_local_region = |region: &String| region == LOCAL;
+    let is_local_cluster = |cluster: &String| cluster == LOCAL || cluster == cluster_name;
+
+    let Some(topology) = topology else {
+        // without super cluster only the local cluster can be searched
+        let mut warnings = vec![];
+        if regions.iter().any(|v| !is_local_region(v))
+            || clusters.iter().any(|v| !is_local_cluster(v))
+        {
+            let warning = format!(
+                "Super cluster is not enabled, regions {regions:?} and clusters {clusters:?} are ignored and the local cluster is searched"
+            );
+            log::warn!("[SEARCH] {warning}");
+            warnings.push(warning);
+        }
+        return Ok(SearchRouting {
+            regions: vec![],
+            clusters: vec![],
+            warnings,
+        });
+    };
+
+    let unknown = regions
+        .iter()
+        .filter(|v| !is_local_region(v) && !topology.contains_key(*v))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(RoutingError::UnknownRegions {
+            unknown,
+            valid: topology.keys().cloned().collect(),
+        });
+    }
+    let all_clusters = topology.values().flatten().collect::<BTreeSet<_>>();
+    let unknown = clusters
+        .iter()
+        .filter(|v| !is_local_cluster(v) && !all_clusters.contains(v))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(RoutingError::UnknownClusters {
+            unknown,
+            valid: all_clusters.into_iter().cloned().collect(),
+        });
+    }
+
+    // the local identifiers keep their meaning, they are routed as they are
+    if regions.iter().any(is_local_region) || clusters.iter().any(is_local_cluster) {
+        return Ok(SearchRouting {
+            regions,
+            clusters,
+            warnings: vec![],
+        });
+    }
+
+    let region_clusters = regions
+        .iter()
+        .flat_map(|region| topology[region].iter().cloned())
+        .collect::<BTreeSet<_>>();
+    if clusters.is_empty() {
+        return Ok(SearchRouting {
+            regions,
+            clusters: region_clusters.into_iter().collect(),
+            warnings: vec![],
+        });
+    }
+    if !regions.is_empty() {
+        let outside = clusters
+            .iter()
+            .filter(|v| !region_clusters.contains(*v))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !outside.is_empty() {
+            return Err(RoutingError::ClustersOutsideRegions {
+                clusters: outside,
+                regions,
+                valid: region_clusters.into_iter().collect(),
+            });
+        }
+    }
+    Ok(SearchRouting {
+        regions,
+        clusters,
+        warnings: vec![],
+    })
+}
+
+/// Returns the routing values hashed into the result cache key, the clusters
+/// decide where a search runs so the regions they were expanded from are left out.
+pub fn cache_hash_values(regions: &[String], clusters: &[String]) -> Vec<String> {
+    if clusters.is_empty() {
+        normalize(regions)
+    } else {
+        normalize(clusters)
+    }
+}
+
+fn normalize(values: &[String]) -> Vec<String> {
+    values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topology() -> Topology {
+        let mut topology = Topology::new();
+        topology.insert(
+            "eu".to_string(),
+            ["eu-central-1-a", "eu-west-1-a"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        );
+        topology.insert(
+            "us".to_string(),
+            ["us-west-2-a"].into_iter().map(String::from).collect(),
+        );
+        topology
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_routing_matrix() {
+        let topology = topology();
+        let resolve = |regions: &[&str], clusters: &[&str]| {
+            resolve_with_topology(Some(&topology), "o2", &strings(regions), &strings(clusters))
+        };
+
+        // nothing requested, every cluster is searched
+        assert_eq!(resolve(&[], &[]).unwrap(), SearchRouting::default());
+        // region only requests are expanded to the clusters of the regions
+        assert_eq!(
+            resolve(&["eu"], &[]).unwrap().clusters,
+            strings(&["eu-central-1-a", "eu-west-1-a"])
+        );
+        // cluster only
+        assert_eq!(
+            resolve(&[], &["us-west-2-a"]).unwrap().clusters,
+            strings(&["us-west-2-a"])
+        );
+        // clusters of the requested regions
+        assert_eq!(
+            resolve(&["eu", "us"], &["eu-west-1-a", "us-west-2-a"])
+                .unwrap()
+                .clusters,
+            strings(&["eu-west-1-a", "us-west-2-a"])
+        );
+        // a cluster of another region
+        assert_eq!(
+            resolve(&["eu"], &["us-west-2-a"]).unwrap_err(),
+            RoutingError::ClustersOutsideRegions {
+                clusters: strings(&["us-west-2-a"]),
+                regions: strings(&["eu"]),
+                valid: strings(&["eu-central-1-a", "eu-west-1-a"]),
+            }
+        );
+        assert!(matches!(
+            resolve(&["apac"], &[]).unwrap_err(),
+            RoutingError::UnknownRegions { .. }
+        ));
+        assert!(matches!(
+            resolve(&[], &["eu-north-1-a"]).unwrap_err(),
+            RoutingError::UnknownClusters { .. }
+        ));
+        // the local identifiers are routed as they are
+        assert_eq!(
+            resolve(&["local"], &["local"]).unwrap(),
+            SearchRouting {
+                regions: strings(&["local"]),
+                clusters: strings(&["local"]),
+                warnings: vec![],
+            }
+        );
+        assert_eq!(resolve(&[], &["o2"]).unwrap().clusters, strings(&["o2"]));
+    }
+
+    #[test]
+    fn test_resolve_routing_without_super_cluster() {
+        let routing =
+            resolve_with_topology(None, "o2", &strings(&["eu"]), &strings(&["us-west-2-a"]))
+                .unwrap();
+        assert!(routing.regions.is_empty() && routing.clusters.is_empty());
+        assert_eq!(routing.warnings.len(), 1);
+
+        let routing =
+            resolve_with_topology(None, "o2", &strings(&["local"]), &strings(&["o2"])).unwrap();
+        assert!(routing.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_cache_hash_values_expansion() {
+        let topology = topology();
+        // a region and its explicit clusters route to the same clusters and hash the same
+        let by_region =
+            resolve_with_topology(Some(&topology), "o2", &strings(&["eu"]), &[]).unwrap();
+        let by_clusters = resolve_with_topology(
+            Some(&topology),
+            "o2",
+            &[],
+            &strings(&["eu-west-1-a", "eu-central-1-a"]),
+        )
+        .unwrap();
+        assert_eq!(
+            cache_hash_values(&by_region.regions, &by_region.clusters),
+            cache_hash_values(&by_clusters.regions, &by_clusters.clusters)
+        );
+        assert_eq!(
+            cache_hash_values(&by_region.regions, &by_region.clusters),
+            strings(&["eu-central-1-a", "eu-west-1-a"])
+        );
+        // the order and duplicates of the request don't change the key
+        assert_eq!(
+            cache_hash_values(&[], &strings(&["b", "a", "b"])),
+            cache_hash_values(&[], &strings(&["a", "b"]))
+        );
+    }
+}
@@ -13,15 +13,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{ops::ControlFlow, sync::Arc};
+use std::{ops::ControlFlow, str::FromStr, sync::Arc};
 
+use arrow::array::timezone::Tz;
 use arrow_schema::FieldRef;
 use chrono::Duration;
 use config::{
     get_config,
     meta::{
         inverted_index::InvertedIndexOptimizeMode,
-        sql::{resolve_stream_names_with_type, OrderBy, Sql as MetaSql, TableReferenceExt},
+        search::{ExplainFilterItem, ExplainResponse, SearchQueryRef},
+        sql::{
+            check_stream_type_qualifiers, resolve_stream_names_with_type, OrderBy, Sql as MetaSql,
+            TableReferenceExt,
+        },
         stream::StreamType,
     },
     utils::sql::AGGREGATE_UDF_LIST,
@@ -43,8 +48,8 @@ use sqlparser::{
     ast::{
         BinaryOperator, DuplicateTreatment, Expr, Function, FunctionArg, FunctionArgExpr,
         FunctionArgumentList, FunctionArguments, GroupByExpr, Ident, ObjectName, OrderByExpr,
-        Query, Select, SelectItem, SetExpr, Statement, TableFactor, TableWithJoins, VisitMut,
-        VisitorMut,
+        Query, Select, SelectItem, SetExpr, Statement, TableFactor, TableWithJoins, Value,
+        VisitMut, VisitorMut,
     },
     dialect::PostgreSqlDialect,
     parser::Parser,
@@ -87,43 +92,135 @@ pub struct Sql {
     pub time_range: Option<(i64, i64)>,
     pub group_by: Vec<String>,
     pub order_by: Vec<(String, OrderBy)>,
-    pub histogram_interval: Option<i64>,
-    pub sorted_by_time: bool,     // if only order by _timestamp
-    pub use_inverted_index: bool, // if can use inverted index
+    pub histogram_interval: Option<i64>, // interval of the first histogram() call
+    pub histogram_intervals: Vec<i64>,   // distinct intervals of all the histogram() calls
+    pub histogram_timezone: Option<String>, // time zone histogram buckets are aligned to
+    pub histogram_calendar_interval: Option<String>, // like `1 month`, of the first histogram()
+    pub has_calendar_histogram: bool,    // if any histogram() call has calendar buckets
+    pub sorted_by_time: bool,            // if only order by _timestamp
+    pub use_inverted_index: bool,        // if can use inverted index
     pub index_condition: Option<IndexCondition>, // use for tantivy index
     pub index_optimize_mode: Option<InvertedIndexOptimizeMode>,
+    pub warnings: Vec<String>, // warnings to return with the search response
 }
 
 impl Sql {
+    /// Whether the histogram buckets are whole days aligned to a time zone other
+    /// than UTC, whose boundaries can't be derived from UTC aligned time ranges.
+    /// Intervals that can't be converted to seconds (weeks) and calendar
+    /// intervals count as days.
+    pub fn has_local_day_histogram(&self) -> bool {
+        self.histogram_timezone.is_some()
+            && self
+                .histogram_interval
+                .is_some_and(|interval| interval <= 0 || interval % 86400 == 0)
+    }
+
+    /// Width in months of the buckets of the first histogram when they are
+    /// calendar months, quarters or years.
+    pub fn histogram_calendar_months(&self) -> Option<i64> {
+        self.histogram_calendar_interval
+            .as_deref()
+            .and_then(parse_calendar_interval)
+    }
+
+    /// Describes how the query is planned, for the requests sent with
+    /// `explain`.
+    pub fn explain(self: &Arc<Self>) -> ExplainResponse {
+        let (use_inverted_index, _) = super::is_use_inverted_index(self);
+        let filter_items = |items: &HashMap<TableReference, Vec<(String, String)>>| {
+            items
+                .iter()
+                .map(|(stream, items)| {
+                    let items = items
+                        .iter()
+                        .map(|(field, value)| ExplainFilterItem {
+                            field: field.clone(),
+                            value: value.clone(),
+                        })
+                        .collect();
+                    (stream.to_string(), items)
+                })
+                .collect()
+        };
+        ExplainResponse {
+            stream_names: self.stream_names.iter().map(|s| s.to_string()).collect(),
+            use_inverted_index,
+            index_condition: self.index_condition.as_ref().map(|c| c.to_query()),
+            equal_items: filter_items(&self.equal_items),
+            prefix_items: filter_items(&self.prefix_items),
+            histogram_interval: self.histogram_interval,
+        }
+    }
+
+    /// Interval in seconds the partitions of the query are aligned to, the
+    /// least common multiple of the histogram intervals so that no bucket of
+    /// any of the histograms is split across partitions.
+    pub fn histogram_partition_interval(&self) -> Option<i64> {
+        fn gcd(a: i64, b: i64) -> i64 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        let primary = self.histogram_interval?;
+        if primary <= 0 {
+            return Some(primary);
+        }
+        Some(
+            self.histogram_intervals
+                .iter()
+                .filter(|interval| **interval > 0)
+                .fold(primary, |acc, interval| {
+                    acc / gcd(acc, *interval) * interval
+                }),
+        )
+    }
+
     pub async fn new_from_req(req: &Request, query: &SearchQuery) -> Result<Sql, Error> {
         Self::new(query, &req.org_id, req.stream_type).await
     }
 
-    pub async fn new(
-        query: &SearchQuery,
+    pub async fn new<'a>(
+        query: impl Into<SearchQueryRef<'a>>,
         org_id: &str,
         stream_type: StreamType,
     ) -> Result<Sql, Error> {
+        let query: SearchQueryRef = query.into();
         let cfg = get_config();
-        let sql = query.sql.clone();
-        let limit = query.size as i64;
-        let offset = query.from as i64;
+        let sql = query.sql;
 
         // 1. get table name
         let stream_names =
             resolve_stream_names_with_type(&sql).map_err(|e| Error::Message(e.to_string()))?;
+        check_stream_type_qualifiers(&stream_names)
+            .map_err(|e| Error::ErrorCode(ErrorCodes::SearchSQLNotValid(e.to_string())))?;
         if stream_names.len() > 1 && stream_names.iter().any(|s| s.schema() == Some("index")) {
             return Err(Error::Message(
                 "Index stream is not supported in multi-stream query".to_string(),
             ));
         }
+        let streams = stream_names
+            .iter()
+            .map(|stream| (stream.stream_name(), stream.get_stream_type(stream_type)))
+            .collect::<Vec<_>>();
+        let schemas = infra::schema::get_many(org_id, &streams).await;
         let mut total_schemas = HashMap::with_capacity(stream_names.len());
-        for stream in stream_names.iter() {
-            let stream_name = stream.stream_name();
-            let stream_type = stream.get_stream_type(stream_type);
-            let schema = infra::schema::get(org_id, &stream_name, stream_type)
+        for (stream, schema) in stream_names.iter().zip(schemas) {
+            let mut schema = schema.unwrap_or_else(|_| Schema::empty());
+            if schema.fields().is_empty() {
+                // the stream may have been created since this node last looked it up
+                if let Ok(rechecked) = infra::schema::recheck_not_found(
+                    org_id,
+                    &stream.stream_name(),
+                    stream.get_stream_type(stream_type),
+                )
                 .await
-                .unwrap_or_else(|_| Schema::empty());
+                {
+                    schema = rechecked;
+                }
+            }
             total_schemas.insert(stream.clone(), Arc::new(SchemaCache::new(schema)));
         }
 
@@ -132,6 +229,10 @@ impl Sql {
             .pop()
             .unwrap();
 
+        // the LIMIT and OFFSET of the sql win over the size and from of the request
+        let (limit, offset, warnings) =
+            reconcile_limit(get_statement_limit(&statement), query.size, query.from);
+
         // 2. rewrite track_total_hits
         if query.track_total_hits {
             let mut trace_total_hits_visitor = TrackTotalHitsVisitor::new();
@@ -215,17 +316,28 @@ impl Sql {
         }
 
         // 7. get partition column value
-        let mut partition_column_visitor = PartitionColumnVisitor::new(&used_schemas);
-        statement.visit(&mut partition_column_visitor);
-
         // 8. get prefix column value
-        let mut prefix_column_visitor = PrefixColumnVisitor::new(&used_schemas);
-        statement.visit(&mut prefix_column_visitor);
+        let (equal_items, prefix_items) = get_filter_items(&mut statement, &used_schemas);
 
         // 9. pick up histogram interval
         let mut histogram_interval_visitor =
             HistogramIntervalVistor::new(Some((query.start_time, query.end_time)));
         statement.visit(&mut histogram_interval_visitor);
+        let histogram_timezone = if histogram_interval_visitor.interval.is_some() {
+            match histogram_interval_visitor.timezone.as_deref() {
+                Some(timezone) => normalize_histogram_timezone(timezone),
+                None => normalize_histogram_timezone(query.timezone),
+            }
+        } else {
+            None
+        };
+        if let Some(timezone) = &histogram_timezone {
+            if Tz::from_str(timezone).is_err() {
+                return Err(Error::Message(format!(
+                    "Invalid time zone in histogram: {timezone}"
+                )));
+            }
+        }
 
         // NOTE: only this place modify the sql
         // 10. add _timestamp and _o2_id if need
@@ -282,8 +394,8 @@ impl Sql {
             stream_type,
             stream_names,
             match_items: match_visitor.match_items,
-            equal_items: partition_column_visitor.equal_items,
-            prefix_items: prefix_column_visitor.prefix_items,
+            equal_items,
+            prefix_items,
             columns,
             aliases,
             schemas: used_schemas,
@@ -293,10 +405,15 @@ impl Sql {
             group_by,
             order_by,
             histogram_interval: histogram_interval_visitor.interval,
+            histogram_intervals: histogram_interval_visitor.intervals,
+            histogram_timezone,
+            histogram_calendar_interval: histogram_interval_visitor.calendar_interval,
+            has_calendar_histogram: histogram_interval_visitor.has_calendar,
             sorted_by_time: need_sort_by_time,
             use_inverted_index,
             index_condition,
             index_optimize_mode,
+            warnings,
         })
     }
 }
@@ -740,6 +857,102 @@ impl VisitorMut for IndexVisitor {
     }
 }
 
+type FilterItems = HashMap<TableReference, Vec<(String, String)>>;
+
+/// Returns the LIMIT and OFFSET of the outermost query of the statement, the
+/// limits of subqueries don't bound the rows returned by the statement.
+fn get_statement_limit(statement: &Statement) -> (Option<i64>, Option<i64>) {
+    let Statement::Query(query) = statement else {
+        return (None, None);
+    };
+    let parse = |expr: &Expr| match expr {
+        Expr::Value(Value::Number(v, _)) => v.parse::<i64>().ok(),
+        _ => None,
+    };
+    (
+        query.limit.as_ref().and_then(parse),
+        query
+            .offset
+            .as_ref()
+            .and_then(|offset| parse(&offset.value)),
+    )
+}
+
+/// Returns the LIMIT and OFFSET a search with the size and from of the request
+/// runs with, and the warnings about the request values they override. Sql
+/// that doesn't parse keeps the values of the request.
+pub fn get_search_limit(sql: &str, size: i64, from: i64) -> (i64, i64, Vec<String>) {
+    let statement = match Parser::parse_sql(&PostgreSqlDialect {}, sql) {
+        Ok(mut statements) => statements.pop(),
+        Err(_) => None,
+    };
+    match statement {
+        Some(statement) => reconcile_limit(get_statement_limit(&statement), size, from),
+        None => (size, from, vec![]),
+    }
+}
+
+/// Reconciles the LIMIT and OFFSET of the sql with the size and from of the
+/// request, the values written in the sql win. Request values that differ
+/// from the sql produce a warning instead of an error.
+fn reconcile_limit(
+    statement: (Option<i64>, Option<i64>),
+    size: i64,
+    from: i64,
+) -> (i64, i64, Vec<String>) {
+    let (sql_limit, sql_offset) = statement;
+    let mut warnings = vec![];
+    let limit = match sql_limit {
+        Some(limit) => {
+            if size > limit {
+                warnings.push(format!(
+                    "The size {size} of the request is larger than the LIMIT {limit} of the SQL, only {limit} records are returned"
+                ));
+            } else if size > 0 && size != limit {
+                warnings.push(format!(
+                    "The size {size} of the request is overridden by the LIMIT {limit} of the SQL"
+                ));
+            }
+            limit
+        }
+        None => size,
+    };
+    let offset = match sql_offset {
+        Some(offset) => {
+            if from > 0 && from != offset {
+                warnings.push(format!(
+                    "The from {from} of the request is overridden by the OFFSET {offset} of the SQL"
+                ));
+            }
+            offset
+        }
+        None => from,
+    };
+    for warning in warnings.iter() {
+        log::warn!("search limit: {warning}");
+    }
+    (limit, offset, warnings)
+}
+
+/// get the equal and prefix items used to prune the files, the filters of a
+/// nested query belong to the inner scope so nothing is extracted for them
+fn get_filter_items(
+    statement: &mut Statement,
+    schemas: &HashMap<TableReference, Arc<SchemaCache>>,
+) -> (FilterItems, FilterItems) {
+    if has_subquery(statement) {
+        return (HashMap::new(), HashMap::new());
+    }
+    let mut partition_column_visitor = PartitionColumnVisitor::new(schemas);
+    statement.visit(&mut partition_column_visitor);
+    let mut prefix_column_visitor = PrefixColumnVisitor::new(schemas);
+    statement.visit(&mut prefix_column_visitor);
+    (
+        partition_column_visitor.equal_items,
+        prefix_column_visitor.prefix_items,
+    )
+}
+
 /// get all equal items from where clause
 struct PartitionColumnVisitor<'a> {
     equal_items: HashMap<TableReference, Vec<(String, String)>>, // filed = value
@@ -1192,7 +1405,7 @@ fn is_complex_query(statement: &mut Statement) -> bool {
 }
 
 // check if the query is complex query
-// 1. has subquery
+// 1. has subquery, derived table or CTE
 // 2. has join
 // 3. has group by
 // 4. has aggregate
@@ -1213,6 +1426,11 @@ impl VisitorMut for ComplexQueryVisitor {
     type Break = ();
 
     fn pre_visit_query(&mut self, query: &mut Query) -> ControlFlow<Self::Break> {
+        // check if has CTE
+        if query.with.is_some() {
+            self.is_complex = true;
+            return ControlFlow::Break(());
+        }
         match query.body.as_ref() {
             sqlparser::ast::SetExpr::Select(select) => {
                 // check if has group by
@@ -1227,12 +1445,22 @@ impl VisitorMut for ComplexQueryVisitor {
                 if select.distinct.is_some() {
                     self.is_complex = true;
                 }
+                // check if has derived table
+                if select.from.iter().any(|from| {
+                    matches!(from.relation, TableFactor::Derived { .. })
+                        || from
+                            .joins
+                            .iter()
+                            .any(|join| matches!(join.relation, TableFactor::Derived { .. }))
+                }) {
+                    self.is_complex = true;
+                }
                 if self.is_complex {
                     return ControlFlow::Break(());
                 }
             }
-            // check if SetOperation
-            sqlparser::ast::SetExpr::SetOperation { .. } => {
+            // check if SetOperation or nested query
+            sqlparser::ast::SetExpr::SetOperation { .. } | sqlparser::ast::SetExpr::Query(_) => {
                 self.is_complex = true;
                 return ControlFlow::Break(());
             }
@@ -1269,8 +1497,46 @@ impl VisitorMut for ComplexQueryVisitor {
     }
 }
 
+/// check if the sql has a nested query, e.g. CTE, derived table or subquery
+fn has_subquery(statement: &mut Statement) -> bool {
+    let mut visitor = SubqueryVisitor::new();
+    statement.visit(&mut visitor);
+    visitor.queries > 1
+}
+
+// every CTE, derived table and subquery expression is visited as a query
+struct SubqueryVisitor {
+    queries: usize,
+}
+
+impl SubqueryVisitor {
+    fn new() -> Self {
+        Self { queries: 0 }
+    }
+}
+
+impl VisitorMut for SubqueryVisitor {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, _query: &mut Query) -> ControlFlow<Self::Break> {
+        self.queries += 1;
+        if self.queries > 1 {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Collects the distinct intervals of the `histogram()` calls of the query.
+/// The first call is the primary histogram, its interval and time zone are
+/// kept in `interval` and `timezone`, and its interval as written in
+/// `calendar_interval` when it is a calendar one.
 struct HistogramIntervalVistor {
     pub interval: Option<i64>,
+    pub intervals: Vec<i64>,
+    pub timezone: Option<String>,
+    pub calendar_interval: Option<String>,
+    pub has_calendar: bool,
     time_range: Option<(i64, i64)>,
 }
 
@@ -1278,6 +1544,10 @@ impl HistogramIntervalVistor {
     fn new(time_range: Option<(i64, i64)>) -> Self {
         Self {
             interval: None,
+            intervals: Vec::new(),
+            timezone: None,
+            calendar_interval: None,
+            has_calendar: false,
             time_range,
         }
     }
@@ -1306,10 +1576,26 @@ impl VisitorMut for HistogramIntervalVistor {
                     } else {
                         generate_histogram_interval(self.time_range, 0)
                     };
-                    self.interval =
-                        Some(convert_histogram_interval_to_seconds(&interval).unwrap_or_default());
+                    let seconds =
+                        convert_histogram_interval_to_seconds(&interval).unwrap_or_default();
+                    let is_calendar = parse_calendar_interval(&interval).is_some();
+                    self.has_calendar |= is_calendar;
+                    if !self.intervals.contains(&seconds) {
+                        self.intervals.push(seconds);
+                    }
+                    if self.interval.is_none() {
+                        self.interval = Some(seconds);
+                        self.calendar_interval =
+                            is_calendar.then(|| interval.trim().to_lowercase());
+                        // third is time zone
+                        self.timezone = args.next().map(|timezone| {
+                            timezone
+                                .to_string()
+                                .trim_matches(|v| v == '\'' || v == '"')
+                                .to_string()
+                        });
+                    }
                 }
-                return ControlFlow::Break(());
             }
         }
         ControlFlow::Continue(())
@@ -1479,6 +1765,21 @@ fn checking_inverted_index_inner(index_fields: &HashSet<&String>, expr: &Expr) -
     }
 }
 
+/// Returns the time zone histogram buckets should be aligned to, or `None`
+/// when they are aligned to UTC.
+pub fn normalize_histogram_timezone(timezone: &str) -> Option<String> {
+    let timezone = timezone.trim();
+    if timezone.is_empty()
+        || timezone.eq_ignore_ascii_case("utc")
+        || timezone.eq_ignore_ascii_case("etc/utc")
+        || timezone == "+00:00"
+    {
+        None
+    } else {
+        Some(timezone.to_string())
+    }
+}
+
 pub fn generate_histogram_interval(time_range: Option<(i64, i64)>, num: u16) -> String {
     if time_range.is_none() || time_range.unwrap().eq(&(0, 0)) {
         return "1 hour".to_string();
@@ -1520,7 +1821,31 @@ pub fn generate_histogram_interval(time_range: Option<(i64, i64)>, num: u16) ->
     "10 second".to_string()
 }
 
+/// Nominal length of a calendar month in seconds, used to report calendar
+/// histogram intervals to the consumers expecting seconds.
+pub const SECONDS_PER_CALENDAR_MONTH: i64 = 30 * 86400;
+
+/// Returns the number of months of a calendar histogram interval, like
+/// `1 month`, `2 quarters` or `1 year`, or `None` for fixed width intervals.
+pub fn parse_calendar_interval(interval: &str) -> Option<i64> {
+    let interval = interval.trim();
+    let pos = interval.find(|c: char| !c.is_ascii_digit())?;
+    let (num, unit) = interval.split_at(pos);
+    let num = num.parse::<i64>().ok().filter(|num| *num > 0)?;
+    match unit.trim().to_lowercase().as_str() {
+        "month" | "months" | "mon" | "mons" => Some(num),
+        "quarter" | "quarters" | "qtr" | "qtrs" => Some(num * 3),
+        "year" | "years" | "yr" | "yrs" => Some(num * 12),
+        _ => None,
+    }
+}
+
+/// Converts a histogram interval to seconds, calendar intervals are counted
+/// in months of [`SECONDS_PER_CALENDAR_MONTH`].
 pub fn convert_histogram_interval_to_seconds(interval: &str) -> Result<i64, Error> {
+    if let Some(months) = parse_calendar_interval(interval) {
+        return Ok(months * SECONDS_PER_CALENDAR_MONTH);
+    }
     let interval = interval.trim();
     let (num, unit) = interval
         .find(|c: char| !c.is_numeric())
@@ -1804,6 +2129,50 @@ mod tests {
         assert_eq!(statement.to_string(), expected_sql);
     }
 
+    #[test]
+    fn test_index_visitor_like_patterns() {
+        let cases = [
+            ("name LIKE 'err%'", Some("name LIKE 'err%'"), ""),
+            ("name LIKE '%err'", Some("name LIKE '%err'"), ""),
+            ("name LIKE '%err%'", Some("name LIKE '%err%'"), ""),
+            // escaped wildcards are part of the value
+            (r"name LIKE '100\%'", Some(r"name LIKE '100\%'"), ""),
+            // the index can't answer a wildcard in the middle or a single character wildcard
+            ("name LIKE 'e%r'", None, " WHERE name LIKE 'e%r'"),
+            ("name LIKE 'er_'", None, " WHERE name LIKE 'er_'"),
+            (
+                "name NOT LIKE '%err%'",
+                None,
+                " WHERE name NOT LIKE '%err%'",
+            ),
+            // not an index field
+            ("log LIKE '%err%'", None, " WHERE log LIKE '%err%'"),
+        ];
+        for (filter, expected, expected_where) in cases {
+            let sql = format!("SELECT * FROM t WHERE {filter}");
+            let mut statement = Parser::parse_sql(&PostgreSqlDialect {}, &sql)
+                .unwrap()
+                .pop()
+                .unwrap();
+            let mut index_fields = HashSet::new();
+            index_fields.insert("name".to_string());
+            let mut index_visitor = IndexVisitor::new_from_index_fields(index_fields, true);
+            statement.visit(&mut index_visitor);
+            assert_eq!(
+                index_visitor
+                    .index_condition
+                    .map(|v| v.to_query())
+                    .as_deref(),
+                expected,
+                "{filter}"
+            );
+            assert_eq!(
+                statement.to_string(),
+                format!("SELECT * FROM t{expected_where}")
+            );
+        }
+    }
+
     #[test]
     fn test_track_total_hits1() {
         let sql = "SELECT * FROM t WHERE name = 'a'";
@@ -1995,6 +2364,193 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize_histogram_timezone() {
+        assert_eq!(normalize_histogram_timezone(""), None);
+        assert_eq!(normalize_histogram_timezone("UTC"), None);
+        assert_eq!(normalize_histogram_timezone("Etc/UTC"), None);
+        assert_eq!(
+            normalize_histogram_timezone(" America/Los_Angeles "),
+            Some("America/Los_Angeles".to_string())
+        );
+        assert_eq!(
+            normalize_histogram_timezone("+05:30"),
+            Some("+05:30".to_string())
+        );
+    }
+
+    #[test]
+    fn test_histogram_interval_visitor_multiple_intervals() {
+        let sql = "SELECT histogram(_timestamp, '1 minute') AS k1, histogram(_timestamp, '5 minute', 'Asia/Tokyo') AS k2, histogram(_timestamp, '1 minute') AS k3, count(*) FROM t GROUP BY k1, k2, k3";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut visitor = HistogramIntervalVistor::new(None);
+        statement.visit(&mut visitor);
+        // the first histogram stays the primary one
+        assert_eq!(visitor.interval, Some(60));
+        assert_eq!(visitor.timezone, None);
+        assert_eq!(visitor.intervals, vec![60, 300]);
+
+        let sql = "SELECT histogram(_timestamp, '5 minute') AS k, count(*) FROM t GROUP BY k";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut visitor = HistogramIntervalVistor::new(None);
+        statement.visit(&mut visitor);
+        assert_eq!(visitor.interval, Some(300));
+        assert_eq!(visitor.intervals, vec![300]);
+    }
+
+    fn filter_test_schemas() -> HashMap<TableReference, Arc<SchemaCache>> {
+        let schema = Schema::new(vec![
+            arrow_schema::Field::new("_timestamp", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("k8s_namespace", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new("code", arrow_schema::DataType::Utf8, true),
+        ]);
+        let mut schemas = HashMap::new();
+        schemas.insert(
+            TableReference::from("t"),
+            Arc::new(SchemaCache::new(schema)),
+        );
+        schemas
+    }
+
+    #[test]
+    fn test_filter_items_simple_query() {
+        let sql = "SELECT code FROM t WHERE k8s_namespace = 'ns1' AND code LIKE '5%'";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert!(!is_complex_query(&mut statement));
+        let (equal_items, prefix_items) = get_filter_items(&mut statement, &filter_test_schemas());
+        assert_eq!(
+            equal_items.get(&TableReference::from("t")),
+            Some(&vec![("k8s_namespace".to_string(), "ns1".to_string())])
+        );
+        assert_eq!(
+            prefix_items.get(&TableReference::from("t")),
+            Some(&vec![("code".to_string(), "5".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_filter_items_cte() {
+        let sql = "WITH errors AS (SELECT code FROM t WHERE k8s_namespace = 'ns1') SELECT code FROM errors";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert!(is_complex_query(&mut statement));
+        let (equal_items, prefix_items) = get_filter_items(&mut statement, &filter_test_schemas());
+        assert!(equal_items.is_empty());
+        assert!(prefix_items.is_empty());
+    }
+
+    #[test]
+    fn test_filter_items_subquery_in_where() {
+        let sql =
+            "SELECT code FROM t WHERE code IN (SELECT code FROM t WHERE k8s_namespace = 'ns1')";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert!(is_complex_query(&mut statement));
+        let (equal_items, _) = get_filter_items(&mut statement, &filter_test_schemas());
+        assert!(equal_items.is_empty());
+    }
+
+    fn statement_limit(sql: &str) -> (Option<i64>, Option<i64>) {
+        let statement = sqlparser::parser::Parser::parse_sql(&PostgreSqlDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        get_statement_limit(&statement)
+    }
+
+    #[test]
+    fn test_statement_limit_in_sql() {
+        assert_eq!(
+            statement_limit("SELECT * FROM t ORDER BY _timestamp DESC LIMIT 10 OFFSET 5"),
+            (Some(10), Some(5))
+        );
+        // only the outermost query bounds the rows returned
+        assert_eq!(
+            statement_limit("SELECT * FROM (SELECT * FROM t LIMIT 10) ORDER BY code"),
+            (None, None)
+        );
+        assert_eq!(
+            statement_limit("SELECT * FROM (SELECT * FROM t LIMIT 10) ORDER BY code LIMIT 3"),
+            (Some(3), None)
+        );
+    }
+
+    #[test]
+    fn test_search_limit_sql_only() {
+        assert_eq!(
+            get_search_limit("SELECT * FROM t LIMIT 10 OFFSET 5", 0, 0),
+            (10, 5, vec![])
+        );
+        assert_eq!(
+            get_search_limit("SELECT * FROM t LIMIT 10", -1, 0),
+            (10, 0, vec![])
+        );
+    }
+
+    #[test]
+    fn test_search_limit_request_only() {
+        assert_eq!(
+            get_search_limit("SELECT * FROM t", 100, 20),
+            (100, 20, vec![])
+        );
+        assert_eq!(get_search_limit("SELECT * FROM t", -1, 0), (-1, 0, vec![]));
+        // sql that doesn't parse keeps the request values
+        assert_eq!(
+            get_search_limit("SELECT FROM WHERE", 100, 20),
+            (100, 20, vec![])
+        );
+    }
+
+    #[test]
+    fn test_search_limit_both_agreeing() {
+        assert_eq!(
+            get_search_limit("SELECT * FROM t LIMIT 10 OFFSET 5", 10, 5),
+            (10, 5, vec![])
+        );
+    }
+
+    #[test]
+    fn test_search_limit_both_conflicting() {
+        // the sql wins over the request, with a warning instead of an error
+        let (limit, offset, warnings) =
+            get_search_limit("SELECT * FROM t LIMIT 10 OFFSET 5", 100, 20);
+        assert_eq!((limit, offset), (10, 5));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("larger than the LIMIT 10"));
+        assert!(warnings[1].contains("OFFSET 5"));
+        // a smaller size is overridden too
+        let (limit, _, warnings) = get_search_limit("SELECT * FROM t LIMIT 10", 3, 0);
+        assert_eq!(limit, 10);
+        assert!(warnings[0].contains("overridden by the LIMIT 10"));
+        // the request fills what the sql doesn't set
+        let (limit, offset, _) = get_search_limit("SELECT * FROM t LIMIT 10", 100, 20);
+        assert_eq!((limit, offset), (10, 20));
+    }
+
+    #[test]
+    fn test_is_complex_query_derived_table() {
+        let sql = "SELECT code FROM (SELECT code FROM t WHERE k8s_namespace = 'ns1') AS s";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert!(is_complex_query(&mut statement));
+        assert!(has_subquery(&mut statement));
+    }
+
     #[test]
     fn test_convert_histogram_interval_abbreviations() {
         // Test abbreviated formats
@@ -2007,6 +2563,47 @@ mod tests {
         assert!(convert_histogram_interval_to_seconds("1y").is_err()); // year is not supported
     }
 
+    #[test]
+    fn test_parse_calendar_interval() {
+        assert_eq!(parse_calendar_interval("1 month"), Some(1));
+        assert_eq!(parse_calendar_interval(" 2 Months "), Some(2));
+        assert_eq!(parse_calendar_interval("1 quarter"), Some(3));
+        assert_eq!(parse_calendar_interval("2 quarters"), Some(6));
+        assert_eq!(parse_calendar_interval("1 year"), Some(12));
+        assert_eq!(parse_calendar_interval("30 day"), None);
+        assert_eq!(parse_calendar_interval("1M"), None);
+        assert_eq!(parse_calendar_interval("0 month"), None);
+        assert_eq!(
+            convert_histogram_interval_to_seconds("1 quarter").unwrap(),
+            3 * SECONDS_PER_CALENDAR_MONTH
+        );
+    }
+
+    #[test]
+    fn test_histogram_interval_visitor_calendar_interval() {
+        let sql = "SELECT histogram(_timestamp, '1 Month') AS k, count(*) FROM t GROUP BY k";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut visitor = HistogramIntervalVistor::new(None);
+        statement.visit(&mut visitor);
+        assert_eq!(visitor.interval, Some(SECONDS_PER_CALENDAR_MONTH));
+        assert_eq!(visitor.calendar_interval.as_deref(), Some("1 month"));
+        assert!(visitor.has_calendar);
+
+        let sql = "SELECT histogram(_timestamp, '30 day') AS k, count(*) FROM t GROUP BY k";
+        let mut statement = sqlparser::parser::Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut visitor = HistogramIntervalVistor::new(None);
+        statement.visit(&mut visitor);
+        assert_eq!(visitor.interval, Some(SECONDS_PER_CALENDAR_MONTH));
+        assert_eq!(visitor.calendar_interval, None);
+        assert!(!visitor.has_calendar);
+    }
+
     #[test]
     fn test_convert_histogram_interval_full_words() {
         // Test full word formats
@@ -2130,4 +2727,111 @@ mod tests {
             1000000
         );
     }
+
+    #[tokio::test]
+    async fn test_explain_match_all_and_equality() {
+        let org_id = "explain_test";
+        let settings = config::meta::stream::StreamSettings {
+            full_text_search_keys: vec!["log".to_string()],
+            index_fields: vec!["k8s_namespace".to_string()],
+            ..Default::default()
+        };
+        let schema = Schema::new(vec![
+            arrow_schema::Field::new("_timestamp", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("log", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new("k8s_namespace", arrow_schema::DataType::Utf8, true),
+        ])
+        .with_metadata(
+            [(
+                "settings".to_string(),
+                config::utils::json::to_string(&settings).unwrap(),
+            )]
+            .into(),
+        );
+        infra::schema::STREAM_SCHEMAS_LATEST.write().await.insert(
+            format!("{org_id}/{}/t", StreamType::Logs),
+            SchemaCache::new(schema),
+        );
+
+        let query = SearchQuery {
+            sql: "SELECT histogram(_timestamp, '1 minute') AS k, count(*) FROM t WHERE match_all('error') AND k8s_namespace = 'ns1' GROUP BY k".to_string(),
+            start_time: 0,
+            end_time: 3_600_000_000,
+            ..Default::default()
+        };
+        let sql = Arc::new(Sql::new(&query, org_id, StreamType::Logs).await.unwrap());
+        let explain = sql.explain();
+        assert_eq!(explain.stream_names, vec!["t".to_string()]);
+        assert!(explain.use_inverted_index);
+        assert_eq!(
+            explain.index_condition.as_deref(),
+            Some("_all:error AND k8s_namespace=ns1")
+        );
+        assert_eq!(
+            explain.equal_items.get("t"),
+            Some(&vec![ExplainFilterItem {
+                field: "k8s_namespace".to_string(),
+                value: "ns1".to_string(),
+            }])
+        );
+        assert!(explain.prefix_items.is_empty());
+        assert_eq!(explain.histogram_interval, Some(60));
+    }
+
+    #[tokio::test]
+    async fn test_sql_new_resolves_qualified_stream_types() {
+        let org_id = "qualified_stream_type_test";
+        let logs_schema = Schema::new(vec![
+            arrow_schema::Field::new("_timestamp", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("trace_id", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new("log", arrow_schema::DataType::Utf8, true),
+        ]);
+        let traces_schema = Schema::new(vec![
+            arrow_schema::Field::new("_timestamp", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("trace_id", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new("duration", arrow_schema::DataType::Int64, true),
+        ]);
+        {
+            let mut w = infra::schema::STREAM_SCHEMAS_LATEST.write().await;
+            w.insert(
+                format!("{org_id}/{}/default", StreamType::Logs),
+                SchemaCache::new(logs_schema),
+            );
+            w.insert(
+                format!("{org_id}/{}/default", StreamType::Traces),
+                SchemaCache::new(traces_schema),
+            );
+        }
+
+        // the unqualified stream is searched with the request's stream type
+        let query = SearchQuery {
+            sql: r#"SELECT l.log, t.duration FROM "default" AS l JOIN "traces"."default" AS t ON l.trace_id = t.trace_id"#.to_string(),
+            start_time: 0,
+            end_time: 3_600_000_000,
+            ..Default::default()
+        };
+        let sql = Sql::new(&query, org_id, StreamType::Logs).await.unwrap();
+        assert_eq!(sql.schemas.len(), 2);
+        let field_of = |stream_type: StreamType, field: &str| {
+            sql.schemas
+                .iter()
+                .find(|(table, _)| table.get_stream_type(StreamType::Logs) == stream_type)
+                .is_some_and(|(_, schema)| schema.schema().field_with_name(field).is_ok())
+        };
+        assert!(field_of(StreamType::Logs, "log"));
+        assert!(field_of(StreamType::Traces, "duration"));
+        assert!(!field_of(StreamType::Traces, "log"));
+
+        // an unknown qualifier is rejected instead of being searched as logs
+        let query = SearchQuery {
+            sql: r#"SELECT * FROM "trace"."default""#.to_string(),
+            start_time: 0,
+            end_time: 3_600_000_000,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Sql::new(&query, org_id, StreamType::Logs).await,
+            Err(Error::ErrorCode(ErrorCodes::SearchSQLNotValid(_)))
+        ));
+    }
 }
@@ -40,7 +40,7 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::service::search::{
     cluster::flight::{
-        check_work_group, get_inverted_index_file_list, get_online_querier_nodes,
+        check_work_group, get_inverted_index_file_list, get_online_querier_nodes, get_seam_files,
         partition_filt_list,
     },
     datafusion::{
@@ -136,6 +136,16 @@ pub async fn search(
         ..Default::default()
     };
 
+    // get the recently uploaded files, ingesters skip their wal copies
+    let seam_files = get_seam_files(
+        &req.org_id,
+        stream_type,
+        &stream_name,
+        req.time_range,
+        &file_id_list,
+    )
+    .await?;
+
     // 2. get inverted index file list
     let (use_ttv_inverted_index, idx_file_list, idx_scan_size, _idx_took) =
         get_inverted_index_file_lists(
@@ -200,7 +210,11 @@ pub async fn search(
     });
 
     // partition file list
-    let partition_file_lists = partition_filt_list(file_id_list, &nodes, node_group).await?;
+    let cache_latest_files =
+        infra::cache::file_data::memory::cache_latest_files(&req.org_id, stream_type, &stream_name)
+            .await;
+    let partition_file_lists =
+        partition_filt_list(file_id_list, &nodes, node_group, cache_latest_files).await?;
 
     // update search session scan stats
     super::super::SEARCH_SERVER
@@ -217,6 +231,7 @@ pub async fn search(
         plan: vec![],
         file_id_list: partition_file_lists.clone(),
         idx_file_list,
+        seam_files,
         start_time: req.time_range.as_ref().map(|x| x.0).unwrap_or(0),
         end_time: req.time_range.as_ref().map(|x| x.1).unwrap_or(0),
         timeout: req.timeout as u64,
@@ -19,7 +19,11 @@ use arrow::array::RecordBatch;
 use async_recursion::async_recursion;
 use config::{
     get_config,
-    meta::{cluster::NodeInfo, search::ScanStats, sql::TableReferenceExt},
+    meta::{
+        cluster::NodeInfo,
+        search::{ExecutionStats, ScanStats},
+        sql::TableReferenceExt,
+    },
     utils::json,
 };
 use datafusion::{
@@ -36,13 +40,18 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::service::search::{
     cluster::flight::{generate_context, register_table},
-    datafusion::distributed_plan::{
-        remote_scan::RemoteScanExec,
-        rewrite::{RemoteScanRewriter, StreamingAggsRewriter},
+    datafusion::{
+        distributed_plan::{
+            remote_scan::RemoteScanExec,
+            rewrite::{RemoteScanRewriter, StreamingAggsRewriter},
+        },
+        peak_memory::get_peak_memory,
+        pruning::collect_pruning_stats,
     },
+    get_query_timeout,
     request::Request,
     sql::Sql,
-    utils::ScanStatsVisitor,
+    utils::{AbortOnDrop, ScanStatsVisitor},
     DATAFUSION_RUNTIME,
 };
 
@@ -59,16 +68,19 @@ pub async fn search(
     _query: cluster_rpc::SearchQuery,
     req_regions: Vec<String>,
     req_clusters: Vec<String>,
-) -> Result<(Vec<RecordBatch>, ScanStats, usize, bool, usize, String)> {
+) -> Result<(
+    Vec<RecordBatch>,
+    ScanStats,
+    usize,
+    bool,
+    usize,
+    String,
+    ExecutionStats,
+)> {
     let _start = std::time::Instant::now();
-    let cfg = get_config();
     log::info!("[trace_id {trace_id}] super cluster leader: start {}", sql);
 
-    let timeout = if req.timeout > 0 {
-        req.timeout as u64
-    } else {
-        cfg.limit.query_timeout
-    };
+    let timeout = get_query_timeout(req.timeout);
     req.timeout = timeout as _;
 
     if sql
@@ -76,7 +88,15 @@ pub async fn search(
         .iter()
         .any(|(_, schema)| schema.schema().fields().is_empty())
     {
-        return Ok((vec![], ScanStats::new(), 0, false, 0, "".to_string()));
+        return Ok((
+            vec![],
+            ScanStats::new(),
+            0,
+            false,
+            0,
+            "".to_string(),
+            ExecutionStats::default(),
+        ));
     }
 
     let (use_inverted_index, _) = super::super::is_use_inverted_index(&sql);
@@ -120,6 +140,8 @@ pub async fn search(
             .instrument(datafusion_span)
             .await
     });
+    // stop the datafusion task if this search is dropped, e.g. the client cancelled it
+    let _query_task_guard = AbortOnDrop::new(query_task.abort_handle());
     tokio::pin!(query_task);
 
     let task = tokio::select! {
@@ -154,7 +176,7 @@ pub async fn search(
             _ => Err(Error::Message(err.to_string())),
         },
     };
-    let (data, mut scan_stats, partial_err) = match data {
+    let (data, mut scan_stats, partial_err, execution_stats) = match data {
         Ok(v) => v,
         Err(e) => {
             return Err(e);
@@ -164,7 +186,15 @@ pub async fn search(
     log::info!("[trace_id {trace_id}] super cluster leader: search finished");
 
     scan_stats.format_to_mb();
-    Ok((data, scan_stats, 0, !partial_err.is_empty(), 0, partial_err))
+    Ok((
+        data,
+        scan_stats,
+        0,
+        !partial_err.is_empty(),
+        0,
+        partial_err,
+        execution_stats,
+    ))
 }
 
 async fn run_datafusion(
@@ -172,7 +202,7 @@ async fn run_datafusion(
     req: Request,
     sql: Arc<Sql>,
     nodes: Vec<Arc<dyn NodeInfo>>,
-) -> Result<(Vec<RecordBatch>, ScanStats, String)> {
+) -> Result<(Vec<RecordBatch>, ScanStats, String, ExecutionStats)> {
     let cfg = get_config();
     // construct physical plan
     let ctx = match generate_context(&req, &sql, cfg.limit.cpu_num).await {
@@ -235,6 +265,7 @@ async fn run_datafusion(
         nodes,
         HashMap::new(),
         Vec::new(),
+        HashMap::new(),
         partition_keys,
         match_all_keys,
         sql.index_condition.clone(),
@@ -277,12 +308,14 @@ async fn run_datafusion(
     let ret = datafusion::physical_plan::collect(physical_plan.clone(), ctx.task_ctx()).await;
     let mut visit = ScanStatsVisitor::new();
     let _ = visit_execution_plan(physical_plan.as_ref(), &mut visit);
+    let (_, mut execution_stats) = collect_pruning_stats(physical_plan.as_ref());
+    execution_stats.peak_memory += get_peak_memory(&ctx);
     if let Err(e) = ret {
         log::error!("[trace_id {trace_id}] super cluster leader: datafusion collect error: {e}");
         Err(e.into())
     } else {
         log::info!("[trace_id {trace_id}] super cluster leader: datafusion collect done");
-        ret.map(|data| (data, visit.scan_stats, visit.partial_err))
+        ret.map(|data| (data, visit.scan_stats, visit.partial_err, execution_stats))
             .map_err(|e| e.into())
     }
 }
@@ -51,6 +51,21 @@ impl Drop for AsyncDefer {
     }
 }
 
+/// Aborts the task when dropped, so a spawned search stops once its caller goes away.
+pub struct AbortOnDrop(tokio::task::AbortHandle);
+
+impl AbortOnDrop {
+    pub fn new(handle: tokio::task::AbortHandle) -> Self {
+        AbortOnDrop(handle)
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 #[derive(Debug)]
 pub struct ScanStatsVisitor {
     pub scan_stats: ScanStats,
@@ -19,25 +19,90 @@ use config::{
     get_config,
     meta::{
         search::SearchEventType,
-        self_reporting::{
-            usage::{AggregatedData, GroupKey, UsageData, UsageEvent, USAGE_STREAM},
-            ReportingData,
-        },
+        self_reporting::usage::{AggregatedData, GroupKey, UsageData, UsageEvent, USAGE_STREAM},
         stream::{StreamParams, StreamType},
     },
     utils::json,
     META_ORG_ID,
 };
-use hashbrown::HashMap;
+use hashbrown::{hash_map::Entry, HashMap};
+use once_cell::sync::Lazy;
 use proto::cluster_rpc;
 
 use crate::{common::meta::ingestion, service};
 
+/// Client for `ZO_USAGE_REPORTING_URL`. Requests time out so an unresponsive
+/// endpoint can't hold up the reporting threads.
+static REMOTE_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    let timeout = std::cmp::max(1, get_config().common.usage_publish_interval) as u64;
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(timeout))
+        .build()
+        .unwrap()
+});
+
+/// Folds `usage_data` into the aggregate of its group, summing the number of
+/// records, size, compressed size and response time of every event sharing the
+/// same key and keeping the earliest `min_ts` and latest `max_ts`. Events of
+/// different users are only kept apart when `by_user` is set.
+fn aggregate_usage(
+    groups: &mut HashMap<GroupKey, AggregatedData>,
+    usage_data: &UsageData,
+    by_user: bool,
+) {
+    let email = if by_user {
+        usage_data.user_email.clone()
+    } else {
+        String::new()
+    };
+    let key = GroupKey {
+        stream_name: usage_data.stream_name.clone(),
+        org_id: usage_data.org_id.clone(),
+        stream_type: usage_data.stream_type,
+        day: usage_data.day,
+        hour: usage_data.hour,
+        event: usage_data.event,
+        email: email.clone(),
+        node: usage_data.node_name.clone().unwrap_or_default(),
+        synthetic: usage_data.synthetic.unwrap_or_default(),
+        function_name: usage_data.function_name.clone().unwrap_or_default(),
+    };
+    match groups.entry(key) {
+        Entry::Occupied(mut entry) => {
+            let entry = entry.get_mut();
+            entry.usage_data.num_records += usage_data.num_records;
+            entry.usage_data.size += usage_data.size;
+            entry.usage_data.response_time += usage_data.response_time;
+            if let Some(compressed_size) = usage_data.compressed_size {
+                *entry.usage_data.compressed_size.get_or_insert(0.0) += compressed_size;
+            }
+            entry.usage_data.min_ts = match (entry.usage_data.min_ts, usage_data.min_ts) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            entry.usage_data.max_ts = match (entry.usage_data.max_ts, usage_data.max_ts) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            entry.count += 1;
+        }
+        Entry::Vacant(entry) => {
+            let mut usage_data = usage_data.clone();
+            usage_data.user_email = email;
+            entry.insert(AggregatedData {
+                count: 1,
+                usage_data,
+            });
+        }
+    }
+}
+
 pub(super) async fn ingest_usages(mut curr_usages: Vec<UsageData>) {
     if curr_usages.is_empty() {
         log::info!("[SELF-REPORTING] Returning as no usages reported ");
         return;
     }
+    let by_user = get_config().common.usage_report_by_user;
     let mut groups: HashMap<GroupKey, AggregatedData> = HashMap::new();
     let mut search_events = vec![];
     for usage_data in curr_usages.iter_mut() {
@@ -70,32 +135,7 @@ pub(super) async fn ingest_usages(mut curr_usages: Vec<UsageData>) {
             search_events.push(usage_data.clone());
             continue;
         }
-        let node = usage_data.node_name.clone().unwrap_or_default();
-        let key = GroupKey {
-            stream_name: usage_data.stream_name.clone(),
-            org_id: usage_data.org_id.clone(),
-            stream_type: usage_data.stream_type,
-            day: usage_data.day,
-            hour: usage_data.hour,
-            event: usage_data.event,
-            email: usage_data.user_email.clone(),
-            node,
-        };
-
-        let is_new = groups.contains_key(&key);
-
-        let entry = groups.entry(key).or_insert_with(|| AggregatedData {
-            count: 1,
-            usage_data: usage_data.clone(),
-        });
-        if !is_new {
-            continue;
-        } else {
-            entry.usage_data.num_records += usage_data.num_records;
-            entry.usage_data.size += usage_data.size;
-            entry.usage_data.response_time += usage_data.response_time;
-            entry.count += 1;
-        }
+        aggregate_usage(&mut groups, usage_data, by_user);
     }
 
     let mut report_data = vec![];
@@ -108,99 +148,62 @@ pub(super) async fn ingest_usages(mut curr_usages: Vec<UsageData>) {
     // Push all the search events
     report_data.append(&mut search_events);
     let cfg = get_config();
-    if &cfg.common.usage_reporting_mode != "local"
-        && !cfg.common.usage_reporting_url.is_empty()
-        && !cfg.common.usage_reporting_creds.is_empty()
-    {
-        let url = url::Url::parse(&cfg.common.usage_reporting_url).unwrap();
+    if &cfg.common.usage_reporting_mode != "local" {
+        if let Err(e) = send_remote_usages(&report_data).await {
+            log::error!(
+                "[SELF-REPORTING] Error in ingesting usage data to external URL: {e}, will retry"
+            );
+            // retried separately so the local ingestion isn't repeated in `both` mode
+            super::queues::enqueue_remote_retry(report_data.clone());
+        }
+    }
+
+    if &cfg.common.usage_reporting_mode != "remote" {
+        if let Err(e) = ingest_usage_batch(&report_data).await {
+            log::error!("[SELF-REPORTING] Error in ingesting usage data: {e}, will retry");
+            // retried with backoff by a dedicated job instead of going through the queue again
+            super::queues::enqueue_local_retry(report_data);
+        }
+    }
+}
+
+/// Ingests aggregated usage data into the usage stream of the meta org.
+pub(super) async fn ingest_usage_batch(report_data: &[UsageData]) -> Result<()> {
+    let report_data = report_data
+        .iter()
+        .map(|usage| json::to_value(usage).unwrap())
+        .collect::<Vec<_>>();
+    let usage_stream = StreamParams::new(META_ORG_ID, USAGE_STREAM, StreamType::Logs);
+    ingest_reporting_data(report_data, usage_stream).await
+}
+
+/// Posts aggregated usage data to `ZO_USAGE_REPORTING_URL`.
+pub(super) async fn send_remote_usages(report_data: &[UsageData]) -> Result<()> {
+    let cfg = get_config();
+    let url = url::Url::parse(&cfg.common.usage_reporting_url)?;
+    let mut req = REMOTE_CLIENT
+        .post(url)
+        .header("Content-Type", "application/json")
+        .json(report_data);
+    if !cfg.common.usage_reporting_creds.is_empty() {
         let creds = if cfg.common.usage_reporting_creds.starts_with("Basic") {
             cfg.common.usage_reporting_creds.to_string()
         } else {
             format!("Basic {}", &cfg.common.usage_reporting_creds)
         };
-        match reqwest::Client::builder()
-            .build()
-            .unwrap()
-            .post(url)
-            .header("Content-Type", "application/json")
-            .header(reqwest::header::AUTHORIZATION, creds)
-            .json(&report_data)
-            .send()
-            .await
-        {
-            Ok(resp) => {
-                let resp_status = resp.status();
-                if !resp_status.is_success() {
-                    log::error!(
-                        "[SELF-REPORTING] Error in ingesting usage data to external URL: {}",
-                        resp.text()
-                            .await
-                            .unwrap_or_else(|_| resp_status.to_string())
-                    );
-                    if &cfg.common.usage_reporting_mode != "both" {
-                        // on error in ingesting usage data, push back the data
-                        let curr_usages = curr_usages.clone();
-                        for usage_data in curr_usages {
-                            if let Err(e) = super::queues::USAGE_QUEUE
-                                .enqueue(ReportingData::Usage(Box::new(usage_data)))
-                                .await
-                            {
-                                log::error!(
-                                    "[SELF-REPORTING] Error in pushing back un-ingested Usage data to UsageQueuer: {e}"
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                log::error!(
-                    "[SELF-REPORTING] Error in ingesting usage data to external URL {:?}",
-                    e
-                );
-                if &cfg.common.usage_reporting_mode != "both" {
-                    // on error in ingesting usage data, push back the data
-                    let curr_usages = curr_usages.clone();
-                    for usage_data in curr_usages {
-                        if let Err(e) = super::queues::USAGE_QUEUE
-                            .enqueue(ReportingData::Usage(Box::new(usage_data)))
-                            .await
-                        {
-                            log::error!(
-                                "[SELF-REPORTING] Error in pushing back un-ingested Usage data to UsageQueuer: {e}"
-                            );
-                        }
-                    }
-                }
-            }
-        }
+        req = req.header(reqwest::header::AUTHORIZATION, creds);
     }
 
-    if &cfg.common.usage_reporting_mode != "remote" {
-        let report_data = report_data
-            .iter_mut()
-            .map(|usage| json::to_value(usage).unwrap())
-            .collect::<Vec<_>>();
-        // report usage data
-        let usage_stream = StreamParams::new(META_ORG_ID, USAGE_STREAM, StreamType::Logs);
-        if ingest_reporting_data(report_data, usage_stream)
+    let resp = req.send().await?;
+    let resp_status = resp.status();
+    if !resp_status.is_success() {
+        let body = resp
+            .text()
             .await
-            .is_err()
-            && &cfg.common.usage_reporting_mode != "both"
-        {
-            // on error in ingesting usage data, push back the data
-            for usage_data in curr_usages {
-                if let Err(e) = super::queues::USAGE_QUEUE
-                    .enqueue(ReportingData::Usage(Box::new(usage_data)))
-                    .await
-                {
-                    log::error!(
-                        "[SELF-REPORTING] Error in pushing back un-ingested Usage data to UsageQueuer: {e}"
-                    );
-                }
-            }
-        }
+            .unwrap_or_else(|_| resp_status.to_string());
+        return Err(anyhow!("{resp_status}: {body}"));
     }
+    Ok(())
 }
 
 pub(super) async fn ingest_reporting_data(
@@ -270,3 +273,187 @@ pub(super) async fn ingest_reporting_data(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(num_records: i64, size: f64, response_time: f64) -> UsageData {
+        UsageData {
+            _timestamp: 1_700_000_000_000_000,
+            event: UsageEvent::Ingestion,
+            year: 2023,
+            month: 11,
+            day: 14,
+            hour: 22,
+            event_time_hour: "2023111422".to_string(),
+            org_id: "default".to_string(),
+            request_body: "".to_string(),
+            size,
+            unit: "MB".to_string(),
+            user_email: "root@example.com".to_string(),
+            response_time,
+            stream_type: StreamType::Logs,
+            num_records,
+            dropped_records: 0,
+            stream_name: "default".to_string(),
+            trace_id: None,
+            cached_ratio: None,
+            compressed_size: None,
+            min_ts: None,
+            max_ts: None,
+            search_type: None,
+            search_event_context: None,
+            took_wait_in_queue: None,
+            result_cache_ratio: None,
+            function: None,
+            is_partial: false,
+            work_group: None,
+            node_name: Some("node-1".to_string()),
+            sampled_from: None,
+            scan_files: None,
+            peak_memory: None,
+            num_nodes: None,
+            synthetic: None,
+            function_name: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_usage_sums_events_with_same_key() {
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        aggregate_usage(&mut groups, &usage(20, 2.0, 1.0), true);
+        aggregate_usage(&mut groups, &usage(30, 3.0, 1.5), true);
+
+        assert_eq!(groups.len(), 1);
+        let data = groups.values().next().unwrap();
+        assert_eq!(data.count, 3);
+        assert_eq!(data.usage_data.num_records, 60);
+        assert_eq!(data.usage_data.size, 6.0);
+        assert_eq!(data.usage_data.response_time, 3.0);
+    }
+
+    #[test]
+    fn test_aggregate_usage_sums_compressed_size_and_time_range() {
+        let mut first = usage(10, 1.0, 0.5);
+        first.min_ts = Some(200);
+        first.max_ts = Some(300);
+        let mut second = usage(20, 2.0, 1.0);
+        second.compressed_size = Some(0.5);
+        second.min_ts = Some(100);
+        second.max_ts = Some(250);
+        let mut third = usage(30, 3.0, 1.5);
+        third.compressed_size = Some(0.25);
+        third.max_ts = Some(400);
+
+        let mut groups = HashMap::new();
+        for usage_data in [&first, &second, &third] {
+            aggregate_usage(&mut groups, usage_data, true);
+        }
+
+        assert_eq!(groups.len(), 1);
+        let data = groups.values().next().unwrap();
+        assert_eq!(data.count, 3);
+        assert_eq!(data.usage_data.num_records, 60);
+        assert_eq!(data.usage_data.compressed_size, Some(0.75));
+        assert_eq!(data.usage_data.min_ts, Some(100));
+        assert_eq!(data.usage_data.max_ts, Some(400));
+
+        // events without compressed size don't report one
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        aggregate_usage(&mut groups, &usage(20, 2.0, 1.0), true);
+        let data = groups.values().next().unwrap();
+        assert_eq!(data.usage_data.compressed_size, None);
+        assert_eq!(data.usage_data.min_ts, None);
+    }
+
+    #[test]
+    fn test_aggregate_usage_keeps_distinct_keys_apart() {
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        let mut other = usage(20, 2.0, 1.0);
+        other.stream_name = "other".to_string();
+        aggregate_usage(&mut groups, &other, true);
+
+        assert_eq!(groups.len(), 2);
+        assert!(groups.values().all(|data| data.count == 1));
+    }
+
+    #[test]
+    fn test_aggregate_usage_by_user() {
+        let mut other_user = usage(20, 2.0, 1.0);
+        other_user.user_email = "admin@example.com".to_string();
+
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        aggregate_usage(&mut groups, &other_user, true);
+        assert_eq!(groups.len(), 2);
+        let mut emails = groups
+            .values()
+            .map(|data| data.usage_data.user_email.as_str())
+            .collect::<Vec<_>>();
+        emails.sort();
+        assert_eq!(emails, vec!["admin@example.com", "root@example.com"]);
+
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), false);
+        aggregate_usage(&mut groups, &other_user, false);
+        assert_eq!(groups.len(), 1);
+        let data = groups.values().next().unwrap();
+        assert_eq!(data.count, 2);
+        assert_eq!(data.usage_data.num_records, 30);
+        assert_eq!(data.usage_data.user_email, "");
+    }
+
+    #[test]
+    fn test_aggregate_usage_keeps_sample_tag() {
+        let mut sample = usage(5, 0.5, 0.1);
+        sample.stream_name = "default_sampled".to_string();
+        sample.sampled_from = Some("default".to_string());
+
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        aggregate_usage(&mut groups, &sample, true);
+        aggregate_usage(&mut groups, &sample, true);
+        assert_eq!(groups.len(), 2);
+
+        let sampled = groups
+            .values()
+            .find(|data| data.usage_data.stream_name == "default_sampled")
+            .unwrap();
+        assert_eq!(sampled.usage_data.num_records, 10);
+        assert_eq!(sampled.usage_data.sampled_from.as_deref(), Some("default"));
+        let value = json::to_value(&sampled.usage_data).unwrap();
+        assert_eq!(value["sampled_from"], "default");
+
+        let source = groups
+            .values()
+            .find(|data| data.usage_data.stream_name == "default")
+            .unwrap();
+        assert!(source.usage_data.sampled_from.is_none());
+        let value = json::to_value(&source.usage_data).unwrap();
+        assert!(value.get("sampled_from").is_none());
+    }
+
+    #[test]
+    fn test_aggregate_usage_keeps_synthetic_apart() {
+        let mut synthetic = usage(5, 0.5, 0.1);
+        synthetic.synthetic = Some(true);
+
+        let mut groups = HashMap::new();
+        aggregate_usage(&mut groups, &usage(10, 1.0, 0.5), true);
+        aggregate_usage(&mut groups, &synthetic, true);
+        aggregate_usage(&mut groups, &synthetic, true);
+        assert_eq!(groups.len(), 2);
+
+        let tagged = groups
+            .values()
+            .find(|data| data.usage_data.synthetic == Some(true))
+            .unwrap();
+        assert_eq!(tagged.usage_data.num_records, 10);
+        let value = json::to_value(&tagged.usage_data).unwrap();
+        assert_eq!(value["synthetic"], true);
+    }
+}
@@ -38,6 +38,17 @@ use tokio::sync::oneshot;
 mod ingestion;
 mod queues;
 
+tokio::task_local! {
+    /// Set while a synthetic data generator writes, so the usage of the data
+    /// it ingests is tagged as synthetic
+    pub static SYNTHETIC: bool;
+}
+
+/// Whether the current task ingests data of a synthetic data generator
+pub fn is_synthetic() -> bool {
+    SYNTHETIC.try_with(|v| *v).unwrap_or(false)
+}
+
 pub async fn run() {
     let cfg = get_config();
     if !cfg.common.usage_enabled {
@@ -68,6 +79,8 @@ pub async fn run() {
         return;
     }
 
+    queues::start_retry_jobs();
+
     log::debug!("[SELF-REPORTING] successfully initialized reporting queues");
 }
 
@@ -95,6 +108,7 @@ pub async fn report_request_usage_stats(
 
     let request_body = stats.request_body.unwrap_or(usage_type.to_string());
     let user_email = stats.user_email.unwrap_or("".to_owned());
+    let synthetic = is_synthetic().then_some(true);
 
     let mut usage = vec![];
 
@@ -136,10 +150,17 @@ pub async fn report_request_usage_stats(
             is_partial: stats.is_partial,
             work_group: None,
             node_name: stats.node_name.clone(),
+            sampled_from: stats.sampled_from.clone(),
+            scan_files: None,
+            peak_memory: None,
+            num_nodes: None,
+            synthetic,
+            function_name: None,
         });
     };
 
-    usage.push(UsageData {
+    let function_names = stats.function_names.unwrap_or_default();
+    let data = UsageData {
         _timestamp: timestamp,
         event,
         day: now.day(),
@@ -176,12 +197,40 @@ pub async fn report_request_usage_stats(
         is_partial: stats.is_partial,
         work_group: stats.work_group,
         node_name: stats.node_name,
-    });
+        sampled_from: stats.sampled_from,
+        scan_files: stats.scan_files,
+        peak_memory: stats.peak_memory,
+        num_nodes: stats.num_nodes,
+        synthetic,
+        function_name: None,
+    };
+    usage.extend(function_usages(&data, &function_names));
+    usage.push(data);
     if !usage.is_empty() {
         publish_usage(usage).await;
     }
 }
 
+/// Builds one `FunctionCall` row per function from the usage row of the
+/// request, so function usage can be attributed to each function. The rows
+/// count calls only, the size and records of the request stay on its own row
+/// so they are not summed once per function.
+fn function_usages(usage: &UsageData, function_names: &[String]) -> Vec<UsageData> {
+    function_names
+        .iter()
+        .map(|name| UsageData {
+            event: UsageEvent::FunctionCall,
+            function: None,
+            function_name: Some(name.to_owned()),
+            size: 0.0,
+            num_records: 0,
+            dropped_records: 0,
+            compressed_size: None,
+            ..usage.clone()
+        })
+        .collect()
+}
+
 async fn publish_usage(usages: Vec<UsageData>) {
     let cfg = get_config();
     if !cfg.common.usage_enabled {
@@ -328,3 +377,64 @@ pub fn http_report_metrics(
         .with_label_values(&[&uri, code, org_id, stream_name, stream_type.as_str()])
         .inc();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_usages_per_function() {
+        let now = DateTime::from_timestamp_micros(1_700_000_000_000_000).unwrap();
+        let usage = UsageData {
+            _timestamp: now.timestamp_micros(),
+            event: UsageEvent::Search,
+            day: now.day(),
+            hour: now.hour(),
+            month: now.month(),
+            year: now.year(),
+            event_time_hour: "2023111422".to_owned(),
+            org_id: "default".to_owned(),
+            request_body: "select * from logs".to_owned(),
+            size: 1.5,
+            unit: "MB".to_owned(),
+            user_email: "root@example.com".to_owned(),
+            response_time: 0.2,
+            function: Some(".a = 1".to_owned()),
+            num_records: 10,
+            dropped_records: 0,
+            stream_type: StreamType::Logs,
+            stream_name: "logs".to_owned(),
+            min_ts: None,
+            max_ts: None,
+            cached_ratio: None,
+            compressed_size: None,
+            search_type: None,
+            search_event_context: None,
+            trace_id: None,
+            took_wait_in_queue: None,
+            result_cache_ratio: None,
+            is_partial: false,
+            work_group: None,
+            node_name: None,
+            sampled_from: None,
+            scan_files: None,
+            peak_memory: None,
+            num_nodes: None,
+            synthetic: None,
+            function_name: None,
+        };
+
+        let rows = function_usages(&usage, &["geo_ip".to_owned(), "mask".to_owned()]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].function_name.as_deref(), Some("geo_ip"));
+        assert_eq!(rows[1].function_name.as_deref(), Some("mask"));
+        for row in rows {
+            assert_eq!(row.event, UsageEvent::FunctionCall);
+            assert_eq!(row.num_records, 0);
+            assert_eq!(row.size, 0.0);
+            assert_eq!(row.user_email, usage.user_email);
+            assert!(row.function.is_none());
+        }
+        assert!(function_usages(&usage, &[]).is_empty());
+    }
+}
@@ -13,14 +13,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
 use config::{
     get_config,
     meta::{
         self_reporting::{
             error::ErrorData,
-            usage::{TriggerData, ERROR_STREAM, TRIGGERS_USAGE_STREAM},
+            usage::{TriggerData, UsageData, ERROR_STREAM, TRIGGERS_USAGE_STREAM},
             ReportingData, ReportingMessage, ReportingQueue, ReportingRunner,
         },
         stream::{StreamParams, StreamType},
@@ -30,7 +30,7 @@ use config::{
 };
 use once_cell::sync::Lazy;
 use tokio::{
-    sync::{mpsc, Mutex},
+    sync::{mpsc, Mutex, Notify},
     time,
 };
 
@@ -40,6 +40,168 @@ pub(super) static USAGE_QUEUE: Lazy<Arc<ReportingQueue>> =
 pub(super) static ERROR_QUEUE: Lazy<Arc<ReportingQueue>> =
     Lazy::new(|| Arc::new(initialize_error_queue()));
 
+/// Batches of aggregated usage data that couldn't be sent to
+/// `ZO_USAGE_REPORTING_URL`, oldest first.
+static REMOTE_RETRY_QUEUE: Lazy<Arc<parking_lot::Mutex<VecDeque<Vec<UsageData>>>>> =
+    Lazy::new(|| Arc::new(parking_lot::Mutex::new(VecDeque::new())));
+
+/// Batches of aggregated usage data that couldn't be ingested into the usage
+/// stream, oldest first.
+static LOCAL_RETRY_QUEUE: Lazy<Arc<parking_lot::Mutex<VecDeque<Vec<UsageData>>>>> =
+    Lazy::new(|| Arc::new(parking_lot::Mutex::new(VecDeque::new())));
+
+/// Wakes up the local retry job once `ZO_USAGE_BATCH_SIZE` records are pending.
+static LOCAL_RETRY_NOTIFY: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Upper bound of the retry backoff, as a power of two of
+/// `ZO_USAGE_PUBLISH_INTERVAL`.
+const MAX_BACKOFF_EXPONENT: u32 = 6;
+
+/// Queues `report_data` to be sent to `ZO_USAGE_REPORTING_URL` again later.
+/// The queue is bounded by `ZO_USAGE_REPORTING_RETRY_QUEUE_SIZE` batches.
+pub(super) fn enqueue_remote_retry(report_data: Vec<UsageData>) {
+    let max_batches = get_config().common.usage_reporting_retry_queue_size;
+    let dropped = push_bounded(&mut REMOTE_RETRY_QUEUE.lock(), report_data, max_batches);
+    if dropped > 0 {
+        log::error!(
+            "[SELF-REPORTING] Remote usage retry queue is full, dropped {dropped} usage records"
+        );
+    }
+}
+
+/// Queues `report_data` to be ingested into the usage stream again later. The
+/// queue is bounded by `ZO_USAGE_REPORTING_RETRY_QUEUE_SIZE` batches.
+pub(super) fn enqueue_local_retry(report_data: Vec<UsageData>) {
+    let cfg = get_config();
+    let pending = {
+        let mut queue = LOCAL_RETRY_QUEUE.lock();
+        let dropped = push_bounded(
+            &mut queue,
+            report_data,
+            cfg.common.usage_reporting_retry_queue_size,
+        );
+        if dropped > 0 {
+            log::error!(
+                "[SELF-REPORTING] Local usage retry queue is full, dropped {dropped} usage records"
+            );
+        }
+        queue.iter().map(|batch| batch.len()).sum::<usize>()
+    };
+    if pending >= cfg.common.usage_batch_size {
+        LOCAL_RETRY_NOTIFY.notify_one();
+    }
+}
+
+/// Appends `batch` to `queue` and drops the oldest batches beyond
+/// `max_batches`. Returns the number of records dropped.
+fn push_bounded<T>(queue: &mut VecDeque<Vec<T>>, batch: Vec<T>, max_batches: usize) -> usize {
+    queue.push_back(batch);
+    let mut dropped = 0;
+    while queue.len() > max_batches {
+        dropped += queue
+            .pop_front()
+            .map(|batch| batch.len())
+            .unwrap_or_default();
+    }
+    dropped
+}
+
+/// Sends the queued batches in order until one fails. A batch leaves the queue
+/// only once sent, so a failure part way through never resends the batches
+/// that already went out.
+async fn drain_retry_queue<T, F, Fut>(
+    queue: &parking_lot::Mutex<VecDeque<Vec<T>>>,
+    mut send: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), (Vec<T>, anyhow::Error)>>,
+{
+    loop {
+        let Some(batch) = queue.lock().pop_front() else {
+            return Ok(());
+        };
+        if let Err((batch, e)) = send(batch).await {
+            queue.lock().push_front(batch);
+            return Err(e);
+        }
+    }
+}
+
+/// Delay before the next retry after `failures` consecutive failed attempts.
+fn retry_backoff(interval: time::Duration, failures: u32) -> time::Duration {
+    interval * 2u32.pow(failures.min(MAX_BACKOFF_EXPONENT))
+}
+
+/// Starts the jobs draining the remote and local usage retry queues.
+pub(super) fn start_retry_jobs() {
+    tokio::task::spawn(async move { retry_remote_usages_job().await });
+    tokio::task::spawn(async move { retry_local_usages_job().await });
+}
+
+/// Resends the queued usage batches every `ZO_USAGE_PUBLISH_INTERVAL`, in the
+/// order they failed, until the remote endpoint rejects one again.
+async fn retry_remote_usages_job() {
+    let interval = std::cmp::max(1, get_config().common.usage_publish_interval) as u64;
+    let mut interval = time::interval(time::Duration::from_secs(interval));
+    interval.tick().await; // the first tick completes immediately
+    loop {
+        interval.tick().await;
+        let ret = drain_retry_queue(&REMOTE_RETRY_QUEUE, |batch| async move {
+            match super::ingestion::send_remote_usages(&batch).await {
+                Ok(()) => Ok(()),
+                Err(e) => Err((batch, e)),
+            }
+        })
+        .await;
+        if let Err(e) = ret {
+            log::error!(
+                "[SELF-REPORTING] Error in retrying usage data to external URL: {e}, {} batches pending",
+                REMOTE_RETRY_QUEUE.lock().len()
+            );
+        }
+    }
+}
+
+/// Ingests the queued usage batches every `ZO_USAGE_PUBLISH_INTERVAL`, or as
+/// soon as `ZO_USAGE_BATCH_SIZE` records are pending, independently of new
+/// usage events. Consecutive failures back off exponentially so a struggling
+/// ingester isn't hammered.
+async fn retry_local_usages_job() {
+    let interval = std::cmp::max(1, get_config().common.usage_publish_interval) as u64;
+    let interval = time::Duration::from_secs(interval);
+    let mut failures: u32 = 0;
+    loop {
+        let delay = retry_backoff(interval, failures);
+        if failures == 0 {
+            tokio::select! {
+                _ = time::sleep(delay) => {}
+                _ = LOCAL_RETRY_NOTIFY.notified() => {}
+            }
+        } else {
+            time::sleep(delay).await;
+        }
+        let ret = drain_retry_queue(&LOCAL_RETRY_QUEUE, |batch| async move {
+            match super::ingestion::ingest_usage_batch(&batch).await {
+                Ok(()) => Ok(()),
+                Err(e) => Err((batch, e)),
+            }
+        })
+        .await;
+        match ret {
+            Ok(()) => failures = 0,
+            Err(e) => {
+                failures = failures.saturating_add(1);
+                log::error!(
+                    "[SELF-REPORTING] Error in retrying usage data ingestion: {e}, {} batches pending, next attempt in {:?}",
+                    LOCAL_RETRY_QUEUE.lock().len(),
+                    retry_backoff(interval, failures)
+                );
+            }
+        }
+    }
+}
+
 fn initialize_usage_queue() -> ReportingQueue {
     let cfg = get_config();
     let timeout = time::Duration::from_secs(
@@ -221,3 +383,62 @@ async fn ingest_buffered_data(thread_id: usize, buffered: Vec<ReportingData>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_bounded() {
+        let mut queue = VecDeque::new();
+        assert_eq!(push_bounded(&mut queue, vec![1, 2], 2), 0);
+        assert_eq!(push_bounded(&mut queue, vec![3], 2), 0);
+        // the oldest batch is dropped
+        assert_eq!(push_bounded(&mut queue, vec![4, 5, 6], 2), 2);
+        assert_eq!(queue, VecDeque::from(vec![vec![3], vec![4, 5, 6]]));
+
+        // nothing is kept without room for retries
+        let mut queue = VecDeque::new();
+        assert_eq!(push_bounded(&mut queue, vec![1, 2, 3], 0), 3);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let interval = time::Duration::from_secs(10);
+        assert_eq!(retry_backoff(interval, 0), interval);
+        assert_eq!(retry_backoff(interval, 1), interval * 2);
+        assert_eq!(retry_backoff(interval, 3), interval * 8);
+        assert_eq!(
+            retry_backoff(interval, 100),
+            interval * 2u32.pow(MAX_BACKOFF_EXPONENT)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drain_retry_queue_ingests_each_row_once() {
+        let queue = parking_lot::Mutex::new(VecDeque::from(vec![vec![1, 2], vec![3], vec![4, 5]]));
+        let ingested = parking_lot::Mutex::new(Vec::new());
+        let attempts = std::sync::atomic::AtomicUsize::new(0);
+        let send = |batch: Vec<i32>| {
+            let attempt = attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let ingested = &ingested;
+            async move {
+                // the second batch fails the first time it is sent
+                if attempt == 1 {
+                    return Err((batch, anyhow::anyhow!("ingester unavailable")));
+                }
+                ingested.lock().extend(batch);
+                Ok(())
+            }
+        };
+
+        assert!(drain_retry_queue(&queue, send).await.is_err());
+        assert_eq!(*ingested.lock(), vec![1, 2]);
+        assert_eq!(queue.lock().len(), 2);
+
+        assert!(drain_retry_queue(&queue, send).await.is_ok());
+        assert_eq!(*ingested.lock(), vec![1, 2, 3, 4, 5]);
+        assert!(queue.lock().is_empty());
+    }
+}
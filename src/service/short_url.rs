@@ -14,10 +14,14 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use chrono::Utc;
-use config::{get_config, utils::md5};
+use config::{
+    get_config,
+    meta::short_url::{ListShortUrlsResponse, ShortUrlItem},
+    utils::md5,
+};
 use infra::{
     errors::{DbError, Error},
-    table::short_urls::ShortUrlRecord,
+    table::short_urls::{self, ShortUrlRecord},
 };
 
 use crate::service::db;
@@ -46,7 +50,7 @@ async fn store_short_url(
     original_url: &str,
 ) -> Result<String, anyhow::Error> {
     let entry = ShortUrlRecord::new(short_id, original_url);
-    db::short_url::set(short_id, entry).await?;
+    db::short_url::set(org_id, short_id, entry).await?;
     Ok(construct_short_url(org_id, short_id))
 }
 
@@ -92,7 +96,45 @@ pub async fn shorten(org_id: &str, original_url: &str) -> Result<String, anyhow:
 
 /// Retrieves the original URL corresponding to the given short ID
 pub async fn retrieve(short_id: &str) -> Option<String> {
-    db::short_url::get(short_id).await.ok()
+    let original_url = db::short_url::get(short_id).await.ok()?;
+    let short_id = short_id.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = db::short_url::record_access(&short_id).await {
+            log::error!("Failed to record access of short URL {short_id}: {e}");
+        }
+    });
+    Some(original_url)
+}
+
+/// Lists a page of the short URLs of the org, newest first.
+pub async fn list(
+    org_id: &str,
+    page_size: u64,
+    page_idx: u64,
+) -> Result<ListShortUrlsResponse, anyhow::Error> {
+    let (items, total) = short_urls::list_by_org(org_id, page_size, page_idx).await?;
+    let list = items
+        .into_iter()
+        .map(|item| ShortUrlItem {
+            short_url: construct_short_url(org_id, &item.short_id),
+            id: item.short_id,
+            original_url: item.original_url,
+            created_at: item.created_ts,
+            last_accessed: item.last_accessed,
+        })
+        .collect();
+    Ok(ListShortUrlsResponse { list, total })
+}
+
+/// Deletes the short URL of the org, returning whether it existed.
+pub async fn delete(org_id: &str, short_id: &str) -> Result<bool, anyhow::Error> {
+    db::short_url::delete(org_id, short_id).await
+}
+
+/// Deletes the short URLs of the org that are past the retention period,
+/// returning how many were deleted.
+pub async fn purge_expired(org_id: &str) -> Result<usize, anyhow::Error> {
+    db::short_url::purge_expired(org_id).await
 }
 
 #[cfg(test)]
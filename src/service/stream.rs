@@ -22,8 +22,8 @@ use config::{
     meta::{
         promql,
         stream::{
-            DistinctField, StreamParams, StreamSettings, StreamStats, StreamType,
-            UpdateStreamSettings,
+            DistinctField, PartitionTimeLevel, PatchStreamSettings, StreamParams, StreamSettings,
+            StreamStats, StreamType, UpdateStreamSettings,
         },
     },
     utils::{json, time::now_micros},
@@ -46,7 +46,9 @@ use crate::{
         http::HttpResponse as MetaHttpResponse,
         stream::{Stream, StreamProperty},
     },
-    service::{db, db::distinct_values, metrics::get_prom_metadata_from_schema},
+    service::{
+        compact::retention, db, db::distinct_values, metrics::get_prom_metadata_from_schema,
+    },
 };
 
 const LOCAL: &str = "disk";
@@ -195,12 +197,31 @@ pub async fn save_stream_settings(
     org_id: &str,
     stream_name: &str,
     stream_type: StreamType,
-    mut settings: StreamSettings,
+    settings: StreamSettings,
 ) -> Result<HttpResponse, Error> {
+    match store_stream_settings(org_id, stream_name, stream_type, settings, None).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
+            http::StatusCode::OK.into(),
+            "".to_string(),
+        ))),
+        Err(resp) => Ok(resp),
+    }
+}
+
+/// Validates and stores `settings`, bumping their version. Returns the stored
+/// settings or the error response to send back. When `expected_version` is
+/// given, the stored settings must still be at that version.
+async fn store_stream_settings(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    mut settings: StreamSettings,
+    expected_version: Option<i64>,
+) -> Result<StreamSettings, HttpResponse> {
     let cfg = config::get_config();
     // check if we are allowed to ingest
     if db::compact::retention::is_deleting_stream(org_id, stream_type, stream_name, None) {
-        return Ok(
+        return Err(
             HttpResponse::InternalServerError().json(MetaHttpResponse::error(
                 http::StatusCode::INTERNAL_SERVER_ERROR.into(),
                 format!("stream [{stream_name}] is being deleted"),
@@ -213,7 +234,7 @@ pub async fn save_stream_settings(
         && settings.defined_schema_fields.is_some()
         && !settings.defined_schema_fields.as_ref().unwrap().is_empty()
     {
-        return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+        return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
             http::StatusCode::BAD_REQUEST.into(),
             "only logs stream can have user defined schema".to_string(),
         )));
@@ -222,7 +243,7 @@ pub async fn save_stream_settings(
     // _all field can't setting for inverted index & index field
     for key in settings.full_text_search_keys.iter() {
         if key == &cfg.common.column_all {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 format!("field [{}] can't be used for full text search", key),
             )));
@@ -230,7 +251,7 @@ pub async fn save_stream_settings(
     }
     for key in settings.index_fields.iter() {
         if key == &cfg.common.column_all {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 format!("field [{}] can't be used for secondary index", key),
             )));
@@ -239,7 +260,7 @@ pub async fn save_stream_settings(
 
     for key in settings.partition_keys.iter() {
         if SQL_FULL_TEXT_SEARCH_FIELDS.contains(&key.field) || key.field == cfg.common.column_all {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 format!("field [{}] can't be used for partition key", key.field),
             )));
@@ -250,7 +271,7 @@ pub async fn save_stream_settings(
     let schema = match infra::schema::get(org_id, stream_name, stream_type).await {
         Ok(schema) => schema,
         Err(e) => {
-            return Ok(
+            return Err(
                 HttpResponse::InternalServerError().json(MetaHttpResponse::error(
                     http::StatusCode::INTERNAL_SERVER_ERROR.into(),
                     format!("error in getting schema : {e}"),
@@ -267,13 +288,13 @@ pub async fn save_stream_settings(
     // check the full text search keys must be text field
     for key in settings.full_text_search_keys.iter() {
         let Some(field) = schema_fields.get(key) else {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 format!("field [{}] not found in schema", key),
             )));
         };
         if field.data_type() != &DataType::Utf8 {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 format!("full text search field [{}] must be text field", key),
             )));
@@ -293,7 +314,7 @@ pub async fn save_stream_settings(
     for v in settings.partition_keys.iter() {
         if let Some(old_field) = old_partition_keys.iter_mut().find(|k| k.field == v.field) {
             if old_field.types != v.types {
-                return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                     http::StatusCode::BAD_REQUEST.into(),
                     format!("field [{}] partition types can't be changed", v.field),
                 )));
@@ -305,15 +326,71 @@ pub async fn save_stream_settings(
     }
     settings.partition_keys = old_partition_keys;
 
+    // a rate of 0 turns sampling off, the companion stream is left as is
+    if settings.sampling.as_ref().is_some_and(|s| s.rate <= 0.0) {
+        settings.sampling = None;
+    }
+    if let Some(sampling) = settings.sampling.as_ref() {
+        if stream_type != StreamType::Logs {
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                "only logs stream can be sampled".to_string(),
+            )));
+        }
+        if sampling.rate > 1.0 {
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                "sampling rate must be between 0 and 1".to_string(),
+            )));
+        }
+        if sampling.destination(stream_name) == stream_name {
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                "sampling destination must be a different stream".to_string(),
+            )));
+        }
+    }
+
+    // a ttl of 0 removes it
+    match settings.memory_cache_ttl {
+        Some(ttl) if ttl < 0 => {
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+                http::StatusCode::BAD_REQUEST.into(),
+                "memory cache ttl can't be negative".to_string(),
+            )));
+        }
+        Some(0) => settings.memory_cache_ttl = None,
+        _ => {}
+    }
+
+    if let Err(e) = check_original_data_retention(&settings, cfg.compact.data_retention_days) {
+        return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            http::StatusCode::BAD_REQUEST.into(),
+            e,
+        )));
+    }
+
     for range in settings.extended_retention_days.iter() {
         if range.start > range.end {
-            return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            return Err(HttpResponse::BadRequest().json(MetaHttpResponse::error(
                 http::StatusCode::BAD_REQUEST.into(),
                 "start day should be less than end day".to_string(),
             )));
         }
     }
 
+    let current_version = unwrap_stream_settings(&schema)
+        .map(|s| s.settings_version)
+        .unwrap_or_default();
+    if expected_version.is_some_and(|version| version != current_version) {
+        return Err(HttpResponse::Conflict().json(MetaHttpResponse::error(
+            http::StatusCode::CONFLICT.into(),
+            format!(
+                "stream settings were changed concurrently, current version is {current_version}"
+            ),
+        )));
+    }
+    settings.settings_version = current_version + 1;
     let mut metadata = schema.metadata.clone();
     metadata.insert("settings".to_string(), json::to_string(&settings).unwrap());
     if !metadata.contains_key("created_at") {
@@ -326,10 +403,7 @@ pub async fn save_stream_settings(
         .await
         .unwrap();
 
-    Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
-        http::StatusCode::OK.into(),
-        "".to_string(),
-    )))
+    Ok(settings)
 }
 
 #[tracing::instrument(skip(new_settings))]
@@ -348,6 +422,9 @@ pub async fn update_stream_settings(
             if let Some(store_original_data) = new_settings.store_original_data {
                 settings.store_original_data = store_original_data;
             }
+            if let Some(original_data_retention_days) = new_settings.original_data_retention_days {
+                settings.original_data_retention_days = original_data_retention_days;
+            }
             if let Some(approx_partition) = new_settings.approx_partition {
                 settings.approx_partition = approx_partition;
             }
@@ -360,6 +437,18 @@ pub async fn update_stream_settings(
                 settings.data_retention = data_retention;
             }
 
+            if let Some(sampling) = new_settings.sampling {
+                settings.sampling = Some(sampling);
+            }
+
+            if let Some(cache_latest_files) = new_settings.cache_latest_files {
+                settings.cache_latest_files = Some(cache_latest_files);
+            }
+
+            if let Some(memory_cache_ttl) = new_settings.memory_cache_ttl {
+                settings.memory_cache_ttl = Some(memory_cache_ttl);
+            }
+
             // check for user defined schema
             if !new_settings.defined_schema_fields.add.is_empty() {
                 settings.defined_schema_fields =
@@ -494,7 +583,25 @@ pub async fn update_stream_settings(
                     }
                 }
                 // here we are sure that all fields to be removed can be removed,
-                // so we bulk filter
+                // so we drop their stream entries and bulk filter
+                for f in &new_settings.distinct_value_fields.remove {
+                    let record = DistinctFieldRecord::new(
+                        OriginType::Stream,
+                        stream_name,
+                        org_id,
+                        stream_name,
+                        stream_type.to_string(),
+                        f,
+                    );
+                    if let Err(e) = distinct_values::remove(record).await {
+                        return Ok(HttpResponse::InternalServerError().json(
+                            MetaHttpResponse::error(
+                                http::StatusCode::INTERNAL_SERVER_ERROR.into(),
+                                format!("error in updating settings : {e}"),
+                            ),
+                        ));
+                    }
+                }
                 settings.distinct_value_fields.retain(|field| {
                     !new_settings
                         .distinct_value_fields
@@ -540,7 +647,195 @@ pub async fn update_stream_settings(
     }
 }
 
+#[tracing::instrument(skip(patch))]
+pub async fn patch_stream_settings(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    patch: PatchStreamSettings,
+) -> Result<HttpResponse, Error> {
+    let Some(mut settings) = infra::schema::get_settings(org_id, stream_name, stream_type).await
+    else {
+        return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            http::StatusCode::BAD_REQUEST.into(),
+            "stream settings could not be found".to_string(),
+        )));
+    };
+    let schema = match infra::schema::get(org_id, stream_name, stream_type).await {
+        Ok(schema) => schema,
+        Err(e) => {
+            return Ok(
+                HttpResponse::InternalServerError().json(MetaHttpResponse::error(
+                    http::StatusCode::INTERNAL_SERVER_ERROR.into(),
+                    format!("error in getting schema : {e}"),
+                )),
+            );
+        }
+    };
+    if let Err(e) = validate_settings_patch(&patch, &schema) {
+        return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            http::StatusCode::BAD_REQUEST.into(),
+            e,
+        )));
+    }
+
+    let expected_version = patch.settings_version;
+    settings.apply_patch(patch, now_micros());
+    match store_stream_settings(org_id, stream_name, stream_type, settings, expected_version).await
+    {
+        Ok(settings) => Ok(HttpResponse::Ok().json(settings)),
+        Err(resp) => Ok(resp),
+    }
+}
+
+/// Checks the fields given in `patch`, the stored ones are left alone so a
+/// stale value can't block an unrelated change.
+fn validate_settings_patch(patch: &PatchStreamSettings, schema: &Schema) -> Result<(), String> {
+    if let Some(data_retention) = patch.data_retention {
+        // same rule as ZO_COMPACT_DATA_RETENTION_DAYS, 0 falls back to it
+        if data_retention < 0 || (data_retention > 0 && data_retention < 3) {
+            return Err("data retention must be 0 or at least 3 days".to_string());
+        }
+    }
+    if patch.max_query_range.is_some_and(|v| v < 0) {
+        return Err("max query range can't be negative".to_string());
+    }
+    if patch.memory_cache_ttl.is_some_and(|v| v < 0) {
+        return Err("memory cache ttl can't be negative".to_string());
+    }
+    if patch.original_data_retention_days.is_some_and(|v| v < 0) {
+        return Err("original data retention can't be negative".to_string());
+    }
+    let fields = [
+        ("secondary index", patch.index_fields.as_ref()),
+        ("bloom filter", patch.bloom_filter_fields.as_ref()),
+    ];
+    for (kind, keys) in fields {
+        for key in keys.into_iter().flatten() {
+            if schema.field_with_name(key).is_err() {
+                return Err(format!("{kind} field [{key}] not found in schema"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `_original` column can't outlive the records, `default_retention` is
+/// used when the stream has no retention of its own.
+fn check_original_data_retention(
+    settings: &StreamSettings,
+    default_retention: i64,
+) -> Result<(), String> {
+    let days = settings.original_data_retention_days;
+    if days < 0 {
+        return Err("original data retention can't be negative".to_string());
+    }
+    let data_retention = if settings.data_retention > 0 {
+        settings.data_retention
+    } else {
+        default_retention
+    };
+    if days > data_retention {
+        return Err(format!(
+            "original data retention [{days}] can't be longer than the data retention [{data_retention}]"
+        ));
+    }
+    Ok(())
+}
+
+/// Create a compactor job to delete the stream data in `time_range`, the stream is kept.
 #[tracing::instrument]
+pub async fn delete_stream_data(
+    org_id: &str,
+    stream_name: &str,
+    stream_type: StreamType,
+    time_range: &str,
+) -> Result<HttpResponse, Error> {
+    let schema = infra::schema::get_versions(org_id, stream_name, stream_type, None)
+        .await
+        .unwrap_or_default();
+    if schema.is_empty() {
+        return Ok(HttpResponse::NotFound().json(MetaHttpResponse::error(
+            StatusCode::NOT_FOUND.into(),
+            "stream not found".to_string(),
+        )));
+    }
+
+    let stream_settings = infra::schema::get_settings(org_id, stream_name, stream_type)
+        .await
+        .unwrap_or_default();
+    let partition_time_level =
+        unwrap_partition_time_level(stream_settings.partition_time_level, stream_type);
+    let (start, end) = match parse_delete_time_range(time_range, partition_time_level) {
+        Ok(v) => v,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .json(MetaHttpResponse::error(StatusCode::BAD_REQUEST.into(), e)));
+        }
+    };
+
+    // create delete for compactor, whole days keep the date range job and the
+    // other ranges delete whole hours with an inclusive end hour
+    let (job_start, job_end) = (
+        retention::format_retention_time(start),
+        retention::format_retention_time(end),
+    );
+    let ret = if start.timestamp() % 86400 == 0 && end.timestamp() % 86400 == 0 {
+        db::compact::retention::delete_stream(
+            org_id,
+            stream_type,
+            stream_name,
+            Some((&job_start, &job_end)),
+        )
+        .await
+    } else {
+        db::compact::retention::delete_stream_hours(
+            org_id,
+            stream_type,
+            stream_name,
+            (
+                &job_start,
+                &retention::format_retention_time(end - chrono::Duration::hours(1)),
+            ),
+        )
+        .await
+    };
+    if let Err(e) = ret {
+        return Ok(
+            HttpResponse::InternalServerError().json(MetaHttpResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR.into(),
+                format!("failed to delete stream data: {e}"),
+            )),
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
+        StatusCode::OK.into(),
+        format!("stream data deletion scheduled for {job_start},{job_end}"),
+    )))
+}
+
+/// Parse the end exclusive `start,end` time range of a data delete request, it must be
+/// aligned to the partitions of the stream.
+fn parse_delete_time_range(
+    time_range: &str,
+    partition_time_level: PartitionTimeLevel,
+) -> Result<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>), String> {
+    let Some(date_range) = time_range.split_once(',') else {
+        return Err("time_range must be in the format start,end".to_string());
+    };
+    let (start, end) = retention::parse_retention_range(date_range).map_err(|e| e.to_string())?;
+    if start == end {
+        return Err("time_range is empty".to_string());
+    }
+    if retention::align_to_partition(start, end, partition_time_level) != (start, end) {
+        return Err(format!(
+            "time_range must be aligned to the {partition_time_level} partitions of the stream"
+        ));
+    }
+    Ok((start, end))
+}
+
 pub async fn delete_stream(
     org_id: &str,
     stream_name: &str,
@@ -609,6 +904,9 @@ pub async fn delete_stream(
             )),
         );
     };
+    if let Err(e) = db::compact::original_data::del_offset(org_id, stream_type, stream_name).await {
+        log::error!("failed to delete stream original data offset: {e}");
+    }
 
     // delete associated pipelines
     if let Some(pipeline) =
@@ -678,6 +976,61 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_validate_settings_patch() {
+        let schema = Schema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new("trace_id", DataType::Utf8, true),
+        ]);
+        let patch = PatchStreamSettings {
+            index_fields: Some(vec!["host".to_string()]),
+            bloom_filter_fields: Some(vec!["trace_id".to_string()]),
+            data_retention: Some(3),
+            ..Default::default()
+        };
+        assert!(validate_settings_patch(&patch, &schema).is_ok());
+
+        let patch = PatchStreamSettings {
+            index_fields: Some(vec!["host".to_string(), "pod".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_settings_patch(&patch, &schema).unwrap_err(),
+            "secondary index field [pod] not found in schema"
+        );
+
+        for data_retention in [-1, 1, 2] {
+            let patch = PatchStreamSettings {
+                data_retention: Some(data_retention),
+                ..Default::default()
+            };
+            assert!(validate_settings_patch(&patch, &schema).is_err());
+        }
+        let patch = PatchStreamSettings {
+            data_retention: Some(0),
+            ..Default::default()
+        };
+        assert!(validate_settings_patch(&patch, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_check_original_data_retention() {
+        let mut settings = StreamSettings::default();
+        assert!(check_original_data_retention(&settings, 3650).is_ok());
+
+        settings.original_data_retention_days = 7;
+        assert!(check_original_data_retention(&settings, 3650).is_ok());
+        assert!(check_original_data_retention(&settings, 3).is_err());
+
+        settings.data_retention = 7;
+        assert!(check_original_data_retention(&settings, 3).is_ok());
+        settings.data_retention = 5;
+        assert!(check_original_data_retention(&settings, 3650).is_err());
+
+        settings.original_data_retention_days = -1;
+        assert!(check_original_data_retention(&settings, 3650).is_err());
+    }
+
     #[test]
     fn test_stream_res() {
         let stats = StreamStats::default();
@@ -685,4 +1038,50 @@ mod tests {
         let res = stream_res("Test", StreamType::Logs, schema, Some(stats.clone()));
         assert_eq!(res.stats, stats);
     }
+
+    #[test]
+    fn test_parse_delete_time_range() {
+        let parse = |time_range, level| {
+            parse_delete_time_range(time_range, level).map(|(start, end)| {
+                (
+                    retention::format_retention_time(start),
+                    retention::format_retention_time(end),
+                )
+            })
+        };
+        // old date format
+        assert_eq!(
+            parse("2024-11-03,2024-11-04", PartitionTimeLevel::Daily).unwrap(),
+            ("2024-11-03".to_string(), "2024-11-04".to_string())
+        );
+        // hour precision
+        assert_eq!(
+            parse(
+                "2024-11-03T14:00:00Z,2024-11-03T16:00:00Z",
+                PartitionTimeLevel::Hourly
+            )
+            .unwrap(),
+            (
+                "2024-11-03T14:00:00Z".to_string(),
+                "2024-11-03T16:00:00Z".to_string()
+            )
+        );
+        // daily partitions can't delete part of a day
+        assert!(parse_delete_time_range(
+            "2024-11-03T14:00:00Z,2024-11-03T16:00:00Z",
+            PartitionTimeLevel::Daily
+        )
+        .is_err());
+        assert!(parse_delete_time_range(
+            "2024-11-03T14:30:00Z,2024-11-03T16:00:00Z",
+            PartitionTimeLevel::Hourly
+        )
+        .is_err());
+        assert!(parse_delete_time_range(
+            "2024-11-03T16:00:00Z,2024-11-03T14:00:00Z",
+            PartitionTimeLevel::Hourly
+        )
+        .is_err());
+        assert!(parse_delete_time_range("2024-11-03", PartitionTimeLevel::Hourly).is_err());
+    }
 }
@@ -0,0 +1,515 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Record builders of the synthetic data generators. A builder only depends on
+//! its seed and the timestamps it is given, so the same seed always yields the
+//! same records.
+
+use std::collections::BTreeMap;
+
+use config::{
+    meta::{
+        promql::{NAME_LABEL, TYPE_LABEL, VALUE_LABEL},
+        synthetic::{Cardinality, LogTemplate, MetricKind},
+    },
+    utils::json::{self, Map, Value},
+    TIMESTAMP_COL_NAME,
+};
+use hashbrown::HashMap;
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::ExportTraceServiceRequest,
+    common::v1::{any_value, AnyValue, KeyValue},
+    resource::v1::Resource,
+    trace::v1::{span::SpanKind, status::StatusCode, ResourceSpans, ScopeSpans, Span, Status},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+const METHODS: [&str; 5] = ["GET", "GET", "GET", "POST", "PUT"];
+const PATHS: [&str; 6] = [
+    "/",
+    "/api/users",
+    "/api/orders",
+    "/api/products",
+    "/health",
+    "/static/app.js",
+];
+const STATUSES: [u16; 8] = [200, 200, 200, 200, 201, 304, 404, 500];
+const LEVELS: [&str; 6] = ["info", "info", "info", "debug", "warn", "error"];
+const USER_AGENTS: [&str; 3] = [
+    "Mozilla/5.0 (X11; Linux x86_64)",
+    "curl/8.5.0",
+    "Go-http-client/1.1",
+];
+const OPERATIONS: [&str; 5] = [
+    "handle_request",
+    "query_db",
+    "call_upstream",
+    "render",
+    "auth",
+];
+
+fn service_name(idx: usize) -> String {
+    format!("service-{idx}")
+}
+
+fn host_name(idx: usize) -> String {
+    format!("host-{idx}")
+}
+
+fn pick(rng: &mut StdRng, cardinality: usize) -> usize {
+    rng.gen_range(0..cardinality.max(1))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub struct LogBuilder {
+    rng: StdRng,
+    template: LogTemplate,
+    cardinality: Cardinality,
+}
+
+impl LogBuilder {
+    pub fn new(seed: u64, template: LogTemplate, cardinality: Cardinality) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            template,
+            cardinality,
+        }
+    }
+
+    /// Builds a record of the template, every record of a template has the
+    /// same fields
+    pub fn build(&mut self, timestamp: i64) -> Map<String, Value> {
+        let service = service_name(pick(&mut self.rng, self.cardinality.services));
+        let host = host_name(pick(&mut self.rng, self.cardinality.hosts));
+        let mut record = match self.template {
+            LogTemplate::Nginx => self.nginx(service, host),
+            LogTemplate::JsonApp => self.json_app(service, host),
+            LogTemplate::K8s => self.k8s(service, host),
+        };
+        record.insert(TIMESTAMP_COL_NAME.to_string(), timestamp.into());
+        record
+    }
+
+    fn nginx(&mut self, service: String, host: String) -> Map<String, Value> {
+        let rng = &mut self.rng;
+        let remote_addr = format!(
+            "10.{}.{}.{}",
+            rng.gen::<u8>(),
+            rng.gen::<u8>(),
+            rng.gen::<u8>()
+        );
+        let method = *METHODS.choose(rng).unwrap();
+        let path = *PATHS.choose(rng).unwrap();
+        let status = *STATUSES.choose(rng).unwrap();
+        let bytes_sent = rng.gen_range(64..65536u64);
+        let request_time = rng.gen_range(1..2000u64) as f64 / 1000.0;
+        let user_agent = *USER_AGENTS.choose(rng).unwrap();
+        let message = format!(
+            "{remote_addr} - - \"{method} {path} HTTP/1.1\" {status} {bytes_sent} \"-\" \"{user_agent}\" {request_time}"
+        );
+        let mut record = Map::new();
+        record.insert("service".to_string(), service.into());
+        record.insert("host".to_string(), host.into());
+        record.insert("remote_addr".to_string(), remote_addr.into());
+        record.insert("method".to_string(), method.into());
+        record.insert("path".to_string(), path.into());
+        record.insert("status".to_string(), status.into());
+        record.insert("bytes_sent".to_string(), bytes_sent.into());
+        record.insert("request_time".to_string(), request_time.into());
+        record.insert("http_user_agent".to_string(), user_agent.into());
+        record.insert("message".to_string(), message.into());
+        record
+    }
+
+    fn json_app(&mut self, service: String, host: String) -> Map<String, Value> {
+        let rng = &mut self.rng;
+        let level = *LEVELS.choose(rng).unwrap();
+        let operation = *OPERATIONS.choose(rng).unwrap();
+        let duration_ms = rng.gen_range(1..1500u64);
+        let user_id = format!("user-{}", rng.gen_range(0..10000u32));
+        let trace_id = hex(&rng.gen::<[u8; 16]>());
+        let mut record = Map::new();
+        record.insert("service".to_string(), service.clone().into());
+        record.insert("host".to_string(), host.into());
+        record.insert("level".to_string(), level.into());
+        record.insert(
+            "logger".to_string(),
+            format!("{service}::{operation}").into(),
+        );
+        record.insert(
+            "message".to_string(),
+            format!("{operation} finished in {duration_ms}ms").into(),
+        );
+        record.insert("duration_ms".to_string(), duration_ms.into());
+        record.insert("user_id".to_string(), user_id.into());
+        record.insert("trace_id".to_string(), trace_id.into());
+        record
+    }
+
+    fn k8s(&mut self, service: String, host: String) -> Map<String, Value> {
+        let rng = &mut self.rng;
+        let level = *LEVELS.choose(rng).unwrap();
+        let operation = *OPERATIONS.choose(rng).unwrap();
+        let pod = format!("{service}-{:08x}", rng.gen::<u32>());
+        let stream = if level == "error" { "stderr" } else { "stdout" };
+        let mut record = Map::new();
+        record.insert("kubernetes_namespace_name".to_string(), "synthetic".into());
+        record.insert("kubernetes_pod_name".to_string(), pod.into());
+        record.insert(
+            "kubernetes_container_name".to_string(),
+            service.clone().into(),
+        );
+        record.insert("kubernetes_labels_app".to_string(), service.into());
+        record.insert("kubernetes_host".to_string(), host.into());
+        record.insert("stream".to_string(), stream.into());
+        record.insert("level".to_string(), level.into());
+        record.insert(
+            "log".to_string(),
+            format!("level={level} msg=\"{operation} done\"").into(),
+        );
+        record
+    }
+}
+
+pub struct MetricBuilder {
+    rng: StdRng,
+    name: String,
+    metric_type: MetricKind,
+    cardinality: Cardinality,
+    /// Current value of the counter of every series
+    counters: HashMap<(usize, usize), f64>,
+}
+
+impl MetricBuilder {
+    pub fn new(seed: u64, name: &str, metric_type: MetricKind, cardinality: Cardinality) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            name: name.to_string(),
+            metric_type,
+            cardinality,
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Builds a sample of a random series, counters only ever increase
+    pub fn build(&mut self, timestamp: i64) -> Map<String, Value> {
+        let service = pick(&mut self.rng, self.cardinality.services);
+        let host = pick(&mut self.rng, self.cardinality.hosts);
+        let value = match self.metric_type {
+            MetricKind::Gauge => self.rng.gen_range(0..10000u64) as f64 / 100.0,
+            MetricKind::Counter => {
+                let inc = self.rng.gen_range(1..100u64) as f64;
+                let counter = self.counters.entry((service, host)).or_default();
+                *counter += inc;
+                *counter
+            }
+        };
+        let metric_type = match self.metric_type {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+        };
+        let mut record = Map::new();
+        record.insert(NAME_LABEL.to_string(), self.name.clone().into());
+        record.insert(TYPE_LABEL.to_string(), metric_type.into());
+        record.insert("service".to_string(), service_name(service).into());
+        record.insert("host".to_string(), host_name(host).into());
+        record.insert(TIMESTAMP_COL_NAME.to_string(), timestamp.into());
+        record.insert(VALUE_LABEL.to_string(), json::json!(value));
+        record
+    }
+}
+
+pub struct TraceBuilder {
+    rng: StdRng,
+    depth: usize,
+    fanout: usize,
+    cardinality: Cardinality,
+}
+
+impl TraceBuilder {
+    pub fn new(seed: u64, depth: usize, fanout: usize, cardinality: Cardinality) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            depth,
+            fanout,
+            cardinality,
+        }
+    }
+
+    /// Builds the spans of one call tree starting at `timestamp`, grouped by
+    /// the service of the span
+    pub fn build(&mut self, timestamp: i64) -> Vec<ResourceSpans> {
+        let trace_id = self.rng.gen::<[u8; 16]>().to_vec();
+        let start = (timestamp as u64) * 1000;
+        let duration = self.rng.gen_range(10_000_000..500_000_000u64);
+        let mut spans: BTreeMap<String, Vec<Span>> = BTreeMap::new();
+        self.build_span(&trace_id, Vec::new(), start, duration, 1, &mut spans);
+        spans
+            .into_iter()
+            .map(|(service, spans)| ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![string_attribute("service.name", service)],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Builds the request of `traces` call trees spread over one second
+    pub fn build_request(&mut self, timestamp: i64, traces: u64) -> ExportTraceServiceRequest {
+        let mut resource_spans = Vec::new();
+        for i in 0..traces {
+            let ts = timestamp + (i * 1_000_000 / traces.max(1)) as i64;
+            resource_spans.extend(self.build(ts));
+        }
+        ExportTraceServiceRequest { resource_spans }
+    }
+
+    fn build_span(
+        &mut self,
+        trace_id: &[u8],
+        parent_span_id: Vec<u8>,
+        start: u64,
+        duration: u64,
+        level: usize,
+        spans: &mut BTreeMap<String, Vec<Span>>,
+    ) {
+        let span_id = self.rng.gen::<[u8; 8]>().to_vec();
+        let service = service_name(pick(&mut self.rng, self.cardinality.services));
+        let host = host_name(pick(&mut self.rng, self.cardinality.hosts));
+        let operation = *OPERATIONS.choose(&mut self.rng).unwrap();
+        let status = if self.rng.gen_ratio(1, 50) {
+            StatusCode::Error
+        } else {
+            StatusCode::Ok
+        };
+        let kind = if parent_span_id.is_empty() {
+            SpanKind::Server
+        } else {
+            SpanKind::Internal
+        };
+
+        if level < self.depth {
+            // children run one after another inside the parent
+            let slot = duration / (self.fanout as u64 + 1);
+            for i in 0..self.fanout {
+                let child_start = start + slot / 2 + slot * i as u64;
+                let child_duration = self.rng.gen_range(slot / 4..=slot);
+                self.build_span(
+                    trace_id,
+                    span_id.clone(),
+                    child_start,
+                    child_duration,
+                    level + 1,
+                    spans,
+                );
+            }
+        }
+
+        let span = Span {
+            trace_id: trace_id.to_vec(),
+            span_id,
+            parent_span_id,
+            name: operation.to_string(),
+            kind: kind as i32,
+            start_time_unix_nano: start,
+            end_time_unix_nano: start + duration,
+            attributes: vec![string_attribute("host.name", host)],
+            status: Some(Status {
+                code: status as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        spans.entry(service).or_default().push(span);
+    }
+}
+
+fn string_attribute(key: &str, value: String) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value)),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn keys(record: &Map<String, Value>) -> BTreeSet<&str> {
+        record.keys().map(|k| k.as_str()).collect()
+    }
+
+    #[test]
+    fn test_log_builder_schema_is_stable() {
+        let cases = [
+            (
+                LogTemplate::Nginx,
+                vec![
+                    "_timestamp",
+                    "bytes_sent",
+                    "host",
+                    "http_user_agent",
+                    "message",
+                    "method",
+                    "path",
+                    "remote_addr",
+                    "request_time",
+                    "service",
+                    "status",
+                ],
+            ),
+            (
+                LogTemplate::JsonApp,
+                vec![
+                    "_timestamp",
+                    "duration_ms",
+                    "host",
+                    "level",
+                    "logger",
+                    "message",
+                    "service",
+                    "trace_id",
+                    "user_id",
+                ],
+            ),
+            (
+                LogTemplate::K8s,
+                vec![
+                    "_timestamp",
+                    "kubernetes_container_name",
+                    "kubernetes_host",
+                    "kubernetes_labels_app",
+                    "kubernetes_namespace_name",
+                    "kubernetes_pod_name",
+                    "level",
+                    "log",
+                    "stream",
+                ],
+            ),
+        ];
+        for (template, expected) in cases {
+            let mut builder = LogBuilder::new(7, template, Cardinality::default());
+            let expected: BTreeSet<&str> = expected.into_iter().collect();
+            for i in 0..200 {
+                let record = builder.build(1_700_000_000_000_000 + i);
+                assert_eq!(keys(&record), expected, "{template:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_log_builder_is_deterministic() {
+        let cardinality = Cardinality {
+            services: 3,
+            hosts: 4,
+        };
+        let mut a = LogBuilder::new(42, LogTemplate::JsonApp, cardinality);
+        let mut b = LogBuilder::new(42, LogTemplate::JsonApp, cardinality);
+        let mut c = LogBuilder::new(43, LogTemplate::JsonApp, cardinality);
+        let a: Vec<_> = (0..50).map(|i| a.build(i)).collect();
+        let b: Vec<_> = (0..50).map(|i| b.build(i)).collect();
+        let c: Vec<_> = (0..50).map(|i| c.build(i)).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let services: BTreeSet<_> = a.iter().map(|r| r["service"].as_str().unwrap()).collect();
+        assert!(services.len() <= 3);
+        let hosts: BTreeSet<_> = a.iter().map(|r| r["host"].as_str().unwrap()).collect();
+        assert!(hosts.len() <= 4);
+    }
+
+    #[test]
+    fn test_metric_builder_counters_increase() {
+        let cardinality = Cardinality {
+            services: 2,
+            hosts: 2,
+        };
+        let mut builder = MetricBuilder::new(1, "requests_total", MetricKind::Counter, cardinality);
+        let mut last: HashMap<(String, String), f64> = HashMap::new();
+        for i in 0..100 {
+            let record = builder.build(i);
+            assert_eq!(
+                keys(&record),
+                BTreeSet::from([
+                    "__name__",
+                    "__type__",
+                    "_timestamp",
+                    "host",
+                    "service",
+                    "value"
+                ])
+            );
+            assert_eq!(record[TYPE_LABEL], "counter");
+            let series = (
+                record["service"].as_str().unwrap().to_string(),
+                record["host"].as_str().unwrap().to_string(),
+            );
+            let value = record[VALUE_LABEL].as_f64().unwrap();
+            if let Some(prev) = last.insert(series, value) {
+                assert!(value > prev);
+            }
+        }
+        assert!(last.len() <= 4);
+
+        let mut a = MetricBuilder::new(5, "cpu", MetricKind::Gauge, cardinality);
+        let mut b = MetricBuilder::new(5, "cpu", MetricKind::Gauge, cardinality);
+        for i in 0..20 {
+            assert_eq!(a.build(i), b.build(i));
+        }
+    }
+
+    #[test]
+    fn test_trace_builder_call_tree() {
+        let mut builder = TraceBuilder::new(9, 3, 2, Cardinality::default());
+        let resource_spans = builder.build(1_700_000_000_000_000);
+        let spans: Vec<&Span> = resource_spans
+            .iter()
+            .flat_map(|rs| rs.scope_spans.iter().flat_map(|ss| ss.spans.iter()))
+            .collect();
+        assert_eq!(spans.len(), 7);
+        let trace_id = &spans[0].trace_id;
+        assert!(spans.iter().all(|s| &s.trace_id == trace_id));
+
+        let roots: Vec<_> = spans
+            .iter()
+            .filter(|s| s.parent_span_id.is_empty())
+            .collect();
+        assert_eq!(roots.len(), 1);
+        let by_id: HashMap<&Vec<u8>, &&Span> = spans.iter().map(|s| (&s.span_id, s)).collect();
+        for span in spans.iter().filter(|s| !s.parent_span_id.is_empty()) {
+            let parent = by_id[&span.parent_span_id];
+            assert!(span.start_time_unix_nano >= parent.start_time_unix_nano);
+            assert!(span.end_time_unix_nano <= parent.end_time_unix_nano);
+        }
+
+        let request =
+            |seed| TraceBuilder::new(seed, 3, 2, Cardinality::default()).build_request(0, 3);
+        assert_eq!(request(9), request(9));
+        assert_ne!(request(9), request(10));
+    }
+}
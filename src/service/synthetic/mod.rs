@@ -0,0 +1,546 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Synthetic data generators write test data through the normal ingestion
+//! path. Generators are stored in the db so any node can list and cancel them,
+//! the node running a generator stops once its key is gone.
+
+use std::{future::Future, time::Duration};
+
+use config::{
+    cluster::LOCAL_NODE,
+    get_config, ider,
+    meta::synthetic::{
+        SyntheticGenerator, SyntheticLogsRequest, SyntheticMetricsRequest, SyntheticSpec,
+        SyntheticTracesRequest,
+    },
+    utils::{json, time::now_micros},
+    RwHashSet,
+};
+use infra::dist_lock;
+use once_cell::sync::Lazy;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use rand::Rng;
+use tokio::task::JoinHandle;
+
+use crate::{
+    common::{infra::cluster::get_cached_node_by_name, meta::ingestion::IngestionRequest},
+    service::{db, self_reporting::SYNTHETIC},
+};
+
+pub mod builders;
+
+use builders::{LogBuilder, MetricBuilder, TraceBuilder};
+
+/// Maximum depth and fanout of the call trees of a traces generator
+const MAX_TRACE_DEPTH: usize = 10;
+const MAX_TRACE_FANOUT: usize = 10;
+
+/// Held while counting the generators and registering a new one
+const START_LOCK_KEY: &str = "/synthetic/start";
+
+/// Generators running on this node, the ones registered for this node but
+/// missing here were left over by a restart
+static RUNNING: Lazy<RwHashSet<String>> = Lazy::new(Default::default);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyntheticError {
+    #[error("Synthetic data generators are disabled")]
+    Disabled,
+    #[error("Synthetic data generators only run on ingester nodes")]
+    NotIngester,
+    #[error("Maximum of {0} synthetic data generators are already running")]
+    TooMany(usize),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error(transparent)]
+    Db(#[from] anyhow::Error),
+}
+
+/// One second of records of a generator
+pub enum Batch {
+    Logs(Vec<json::Value>),
+    Metrics(Vec<json::Value>),
+    Traces(ExportTraceServiceRequest),
+}
+
+enum Builder {
+    Logs(LogBuilder),
+    Metrics(MetricBuilder),
+    Traces(TraceBuilder),
+}
+
+impl Builder {
+    fn new(generator: &SyntheticGenerator) -> Self {
+        match &generator.spec {
+            SyntheticSpec::Logs {
+                template,
+                cardinality,
+            } => Builder::Logs(LogBuilder::new(generator.seed, *template, *cardinality)),
+            SyntheticSpec::Metrics {
+                metric_type,
+                cardinality,
+            } => Builder::Metrics(MetricBuilder::new(
+                generator.seed,
+                &generator.stream,
+                *metric_type,
+                *cardinality,
+            )),
+            SyntheticSpec::Traces {
+                depth,
+                fanout,
+                cardinality,
+            } => Builder::Traces(TraceBuilder::new(
+                generator.seed,
+                *depth,
+                *fanout,
+                *cardinality,
+            )),
+        }
+    }
+
+    /// Builds `count` records spread over the second starting at `timestamp`
+    fn build(&mut self, timestamp: i64, count: u64) -> Batch {
+        let ts = |i: u64| timestamp + (i * 1_000_000 / count.max(1)) as i64;
+        match self {
+            Builder::Logs(b) => Batch::Logs((0..count).map(|i| b.build(ts(i)).into()).collect()),
+            Builder::Metrics(b) => {
+                Batch::Metrics((0..count).map(|i| b.build(ts(i)).into()).collect())
+            }
+            Builder::Traces(b) => Batch::Traces(b.build_request(timestamp, count)),
+        }
+    }
+}
+
+pub async fn start_logs(
+    req: SyntheticLogsRequest,
+    user_email: &str,
+) -> Result<SyntheticGenerator, SyntheticError> {
+    let spec = SyntheticSpec::Logs {
+        template: req.template,
+        cardinality: req.cardinality,
+    };
+    let generator = new_generator(
+        req.org,
+        req.stream,
+        req.rate_per_sec,
+        req.duration_secs,
+        req.seed,
+        user_email,
+        spec,
+    );
+    start(generator).await
+}
+
+pub async fn start_metrics(
+    req: SyntheticMetricsRequest,
+    user_email: &str,
+) -> Result<SyntheticGenerator, SyntheticError> {
+    let spec = SyntheticSpec::Metrics {
+        metric_type: req.metric_type,
+        cardinality: req.cardinality,
+    };
+    let generator = new_generator(
+        req.org,
+        req.stream,
+        req.rate_per_sec,
+        req.duration_secs,
+        req.seed,
+        user_email,
+        spec,
+    );
+    start(generator).await
+}
+
+pub async fn start_traces(
+    req: SyntheticTracesRequest,
+    user_email: &str,
+) -> Result<SyntheticGenerator, SyntheticError> {
+    let spec = SyntheticSpec::Traces {
+        depth: req.depth,
+        fanout: req.fanout,
+        cardinality: req.cardinality,
+    };
+    let generator = new_generator(
+        req.org,
+        req.stream,
+        req.rate_per_sec,
+        req.duration_secs,
+        req.seed,
+        user_email,
+        spec,
+    );
+    start(generator).await
+}
+
+fn new_generator(
+    org: String,
+    stream: String,
+    rate_per_sec: u64,
+    duration_secs: u64,
+    seed: Option<u64>,
+    user_email: &str,
+    spec: SyntheticSpec,
+) -> SyntheticGenerator {
+    SyntheticGenerator {
+        id: ider::generate(),
+        org,
+        stream,
+        rate_per_sec,
+        duration_secs,
+        seed: seed.unwrap_or_else(|| rand::thread_rng().gen()),
+        created_at: now_micros(),
+        created_by: user_email.to_string(),
+        node: LOCAL_NODE.name.clone(),
+        spec,
+    }
+}
+
+fn validate(generator: &SyntheticGenerator) -> Result<(), SyntheticError> {
+    let cfg = get_config();
+    let invalid = |msg: String| Err(SyntheticError::InvalidRequest(msg));
+    if generator.org.is_empty() || generator.stream.is_empty() {
+        return invalid("org and stream are required".to_string());
+    }
+    if generator.rate_per_sec == 0 {
+        return invalid("rate_per_sec must be greater than 0".to_string());
+    }
+    let records = generator
+        .rate_per_sec
+        .saturating_mul(generator.spec.records_per_unit());
+    if records > cfg.limit.synthetic_max_rate {
+        return invalid(format!(
+            "generator would write {records} records per second, the maximum is {}",
+            cfg.limit.synthetic_max_rate
+        ));
+    }
+    if generator.duration_secs == 0 || generator.duration_secs > cfg.limit.synthetic_max_duration {
+        return invalid(format!(
+            "duration_secs must be between 1 and {}",
+            cfg.limit.synthetic_max_duration
+        ));
+    }
+    let cardinality = match &generator.spec {
+        SyntheticSpec::Logs { cardinality, .. } | SyntheticSpec::Metrics { cardinality, .. } => {
+            cardinality
+        }
+        SyntheticSpec::Traces {
+            depth,
+            fanout,
+            cardinality,
+        } => {
+            if *depth == 0 || *depth > MAX_TRACE_DEPTH {
+                return invalid(format!("depth must be between 1 and {MAX_TRACE_DEPTH}"));
+            }
+            if *fanout == 0 || *fanout > MAX_TRACE_FANOUT {
+                return invalid(format!("fanout must be between 1 and {MAX_TRACE_FANOUT}"));
+            }
+            cardinality
+        }
+    };
+    if cardinality.services == 0 || cardinality.hosts == 0 {
+        return invalid("cardinality must be greater than 0".to_string());
+    }
+    Ok(())
+}
+
+/// Registers the generator and spawns the task writing its records
+pub async fn start(generator: SyntheticGenerator) -> Result<SyntheticGenerator, SyntheticError> {
+    if !get_config().common.synthetic_enabled {
+        return Err(SyntheticError::Disabled);
+    }
+    if !LOCAL_NODE.is_ingester() {
+        return Err(SyntheticError::NotIngester);
+    }
+    let writer = generator.clone();
+    spawn(generator, move |batch| write_batch(writer.clone(), batch))
+        .await
+        .map(|(generator, _)| generator)
+}
+
+async fn spawn<W, Fut>(
+    generator: SyntheticGenerator,
+    write: W,
+) -> Result<(SyntheticGenerator, JoinHandle<()>), SyntheticError>
+where
+    W: FnMut(Batch) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), anyhow::Error>> + Send,
+{
+    validate(&generator)?;
+    // concurrent requests must not both take the last free slot
+    let locker = dist_lock::lock(START_LOCK_KEY, 0)
+        .await
+        .map_err(anyhow::Error::from)?;
+    let ret = register(&generator).await;
+    dist_lock::unlock(&locker)
+        .await
+        .map_err(anyhow::Error::from)?;
+    ret?;
+    log::info!(
+        "[SYNTHETIC] start generator {} writing to {}/{}",
+        generator.id,
+        generator.org,
+        generator.stream
+    );
+    let task = generator.clone();
+    let handle = tokio::task::spawn(async move {
+        let id = task.id.clone();
+        run(task, write).await;
+        RUNNING.remove(&id);
+    });
+    Ok((generator, handle))
+}
+
+/// Stores the generator unless the maximum of generators is running
+async fn register(generator: &SyntheticGenerator) -> Result<(), SyntheticError> {
+    let max = get_config().limit.synthetic_max_generators;
+    if list().await?.len() >= max {
+        return Err(SyntheticError::TooMany(max));
+    }
+    RUNNING.insert(generator.id.clone());
+    if let Err(e) = db::synthetic::set(generator).await {
+        RUNNING.remove(&generator.id);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Lists the running generators, removing the ones that are past their
+/// duration or whose node no longer runs them
+pub async fn list() -> Result<Vec<SyntheticGenerator>, SyntheticError> {
+    let now = now_micros();
+    let mut running = Vec::new();
+    for generator in db::synthetic::list().await? {
+        if generator.end_time() > now && !is_orphan(&generator).await {
+            running.push(generator);
+        } else {
+            db::synthetic::delete(&generator.id).await?;
+        }
+    }
+    running.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(running)
+}
+
+/// Whether the node of the generator left the cluster or restarted since it
+/// started the generator
+async fn is_orphan(generator: &SyntheticGenerator) -> bool {
+    if generator.node == LOCAL_NODE.name {
+        !RUNNING.contains(&generator.id)
+    } else {
+        get_cached_node_by_name(&generator.node).await.is_none()
+    }
+}
+
+/// Cancels the generator, returning whether it was running
+pub async fn cancel(id: &str) -> Result<bool, SyntheticError> {
+    if db::synthetic::get(id).await?.is_none() {
+        return Ok(false);
+    }
+    db::synthetic::delete(id).await?;
+    log::info!("[SYNTHETIC] cancel generator {id}");
+    Ok(true)
+}
+
+async fn run<W, Fut>(generator: SyntheticGenerator, mut write: W)
+where
+    W: FnMut(Batch) -> Fut,
+    Fut: Future<Output = Result<(), anyhow::Error>>,
+{
+    let mut builder = Builder::new(&generator);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        let now = now_micros();
+        if now >= generator.end_time() {
+            break;
+        }
+        match db::synthetic::get(&generator.id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                log::info!("[SYNTHETIC] generator {} canceled", generator.id);
+                return;
+            }
+            Err(e) => {
+                log::error!("[SYNTHETIC] error checking generator {}: {e}", generator.id);
+                continue;
+            }
+        }
+        let batch = builder.build(now, generator.rate_per_sec);
+        if let Err(e) = write(batch).await {
+            log::error!(
+                "[SYNTHETIC] generator {} failed to write to {}/{}: {e}",
+                generator.id,
+                generator.org,
+                generator.stream
+            );
+        }
+    }
+    log::info!("[SYNTHETIC] generator {} finished", generator.id);
+    if let Err(e) = db::synthetic::delete(&generator.id).await {
+        log::error!("[SYNTHETIC] error removing generator {}: {e}", generator.id);
+    }
+}
+
+/// Ingests the batch, the usage it reports is tagged as synthetic
+async fn write_batch(generator: SyntheticGenerator, batch: Batch) -> Result<(), anyhow::Error> {
+    SYNTHETIC
+        .scope(true, async move {
+            match batch {
+                Batch::Logs(records) => {
+                    let body = json::to_vec(&records)?.into();
+                    let resp = crate::service::logs::ingest::ingest(
+                        0,
+                        &generator.org,
+                        &generator.stream,
+                        IngestionRequest::JSON(&body),
+                        &generator.created_by,
+                        None,
+                    )
+                    .await?;
+                    match resp.error {
+                        Some(e) if resp.code != 200 => Err(anyhow::anyhow!(e)),
+                        _ => Ok(()),
+                    }
+                }
+                Batch::Metrics(records) => {
+                    let body = json::to_vec(&records)?.into();
+                    let resp = crate::service::metrics::json::ingest(&generator.org, body).await?;
+                    match resp.error {
+                        Some(e) if resp.code != 200 => Err(anyhow::anyhow!(e)),
+                        _ => Ok(()),
+                    }
+                }
+                Batch::Traces(request) => {
+                    let resp = crate::service::traces::handle_otlp_request(
+                        &generator.org,
+                        request,
+                        config::meta::otlp::OtlpRequestType::Grpc,
+                        Some(&generator.stream),
+                    )
+                    .await?;
+                    if resp.status().is_success() {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("status {}", resp.status()))
+                    }
+                }
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    use config::meta::synthetic::{Cardinality, LogTemplate};
+    use infra::db as infra_db;
+
+    use super::*;
+
+    fn generator(rate_per_sec: u64, duration_secs: u64) -> SyntheticGenerator {
+        new_generator(
+            "default".to_string(),
+            "synthetic".to_string(),
+            rate_per_sec,
+            duration_secs,
+            Some(1),
+            "root@example.com",
+            SyntheticSpec::Logs {
+                template: LogTemplate::Nginx,
+                cardinality: Cardinality::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&generator(10, 60)).is_ok());
+        assert!(validate(&generator(0, 60)).is_err());
+        assert!(validate(&generator(10, 0)).is_err());
+        let max_rate = get_config().limit.synthetic_max_rate;
+        assert!(validate(&generator(max_rate + 1, 60)).is_err());
+
+        let mut traces = generator(max_rate / 7, 60);
+        traces.spec = SyntheticSpec::Traces {
+            depth: 3,
+            fanout: 2,
+            cardinality: Cardinality::default(),
+        };
+        assert!(validate(&traces).is_ok());
+        traces.rate_per_sec += 1;
+        assert!(validate(&traces).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_start_and_cancel() {
+        infra_db::create_table().await.unwrap();
+        let written = Arc::new(AtomicU64::new(0));
+        let counter = written.clone();
+        let (generator, handle) = spawn(generator(5, 600), move |batch| {
+            let counter = counter.clone();
+            async move {
+                if let Batch::Logs(records) = batch {
+                    counter.fetch_add(records.len() as u64, Ordering::SeqCst);
+                }
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+
+        // the first batch is written right away
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while written.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(written.load(Ordering::SeqCst) >= 5);
+        assert!(list().await.unwrap().iter().any(|g| g.id == generator.id));
+
+        assert!(cancel(&generator.id).await.unwrap());
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!list().await.unwrap().iter().any(|g| g.id == generator.id));
+        assert!(!cancel(&generator.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_reaps_orphans() {
+        infra_db::create_table().await.unwrap();
+        // left over by a restart of this node
+        let restarted = generator(5, 600);
+        db::synthetic::set(&restarted).await.unwrap();
+        // started by a node which left the cluster
+        let mut gone = generator(5, 600);
+        gone.node = "synthetic-gone-node".to_string();
+        db::synthetic::set(&gone).await.unwrap();
+
+        let running = list().await.unwrap();
+        assert!(!running
+            .iter()
+            .any(|g| g.id == restarted.id || g.id == gone.id));
+        assert!(db::synthetic::get(&restarted.id).await.unwrap().is_none());
+        assert!(db::synthetic::get(&gone.id).await.unwrap().is_none());
+    }
+}
@@ -29,7 +29,7 @@ use config::{
     },
     metrics,
     utils::{flatten, json, schema_ext::SchemaExt},
-    DISTINCT_FIELDS, TIMESTAMP_COL_NAME,
+    TIMESTAMP_COL_NAME,
 };
 use hashbrown::HashSet;
 use infra::schema::{unwrap_partition_time_level, SchemaCache};
@@ -41,7 +41,6 @@ use opentelemetry_proto::tonic::{
     trace::v1::{status::StatusCode, Status},
 };
 use prost::Message;
-use serde_json::Map;
 
 use super::{
     logs::O2IngestJsonData, metadata::distinct_values::DISTINCT_STREAM_PREFIX,
@@ -58,8 +57,8 @@ use crate::{
         db, format_stream_name,
         ingestion::{evaluate_trigger, grpc::get_val, write_file, TriggerAlertData},
         metadata::{
-            distinct_values::DvItem, trace_list_index::TraceListItem, write, MetadataItem,
-            MetadataType,
+            distinct_values::DvItem, service_latency_index::ServiceLatencyItem,
+            trace_list_index::TraceListItem, write, MetadataItem, MetadataType,
         },
         schema::{check_for_schema, stream_schema_exists},
         self_reporting::report_request_usage_stats,
@@ -869,29 +868,20 @@ async fn write_traces(
     let mut data_buf: HashMap<String, SchemaRecords> = HashMap::new();
     let mut distinct_values = Vec::with_capacity(16);
     let mut trace_index_values = Vec::with_capacity(json_data.len());
+    let mut latency_values = Vec::with_capacity(json_data.len());
 
     // Start write data
     for (timestamp, record_val) in json_data {
         // get service_name
         let service_name = json::get_string_value(record_val.get("service_name").unwrap());
         // get distinct_value item
-        let mut map = Map::new();
-        for field in DISTINCT_FIELDS.iter().chain(
-            stream_settings
-                .distinct_value_fields
-                .iter()
-                .map(|f| &f.name),
+        if let Some(item) = DvItem::from_record(
+            StreamType::Traces,
+            stream_name,
+            &stream_settings,
+            &record_val,
         ) {
-            if let Some(val) = record_val.get(field) {
-                map.insert(field.clone(), val.clone());
-            }
-        }
-        if !map.is_empty() {
-            distinct_values.push(MetadataItem::DistinctValues(DvItem {
-                stream_type: StreamType::Traces,
-                stream_name: stream_name.to_string(),
-                value: map,
-            }));
+            distinct_values.push(MetadataItem::DistinctValues(item));
         }
 
         // build trace metadata
@@ -907,6 +897,22 @@ async fn write_traces(
             service_name: service_name.to_string(),
             trace_id,
         }));
+        latency_values.push(MetadataItem::ServiceLatencyIndex(ServiceLatencyItem {
+            _timestamp: timestamp,
+            stream_name: stream_name.to_string(),
+            service_name: service_name.to_string(),
+            operation_name: record_val
+                .get("operation_name")
+                .map(json::get_string_value)
+                .unwrap_or_default(),
+            duration: record_val
+                .get("duration")
+                .map(json::get_int_value)
+                .unwrap_or_default(),
+            is_error: record_val
+                .get("span_status")
+                .is_some_and(|v| v.as_str() == Some("ERROR")),
+        }));
 
         // Start check for alert trigger
         if let Some(alerts) = cur_stream_alerts {
@@ -985,6 +991,13 @@ async fn write_traces(
         }
     }
 
+    // send service latency metadata
+    if !latency_values.is_empty() {
+        if let Err(e) = write(org_id, MetadataType::ServiceLatencyIndex, latency_values).await {
+            log::error!("Error while writing service latency values: {}", e);
+        }
+    }
+
     // only one trigger per request
     evaluate_trigger(triggers).await;
 
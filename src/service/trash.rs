@@ -0,0 +1,219 @@
+// Copyright 2025 OpenObserve Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The trash keeps deleted dashboards and alerts for
+//! `ZO_TRASH_RETENTION_DAYS` so that they can be restored, after which they
+//! are purged for good.
+
+use config::{
+    get_config,
+    meta::{
+        folder::{Folder, FolderType, DEFAULT_FOLDER},
+        trash::{TrashItem, TrashItemType},
+    },
+};
+use infra::{
+    db::{connect_to_orm, ORM_CLIENT},
+    table,
+};
+use svix_ksuid::Ksuid;
+
+use super::{
+    alerts::alert::{self, AlertError},
+    dashboards::{self, DashboardError},
+    folders::{self, is_folder_permitted, permitted_folders},
+};
+
+/// Errors that can occur when interacting with the trash.
+#[derive(Debug, thiserror::Error)]
+pub enum TrashError {
+    /// An error that occurs while interacting with the database through the
+    /// [infra] crate.
+    #[error("InfraError# {0}")]
+    InfraError(#[from] infra::errors::Error),
+
+    /// An error that occurs while restoring or purging a dashboard.
+    #[error("DashboardError# {0}")]
+    DashboardError(#[from] DashboardError),
+
+    /// An error that occurs while restoring or purging an alert.
+    #[error("AlertError# {0}")]
+    AlertError(#[from] AlertError),
+
+    /// An error that occurs when trying to restore an item that is not in the
+    /// trash.
+    #[error("Item not found in trash")]
+    NotFound,
+
+    /// An error that occurs when the default folder that receives the trashed
+    /// items of a deleted folder cannot be created.
+    #[error("Error saving default folder")]
+    CreateDefaultFolder,
+
+    /// An error that occurs when the folders the user is permitted to access
+    /// cannot be determined.
+    #[error("PermittedFolders# {0}")]
+    PermittedFolders(String),
+}
+
+/// Lists the items in the trash of the organization that are in a folder the
+/// user is permitted to access, most recently deleted first. Lists both
+/// dashboards and alerts unless `item_type` is given.
+pub async fn list(
+    org_id: &str,
+    user_id: Option<&str>,
+    item_type: Option<TrashItemType>,
+) -> Result<Vec<TrashItem>, TrashError> {
+    let permitted_folders = permitted_folders(org_id, user_id)
+        .await
+        .map_err(|e| TrashError::PermittedFolders(e.to_string()))?;
+    let mut items = vec![];
+    if item_type.is_none() || item_type == Some(TrashItemType::Dashboard) {
+        items.extend(table::dashboards::list_trashed(org_id).await?);
+    }
+    if item_type.is_none() || item_type == Some(TrashItemType::Alert) {
+        let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+        items.extend(table::alerts::list_trashed(client, org_id).await?);
+    }
+    items.retain(|item| is_folder_permitted(permitted_folders.as_deref(), org_id, &item.folder_id));
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+/// Restores an item from the trash into the folder it was deleted from, or
+/// into the default folder if that folder has been deleted since. Returns the
+/// type of the restored item and the folder it was restored to.
+///
+/// Only the items that [list] returns to the user can be restored. When no
+/// `item_type` is given the ID is looked up among the trashed dashboards first
+/// and then among the trashed alerts.
+pub async fn restore(
+    org_id: &str,
+    user_id: Option<&str>,
+    id: &str,
+    item_type: Option<TrashItemType>,
+) -> Result<(TrashItemType, Folder), TrashError> {
+    let items = list(org_id, user_id, item_type).await?;
+    let is_listed = |item_type| items.iter().any(|i| i.id == id && i.item_type == item_type);
+    if is_listed(TrashItemType::Dashboard) {
+        match dashboards::restore_dashboard(org_id, id).await {
+            Ok((folder, _dashboard)) => return Ok((TrashItemType::Dashboard, folder)),
+            Err(DashboardError::DashboardNotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if is_listed(TrashItemType::Alert) {
+        if let Ok(alert_id) = id.parse::<Ksuid>() {
+            let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+            match alert::restore_by_id(client, org_id, alert_id).await {
+                Ok((folder, _alert)) => return Ok((TrashItemType::Alert, folder)),
+                Err(AlertError::AlertNotFound) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    Err(TrashError::NotFound)
+}
+
+/// Returns the unix timestamp in microseconds before which trashed items are
+/// past the retention period at `now`.
+pub fn retention_cutoff(now: i64) -> i64 {
+    now - expires_after()
+}
+
+/// Returns the retention period of trashed items in microseconds.
+pub fn expires_after() -> i64 {
+    get_config().limit.trash_retention_days * 24 * 3600 * 1_000_000
+}
+
+/// Permanently deletes the items that were moved to the trash before
+/// `deleted_before` (unix timestamp in microseconds), along with their
+/// dependent rows. Items restored since they were listed are left alone.
+/// Returns the number of purged items.
+pub async fn purge(deleted_before: i64) -> Result<usize, TrashError> {
+    let mut purged = 0;
+    for (org_id, folder_id, dashboard_id) in
+        table::dashboards::list_trashed_before(deleted_before).await?
+    {
+        match dashboards::purge_dashboard(&org_id, &folder_id, &dashboard_id, deleted_before).await
+        {
+            Ok(true) => purged += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("[TRASH] Failed to purge dashboard {dashboard_id}: {e}"),
+        }
+    }
+
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    for alert in table::alerts::list_trashed_before(client, deleted_before).await? {
+        match alert::purge(client, &alert.org_id, &alert, deleted_before).await {
+            Ok(true) => purged += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("[TRASH] Failed to purge alert {}: {e}", alert.name),
+        }
+    }
+    Ok(purged)
+}
+
+/// Prepares the trashed items of a folder for the deletion of the folder.
+///
+/// The items are moved into the default folder so that they can still be
+/// restored. The trashed items of the default folder itself have nowhere to go
+/// and are purged.
+pub(crate) async fn release_folder(
+    org_id: &str,
+    folder_id: &str,
+    folder_type: FolderType,
+) -> Result<(), TrashError> {
+    let client = ORM_CLIENT.get_or_init(connect_to_orm).await;
+    if folder_id == DEFAULT_FOLDER {
+        match folder_type {
+            FolderType::Dashboards => {
+                for dashboard_id in
+                    table::dashboards::list_trashed_in_folder(org_id, folder_id).await?
+                {
+                    dashboards::purge_dashboard(org_id, folder_id, &dashboard_id, i64::MAX).await?;
+                }
+            }
+            FolderType::Alerts => {
+                for alert in
+                    table::alerts::list_trashed_in_folder(client, org_id, folder_id).await?
+                {
+                    alert::purge(client, org_id, &alert, i64::MAX).await?;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if !table::folders::exists(org_id, DEFAULT_FOLDER, folder_type).await? {
+        let default_folder = Folder {
+            folder_id: DEFAULT_FOLDER.to_owned(),
+            name: DEFAULT_FOLDER.to_owned(),
+            description: DEFAULT_FOLDER.to_owned(),
+        };
+        folders::save_folder(org_id, default_folder, folder_type, true)
+            .await
+            .map_err(|_| TrashError::CreateDefaultFolder)?;
+    }
+    match folder_type {
+        FolderType::Dashboards => {
+            dashboards::move_trashed_dashboards(org_id, folder_id, DEFAULT_FOLDER).await?;
+        }
+        FolderType::Alerts => {
+            table::alerts::move_trashed(client, org_id, folder_id, DEFAULT_FOLDER).await?;
+        }
+    }
+    Ok(())
+}
@@ -13,7 +13,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Error;
+use std::{
+    collections::{HashMap, HashSet},
+    io::Error,
+};
 
 use actix_web::{http, HttpResponse};
 use config::{get_config, ider, utils::rand::generate_random_string};
@@ -30,7 +33,9 @@ use crate::{
             http::HttpResponse as MetaHttpResponse,
             organization::DEFAULT_ORG,
             user::{
-                DBUser, UpdateUser, User, UserList, UserOrg, UserRequest, UserResponse, UserRole,
+                BulkUserAction, BulkUserStatus, BulkUserUpdateItem, BulkUserUpdateRequest,
+                BulkUserUpdateResponse, DBUser, UpdateUser, User, UserList, UserOrg, UserRequest,
+                UserResponse, UserRole,
             },
         },
         utils::auth::{get_hash, get_role, is_root_user},
@@ -38,39 +43,24 @@ use crate::{
     service::db,
 };
 
-pub async fn post_user(
-    org_id: &str,
-    usr_req: UserRequest,
-    initiator_id: &str,
-) -> Result<HttpResponse, Error> {
+/// Whether the address looks like an email the users service accepts
+pub(crate) fn is_valid_email(email: &str) -> bool {
     let email_regex = Regex::new(
         r"^([a-z0-9_+]([a-z0-9_+.-]*[a-z0-9_+])?)@([a-z0-9]+([\-\.]{1}[a-z0-9]+)*\.[a-z]{2,6})",
     )
     .expect("Email regex is valid");
-    if !email_regex.is_match(&usr_req.email) {
-        return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
-            http::StatusCode::BAD_REQUEST.into(),
-            "Invalid email".to_string(),
-        )));
-    }
-    let cfg = get_config();
+    email_regex.is_match(email)
+}
+
+/// Whether the initiator may manage the users of the org
+pub(crate) async fn can_manage_users(org_id: &str, initiator_id: &str) -> bool {
     let is_allowed = if is_root_user(initiator_id) {
         true
     } else {
-        let initiator_user = db::user::get(Some(org_id), initiator_id).await;
-        let Ok(initiator_user) = initiator_user else {
-            return Ok(HttpResponse::Unauthorized().json(MetaHttpResponse::error(
-                http::StatusCode::UNAUTHORIZED.into(),
-                "Not Allowed".to_string(),
-            )));
-        };
-        let Some(initiator_user) = initiator_user else {
-            return Ok(HttpResponse::Unauthorized().json(MetaHttpResponse::error(
-                http::StatusCode::UNAUTHORIZED.into(),
-                "Not Allowed".to_string(),
-            )));
-        };
-        initiator_user.role.eq(&UserRole::Admin)
+        match db::user::get(Some(org_id), initiator_id).await {
+            Ok(Some(initiator_user)) => initiator_user.role.eq(&UserRole::Admin),
+            _ => false,
+        }
     };
 
     #[cfg(feature = "enterprise")]
@@ -81,70 +71,37 @@ pub async fn post_user(
         is_allowed
     };
 
-    if is_allowed {
+    is_allowed
+}
+
+pub async fn post_user(
+    org_id: &str,
+    usr_req: UserRequest,
+    initiator_id: &str,
+) -> Result<HttpResponse, Error> {
+    if !is_valid_email(&usr_req.email) {
+        return Ok(HttpResponse::BadRequest().json(MetaHttpResponse::error(
+            http::StatusCode::BAD_REQUEST.into(),
+            "Invalid email".to_string(),
+        )));
+    }
+    if !is_root_user(initiator_id)
+        && !matches!(db::user::get(Some(org_id), initiator_id).await, Ok(Some(_)))
+    {
+        return Ok(HttpResponse::Unauthorized().json(MetaHttpResponse::error(
+            http::StatusCode::UNAUTHORIZED.into(),
+            "Not Allowed".to_string(),
+        )));
+    }
+
+    if can_manage_users(org_id, initiator_id).await {
         let existing_user = if is_root_user(&usr_req.email) {
             db::user::get(None, &usr_req.email).await
         } else {
             db::user::get(Some(org_id), &usr_req.email).await
         };
         if existing_user.is_err() {
-            let salt = ider::uuid();
-            let password = get_hash(&usr_req.password, &salt);
-            let password_ext = get_hash(&usr_req.password, &cfg.auth.ext_auth_salt);
-            let token = generate_random_string(16);
-            let rum_token = format!("rum{}", generate_random_string(16));
-            let org_id = org_id.replace(' ', "_");
-            let user = usr_req.to_new_dbuser(
-                password,
-                salt,
-                org_id.clone(),
-                token,
-                rum_token,
-                usr_req.is_external,
-                password_ext,
-            );
-            db::user::set(&user).await.unwrap();
-            // Update OFGA
-            #[cfg(feature = "enterprise")]
-            {
-                use o2_openfga::{
-                    authorizer::authz::{
-                        get_org_creation_tuples, get_service_account_creation_tuple,
-                        get_user_role_tuple, update_tuples,
-                    },
-                    meta::mapping::{NON_OWNING_ORG, OFGA_MODELS},
-                };
-                if get_openfga_config().enabled {
-                    let mut tuples = vec![];
-                    get_user_role_tuple(
-                        &usr_req.role.to_string(),
-                        &usr_req.email,
-                        &org_id,
-                        &mut tuples,
-                    );
-                    if usr_req.role.eq(&UserRole::ServiceAccount) {
-                        get_service_account_creation_tuple(&org_id, &usr_req.email, &mut tuples);
-                    }
-                    get_org_creation_tuples(
-                        &org_id,
-                        &mut tuples,
-                        OFGA_MODELS
-                            .iter()
-                            .map(|(_, fga_entity)| fga_entity.key)
-                            .collect(),
-                        NON_OWNING_ORG.to_vec(),
-                    )
-                    .await;
-                    match update_tuples(tuples, vec![]).await {
-                        Ok(_) => {
-                            log::info!("User saved successfully in openfga");
-                        }
-                        Err(e) => {
-                            log::error!("Error creating user in openfga: {}", e);
-                        }
-                    }
-                }
-            }
+            create_user(org_id, &usr_req).await.unwrap();
             Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
                 http::StatusCode::OK.into(),
                 "User saved successfully".to_string(),
@@ -163,6 +120,69 @@ pub async fn post_user(
     }
 }
 
+/// Creates the user as a member of the org with the requested role
+pub(crate) async fn create_user(org_id: &str, usr_req: &UserRequest) -> Result<(), anyhow::Error> {
+    let cfg = get_config();
+    let salt = ider::uuid();
+    let password = get_hash(&usr_req.password, &salt);
+    let password_ext = get_hash(&usr_req.password, &cfg.auth.ext_auth_salt);
+    let token = generate_random_string(16);
+    let rum_token = format!("rum{}", generate_random_string(16));
+    let org_id = org_id.replace(' ', "_");
+    let user = usr_req.to_new_dbuser(
+        password,
+        salt,
+        org_id.clone(),
+        token,
+        rum_token,
+        usr_req.is_external,
+        password_ext,
+    );
+    db::user::set(&user).await?;
+    // Update OFGA
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_openfga::{
+            authorizer::authz::{
+                get_org_creation_tuples, get_service_account_creation_tuple, get_user_role_tuple,
+                update_tuples,
+            },
+            meta::mapping::{NON_OWNING_ORG, OFGA_MODELS},
+        };
+        if get_openfga_config().enabled {
+            let mut tuples = vec![];
+            get_user_role_tuple(
+                &usr_req.role.to_string(),
+                &usr_req.email,
+                &org_id,
+                &mut tuples,
+            );
+            if usr_req.role.eq(&UserRole::ServiceAccount) {
+                get_service_account_creation_tuple(&org_id, &usr_req.email, &mut tuples);
+            }
+            get_org_creation_tuples(
+                &org_id,
+                &mut tuples,
+                OFGA_MODELS
+                    .iter()
+                    .map(|(_, fga_entity)| fga_entity.key)
+                    .collect(),
+                NON_OWNING_ORG.to_vec(),
+            )
+            .await;
+            match update_tuples(tuples, vec![]).await {
+                Ok(_) => {
+                    log::info!("User saved successfully in openfga");
+                }
+                Err(e) => {
+                    log::error!("Error creating user in openfga: {}", e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub async fn update_db_user(mut db_user: DBUser) -> Result<(), anyhow::Error> {
     if db_user.password.is_empty() {
         let salt = ider::uuid();
@@ -422,72 +442,16 @@ pub async fn add_user_to_org(
         };
         let role = get_role(role);
         if initiating_user.role.eq(&UserRole::Root) || initiating_user.role.eq(&UserRole::Admin) {
-            let token = generate_random_string(16);
-            let rum_token = format!("rum{}", generate_random_string(16));
-            let mut orgs = db_user.clone().organizations;
-            let new_orgs = if orgs.is_empty() {
-                vec![UserOrg {
-                    name: local_org.to_string(),
-                    token,
-                    rum_token: Some(rum_token),
-                    role: role.clone(),
-                }]
-            } else {
-                if db_user.is_external {
-                    for org in orgs.iter() {
-                        if org.name.eq(org_id) {
-                            // External user is already part of this org
-                            return Ok(HttpResponse::Conflict().json(MetaHttpResponse::error(
-                                http::StatusCode::CONFLICT.into(),
-                                "User is already part of the org".to_string(),
-                            )));
-                        }
-                    }
-                }
-                orgs.retain(|org| !org.name.eq(&local_org));
-                orgs.push(UserOrg {
-                    name: local_org.to_string(),
-                    token,
-                    rum_token: Some(rum_token),
-                    role: role.clone(),
-                });
-                orgs
-            };
-            db_user.organizations = new_orgs;
-            db::user::set(&db_user).await.unwrap();
-
-            // Update OFGA
-            #[cfg(feature = "enterprise")]
-            {
-                use o2_openfga::{
-                    authorizer::authz::{
-                        get_org_creation_tuples, get_user_role_tuple, update_tuples,
-                    },
-                    meta::mapping::{NON_OWNING_ORG, OFGA_MODELS},
-                };
-                if get_openfga_config().enabled {
-                    let mut tuples = vec![];
-                    get_user_role_tuple(&role.to_string(), email, org_id, &mut tuples);
-                    get_org_creation_tuples(
-                        org_id,
-                        &mut tuples,
-                        OFGA_MODELS
-                            .iter()
-                            .map(|(_, fga_entity)| fga_entity.key)
-                            .collect(),
-                        NON_OWNING_ORG.to_vec(),
-                    )
-                    .await;
-                    match update_tuples(tuples, vec![]).await {
-                        Ok(_) => {
-                            log::info!("User added to org successfully in openfga");
-                        }
-                        Err(e) => {
-                            log::error!("Error adding user to the org in openfga: {}", e);
-                        }
-                    }
-                }
+            if db_user.is_external && db_user.organizations.iter().any(|org| org.name.eq(org_id)) {
+                // External user is already part of this org
+                return Ok(HttpResponse::Conflict().json(MetaHttpResponse::error(
+                    http::StatusCode::CONFLICT.into(),
+                    "User is already part of the org".to_string(),
+                )));
             }
+            set_user_org_role(&mut db_user, &local_org, role)
+                .await
+                .unwrap();
             Ok(HttpResponse::Ok().json(MetaHttpResponse::message(
                 http::StatusCode::OK.into(),
                 "User added to org successfully".to_string(),
@@ -506,6 +470,63 @@ pub async fn add_user_to_org(
     }
 }
 
+/// Replaces the membership of the user in the org with a new one, with fresh
+/// ingestion tokens
+pub(crate) fn assign_org_role(db_user: &mut DBUser, org_id: &str, role: UserRole) {
+    let token = generate_random_string(16);
+    let rum_token = format!("rum{}", generate_random_string(16));
+    db_user.organizations.retain(|org| !org.name.eq(org_id));
+    db_user.organizations.push(UserOrg {
+        name: org_id.to_string(),
+        token,
+        rum_token: Some(rum_token),
+        role,
+    });
+}
+
+/// Adds the user to the org with the given role, replacing the role the user
+/// had in the org if already a member
+pub(crate) async fn set_user_org_role(
+    db_user: &mut DBUser,
+    org_id: &str,
+    role: UserRole,
+) -> Result<(), anyhow::Error> {
+    assign_org_role(db_user, org_id, role.clone());
+    db::user::set(db_user).await?;
+
+    // Update OFGA
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_openfga::{
+            authorizer::authz::{get_org_creation_tuples, get_user_role_tuple, update_tuples},
+            meta::mapping::{NON_OWNING_ORG, OFGA_MODELS},
+        };
+        if get_openfga_config().enabled {
+            let mut tuples = vec![];
+            get_user_role_tuple(&role.to_string(), &db_user.email, org_id, &mut tuples);
+            get_org_creation_tuples(
+                org_id,
+                &mut tuples,
+                OFGA_MODELS
+                    .iter()
+                    .map(|(_, fga_entity)| fga_entity.key)
+                    .collect(),
+                NON_OWNING_ORG.to_vec(),
+            )
+            .await;
+            match update_tuples(tuples, vec![]).await {
+                Ok(_) => {
+                    log::info!("User added to org successfully in openfga");
+                }
+                Err(e) => {
+                    log::error!("Error adding user to the org in openfga: {}", e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub async fn get_user(org_id: Option<&str>, name: &str) -> Option<User> {
     let key = match org_id {
         Some(local_org) => format!("{local_org}/{name}"),
@@ -742,6 +763,299 @@ pub async fn remove_user_from_org(
     }
 }
 
+fn is_org_admin(role: &UserRole) -> bool {
+    role.eq(&UserRole::Admin) || role.eq(&UserRole::Root)
+}
+
+/// Roles of the members of the org, keyed by email
+fn org_member_roles(org_id: &str) -> HashMap<String, UserRole> {
+    USERS
+        .iter()
+        .filter(|user| user.key().starts_with(&format!("{org_id}/")))
+        .map(|user| (user.value().email.clone(), user.value().role.clone()))
+        .collect()
+}
+
+/// Computes current vs proposed role for every entry of a bulk update without
+/// touching any state. Role changes come first, then removals, in request order.
+///
+/// Granting or revoking admin needs a root initiator, and the last admin/root
+/// of the org can neither be demoted nor removed.
+pub(crate) fn plan_bulk_update(
+    initiator_role: &UserRole,
+    members: &HashMap<String, UserRole>,
+    req: &BulkUserUpdateRequest,
+) -> Vec<BulkUserUpdateItem> {
+    let entries = req
+        .changes
+        .iter()
+        .map(|change| (change.email.trim().to_string(), Some(change.role.clone())))
+        .chain(
+            req.remove
+                .iter()
+                .map(|email| (email.trim().to_string(), None)),
+        );
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    for (email, proposed_role) in entries {
+        let current_role = members.get(&email).cloned();
+        let action = if proposed_role.is_some() {
+            BulkUserAction::Update
+        } else {
+            BulkUserAction::Remove
+        };
+        let touches_admin = current_role.as_ref().is_some_and(is_org_admin)
+            || proposed_role.as_ref().is_some_and(is_org_admin);
+        let (status, message) = if !seen.insert(email.clone()) {
+            (
+                BulkUserStatus::Rejected,
+                Some("User is listed more than once".to_string()),
+            )
+        } else if current_role.is_none() {
+            (
+                BulkUserStatus::UnknownUser,
+                Some("User for the organization not found".to_string()),
+            )
+        } else if current_role == Some(UserRole::Root) || proposed_role == Some(UserRole::Root) {
+            (
+                BulkUserStatus::Rejected,
+                Some("Root user can not be changed".to_string()),
+            )
+        } else if current_role == Some(UserRole::ServiceAccount)
+            || proposed_role == Some(UserRole::ServiceAccount)
+        {
+            (
+                BulkUserStatus::Rejected,
+                Some("Service accounts are managed separately".to_string()),
+            )
+        } else if proposed_role.is_some() && proposed_role == current_role {
+            (BulkUserStatus::Unchanged, None)
+        } else if touches_admin && !initiator_role.eq(&UserRole::Root) {
+            (
+                BulkUserStatus::Rejected,
+                Some("Only root can grant or revoke the admin role".to_string()),
+            )
+        } else {
+            (BulkUserStatus::Planned, None)
+        };
+        items.push(BulkUserUpdateItem {
+            email,
+            action,
+            current_role,
+            proposed_role,
+            status,
+            message,
+        });
+    }
+
+    // promotions in the same request count towards the remaining admins
+    let mut admins = members.values().filter(|role| is_org_admin(role)).count()
+        + items
+            .iter()
+            .filter(|item| item.status == BulkUserStatus::Planned && is_promotion(item))
+            .count();
+    for item in items.iter_mut() {
+        if item.status == BulkUserStatus::Planned && is_demotion(item) {
+            if admins <= 1 {
+                item.status = BulkUserStatus::Rejected;
+                item.message = Some("Can not remove or demote the last admin".to_string());
+            } else {
+                admins -= 1;
+            }
+        }
+    }
+    items
+}
+
+fn is_promotion(item: &BulkUserUpdateItem) -> bool {
+    !item.current_role.as_ref().is_some_and(is_org_admin)
+        && item.proposed_role.as_ref().is_some_and(is_org_admin)
+}
+
+fn is_demotion(item: &BulkUserUpdateItem) -> bool {
+    item.current_role.as_ref().is_some_and(is_org_admin)
+        && !item.proposed_role.as_ref().is_some_and(is_org_admin)
+}
+
+/// Plans a bulk update and, unless `dry_run` is set, applies it user by user.
+///
+/// Promotions are applied first so the last-admin guardrail, which is checked
+/// again against what actually got applied, never locks the org out.
+pub(crate) async fn execute_bulk_update(
+    org_id: &str,
+    initiator_id: &str,
+    initiator_role: &UserRole,
+    req: &BulkUserUpdateRequest,
+    dry_run: bool,
+) -> BulkUserUpdateResponse {
+    let members = org_member_roles(org_id);
+    let mut items = plan_bulk_update(initiator_role, &members, req);
+    if dry_run {
+        return BulkUserUpdateResponse { dry_run, items };
+    }
+
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by_key(|idx| !is_promotion(&items[*idx]));
+    let mut admins = members.values().filter(|role| is_org_admin(role)).count();
+    for idx in order {
+        let item = &mut items[idx];
+        if item.status != BulkUserStatus::Planned {
+            continue;
+        }
+        if is_demotion(item) && admins <= 1 {
+            item.status = BulkUserStatus::Rejected;
+            item.message = Some("Can not remove or demote the last admin".to_string());
+            continue;
+        }
+        let ret = match item.action {
+            BulkUserAction::Update => {
+                set_user_org_role(org_id, &item.email, item.proposed_role.as_ref().unwrap()).await
+            }
+            BulkUserAction::Remove => remove_org_member(org_id, &item.email, initiator_id).await,
+        };
+        match ret {
+            Ok(()) => {
+                item.status = BulkUserStatus::Applied;
+                if is_promotion(item) {
+                    admins += 1;
+                } else if is_demotion(item) {
+                    admins -= 1;
+                }
+            }
+            Err(e) => {
+                log::error!(
+                    "bulk update of user {} in org {org_id} failed: {e}",
+                    item.email
+                );
+                item.status = BulkUserStatus::Failed;
+                item.message = Some(e.to_string());
+            }
+        }
+        #[cfg(feature = "enterprise")]
+        audit_bulk_update_item(org_id, initiator_id, item).await;
+    }
+    BulkUserUpdateResponse { dry_run, items }
+}
+
+pub async fn bulk_update_users(
+    org_id: &str,
+    initiator_id: &str,
+    req: BulkUserUpdateRequest,
+    dry_run: bool,
+) -> Result<HttpResponse, Error> {
+    let initiator_role = if is_root_user(initiator_id) {
+        Some(UserRole::Root)
+    } else {
+        match db::user::get(Some(org_id), initiator_id).await {
+            Ok(Some(user)) => Some(user.role),
+            _ => None,
+        }
+    };
+    let initiator_role = match initiator_role {
+        Some(role) if is_org_admin(&role) => role,
+        _ => {
+            return Ok(HttpResponse::Forbidden().json(MetaHttpResponse::error(
+                http::StatusCode::FORBIDDEN.into(),
+                "Not Allowed".to_string(),
+            )));
+        }
+    };
+    let resp = execute_bulk_update(org_id, initiator_id, &initiator_role, &req, dry_run).await;
+    Ok(HttpResponse::Ok().json(resp))
+}
+
+/// Changes the role of an existing member, refreshing the users cache so the
+/// user's next request is authorized with the new role
+async fn set_user_org_role(
+    org_id: &str,
+    email: &str,
+    role: &UserRole,
+) -> Result<(), anyhow::Error> {
+    let mut db_user = db::user::get_db_user(email).await?;
+    let Some(org) = db_user
+        .organizations
+        .iter_mut()
+        .find(|org| org.name.eq(org_id))
+    else {
+        anyhow::bail!("User for the organization not found");
+    };
+    let _old_role = std::mem::replace(&mut org.role, role.clone());
+    db::user::set(&db_user).await?;
+
+    #[cfg(feature = "enterprise")]
+    {
+        use o2_openfga::authorizer::authz::update_user_role;
+
+        let old_str = openfga_role(&_old_role);
+        let new_str = openfga_role(role);
+        if get_openfga_config().enabled && old_str != new_str {
+            log::debug!("updating openfga role for {email} from {old_str} to {new_str}");
+            update_user_role(&old_str, &new_str, email, org_id).await;
+        }
+    }
+    Ok(())
+}
+
+/// Removes a member from the org and drops its cached entries right away, the
+/// watcher only catches up asynchronously
+async fn remove_org_member(
+    org_id: &str,
+    email: &str,
+    initiator_id: &str,
+) -> Result<(), anyhow::Error> {
+    let cached = USERS.get(&format!("{org_id}/{email}")).map(|u| u.clone());
+    let resp = remove_user_from_org(org_id, email, initiator_id).await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("Failed to remove user from organization: {}", resp.status());
+    }
+    USERS.remove(&format!("{org_id}/{email}"));
+    if let Some(rum_token) = cached.and_then(|u| u.rum_token) {
+        USERS_RUM_TOKEN.remove(&format!("{org_id}/{rum_token}"));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "enterprise")]
+fn openfga_role(role: &UserRole) -> String {
+    if role.eq(&UserRole::User) || role.eq(&UserRole::ServiceAccount) {
+        "allowed_user".to_string()
+    } else {
+        role.to_string()
+    }
+}
+
+#[cfg(feature = "enterprise")]
+async fn audit_bulk_update_item(org_id: &str, initiator_id: &str, item: &BulkUserUpdateItem) {
+    use o2_enterprise::enterprise::common::auditor::{AuditMessage, HttpMeta, Protocol};
+
+    let (method, body) = match item.action {
+        BulkUserAction::Update => (
+            "PUT",
+            config::utils::json::json!({ "role": item.proposed_role }).to_string(),
+        ),
+        BulkUserAction::Remove => ("DELETE", "".to_string()),
+    };
+    let response_code = if item.status == BulkUserStatus::Applied {
+        200
+    } else {
+        500
+    };
+    crate::service::self_reporting::audit(AuditMessage {
+        user_email: initiator_id.to_string(),
+        org_id: org_id.to_string(),
+        _timestamp: chrono::Utc::now().timestamp_micros(),
+        protocol: Protocol::Http(HttpMeta {
+            method: method.to_string(),
+            path: format!("/api/{org_id}/users/{}", item.email),
+            body,
+            query_params: "bulk_update=true".to_string(),
+            response_code,
+        }),
+    })
+    .await;
+}
+
 pub async fn delete_user(email_id: &str) -> Result<HttpResponse, Error> {
     let result = db::user::delete(email_id).await;
     match result {
@@ -920,4 +1234,153 @@ mod tests {
 
         assert!(resp.is_ok());
     }
+
+    fn bulk_request(changes: &[(&str, UserRole)], remove: &[&str]) -> BulkUserUpdateRequest {
+        BulkUserUpdateRequest {
+            changes: changes
+                .iter()
+                .map(
+                    |(email, role)| crate::common::meta::user::BulkUserRoleChange {
+                        email: email.to_string(),
+                        role: role.clone(),
+                    },
+                )
+                .collect(),
+            remove: remove.iter().map(|email| email.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_plan_bulk_update_last_admin_guardrail() {
+        let members = HashMap::from([
+            ("a1@zo.dev".to_string(), UserRole::Admin),
+            ("a2@zo.dev".to_string(), UserRole::Admin),
+            ("m1@zo.dev".to_string(), UserRole::Member),
+        ]);
+
+        // the second admin to go is refused, whatever the action
+        let req = bulk_request(&[("a1@zo.dev", UserRole::Member)], &["a2@zo.dev"]);
+        let items = plan_bulk_update(&UserRole::Root, &members, &req);
+        assert_eq!(items[0].status, BulkUserStatus::Planned);
+        assert_eq!(items[1].status, BulkUserStatus::Rejected);
+
+        // a promotion in the same request keeps an admin around
+        let req = bulk_request(
+            &[
+                ("a1@zo.dev", UserRole::Member),
+                ("m1@zo.dev", UserRole::Admin),
+            ],
+            &["a2@zo.dev"],
+        );
+        let items = plan_bulk_update(&UserRole::Root, &members, &req);
+        assert!(items.iter().all(|i| i.status == BulkUserStatus::Planned));
+
+        let single = HashMap::from([("a1@zo.dev".to_string(), UserRole::Admin)]);
+        let req = bulk_request(&[], &["a1@zo.dev"]);
+        let items = plan_bulk_update(&UserRole::Root, &single, &req);
+        assert_eq!(items[0].status, BulkUserStatus::Rejected);
+    }
+
+    #[test]
+    fn test_plan_bulk_update_permissions() {
+        let members = HashMap::from([
+            ("a1@zo.dev".to_string(), UserRole::Admin),
+            ("a2@zo.dev".to_string(), UserRole::Admin),
+            ("m1@zo.dev".to_string(), UserRole::Member),
+            ("root@zo.dev".to_string(), UserRole::Root),
+        ]);
+        let req = bulk_request(
+            &[
+                ("m1@zo.dev", UserRole::Admin),
+                ("a2@zo.dev", UserRole::Member),
+                ("root@zo.dev", UserRole::Member),
+                ("ghost@zo.dev", UserRole::Member),
+            ],
+            &["m1@zo.dev"],
+        );
+        let items = plan_bulk_update(&UserRole::Admin, &members, &req);
+        let statuses: Vec<_> = items.iter().map(|i| i.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BulkUserStatus::Rejected,
+                BulkUserStatus::Rejected,
+                BulkUserStatus::Rejected,
+                BulkUserStatus::UnknownUser,
+                BulkUserStatus::Rejected,
+            ]
+        );
+        assert_eq!(items[0].current_role, Some(UserRole::Member));
+        assert_eq!(items[0].proposed_role, Some(UserRole::Admin));
+        assert_eq!(items[3].current_role, None);
+
+        // admins still manage lower roles
+        let req = bulk_request(&[], &["m1@zo.dev"]);
+        let items = plan_bulk_update(&UserRole::Admin, &members, &req);
+        assert_eq!(items[0].status, BulkUserStatus::Planned);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_dry_run_and_cache() {
+        infra_db::create_table().await.unwrap();
+        let org_id = "bulk_org";
+        for (email, role) in [
+            ("bulk_a1@zo.dev", UserRole::Admin),
+            ("bulk_a2@zo.dev", UserRole::Admin),
+            ("bulk_m1@zo.dev", UserRole::Member),
+        ] {
+            db::user::set(&DBUser {
+                email: email.to_string(),
+                first_name: "".to_string(),
+                last_name: "".to_string(),
+                password: "pass".to_string(),
+                salt: "".to_string(),
+                organizations: vec![UserOrg {
+                    name: org_id.to_string(),
+                    token: "token".to_string(),
+                    rum_token: None,
+                    role,
+                }],
+                is_external: false,
+                password_ext: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        let req = bulk_request(
+            &[
+                ("bulk_a2@zo.dev", UserRole::Member),
+                ("bulk_ghost@zo.dev", UserRole::Member),
+            ],
+            &[],
+        );
+        let planned =
+            execute_bulk_update(org_id, "bulk_a1@zo.dev", &UserRole::Root, &req, true).await;
+        assert!(planned.dry_run);
+        assert_eq!(planned.items[0].status, BulkUserStatus::Planned);
+        assert_eq!(planned.items[1].status, BulkUserStatus::UnknownUser);
+        let user = db::user::get(Some(org_id), "bulk_a2@zo.dev")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.role, UserRole::Admin);
+
+        let applied =
+            execute_bulk_update(org_id, "bulk_a1@zo.dev", &UserRole::Root, &req, false).await;
+        for (plan, done) in planned.items.iter().zip(applied.items.iter()) {
+            assert_eq!(plan.email, done.email);
+            assert_eq!(plan.current_role, done.current_role);
+            assert_eq!(plan.proposed_role, done.proposed_role);
+        }
+        assert_eq!(applied.items[0].status, BulkUserStatus::Applied);
+        assert_eq!(applied.items[1].status, BulkUserStatus::UnknownUser);
+
+        // the demoted user's next lookup already sees the new role
+        let user = db::user::get(Some(org_id), "bulk_a2@zo.dev")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.role, UserRole::Member);
+    }
 }
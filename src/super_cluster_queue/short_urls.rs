@@ -19,7 +19,7 @@ use o2_enterprise::enterprise::super_cluster::queue::{Message, MessageType};
 pub(crate) async fn process(msg: Message) -> Result<()> {
     match msg.message_type {
         MessageType::ShortUrlPut => {
-            let short_id = parse_key(&msg.key)?;
+            let (short_id, org_id) = parse_key(&msg.key)?;
             let original_url: String = match msg.value {
                 Some(ref value) => String::from_utf8_lossy(value).to_string(),
                 None => String::new(),
@@ -30,10 +30,10 @@ pub(crate) async fn process(msg: Message) -> Result<()> {
             if infra::table::short_urls::contains(&short_id).await? {
                 return Ok(());
             }
-            infra::table::short_urls::add(&short_id, &original_url).await?;
+            infra::table::short_urls::add(&org_id, &short_id, &original_url).await?;
         }
         MessageType::ShortUrlDelete => {
-            let short_id = parse_key(&msg.key)?;
+            let (short_id, _) = parse_key(&msg.key)?;
             infra::table::short_urls::remove(&short_id).await?;
         }
         _ => {
@@ -48,10 +48,13 @@ pub(crate) async fn process(msg: Message) -> Result<()> {
     Ok(())
 }
 
-fn parse_key(key: &str) -> Result<String> {
+/// Parses the short ID and the org, which is missing from the keys of older
+/// versions, from the key `/short_urls/{short_id}[/{org_id}]`.
+fn parse_key(key: &str) -> Result<(String, String)> {
     let key_columns: Vec<&str> = key.split('/').collect();
     if key_columns.len() < 3 || key_columns[2].is_empty() {
         return Err(Error::Message("Invalid key".to_string()));
     }
-    Ok(key_columns[2].into())
+    let org_id = key_columns.get(3).copied().unwrap_or_default();
+    Ok((key_columns[2].into(), org_id.into()))
 }
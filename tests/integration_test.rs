@@ -25,12 +25,22 @@ mod tests {
     use config::{
         get_config,
         meta::{
-            alerts::{alert::Alert, Operator, QueryCondition, TriggerCondition},
-            dashboards::{v1, Dashboard},
-            triggers::Trigger,
+            alerts::{
+                alert::Alert,
+                state::{AlertState, AlertStateEvent},
+                Operator, QueryCondition, TriggerCondition,
+            },
+            dashboards::{
+                reports::{Report, ReportDashboard},
+                v1, Dashboard,
+            },
+            folder::{Folder, FolderType},
+            stream::StreamType,
+            triggers::{ScheduledTriggerData, Trigger},
         },
         utils::json,
     };
+    use infra::schema::not_found::STREAM_SCHEMAS_NOT_FOUND;
     use openobserve::{
         handler::{
             grpc::{auth::check_auth, flight::FlightServiceImpl},
@@ -59,6 +69,7 @@ mod tests {
             env::set_var("ZO_RESULT_CACHE_ENABLED", "false");
             env::set_var("ZO_PRINT_KEY_SQL", "true");
             env::set_var("ZO_SMTP_ENABLED", "true");
+            env::set_var("ZO_QUERY_FUNCTION_DENIED_ROLES", "member");
 
             env_logger::init_from_env(
                 env_logger::Env::new().default_filter_or(&get_config().log.level),
@@ -137,6 +148,7 @@ mod tests {
         for _i in 0..3 {
             e2e_1_post_bulk().await;
         }
+        e2e_post_bulk_positional_errors().await;
 
         // ingest
         e2e_post_json().await;
@@ -160,6 +172,7 @@ mod tests {
         // search
         e2e_search().await;
         e2e_search_around().await;
+        e2e_search_new_stream_from_another_node().await;
 
         // users
         e2e_post_user().await;
@@ -172,6 +185,7 @@ mod tests {
         e2e_update_user_passcode().await;
         e2e_user_authentication().await;
         e2e_user_authentication_with_error().await;
+        e2e_search_query_fn_permission().await;
 
         // dashboards
         {
@@ -192,9 +206,71 @@ mod tests {
                 e2e_get_dashboard(&board.clone().v1.unwrap().dashboard_id).await,
                 board
             );
-            e2e_delete_dashboard(&board.v1.unwrap().dashboard_id).await;
+            let board_id = board.dashboard_id().unwrap().to_owned();
+            let copy = e2e_duplicate_dashboard(&board_id, "e2e test copy").await;
+            let copy_id = copy.dashboard_id().unwrap().to_owned();
+            assert_ne!(copy_id, board_id);
+            assert_ne!(copy.hash, board.hash);
+            assert_eq!(copy.title(), Some("e2e test copy"));
+            assert_eq!(e2e_get_dashboard(&board_id).await, board);
+
+            // the copy outlives the original
+            e2e_delete_dashboard(&board_id).await;
+            assert_eq!(e2e_get_dashboard(&copy_id).await, copy);
+            assert_eq!(e2e_list_dashboards().await, vec![copy.clone()]);
+
+            // the original waits in the trash and can be restored
+            let trash = e2e_list_trash("dashboard").await;
+            assert_eq!(trash.len(), 1);
+            assert_eq!(trash[0]["id"], board_id.as_str());
+            assert_eq!(trash[0]["folderId"], "default");
+            assert_eq!(trash[0]["deletedBy"], "root@example.com");
+            assert_eq!(e2e_restore_trash_item(&board_id).await, 200);
+            assert!(e2e_list_trash("dashboard").await.is_empty());
+            assert_eq!(e2e_get_dashboard(&board_id).await, board);
+            assert_eq!(e2e_restore_trash_item(&board_id).await, 404);
+
+            // dashboards which aren't in the trash are never purged
+            let now = Utc::now().timestamp_micros();
+            assert!(!openobserve::service::dashboards::purge_dashboard(
+                "e2e",
+                "default",
+                &board_id,
+                now + 1
+            )
+            .await
+            .unwrap());
+            assert_eq!(e2e_get_dashboard(&board_id).await, board);
+
+            // trashed dashboards are purged once past the retention period
+            e2e_delete_dashboard(&board_id).await;
+            e2e_delete_dashboard(&copy_id).await;
             assert!(e2e_list_dashboards().await.is_empty());
+            assert_eq!(e2e_list_trash("dashboard").await.len(), 2);
+            let now = Utc::now().timestamp_micros();
+            let purged = openobserve::service::trash::purge(
+                openobserve::service::trash::retention_cutoff(now),
+            )
+            .await
+            .unwrap();
+            assert_eq!(purged, 0);
+            assert_eq!(e2e_list_trash("dashboard").await.len(), 2);
+            let purged = openobserve::service::trash::purge(now + 1).await.unwrap();
+            assert_eq!(purged, 2);
+            assert!(e2e_list_trash("dashboard").await.is_empty());
+            assert_eq!(e2e_restore_trash_item(&board_id).await, 404);
         }
+        e2e_delete_folder_with_contents().await;
+
+        // short urls
+        e2e_short_urls().await;
+
+        // search queue
+        #[cfg(not(feature = "enterprise"))]
+        e2e_search_queue_status().await;
+
+        // stream sampling
+        e2e_stream_sampling().await;
 
         // alert
         e2e_post_alert_template().await;
@@ -210,9 +286,11 @@ mod tests {
         e2e_handle_alert_after_destination_retries().await;
         e2e_handle_alert_after_evaluation_retries().await;
         e2e_handle_alert_reached_max_retries().await;
+        e2e_handle_alert_state_events_after_restart().await;
         e2e_list_alerts().await;
         e2e_list_real_time_alerts().await;
         e2e_delete_alert().await;
+        e2e_restore_and_purge_alert().await;
         e2e_delete_alert_destination().await;
         e2e_delete_alert_template().await;
 
@@ -286,6 +364,71 @@ mod tests {
         assert!(resp.status().is_success());
     }
 
+    async fn e2e_post_bulk_positional_errors() {
+        let auth = setup();
+        let now = Utc::now().timestamp_micros();
+        let too_old = (Utc::now() - Duration::try_days(365).unwrap()).timestamp_micros();
+        let body_str = [
+            r#"{"index":{"_index":"olympics_positional"}}"#.to_string(),
+            format!(r#"{{"city":"Athens","_timestamp":{now}}}"#),
+            r#"{"index":{"_index":"olympics_positional"}}"#.to_string(),
+            format!(r#"{{"city":"Paris","_timestamp":{too_old}}}"#),
+            r#"{"index":{"_index":"_"}}"#.to_string(),
+            r#"{"city":"Nowhere"}"#.to_string(),
+            r#"{"create":{"_index":"olympics_positional","_id":"4"}}"#.to_string(),
+            r#"{"city":"London","_timestamp":"not a timestamp"}"#.to_string(),
+            r#"{"index":{"_index":"olympics_positional"}}"#.to_string(),
+            format!(r#"{{"city":"Rome","_timestamp":{now}}}"#),
+        ]
+        .join("\n");
+        let thread_id: usize = 0;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .app_data(web::Data::new(thread_id))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/_bulk?errors=positional", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let res: json::Value = json::from_slice(&body).unwrap();
+        assert_eq!(res["errors"], true);
+
+        let items = res["items"].as_array().unwrap();
+        assert_eq!(items.len(), 5);
+        let items = items
+            .iter()
+            .map(|item| item.as_object().unwrap().iter().next().unwrap())
+            .collect::<Vec<_>>();
+        for (i, (_, item)) in items.iter().enumerate() {
+            assert_eq!(item["position"], i);
+        }
+        let statuses = items
+            .iter()
+            .map(|(_, item)| item["status"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec![200, 422, 422, 422, 200]);
+        assert!(items[1].1["error"]["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with("Too old data"));
+        assert!(items[2].1["error"]["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid stream name"));
+        assert_eq!(items[3].0, "create");
+        assert_eq!(items[3].1["error"]["type"], "timestamp_parsing_failed");
+    }
+
     async fn e2e_post_json() {
         let auth = setup();
         let body_str = "[{\"Year\": 1896, \"City\": \"Athens\", \"Sport\": \"Aquatics\", \"Discipline\": \"Swimming\", \"Athlete\": \"HERSCHMANN, Otto\", \"Country\": \"AUT\", \"Gender\": \"Men\", \"Event\": \"100M Freestyle\", \"Medal\": \"Silver\", \"Season\": \"summer\",\"_timestamp\":1665136888163792}]";
@@ -554,6 +697,86 @@ mod tests {
         assert!(resp.status().is_success());
     }
 
+    /// The first records of a stream are ingested by one node and searched on
+    /// another one, which looked the stream up before it existed and hasn't
+    /// received its watch event yet.
+    async fn e2e_search_new_stream_from_another_node() {
+        let auth = setup();
+        let stream_name = "e2e_new_stream";
+        let cache_key = format!("e2e/logs/{stream_name}");
+        let thread_id: usize = 0;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .app_data(web::Data::new(thread_id))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+
+        // node A creates the stream with its first records
+        let now = Utc::now().timestamp_micros();
+        let body_str = format!(
+            r#"[{{"level": "info", "_timestamp": {now}}}, {{"level": "error", "_timestamp": {now}}}]"#
+        );
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/e2e/{stream_name}/_json"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let ingested_at = std::time::Instant::now();
+
+        // the creation published the schema to the meta store right away
+        let schema = infra::schema::get_from_db("e2e", stream_name, StreamType::Logs)
+            .await
+            .unwrap();
+        assert!(schema.field_with_name("level").is_ok());
+        assert!(schema.metadata().contains_key("created_at"));
+
+        // node B still remembers the stream as not found
+        infra::schema::STREAM_SCHEMAS_LATEST
+            .write()
+            .await
+            .remove(&cache_key);
+        infra::schema::forget_not_found(&cache_key);
+        STREAM_SCHEMAS_NOT_FOUND.insert(&cache_key, Utc::now().timestamp_micros(), 60);
+
+        // the records are only in the memtable of the ingester, without any
+        // file_list entry yet
+        let files = openobserve::service::file_list::query_ids(
+            "e2e",
+            StreamType::Logs,
+            stream_name,
+            Some((now - 60_000_000, now + 60_000_000)),
+        )
+        .await
+        .unwrap();
+        assert!(files.is_empty());
+
+        let body_str = format!(
+            r#"{{"query": {{"sql": "select * from {stream_name}", "from": 0, "size": 100, "start_time": {}, "end_time": {}}}}}"#,
+            now - 60_000_000,
+            now + 60_000_000,
+        );
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search")
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body: json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["hits"].as_array().unwrap().len(), 2);
+        assert!(ingested_at.elapsed() < std::time::Duration::from_secs(1));
+    }
+
     async fn e2e_search_around() {
         let auth = setup();
 
@@ -581,6 +804,148 @@ mod tests {
         assert!(resp.status().is_success());
     }
 
+    async fn e2e_search_query_fn_permission() {
+        let root_auth = setup();
+        // nonadmin@example.com is a member, which is denied ad-hoc functions by the setup
+        let member_auth = (
+            "Authorization",
+            format!(
+                "Basic {}",
+                config::utils::base64::encode("nonadmin@example.com:Abcd12345")
+            ),
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let query_fn = config::utils::base64::encode_url(".e2e_fn = 1");
+        let ts = chrono::Utc::now().timestamp_micros();
+        let search_body = |query_fn: Option<&str>| {
+            let mut query = json::json!({
+                "sql": "select * from olympics_schema",
+                "from": 0,
+                "size": 10,
+                "start_time": 1714857600000i64,
+                "end_time": 1714944000000i64
+            });
+            if let Some(query_fn) = query_fn {
+                query["query_fn"] = json::json!(query_fn);
+            }
+            json::json!({ "query": query }).to_string()
+        };
+        let multi_body = |query_fn: Option<&str>| {
+            let mut body = json::json!({
+                "sql": ["select * from olympics_schema"],
+                "from": 0,
+                "size": 10,
+                "start_time": 1714857600000i64,
+                "end_time": 1714944000000i64
+            });
+            if let Some(query_fn) = query_fn {
+                body["query_fn"] = json::json!(query_fn);
+            }
+            body.to_string()
+        };
+        let assert_forbidden = |resp: actix_web::dev::ServiceResponse| async move {
+            assert_eq!(resp.status().as_u16(), 403);
+            let body: json::Value = json::from_slice(&test::read_body(resp).await).unwrap();
+            assert_eq!(body["code"], 20013);
+            assert_eq!(body["error_detail"], "function:execute_adhoc");
+        };
+
+        // _search
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search")
+            .insert_header(ContentType::json())
+            .append_header(member_auth.clone())
+            .set_payload(search_body(Some(".e2e_fn = 1")))
+            .to_request();
+        assert_forbidden(test::call_service(&app, req).await).await;
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search")
+            .insert_header(ContentType::json())
+            .append_header(member_auth.clone())
+            .set_payload(search_body(None))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search")
+            .insert_header(ContentType::json())
+            .append_header(root_auth)
+            .set_payload(search_body(Some(".e2e_fn = 1")))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        // _search_multi
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search_multi")
+            .insert_header(ContentType::json())
+            .append_header(member_auth.clone())
+            .set_payload(multi_body(Some(&query_fn)))
+            .to_request();
+        assert_forbidden(test::call_service(&app, req).await).await;
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search_multi")
+            .insert_header(ContentType::json())
+            .append_header(member_auth.clone())
+            .set_payload(multi_body(None))
+            .to_request();
+        assert_ne!(test::call_service(&app, req).await.status().as_u16(), 403);
+
+        // _around
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/e2e/olympics_schema/_around?key={ts}&size=10&query_fn={query_fn}"
+            ))
+            .append_header(member_auth.clone())
+            .to_request();
+        assert_forbidden(test::call_service(&app, req).await).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/e2e/olympics_schema/_around?key={ts}&size=10"
+            ))
+            .append_header(member_auth.clone())
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        // _around_multi
+        let streams = config::utils::base64::encode_url("olympics_schema");
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/e2e/{streams}/_around_multi?key={ts}&size=10&query_fn={query_fn}"
+            ))
+            .append_header(member_auth.clone())
+            .to_request();
+        assert_forbidden(test::call_service(&app, req).await).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/e2e/{streams}/_around_multi?key={ts}&size=10"
+            ))
+            .append_header(member_auth.clone())
+            .to_request();
+        assert_ne!(test::call_service(&app, req).await.status().as_u16(), 403);
+
+        // _values
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/e2e/olympics_schema/_values?fields=country&size=10&start_time=1714857600000&end_time=1714944000000&query_fn={query_fn}"
+            ))
+            .append_header(member_auth.clone())
+            .to_request();
+        assert_forbidden(test::call_service(&app, req).await).await;
+        let req = test::TestRequest::get()
+            .uri("/api/e2e/olympics_schema/_values?fields=country&size=10&start_time=1714857600000&end_time=1714944000000")
+            .append_header(member_auth)
+            .to_request();
+        assert_ne!(test::call_service(&app, req).await.status().as_u16(), 403);
+    }
+
     async fn e2e_list_users() {
         let auth = setup();
         let app = test::init_service(
@@ -943,6 +1308,32 @@ mod tests {
         json::from_slice(&body).unwrap()
     }
 
+    async fn e2e_duplicate_dashboard(dashboard_id: &str, title: &str) -> Dashboard {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/api/{}/folders/default/dashboards/{dashboard_id}/_duplicate",
+                "e2e"
+            ))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(json::to_string(&json::json!({ "title": title })).unwrap())
+            .to_request();
+
+        let body = test::call_and_read_body(&app, req).await;
+        json::from_slice(&body).unwrap()
+    }
+
     async fn e2e_delete_dashboard(dashboard_id: &str) {
         let auth = setup();
         let app = test::init_service(
@@ -964,6 +1355,168 @@ mod tests {
         assert!(resp.status().is_success());
     }
 
+    async fn e2e_create_dashboard_in_folder(folder_id: &str) -> Dashboard {
+        let auth = setup();
+        let body_str = r##"{"title":"in folder","dashboardId":"","description":"","role":"","owner":"root@example.com","created":"2023-03-30T07:49:41.744+00:00","panels":[],"layouts":[]}"##;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/dashboards?folder={folder_id}", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+
+        let body = test::call_and_read_body(&app, req).await;
+        json::from_slice(&body).unwrap()
+    }
+
+    async fn e2e_delete_folder(folder_id: &str, force: bool) -> (u16, json::Value) {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::delete()
+            .uri(&format!(
+                "/api/v2/{}/folders/dashboards/{folder_id}?force={force}",
+                "e2e"
+            ))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status().as_u16();
+        let body = test::read_body(resp).await;
+        (status, json::from_slice(&body).unwrap())
+    }
+
+    async fn e2e_delete_folder_with_contents() {
+        let folder = openobserve::service::folders::save_folder(
+            "e2e",
+            Folder {
+                folder_id: "".to_string(),
+                name: "e2e folder with contents".to_string(),
+                description: "".to_string(),
+            },
+            FolderType::Dashboards,
+            false,
+        )
+        .await
+        .unwrap();
+        let folder_id = folder.folder_id.as_str();
+
+        // a live dashboard used by a report, and a dashboard already trashed
+        let live_id = e2e_create_dashboard_in_folder(folder_id)
+            .await
+            .dashboard_id()
+            .unwrap()
+            .to_owned();
+        let trashed_id = e2e_create_dashboard_in_folder(folder_id)
+            .await
+            .dashboard_id()
+            .unwrap()
+            .to_owned();
+        e2e_delete_dashboard(&trashed_id).await;
+        let report = Report {
+            name: "e2e_folder_report".to_string(),
+            org_id: "e2e".to_string(),
+            dashboards: vec![ReportDashboard {
+                dashboard: live_id.clone(),
+                folder: folder_id.to_string(),
+                tabs: vec![],
+                variables: vec![],
+                timerange: Default::default(),
+            }],
+            ..Default::default()
+        };
+        openobserve::service::db::dashboards::reports::set("e2e", &report, true)
+            .await
+            .unwrap();
+
+        // a folder with contents is only deleted with force, the conflict
+        // lists the live contents
+        let (status, body) = e2e_delete_folder(folder_id, false).await;
+        assert_eq!(status, 409);
+        assert_eq!(
+            body["contents"],
+            json::json!({
+                "dashboards": { "count": 1, "ids": [live_id] },
+                "alerts": { "count": 0, "ids": [] },
+                "reports": { "count": 1, "ids": ["e2e_folder_report"] },
+            })
+        );
+
+        // contents added after the check keep the folder and its contents
+        assert!(!infra::table::folders::delete_with_contents(
+            "e2e",
+            folder_id,
+            FolderType::Dashboards,
+            "default",
+            "root@example.com",
+            Utc::now().timestamp_micros(),
+            &[],
+        )
+        .await
+        .unwrap());
+        assert!(openobserve::service::folders::get_folder(
+            "e2e",
+            folder_id,
+            FolderType::Dashboards
+        )
+        .await
+        .is_ok());
+        assert_eq!(
+            e2e_get_dashboard(&live_id).await.dashboard_id(),
+            Some(live_id.as_str())
+        );
+
+        // the forced delete trashes the dashboards and deletes the report
+        let (status, body) = e2e_delete_folder(folder_id, true).await;
+        assert_eq!(status, 200);
+        assert_eq!(
+            body["dashboards"],
+            json::json!({ "count": 1, "ids": [live_id] })
+        );
+        assert_eq!(
+            body["reports"],
+            json::json!({ "count": 1, "ids": ["e2e_folder_report"] })
+        );
+        assert!(openobserve::service::folders::get_folder(
+            "e2e",
+            folder_id,
+            FolderType::Dashboards
+        )
+        .await
+        .is_err());
+        let trash = e2e_list_trash("dashboard").await;
+        assert_eq!(trash.len(), 2);
+        assert!(trash.iter().all(|item| item["folderId"] == "default"));
+        assert!(openobserve::service::db::dashboards::reports::list("e2e")
+            .await
+            .unwrap()
+            .iter()
+            .all(|r| r.name != "e2e_folder_report"));
+
+        let now = Utc::now().timestamp_micros();
+        let purged = openobserve::service::trash::purge(now + 1).await.unwrap();
+        assert_eq!(purged, 2);
+    }
+
     async fn e2e_post_trace() {
         let auth = setup();
         let path = "./tests/trace_input.json";
@@ -2004,24 +2557,161 @@ mod tests {
             config::meta::stream::StreamType::Logs,
             "olympics_schema",
             "test_alert_wrong_sql",
+            "root@example.com",
         )
         .await;
         assert!(res.is_ok());
     }
 
-    async fn e2e_delete_alert() {
+    /// A scheduler stopped after persisting a state change of the alert but
+    /// before emitting it, the next run emits it exactly once.
+    async fn e2e_handle_alert_state_events_after_restart() {
         let auth = setup();
-        let app = test::init_service(
-            App::new()
-                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
-                .app_data(web::PayloadConfig::new(
-                    get_config().limit.req_payload_limit,
-                ))
-                .configure(get_service_routes)
-                .configure(get_basic_routes),
-        )
-        .await;
-        let req = test::TestRequest::delete()
+        let stream_name = "e2e_new_stream";
+        let alert_name = "alert_state_events";
+        let module_key = format!("logs/{stream_name}/{alert_name}");
+
+        let mut alert: Alert = Default::default();
+        alert.name = alert_name.to_string();
+        alert.stream_type = "logs".into();
+        alert.stream_name = stream_name.to_string();
+        alert.is_real_time = false;
+        alert.enabled = true;
+        alert.emit_state_events = true;
+        alert.query_condition = QueryCondition {
+            query_type: "sql".into(),
+            conditions: None,
+            sql: Some(format!(
+                "SELECT level FROM \"{stream_name}\" WHERE level = 'error'"
+            )),
+            ..Default::default()
+        };
+        alert.trigger_condition = TriggerCondition {
+            period: 60,
+            threshold: 1,
+            silence: 0,
+            frequency: 3600,
+            operator: Operator::GreaterThanEquals,
+            ..Default::default()
+        };
+        alert.destinations = vec!["slack".to_string()];
+        openobserve::service::db::alerts::alert::set(
+            "e2e",
+            StreamType::Logs,
+            stream_name,
+            alert,
+            true,
+        )
+        .await
+        .unwrap();
+        let alert = openobserve::service::db::alerts::alert::get_by_name(
+            "e2e",
+            StreamType::Logs,
+            stream_name,
+            alert_name,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // the state change the previous run persisted before it stopped
+        let now = Utc::now().timestamp_micros();
+        let event = AlertStateEvent::new(&alert, AlertState::Inactive, AlertState::Pending, now);
+        let trigger_data = ScheduledTriggerData {
+            alert_state: AlertState::Pending,
+            pending_state_events: vec![event.clone()],
+            ..Default::default()
+        };
+        let trigger = Trigger {
+            org: "e2e".to_string(),
+            module: config::meta::triggers::TriggerModule::Alert,
+            module_key: module_key.clone(),
+            start_time: Some(now),
+            end_time: Some(
+                now + Duration::try_minutes(3)
+                    .unwrap()
+                    .num_microseconds()
+                    .unwrap(),
+            ),
+            next_run_at: now,
+            is_realtime: false,
+            is_silenced: false,
+            status: config::meta::triggers::TriggerStatus::Processing,
+            retries: 0,
+            data: json::to_string(&trigger_data).unwrap(),
+        };
+        let _ = handle_triggers("test_trace_id", trigger).await;
+
+        // the event was emitted and is no longer pending in the stored trigger
+        let trigger = openobserve::service::db::scheduler::get(
+            "e2e",
+            config::meta::triggers::TriggerModule::Alert,
+            &module_key,
+        )
+        .await
+        .unwrap();
+        let trigger_data: ScheduledTriggerData = json::from_str(&trigger.data).unwrap();
+        assert!(trigger_data.pending_state_events.is_empty());
+        assert_ne!(trigger_data.alert_state, AlertState::Inactive);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let body_str = json::json!({
+            "query": {
+                "sql": format!(
+                    "select * from {} where event_id = '{}'",
+                    openobserve::service::alerts::state_events::STREAM_NAME,
+                    event.event_id
+                ),
+                "from": 0,
+                "size": 100,
+                "start_time": now - 60_000_000,
+                "end_time": Utc::now().timestamp_micros() + 60_000_000,
+            }
+        });
+        let req = test::TestRequest::post()
+            .uri("/api/e2e/_search")
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str.to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body: json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["hits"].as_array().unwrap().len(), 1);
+
+        openobserve::service::db::alerts::alert::delete_by_name(
+            "e2e",
+            StreamType::Logs,
+            stream_name,
+            alert_name,
+            "root@example.com",
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn e2e_delete_alert() {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::delete()
             .uri(&format!(
                 "/api/{}/{}/alerts/{}",
                 "e2e", "olympics_schema", "alertChk"
@@ -2042,6 +2732,351 @@ mod tests {
         assert!(!trigger);
     }
 
+    async fn e2e_short_urls() {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let original_url =
+            "http://localhost:5080/web/logs?org_identifier=e2e&stream=olympics_schema";
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/short", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(json::json!({ "original_url": original_url }).to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body: json::Value = test::read_body_json(resp).await;
+        let short_url = body["short_url"].as_str().unwrap().to_owned();
+        let short_id = short_url.rsplit('/').next().unwrap().to_owned();
+
+        // the short url is listed for its org only
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/short_urls?page_size=10", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let body: json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["list"][0]["id"], short_id.as_str());
+        assert_eq!(body["list"][0]["short_url"], short_url.as_str());
+        assert_eq!(body["list"][0]["original_url"], original_url);
+        assert!(body["list"][0]["created_at"].as_i64().unwrap() > 0);
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/short_urls", "other_org"))
+            .append_header(auth)
+            .to_request();
+        let body: json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["total"], 0);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/short/{short_id}", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 302);
+
+        // only expired short urls can be purged in bulk
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/{}/short_urls", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 400);
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/{}/short_urls?expired=true", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let body: json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["deleted"], 0);
+
+        // a short url can only be deleted through its org
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/{}/short_urls/{short_id}", "other_org"))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 404);
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/{}/short_urls/{short_id}", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/short_urls", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let body: json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["total"], 0);
+    }
+
+    async fn e2e_stream_sampling() {
+        let auth = setup();
+        let thread_id: usize = 0;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .app_data(web::Data::new(thread_id))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let body_str = "[{\"City\": \"Athens\", \"Sport\": \"Aquatics\", \"Medal\": \"Silver\"}]";
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/{}/_json", "e2e", "olympics_sampling"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // a rate above 1 is rejected
+        let req = test::TestRequest::put()
+            .uri(&format!(
+                "/api/{}/streams/{}/settings",
+                "e2e", "olympics_sampling"
+            ))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(r#"{"sampling":{"rate":2.0}}"#)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status().as_u16(), 400);
+
+        let req = test::TestRequest::put()
+            .uri(&format!(
+                "/api/{}/streams/{}/settings",
+                "e2e", "olympics_sampling"
+            ))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(r#"{"data_retention":10,"sampling":{"rate":1.0}}"#)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // every record is sampled at rate 1, the companion stream is created
+        // with the same settings minus sampling
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/{}/_json", "e2e", "olympics_sampling"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(body_str)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/{}/streams/{}/schema",
+                "e2e", "olympics_sampling_sampled"
+            ))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body: json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["settings"]["data_retention"], 10);
+        assert!(body["settings"].get("sampling").is_none());
+        assert!(body["schema"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|f| f["name"] == "city"));
+
+        // turning sampling off leaves the companion stream in place
+        let req = test::TestRequest::put()
+            .uri(&format!(
+                "/api/{}/streams/{}/settings",
+                "e2e", "olympics_sampling"
+            ))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .set_payload(r#"{"sampling":{"rate":0}}"#)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/{}/streams/{}/schema",
+                "e2e", "olympics_sampling"
+            ))
+            .append_header(auth)
+            .to_request();
+        let body: json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body["settings"].get("sampling").is_none());
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/{}/streams/{}/schema",
+                "e2e", "olympics_sampling_sampled"
+            ))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+
+    #[cfg(not(feature = "enterprise"))]
+    async fn e2e_search_queue_status() {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/query_manager/status", "e2e"))
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body: json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["waiting"], 0);
+        assert_eq!(body["running"], 0);
+        assert!(body["status"].as_array().unwrap().is_empty());
+    }
+
+    async fn e2e_restore_and_purge_alert() {
+        let trash = e2e_list_trash("alert").await;
+        let item = trash
+            .iter()
+            .find(|item| item["name"] == "alertChk")
+            .expect("deleted alert should be in the trash");
+        let alert_id = item["id"].as_str().unwrap().to_owned();
+        assert_eq!(item["type"], "alert");
+        assert_eq!(item["folderId"], "default");
+        assert_eq!(e2e_get_alert_status(&alert_id).await, 404);
+
+        // restoring the alert brings back its scheduler trigger
+        assert_eq!(e2e_restore_trash_item(&alert_id).await, 200);
+        assert_eq!(e2e_get_alert_status(&alert_id).await, 200);
+        assert!(!e2e_list_trash("alert")
+            .await
+            .iter()
+            .any(|item| item["id"] == alert_id.as_str()));
+        let trigger = openobserve::service::db::scheduler::exists(
+            "e2e",
+            config::meta::triggers::TriggerModule::Alert,
+            "logs/olympics_schema/alertChk",
+        )
+        .await;
+        assert!(trigger);
+
+        // trashed alerts are purged once past the retention period
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/v2/{}/alerts/{alert_id}", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(e2e_get_alert_status(&alert_id).await, 404);
+
+        let now = Utc::now().timestamp_micros();
+        let purged = openobserve::service::trash::purge(now + 1).await.unwrap();
+        assert!(purged >= 1);
+        assert!(e2e_list_trash("alert").await.is_empty());
+        assert_eq!(e2e_restore_trash_item(&alert_id).await, 404);
+        let trigger = openobserve::service::db::scheduler::exists(
+            "e2e",
+            config::meta::triggers::TriggerModule::Alert,
+            "logs/olympics_schema/alertChk",
+        )
+        .await;
+        assert!(!trigger);
+    }
+
+    async fn e2e_get_alert_status(alert_id: &str) -> u16 {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v2/{}/alerts/{alert_id}", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .to_request();
+        test::call_service(&app, req).await.status().as_u16()
+    }
+
+    async fn e2e_list_trash(item_type: &str) -> Vec<json::Value> {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/{}/trash?type={item_type}", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .to_request();
+
+        let body = test::call_and_read_body(&app, req).await;
+        let mut body_json: json::Value = json::from_slice(&body).unwrap();
+        let list_json = body_json.as_object_mut().unwrap().remove("list").unwrap();
+        json::from_value(list_json).unwrap()
+    }
+
+    async fn e2e_restore_trash_item(id: &str) -> u16 {
+        let auth = setup();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(get_config().limit.req_json_limit))
+                .app_data(web::PayloadConfig::new(
+                    get_config().limit.req_payload_limit,
+                ))
+                .configure(get_service_routes)
+                .configure(get_basic_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/{}/trash/{id}/restore", "e2e"))
+            .insert_header(ContentType::json())
+            .append_header(auth)
+            .to_request();
+        test::call_service(&app, req).await.status().as_u16()
+    }
+
     async fn e2e_list_alerts() {
         let auth = setup();
         let app = test::init_service(